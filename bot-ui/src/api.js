@@ -16,7 +16,16 @@ export class Api {
     return fetch(`${this.url}/${path}`, data).then((r) => {
       if (!r.ok) {
         return r.text().then(text => {
-          throw Error(`got bad status code: ${r.status}: ${text}`);
+          let e = Error(`got bad status code: ${r.status}: ${text}`);
+          e.status = r.status;
+
+          try {
+            e.body = JSON.parse(text);
+          } catch(_) {
+            e.body = null;
+          }
+
+          throw e;
         });
       }
 
@@ -57,10 +66,57 @@ export class Api {
   }
 
   /**
-   * Get a list of devices.
+   * Get afterstream messages and their replies.
+   *
+   * @param {string} status optional status to filter by, like `open`.
+   */
+  afterStreams(status = null) {
+    let query = "";
+
+    if (!!status) {
+      query = `?status=${encodeURIComponent(status)}`;
+    }
+
+    return this.fetch(`after-streams${query}`);
+  }
+
+  /**
+   * Set the status of multiple afterstream messages.
+   *
+   * @param {array<number>} ids ids of the afterstream messages to update.
+   * @param {string} status the new status, like `resolved` or `ignored`.
+   */
+  setAfterStreamStatus(ids, status) {
+    return this.fetch(["after-streams", "status"], {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({ ids, status }),
+    });
+  }
+
+  /**
+   * Reply to an afterstream message.
+   *
+   * @param {number} id id of the afterstream message to reply to.
+   * @param {string} text text of the reply.
    */
-  afterStreams() {
-    return this.fetch("after-streams");
+  replyAfterStream(id, text) {
+    return this.fetch(["after-stream", String(id), "replies"], {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({ text }),
+    });
+  }
+
+  /**
+   * Get the most recently created stream markers.
+   */
+  markers() {
+    return this.fetch("markers");
   }
 
   /**
@@ -121,6 +177,290 @@ export class Api {
     });
   }
 
+  /**
+   * Get the state of all feature flags.
+   */
+  flags() {
+    return this.fetch("flags");
+  }
+
+  /**
+   * Get the most recent system notifications.
+   */
+  notifications() {
+    return this.fetch("notifications");
+  }
+
+  /**
+   * Mark the given notification as read.
+   *
+   * @param {number} id the notification to mark as read
+   */
+  notificationRead(id) {
+    return this.fetch(`notifications/${id}/read`, {
+      method: "POST",
+    });
+  }
+
+  /**
+   * Mark all notifications as read.
+   */
+  notificationsReadAll() {
+    return this.fetch("notifications/read", {
+      method: "POST",
+    });
+  }
+
+  /**
+   * Get recent donations and donation totals.
+   */
+  donations() {
+    return this.fetch("donations");
+  }
+
+  /**
+   * Get all questions in the trivia question bank.
+   */
+  triviaQuestions() {
+    return this.fetch("trivia/questions");
+  }
+
+  /**
+   * Import questions into the trivia question bank.
+   *
+   * @param {string} format the format of the questions, either `json` or `csv`.
+   * @param {string} body the questions to import.
+   */
+  triviaImport(format, body) {
+    return this.fetch(["trivia", "import", format], {
+      method: "PUT",
+      headers: {
+        "Content-Type": format === "json" ? "application/json" : "text/csv",
+      },
+      body,
+    });
+  }
+
+  /**
+   * Delete a question from the trivia question bank.
+   *
+   * @param {number} id the id of the question to delete.
+   */
+  triviaDeleteQuestion(id) {
+    return this.fetch(`trivia/questions/${id}`, {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * Get the trivia scoreboard for the latest stream.
+   */
+  triviaScoreboard() {
+    return this.fetch("trivia/scoreboard");
+  }
+
+  /**
+   * Get the current title and game of the channel.
+   */
+  channel() {
+    return this.fetch("channel");
+  }
+
+  /**
+   * Set the title of the channel.
+   *
+   * @param {string} title the new title.
+   */
+  channelSetTitle(title) {
+    return this.fetch("channel/title", {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({ title }),
+    });
+  }
+
+  /**
+   * Set the game of the channel to the one best matching the given name.
+   *
+   * @param {string} game the name of the game.
+   */
+  channelSetGame(game) {
+    return this.fetch("channel/game", {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({ game }),
+    });
+  }
+
+  /**
+   * Search for games by name.
+   *
+   * @param {string} q the name to search for.
+   */
+  channelSearchGames(q) {
+    return this.fetch(`channel/games?q=${encodeURIComponent(q)}`);
+  }
+
+  /**
+   * Get the queue of viewers waiting to play, in the order they will be picked.
+   *
+   * @param {string} channel the channel to get the queue for.
+   */
+  viewerQueue(channel) {
+    return this.fetch(["viewer-queue", channel]);
+  }
+
+  /**
+   * Pick the next viewer from the queue.
+   *
+   * @param {string} channel the channel of the queue.
+   */
+  viewerQueueNext(channel) {
+    return this.fetch(["viewer-queue", channel, "next"], {
+      method: "POST",
+    });
+  }
+
+  /**
+   * Remove a viewer from the queue.
+   *
+   * @param {string} channel the channel of the queue.
+   * @param {string} user the user to remove.
+   */
+  viewerQueueRemove(channel, user) {
+    return this.fetch(["viewer-queue", channel, user], {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * Remove everyone from the queue.
+   *
+   * @param {string} channel the channel of the queue.
+   */
+  viewerQueueClear(channel) {
+    return this.fetch(["viewer-queue", channel], {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * List the custom answers of the magic 8 ball.
+   *
+   * @param {string} channel the channel to list answers for.
+   */
+  eightBallAnswers(channel) {
+    return this.fetch(["8ball", channel]);
+  }
+
+  /**
+   * Add a custom answer to the magic 8 ball.
+   *
+   * @param {string} channel the channel to add the answer to.
+   * @param {string} answer the template of the answer.
+   * @param {number} weight how likely the answer is to be picked.
+   */
+  eightBallAdd(channel, answer, weight) {
+    return this.fetch(["8ball", channel], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({ answer, weight }),
+    });
+  }
+
+  /**
+   * Remove a custom answer from the magic 8 ball.
+   *
+   * @param {string} channel the channel of the answer.
+   * @param {number} id the id of the answer to remove.
+   */
+  eightBallRemove(channel, id) {
+    return this.fetch(["8ball", channel, id.toString()], {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * List moderation actions in the given channel, most recent first.
+   *
+   * @param {string} channel the channel to list actions for.
+   * @param {object} filter optional `action` and `user` to filter by.
+   */
+  moderationLog(channel, filter = {}) {
+    let queries = [];
+
+    if (!!filter.action) {
+      queries.push(`action=${encodeURIComponent(filter.action)}`);
+    }
+
+    if (!!filter.user) {
+      queries.push(`user=${encodeURIComponent(filter.user)}`);
+    }
+
+    let query = "";
+
+    if (queries.length > 0) {
+      query = `?${queries.join("&")}`;
+    }
+
+    return this.fetch(`moderation/${encodeURIComponent(channel)}${query}`);
+  }
+
+  /**
+   * Undo a timeout or ban.
+   *
+   * @param {string} channel the channel the action was taken in.
+   * @param {number} id the id of the action to undo.
+   */
+  moderationUndo(channel, id) {
+    return this.fetch(["moderation", channel, String(id), "undo"], {
+      method: "POST",
+    });
+  }
+
+  /**
+   * Get the most recently created clips.
+   */
+  clips() {
+    return this.fetch("clips");
+  }
+
+  /**
+   * Get everyone who is currently away from keyboard.
+   */
+  afk() {
+    return this.fetch("afk");
+  }
+
+  /**
+   * Get chat activity for the current and past streams.
+   */
+  chatStatsStreams() {
+    return this.fetch("chat-stats/streams");
+  }
+
+  /**
+   * List recorded stream sessions, most recent first.
+   */
+  sessions() {
+    return this.fetch("sessions");
+  }
+
+  /**
+   * Get the report for a single stream session.
+   *
+   * @param {number} id the id of the session.
+   */
+  sessionReport(id) {
+    return this.fetch(["sessions", id, "report"]);
+  }
+
   /**
    * Get all cache entries.
    */
@@ -128,6 +468,40 @@ export class Api {
     return this.fetch("cache");
   }
 
+  /**
+   * Get all chat messages in the current language, with their overrides.
+   */
+  messages() {
+    return this.fetch("messages");
+  }
+
+  /**
+   * Override the given chat message.
+   *
+   * @param {string} key the key of the message to override.
+   * @param {string} template the template to override it with.
+   */
+  messageEdit(key, template) {
+    return this.fetch(`messages/${settingsKey(key)}`, {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({template}),
+    });
+  }
+
+  /**
+   * Restore the default of the given chat message.
+   *
+   * @param {string} key the key of the message to restore.
+   */
+  messageDelete(key) {
+    return this.fetch(`messages/${settingsKey(key)}`, {
+      method: "DELETE",
+    });
+  }
+
   /**
    * Delete a setting.
    *
@@ -161,6 +535,76 @@ export class Api {
     });
   }
 
+  /**
+   * Export all settings except secrets.
+   *
+   * @param {string} format format to export in, either "json" or "toml".
+   */
+  exportSettings(format) {
+    return this.fetch(`settings-export?format=${encodeURIComponent(format)}`);
+  }
+
+  /**
+   * Import settings, like the ones produced by an export.
+   *
+   * @param {string} format format of the content, either "json" or "toml".
+   * @param {string} content the settings to import.
+   */
+  importSettings(format, content) {
+    return this.fetch("settings-import", {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({format, content}),
+    });
+  }
+
+  /**
+   * List all settings profiles.
+   */
+  profiles() {
+    return this.fetch("profiles");
+  }
+
+  /**
+   * Save the current settings as a profile.
+   *
+   * @param {string} name name of the profile.
+   * @param {array<string>} prefixes prefixes of settings to save, or empty for all.
+   */
+  profileSave(name, prefixes) {
+    return this.fetch(["profiles", name], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({prefixes}),
+    });
+  }
+
+  /**
+   * Activate the given profile.
+   *
+   * @param {string} name name of the profile.
+   */
+  profileActivate(name) {
+    return this.fetch(["profiles", name, "activate"], {
+      method: "POST",
+    });
+  }
+
+  /**
+   * Delete the given profile.
+   *
+   * @param {string} name name of the profile.
+   */
+  profileDelete(name) {
+    return this.fetch(["profiles", name], {
+      method: "DELETE",
+    });
+  }
+
   /**
    * Export balances.
    */
@@ -314,12 +758,68 @@ export class Api {
     });
   }
 
+  /**
+   * Grant a scope to the given role and every role above it, or revoke it
+   * from everyone if role is null.
+   */
+  authMinimumRole(scope, role) {
+    return this.fetch(["auth", "minimum-role"], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({scope, role}),
+    });
+  }
+
   /**
    * Get all existing chat messages.
    */
   chatMessages() {
     return this.fetch(["chat", "messages"]);
   }
+
+  /**
+   * Get the state of the first-run setup.
+   */
+  setup() {
+    return this.fetch("setup");
+  }
+
+  /**
+   * Enable or disable features as part of the first-run setup.
+   *
+   * @param {object} features feature toggles, keyed by setting.
+   */
+  setupModules(features) {
+    return this.fetch(["setup", "modules"], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({features}),
+    });
+  }
+
+  /**
+   * Skip an optional step of the first-run setup.
+   *
+   * @param {string} step the id of the step to skip.
+   */
+  setupSkip(step) {
+    return this.fetch(["setup", step, "skip"], {
+      method: "POST",
+    });
+  }
+
+  /**
+   * Complete the first-run setup.
+   */
+  setupComplete() {
+    return this.fetch(["setup", "complete"], {
+      method: "POST",
+    });
+  }
 }
 
 function encodePath(path) {
@@ -0,0 +1,75 @@
+import React from "react";
+import {Alert, Table} from "react-bootstrap";
+import * as moment from "moment";
+import {Error} from 'shared-ui/components';
+
+/**
+ * How often to refresh who is away, in milliseconds.
+ */
+const REFRESH_INTERVAL = 10000;
+
+/**
+ * Shows who is currently away from keyboard, or nothing if nobody is.
+ */
+export default class Afk extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      error: null,
+      statuses: [],
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+    this.interval = setInterval(() => this.list(), REFRESH_INTERVAL);
+  }
+
+  componentWillUnmount() {
+    clearInterval(this.interval);
+  }
+
+  /**
+   * Refresh who is away.
+   */
+  async list() {
+    try {
+      let statuses = await this.api.afk();
+      this.setState({error: null, statuses});
+    } catch (e) {
+      this.setState({error: `failed to load afk statuses: ${e}`, statuses: []});
+    }
+  }
+
+  render() {
+    if (this.state.error !== null) {
+      return <Error error={this.state.error} />;
+    }
+
+    if (this.state.statuses.length === 0) {
+      return null;
+    }
+
+    return (
+      <>
+        <h4>Away From Keyboard</h4>
+
+        <Alert variant="warning">
+          <Table size="sm" className="mb-0">
+            <tbody>
+              {this.state.statuses.map(s => (
+                <tr key={s.user}>
+                  <td><b>{s.display_name}</b></td>
+                  <td className="table-fill">{s.reason || <em>no reason given</em>}</td>
+                  <td>for {moment(s.since).fromNow(true)}</td>
+                </tr>
+              ))}
+            </tbody>
+          </Table>
+        </Alert>
+      </>
+    );
+  }
+}
@@ -1,9 +1,61 @@
 import React from "react";
-import {Button, Alert, Table} from "react-bootstrap";
+import {Button, ButtonGroup, Alert, Table, Form, Badge, InputGroup} from "react-bootstrap";
 import {FontAwesomeIcon} from "@fortawesome/react-fontawesome";
 import ConfigurationPrompt from "./ConfigurationPrompt";
 import {Loading, Error} from 'shared-ui/components';
 
+/**
+ * Format a position in the stream as hours, minutes, and seconds.
+ *
+ * @param {number} seconds position in seconds
+ */
+function formatPosition(seconds) {
+  let h = Math.floor(seconds / 3600);
+  let m = Math.floor((seconds % 3600) / 60);
+  let s = seconds % 60;
+  let pad = n => n.toString().padStart(2, "0");
+  return `${h}:${pad(m)}:${pad(s)}`;
+}
+
+const STATUSES = [
+  {value: "open", title: "Open"},
+  {value: "resolved", title: "Resolved"},
+  {value: "ignored", title: "Ignored"},
+  {value: "", title: "All"},
+];
+
+const STATUS_VARIANTS = {
+  open: "primary",
+  resolved: "success",
+  ignored: "secondary",
+};
+
+/**
+ * Group afterstream messages by the stream they were added during, keeping
+ * the order of the messages.
+ *
+ * @param {array} data afterstream messages to group.
+ */
+function groupByStream(data) {
+  let groups = [];
+  let byStream = {};
+
+  for (let a of data) {
+    let key = a.stream_id || "";
+    let group = byStream[key];
+
+    if (group === undefined) {
+      group = {streamId: a.stream_id, entries: []};
+      byStream[key] = group;
+      groups.push(group);
+    }
+
+    group.entries.push(a);
+  }
+
+  return groups;
+}
+
 export default class AfterStreams extends React.Component {
   constructor(props) {
     super(props);
@@ -14,6 +66,11 @@ export default class AfterStreams extends React.Component {
       configLoading: false,
       error: null,
       data: null,
+      markers: null,
+      status: "open",
+      selected: {},
+      replyTo: null,
+      replyText: "",
     };
   }
 
@@ -24,28 +81,44 @@ export default class AfterStreams extends React.Component {
   /**
    * Refresh the list of after streams.
    */
-  async list() {
+  async list(status = this.state.status) {
     this.setState({
       loading: true,
     });
 
     try {
-      let data = await this.api.afterStreams();
+      let [data, markers] = await Promise.all([
+        this.api.afterStreams(status),
+        this.api.markers(),
+      ]);
 
       this.setState({
         loading: false,
         error: null,
         data,
+        markers,
+        selected: {},
       });
     } catch(e) {
       this.setState({
         loading: false,
         error: `failed to request after streams: ${e}`,
         data: null,
+        markers: null,
       });
     }
   }
 
+  /**
+   * Change the status to filter by.
+   *
+   * @param {string} status the status to show.
+   */
+  async filter(status) {
+    this.setState({status});
+    await this.list(status);
+  }
+
   /**
    * Delete the given afterstream.
    *
@@ -63,6 +136,202 @@ export default class AfterStreams extends React.Component {
     }
   }
 
+  /**
+   * Set the status of the given afterstreams.
+   *
+   * @param {array<number>} ids afterstream ids to update.
+   * @param {string} status the status to set.
+   */
+  async setStatus(ids, status) {
+    if (ids.length === 0) {
+      return;
+    }
+
+    this.setState({loading: true});
+
+    try {
+      await this.api.setAfterStreamStatus(ids, status);
+      await this.list();
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `failed to update after streams: ${e}`,
+      });
+    }
+  }
+
+  /**
+   * Submit the reply which is being written.
+   */
+  async reply(e) {
+    e.preventDefault();
+
+    if (this.state.replyTo === null || this.state.replyText.trim() === "") {
+      return;
+    }
+
+    this.setState({loading: true});
+
+    try {
+      await this.api.replyAfterStream(this.state.replyTo, this.state.replyText);
+      this.setState({replyTo: null, replyText: ""});
+      await this.list();
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `failed to reply to after stream: ${e}`,
+      });
+    }
+  }
+
+  /**
+   * Get the ids of all selected afterstreams.
+   */
+  selectedIds() {
+    return Object.keys(this.state.selected)
+      .filter(id => this.state.selected[id])
+      .map(id => parseInt(id));
+  }
+
+  /**
+   * Toggle if the given afterstreams are selected.
+   *
+   * @param {array<number>} ids afterstream ids to toggle.
+   * @param {bool} checked if they should be selected.
+   */
+  select(ids, checked) {
+    let selected = Object.assign({}, this.state.selected);
+
+    for (let id of ids) {
+      selected[id] = checked;
+    }
+
+    this.setState({selected});
+  }
+
+  renderReplies(a) {
+    let replies = a.replies.map(r => {
+      return (
+        <div key={r.id} className="afterstream-reply">
+          <b>{r.user ? `@${r.user}` : "Streamer"}</b>: {r.text}
+          <span className="afterstream-added-at">
+            <span className="afterstream-at">at</span>
+            <span className="afterstream-datetime datetime">{r.added_at}</span>
+          </span>
+        </div>
+      );
+    });
+
+    let form = null;
+
+    if (this.state.replyTo === a.id) {
+      form = (
+        <Form onSubmit={e => this.reply(e)}>
+          <InputGroup size="sm">
+            <Form.Control
+              autoFocus
+              placeholder="Reply..."
+              value={this.state.replyText}
+              disabled={this.state.loading}
+              onChange={e => this.setState({replyText: e.target.value})} />
+            <InputGroup.Append>
+              <Button type="submit" variant="primary" disabled={this.state.loading}>Reply</Button>
+              <Button variant="secondary" onClick={() => this.setState({replyTo: null, replyText: ""})}>Cancel</Button>
+            </InputGroup.Append>
+          </InputGroup>
+        </Form>
+      );
+    }
+
+    return <>{replies}{form}</>;
+  }
+
+  renderEntry(a) {
+    let actions = [];
+
+    if (a.status === "open") {
+      actions.push(
+        <Button key="resolve" size="sm" variant="success" title="Resolve" onClick={() => this.setStatus([a.id], "resolved")}>
+          <FontAwesomeIcon icon="check" />
+        </Button>
+      );
+    } else {
+      actions.push(
+        <Button key="reopen" size="sm" variant="secondary" title="Reopen" onClick={() => this.setStatus([a.id], "open")}>
+          <FontAwesomeIcon icon="undo" />
+        </Button>
+      );
+    }
+
+    return (
+      <tr key={a.id}>
+        <td>
+          <Form.Check
+            type="checkbox"
+            checked={!!this.state.selected[a.id]}
+            onChange={e => this.select([a.id], e.target.checked)} />
+        </td>
+        <td><code>#{a.id}</code></td>
+        <td className="afterstream-user">
+          <a className="afterstream-name" href={`https://twitch.tv/${a.user}`}>@{a.user}</a>
+          <span className="afterstream-added-at">
+            <span className="afterstream-at">at</span>
+            <span className="afterstream-datetime datetime">{a.added_at}</span>
+          </span>
+        </td>
+        <td>
+          <code>{a.text}</code>
+          {this.renderReplies(a)}
+        </td>
+        <td><Badge variant={STATUS_VARIANTS[a.status] || "secondary"}>{a.status}</Badge></td>
+        <td>
+          <ButtonGroup>
+            {actions}
+            <Button size="sm" variant="primary" title="Reply" onClick={() => this.setState({replyTo: a.id, replyText: ""})}>
+              <FontAwesomeIcon icon="reply" />
+            </Button>
+            <Button size="sm" variant="danger" title="Delete" onClick={() => this.delete(a.id)}>
+              <FontAwesomeIcon icon="trash" />
+            </Button>
+          </ButtonGroup>
+        </td>
+      </tr>
+    );
+  }
+
+  renderGroup(group) {
+    let ids = group.entries.map(a => a.id);
+    let allSelected = ids.every(id => !!this.state.selected[id]);
+    let title = group.streamId ? `Stream ${group.streamId}` : "Outside of streams";
+
+    return (
+      <div key={group.streamId || ""}>
+        <h4>{title} <small className="text-muted">({group.entries.length})</small></h4>
+
+        <Table responsive="sm">
+          <thead>
+            <tr>
+              <th>
+                <Form.Check
+                  type="checkbox"
+                  checked={allSelected}
+                  onChange={e => this.select(ids, e.target.checked)} />
+              </th>
+              <th>#</th>
+              <th>User</th>
+              <th className="table-fill">Message</th>
+              <th>Status</th>
+              <th></th>
+            </tr>
+          </thead>
+          <tbody>
+            {group.entries.map(a => this.renderEntry(a))}
+          </tbody>
+        </Table>
+      </div>
+    );
+  }
+
   render() {
     let content = null;
 
@@ -74,41 +343,75 @@ export default class AfterStreams extends React.Component {
           </Alert>
         );
       } else {
-        content = (
-          <Table responsive="sm">
-            <thead>
-              <tr>
-                <th>User</th>
-                <th className="table-fill">Message</th>
-                <th></th>
-              </tr>
-            </thead>
-            <tbody>
-              {this.state.data.map((a, id) => {
-                return (
-                  <tr key={id}>
-                    <td className="afterstream-user">
-                      <a className="afterstream-name" href={`https://twitch.tv/${a.user}`}>@{a.user}</a>
-                      <span className="afterstream-added-at">
-                        <span className="afterstream-at">at</span>
-                        <span className="afterstream-datetime datetime">{a.added_at}</span>
-                      </span>
-                    </td>
-                    <td><code>{a.text}</code></td>
-                    <td>
-                      <Button size="sm" variant="danger" className="action" onClick={() => this.delete(a.id)}>
-                        <FontAwesomeIcon icon="trash" />
-                      </Button>
-                    </td>
-                  </tr>
-                );
-              })}
-            </tbody>
-          </Table>
-        );
+        content = groupByStream(this.state.data).map(group => this.renderGroup(group));
       }
     }
 
+    let selected = this.selectedIds();
+
+    let controls = (
+      <Form inline className="mb-3">
+        <Form.Control
+          as="select"
+          size="sm"
+          className="mr-2"
+          value={this.state.status}
+          disabled={this.state.loading}
+          onChange={e => this.filter(e.target.value)}>
+          {STATUSES.map(s => <option key={s.value} value={s.value}>{s.title}</option>)}
+        </Form.Control>
+
+        <ButtonGroup size="sm">
+          <Button variant="success" disabled={this.state.loading || selected.length === 0} onClick={() => this.setStatus(selected, "resolved")}>
+            Resolve selected ({selected.length})
+          </Button>
+          <Button variant="secondary" disabled={this.state.loading || selected.length === 0} onClick={() => this.setStatus(selected, "ignored")}>
+            Ignore selected
+          </Button>
+          <Button variant="primary" disabled={this.state.loading || selected.length === 0} onClick={() => this.setStatus(selected, "open")}>
+            Reopen selected
+          </Button>
+        </ButtonGroup>
+      </Form>
+    );
+
+    let markers = null;
+
+    if (this.state.markers && this.state.markers.length > 0) {
+      markers = <>
+        <h2>Stream Markers</h2>
+
+        <Table responsive="sm">
+          <thead>
+            <tr>
+              <th>Position</th>
+              <th className="table-fill">Description</th>
+              <th>Song</th>
+              <th>User</th>
+            </tr>
+          </thead>
+          <tbody>
+            {this.state.markers.map(m => {
+              return (
+                <tr key={m.id}>
+                  <td>
+                    <code>{formatPosition(m.position_seconds)}</code>
+                    <span className="afterstream-added-at">
+                      <span className="afterstream-at">at</span>
+                      <span className="afterstream-datetime datetime">{m.created_at}</span>
+                    </span>
+                  </td>
+                  <td>{m.description}</td>
+                  <td>{m.song}</td>
+                  <td>{m.user ? `@${m.user}` : null}</td>
+                </tr>
+              );
+            })}
+          </tbody>
+        </Table>
+      </>;
+    }
+
     return <>
       <h1 className='oxi-page-title'>After Streams</h1>
       <Loading isLoading={this.state.loading || this.state.configLoading} />
@@ -118,7 +421,9 @@ export default class AfterStreams extends React.Component {
         onError={error => this.setState({configLoading: false, error})}
       />
 
+      {controls}
       {content}
+      {markers}
     </>;
   }
 }
\ No newline at end of file
@@ -0,0 +1,199 @@
+import React from "react";
+import {Alert, Button, Form, Table} from "react-bootstrap";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Setting where API keys are stored.
+ */
+const SETTING = "web/api-keys";
+
+/**
+ * Roles that can be associated with a key.
+ */
+const ROLES = [
+  {title: "Streamer", value: "@streamer"},
+  {title: "Moderator", value: "@moderator"},
+  {title: "Subscriber", value: "@subscriber"},
+  {title: "VIP", value: "@vip"},
+  {title: "Editor", value: "@editor"},
+  {title: "Everyone", value: "@everyone"},
+];
+
+/**
+ * Generate a new random key.
+ */
+function generateKey() {
+  let bytes = new Uint8Array(24);
+  window.crypto.getRandomValues(bytes);
+  return Array.from(bytes, b => b.toString(16).padStart(2, "0")).join("");
+}
+
+/**
+ * Only show the start of a key.
+ */
+function maskKey(key) {
+  return `${key.slice(0, 6)}…`;
+}
+
+/**
+ * Management of API keys, used by external tools like stream decks to access
+ * the web API.
+ */
+export default class ApiKeys extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      keys: [],
+      role: "@streamer",
+      created: null,
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the list of keys.
+   */
+  async list() {
+    try {
+      let settings = await this.api.settings({key: [SETTING]});
+      let keys = [];
+
+      if (settings.length > 0 && settings[0].value !== null) {
+        keys = settings[0].value;
+      }
+
+      this.setState({loading: false, error: null, keys});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load API keys: ${e}`, keys: []});
+    }
+  }
+
+  /**
+   * Store the given set of keys.
+   */
+  async store(keys) {
+    try {
+      await this.api.editSetting(SETTING, keys);
+      await this.list();
+      return true;
+    } catch (e) {
+      this.setState({error: `failed to store API keys: ${e}`});
+      return false;
+    }
+  }
+
+  /**
+   * Create a new key with the selected role.
+   */
+  async create(e) {
+    e.preventDefault();
+
+    let created = {key: generateKey(), role: this.state.role};
+
+    if (await this.store([...this.state.keys, created])) {
+      this.setState({created});
+    }
+  }
+
+  /**
+   * Revoke the given key.
+   */
+  async revoke(key) {
+    let keys = this.state.keys.filter(k => k.key !== key);
+
+    if (await this.store(keys)) {
+      if (this.state.created !== null && this.state.created.key === key) {
+        this.setState({created: null});
+      }
+    }
+  }
+
+  render() {
+    let created = null;
+
+    if (this.state.created !== null) {
+      created = (
+        <Alert variant="success">
+          Created a new key for <b>{this.state.created.role}</b>, copy it now since it won't be shown again:
+          <pre className="mt-2 mb-0"><code>{this.state.created.key}</code></pre>
+        </Alert>
+      );
+    }
+
+    let content = null;
+
+    if (this.state.keys.length === 0) {
+      content = <Alert variant="info">No API keys.</Alert>;
+    } else {
+      content = (
+        <Table responsive="sm" size="sm">
+          <thead>
+            <tr>
+              <th>Key</th>
+              <th className="table-fill">Role</th>
+              <th></th>
+            </tr>
+          </thead>
+          <tbody>
+            {this.state.keys.map(k => (
+              <tr key={k.key}>
+                <td><code>{maskKey(k.key)}</code></td>
+                <td>{k.role}</td>
+                <td>
+                  <Button size="sm" variant="danger" onClick={() => this.revoke(k.key)}>
+                    Revoke
+                  </Button>
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </Table>
+      );
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">API Keys</h1>
+
+        <p>
+          API keys let external tools, like stream decks, use the web API.
+          Provide the key through the <code>X-Api-Key</code> header.
+          For example, <code>POST /api/chat/send</code> with <code>{`{"message": "Hello!"}`}</code> sends a message to chat,
+          and <code>POST /api/command/run</code> with <code>{`{"command": "song skip"}`}</code> runs a command.
+          What a key has access to is determined by its role.
+        </p>
+
+        <p>
+          Stream Deck actions which make web requests can use the URLs listed by <code>GET /api/deck</code>,
+          like <code>POST /api/deck/skip?key=&lt;key&gt;</code>.
+          These always require a key.
+        </p>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {created}
+
+        <Form inline className="mb-3" onSubmit={e => this.create(e)}>
+          <Form.Control
+            as="select"
+            size="sm"
+            className="mr-2"
+            value={this.state.role}
+            onChange={e => this.setState({role: e.target.value})}>
+            {ROLES.map(r => <option key={r.value} value={r.value}>{r.title}</option>)}
+          </Form.Control>
+          <Button size="sm" type="submit">Create key</Button>
+        </Form>
+
+        {content}
+      </>
+    );
+  }
+}
@@ -133,6 +133,63 @@ export default class Authorization extends React.Component {
     }
   }
 
+  async setMinimumRole(scope, role) {
+    this.setState({loading: true});
+
+    try {
+      await this.api.authMinimumRole(scope, role);
+      await this.list();
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `failed to set minimum role: ${e}`,
+      });
+    }
+  }
+
+  /**
+   * Roles which are part of the role hierarchy, most privileged first.
+   */
+  hierarchy(data) {
+    return data.roles
+      .filter(role => role.rank !== null)
+      .sort((a, b) => b.rank - a.rank);
+  }
+
+  /**
+   * Get the least privileged role which, together with every role above it,
+   * has been granted the given scope.
+   */
+  minimumRole(scope, data) {
+    let minimum = null;
+
+    for (let role of this.hierarchy(data)) {
+      if (!data.grants[`${scope.scope}:${role.role}`]) {
+        break;
+      }
+
+      minimum = role.role;
+    }
+
+    return minimum;
+  }
+
+  /**
+   * Render the selection of a minimum role.
+   */
+  renderMinimumRole(scope, data) {
+    let onChange = e => this.setMinimumRole(scope.scope, e.target.value || null);
+
+    return (
+      <td className="auth-minimum-role">
+        <Form.Control as="select" size="sm" value={this.minimumRole(scope, data) || ""} onChange={onChange} disabled={this.state.loading}>
+          <option value="">nobody</option>
+          {this.hierarchy(data).map(role => <option key={role.role} value={role.role}>{role.role}</option>)}
+        </Form.Control>
+      </td>
+    );
+  }
+
   filtered(data) {
     if (!this.state.filter) {
       return data;
@@ -265,6 +322,7 @@ export default class Authorization extends React.Component {
             <ReactMarkdown source={scope.doc} />
           </div>
         </td>
+        {this.renderMinimumRole(scope, data)}
         {data.roles.map(role => this.renderAuthButton(scope, role, data.grants))}
       </tr>
     );
@@ -278,7 +336,7 @@ export default class Authorization extends React.Component {
 
     return [
       <tr key={`title:${name}`} className="auth-scope-short">
-        <td colSpan={data.roles.length + 1} className="auth-group" title={`Filter for "${name}"`} onClick={setFilter(name)}>
+        <td colSpan={data.roles.length + 2} className="auth-group" title={`Filter for "${name}"`} onClick={setFilter(name)}>
           {name}
 
           <a className="auth-group-filter">
@@ -309,6 +367,7 @@ export default class Authorization extends React.Component {
           <tbody>
             <tr>
               <th className="table-fill"></th>
+              <th title="Grant to this role and every role above it">Minimum Role</th>
               {data.roles.map(role => {
                 return (
                   <th key={role.role} title={role.doc}>
@@ -0,0 +1,153 @@
+import React from "react";
+import {Form, Button, Alert} from "react-bootstrap";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Minimum length of a game name before we search for it.
+ */
+const SEARCH_MIN_LENGTH = 2;
+
+/**
+ * The title and game of the channel.
+ */
+export default class Channel extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      updated: null,
+      title: "",
+      game: "",
+      games: [],
+    };
+  }
+
+  async componentDidMount() {
+    await this.refresh();
+  }
+
+  /**
+   * Refresh the current title and game.
+   */
+  async refresh() {
+    this.setState({loading: true});
+
+    try {
+      let channel = await this.api.channel();
+
+      this.setState({
+        loading: false,
+        error: null,
+        title: channel.title || "",
+        game: channel.game || "",
+      });
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load channel: ${e}`});
+    }
+  }
+
+  /**
+   * Update the title.
+   */
+  async setTitle(e) {
+    e.preventDefault();
+    this.setState({loading: true, updated: null});
+
+    try {
+      await this.api.channelSetTitle(this.state.title);
+      this.setState({loading: false, error: null, updated: "Title updated."});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to set title: ${e}`});
+    }
+  }
+
+  /**
+   * Update the game.
+   */
+  async setGame(e) {
+    e.preventDefault();
+    this.setState({loading: true, updated: null});
+
+    try {
+      let game = await this.api.channelSetGame(this.state.game);
+      this.setState({loading: false, error: null, game: game.name, updated: `Game updated to ${game.name}.`});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to set game: ${e}`});
+    }
+  }
+
+  /**
+   * Change the game being edited, and search for games matching it.
+   */
+  async changeGame(game) {
+    this.setState({game});
+
+    if (game.length < SEARCH_MIN_LENGTH) {
+      return;
+    }
+
+    try {
+      let games = await this.api.channelSearchGames(game);
+      this.setState({games});
+    } catch (e) {
+      this.setState({error: `failed to search for games: ${e}`});
+    }
+  }
+
+  render() {
+    let updated = null;
+
+    if (this.state.updated !== null) {
+      updated = <Alert variant="success">{this.state.updated}</Alert>;
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Channel</h1>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+
+        {updated}
+
+        <Form onSubmit={e => this.setTitle(e)}>
+          <Form.Group id="title">
+            <Form.Label>Title</Form.Label>
+            <Form.Control
+              disabled={this.state.loading}
+              value={this.state.title}
+              onChange={e => this.setState({title: e.target.value})} />
+          </Form.Group>
+
+          <Button variant="primary" type="submit" disabled={this.state.loading || !this.state.title}>
+            Update Title
+          </Button>
+        </Form>
+
+        <Form className="mt-3" onSubmit={e => this.setGame(e)}>
+          <Form.Group id="game">
+            <Form.Label>Game</Form.Label>
+            <Form.Control
+              list="games"
+              disabled={this.state.loading}
+              value={this.state.game}
+              onChange={e => this.changeGame(e.target.value)} />
+            <datalist id="games">
+              {this.state.games.map(g => <option key={g.id} value={g.name} />)}
+            </datalist>
+            <Form.Text>
+              The game doesn't have to be spelled exactly, the closest match will be used.
+            </Form.Text>
+          </Form.Group>
+
+          <Button variant="primary" type="submit" disabled={this.state.loading || !this.state.game}>
+            Update Game
+          </Button>
+        </Form>
+      </>
+    );
+  }
+}
@@ -400,6 +400,27 @@ export default class Chat extends React.Component {
     return React.cloneElement(img, {key});
   }
 
+  /**
+   * Render an emote, with any zero-width emotes stacked on top of it.
+   */
+  renderEmote(key, rendered, item) {
+    let overlays = item.overlays || [];
+
+    if (overlays.length === 0) {
+      return this.cachedEmote(key, rendered, item);
+    }
+
+    return (
+      <span className="chat-emote-stack" key={key}>
+        {this.cachedEmote(0, rendered, item)}
+        {overlays.map((emote, i) => {
+          let img = this.cachedEmote(i + 1, rendered, {emote});
+          return React.cloneElement(img, {className: "chat-emote-overlay"});
+        })}
+      </span>
+    );
+  }
+
   /**
    * Renders all badges as elements.
    */
@@ -449,7 +470,7 @@ export default class Chat extends React.Component {
         case "url":
           return <a className="url" href={item.url} key={i}>{item.url}</a>;
         case "emote":
-          return this.cachedEmote(i, rendered, item);
+          return this.renderEmote(i, rendered, item);
         default:
           return <em key={i}>?</em>;
       }
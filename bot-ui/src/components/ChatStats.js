@@ -0,0 +1,100 @@
+import React from "react";
+import {Table} from "react-bootstrap";
+import * as moment from "moment";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Format the most used emotes of a stream.
+ */
+function formatEmotes(emotes) {
+  if (emotes.length === 0) {
+    return <em>none</em>;
+  }
+
+  return emotes.map(([emote, count]) => `${emote} (${count})`).join(", ");
+}
+
+/**
+ * Format subscriptions made during a stream.
+ */
+function formatSubs(subs) {
+  return `${subs.subs} new, ${subs.resubs} resub(s), ${subs.gifted} gifted`;
+}
+
+/**
+ * Chat activity for the current and past streams.
+ */
+export default class ChatStats extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      data: null,
+    };
+  }
+
+  async componentDidMount() {
+    try {
+      let data = await this.api.chatStatsStreams();
+      this.setState({loading: false, error: null, data});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load chat stats: ${e}`, data: null});
+    }
+  }
+
+  renderRow(stream, key, title) {
+    let started = stream.started_at ? moment(stream.started_at).format("LLL") : <em>unknown</em>;
+
+    return (
+      <tr key={key}>
+        <td>{title}</td>
+        <td>{started}</td>
+        <td>{stream.messages}</td>
+        <td>{stream.chatters}</td>
+        <td>{formatEmotes(stream.emotes)}</td>
+        <td>{formatSubs(stream.subs)}</td>
+      </tr>
+    );
+  }
+
+  render() {
+    let content = null;
+
+    if (this.state.data !== null) {
+      let {current, history} = this.state.data;
+
+      content = (
+        <Table responsive="sm" size="sm">
+          <thead>
+            <tr>
+              <th>Stream</th>
+              <th>Started</th>
+              <th>Messages</th>
+              <th>Chatters</th>
+              <th>Top Emotes</th>
+              <th>Subscriptions</th>
+            </tr>
+          </thead>
+          <tbody>
+            {this.renderRow(current, "current", <b>Current</b>)}
+            {history.map((stream, index) => {
+              return this.renderRow(stream, index, `Ended ${moment(stream.ended_at).fromNow()}`);
+            })}
+          </tbody>
+        </Table>
+      );
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Chat Stats</h1>
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {content}
+      </>
+    );
+  }
+}
@@ -0,0 +1,89 @@
+import React from "react";
+import {Alert, Table} from "react-bootstrap";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Base URL of clips.
+ */
+const CLIPS_URL = "http://clips.twitch.tv";
+
+/**
+ * History of clips created through the !clip command.
+ */
+export default class Clips extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      clips: [],
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the list of clips.
+   */
+  async list() {
+    try {
+      let clips = await this.api.clips();
+      this.setState({loading: false, error: null, clips});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load clips: ${e}`, clips: []});
+    }
+  }
+
+  render() {
+    let content = null;
+
+    if (!this.state.loading) {
+      if (this.state.clips.length === 0) {
+        content = <Alert variant="info">No clips.</Alert>;
+      } else {
+        content = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>When</th>
+                <th className="table-fill">Title</th>
+                <th>Game</th>
+                <th>Song</th>
+                <th>User</th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.clips.map(c => (
+                <tr key={c.id}>
+                  <td><span className="datetime">{c.created_at}</span></td>
+                  <td>
+                    <a href={`${CLIPS_URL}/${c.clip_id}`} target="_blank" rel="noopener noreferrer">
+                      {c.title || c.clip_id}
+                    </a>
+                  </td>
+                  <td>{c.game}</td>
+                  <td>{c.song}</td>
+                  <td>{c.user}</td>
+                </tr>
+              ))}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Clips</h1>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {content}
+      </>
+    );
+  }
+}
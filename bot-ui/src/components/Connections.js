@@ -1,8 +1,26 @@
 import React from "react";
-import {Alert, Table} from "react-bootstrap";
+import {Alert, Badge, Table} from "react-bootstrap";
 import Loading from 'shared-ui/components/Loading';
 import Error from 'shared-ui/components/Error';
 
+const CONNECTIONS_URL = "https://setbac.tv/connections";
+
+/**
+ * Render the state of a connection, if it requires attention.
+ */
+function ConnectionState(props) {
+  switch (props.state) {
+  case "expiring":
+    return <Badge variant="warning">Expiring</Badge>;
+  case "degraded":
+    return <Badge variant="danger">Degraded</Badge>;
+  case "disconnected":
+    return <Badge variant="danger">Disconnected</Badge>;
+  default:
+    return null;
+  }
+}
+
 export default class Connections extends React.Component {
   constructor(props) {
     super(props);
@@ -40,6 +58,17 @@ export default class Connections extends React.Component {
     }
 
     let content = null;
+    let degraded = this.state.connections.filter(c => c.state && c.state !== "healthy");
+    let degradedAlert = null;
+
+    if (degraded.length > 0) {
+      degradedAlert = (
+        <Alert variant="danger">
+          <b>Connection degraded:</b> {degraded.map(c => c.title).join(", ")}.
+          {" "}Please <a href={CONNECTIONS_URL}>re-authenticate on setbac.tv</a>.
+        </Alert>
+      );
+    }
 
     if (!this.state.loading) {
       content = (
@@ -49,7 +78,7 @@ export default class Connections extends React.Component {
               return (
                 <tr key={id}>
                   <td>
-                    <b>{c.title}</b><br />
+                    <b>{c.title}</b> <ConnectionState state={c.state} /><br />
                     {c.description}
                   </td>
                 </tr>
@@ -64,11 +93,12 @@ export default class Connections extends React.Component {
       <>
         <p>
           These are your active connections.
-          You can manage them in <a href="https://setbac.tv/connections">My Connections on setbac.tv</a>.
+          You can manage them in <a href={CONNECTIONS_URL}>My Connections on setbac.tv</a>.
         </p>
 
         <Loading isLoading={this.state.loading} />
         <Error error={this.state.error} />
+        {degradedAlert}
 
         {content}
       </>
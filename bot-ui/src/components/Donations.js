@@ -0,0 +1,141 @@
+import React from "react";
+import {Alert, Table} from "react-bootstrap";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Format a donation amount.
+ */
+function formatAmount(amount, currency) {
+  return `${amount.toFixed(2)} ${currency}`;
+}
+
+/**
+ * Table of donation totals, optionally by donor.
+ */
+function Totals(props) {
+  if (props.totals.length === 0) {
+    return <Alert variant="info">No donations.</Alert>;
+  }
+
+  return (
+    <Table responsive="sm" size="sm">
+      <thead>
+        <tr>
+          {props.byUser ? <th>Donor</th> : null}
+          <th className="table-fill">Amount</th>
+          <th>Donations</th>
+        </tr>
+      </thead>
+      <tbody>
+        {props.totals.map((t, i) => (
+          <tr key={i}>
+            {props.byUser ? <td>{t.user}</td> : null}
+            <td>{formatAmount(t.amount, t.currency)}</td>
+            <td>{t.count}</td>
+          </tr>
+        ))}
+      </tbody>
+    </Table>
+  );
+}
+
+/**
+ * Donations made through Streamlabs or StreamElements.
+ */
+export default class Donations extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      summary: null,
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the donation summary.
+   */
+  async list() {
+    try {
+      let summary = await this.api.donations();
+      this.setState({loading: false, error: null, summary});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load donations: ${e}`, summary: null});
+    }
+  }
+
+  render() {
+    let content = null;
+    let summary = this.state.summary;
+
+    if (!this.state.loading && summary !== null) {
+      let stream = null;
+
+      if (summary.stream !== null) {
+        stream = (
+          <>
+            <h4>Latest Stream <small className="datetime">{summary.stream.started_at}</small></h4>
+            <Totals totals={summary.stream.totals} />
+            <Totals totals={summary.stream.top} byUser={true} />
+          </>
+        );
+      }
+
+      let recent = <Alert variant="info">No donations.</Alert>;
+
+      if (summary.recent.length > 0) {
+        recent = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>When</th>
+                <th>Donor</th>
+                <th>Amount</th>
+                <th className="table-fill">Message</th>
+                <th>Via</th>
+              </tr>
+            </thead>
+            <tbody>
+              {summary.recent.map(d => (
+                <tr key={d.id}>
+                  <td><span className="datetime">{d.donated_at}</span></td>
+                  <td>{d.user}</td>
+                  <td>{formatAmount(d.amount, d.currency)}</td>
+                  <td>{d.message}</td>
+                  <td>{d.provider}</td>
+                </tr>
+              ))}
+            </tbody>
+          </Table>
+        );
+      }
+
+      content = (
+        <>
+          {stream}
+          <h4>All Time</h4>
+          <Totals totals={summary.totals} />
+          <Totals totals={summary.top} byUser={true} />
+          <h4>Recent Donations</h4>
+          {recent}
+        </>
+      );
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Donations</h1>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {content}
+      </>
+    );
+  }
+}
@@ -0,0 +1,145 @@
+import React from "react";
+import {Form, Button, Alert, Table, InputGroup} from "react-bootstrap";
+import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * The custom answers of the magic 8 ball.
+ */
+export default class EightBall extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      answers: null,
+      answer: "",
+      weight: 1,
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the list of answers.
+   */
+  async list() {
+    this.setState({loading: true});
+
+    try {
+      let answers = await this.api.eightBallAnswers(this.props.current.channel);
+      this.setState({loading: false, error: null, answers});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load answers: ${e}`, answers: null});
+    }
+  }
+
+  /**
+   * Add the answer in the form.
+   */
+  async add(e) {
+    e.preventDefault();
+    this.setState({loading: true});
+
+    try {
+      await this.api.eightBallAdd(this.props.current.channel, this.state.answer, parseInt(this.state.weight, 10));
+      this.setState({answer: "", weight: 1});
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to add answer: ${e}`});
+    }
+  }
+
+  /**
+   * Remove the answer with the given id.
+   */
+  async remove(id) {
+    this.setState({loading: true});
+
+    try {
+      await this.api.eightBallRemove(this.props.current.channel, id);
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to remove answer: ${e}`});
+    }
+  }
+
+  render() {
+    let answers = null;
+
+    if (this.state.answers !== null) {
+      if (this.state.answers.length === 0) {
+        answers = <Alert variant="info">No custom answers, the built-in answers are used.</Alert>;
+      } else {
+        let total = this.state.answers.reduce((sum, a) => sum + a.weight, 0);
+
+        answers = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>#</th>
+                <th className="table-fill">Answer</th>
+                <th>Weight</th>
+                <th>Chance</th>
+                <th></th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.answers.map(a => (
+                <tr key={a.id}>
+                  <td>{a.id}</td>
+                  <td><code>{a.answer}</code></td>
+                  <td>{a.weight}</td>
+                  <td>{total > 0 ? `${Math.round(a.weight * 100 / total)}%` : "-"}</td>
+                  <td>
+                    <Button size="sm" variant="danger" disabled={this.state.loading} onClick={() => this.remove(a.id)}>
+                      <FontAwesomeIcon icon="trash" />
+                    </Button>
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">8 Ball</h1>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+
+        <Form onSubmit={e => this.add(e)} className="mb-3">
+          <InputGroup size="sm">
+            <Form.Control
+              disabled={this.state.loading}
+              value={this.state.answer}
+              placeholder="Ask again later, {{name}}."
+              onChange={e => this.setState({answer: e.target.value})} />
+            <Form.Control type="number" min="1" max="1000" className="eight-ball-weight"
+              disabled={this.state.loading}
+              value={this.state.weight}
+              onChange={e => this.setState({weight: e.target.value})} />
+            <InputGroup.Append>
+              <Button variant="primary" type="submit" disabled={this.state.loading || !this.state.answer || !(this.state.weight >= 1 && this.state.weight <= 1000)}>
+                Add
+              </Button>
+            </InputGroup.Append>
+          </InputGroup>
+          <Form.Text>
+            Answers are templates, where <code>{"{{name}}"}</code> is the name of the asker and <code>{"{{question}}"}</code> is their question.
+            Answers with a higher weight are picked more often.
+          </Form.Text>
+        </Form>
+
+        {answers}
+      </>
+    );
+  }
+}
@@ -0,0 +1,91 @@
+import React from "react";
+import {Alert, Table} from "react-bootstrap";
+import {Link} from "react-router-dom";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Render an optional flag value.
+ */
+function renderValue(value) {
+  if (value === null) {
+    return <em>not set</em>;
+  }
+
+  return value ? "enabled" : "disabled";
+}
+
+/**
+ * Feature flags, as published remotely and overridden locally.
+ */
+export default class Flags extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      flags: null,
+    };
+  }
+
+  async componentDidMount() {
+    try {
+      let flags = await this.api.flags();
+      this.setState({loading: false, error: null, flags});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load flags: ${e}`, flags: null});
+    }
+  }
+
+  render() {
+    let content = null;
+
+    if (this.state.flags !== null) {
+      if (this.state.flags.length === 0) {
+        content = <Alert variant="info">No feature flags are known.</Alert>;
+      } else {
+        content = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>Flag</th>
+                <th>Remote</th>
+                <th>Local</th>
+                <th>In Effect</th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.flags.map(f => (
+                <tr key={f.id}>
+                  <td>
+                    <code>{f.id}</code>
+                    {f.doc ? <div className="text-muted">{f.doc}</div> : null}
+                  </td>
+                  <td>{renderValue(f.remote)}</td>
+                  <td>{renderValue(f.local)}</td>
+                  <td><b>{f.enabled ? "enabled" : "disabled"}</b></td>
+                </tr>
+              ))}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Feature Flags</h1>
+
+        <p>
+          Feature flags gate experimental modules and features.
+          They can be overridden locally under <Link to="/settings?q=flags/">Settings</Link>.
+        </p>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {content}
+      </>
+    );
+  }
+}
@@ -17,6 +17,9 @@ export default class ImportExport extends React.Component {
       <Row>
         <Col sm="2">
           <Nav className="flex-column" variant="pills">
+            <Nav.Link as={Link} active={path === "/import-export/settings"} to="/import-export/settings">
+              OxidizeBot Settings
+            </Nav.Link>
             <Nav.Link as={Link} active={path === "/import-export/phantombot"} to="/import-export/phantombot">
               PhantomBot
             </Nav.Link>
@@ -27,6 +30,7 @@ export default class ImportExport extends React.Component {
         </Col>
         <Col>
           <Route path="/import-export" exact render={props => <Index {...props} />} />
+          <Route path="/import-export/settings" render={props => <Settings api={this.props.api} {...props} />} />
           <Route path="/import-export/phantombot" render={props => <PhantomBot api={this.props.api} {...props} />} />
           <Route path="/import-export/drangrybot" render={props => <DrangryBot api={this.props.api} {...props} />} />
         </Col>
@@ -53,6 +57,174 @@ class Index extends React.Component {
   }
 }
 
+class Settings extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: false,
+      error: null,
+      format: "toml",
+      text: "",
+      result: null,
+    };
+  }
+
+  /**
+   * Export all settings to a file.
+   *
+   * @param {*} e the event being handled.
+   */
+  async export(e) {
+    e.preventDefault();
+
+    try {
+      let exported = await this.api.exportSettings(this.state.format);
+      utils.download("text/plain", exported.content, `settings.${exported.format}`);
+      this.setState({error: null});
+    } catch(e) {
+      this.setState({error: `Failed to export settings: ${e}`});
+    }
+
+    return false;
+  }
+
+  /**
+   * Import settings to OxidizeBot.
+   *
+   * @param {*} e the event being handled.
+   */
+  async import(e) {
+    e.preventDefault();
+
+    this.setState({
+      loading: true,
+      result: null,
+    });
+
+    try {
+      let result = await this.api.importSettings(this.state.format, this.state.text);
+
+      this.setState({
+        loading: false,
+        error: null,
+        result,
+      });
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `Failed to import settings: ${e}`,
+      });
+    }
+  }
+
+  renderResult() {
+    let result = this.state.result;
+
+    if (result === null) {
+      return null;
+    }
+
+    let errors = Object.keys(result.errors);
+
+    if (errors.length > 0) {
+      return (
+        <Alert variant="danger">
+          No settings were imported, since some of them are invalid:
+          <ul className="mb-0">
+            {errors.map(key => (
+              <li key={key}>
+                <code>{key}</code>: {result.errors[key].map(e => `${e.path} ${e.message}`.trim()).join(", ")}
+              </li>
+            ))}
+          </ul>
+        </Alert>
+      );
+    }
+
+    let ignored = null;
+
+    if (result.ignored.length > 0) {
+      ignored = <> Ignored unknown or secret settings: {result.ignored.map(key => <code key={key} className="mr-1">{key}</code>)}</>;
+    }
+
+    return (
+      <Alert variant="info">
+        Successfully imported {result.imported.length} settings!{ignored}
+      </Alert>
+    );
+  }
+
+  render() {
+    let error = null;
+
+    if (!!this.state.error) {
+      error = <Alert variant="danger">{this.state.error}</Alert>;
+    }
+
+    return (
+      <>
+        <div className="mb-3">
+          <h2>OxidizeBot Settings</h2>
+
+          <p>
+            Move settings between installations of OxidizeBot, or keep a copy of them around.
+            Secrets, like connection tokens, are never exported.
+          </p>
+        </div>
+
+        {error}
+        {this.renderResult()}
+
+        <Form.Group id="format">
+          <Form.Label>Format</Form.Label>
+          <Form.Control as="select"
+            disabled={this.state.loading}
+            value={this.state.format}
+            onChange={e => this.setState({format: e.target.value})}>
+            <option value="toml">TOML</option>
+            <option value="json">JSON</option>
+          </Form.Control>
+        </Form.Group>
+
+        <Row>
+          <Col>
+            <h4>Import</h4>
+
+            <Form onSubmit={e => this.import(e)} disabled={this.state.loading}>
+              <Form.Group id="content">
+                <Form.Control as="textarea" rows="10"
+                  disabled={this.state.loading}
+                  value={this.state.text}
+                  onChange={e => this.setState({text: e.target.value})}
+                  placeholder={'"song/max-queue-length" = 30'} />
+                <Form.Text>
+                  Settings to import. Nothing is imported if any setting is invalid.
+                </Form.Text>
+              </Form.Group>
+
+              <Button variant="primary" type="submit" disabled={this.state.loading}>
+                Import
+              </Button>
+
+              <Loading isLoading={this.state.loading} />
+            </Form>
+          </Col>
+
+          <Col>
+            <h4>Export</h4>
+
+            <Form onSubmit={e => this.export(e)}>
+              <Button type="submit">Export to File</Button>
+            </Form>
+          </Col>
+        </Row>
+      </>
+    );
+  }
+}
+
 class PhantomBot extends React.Component {
   constructor(props) {
     super(props);
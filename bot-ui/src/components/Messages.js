@@ -0,0 +1,225 @@
+import React from "react";
+import {Button, Alert, Table, Form} from "react-bootstrap";
+import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Pick the language of chat messages, and override individual messages.
+ */
+export default class Messages extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      data: null,
+      filter: "",
+      editing: null,
+      template: "",
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the list of messages.
+   */
+  async list() {
+    this.setState({loading: true});
+
+    try {
+      let data = await this.api.messages();
+      this.setState({loading: false, error: null, data});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load messages: ${e}`, data: null});
+    }
+  }
+
+  /**
+   * Change the language of messages.
+   */
+  async setLanguage(language) {
+    this.setState({loading: true});
+
+    try {
+      await this.api.editSetting("language", language);
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to change language: ${e}`});
+    }
+  }
+
+  /**
+   * Start editing the given message.
+   */
+  edit(m) {
+    let template = m.override !== null ? m.override : (m.default || "");
+    this.setState({editing: m.key, template});
+  }
+
+  /**
+   * Save the override of the message being edited.
+   */
+  async save(e) {
+    e.preventDefault();
+    this.setState({loading: true});
+
+    try {
+      await this.api.messageEdit(this.state.editing, this.state.template);
+      this.setState({editing: null, template: ""});
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to override message: ${e}`});
+    }
+  }
+
+  /**
+   * Restore the default of the given message.
+   */
+  async reset(key) {
+    this.setState({loading: true});
+
+    try {
+      await this.api.messageDelete(key);
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to reset message: ${e}`});
+    }
+  }
+
+  renderMessage(m) {
+    let text = null;
+
+    if (this.state.editing === m.key) {
+      text = (
+        <Form onSubmit={e => this.save(e)}>
+          <Form.Control
+            as="textarea"
+            size="sm"
+            className="mb-2"
+            value={this.state.template}
+            onChange={e => this.setState({template: e.target.value})} />
+          <Button size="sm" type="submit" className="mr-2" disabled={this.state.loading}>
+            Save
+          </Button>
+          <Button size="sm" variant="secondary" onClick={() => this.setState({editing: null})}>
+            Cancel
+          </Button>
+        </Form>
+      );
+    } else if (m.override !== null) {
+      text = <>
+        <div><b>{m.override}</b></div>
+        <small className="text-muted">Default: {m.default}</small>
+      </>;
+    } else {
+      text = <div>{m.default}</div>;
+    }
+
+    let vars = null;
+
+    if (m.vars.length > 0) {
+      vars = <small className="text-muted">Variables: {m.vars.map(v => `{{${v}}}`).join(", ")}</small>;
+    }
+
+    let reset = null;
+
+    if (m.override !== null) {
+      reset = (
+        <Button size="sm" variant="danger" disabled={this.state.loading} onClick={() => this.reset(m.key)}>
+          <FontAwesomeIcon icon="undo" title="Restore default" />
+        </Button>
+      );
+    }
+
+    return (
+      <tr key={m.key}>
+        <td>
+          <code>{m.key}</code>
+          <div><small>{m.doc}</small></div>
+        </td>
+        <td className="table-fill">
+          {text}
+          {vars}
+        </td>
+        <td>
+          <Button size="sm" className="mr-2" disabled={this.state.loading} onClick={() => this.edit(m)}>
+            <FontAwesomeIcon icon="edit" title="Override" />
+          </Button>
+          {reset}
+        </td>
+      </tr>
+    );
+  }
+
+  render() {
+    let content = null;
+
+    if (this.state.data !== null) {
+      let filter = this.state.filter.toLowerCase();
+      let messages = this.state.data.messages.filter(m => {
+        return filter === "" || m.key.includes(filter) || (m.default || "").toLowerCase().includes(filter);
+      });
+
+      let table = <Alert variant="info">No matching messages.</Alert>;
+
+      if (messages.length > 0) {
+        table = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>Message</th>
+                <th className="table-fill">Text</th>
+                <th></th>
+              </tr>
+            </thead>
+            <tbody>
+              {messages.map(m => this.renderMessage(m))}
+            </tbody>
+          </Table>
+        );
+      }
+
+      content = <>
+        <Form inline className="mb-3">
+          <Form.Label className="mr-2">Language</Form.Label>
+          <Form.Control
+            as="select"
+            size="sm"
+            className="mr-2"
+            value={this.state.data.language}
+            disabled={this.state.loading}
+            onChange={e => this.setLanguage(e.target.value)}>
+            {this.state.data.languages.map(l => <option key={l.id} value={l.id}>{l.title}</option>)}
+          </Form.Control>
+          <Form.Control
+            size="sm"
+            placeholder="Filter"
+            value={this.state.filter}
+            onChange={e => this.setState({filter: e.target.value})} />
+        </Form>
+
+        {table}
+      </>;
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Messages</h1>
+
+        <p>
+          Chat messages sent by the bot. Overrides are used regardless of the selected language.
+        </p>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+
+        {content}
+      </>
+    );
+  }
+}
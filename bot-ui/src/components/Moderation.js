@@ -0,0 +1,164 @@
+import React from "react";
+import {Button, Alert, Table, Form} from "react-bootstrap";
+import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
+import {Loading, Error} from 'shared-ui/components';
+
+const ACTIONS = [
+  {value: "", title: "All actions"},
+  {value: "delete", title: "Deletions"},
+  {value: "timeout", title: "Timeouts"},
+  {value: "ban", title: "Bans"},
+];
+
+/**
+ * Review deletions, timeouts, and bans in chat.
+ */
+export default class Moderation extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      actions: null,
+      action: "",
+      user: "",
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the list of moderation actions.
+   */
+  async list(e) {
+    if (e) {
+      e.preventDefault();
+    }
+
+    this.setState({loading: true});
+
+    try {
+      let actions = await this.api.moderationLog(this.props.current.channel, {
+        action: this.state.action,
+        user: this.state.user,
+      });
+
+      this.setState({loading: false, error: null, actions});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load moderation log: ${e}`, actions: null});
+    }
+  }
+
+  /**
+   * Undo the given timeout or ban.
+   */
+  async undo(id) {
+    this.setState({loading: true});
+
+    try {
+      await this.api.moderationUndo(this.props.current.channel, id);
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to undo action: ${e}`});
+    }
+  }
+
+  renderAction(a) {
+    let undo = null;
+
+    if (a.undone_at !== null) {
+      undo = <small className="text-muted">undone</small>;
+    } else if (a.action === "timeout" || a.action === "ban") {
+      undo = (
+        <Button size="sm" variant="warning" disabled={this.state.loading} onClick={() => this.undo(a.id)}>
+          <FontAwesomeIcon icon="undo" title="Undo" />
+        </Button>
+      );
+    }
+
+    let action = a.action;
+
+    if (a.duration !== null) {
+      action = `${action} (${a.duration}s)`;
+    }
+
+    return (
+      <tr key={a.id}>
+        <td><small className="datetime">{a.at}</small></td>
+        <td>{action}</td>
+        <td>{a.user}</td>
+        <td>{a.rule}</td>
+        <td className="table-fill">{a.message}</td>
+        <td>{undo}</td>
+      </tr>
+    );
+  }
+
+  render() {
+    let actions = null;
+
+    if (this.state.actions !== null) {
+      if (this.state.actions.length === 0) {
+        actions = <Alert variant="info">No moderation actions.</Alert>;
+      } else {
+        actions = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>When</th>
+                <th>Action</th>
+                <th>User</th>
+                <th>Rule</th>
+                <th className="table-fill">Message</th>
+                <th></th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.actions.map(a => this.renderAction(a))}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Moderation</h1>
+
+        <p>
+          Deletions made by the bot, and timeouts and bans seen in chat.
+          Timeouts and bans can be undone here.
+        </p>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+
+        <Form inline className="mb-3" onSubmit={e => this.list(e)}>
+          <Form.Control
+            as="select"
+            size="sm"
+            className="mr-2"
+            value={this.state.action}
+            onChange={e => this.setState({action: e.target.value})}>
+            {ACTIONS.map(a => <option key={a.value} value={a.value}>{a.title}</option>)}
+          </Form.Control>
+          <Form.Control
+            size="sm"
+            className="mr-2"
+            placeholder="User"
+            value={this.state.user}
+            onChange={e => this.setState({user: e.target.value})} />
+          <Button size="sm" type="submit" disabled={this.state.loading}>
+            <FontAwesomeIcon icon="search" />
+          </Button>
+        </Form>
+
+        {actions}
+      </>
+    );
+  }
+}
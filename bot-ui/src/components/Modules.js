@@ -105,6 +105,25 @@ function ChatLog(props) {
   );
 }
 
+function Shoutout(props) {
+  return (
+    <div>
+      <h3>Shoutouts</h3>
+
+      <p>
+        Give shoutouts to other streamers with <code>!so &lt;user&gt;</code>.
+        The shoutout is rendered from a template, which can link to their channel and mention the game they last played.
+      </p>
+
+      <ConfigurationPrompt
+        group={true}
+        filter={{prefix: ["shoutout"]}}
+        {...props}
+        />
+    </div>
+  );
+}
+
 function Index(props) {
   return (
     <div>
@@ -146,6 +165,9 @@ export default class Modules extends React.Component {
             <Nav.Link as={Link} active={path === "/modules/chat-log"} to="/modules/chat-log">
               Chat Log
             </Nav.Link>
+            <Nav.Link as={Link} active={path === "/modules/shoutout"} to="/modules/shoutout">
+              Shoutouts
+            </Nav.Link>
             <Nav.Link as={Link} active={path === "/modules/gtav"} to="/modules/gtav">
               ChaosMod
             </Nav.Link>
@@ -157,6 +179,7 @@ export default class Modules extends React.Component {
           <Route path="/modules/player" render={props => <Player api={this.props.api} {...props} />} />
           <Route path="/modules/currency" render={props => <Currency api={this.props.api} {...props} />} />
           <Route path="/modules/chat-log" render={props => <ChatLog api={this.props.api} {...props} />} />
+          <Route path="/modules/shoutout" render={props => <Shoutout api={this.props.api} {...props} />} />
           <Route path="/modules/gtav" render={props => <Gtav api={this.props.api} {...props} />} />
         </Col>
       </Row>
@@ -0,0 +1,131 @@
+import React from "react";
+import {Alert, Badge, Button, Table} from "react-bootstrap";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Map a notification icon to a badge variant.
+ */
+function iconVariant(icon) {
+  switch (icon) {
+    case "error":
+      return "danger";
+    case "warning":
+      return "warning";
+    default:
+      return "info";
+  }
+}
+
+/**
+ * System notifications, like crashes, which would otherwise only show up in
+ * the system tray.
+ */
+export default class Notifications extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      feed: null,
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the list of notifications.
+   */
+  async list() {
+    try {
+      let feed = await this.api.notifications();
+      this.setState({loading: false, error: null, feed});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load notifications: ${e}`, feed: null});
+    }
+  }
+
+  /**
+   * Mark the given notification as read, or all of them if no id is given.
+   *
+   * @param {number | undefined} id notification to mark as read
+   */
+  async read(id) {
+    try {
+      if (id === undefined) {
+        await this.api.notificationsReadAll();
+      } else {
+        await this.api.notificationRead(id);
+      }
+
+      await this.list();
+    } catch (e) {
+      this.setState({error: `failed to mark notification as read: ${e}`});
+    }
+  }
+
+  render() {
+    let content = null;
+
+    if (this.state.feed !== null) {
+      if (this.state.feed.notifications.length === 0) {
+        content = <Alert variant="info">No notifications.</Alert>;
+      } else {
+        content = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>When</th>
+                <th className="table-fill">Notification</th>
+                <th></th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.feed.notifications.map(n => (
+                <tr key={n.id} className={n.read ? "text-muted" : null}>
+                  <td><span className="datetime">{n.added_at}</span></td>
+                  <td>
+                    <Badge variant={iconVariant(n.icon)}>{n.icon}</Badge>{" "}
+                    {n.title ? <b>{n.title}: </b> : null}
+                    {n.message}
+                  </td>
+                  <td>
+                    {n.read ? null : (
+                      <Button size="sm" variant="secondary" onClick={() => this.read(n.id)}>
+                        Mark read
+                      </Button>
+                    )}
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    let readAll = null;
+
+    if (this.state.feed !== null && this.state.feed.unread > 0) {
+      readAll = (
+        <Button size="sm" className="mb-3" onClick={() => this.read()}>
+          Mark all as read ({this.state.feed.unread} unread)
+        </Button>
+      );
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Notifications</h1>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {readAll}
+        {content}
+      </>
+    );
+  }
+}
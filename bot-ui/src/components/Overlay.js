@@ -178,6 +178,11 @@ export default class Overlay extends React.Component {
                 update.artist = null;
               }
 
+              break;
+            case "bandcamp":
+              update.track = data.track.track.title;
+              update.artist = {name: data.track.track.artist};
+              update.albumArt = null;
               break;
             default:
               break;
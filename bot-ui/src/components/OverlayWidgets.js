@@ -0,0 +1,389 @@
+import Websocket from "react-websocket";
+import React from "react";
+import {websocketUrl} from "../utils.js";
+
+function parseMessage(d) {
+  try {
+    return JSON.parse(d);
+  } catch(e) {
+    console.log("failed to deserialize message");
+    return null;
+  }
+}
+
+/**
+ * Overlay showing the current song, as rendered by the bot.
+ */
+export class OverlayCurrentSong extends React.Component {
+  constructor(props) {
+    super(props);
+
+    this.state = {
+      text: null,
+      theme: "default",
+    };
+  }
+
+  handleData(d) {
+    let data = parseMessage(d);
+
+    if (data === null) {
+      return;
+    }
+
+    switch (data.type) {
+      case "overlay/current-song":
+        this.setState({
+          text: data.text,
+          theme: data.theme,
+        });
+        break;
+      default:
+        break;
+    }
+  }
+
+  render() {
+    let text = null;
+
+    if (this.state.text !== null) {
+      text = <div className="overlay-text">{this.state.text}</div>;
+    }
+
+    return (
+      <div id="overlay-current-song" className={`overlay-theme-${this.state.theme}`}>
+        <Websocket url={websocketUrl("ws/overlay")} onMessage={this.handleData.bind(this)} />
+        {text}
+      </div>
+    );
+  }
+}
+
+/**
+ * Overlay showing chat alerts one at a time, each for as long as configured in the bot.
+ */
+export class OverlayAlerts extends React.Component {
+  constructor(props) {
+    super(props);
+
+    this.timeout = null;
+
+    this.state = {
+      current: null,
+      queue: [],
+    };
+  }
+
+  componentWillUnmount() {
+    if (this.timeout !== null) {
+      clearTimeout(this.timeout);
+      this.timeout = null;
+    }
+  }
+
+  handleData(d) {
+    let data = parseMessage(d);
+
+    if (data === null) {
+      return;
+    }
+
+    switch (data.type) {
+      case "overlay/alert":
+        let alert = {
+          text: data.text,
+          theme: data.theme,
+          duration: data.duration,
+        };
+
+        this.setState(state => ({queue: [...state.queue, alert]}), () => this.next());
+        break;
+      default:
+        break;
+    }
+  }
+
+  /**
+   * Show the next alert in the queue, unless one is already showing.
+   */
+  next() {
+    if (this.state.current !== null || this.state.queue.length === 0) {
+      return;
+    }
+
+    let [current, ...queue] = this.state.queue;
+    this.setState({current, queue});
+
+    this.timeout = setTimeout(() => {
+      this.timeout = null;
+      this.setState({current: null}, () => this.next());
+    }, current.duration * 1000);
+  }
+
+  render() {
+    let alert = null;
+
+    if (this.state.current !== null) {
+      alert = (
+        <div className={`overlay-alert overlay-theme-${this.state.current.theme}`}>
+          {this.state.current.text}
+        </div>
+      );
+    }
+
+    return (
+      <div id="overlay-alerts">
+        <Websocket url={websocketUrl("ws/overlay")} onMessage={this.handleData.bind(this)} />
+        {alert}
+      </div>
+    );
+  }
+}
+
+/**
+ * Overlay speaking text-to-speech messages one at a time, either by playing
+ * audio synthesized by the bot or through the browser's speech synthesis.
+ */
+export class OverlayTts extends React.Component {
+  constructor(props) {
+    super(props);
+
+    this.audio = null;
+    this.speaking = false;
+    this.queue = [];
+    // Identifies the message being spoken, so that late callbacks from a
+    // skipped message are ignored.
+    this.current = 0;
+  }
+
+  componentWillUnmount() {
+    this.skip();
+    this.queue = [];
+  }
+
+  handleData(d) {
+    let data = parseMessage(d);
+
+    if (data === null) {
+      return;
+    }
+
+    switch (data.type) {
+      case "overlay/tts":
+        this.queue.push(data);
+        this.next();
+        break;
+      case "overlay/tts-skip":
+        this.skip();
+        break;
+      default:
+        break;
+    }
+  }
+
+  /**
+   * Stop speaking the current message.
+   */
+  skip() {
+    if (this.audio !== null) {
+      this.audio.pause();
+      this.audio = null;
+    }
+
+    if (window.speechSynthesis) {
+      window.speechSynthesis.cancel();
+    }
+
+    this.done(this.current);
+  }
+
+  /**
+   * Called when the given message is done being spoken.
+   */
+  done(id) {
+    if (id !== this.current) {
+      return;
+    }
+
+    this.current += 1;
+    this.audio = null;
+    this.speaking = false;
+    this.next();
+  }
+
+  /**
+   * Speak the next message in the queue, unless one is already being spoken.
+   */
+  next() {
+    if (this.speaking || this.queue.length === 0) {
+      return;
+    }
+
+    let message = this.queue.shift();
+    let id = this.current;
+    this.speaking = true;
+
+    if (message.audio) {
+      this.audio = new Audio(message.audio);
+      this.audio.onended = () => this.done(id);
+      this.audio.onerror = () => this.done(id);
+      this.audio.play().catch(() => this.done(id));
+      return;
+    }
+
+    if (!window.speechSynthesis) {
+      console.log("speech synthesis not supported");
+      this.done(id);
+      return;
+    }
+
+    let utterance = new SpeechSynthesisUtterance(message.text);
+
+    if (message.voice) {
+      let voice = window.speechSynthesis.getVoices().find(v => v.name === message.voice);
+
+      if (voice) {
+        utterance.voice = voice;
+      }
+    }
+
+    utterance.onend = () => this.done(id);
+    utterance.onerror = () => this.done(id);
+    window.speechSynthesis.speak(utterance);
+  }
+
+  render() {
+    return (
+      <div id="overlay-tts">
+        <Websocket url={websocketUrl("ws/overlay")} onMessage={this.handleData.bind(this)} />
+      </div>
+    );
+  }
+}
+
+/**
+ * Overlay showing all running countdowns, one per line.
+ */
+export class OverlayCountdowns extends React.Component {
+  constructor(props) {
+    super(props);
+
+    this.state = {
+      countdowns: [],
+    };
+  }
+
+  handleData(d) {
+    let data = parseMessage(d);
+
+    if (data === null) {
+      return;
+    }
+
+    switch (data.type) {
+      case "countdowns":
+        this.setState({countdowns: data.countdowns});
+        break;
+      default:
+        break;
+    }
+  }
+
+  render() {
+    let countdowns = this.state.countdowns.map(c => {
+      return <div key={c.name}><div className="overlay-text">{c.text}</div></div>;
+    });
+
+    return (
+      <div id="overlay-countdowns" className="overlay-theme-default">
+        <Websocket url={websocketUrl("ws/overlay")} onMessage={this.handleData.bind(this)} />
+        {countdowns}
+      </div>
+    );
+  }
+}
+
+/**
+ * Overlay showing the live tally of the current poll.
+ *
+ * Closed polls are shown for a little while before they are hidden.
+ */
+export class OverlayPoll extends React.Component {
+  constructor(props) {
+    super(props);
+
+    this.timeout = null;
+
+    this.state = {
+      poll: null,
+    };
+  }
+
+  componentWillUnmount() {
+    this.clearTimeout();
+  }
+
+  clearTimeout() {
+    if (this.timeout !== null) {
+      clearTimeout(this.timeout);
+      this.timeout = null;
+    }
+  }
+
+  handleData(d) {
+    let data = parseMessage(d);
+
+    if (data === null) {
+      return;
+    }
+
+    switch (data.type) {
+      case "poll":
+        this.clearTimeout();
+        this.setState({poll: data});
+
+        if (data.closed) {
+          this.timeout = setTimeout(() => {
+            this.timeout = null;
+            this.setState({poll: null});
+          }, 15000);
+        }
+        break;
+      default:
+        break;
+    }
+  }
+
+  render() {
+    let poll = null;
+
+    if (this.state.poll !== null) {
+      let total = this.state.poll.results.reduce((total, r) => total + r.votes, 0);
+
+      let results = this.state.poll.results.map((r, index) => {
+        let percentage = total === 0 ? 0 : Math.round((r.votes * 100) / total);
+
+        return (
+          <div key={index} className="overlay-poll-result">
+            <div className="overlay-poll-bar" style={{width: `${percentage}%`}} />
+            <div className="overlay-poll-text">{r.description || r.key} ({r.key}): {r.votes} ({percentage}%)</div>
+          </div>
+        );
+      });
+
+      poll = (
+        <div className="overlay-poll overlay-theme-default">
+          <div className="overlay-poll-question">{this.state.poll.question}</div>
+          {results}
+        </div>
+      );
+    }
+
+    return (
+      <div id="overlay-poll">
+        <Websocket url={websocketUrl("ws/overlay")} onMessage={this.handleData.bind(this)} />
+        {poll}
+      </div>
+    );
+  }
+}
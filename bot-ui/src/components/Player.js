@@ -0,0 +1,105 @@
+import React from "react";
+import {Container, Table} from "react-bootstrap";
+import Websocket from "react-websocket";
+import {websocketUrl} from "../utils.js";
+import Loading from 'shared-ui/components/Loading';
+
+/**
+ * Public read-only view of the player, which is kept up to date over a websocket.
+ */
+export default class Player extends React.Component {
+  constructor(props) {
+    super(props);
+
+    this.state = {
+      loading: true,
+      current: null,
+      queue: [],
+      volume: null,
+    };
+  }
+
+  handleData(d) {
+    let data = null;
+
+    try {
+      data = JSON.parse(d);
+    } catch(e) {
+      console.log("failed to deserialize message");
+      return;
+    }
+
+    switch (data.type) {
+      case "player/state":
+        this.setState({
+          loading: false,
+          current: data.current,
+          queue: data.queue,
+          volume: data.volume,
+        });
+        break;
+      default:
+        break;
+    }
+  }
+
+  render() {
+    let streamer = this.props.match.params.streamer;
+    let content = null;
+
+    if (this.state.loading) {
+      content = <Loading isLoading={true} />;
+    } else {
+      let current = <em>Nothing is playing</em>;
+
+      if (this.state.current !== null) {
+        let artists = this.state.current.artists ? ` by ${this.state.current.artists}` : "";
+        let paused = this.state.current.paused ? " (paused)" : "";
+
+        current = <>
+          <b>{this.state.current.name}</b>{artists}{paused}
+        </>;
+      }
+
+      let volume = null;
+
+      if (this.state.volume !== null) {
+        volume = <div className="player-volume">Volume: {this.state.volume}%</div>;
+      }
+
+      content = <>
+        <div className="player-current">Now playing: {current}</div>
+        {volume}
+
+        <Table size="sm" striped>
+          <thead>
+            <tr>
+              <th>#</th>
+              <th>Song</th>
+              <th>Requested by</th>
+              <th>Duration</th>
+            </tr>
+          </thead>
+          <tbody>
+            {this.state.queue.map((item, index) => (
+              <tr key={index}>
+                <td>{index + 1}</td>
+                <td>{item.name}{item.artists ? ` by ${item.artists}` : ""}</td>
+                <td>{item.user || <em>-</em>}</td>
+                <td>{item.duration}</td>
+              </tr>
+            ))}
+          </tbody>
+        </Table>
+      </>;
+    }
+
+    return (
+      <Container className="content">
+        <Websocket url={websocketUrl("ws/player")} onMessage={this.handleData.bind(this)} />
+        <h1>Songs for {streamer}</h1>
+        {content}
+      </Container>
+    );
+  }
+}
@@ -0,0 +1,217 @@
+import React from "react";
+import {Button, Alert, Table, Form, Badge} from "react-bootstrap";
+import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Save and switch between named profiles of settings.
+ */
+export default class Profiles extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      data: null,
+      name: "",
+      prefixes: "",
+      result: null,
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the list of profiles.
+   */
+  async list() {
+    this.setState({loading: true});
+
+    try {
+      let data = await this.api.profiles();
+      this.setState({loading: false, error: null, data});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load profiles: ${e}`, data: null});
+    }
+  }
+
+  /**
+   * Save the current settings as a profile.
+   */
+  async save(e) {
+    e.preventDefault();
+    this.setState({loading: true, result: null});
+
+    let prefixes = this.state.prefixes.split(",").map(p => p.trim()).filter(p => p !== "");
+
+    try {
+      await this.api.profileSave(this.state.name, prefixes);
+      this.setState({name: "", prefixes: ""});
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to save profile: ${e}`});
+    }
+  }
+
+  /**
+   * Activate the given profile.
+   */
+  async activate(name) {
+    this.setState({loading: true, result: null});
+
+    try {
+      let result = await this.api.profileActivate(name);
+      this.setState({result: {name, ...result}});
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to activate profile: ${e}`});
+    }
+  }
+
+  /**
+   * Delete the given profile.
+   */
+  async delete(name) {
+    this.setState({loading: true, result: null});
+
+    try {
+      await this.api.profileDelete(name);
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to delete profile: ${e}`});
+    }
+  }
+
+  renderResult() {
+    let result = this.state.result;
+
+    if (result === null) {
+      return null;
+    }
+
+    let errors = Object.keys(result.errors);
+
+    if (errors.length > 0) {
+      return (
+        <Alert variant="danger">
+          Profile <b>{result.name}</b> was not activated, since some of its settings are invalid:
+          <ul className="mb-0">
+            {errors.map(key => (
+              <li key={key}>
+                <code>{key}</code>: {result.errors[key].map(e => `${e.path} ${e.message}`.trim()).join(", ")}
+              </li>
+            ))}
+          </ul>
+        </Alert>
+      );
+    }
+
+    return (
+      <Alert variant="info">
+        Activated profile <b>{result.name}</b>, updating {result.imported.length} settings.
+      </Alert>
+    );
+  }
+
+  render() {
+    let content = null;
+
+    if (this.state.data !== null) {
+      let names = Object.keys(this.state.data.profiles);
+      let table = <Alert variant="info">No profiles have been saved.</Alert>;
+
+      if (names.length > 0) {
+        table = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>Name</th>
+                <th className="table-fill">Settings</th>
+                <th></th>
+              </tr>
+            </thead>
+            <tbody>
+              {names.map(name => {
+                let keys = Object.keys(this.state.data.profiles[name].settings);
+                let active = null;
+
+                if (this.state.data.active === name) {
+                  active = <Badge variant="success" className="ml-2">active</Badge>;
+                }
+
+                return (
+                  <tr key={name}>
+                    <td><b>{name}</b>{active}</td>
+                    <td className="table-fill">
+                      <small>{keys.length > 0 ? keys.join(", ") : <em>no settings</em>}</small>
+                    </td>
+                    <td>
+                      <Button size="sm" className="mr-2" disabled={this.state.loading} onClick={() => this.activate(name)}>
+                        <FontAwesomeIcon icon="play" title="Activate" />
+                      </Button>
+                      <Button size="sm" variant="danger" disabled={this.state.loading} onClick={() => this.delete(name)}>
+                        <FontAwesomeIcon icon="trash" title="Delete" />
+                      </Button>
+                    </td>
+                  </tr>
+                );
+              })}
+            </tbody>
+          </Table>
+        );
+      }
+
+      content = <>
+        {table}
+
+        <h4>Save current settings</h4>
+
+        <Form onSubmit={e => this.save(e)}>
+          <Form.Group>
+            <Form.Label>Name</Form.Label>
+            <Form.Control
+              value={this.state.name}
+              placeholder="music"
+              onChange={e => this.setState({name: e.target.value})} />
+          </Form.Group>
+
+          <Form.Group>
+            <Form.Label>Prefixes</Form.Label>
+            <Form.Control
+              value={this.state.prefixes}
+              placeholder="song/, player/"
+              onChange={e => this.setState({prefixes: e.target.value})} />
+            <Form.Text>
+              Comma-separated prefixes of settings to save, like <code>song/</code>. Leave empty to save all settings.
+            </Form.Text>
+          </Form.Group>
+
+          <Button type="submit" disabled={this.state.loading || this.state.name.trim() === ""}>
+            Save Profile
+          </Button>
+        </Form>
+      </>;
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Profiles</h1>
+
+        <p>
+          Profiles are saved snapshots of settings, like song limits for a music stream, which can be activated at any time.
+          Secrets are never saved in profiles.
+        </p>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {this.renderResult()}
+
+        {content}
+      </>
+    );
+  }
+}
@@ -0,0 +1,262 @@
+import React from "react";
+import {Link} from "react-router-dom";
+import {Alert, Table, Row, Col} from "react-bootstrap";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Format the most used emotes of a stream.
+ */
+function formatEmotes(emotes) {
+  if (emotes.length === 0) {
+    return <em>none</em>;
+  }
+
+  return emotes.map(([emote, count]) => `${emote} (${count})`).join(", ");
+}
+
+/**
+ * List of recorded stream sessions.
+ */
+export class Sessions extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      sessions: [],
+    };
+  }
+
+  async componentDidMount() {
+    try {
+      let sessions = await this.api.sessions();
+      this.setState({loading: false, error: null, sessions});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load stream sessions: ${e}`, sessions: []});
+    }
+  }
+
+  render() {
+    let content = null;
+
+    if (!this.state.loading) {
+      if (this.state.sessions.length === 0) {
+        content = <Alert variant="info">No stream sessions recorded.</Alert>;
+      } else {
+        content = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>Started</th>
+                <th>Ended</th>
+                <th className="table-fill">Title</th>
+                <th>Game</th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.sessions.map(s => (
+                <tr key={s.id}>
+                  <td><span className="datetime">{s.started_at}</span></td>
+                  <td>{s.ended_at ? <span className="datetime">{s.ended_at}</span> : <b>Live</b>}</td>
+                  <td><Link to={`/sessions/${s.id}`}>{s.title}</Link></td>
+                  <td>{s.game}</td>
+                </tr>
+              ))}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Stream Sessions</h1>
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {content}
+      </>
+    );
+  }
+}
+
+/**
+ * Report of everything that happened during a single stream session.
+ */
+export class SessionReport extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      report: null,
+    };
+  }
+
+  async componentDidMount() {
+    let id = this.props.match.params.id;
+
+    try {
+      let report = await this.api.sessionReport(id);
+      this.setState({loading: false, error: null, report});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load stream session: ${e}`, report: null});
+    }
+  }
+
+  renderChat(chat) {
+    if (!chat) {
+      return <Alert variant="info">No chat activity recorded.</Alert>;
+    }
+
+    return (
+      <Table responsive="sm" size="sm">
+        <tbody>
+          <tr><td>Messages</td><td>{chat.messages}</td></tr>
+          <tr><td>Chatters</td><td>{chat.chatters}</td></tr>
+          <tr><td>Top Emotes</td><td>{formatEmotes(chat.emotes)}</td></tr>
+        </tbody>
+      </Table>
+    );
+  }
+
+  renderEvents(events, empty) {
+    if (events.length === 0) {
+      return <Alert variant="info">{empty}</Alert>;
+    }
+
+    return (
+      <Table responsive="sm" size="sm">
+        <tbody>
+          {events.map(e => (
+            <tr key={e.id}>
+              <td><span className="datetime">{e.at}</span></td>
+              <td className="table-fill">{e.user}</td>
+              <td>{e.kind}</td>
+            </tr>
+          ))}
+        </tbody>
+      </Table>
+    );
+  }
+
+  renderCommands(commands) {
+    if (commands.length === 0) {
+      return <Alert variant="info">No commands used.</Alert>;
+    }
+
+    return (
+      <Table responsive="sm" size="sm">
+        <tbody>
+          {commands.map(c => (
+            <tr key={c.command}>
+              <td className="table-fill">!{c.command}</td>
+              <td>{c.uses}</td>
+            </tr>
+          ))}
+        </tbody>
+      </Table>
+    );
+  }
+
+  renderSongs(songs) {
+    if (songs.length === 0) {
+      return <Alert variant="info">No songs played.</Alert>;
+    }
+
+    return (
+      <Table responsive="sm" size="sm">
+        <tbody>
+          {songs.map(s => (
+            <tr key={s.id}>
+              <td><span className="datetime">{s.played_at}</span></td>
+              <td className="table-fill">{s.name}</td>
+              <td>{s.user}</td>
+            </tr>
+          ))}
+        </tbody>
+      </Table>
+    );
+  }
+
+  renderAfterStreams(afterStreams) {
+    if (afterStreams.length === 0) {
+      return <Alert variant="info">No afterstream messages.</Alert>;
+    }
+
+    return (
+      <Table responsive="sm" size="sm">
+        <tbody>
+          {afterStreams.map(a => (
+            <tr key={a.id}>
+              <td>{a.user}</td>
+              <td className="table-fill">{a.text}</td>
+              <td>{a.status}</td>
+            </tr>
+          ))}
+        </tbody>
+      </Table>
+    );
+  }
+
+  render() {
+    let content = null;
+    let title = "Stream Session";
+
+    if (this.state.report !== null) {
+      let report = this.state.report;
+      title = report.session.title;
+
+      content = (
+        <>
+          <p>
+            Started <span className="datetime">{report.session.started_at}</span>
+            {report.session.game ? <> playing <b>{report.session.game}</b></> : null}
+            {report.session.ended_at ? <>, ended <span className="datetime">{report.session.ended_at}</span></> : <> (<b>live</b>)</>}.
+          </p>
+
+          <Row>
+            <Col lg="6">
+              <h4>Chat</h4>
+              {this.renderChat(report.chat)}
+            </Col>
+            <Col lg="6">
+              <h4>Commands</h4>
+              {this.renderCommands(report.commands)}
+            </Col>
+          </Row>
+
+          <Row>
+            <Col lg="6">
+              <h4>New Followers</h4>
+              {this.renderEvents(report.follows, "No new followers.")}
+            </Col>
+            <Col lg="6">
+              <h4>Subscriptions</h4>
+              {this.renderEvents(report.subs, "No subscriptions.")}
+            </Col>
+          </Row>
+
+          <h4>Songs</h4>
+          {this.renderSongs(report.songs)}
+
+          <h4>After Streams</h4>
+          {this.renderAfterStreams(report.after_streams)}
+        </>
+      );
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">{title}</h1>
+        <p><Link to="/sessions">Back to all sessions</Link></p>
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {content}
+      </>
+    );
+  }
+}
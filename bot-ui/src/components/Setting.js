@@ -122,7 +122,7 @@ export default class Setting extends React.Component {
         });
       };
 
-      if (setting.value !== null) {
+      if (setting.value !== null && !setting.isDefault) {
         buttons.push(
           <Button key="delete" size="sm" variant="danger" className="action" disabled={this.state.loading} onClick={del}>
             <FontAwesomeIcon icon="trash" />
@@ -167,6 +167,10 @@ export default class Setting extends React.Component {
       } else {
         value = setting.control.render(setting.value, renderOnChange);
       }
+
+      if (setting.isDefault) {
+        value = <>{value} <em title="No value has been set, so the default value is used">(default)</em></>;
+      }
     }
 
     if (this.state.delete) {
@@ -248,6 +252,19 @@ export default class Setting extends React.Component {
       );
     }
 
+    let errors = null;
+
+    if (this.props.errors && this.props.errors.length > 0) {
+      errors = (
+        <ul className="settings-errors text-danger mt-1 mb-0">
+          {this.props.errors.map((e, index) => {
+            let path = e.path ? <code>{e.path}</code> : null;
+            return <li key={index}>{path} {e.message}</li>;
+          })}
+        </ul>
+      );
+    }
+
     return (
       <tr>
         <td>
@@ -262,6 +279,7 @@ export default class Setting extends React.Component {
                 <div className="flex-fill align-middle">{value}</div>
                 {buttons}
               </div>
+              {errors}
             </Col>
           </Row>
         </td>
@@ -22,6 +22,8 @@ export default class Settings extends React.Component {
       data: null,
       // current filter being applied to filter visible settings.
       filter,
+      // validation errors from the last edit, by key.
+      errors: {},
     };
 
     this.onLoading = () => {};
@@ -82,6 +84,7 @@ export default class Settings extends React.Component {
           key: d.key,
           control,
           value,
+          isDefault: d.is_default,
           ...d.schema,
         }
       });
@@ -132,13 +135,40 @@ export default class Settings extends React.Component {
 
     try {
       await this.api.editSetting(key, control.serialize(value));
+      this.setErrors(key, null);
       await this.list();
       this.onLoading(false);
     } catch(e) {
+      if (e.body && e.body.errors) {
+        this.setErrors(key, e.body.errors);
+        await this.list();
+        return;
+      }
+
       this.onError(e);
     }
   }
 
+  /**
+   * Set the validation errors for the given setting.
+   *
+   * @param {string} key key of the setting.
+   * @param {array | null} errors errors to set, or null to clear them.
+   */
+  setErrors(key, errors) {
+    this.setState(state => {
+      let next = Object.assign({}, state.errors);
+
+      if (errors === null) {
+        delete next[key];
+      } else {
+        next[key] = errors;
+      }
+
+      return {errors: next};
+    });
+  }
+
   /**
    * Filter the data if applicable.
    */
@@ -229,6 +259,7 @@ export default class Settings extends React.Component {
                   setting={s}
                   onEdit={this.edit.bind(this)}
                   onDelete={this.delete.bind(this)}
+                  errors={this.state.errors[s.key]}
                   {...settingProps} />;
               })}
             </tbody>
@@ -249,6 +280,7 @@ export default class Settings extends React.Component {
                 setting={s}
                 onEdit={this.edit.bind(this)}
                 onDelete={this.delete.bind(this)}
+                errors={this.state.errors[s.key]}
                 {...settingProps} />;
             })}
           </tbody>
@@ -277,6 +309,7 @@ export default class Settings extends React.Component {
                     setting={data}
                     onEdit={this.edit.bind(this)}
                     onDelete={this.delete.bind(this)}
+                    errors={this.state.errors[data.key]}
                     keyOverride={short}
                     {...settingProps} />;
                 })}
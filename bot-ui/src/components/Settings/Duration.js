@@ -5,8 +5,10 @@ import {Base} from "./Base";
 const DURATION_REGEX = /^((\d+)d)?((\d+)h)?((\d+)m)?((\d+)s)?$/;
 
 export class Duration extends Base {
-  constructor(optional) {
+  constructor(optional, min, max) {
     super(optional);
+    this.min = !!min ? Duration.parse(min) : null;
+    this.max = !!max ? Duration.parse(max) : null;
   }
 
   /**
@@ -45,7 +47,18 @@ export class Duration extends Base {
     return {days, hours, minutes, seconds};
   }
 
+  /**
+   * Get the total number of seconds in the given duration.
+   */
+  static totalSeconds(value) {
+    return ((value.days * 24 + value.hours) * 60 + value.minutes) * 60 + value.seconds;
+  }
+
   default() {
+    if (this.min !== null) {
+      return Object.assign({}, this.min);
+    }
+
     return {days: 0, hours: 0, minutes: 0, seconds: 1};
   }
 
@@ -69,7 +82,7 @@ export class Duration extends Base {
   }
 
   editControl() {
-    return new EditDuration();
+    return new EditDuration(this.min, this.max);
   }
 
   edit(value) {
@@ -107,7 +120,22 @@ export class Duration extends Base {
 }
 
 class EditDuration {
+  constructor(min, max) {
+    this.min = min;
+    this.max = max;
+  }
+
   validate(value) {
+    let total = Duration.totalSeconds(value);
+
+    if (this.min !== null && total < Duration.totalSeconds(this.min)) {
+      return false;
+    }
+
+    if (this.max !== null && total > Duration.totalSeconds(this.max)) {
+      return false;
+    }
+
     return (
       value.days >= 0 &&
       value.hours >= 0 && value.hours < 24 &&
@@ -30,7 +30,7 @@ export function decode(type, what = "thing") {
     case "oauth2-config":
       return new Oauth2Config(type.optional);
     case "duration":
-      return new Duration(type.optional);
+      return new Duration(type.optional, type.min, type.max);
     case "bool":
       return new Boolean(type.optional);
     case "string":
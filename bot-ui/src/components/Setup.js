@@ -0,0 +1,163 @@
+import React from "react";
+import {Alert, Button, Card, Form} from "react-bootstrap";
+import {Link} from "react-router-dom";
+import Loading from 'shared-ui/components/Loading';
+import Error from 'shared-ui/components/Error';
+import Connections from "./Connections";
+
+/**
+ * The first-run setup, which walks the user through connecting accounts and
+ * enabling modules.
+ */
+export default class Setup extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      status: null,
+      features: [],
+      enabled: {},
+    };
+  }
+
+  async componentDidMount() {
+    try {
+      let [status, features] = await Promise.all([
+        this.api.setup(),
+        this.api.settings({feature: true}),
+      ]);
+
+      let enabled = {};
+
+      for (let f of features) {
+        enabled[f.key] = !!f.value;
+      }
+
+      this.setState({loading: false, error: null, status, features, enabled});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load setup: ${e}`});
+    }
+  }
+
+  /**
+   * Perform a setup action which responds with the new state of the setup.
+   */
+  async update(action) {
+    this.setState({loading: true});
+
+    try {
+      let status = await action();
+      this.setState({loading: false, error: null, status});
+    } catch (e) {
+      this.setState({loading: false, error: `${e}`});
+    }
+  }
+
+  toggle(key) {
+    this.setState(state => ({enabled: {...state.enabled, [key]: !state.enabled[key]}}));
+  }
+
+  renderModules() {
+    return (
+      <Form onSubmit={e => {
+        e.preventDefault();
+        this.update(() => this.api.setupModules(this.state.enabled));
+      }}>
+        {this.state.features.map(f => (
+          <Form.Check
+            key={f.key}
+            id={`setup-${f.key}`}
+            type="switch"
+            label={f.schema.title || f.key}
+            checked={!!this.state.enabled[f.key]}
+            onChange={() => this.toggle(f.key)}
+            disabled={this.state.loading} />
+        ))}
+
+        <Button className="mt-2" type="submit" disabled={this.state.loading}>Save Modules</Button>
+      </Form>
+    );
+  }
+
+  renderStep(step, index) {
+    let content = null;
+
+    if (step.id === "modules") {
+      content = this.renderModules();
+    } else if (!step.done && !step.skipped) {
+      content = <Connections api={this.api} />;
+    }
+
+    let skip = null;
+
+    if (step.optional && !step.done && !step.skipped) {
+      skip = (
+        <Button variant="secondary" size="sm" disabled={this.state.loading}
+          onClick={() => this.update(() => this.api.setupSkip(step.id))}>
+          Skip
+        </Button>
+      );
+    }
+
+    let state = "todo";
+
+    if (step.done) {
+      state = "done";
+    } else if (step.skipped) {
+      state = "skipped";
+    }
+
+    return (
+      <Card key={step.id} className="mb-3">
+        <Card.Header>
+          {index + 1}. <b>{step.title}</b> ({state}) {skip}
+        </Card.Header>
+        <Card.Body>
+          <p>{step.doc}</p>
+          {content}
+        </Card.Body>
+      </Card>
+    );
+  }
+
+  render() {
+    let status = this.state.status;
+
+    if (status === null) {
+      return (
+        <>
+          <Loading isLoading={this.state.loading} />
+          <Error error={this.state.error} />
+        </>
+      );
+    }
+
+    if (status.completed) {
+      return (
+        <Alert variant="success">
+          Setup is complete! Head over to <Link to="/modules">Modules</Link> to configure the bot further.
+        </Alert>
+      );
+    }
+
+    let ready = status.steps.every(s => s.done || s.skipped);
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Setup</h1>
+
+        <Error error={this.state.error} />
+
+        {status.steps.map((step, index) => this.renderStep(step, index))}
+
+        <Button variant="primary" disabled={!ready || this.state.loading}
+          onClick={() => this.update(() => this.api.setupComplete())}>
+          Complete Setup
+        </Button>
+      </>
+    );
+  }
+}
@@ -13,6 +13,11 @@ function trackUrl(trackId) {
     return `https://youtu.be/${id}`;
   }
 
+  if (trackId.startsWith("bandcamp:track:")) {
+    let [artist, name] = trackId.split(":")[2].split("/");
+    return `https://${artist}.bandcamp.com/track/${name}`;
+  }
+
   return null;
 }
 
@@ -0,0 +1,215 @@
+import React from "react";
+import {Form, Button, Alert, Table} from "react-bootstrap";
+import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
+import {Loading, Error} from 'shared-ui/components';
+
+const PLACEHOLDERS = {
+  json: '[{"category": "music", "question": "Who sang \\"Help!\\"?", "answers": ["The Beatles"]}]',
+  csv: 'music,Who sang "Help!"?,The Beatles',
+};
+
+/**
+ * Scores for the latest stream.
+ */
+function Scoreboard(props) {
+  if (props.scoreboard.scores.length === 0) {
+    return <Alert variant="info">No correct answers yet.</Alert>;
+  }
+
+  return (
+    <Table responsive="sm" size="sm">
+      <thead>
+        <tr>
+          <th className="table-fill">User</th>
+          <th>Correct Answers</th>
+        </tr>
+      </thead>
+      <tbody>
+        {props.scoreboard.scores.map(s => (
+          <tr key={s.user}>
+            <td>{s.user}</td>
+            <td>{s.wins}</td>
+          </tr>
+        ))}
+      </tbody>
+    </Table>
+  );
+}
+
+/**
+ * The trivia question bank and scoreboard.
+ */
+export default class Trivia extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      questions: null,
+      scoreboard: null,
+      format: "json",
+      text: "",
+      imported: null,
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the question bank and the scoreboard.
+   */
+  async list() {
+    this.setState({loading: true});
+
+    try {
+      let [questions, scoreboard] = await Promise.all([
+        this.api.triviaQuestions(),
+        this.api.triviaScoreboard(),
+      ]);
+
+      this.setState({loading: false, error: null, questions, scoreboard});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load trivia: ${e}`, questions: null, scoreboard: null});
+    }
+  }
+
+  /**
+   * Import the questions in the text area.
+   */
+  async import(e) {
+    e.preventDefault();
+    this.setState({loading: true, imported: null});
+
+    try {
+      let result = await this.api.triviaImport(this.state.format, this.state.text);
+      this.setState({text: "", imported: result.imported});
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to import questions: ${e}`});
+    }
+  }
+
+  /**
+   * Delete the question with the given id.
+   */
+  async deleteQuestion(id) {
+    this.setState({loading: true});
+
+    try {
+      await this.api.triviaDeleteQuestion(id);
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to delete question: ${e}`});
+    }
+  }
+
+  render() {
+    let scoreboard = null;
+
+    if (this.state.scoreboard !== null) {
+      let stream = null;
+
+      if (this.state.scoreboard.stream !== null) {
+        stream = <small className="datetime">{this.state.scoreboard.stream}</small>;
+      }
+
+      scoreboard = (
+        <>
+          <h4>Latest Stream {stream}</h4>
+          <Scoreboard scoreboard={this.state.scoreboard} />
+        </>
+      );
+    }
+
+    let questions = null;
+
+    if (this.state.questions !== null) {
+      if (this.state.questions.length === 0) {
+        questions = <Alert variant="info">No questions in the question bank.</Alert>;
+      } else {
+        questions = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>Category</th>
+                <th className="table-fill">Question</th>
+                <th>Answers</th>
+                <th></th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.questions.map(q => (
+                <tr key={q.id}>
+                  <td>{q.category}</td>
+                  <td>{q.question}</td>
+                  <td>{q.answers.join(", ")}</td>
+                  <td>
+                    <Button size="sm" variant="danger" disabled={this.state.loading} onClick={() => this.deleteQuestion(q.id)}>
+                      <FontAwesomeIcon icon="trash" />
+                    </Button>
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    let imported = null;
+
+    if (this.state.imported !== null) {
+      imported = <Alert variant="success">Imported {this.state.imported} question(s).</Alert>;
+    }
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Trivia</h1>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+
+        {scoreboard}
+
+        <h4>Import Questions</h4>
+
+        {imported}
+
+        <Form onSubmit={e => this.import(e)}>
+          <Form.Group id="format">
+            <Form.Control as="select" size="sm"
+              disabled={this.state.loading}
+              value={this.state.format}
+              onChange={e => this.setState({format: e.target.value})}>
+              <option value="json">JSON</option>
+              <option value="csv">CSV</option>
+            </Form.Control>
+          </Form.Group>
+
+          <Form.Group id="content">
+            <Form.Control as="textarea" rows="10"
+              disabled={this.state.loading}
+              value={this.state.text}
+              placeholder={PLACEHOLDERS[this.state.format]}
+              onChange={e => this.setState({text: e.target.value})} />
+            <Form.Text>
+              Each line in CSV should be <code>category,question,answers</code>, where alternative answers are separated by <code>|</code>.
+            </Form.Text>
+          </Form.Group>
+
+          <Button variant="primary" type="submit" disabled={this.state.loading || !this.state.text}>
+            Import
+          </Button>
+        </Form>
+
+        <h4>Question Bank</h4>
+
+        {questions}
+      </>
+    );
+  }
+}
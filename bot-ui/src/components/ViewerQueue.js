@@ -0,0 +1,158 @@
+import React from "react";
+import {Button, Alert, Table, ButtonGroup} from "react-bootstrap";
+import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * The queue of viewers waiting to play.
+ */
+export default class ViewerQueue extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      entries: null,
+      next: null,
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the queue.
+   */
+  async list() {
+    this.setState({loading: true});
+
+    try {
+      let entries = await this.api.viewerQueue(this.props.current.channel);
+      this.setState({loading: false, error: null, entries});
+    } catch (e) {
+      this.setState({loading: false, error: `failed to load queue: ${e}`, entries: null});
+    }
+  }
+
+  /**
+   * Pick the next viewer in the queue.
+   */
+  async next() {
+    this.setState({loading: true});
+
+    try {
+      let next = await this.api.viewerQueueNext(this.props.current.channel);
+      this.setState({next});
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to pick next viewer: ${e}`});
+    }
+  }
+
+  /**
+   * Remove the given user from the queue.
+   */
+  async remove(user) {
+    this.setState({loading: true});
+
+    try {
+      await this.api.viewerQueueRemove(this.props.current.channel, user);
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to remove viewer: ${e}`});
+    }
+  }
+
+  /**
+   * Remove everyone from the queue.
+   */
+  async clear() {
+    this.setState({loading: true, next: null});
+
+    try {
+      await this.api.viewerQueueClear(this.props.current.channel);
+      await this.list();
+    } catch (e) {
+      this.setState({loading: false, error: `failed to clear queue: ${e}`});
+    }
+  }
+
+  render() {
+    let next = null;
+
+    if (this.state.next !== null) {
+      let note = this.state.next.note ? ` (${this.state.next.note})` : "";
+      next = <Alert variant="success">Next up: <b>{this.state.next.user}</b>{note}</Alert>;
+    }
+
+    let entries = null;
+
+    if (this.state.entries !== null) {
+      if (this.state.entries.length === 0) {
+        entries = <Alert variant="info">Nobody is in the queue.</Alert>;
+      } else {
+        entries = (
+          <Table responsive="sm" size="sm">
+            <thead>
+              <tr>
+                <th>#</th>
+                <th>User</th>
+                <th className="table-fill">Note</th>
+                <th>Joined</th>
+                <th></th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.entries.map((e, i) => (
+                <tr key={e.user}>
+                  <td>{i + 1}</td>
+                  <td>
+                    {e.user}
+                    {e.subscriber ? <FontAwesomeIcon className="ml-1" icon="star" title="Subscriber" /> : null}
+                  </td>
+                  <td>{e.note}</td>
+                  <td><small className="datetime">{e.joined_at}</small></td>
+                  <td>
+                    <Button size="sm" variant="danger" disabled={this.state.loading} onClick={() => this.remove(e.user)}>
+                      <FontAwesomeIcon icon="trash" />
+                    </Button>
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    let empty = this.state.entries === null || this.state.entries.length === 0;
+
+    return (
+      <>
+        <h1 className="oxi-page-title">Viewer Queue</h1>
+
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+
+        {next}
+
+        <ButtonGroup className="mb-3">
+          <Button variant="primary" disabled={this.state.loading || empty} onClick={() => this.next()}>
+            Next
+          </Button>
+          <Button variant="secondary" disabled={this.state.loading} onClick={() => this.list()}>
+            <FontAwesomeIcon icon="sync" />
+          </Button>
+          <Button variant="danger" disabled={this.state.loading || empty} onClick={() => this.clear()}>
+            Clear
+          </Button>
+        </ButtonGroup>
+
+        {entries}
+      </>
+    );
+  }
+}
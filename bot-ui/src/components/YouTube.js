@@ -23,6 +23,7 @@ export default class YouTube extends React.Component {
     this.playerElement = null;
     this.player = null;
     this.playerRef = React.createRef();
+    this.audioRef = React.createRef();
 
     this.state = {
       stopped: true,
@@ -31,9 +32,48 @@ export default class YouTube extends React.Component {
       events: [],
       api: null,
       videoId: null,
+      audioUrl: null,
     };
   }
 
+  /**
+   * Play an audio file, like a track from Bandcamp.
+   */
+  playAudio(event) {
+    let audio = this.audioRef.current;
+
+    if (!audio) {
+      return;
+    }
+
+    this.player.pauseVideo();
+
+    if (this.state.audioUrl !== event.url) {
+      audio.src = event.url;
+      audio.currentTime = event.elapsed;
+    } else if (Math.abs(event.elapsed - audio.currentTime) > 2) {
+      audio.currentTime = event.elapsed;
+    }
+
+    audio.play();
+
+    this.setState({
+      stopped: false,
+      paused: false,
+      videoId: null,
+      audioUrl: event.url,
+    });
+  }
+
+  /**
+   * Pause the audio player, if it's loaded.
+   */
+  pauseAudio() {
+    if (this.audioRef.current) {
+      this.audioRef.current.pause();
+    }
+  }
+
   handleData(d) {
     let data = null;
 
@@ -48,9 +88,12 @@ export default class YouTube extends React.Component {
       case "youtube/current":
         switch (data.event.type) {
           case "play":
+            this.pauseAudio();
+
             let update = {
               stopped: false,
               paused: false,
+              audioUrl: null,
             };
 
             if (this.state.videoId !== data.event.video_id) {
@@ -71,8 +114,12 @@ export default class YouTube extends React.Component {
 
             this.setState(update);
             break;
+          case "play-audio":
+            this.playAudio(data.event);
+            break;
           case "pause":
             this.player.pauseVideo();
+            this.pauseAudio();
 
             this.setState({
               stopped: false,
@@ -81,11 +128,13 @@ export default class YouTube extends React.Component {
             break;
           case "stop":
             this.player.pauseVideo();
+            this.pauseAudio();
 
             this.setState({
               stopped: true,
               paused: false,
               videoId: null,
+              audioUrl: null,
             });
             break;
           default:
@@ -95,6 +144,10 @@ export default class YouTube extends React.Component {
         break;
       case "youtube/volume":
         this.player.setVolume(data.volume);
+
+        if (this.audioRef.current) {
+          this.audioRef.current.volume = data.volume / 100;
+        }
         break;
       case "song/progress":
         return;
@@ -192,6 +245,8 @@ export default class YouTube extends React.Component {
         <div className="youtube-container" style={playerStyle}>
           <div ref={this.playerRef} className="youtube-embedded"></div>
         </div>
+
+        <audio ref={this.audioRef} />
       </div>
     );
   }
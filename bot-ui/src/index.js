@@ -7,8 +7,10 @@ import {BrowserRouter as Router, Route, Link, withRouter} from "react-router-dom
 import {Container, Row, Col, Navbar, Nav, NavDropdown, Alert, Button, Form} from "react-bootstrap";
 import Connections from "./components/Connections.js";
 import Devices from "./components/Devices.js";
+import Afk from "./components/Afk.js";
 import AfterStreams from "./components/AfterStreams.js";
 import Overlay from "./components/Overlay.js";
+import {OverlayCurrentSong, OverlayAlerts, OverlayTts, OverlayPoll, OverlayCountdowns} from "./components/OverlayWidgets.js";
 import Settings from "./components/Settings.js";
 import Cache from "./components/Cache";
 import Modules from "./components/Modules.js";
@@ -20,8 +22,24 @@ import Aliases from "./components/Aliases";
 import Themes from "./components/Themes";
 import YouTube from "./components/YouTube";
 import Chat from "./components/Chat";
+import Player from "./components/Player";
 import Authorization from "./components/Authorization";
 import ConfigurationPrompt from "./components/ConfigurationPrompt";
+import Setup from "./components/Setup";
+import ChatStats from "./components/ChatStats";
+import {Sessions, SessionReport} from "./components/Sessions";
+import Clips from "./components/Clips";
+import Donations from "./components/Donations";
+import Trivia from "./components/Trivia";
+import Channel from "./components/Channel";
+import ViewerQueue from "./components/ViewerQueue";
+import EightBall from "./components/EightBall";
+import Moderation from "./components/Moderation";
+import Flags from "./components/Flags";
+import Messages from "./components/Messages";
+import Profiles from "./components/Profiles";
+import Notifications from "./components/Notifications";
+import ApiKeys from "./components/ApiKeys";
 import * as semver from "semver";
 import logo from "./logo.png";
 import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
@@ -97,6 +115,201 @@ class ModulesPage extends React.Component {
   }
 }
 
+class SetupPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Setup api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class FlagsPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Flags api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class ApiKeysPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <ApiKeys api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class MessagesPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Messages api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class ProfilesPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Profiles api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class NotificationsPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Notifications api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class SessionsPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Sessions api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class SessionReportPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <SessionReport api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class ClipsPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Clips api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class DonationsPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Donations api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class TriviaPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Trivia api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class ChannelPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Channel api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
+class ChatStatsPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <ChatStats api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
 class ImportExportPage extends React.Component {
   constructor(props) {
     super(props);
@@ -250,6 +463,8 @@ class IndexPage extends React.Component {
       <RouteLayout>
         {versionInfo}
 
+        <Afk api={this.api} />
+
         <Row>
           <Col lg="6">
             <h4>
@@ -334,12 +549,54 @@ class Layout extends React.Component {
                 <NavDropdown.Item as={Link} active={path === "/cache"} to="/cache">
                   Cache
                 </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/flags"} to="/flags">
+                  Feature Flags
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/messages"} to="/messages">
+                  Messages
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/profiles"} to="/profiles">
+                  Profiles
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/notifications"} to="/notifications">
+                  Notifications
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/api-keys"} to="/api-keys">
+                  API Keys
+                </NavDropdown.Item>
               </NavDropdown>
 
               <NavDropdown title="Misc">
+                <NavDropdown.Item as={Link} active={path === "/8ball"} to="/8ball">
+                  8 Ball
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/channel"} to="/channel">
+                  Channel
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/chat-stats"} to="/chat-stats">
+                  Chat Stats
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/clips"} to="/clips">
+                  Clips
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/donations"} to="/donations">
+                  Donations
+                </NavDropdown.Item>
                 <NavDropdown.Item as={Link} active={path === "/import-export"} to="/import-export">
                   Import / Export
                 </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/moderation"} to="/moderation">
+                  Moderation
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path.startsWith("/sessions")} to="/sessions">
+                  Stream Sessions
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/trivia"} to="/trivia">
+                  Trivia
+                </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/viewer-queue"} to="/viewer-queue">
+                  Viewer Queue
+                </NavDropdown.Item>
               </NavDropdown>
 
               <NavDropdown title="Experimental">
@@ -387,6 +644,19 @@ function AppRouter() {
         <AuthorizedPage><Authorization {...props} /></AuthorizedPage>
       )} />
       <Route path="/import-export" component={ImportExportPage} />
+      <Route path="/setup" exact component={SetupPage} />
+      <Route path="/chat-stats" exact component={ChatStatsPage} />
+      <Route path="/clips" exact component={ClipsPage} />
+      <Route path="/sessions" exact component={SessionsPage} />
+      <Route path="/sessions/:id" exact component={SessionReportPage} />
+      <Route path="/donations" exact component={DonationsPage} />
+      <Route path="/trivia" exact component={TriviaPage} />
+      <Route path="/channel" exact component={ChannelPage} />
+      <Route path="/flags" exact component={FlagsPage} />
+      <Route path="/messages" exact component={MessagesPage} />
+      <Route path="/profiles" exact component={ProfilesPage} />
+      <Route path="/notifications" exact component={NotificationsPage} />
+      <Route path="/api-keys" exact component={ApiKeysPage} />
       <Route path="/aliases" exact render={props => (
         <AuthorizedPage><Aliases {...props} /></AuthorizedPage>
       )} />
@@ -399,9 +669,24 @@ function AppRouter() {
       <Route path="/themes" exact render={props => (
         <AuthorizedPage><Themes {...props} /></AuthorizedPage>
       )} />
-      <Route path="/overlay/" component={Overlay} />
+      <Route path="/viewer-queue" exact render={props => (
+        <AuthorizedPage><ViewerQueue {...props} /></AuthorizedPage>
+      )} />
+      <Route path="/8ball" exact render={props => (
+        <AuthorizedPage><EightBall {...props} /></AuthorizedPage>
+      )} />
+      <Route path="/moderation" exact render={props => (
+        <AuthorizedPage><Moderation {...props} /></AuthorizedPage>
+      )} />
+      <Route path="/overlay/" exact component={Overlay} />
+      <Route path="/overlay/current-song" exact component={OverlayCurrentSong} />
+      <Route path="/overlay/alerts" exact component={OverlayAlerts} />
+      <Route path="/overlay/tts" exact component={OverlayTts} />
+      <Route path="/overlay/poll" exact component={OverlayPoll} />
+      <Route path="/overlay/countdowns" exact component={OverlayCountdowns} />
       <Route path="/youtube" component={YouTube} />
       <Route path="/chat" component={Chat} />
+      <Route path="/player/:streamer" exact component={Player} />
     </Router>
   );
 }
@@ -0,0 +1,33 @@
+DROP TABLE greetings;
+DROP TABLE moderation_log;
+DROP TABLE chatters;
+DROP TABLE viewer_queue;
+DROP TABLE variables;
+DROP TABLE counters;
+DROP TABLE counter_history;
+DROP TABLE trivia_wins;
+DROP TABLE trivia_questions;
+DROP TABLE subscribers;
+DROP TABLE donations;
+DROP TABLE markers;
+DROP TABLE clips;
+DROP TABLE notifications;
+DROP TABLE stream_stats;
+DROP TABLE stream_events;
+DROP TABLE audit_log;
+DROP TABLE song_history;
+DROP TABLE banned_tracks;
+DROP TABLE script_keys;
+DROP TABLE user_grants;
+DROP TABLE grants;
+DROP TABLE initialized_grants;
+DROP TABLE themes;
+DROP TABLE promotions;
+DROP TABLE aliases;
+DROP TABLE settings;
+DROP TABLE staged_songs;
+DROP TABLE songs;
+DROP TABLE bad_words;
+DROP TABLE after_streams;
+DROP TABLE commands;
+DROP TABLE balances;
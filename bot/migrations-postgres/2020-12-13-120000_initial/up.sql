@@ -0,0 +1,329 @@
+-- The complete schema, matching all SQLite migrations up to and including 2020-12-06-120000_greetings.
+
+CREATE TABLE balances (
+    channel VARCHAR NOT NULL,
+    "user" VARCHAR NOT NULL,
+    amount BIGINT NOT NULL,
+    watch_time BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (channel, "user")
+);
+
+CREATE TABLE commands (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    pattern VARCHAR,
+    count INTEGER NOT NULL DEFAULT 0,
+    text VARCHAR NOT NULL,
+    "group" VARCHAR,
+    disabled BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE TABLE after_streams (
+    id SERIAL PRIMARY KEY,
+    channel VARCHAR,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "user" VARCHAR NOT NULL,
+    text VARCHAR NOT NULL
+);
+
+CREATE TABLE bad_words (
+    word VARCHAR NOT NULL,
+    why VARCHAR,
+    PRIMARY KEY (word)
+);
+
+CREATE TABLE songs (
+    id SERIAL PRIMARY KEY,
+    deleted BOOLEAN NOT NULL DEFAULT FALSE,
+    track_id VARCHAR NOT NULL,
+    added_at TIMESTAMP NOT NULL,
+    promoted_at TIMESTAMP,
+    promoted_by VARCHAR,
+    "user" VARCHAR
+);
+
+CREATE TABLE staged_songs (
+    id SERIAL PRIMARY KEY,
+    track_id VARCHAR NOT NULL,
+    added_at TIMESTAMP NOT NULL,
+    "user" VARCHAR
+);
+
+CREATE TABLE settings (
+    key VARCHAR NOT NULL,
+    value VARCHAR NOT NULL,
+    PRIMARY KEY (key)
+);
+
+CREATE TABLE aliases (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    pattern VARCHAR,
+    text VARCHAR NOT NULL,
+    "group" VARCHAR,
+    disabled BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE TABLE promotions (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    frequency INTEGER NOT NULL,
+    promoted_at TIMESTAMP,
+    text VARCHAR NOT NULL,
+    "group" VARCHAR,
+    disabled BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE TABLE themes (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    track_id VARCHAR NOT NULL,
+    start INTEGER NOT NULL,
+    "end" INTEGER,
+    "group" VARCHAR,
+    disabled BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE TABLE initialized_grants (
+    scope VARCHAR NOT NULL,
+    version VARCHAR NOT NULL,
+    PRIMARY KEY (scope)
+);
+
+CREATE TABLE grants (
+    scope VARCHAR NOT NULL,
+    role VARCHAR NOT NULL,
+    PRIMARY KEY (scope, role)
+);
+
+CREATE TABLE user_grants (
+    scope VARCHAR NOT NULL,
+    "user" VARCHAR NOT NULL,
+    allow BOOLEAN NOT NULL,
+    PRIMARY KEY (scope, "user")
+);
+
+CREATE TABLE script_keys (
+    channel VARCHAR NOT NULL,
+    key BYTEA NOT NULL,
+    value BYTEA NOT NULL,
+    PRIMARY KEY (channel, key)
+);
+
+CREATE TABLE banned_tracks (
+    kind VARCHAR NOT NULL,
+    id VARCHAR NOT NULL,
+    name VARCHAR,
+    reason VARCHAR,
+    banned_by VARCHAR,
+    banned_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (kind, id)
+);
+
+CREATE TABLE song_history (
+    id SERIAL PRIMARY KEY,
+    track_id VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    "user" VARCHAR,
+    played_at TIMESTAMP NOT NULL,
+    skipped BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE audit_log (
+    id SERIAL PRIMARY KEY,
+    action VARCHAR NOT NULL,
+    actor VARCHAR,
+    target VARCHAR,
+    details VARCHAR,
+    at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE stream_events (
+    id SERIAL PRIMARY KEY,
+    kind VARCHAR NOT NULL,
+    "user" VARCHAR NOT NULL,
+    amount INTEGER,
+    recipient VARCHAR,
+    at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE stream_stats (
+    id SERIAL PRIMARY KEY,
+    started_at TIMESTAMP,
+    ended_at TIMESTAMP NOT NULL,
+    messages INTEGER NOT NULL,
+    chatters INTEGER NOT NULL,
+    emotes VARCHAR NOT NULL,
+    subs INTEGER NOT NULL DEFAULT 0,
+    resubs INTEGER NOT NULL DEFAULT 0,
+    gifted_subs INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE notifications (
+    id SERIAL PRIMARY KEY,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    icon VARCHAR NOT NULL,
+    title VARCHAR,
+    message VARCHAR NOT NULL,
+    read BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE clips (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    clip_id VARCHAR NOT NULL,
+    title VARCHAR NOT NULL,
+    "user" VARCHAR,
+    game VARCHAR,
+    song VARCHAR
+);
+
+CREATE TABLE markers (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    marker_id VARCHAR NOT NULL,
+    position_seconds BIGINT NOT NULL,
+    description VARCHAR,
+    "user" VARCHAR,
+    song VARCHAR
+);
+
+CREATE TABLE donations (
+    id SERIAL PRIMARY KEY,
+    donated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    provider VARCHAR NOT NULL,
+    "user" VARCHAR NOT NULL,
+    amount DOUBLE PRECISION NOT NULL,
+    currency VARCHAR NOT NULL,
+    message VARCHAR,
+    stream_started_at TIMESTAMP
+);
+
+CREATE TABLE subscribers (
+    "user" VARCHAR NOT NULL,
+    months INTEGER NOT NULL DEFAULT 0,
+    streak INTEGER NOT NULL DEFAULT 0,
+    gifts INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP NOT NULL,
+    PRIMARY KEY ("user")
+);
+
+CREATE TABLE trivia_questions (
+    id SERIAL PRIMARY KEY,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    category VARCHAR NOT NULL,
+    question VARCHAR NOT NULL,
+    answers VARCHAR NOT NULL
+);
+
+CREATE TABLE trivia_wins (
+    id SERIAL PRIMARY KEY,
+    won_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "user" VARCHAR NOT NULL,
+    question_id INTEGER NOT NULL,
+    stream_started_at TIMESTAMP
+);
+
+CREATE TABLE counter_history (
+    id SERIAL PRIMARY KEY,
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    count BIGINT NOT NULL,
+    "user" VARCHAR,
+    changed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE counters (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    count BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE TABLE variables (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    value VARCHAR NOT NULL,
+    role VARCHAR,
+    updated_by VARCHAR,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE TABLE viewer_queue (
+    channel VARCHAR NOT NULL,
+    "user" VARCHAR NOT NULL,
+    note VARCHAR,
+    subscriber BOOLEAN NOT NULL DEFAULT FALSE,
+    joined_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (channel, "user")
+);
+
+CREATE TABLE chatters (
+    channel VARCHAR NOT NULL,
+    "user" VARCHAR NOT NULL,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    messages BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (channel, "user")
+);
+
+CREATE TABLE moderation_log (
+    id SERIAL PRIMARY KEY,
+    channel VARCHAR NOT NULL,
+    action VARCHAR NOT NULL,
+    "user" VARCHAR NOT NULL,
+    user_id VARCHAR,
+    message VARCHAR,
+    rule VARCHAR,
+    duration BIGINT,
+    undone_at TIMESTAMP,
+    at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE greetings (
+    channel VARCHAR NOT NULL,
+    "user" VARCHAR NOT NULL,
+    text VARCHAR NOT NULL,
+    PRIMARY KEY (channel, "user")
+);
+
+CREATE INDEX idx_aliases_group ON aliases("group");
+
+CREATE INDEX idx_audit_log_at ON audit_log(at);
+
+CREATE INDEX idx_clips_created_at ON clips(created_at);
+
+CREATE INDEX idx_commands_group ON commands("group");
+
+CREATE INDEX idx_counter_history_counter ON counter_history(channel, name);
+
+CREATE INDEX idx_donations_donated_at ON donations(donated_at);
+
+CREATE INDEX idx_donations_stream_started_at ON donations(stream_started_at);
+
+CREATE INDEX idx_markers_created_at ON markers(created_at);
+
+CREATE INDEX idx_moderation_log_at ON moderation_log(at);
+
+CREATE INDEX idx_notifications_added_at ON notifications(added_at);
+
+CREATE INDEX idx_promotions_group ON promotions("group");
+
+CREATE INDEX idx_song_history_played_at ON song_history(played_at);
+
+CREATE INDEX idx_songs_added_at_id ON songs(added_at, id);
+
+CREATE INDEX idx_stream_stats_ended_at ON stream_stats(ended_at);
+
+CREATE INDEX idx_trivia_questions_category ON trivia_questions(category);
+
+CREATE INDEX idx_trivia_wins_stream_started_at ON trivia_wins(stream_started_at);
+
+CREATE INDEX songs_deleted_added_at ON songs (deleted, track_id);
@@ -0,0 +1,10 @@
+CREATE TABLE polls (
+    id SERIAL PRIMARY KEY,
+    channel VARCHAR NOT NULL,
+    question VARCHAR NOT NULL,
+    results VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    closed_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_polls_closed_at ON polls(closed_at);
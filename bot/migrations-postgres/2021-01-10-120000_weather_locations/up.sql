@@ -0,0 +1,6 @@
+CREATE TABLE weather_locations (
+    channel VARCHAR NOT NULL,
+    "user" VARCHAR NOT NULL,
+    location VARCHAR NOT NULL,
+    PRIMARY KEY (channel, "user")
+);
@@ -0,0 +1,9 @@
+CREATE TABLE eight_ball_answers (
+    id SERIAL PRIMARY KEY,
+    channel VARCHAR NOT NULL,
+    answer VARCHAR NOT NULL,
+    weight INTEGER NOT NULL DEFAULT 1,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_eight_ball_answers_channel ON eight_ball_answers(channel);
@@ -0,0 +1,5 @@
+DROP TABLE after_stream_replies;
+
+ALTER TABLE after_streams DROP COLUMN stream_id;
+ALTER TABLE after_streams DROP COLUMN status;
+ALTER TABLE after_streams DROP COLUMN resolved_at;
@@ -0,0 +1,13 @@
+ALTER TABLE after_streams ADD COLUMN stream_id VARCHAR;
+ALTER TABLE after_streams ADD COLUMN status VARCHAR NOT NULL DEFAULT 'open';
+ALTER TABLE after_streams ADD COLUMN resolved_at TIMESTAMP;
+
+CREATE TABLE after_stream_replies (
+    id SERIAL PRIMARY KEY,
+    after_stream_id INTEGER NOT NULL,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "user" VARCHAR,
+    text VARCHAR NOT NULL
+);
+
+CREATE INDEX idx_after_stream_replies_after_stream_id ON after_stream_replies(after_stream_id);
@@ -0,0 +1,3 @@
+DELETE FROM initialized_grants WHERE scope = 'song/skip';
+DELETE FROM user_grants WHERE scope = 'song/skip';
+DELETE FROM grants WHERE scope = 'song/skip';
@@ -0,0 +1 @@
+DROP TABLE banned_tracks;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE banned_tracks (
+    kind VARCHAR NOT NULL,
+    id VARCHAR NOT NULL,
+    name VARCHAR,
+    reason VARCHAR,
+    banned_by VARCHAR,
+    banned_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (kind, id)
+);
\ No newline at end of file
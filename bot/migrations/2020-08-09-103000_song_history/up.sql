@@ -0,0 +1,10 @@
+CREATE TABLE song_history (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    track_id VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    user VARCHAR,
+    played_at TIMESTAMP NOT NULL,
+    skipped BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX idx_song_history_played_at ON song_history(played_at);
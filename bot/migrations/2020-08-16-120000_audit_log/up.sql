@@ -0,0 +1,10 @@
+CREATE TABLE audit_log (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    action VARCHAR NOT NULL,
+    actor VARCHAR,
+    target VARCHAR,
+    details VARCHAR,
+    at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_audit_log_at ON audit_log(at);
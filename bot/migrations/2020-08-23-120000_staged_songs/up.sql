@@ -0,0 +1,6 @@
+CREATE TABLE staged_songs (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    track_id VARCHAR NOT NULL,
+    added_at TIMESTAMP NOT NULL,
+    user VARCHAR
+);
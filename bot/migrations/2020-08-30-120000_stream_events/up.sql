@@ -0,0 +1,8 @@
+CREATE TABLE stream_events (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    kind VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    amount INTEGER,
+    recipient VARCHAR,
+    at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Scopes that have been explicitly allowed or denied for specific users.
+CREATE TABLE user_grants (
+    scope VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    allow BOOLEAN NOT NULL,
+    PRIMARY KEY (scope, user)
+);
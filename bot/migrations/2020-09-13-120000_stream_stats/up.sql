@@ -0,0 +1,10 @@
+CREATE TABLE stream_stats (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    started_at TIMESTAMP,
+    ended_at TIMESTAMP NOT NULL,
+    messages INTEGER NOT NULL,
+    chatters INTEGER NOT NULL,
+    emotes VARCHAR NOT NULL
+);
+
+CREATE INDEX idx_stream_stats_ended_at ON stream_stats(ended_at);
@@ -0,0 +1,10 @@
+CREATE TABLE notifications (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    icon VARCHAR NOT NULL,
+    title VARCHAR,
+    message VARCHAR NOT NULL,
+    read BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX idx_notifications_added_at ON notifications(added_at);
@@ -0,0 +1,11 @@
+CREATE TABLE clips (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    clip_id VARCHAR NOT NULL,
+    title VARCHAR NOT NULL,
+    user VARCHAR,
+    game VARCHAR,
+    song VARCHAR
+);
+
+CREATE INDEX idx_clips_created_at ON clips(created_at);
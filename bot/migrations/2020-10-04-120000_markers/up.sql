@@ -0,0 +1,11 @@
+CREATE TABLE markers (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    marker_id VARCHAR NOT NULL,
+    position_seconds BIGINT NOT NULL,
+    description VARCHAR,
+    user VARCHAR,
+    song VARCHAR
+);
+
+CREATE INDEX idx_markers_created_at ON markers(created_at);
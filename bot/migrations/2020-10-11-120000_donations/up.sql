@@ -0,0 +1,13 @@
+CREATE TABLE donations (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    donated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    provider VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    amount DOUBLE NOT NULL,
+    currency VARCHAR NOT NULL,
+    message VARCHAR,
+    stream_started_at TIMESTAMP
+);
+
+CREATE INDEX idx_donations_donated_at ON donations(donated_at);
+CREATE INDEX idx_donations_stream_started_at ON donations(stream_started_at);
@@ -0,0 +1,11 @@
+CREATE TABLE subscribers (
+    user VARCHAR NOT NULL PRIMARY KEY,
+    months INTEGER NOT NULL DEFAULT 0,
+    streak INTEGER NOT NULL DEFAULT 0,
+    gifts INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP NOT NULL
+);
+
+ALTER TABLE stream_stats ADD COLUMN subs INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE stream_stats ADD COLUMN resubs INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE stream_stats ADD COLUMN gifted_subs INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+DROP TABLE trivia_wins;
+DROP TABLE trivia_questions;
@@ -0,0 +1,19 @@
+CREATE TABLE trivia_questions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    category VARCHAR NOT NULL,
+    question VARCHAR NOT NULL,
+    answers VARCHAR NOT NULL
+);
+
+CREATE INDEX idx_trivia_questions_category ON trivia_questions(category);
+
+CREATE TABLE trivia_wins (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    won_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    user VARCHAR NOT NULL,
+    question_id INTEGER NOT NULL,
+    stream_started_at TIMESTAMP
+);
+
+CREATE INDEX idx_trivia_wins_stream_started_at ON trivia_wins(stream_started_at);
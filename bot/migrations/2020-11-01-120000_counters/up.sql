@@ -0,0 +1,19 @@
+CREATE TABLE counters (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    count BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE TABLE counter_history (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    count BIGINT NOT NULL,
+    user VARCHAR,
+    changed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_counter_history_counter ON counter_history(channel, name);
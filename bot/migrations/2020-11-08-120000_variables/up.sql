@@ -0,0 +1,9 @@
+CREATE TABLE variables (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    value VARCHAR NOT NULL,
+    role VARCHAR,
+    updated_by VARCHAR,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (channel, name)
+);
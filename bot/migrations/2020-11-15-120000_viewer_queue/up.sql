@@ -0,0 +1,8 @@
+CREATE TABLE viewer_queue (
+    channel VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    note VARCHAR,
+    subscriber BOOLEAN NOT NULL DEFAULT FALSE,
+    joined_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (channel, user)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE chatters (
+    channel VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    messages BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (channel, user)
+);
@@ -0,0 +1,14 @@
+CREATE TABLE moderation_log (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    action VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    user_id VARCHAR,
+    message VARCHAR,
+    rule VARCHAR,
+    duration BIGINT,
+    undone_at TIMESTAMP,
+    at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_moderation_log_at ON moderation_log(at);
@@ -0,0 +1,6 @@
+CREATE TABLE greetings (
+    channel VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    text VARCHAR NOT NULL,
+    PRIMARY KEY (channel, user)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE countdowns (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    template VARCHAR NOT NULL,
+    duration BIGINT NOT NULL,
+    ends_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (channel, name)
+);
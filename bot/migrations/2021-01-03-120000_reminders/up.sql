@@ -0,0 +1,9 @@
+CREATE TABLE reminder_stats (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    fired BIGINT NOT NULL,
+    done BIGINT NOT NULL,
+    last_fired_at TIMESTAMP,
+    last_done_at TIMESTAMP,
+    PRIMARY KEY (channel, name)
+);
@@ -0,0 +1 @@
+DROP TABLE eight_ball_answers;
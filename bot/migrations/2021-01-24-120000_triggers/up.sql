@@ -0,0 +1,13 @@
+CREATE TABLE triggers (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    pattern TEXT,
+    text TEXT NOT NULL,
+    cooldown INTEGER NOT NULL DEFAULT 60,
+    probability INTEGER NOT NULL DEFAULT 100,
+    "group" TEXT,
+    disabled BOOLEAN NOT NULL DEFAULT false,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE INDEX idx_triggers_group ON triggers("group");
@@ -0,0 +1,13 @@
+ALTER TABLE after_streams ADD COLUMN stream_id TEXT;
+ALTER TABLE after_streams ADD COLUMN status TEXT NOT NULL DEFAULT 'open';
+ALTER TABLE after_streams ADD COLUMN resolved_at TIMESTAMP;
+
+CREATE TABLE after_stream_replies (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    after_stream_id INTEGER NOT NULL,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    user TEXT,
+    text TEXT NOT NULL
+);
+
+CREATE INDEX idx_after_stream_replies_after_stream_id ON after_stream_replies(after_stream_id);
@@ -0,0 +1,2 @@
+DROP TABLE stream_session_commands;
+DROP TABLE stream_sessions;
@@ -0,0 +1,18 @@
+CREATE TABLE stream_sessions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    stream_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    game TEXT,
+    started_at TIMESTAMP NOT NULL,
+    ended_at TIMESTAMP,
+    stream_stats_id INTEGER
+);
+
+CREATE INDEX idx_stream_sessions_started_at ON stream_sessions(started_at);
+
+CREATE TABLE stream_session_commands (
+    session_id INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    uses INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (session_id, command)
+);
@@ -0,0 +1,16 @@
+-- song/skip used to be covered by song/playback-control, so carry over any grants made to it.
+INSERT INTO grants (scope, role)
+SELECT 'song/skip', g.role FROM grants g
+WHERE g.scope = 'song/playback-control'
+AND NOT EXISTS (SELECT 1 FROM grants s WHERE s.scope = 'song/skip' AND s.role = g.role);
+
+INSERT INTO user_grants (scope, user, allow)
+SELECT 'song/skip', g.user, g.allow FROM user_grants g
+WHERE g.scope = 'song/playback-control'
+AND NOT EXISTS (SELECT 1 FROM user_grants s WHERE s.scope = 'song/skip' AND s.user = g.user);
+
+-- Don't apply the default grants on top of the copied ones.
+INSERT INTO initialized_grants (scope, version)
+SELECT 'song/skip', '0' FROM initialized_grants g
+WHERE g.scope = 'song/playback-control'
+AND NOT EXISTS (SELECT 1 FROM initialized_grants s WHERE s.scope = 'song/skip');
@@ -0,0 +1,47 @@
+//! Tracking of which users have recently been active in chat.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long activity is remembered for.
+const RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Tracks when users were last active in chat.
+#[derive(Clone, Default)]
+pub struct Activity {
+    /// When each user was last seen.
+    seen: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl Activity {
+    /// Construct a new activity tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indicate that the given user has been seen.
+    pub fn seen(&self, user: &str) {
+        let now = Instant::now();
+        let mut seen = self.seen.lock();
+        seen.insert(user.to_lowercase(), now);
+
+        // NB: prune old entries to keep memory use in check.
+        if seen.len() % 128 == 0 {
+            seen.retain(|_, last| now.duration_since(*last) < RETENTION);
+        }
+    }
+
+    /// Count the number of users that have been active within the given
+    /// duration.
+    pub fn active_within(&self, duration: Duration) -> usize {
+        let now = Instant::now();
+
+        self.seen
+            .lock()
+            .values()
+            .filter(|last| now.duration_since(**last) < duration)
+            .count()
+    }
+}
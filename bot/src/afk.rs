@@ -0,0 +1,56 @@
+//! Keeps track of who is away from keyboard.
+//!
+//! Statuses are set through the `!afk` command, and are only kept in memory.
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The status of a user who is away.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Status {
+    /// The name of the user who is away.
+    pub user: String,
+    /// The display name of the user who is away.
+    pub display_name: String,
+    /// Why the user is away, if they said.
+    pub reason: Option<String>,
+    /// When the user went away.
+    pub since: DateTime<Utc>,
+}
+
+/// Users who are currently away, by name.
+#[derive(Debug, Clone, Default)]
+pub struct Afk {
+    statuses: Arc<RwLock<HashMap<String, Status>>>,
+}
+
+impl Afk {
+    /// Construct a new, empty set of statuses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a user as away.
+    pub fn set(&self, status: Status) {
+        self.statuses.write().insert(status.user.clone(), status);
+    }
+
+    /// Get the status of the given user, if they are away.
+    pub fn get(&self, user: &str) -> Option<Status> {
+        self.statuses.read().get(user).cloned()
+    }
+
+    /// Mark the given user as back, returning their previous status.
+    pub fn clear(&self, user: &str) -> Option<Status> {
+        self.statuses.write().remove(user)
+    }
+
+    /// List everyone who is away, longest away first.
+    pub fn list(&self) -> Vec<Status> {
+        let mut statuses = self.statuses.read().values().cloned().collect::<Vec<_>>();
+        statuses.sort_by_key(|s| s.since);
+        statuses
+    }
+}
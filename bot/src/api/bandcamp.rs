@@ -0,0 +1,233 @@
+//! Bandcamp metadata client.
+//!
+//! Bandcamp doesn't have a public API, so track metadata is scraped from the
+//! album data which is embedded in every track page.
+
+use crate::api::RequestBuilder;
+use anyhow::{anyhow, bail, Result};
+use reqwest::{Client, Method, Url};
+use std::collections::HashMap;
+
+/// The attribute of the track page which holds track metadata.
+const TRALBUM_ATTRIBUTE: &str = "data-tralbum=\"";
+
+/// API integration.
+#[derive(Clone, Debug)]
+pub struct Bandcamp {
+    client: Client,
+}
+
+impl Bandcamp {
+    /// Create a new API integration.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+        })
+    }
+
+    /// Get metadata for the track with the given id, like `<artist>/<slug>`.
+    pub async fn track(&self, id: &str) -> Result<Option<Track>> {
+        let url = match track_url(id) {
+            Some(url) => str::parse::<Url>(&url)?,
+            None => bail!("bad bandcamp track id: {}", id),
+        };
+
+        let req = RequestBuilder::new(self.client.clone(), Method::GET, url.clone());
+        let body = req.execute().await?.not_found().body()?;
+
+        let body = match body {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+
+        let page = String::from_utf8_lossy(body.as_ref());
+        Ok(Some(parse_track(&page, url.as_str())?))
+    }
+}
+
+/// Build the URL of a track page from an id like `<artist>/<slug>`.
+///
+/// Returns `None` unless both the artist and the slug only consist of
+/// lowercase ascii letters, digits and dashes, so that the id can't be used
+/// to point the URL somewhere else.
+pub fn track_url(id: &str) -> Option<String> {
+    let mut parts = id.splitn(2, '/');
+    let artist = parts.next().filter(|s| is_valid_part(s))?;
+    let slug = parts.next().filter(|s| is_valid_part(s))?;
+    Some(format!("https://{}.bandcamp.com/track/{}", artist, slug))
+}
+
+/// Test if the given artist or slug is valid.
+fn is_valid_part(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Metadata for a single track.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Track {
+    pub title: String,
+    pub artist: String,
+    /// URL to the track page.
+    pub url: String,
+    /// Duration of the track in seconds.
+    pub duration: f64,
+    /// URL to stream the track from.
+    ///
+    /// These expire after a while, so they need to be looked up again right
+    /// before the track is played.
+    #[serde(default)]
+    pub stream_url: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TrAlbum {
+    artist: String,
+    #[serde(default)]
+    trackinfo: Vec<TrackInfo>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TrackInfo {
+    title: String,
+    #[serde(default)]
+    duration: f64,
+    #[serde(default)]
+    file: Option<HashMap<String, String>>,
+}
+
+/// Parse track metadata out of a track page.
+fn parse_track(page: &str, url: &str) -> Result<Track> {
+    let start = page
+        .find(TRALBUM_ATTRIBUTE)
+        .ok_or_else(|| anyhow!("track page is missing album data"))?
+        + TRALBUM_ATTRIBUTE.len();
+
+    let end = page[start..]
+        .find('"')
+        .ok_or_else(|| anyhow!("track page has unterminated album data"))?;
+
+    let tralbum = unescape_html(&page[start..start + end]);
+    let tralbum = serde_json::from_str::<TrAlbum>(&tralbum)?;
+
+    let info = match tralbum.trackinfo.into_iter().next() {
+        Some(info) => info,
+        None => bail!("track page doesn't have any tracks"),
+    };
+
+    let stream_url = info
+        .file
+        .and_then(|mut file| file.remove("mp3-128"))
+        .map(|url| {
+            if url.starts_with("//") {
+                format!("https:{}", url)
+            } else {
+                url
+            }
+        });
+
+    Ok(Track {
+        title: info.title,
+        artist: tralbum.artist,
+        url: url.to_string(),
+        duration: info.duration,
+        stream_url,
+    })
+}
+
+/// Unescape the entities which appear in HTML attributes.
+fn unescape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(index) = rest.find('&') {
+        out.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+
+        let c = match &rest[1..end] {
+            "quot" => Some('"'),
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "apos" => Some('\''),
+            entity if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            entity if entity.starts_with('#') => str::parse::<u32>(&entity[1..])
+                .ok()
+                .and_then(std::char::from_u32),
+            _ => None,
+        };
+
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_track, track_url, unescape_html};
+
+    #[test]
+    fn test_track_url() {
+        assert_eq!(
+            Some("https://foo-bar.bandcamp.com/track/baz-2"),
+            track_url("foo-bar/baz-2").as_deref()
+        );
+        assert_eq!(None, track_url("foo"));
+        assert_eq!(None, track_url("foo/"));
+        assert_eq!(None, track_url("foo/baz/../../x"));
+        assert_eq!(None, track_url("evil.com#/baz"));
+        assert_eq!(None, track_url("foo/baz?x=1"));
+        assert_eq!(None, track_url("Foo/baz"));
+    }
+
+    #[test]
+    fn test_unescape_html() {
+        assert_eq!(
+            "\"a\" & <b>'",
+            unescape_html("&quot;a&quot; &amp; &lt;b&gt;&#39;")
+        );
+        assert_eq!("fish & chips", unescape_html("fish & chips"));
+        assert_eq!("&bogus;", unescape_html("&bogus;"));
+    }
+
+    #[test]
+    fn test_parse_track() {
+        let page = concat!(
+            "<script data-tralbum=\"{&quot;artist&quot;:&quot;Foo &amp; Bar&quot;,",
+            "&quot;trackinfo&quot;:[{&quot;title&quot;:&quot;Baz&quot;,",
+            "&quot;duration&quot;:123.5,",
+            "&quot;file&quot;:{&quot;mp3-128&quot;:&quot;//t4.bcbits.com/stream/x&quot;}}]}\">",
+            "</script>",
+        );
+
+        let track = parse_track(page, "https://foo.bandcamp.com/track/baz").unwrap();
+        assert_eq!("Baz", track.title);
+        assert_eq!("Foo & Bar", track.artist);
+        assert!((track.duration - 123.5).abs() < f64::EPSILON);
+        assert_eq!(
+            Some("https://t4.bcbits.com/stream/x"),
+            track.stream_url.as_deref()
+        );
+
+        assert!(parse_track("<html></html>", "").is_err());
+    }
+}
@@ -1,7 +1,10 @@
+use crate::api::limiter::{self, Service};
+use crate::metrics;
 use crate::oauth2;
 use anyhow::{bail, Result};
 use bytes::Bytes;
 use reqwest::{header, Client, Method, StatusCode, Url};
+use std::time::Instant;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -53,6 +56,8 @@ pub struct RequestBuilder {
     /// Add the client id to the specified header if configured.
     client_id_header: Option<&'static str>,
     absent_body: bool,
+    /// The service whose budget this request is subject to.
+    service: Option<Service>,
 }
 
 impl RequestBuilder {
@@ -68,9 +73,26 @@ impl RequestBuilder {
             use_bearer: true,
             client_id_header: None,
             absent_body: false,
+            service: None,
         }
     }
 
+    /// Access the method of the request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Access the url of the request.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Subject the request to the rate limits of the given service.
+    pub fn service(mut self, service: Service) -> Self {
+        self.service = Some(service);
+        self
+    }
+
     /// Use the OAuth2 header instead of Bearer when sending authentication.
     pub fn use_oauth2_header(mut self) -> Self {
         self.use_bearer = false;
@@ -151,6 +173,31 @@ impl RequestBuilder {
 
     /// Execute the request.
     pub async fn execute(&self) -> Result<Response<Bytes>> {
+        match self.service {
+            Some(service) => limiter::execute(service, self).await,
+            None => self.send().await,
+        }
+    }
+
+    /// Key used to identify requests which are identical and can be
+    /// coalesced.
+    pub(crate) async fn coalesce_key(&self) -> Result<String> {
+        let mut key = format!("{} {}", self.method, self.url);
+
+        for (name, value) in &self.headers {
+            key.push_str(&format!(" {}={}", name, value));
+        }
+
+        if let Some(token) = self.token.as_ref() {
+            let token = token.read().await?;
+            key.push_str(&format!(" token={}", token.access_token()));
+        }
+
+        Ok(key)
+    }
+
+    /// Send the request, bypassing any rate limiting.
+    pub(crate) async fn send(&self) -> Result<Response<Bytes>> {
         // NB: scope to only lock the token over the request setup.
         log::trace!("Request: {}: {}", self.method, self.url);
         let mut req = self.client.request(self.method.clone(), self.url.clone());
@@ -189,10 +236,15 @@ impl RequestBuilder {
 
         req = req.header(header::USER_AGENT, USER_AGENT);
 
+        let start = Instant::now();
         let res = req.send().await.map_err(SendRequestError)?;
         let status = res.status();
+        let headers = res.headers().clone();
         let body = res.bytes().await.map_err(ReceiveResponseError)?;
 
+        let host = self.url.host_str().unwrap_or_default();
+        metrics::API_LATENCY.observe(host, start.elapsed());
+
         if log::log_enabled!(log::Level::Trace) {
             let response = String::from_utf8_lossy(&body);
             log::trace!(
@@ -214,18 +266,33 @@ impl RequestBuilder {
             method: self.method.clone(),
             url: self.url.clone(),
             status,
+            headers,
             body,
         })
     }
 }
 
+#[derive(Clone)]
 pub struct Response<B> {
     method: Method,
     url: Url,
     status: StatusCode,
+    headers: header::HeaderMap,
     body: B,
 }
 
+impl<B> Response<B> {
+    /// Access the status of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Access the headers of the response.
+    pub fn headers(&self) -> &header::HeaderMap {
+        &self.headers
+    }
+}
+
 impl Response<Bytes> {
     /// Expect a successful response.
     pub fn ok(self) -> Result<()> {
@@ -353,6 +420,7 @@ where
             method: self.method,
             url: self.url,
             status: self.status,
+            headers: self.headers,
             body,
         }
     }
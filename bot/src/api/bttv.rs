@@ -1,6 +1,6 @@
 //! BetterTTV API Client.
 
-use crate::api::RequestBuilder;
+use crate::api::{RequestBuilder, Service};
 use anyhow::Result;
 use reqwest::{header, Client, Method, Url};
 use std::collections::HashSet;
@@ -33,7 +33,9 @@ impl BetterTTV {
         }
 
         let req = RequestBuilder::new(self.client.clone(), method, url);
-        req.header(header::ACCEPT, "application/json")
+
+        req.service(Service::BetterTTV)
+            .header(header::ACCEPT, "application/json")
     }
 
     /// Get the set associated with the room.
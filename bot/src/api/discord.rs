@@ -0,0 +1,88 @@
+//! Discord webhook integration.
+
+use crate::api::RequestBuilder;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::{header, Client, Method, Url};
+
+/// API integration for Discord webhooks.
+#[derive(Clone, Debug)]
+pub struct Discord {
+    client: Client,
+}
+
+impl Discord {
+    /// Create a new API integration.
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+
+    /// Post a message to the webhook with the given URL.
+    pub async fn execute_webhook(&self, url: &str, message: &WebhookMessage) -> Result<()> {
+        let url = str::parse::<Url>(url)?;
+        let body = serde_json::to_vec(message)?;
+
+        let req = RequestBuilder::new(self.client.clone(), Method::POST, url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+
+        req.execute().await?.ok()
+    }
+}
+
+/// A message posted to a Discord webhook.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct WebhookMessage {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub embeds: Vec<Embed>,
+    /// Always sent, so that nothing in the message pings anyone unless
+    /// explicitly allowed.
+    pub allowed_mentions: AllowedMentions,
+}
+
+/// Which mentions in a message are allowed to ping.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct AllowedMentions {
+    /// Types of mentions to parse, like `everyone`. None by default.
+    pub parse: Vec<String>,
+}
+
+/// A rich embed in a message.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct Embed {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// The color of the embed, as an RGB value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<EmbedImage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<EmbedImage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<EmbedField>,
+}
+
+/// An image in an embed.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EmbedImage {
+    pub url: String,
+}
+
+/// A field in an embed.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
@@ -1,6 +1,6 @@
 //! FrankerFaceZ API Client.
 
-use crate::api::RequestBuilder;
+use crate::api::{RequestBuilder, Service};
 use anyhow::Result;
 use reqwest::{header, Client, Method, Url};
 use std::collections::HashMap;
@@ -33,7 +33,9 @@ impl FrankerFaceZ {
         }
 
         let req = RequestBuilder::new(self.client.clone(), method, url);
-        req.header(header::ACCEPT, "application/json")
+
+        req.service(Service::FrankerFaceZ)
+            .header(header::ACCEPT, "application/json")
     }
 
     /// Get information on a single user.
@@ -107,6 +109,9 @@ pub struct Emoticon {
     pub owner: EmoticonUser,
     pub public: bool,
     pub urls: Urls,
+    /// URLs of the animated version of the emote, if it's animated.
+    #[serde(default)]
+    pub animated: Option<Urls>,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -0,0 +1,153 @@
+//! Google Cloud Text-to-Speech API helpers.
+
+use crate::api::RequestBuilder;
+use crate::injector::Injector;
+use crate::prelude::*;
+use crate::settings::Settings;
+use anyhow::{anyhow, Result};
+use reqwest::{header, Client, Method, Url};
+use std::sync::Arc;
+
+const V1_URL: &str = "https://texttospeech.googleapis.com/v1";
+
+/// API integration.
+#[derive(Clone, Debug)]
+pub struct GoogleTts {
+    client: Client,
+    v1_url: Url,
+    api_key: Arc<String>,
+}
+
+struct Builder {
+    injector: Injector,
+    pub api_key: Option<String>,
+}
+
+impl Builder {
+    /// Inject a newly build value.
+    pub async fn build_and_inject(&self) -> Result<()> {
+        match &self.api_key {
+            Some(api_key) => {
+                self.injector
+                    .update(GoogleTts::new(api_key.to_string())?)
+                    .await;
+            }
+            None => {
+                let _ = self.injector.clear::<GoogleTts>().await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Hook up Google text-to-speech if all necessary settings are available.
+pub async fn setup(
+    settings: Settings,
+    injector: Injector,
+) -> Result<impl Future<Output = Result<()>>> {
+    let (mut api_key_stream, api_key) = settings
+        .stream::<String>("tts/google/api-key")
+        .optional()
+        .await?;
+
+    let mut builder = Builder { injector, api_key };
+
+    builder.build_and_inject().await?;
+
+    Ok(async move {
+        while let Some(api_key) = api_key_stream.next().await {
+            builder.api_key = api_key;
+            builder.build_and_inject().await?;
+        }
+
+        Err(anyhow!("api-key stream ended"))
+    })
+}
+
+impl GoogleTts {
+    /// Create a new API integration.
+    pub fn new(api_key: String) -> Result<GoogleTts> {
+        Ok(GoogleTts {
+            client: Client::new(),
+            v1_url: str::parse::<Url>(V1_URL)?,
+            api_key: Arc::new(api_key),
+        })
+    }
+
+    /// Build request against v1 URL.
+    fn v1(&self, method: Method, path: &[&str]) -> RequestBuilder {
+        let mut url = self.v1_url.clone();
+
+        {
+            let mut url_path = url.path_segments_mut().expect("bad base");
+            url_path.extend(path);
+        }
+
+        let req = RequestBuilder::new(self.client.clone(), method, url);
+        let req = req.query_param("key", &*self.api_key);
+        req.header(header::ACCEPT, "application/json")
+    }
+
+    /// Synthesize the given text, returning base64-encoded MP3 audio.
+    pub async fn synthesize(
+        &self,
+        text: &str,
+        language_code: &str,
+        voice: Option<&str>,
+    ) -> Result<String> {
+        let request = SynthesizeRequest {
+            input: Input { text },
+            voice: Voice {
+                language_code,
+                name: voice,
+            },
+            audio_config: AudioConfig {
+                audio_encoding: "MP3",
+            },
+        };
+
+        let body = serde_json::to_vec(&request)?;
+
+        let req = self
+            .v1(Method::POST, &["text:synthesize"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+
+        let response = req.execute().await?.json::<SynthesizeResponse>()?;
+        Ok(response.audio_content)
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SynthesizeRequest<'a> {
+    input: Input<'a>,
+    voice: Voice<'a>,
+    audio_config: AudioConfig,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Input<'a> {
+    text: &'a str,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Voice<'a> {
+    language_code: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioConfig {
+    audio_encoding: &'static str,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SynthesizeResponse {
+    audio_content: String,
+}
@@ -0,0 +1,198 @@
+//! Rate limiting and request coalescing shared by all API clients.
+//!
+//! Requests tagged with a [Service] through [RequestBuilder::service] are
+//! scheduled according to the budget of that service, so that bursts are
+//! queued up instead of being sent all at once. Services which report their
+//! own rate limits, like Twitch, don't have a budget here. Identical `GET` requests which
+//! are in flight at the same time are coalesced into a single request, which
+//! avoids stampeding an API when something like an emote cache expires.
+//!
+//! [RequestBuilder::service]: crate::api::RequestBuilder::service
+
+use crate::api::base::{RequestBuilder, Response};
+use crate::prelude::*;
+use anyhow::Result;
+use bytes::Bytes;
+use futures::future::{BoxFuture, Shared};
+use parking_lot::Mutex;
+use reqwest::Method;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+lazy_static::lazy_static! {
+    static ref LIMITER: Limiter = Limiter::default();
+}
+
+/// A service with its own request budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    Twitch,
+    Spotify,
+    YouTube,
+    FrankerFaceZ,
+    BetterTTV,
+    SevenTv,
+}
+
+impl Service {
+    /// The budget of the service, if requests to it are scheduled here.
+    fn budget(self) -> Option<Budget> {
+        match self {
+            // NB: Helix requests are scheduled according to the rate limit
+            // reported by Twitch, see `api::twitch`.
+            Service::Twitch => None,
+            Service::Spotify => Some(Budget::new(20, Duration::from_millis(250))),
+            Service::YouTube => Some(Budget::new(10, Duration::from_millis(500))),
+            Service::FrankerFaceZ => Some(Budget::new(10, Duration::from_secs(1))),
+            Service::BetterTTV => Some(Budget::new(10, Duration::from_secs(1))),
+            Service::SevenTv => Some(Budget::new(10, Duration::from_secs(1))),
+        }
+    }
+}
+
+/// How many requests can be sent to a service.
+#[derive(Debug, Clone, Copy)]
+struct Budget {
+    /// Number of requests which can be sent in a burst.
+    burst: u32,
+    /// How often a request is added back to the budget.
+    interval: Duration,
+}
+
+impl Budget {
+    const fn new(burst: u32, interval: Duration) -> Self {
+        Self { burst, interval }
+    }
+}
+
+/// Schedule of requests for a single service.
+#[derive(Debug)]
+struct Schedule {
+    budget: Budget,
+    /// The time at which the budget is fully restored, given all requests
+    /// scheduled so far.
+    restored_at: Option<Instant>,
+}
+
+impl Schedule {
+    fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            restored_at: None,
+        }
+    }
+
+    /// Reserve a request at the given time, returning how long to wait
+    /// before sending it.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let restored_at = match self.restored_at {
+            Some(restored_at) if restored_at > now => restored_at,
+            _ => now,
+        };
+
+        let restored_at = restored_at + self.budget.interval;
+        self.restored_at = Some(restored_at);
+
+        let window = self.budget.interval * self.budget.burst;
+        (restored_at - now).checked_sub(window).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("{0:#}")]
+struct SharedError(Arc<anyhow::Error>);
+
+type SharedResponse = Shared<BoxFuture<'static, Result<Response<Bytes>, SharedError>>>;
+
+#[derive(Default)]
+struct Limiter {
+    schedules: Mutex<HashMap<Service, Schedule>>,
+    in_flight: Mutex<HashMap<String, SharedResponse>>,
+}
+
+impl Limiter {
+    /// Wait until a request can be sent to the given service.
+    async fn acquire(&self, service: Service) {
+        let budget = match service.budget() {
+            Some(budget) => budget,
+            None => return,
+        };
+
+        let delay = self
+            .schedules
+            .lock()
+            .entry(service)
+            .or_insert_with(|| Schedule::new(budget))
+            .reserve(Instant::now());
+
+        if delay > Duration::default() {
+            log::trace!("Delaying request to {:?} by {:?}", service, delay);
+            tokio::time::delay_for(delay).await;
+        }
+    }
+}
+
+/// Execute the given request subject to the budget of the given service.
+pub(crate) async fn execute(service: Service, req: &RequestBuilder) -> Result<Response<Bytes>> {
+    if req.method() != &Method::GET {
+        LIMITER.acquire(service).await;
+        return req.send().await;
+    }
+
+    let key = req.coalesce_key().await?;
+
+    let shared = {
+        let mut in_flight = LIMITER.in_flight.lock();
+
+        match in_flight.get(&key) {
+            Some(shared) => {
+                log::trace!("Coalescing request: {}", req.url());
+                shared.clone()
+            }
+            None => {
+                let req = req.clone();
+                let k = key.clone();
+
+                let future = async move {
+                    LIMITER.acquire(service).await;
+                    let result = req.send().await.map_err(|e| SharedError(Arc::new(e)));
+                    LIMITER.in_flight.lock().remove(&k);
+                    result
+                };
+
+                let shared = future.boxed().shared();
+                in_flight.insert(key, shared.clone());
+                shared
+            }
+        }
+    };
+
+    Ok(shared.await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Budget, Schedule};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_schedule() {
+        let second = Duration::from_secs(1);
+        let mut schedule = Schedule::new(Budget::new(2, second));
+        let now = Instant::now();
+
+        // burst.
+        assert_eq!(Duration::default(), schedule.reserve(now));
+        assert_eq!(Duration::default(), schedule.reserve(now));
+        // queued up.
+        assert_eq!(second, schedule.reserve(now));
+        assert_eq!(second * 2, schedule.reserve(now));
+
+        // budget is restored over time.
+        let later = now + second * 10;
+        assert_eq!(Duration::default(), schedule.reserve(later));
+        assert_eq!(Duration::default(), schedule.reserve(later));
+        assert_eq!(second, schedule.reserve(later));
+    }
+}
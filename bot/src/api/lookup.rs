@@ -0,0 +1,247 @@
+//! Lookups of arbitrary JSON APIs, as used by the `api` template helper.
+
+use super::base::USER_AGENT;
+use crate::injector;
+use crate::storage::Cache;
+use anyhow::{anyhow, bail, Result};
+use futures::future;
+use hyper::body::HttpBody as _;
+use hyper::client::connect::dns::Name;
+use hyper::client::HttpConnector;
+use hyper::service::Service;
+use hyper::{header, Body, Client, Request};
+use hyper_tls::HttpsConnector;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use url::{Host, Url};
+
+/// How long responses, and failures to get them, are cached for.
+const CACHE_TTL: i64 = 60;
+/// How long to wait for a response.
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// The largest response which will be read, in bytes.
+const MAX_BODY: usize = 64 * 1024;
+
+/// API integration.
+#[derive(Clone)]
+pub struct Lookup {
+    tls: native_tls::TlsConnector,
+    cache: injector::Var<Option<Cache>>,
+}
+
+impl Lookup {
+    /// Create a new API integration.
+    pub fn new(cache: injector::Var<Option<Cache>>) -> Result<Self> {
+        Ok(Self {
+            tls: native_tls::TlsConnector::new()?,
+            cache,
+        })
+    }
+
+    /// Get the JSON document at the given URL, using a cached response if
+    /// available.
+    ///
+    /// Failed lookups are cached as well, so that a broken API isn't hit over
+    /// and over.
+    pub async fn get(&self, url: &str) -> Result<serde_json::Value> {
+        let future =
+            async move { Ok::<_, anyhow::Error>(self.fetch(url).await.map_err(|e| e.to_string())) };
+
+        let result = match self.cache.load().await {
+            Some(cache) => {
+                let key = format!("lookup/{}", url);
+                cache
+                    .wrap(key, chrono::Duration::seconds(CACHE_TTL), future)
+                    .await?
+            }
+            None => future.await?,
+        };
+
+        result.map_err(|e| anyhow!(e))
+    }
+
+    /// Get all of the given URLs, by URL.
+    ///
+    /// URLs which couldn't be looked up are logged and left out.
+    pub async fn get_all(
+        &self,
+        urls: impl IntoIterator<Item = String>,
+    ) -> HashMap<String, serde_json::Value> {
+        let futures = urls.into_iter().map(|url| async move {
+            let result = self.get(&url).await;
+            (url, result)
+        });
+
+        let mut out = HashMap::new();
+
+        for (url, result) in futures::future::join_all(futures).await {
+            match result {
+                Ok(value) => {
+                    out.insert(url, value);
+                }
+                Err(e) => {
+                    log_error!(e, "failed to look up: {}", url);
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Fetch the JSON document at the given URL.
+    async fn fetch(&self, url: &str) -> Result<serde_json::Value> {
+        let url = str::parse::<Url>(url)?;
+
+        match url.scheme() {
+            "http" | "https" => (),
+            scheme => bail!("unsupported scheme: {}", scheme),
+        }
+
+        let port = url.port_or_known_default().unwrap_or_default();
+
+        let addrs = match url.host() {
+            Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port))
+                .await?
+                .map(|addr| addr.ip())
+                .collect::<Vec<_>>(),
+            Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+            Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+            None => bail!("missing host"),
+        };
+
+        // NB: don't allow lookups against the bot itself or anything else on
+        // the local network.
+        if addrs.is_empty() || addrs.iter().any(|ip| !is_public(*ip)) {
+            bail!("host is not publicly reachable: {}", url);
+        }
+
+        // NB: connect to the addresses we just checked. Letting the client
+        // resolve the host again would allow it to resolve to something else
+        // the second time around.
+        let mut http = HttpConnector::new_with_resolver(Resolved(addrs));
+        http.enforce_http(false);
+        http.set_connect_timeout(Some(TIMEOUT));
+        let tls = tokio_tls::TlsConnector::from(self.tls.clone());
+        // NB: the client doesn't follow redirects, which could lead to hosts
+        // we don't allow.
+        let client = Client::builder().build::<_, Body>(HttpsConnector::from((http, tls)));
+
+        let req = Request::get(url.as_str())
+            .header(header::ACCEPT, "application/json")
+            .header(header::USER_AGENT, USER_AGENT)
+            .body(Body::empty())?;
+
+        let body = tokio::time::timeout(TIMEOUT, async move {
+            let res = client.request(req).await?;
+
+            if !res.status().is_success() {
+                bail!("Bad response: {}: {}", url, res.status());
+            }
+
+            let mut body = res.into_body();
+            let mut out = Vec::new();
+
+            while let Some(chunk) = body.data().await {
+                let chunk = chunk?;
+
+                if out.len() + chunk.len() > MAX_BODY {
+                    bail!("Response is larger than {} bytes: {}", MAX_BODY, url);
+                }
+
+                out.extend_from_slice(&chunk);
+            }
+
+            Ok(out)
+        })
+        .await
+        .map_err(|_| anyhow!("Request timed out"))??;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+/// DNS resolver which only hands out addresses that have already been
+/// resolved and checked.
+#[derive(Clone)]
+struct Resolved(Vec<IpAddr>);
+
+impl Service<Name> for Resolved {
+    type Response = std::vec::IntoIter<IpAddr>;
+    type Error = io::Error;
+    type Future = future::Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Name) -> Self::Future {
+        future::ready(Ok(self.0.clone().into_iter()))
+    }
+}
+
+/// Test if the given address is publicly reachable.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4() {
+            // NB: IPv4-mapped and IPv4-compatible addresses.
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || a == 0
+        // Shared address space (100.64.0.0/10).
+        || (a == 100 && (b & 0xc0) == 64))
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local (fc00::/7).
+        || (first & 0xfe00) == 0xfc00
+        // Link-local (fe80::/10).
+        || (first & 0xffc0) == 0xfe80)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_public;
+
+    #[test]
+    fn test_is_public() {
+        let public = |ip: &str| is_public(ip.parse().unwrap());
+
+        assert!(public("1.1.1.1"));
+        assert!(public("2606:4700:4700::1111"));
+
+        assert!(!public("127.0.0.1"));
+        assert!(!public("10.0.0.1"));
+        assert!(!public("172.16.0.1"));
+        assert!(!public("192.168.1.1"));
+        assert!(!public("169.254.169.254"));
+        assert!(!public("100.64.0.1"));
+        assert!(!public("0.0.0.0"));
+        assert!(!public("::1"));
+        assert!(!public("::"));
+        assert!(!public("fd00::1"));
+        assert!(!public("fe80::1"));
+        assert!(!public("::ffff:127.0.0.1"));
+    }
+}
@@ -0,0 +1,51 @@
+//! Mastodon API integration.
+
+use crate::api::RequestBuilder;
+use anyhow::{anyhow, Result};
+use reqwest::{header, Client, Method, Url};
+
+/// API integration.
+#[derive(Clone, Debug)]
+pub struct Mastodon {
+    client: Client,
+}
+
+impl Mastodon {
+    /// Create a new API integration.
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+
+    /// Post a public status to the instance with the given URL.
+    ///
+    /// Statuses posted with the same idempotency key are only posted once.
+    pub async fn post_status(
+        &self,
+        instance: &Url,
+        access_token: &str,
+        status: &str,
+        idempotency_key: &str,
+    ) -> Result<()> {
+        let mut url = instance.clone();
+
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("bad instance url: {}", instance))?
+            .pop_if_empty()
+            .extend(&["api", "v1", "statuses"]);
+
+        let body = serde_urlencoded::to_string(&[("status", status)])?;
+
+        let req = RequestBuilder::new(self.client.clone(), Method::POST, url)
+            .header(header::AUTHORIZATION, &format!("Bearer {}", access_token))
+            .header(
+                header::HeaderName::from_static("idempotency-key"),
+                idempotency_key,
+            )
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body);
+
+        req.execute().await?.ok()
+    }
+}
@@ -1,25 +1,47 @@
+pub mod bandcamp;
 mod base;
 pub mod bttv;
+pub mod discord;
 pub mod ffz;
 pub mod github;
+pub mod google_tts;
+pub mod limiter;
+pub mod lookup;
+pub mod mastodon;
 pub mod nightbot;
+pub mod obs;
 pub mod open_weather_map;
 pub mod setbac;
+pub mod seventv;
 pub mod speedrun;
 pub mod spotify;
+pub mod streamelements;
+pub mod streamlabs;
 pub mod tduva;
 pub mod twitch;
+pub mod twitter;
 pub mod youtube;
 
+pub use self::bandcamp::Bandcamp;
 pub use self::base::RequestBuilder;
 pub use self::bttv::BetterTTV;
+pub use self::discord::Discord;
 pub use self::ffz::FrankerFaceZ;
 pub use self::github::GitHub;
+pub use self::google_tts::GoogleTts;
+pub use self::limiter::Service;
+pub use self::lookup::Lookup;
+pub use self::mastodon::Mastodon;
 pub use self::nightbot::NightBot;
+pub use self::obs::Obs;
 pub use self::open_weather_map::OpenWeatherMap;
 pub use self::setbac::Setbac;
+pub use self::seventv::SevenTv;
 pub use self::speedrun::Speedrun;
 pub use self::spotify::Spotify;
+pub use self::streamelements::StreamElements;
+pub use self::streamlabs::Streamlabs;
 pub use self::tduva::Tduva;
 pub use self::twitch::Twitch;
+pub use self::twitter::Twitter;
 pub use self::youtube::YouTube;
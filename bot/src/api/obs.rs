@@ -0,0 +1,403 @@
+//! obs-websocket integration.
+//!
+//! Talks version 4 of the obs-websocket protocol, see:
+//! https://github.com/Palakis/obs-websocket/blob/4.x-current/docs/generated/protocol.md
+
+use crate::backoff;
+use crate::injector::Injector;
+use crate::prelude::*;
+use crate::settings::Settings;
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Map, Value};
+use sha2::{Digest as _, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Message id used while authenticating.
+const AUTH_MESSAGE_ID: &str = "authenticate";
+
+/// A request queued up for the connection.
+struct Request {
+    request_type: &'static str,
+    fields: Map<String, Value>,
+    response: oneshot::Sender<Result<Value>>,
+}
+
+/// A scene in OBS.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Scene {
+    pub name: String,
+}
+
+/// The list of scenes in OBS.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SceneList {
+    #[serde(rename = "current-scene")]
+    pub current_scene: String,
+    pub scenes: Vec<Scene>,
+}
+
+/// Client for a connected OBS instance.
+#[derive(Clone)]
+pub struct Obs {
+    tx: mpsc::UnboundedSender<Request>,
+}
+
+impl Obs {
+    /// Switch to the given scene.
+    pub async fn set_current_scene(&self, scene: &str) -> Result<()> {
+        self.request("SetCurrentScene", json!({ "scene-name": scene }))
+            .await?;
+        Ok(())
+    }
+
+    /// List all available scenes.
+    pub async fn scenes(&self) -> Result<SceneList> {
+        let list = self.request("GetSceneList", json!({})).await?;
+        Ok(serde_json::from_value(list)?)
+    }
+
+    /// Show or hide the given source.
+    ///
+    /// If no scene is specified, the source is looked up in the current scene.
+    pub async fn set_visible(
+        &self,
+        scene: Option<&str>,
+        source: &str,
+        visible: bool,
+    ) -> Result<()> {
+        let mut fields = json!({ "item": source, "visible": visible });
+
+        if let Some(scene) = scene {
+            fields["scene-name"] = json!(scene);
+        }
+
+        self.request("SetSceneItemProperties", fields).await?;
+        Ok(())
+    }
+
+    /// Set the text of a text source.
+    pub async fn set_text(&self, source: &str, text: &str) -> Result<()> {
+        let fields = json!({
+            "sourceName": source,
+            "sourceSettings": { "text": text },
+        });
+
+        self.request("SetSourceSettings", fields).await?;
+        Ok(())
+    }
+
+    /// Mute or unmute the given audio source.
+    pub async fn set_mute(&self, source: &str, mute: bool) -> Result<()> {
+        self.request("SetMute", json!({ "source": source, "mute": mute }))
+            .await?;
+        Ok(())
+    }
+
+    /// Start recording.
+    pub async fn start_recording(&self) -> Result<()> {
+        self.request("StartRecording", json!({})).await?;
+        Ok(())
+    }
+
+    /// Stop recording.
+    pub async fn stop_recording(&self) -> Result<()> {
+        self.request("StopRecording", json!({})).await?;
+        Ok(())
+    }
+
+    /// Start streaming.
+    pub async fn start_streaming(&self) -> Result<()> {
+        self.request("StartStreaming", json!({})).await?;
+        Ok(())
+    }
+
+    /// Stop streaming.
+    pub async fn stop_streaming(&self) -> Result<()> {
+        self.request("StopStreaming", json!({})).await?;
+        Ok(())
+    }
+
+    /// Send a request and wait for its response.
+    async fn request(&self, request_type: &'static str, fields: Value) -> Result<Value> {
+        let fields = match fields {
+            Value::Object(fields) => fields,
+            _ => bail!("request fields must be an object"),
+        };
+
+        let (response, rx) = oneshot::channel();
+
+        self.tx
+            .unbounded_send(Request {
+                request_type,
+                fields,
+                response,
+            })
+            .map_err(|_| anyhow!("not connected to OBS"))?;
+
+        rx.await.map_err(|_| anyhow!("not connected to OBS"))?
+    }
+}
+
+struct Builder {
+    injector: Injector,
+    url: Option<String>,
+    password: Option<String>,
+}
+
+impl Builder {
+    /// Inject a newly built client, and return the future driving its
+    /// connection.
+    async fn build_and_inject(&self) -> Option<Pin<Box<dyn Future<Output = ()> + Send>>> {
+        let url = match &self.url {
+            Some(url) => url.clone(),
+            None => {
+                let _ = self.injector.clear::<Obs>().await;
+                return None;
+            }
+        };
+
+        let (tx, rx) = mpsc::unbounded();
+        self.injector.update(Obs { tx }).await;
+        Some(Box::pin(run(url, self.password.clone(), rx)))
+    }
+}
+
+/// Hook up OBS if it's been configured.
+pub async fn setup(
+    settings: Settings,
+    injector: Injector,
+) -> Result<impl Future<Output = Result<()>>> {
+    let settings = settings.scoped("obs");
+
+    let (mut url_stream, url) = settings.stream::<String>("url").optional().await?;
+    let (mut password_stream, password) = settings.stream::<String>("password").optional().await?;
+
+    let mut builder = Builder {
+        injector,
+        url,
+        password,
+    };
+
+    let mut connection = builder.build_and_inject().await;
+
+    Ok(async move {
+        loop {
+            futures::select! {
+                update = url_stream.select_next_some() => {
+                    builder.url = update;
+                    connection = builder.build_and_inject().await;
+                }
+                update = password_stream.select_next_some() => {
+                    builder.password = update;
+                    connection = builder.build_and_inject().await;
+                }
+                _ = connection.current() => {
+                    return Err(anyhow!("obs connection ended unexpectedly"));
+                }
+            }
+        }
+    })
+}
+
+/// Keep a connection to OBS open, and reconnect with a backoff if it's lost.
+async fn run(url: String, password: Option<String>, mut rx: mpsc::UnboundedReceiver<Request>) {
+    let mut backoff = backoff::Exponential::new(Duration::from_secs(2));
+
+    loop {
+        match Connection::connect(&url, password.as_deref()).await {
+            Ok(connection) => {
+                log::info!("Connected to OBS at {}", url);
+                backoff = backoff::Exponential::new(Duration::from_secs(2));
+
+                if let Err(e) = connection.run(&mut rx).await {
+                    log_warn!(e, "Lost connection to OBS");
+                }
+            }
+            Err(e) => {
+                log_warn!(e, "Failed to connect to OBS at {}", url);
+            }
+        }
+
+        let mut delay = tokio::time::delay_for(backoff.next()).fuse();
+
+        // NB: fail requests while disconnected rather than letting them queue up.
+        loop {
+            futures::select! {
+                request = rx.next() => match request {
+                    Some(request) => {
+                        let _ = request.response.send(Err(anyhow!("not connected to OBS")));
+                    }
+                    None => return,
+                },
+                _ = delay => break,
+            }
+        }
+    }
+}
+
+type WebSocket = tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>;
+
+struct Connection {
+    ws: WebSocket,
+}
+
+impl Connection {
+    /// Connect and authenticate, if OBS requires it.
+    async fn connect(url: &str, password: Option<&str>) -> Result<Self> {
+        let (ws, _) = tokio_tungstenite::connect_async(url).await?;
+        let mut c = Self { ws };
+
+        let auth = c.call("GetAuthRequired", Map::new()).await?;
+
+        if auth["authRequired"].as_bool().unwrap_or_default() {
+            let password = match password {
+                Some(password) => password,
+                None => bail!("OBS requires a password, but none is configured"),
+            };
+
+            let (salt, challenge) = match (auth["salt"].as_str(), auth["challenge"].as_str()) {
+                (Some(salt), Some(challenge)) => (salt, challenge),
+                _ => bail!("OBS did not provide a salt and challenge"),
+            };
+
+            let mut fields = Map::new();
+            fields.insert(
+                String::from("auth"),
+                json!(auth_response(password, salt, challenge)),
+            );
+            c.call("Authenticate", fields).await?;
+        }
+
+        Ok(c)
+    }
+
+    /// Perform a single request while nothing else is in flight.
+    async fn call(&mut self, request_type: &str, mut fields: Map<String, Value>) -> Result<Value> {
+        fields.insert(String::from("request-type"), json!(request_type));
+        fields.insert(String::from("message-id"), json!(AUTH_MESSAGE_ID));
+        self.ws
+            .send(Message::Text(serde_json::to_string(&fields)?))
+            .await?;
+
+        while let Some(message) = self.ws.next().await {
+            if let Message::Text(text) = message? {
+                let (id, result) = match parse_response(&text)? {
+                    Some(response) => response,
+                    None => continue,
+                };
+
+                if id == AUTH_MESSAGE_ID {
+                    return result;
+                }
+            }
+        }
+
+        bail!("connection closed")
+    }
+
+    /// Serve requests until the connection is lost.
+    async fn run(self, rx: &mut mpsc::UnboundedReceiver<Request>) -> Result<()> {
+        let (mut sink, stream) = self.ws.split();
+        let mut stream = stream.fuse();
+        let mut pending = HashMap::<String, oneshot::Sender<Result<Value>>>::new();
+        let mut next_id = 0u64;
+
+        loop {
+            futures::select! {
+                request = rx.next() => {
+                    let request = match request {
+                        Some(request) => request,
+                        None => return Ok(()),
+                    };
+
+                    let id = next_id.to_string();
+                    next_id += 1;
+
+                    let mut fields = request.fields;
+                    fields.insert(String::from("request-type"), json!(request.request_type));
+                    fields.insert(String::from("message-id"), json!(id));
+
+                    sink.send(Message::Text(serde_json::to_string(&fields)?)).await?;
+                    pending.insert(id, request.response);
+                }
+                message = stream.next() => {
+                    let text = match message.transpose()? {
+                        Some(Message::Text(text)) => text,
+                        Some(Message::Close(..)) | None => bail!("connection closed"),
+                        Some(..) => continue,
+                    };
+
+                    if let Some((id, result)) = parse_response(&text)? {
+                        if let Some(response) = pending.remove(&id) {
+                            let _ = response.send(result);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parse a response to a request.
+///
+/// Returns `None` for events, which we don't care about.
+fn parse_response(text: &str) -> Result<Option<(String, Result<Value>)>> {
+    let response: Value = serde_json::from_str(text)?;
+
+    let id = match response["message-id"].as_str() {
+        Some(id) => id.to_string(),
+        None => return Ok(None),
+    };
+
+    let result = match response["status"].as_str() {
+        Some("ok") => Ok(response),
+        _ => match response["error"].as_str() {
+            Some(error) => Err(anyhow!("{}", error)),
+            None => Err(anyhow!("request failed")),
+        },
+    };
+
+    Ok(Some((id, result)))
+}
+
+/// Build the response to an authentication challenge.
+fn auth_response(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = base64::encode(Sha256::digest(format!("{}{}", password, salt).as_bytes()));
+    base64::encode(Sha256::digest(
+        format!("{}{}", secret, challenge).as_bytes(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{auth_response, parse_response};
+
+    #[test]
+    fn test_auth_response() {
+        assert_eq!(
+            "zTM5ki6L2vVvBQiTG9ckH1Lh64AbnCf6XZ226UmnkIA=",
+            auth_response("password", "salt", "challenge")
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        assert!(parse_response(r#"{"update-type": "SwitchScenes"}"#)
+            .unwrap()
+            .is_none());
+
+        let (id, result) = parse_response(r#"{"message-id": "1", "status": "ok"}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!("1", id);
+        assert!(result.is_ok());
+
+        let (_, result) =
+            parse_response(r#"{"message-id": "2", "status": "error", "error": "no such scene"}"#)
+                .unwrap()
+                .unwrap();
+        assert_eq!("no such scene", result.unwrap_err().to_string());
+    }
+}
@@ -94,6 +94,17 @@ impl OpenWeatherMap {
         let req = self.v2(Method::GET, &["weather"]).query_param("q", &q);
         Ok(req.execute().await?.not_found().json()?)
     }
+
+    /// Get the daily forecast at the given coordinates.
+    pub async fn one_call(&self, coord: Coord) -> Result<Option<OneCall>> {
+        let req = self
+            .v2(Method::GET, &["onecall"])
+            .query_param("lat", &coord.lat.to_string())
+            .query_param("lon", &coord.lon.to_string())
+            .query_param("exclude", "current,minutely,hourly,alerts");
+
+        Ok(req.execute().await?.not_found().json()?)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -202,3 +213,38 @@ pub struct Precipitation {
 pub struct Clouds {
     pub all: u64,
 }
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct OneCall {
+    pub lat: f32,
+    pub lon: f32,
+    /// Offset of the local time zone from UTC, in seconds.
+    pub timezone_offset: i64,
+    #[serde(default)]
+    pub daily: Vec<Daily>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Daily {
+    /// Time of the forecast as a unix timestamp in UTC.
+    pub dt: i64,
+    pub temp: DailyTemp,
+    pub weather: Vec<Weather>,
+    /// Probability of precipitation, between `0` and `1`.
+    #[serde(default)]
+    pub pop: Option<f32>,
+    #[serde(default)]
+    pub wind_speed: Option<f32>,
+    /// Volume of rain, in millimeters.
+    #[serde(default)]
+    pub rain: Option<f32>,
+    /// Volume of snow, in millimeters.
+    #[serde(default)]
+    pub snow: Option<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DailyTemp {
+    pub min: f32,
+    pub max: f32,
+}
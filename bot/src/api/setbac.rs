@@ -361,6 +361,16 @@ impl Setbac {
         Ok(data)
     }
 
+    /// Get the feature flags published for this installation.
+    pub async fn flags(&self) -> Result<Vec<Flag>> {
+        let req = self
+            .request(Method::GET, &["api", "flags"])
+            .header(header::CONTENT_TYPE, "application/json");
+
+        let data = req.execute().await?.json::<Vec<Flag>>()?;
+        Ok(data)
+    }
+
     /// Refresh the token corresponding to the given flow.
     pub async fn refresh_connection(&self, id: &str) -> Result<Option<Connection>> {
         let req = self
@@ -383,6 +393,19 @@ impl<T> Default for Data<T> {
     }
 }
 
+/// A feature flag published by the remote API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flag {
+    /// Identifier of the flag.
+    pub id: String,
+    /// If the flag is enabled.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Documentation for the flag.
+    #[serde(default)]
+    pub doc: Option<String>,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct PlayerUpdate {
     /// Current song.
@@ -0,0 +1,153 @@
+//! 7TV API Client.
+
+use crate::api::{RequestBuilder, Service};
+use anyhow::Result;
+use reqwest::{header, Client, Method, Url};
+
+const V3_URL: &str = "https://7tv.io/v3";
+/// Flag on an emote in an emote set which marks it as zero-width.
+const ACTIVE_EMOTE_ZERO_WIDTH: u32 = 1;
+/// Flag on an emote which marks it as zero-width wherever it's used.
+const EMOTE_ZERO_WIDTH: u32 = 1 << 8;
+
+/// API integration.
+#[derive(Clone, Debug)]
+pub struct SevenTv {
+    client: Client,
+    v3_url: Url,
+}
+
+impl SevenTv {
+    /// Create a new API integration.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            v3_url: str::parse::<Url>(V3_URL)?,
+        })
+    }
+
+    /// Build request against v3 URL.
+    fn v3(&self, method: Method, path: &[&str]) -> RequestBuilder {
+        let mut url = self.v3_url.clone();
+
+        {
+            let mut url_path = url.path_segments_mut().expect("bad base");
+            url_path.extend(path);
+        }
+
+        let req = RequestBuilder::new(self.client.clone(), method, url);
+
+        req.service(Service::SevenTv)
+            .header(header::ACCEPT, "application/json")
+    }
+
+    /// Get the 7TV user connected to the Twitch user with the given id.
+    pub async fn twitch_user(&self, id: &str) -> Result<Option<User>> {
+        let req = self.v3(Method::GET, &["users", "twitch", id]);
+        let data = req.execute().await?.not_found().json()?;
+        Ok(data)
+    }
+
+    /// Get the global emote set.
+    pub async fn global_emote_set(&self) -> Result<EmoteSet> {
+        let req = self.v3(Method::GET, &["emote-sets", "global"]);
+        let data = req.execute().await?.json()?;
+        Ok(data)
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct User {
+    /// The emote set which is active in the channel of the user.
+    #[serde(default)]
+    pub emote_set: Option<EmoteSet>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct EmoteSet {
+    #[serde(default)]
+    pub emotes: Vec<ActiveEmote>,
+}
+
+/// An emote which is part of an emote set.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ActiveEmote {
+    pub id: String,
+    /// The name of the emote in the set, which can differ from the name of
+    /// the emote itself.
+    pub name: String,
+    #[serde(default)]
+    pub flags: u32,
+    /// Missing if the emote has been deleted.
+    #[serde(default)]
+    pub data: Option<Emote>,
+}
+
+impl ActiveEmote {
+    /// Test if the emote is zero-width, meaning that it's drawn on top of the
+    /// emote preceding it.
+    pub fn is_zero_width(&self) -> bool {
+        if self.flags & ACTIVE_EMOTE_ZERO_WIDTH != 0 {
+            return true;
+        }
+
+        match &self.data {
+            Some(data) => data.flags & EMOTE_ZERO_WIDTH != 0,
+            None => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Emote {
+    #[serde(default)]
+    pub flags: u32,
+    #[serde(default)]
+    pub animated: bool,
+    pub host: Host,
+}
+
+/// Where the files of an emote are hosted.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Host {
+    /// The URL the files are relative to, without a scheme.
+    pub url: String,
+    #[serde(default)]
+    pub files: Vec<File>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct File {
+    /// The name of the file, like `1x.webp`.
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// The format of the file, like `WEBP` or `AVIF`.
+    pub format: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ActiveEmote;
+
+    #[test]
+    fn test_zero_width() {
+        let emote = |flags, data_flags| {
+            serde_json::from_value::<ActiveEmote>(serde_json::json!({
+                "id": "60aeab8df6a2c3b332d21139",
+                "name": "RainTime",
+                "flags": flags,
+                "data": {
+                    "flags": data_flags,
+                    "animated": true,
+                    "host": {"url": "//cdn.7tv.app/emote/60aeab8df6a2c3b332d21139"},
+                },
+            }))
+            .unwrap()
+        };
+
+        assert!(!emote(0, 0).is_zero_width());
+        assert!(emote(1, 0).is_zero_width());
+        assert!(emote(0, 256).is_zero_width());
+    }
+}
@@ -9,7 +9,7 @@ pub use self::model::search::SearchTracks;
 pub use self::model::senum::DeviceType;
 pub use self::model::track::{FullTrack, SavedTrack};
 pub use self::model::user::PrivateUser;
-use crate::api::RequestBuilder;
+use crate::api::{RequestBuilder, Service};
 use crate::oauth2;
 use crate::prelude::*;
 use anyhow::Result;
@@ -22,6 +22,8 @@ use url::Url;
 mod model;
 
 const API_URL: &str = "https://api.spotify.com/v1";
+/// The maximum number of tracks that can be requested in a single call to `tracks`.
+pub const MAX_TRACKS: usize = 50;
 
 /// API integration.
 #[derive(Clone, Debug)]
@@ -45,7 +47,9 @@ impl Spotify {
     fn request(&self, method: Method, path: &[&str]) -> RequestBuilder {
         let mut url = self.api_url.clone();
         url.path_segments_mut().expect("bad base").extend(path);
-        RequestBuilder::new(self.client.clone(), method, url).token(self.token.clone())
+        RequestBuilder::new(self.client.clone(), method, url)
+            .token(self.token.clone())
+            .service(Service::Spotify)
     }
 
     /// Get user info.
@@ -209,6 +213,36 @@ impl Spotify {
         req.execute().await?.json()
     }
 
+    /// Get several full tracks by ID.
+    ///
+    /// The response has the same order as the requested IDs, and tracks which
+    /// couldn't be found are returned as `None`. At most `MAX_TRACKS` IDs can
+    /// be requested at a time.
+    pub async fn tracks(
+        &self,
+        ids: &[String],
+        market: Option<&str>,
+    ) -> Result<Vec<Option<FullTrack>>> {
+        if ids.len() > MAX_TRACKS {
+            anyhow::bail!("can't request more than {} tracks at a time", MAX_TRACKS);
+        }
+
+        let ids = ids.join(",");
+
+        let req = self
+            .request(Method::GET, &["tracks"])
+            .query_param("ids", &ids)
+            .optional_query_param("market", market);
+
+        let r = req.execute().await?.json::<Response>()?;
+        return Ok(r.tracks);
+
+        #[derive(serde::Deserialize)]
+        struct Response {
+            tracks: Vec<Option<FullTrack>>,
+        }
+    }
+
     /// Search for tracks.
     pub async fn search_track(&self, q: &str) -> Result<Page<FullTrack>> {
         let req = self
@@ -264,8 +298,9 @@ where
 {
     /// Get the next page for a type.
     pub fn next_page(&self, url: Url) -> impl Future<Output = Result<Page<T>>> {
-        let req =
-            RequestBuilder::new(self.client.clone(), Method::GET, url).token(self.token.clone());
+        let req = RequestBuilder::new(self.client.clone(), Method::GET, url)
+            .token(self.token.clone())
+            .service(Service::Spotify);
 
         async move { req.execute().await?.json() }
     }
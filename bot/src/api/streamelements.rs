@@ -0,0 +1,115 @@
+//! StreamElements API helpers.
+
+use crate::api::RequestBuilder;
+use crate::injector::{Injector, Provider};
+use crate::oauth2;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::{header, Client, Method, Url};
+
+static STREAMELEMENTS_URL_V2: &str = "https://api.streamelements.com/kappa/v2";
+
+/// Scopes which are required for the integration to work.
+pub const SCOPES: &[&str] = &["channel:read", "tips:read"];
+
+#[derive(Provider)]
+#[provider(build = "Builder::build", output = "StreamElements")]
+struct Builder {
+    #[dependency(tag = "oauth2::TokenId::StreamElements")]
+    token: oauth2::SyncToken,
+}
+
+impl Builder {
+    pub async fn build(self) -> Option<StreamElements> {
+        self.token.warn_missing_scopes(SCOPES).await;
+        StreamElements::new(self.token).ok()
+    }
+}
+
+/// API integration.
+#[derive(Clone, Debug)]
+pub struct StreamElements {
+    client: Client,
+    api_url: Url,
+    token: oauth2::SyncToken,
+}
+
+impl StreamElements {
+    /// Create a new API integration.
+    pub fn new(token: oauth2::SyncToken) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            api_url: str::parse(STREAMELEMENTS_URL_V2)?,
+            token,
+        })
+    }
+
+    /// Run the stream that updates the streamelements client.
+    pub async fn run(injector: Injector) -> Result<()> {
+        Builder::run(&injector).await?;
+        Ok(())
+    }
+
+    /// Get request against API.
+    fn request(&self, method: Method, path: &[&str]) -> RequestBuilder {
+        let mut url = self.api_url.clone();
+        url.path_segments_mut().expect("bad base").extend(path);
+
+        RequestBuilder::new(self.client.clone(), method, url)
+            .token(self.token.clone())
+            .use_oauth2_header()
+            .header(header::ACCEPT, "application/json")
+    }
+
+    /// Get the channel associated with the token.
+    pub async fn channel(&self) -> Result<Channel> {
+        let req = self.request(Method::GET, &["channels", "me"]);
+        req.execute().await?.json()
+    }
+
+    /// List the most recent tips of the given channel, newest first.
+    pub async fn tips(&self, channel_id: &str, limit: u32) -> Result<Vec<Tip>> {
+        let req = self
+            .request(Method::GET, &["tips", channel_id])
+            .query_param("limit", &limit.to_string())
+            .query_param("sort", "-createdAt");
+
+        let tips = req.execute().await?.json::<Docs<Vec<Tip>>>()?;
+        Ok(tips.docs)
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Docs<T> {
+    docs: T,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Channel {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub username: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Tip {
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    pub donation: TipDonation,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TipDonation {
+    pub user: TipUser,
+    pub amount: f64,
+    pub currency: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TipUser {
+    pub username: String,
+}
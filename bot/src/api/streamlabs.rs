@@ -0,0 +1,138 @@
+//! Streamlabs API helpers.
+
+use crate::api::RequestBuilder;
+use crate::injector::{Injector, Provider};
+use crate::oauth2;
+use anyhow::Result;
+use reqwest::{header, Client, Method, Url};
+
+static STREAMLABS_URL_V1: &str = "https://streamlabs.com/api/v1.0";
+
+/// Scopes which are required for the integration to work.
+pub const SCOPES: &[&str] = &["donations.read"];
+
+#[derive(Provider)]
+#[provider(build = "Builder::build", output = "Streamlabs")]
+struct Builder {
+    #[dependency(tag = "oauth2::TokenId::Streamlabs")]
+    token: oauth2::SyncToken,
+}
+
+impl Builder {
+    pub async fn build(self) -> Option<Streamlabs> {
+        self.token.warn_missing_scopes(SCOPES).await;
+        Streamlabs::new(self.token).ok()
+    }
+}
+
+/// API integration.
+#[derive(Clone, Debug)]
+pub struct Streamlabs {
+    client: Client,
+    api_url: Url,
+    token: oauth2::SyncToken,
+}
+
+impl Streamlabs {
+    /// Create a new API integration.
+    pub fn new(token: oauth2::SyncToken) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            api_url: str::parse(STREAMLABS_URL_V1)?,
+            token,
+        })
+    }
+
+    /// Run the stream that updates the streamlabs client.
+    pub async fn run(injector: Injector) -> Result<()> {
+        Builder::run(&injector).await?;
+        Ok(())
+    }
+
+    /// Get request against API.
+    fn request(&self, method: Method, path: &[&str]) -> RequestBuilder {
+        let mut url = self.api_url.clone();
+        url.path_segments_mut().expect("bad base").extend(path);
+
+        RequestBuilder::new(self.client.clone(), method, url)
+            .token(self.token.clone())
+            .header(header::ACCEPT, "application/json")
+    }
+
+    /// List the most recent donations, newest first.
+    ///
+    /// If `after` is specified, only donations newer than the donation with
+    /// the given id are listed.
+    pub async fn donations(&self, after: Option<u64>, limit: u32) -> Result<Vec<Donation>> {
+        let after = after.map(|id| id.to_string());
+
+        let req = self
+            .request(Method::GET, &["donations"])
+            .query_param("limit", &limit.to_string())
+            .optional_query_param("after", after.as_deref());
+
+        let data = req.execute().await?.json::<Data<Vec<Donation>>>()?;
+        Ok(data.data)
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Data<T> {
+    data: T,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Donation {
+    pub donation_id: u64,
+    /// When the donation was made, in seconds since the unix epoch.
+    pub created_at: i64,
+    pub currency: String,
+    #[serde(deserialize_with = "amount")]
+    pub amount: f64,
+    pub name: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Amounts are sometimes reported as strings, like `"13.3700"`.
+fn amount<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize as _;
+
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Amount {
+        Number(f64),
+        String(String),
+    }
+
+    match Amount::deserialize(deserializer)? {
+        Amount::Number(n) => Ok(n),
+        Amount::String(s) => s.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Donation;
+
+    #[test]
+    fn test_donation_amount() {
+        let donation = serde_json::from_str::<Donation>(
+            r#"{
+                "donation_id": 79105362,
+                "created_at": 1450632645,
+                "currency": "USD",
+                "amount": "13.3700",
+                "name": "Jeff",
+                "message": "Hello!"
+            }"#,
+        )
+        .unwrap();
+
+        assert!((donation.amount - 13.37).abs() < f64::EPSILON);
+        assert_eq!(Some("Hello!"), donation.message.as_deref());
+    }
+}
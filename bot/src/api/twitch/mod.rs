@@ -1,24 +1,35 @@
 //! Twitch API helpers.
+//!
+//! Everything except token management, chatters, and badges through GQL goes
+//! through the Helix API. Helix requests are scheduled according to the
+//! `Ratelimit-*` headers reported by Twitch, see [`ratelimit`].
 
-use crate::api::RequestBuilder;
+use crate::api::base::Response;
+use crate::api::{RequestBuilder, Service};
 use crate::oauth2;
 use crate::prelude::*;
-use anyhow::{Context as _, Result};
+use anyhow::{bail, Context as _, Result};
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use reqwest::{header, Client, Method, StatusCode, Url};
-use std::collections::HashMap;
 
 pub const CLIPS_URL: &str = "http://clips.twitch.tv";
 const TMI_TWITCH_URL: &str = "https://tmi.twitch.tv";
 const API_TWITCH_URL: &str = "https://api.twitch.tv";
 const ID_TWITCH_URL: &str = "https://id.twitch.tv";
-const BADGES_TWITCH_URL: &str = "https://badges.twitch.tv";
 const GQL_URL: &str = "https://gql.twitch.tv/gql";
 
 const GQL_CLIENT_ID: &str = "kimne78kx3ncx6brgo4mv6wki5h1ko";
 
+/// The maximum number of items Helix returns in a single page.
+const PAGE_SIZE: &str = "100";
+/// How many times a rate limited request is retried.
+const RATELIMIT_RETRIES: usize = 3;
+
 mod gql;
+mod ratelimit;
+
+use self::ratelimit::RateLimit;
 
 /// API integration.
 #[derive(Clone, Debug)]
@@ -26,8 +37,8 @@ pub struct Twitch {
     client: Client,
     api_url: Url,
     id_url: Url,
-    badges_url: Url,
     gql_url: Url,
+    ratelimit: RateLimit,
     pub token: oauth2::SyncToken,
 }
 
@@ -38,14 +49,14 @@ impl Twitch {
             client: Client::new(),
             api_url: str::parse::<Url>(API_TWITCH_URL)?,
             id_url: str::parse::<Url>(ID_TWITCH_URL)?,
-            badges_url: str::parse::<Url>(BADGES_TWITCH_URL)?,
             gql_url: str::parse::<Url>(GQL_URL)?,
+            ratelimit: RateLimit::default(),
             token,
         })
     }
 
-    /// Get request against API.
-    fn new_api(&self, method: Method, path: &[&str]) -> RequestBuilder {
+    /// Get request against the Helix API.
+    fn helix(&self, method: Method, path: &[&str]) -> RequestBuilder {
         let mut url = self.api_url.clone();
 
         {
@@ -57,36 +68,33 @@ impl Twitch {
         RequestBuilder::new(self.client.clone(), method, url)
             .token(self.token.clone())
             .client_id_header("Client-ID")
+            .service(Service::Twitch)
     }
 
-    /// Get request against API.
-    fn v5(&self, method: Method, path: &[&str]) -> RequestBuilder {
-        let mut url = self.api_url.clone();
-
-        {
-            let mut url_path = url.path_segments_mut().expect("bad base");
-            url_path.push("kraken");
-            url_path.extend(path);
-        }
-
-        RequestBuilder::new(self.client.clone(), method, url)
-            .header(header::ACCEPT, "application/vnd.twitchtv.v5+json")
-            .token(self.token.clone())
-            .client_id_header("Client-ID")
-            .use_oauth2_header()
+    /// Execute a Helix request, respecting the rate limit.
+    async fn execute(&self, req: &RequestBuilder) -> Result<Response<Bytes>> {
+        execute(&self.ratelimit, req).await
     }
 
-    /// Get request against Badges API.
-    fn badges_v1(&self, method: Method, path: &[&str]) -> RequestBuilder {
-        let mut url = self.badges_url.clone();
+    /// Build a stream of all pages of the given Helix request.
+    fn paged<T>(&self, request: RequestBuilder) -> Paged<T>
+    where
+        T: 'static + Send + serde::de::DeserializeOwned,
+    {
+        let request = request.query_param("first", PAGE_SIZE);
+        let ratelimit = self.ratelimit.clone();
 
-        {
-            let mut url_path = url.path_segments_mut().expect("bad base");
-            url_path.push("v1");
-            url_path.extend(path);
-        }
+        let initial = {
+            let ratelimit = ratelimit.clone();
+            let req = request.clone();
+            async move { execute(&ratelimit, &req).await?.json::<Page<T>>() }
+        };
 
-        RequestBuilder::new(self.client.clone(), method, url)
+        Paged {
+            ratelimit,
+            request,
+            page: Some(initial.boxed()),
+        }
     }
 
     /// Access GQL client.
@@ -102,104 +110,319 @@ impl Twitch {
         Ok(req)
     }
 
-    /// Update the channel information.
-    pub async fn update_channel(
+    /// Modify the channel information of the given broadcaster.
+    pub async fn modify_channel(
         &self,
-        channel_id: &str,
-        request: UpdateChannelRequest,
+        broadcaster_id: &str,
+        request: ModifyChannelRequest,
     ) -> Result<()> {
         let body = Bytes::from(serde_json::to_vec(&request)?);
 
         let req = self
-            .v5(Method::PUT, &["channels", channel_id])
+            .helix(Method::PATCH, &["channels"])
+            .query_param("broadcaster_id", broadcaster_id)
             .header(header::CONTENT_TYPE, "application/json")
             .body(body);
 
-        req.execute().await?.ok()
+        self.execute(&req).await?.ok()
+    }
+
+    /// Get the user associated with the current authentication.
+    pub async fn user(&self) -> Result<User> {
+        let req = self.helix(Method::GET, &["users"]);
+        let res = self.execute(&req).await?.json::<Data<User>>()?;
+
+        match res.data.into_iter().next() {
+            Some(user) => Ok(user),
+            None => bail!("no user associated with the current token"),
+        }
     }
 
     /// Get information on a user.
-    pub async fn user_by_login(&self, login: &str) -> Result<Option<NewUser>> {
+    pub async fn user_by_login(&self, login: &str) -> Result<Option<User>> {
         let req = self
-            .new_api(Method::GET, &["users"])
+            .helix(Method::GET, &["users"])
             .query_param("login", login);
 
-        let res = req.execute().await?.json::<Data<NewUser>>()?;
+        let res = self.execute(&req).await?.json::<Data<User>>()?;
+        Ok(res.data.into_iter().next())
+    }
+
+    /// Get the channel information of the given broadcaster.
+    pub async fn channel_by_id(&self, broadcaster_id: &str) -> Result<Option<Channel>> {
+        let req = self
+            .helix(Method::GET, &["channels"])
+            .query_param("broadcaster_id", broadcaster_id);
 
+        let res = self.execute(&req).await?.json::<Data<Channel>>()?;
         Ok(res.data.into_iter().next())
     }
 
-    /// Get information on a user.
+    /// Find a game by its exact name.
+    pub async fn game_by_name(&self, name: &str) -> Result<Option<Game>> {
+        let req = self
+            .helix(Method::GET, &["games"])
+            .query_param("name", name);
+
+        let res = self.execute(&req).await?.json::<Data<Game>>()?;
+        Ok(res.data.into_iter().next())
+    }
+
+    /// Find a game by its id.
+    pub async fn game_by_id(&self, id: &str) -> Result<Option<Game>> {
+        let req = self.helix(Method::GET, &["games"]).query_param("id", id);
+        let res = self.execute(&req).await?.json::<Data<Game>>()?;
+        Ok(res.data.into_iter().next())
+    }
+
+    /// Search for categories, like games, by name.
+    pub async fn search_categories(&self, query: &str) -> Result<Vec<Game>> {
+        let req = self
+            .helix(Method::GET, &["search", "categories"])
+            .query_param("query", query);
+
+        let res = self.execute(&req).await?.json::<Page<Game>>()?;
+        Ok(res.data)
+    }
+
+    /// Get stream information.
+    pub async fn stream_by_id(&self, id: &str) -> Result<Option<Stream>> {
+        let req = self
+            .helix(Method::GET, &["streams"])
+            .query_param("user_id", id);
+
+        let res = self.execute(&req).await?.json::<Page<Stream>>()?;
+        Ok(res.data.into_iter().next())
+    }
+
+    /// Get the subscriptions of the given broadcaster, optionally limited to
+    /// the given users.
     pub fn stream_subscriptions(
         &self,
         broadcaster_id: &str,
         user_ids: Vec<String>,
     ) -> Paged<Subscription> {
         let mut request = self
-            .new_api(Method::GET, &["subscriptions"])
+            .helix(Method::GET, &["subscriptions"])
             .query_param("broadcaster_id", broadcaster_id);
 
         for user_id in &user_ids {
             request = request.query_param("user_id", user_id);
         }
 
-        let req = request.clone();
+        self.paged(request)
+    }
 
-        let initial = async move { req.execute().await?.json::<Page<Subscription>>() };
+    /// Get the most recent followers of the given user, newest first.
+    pub async fn followers(&self, user_id: &str, first: u32) -> Result<Vec<Follow>> {
+        let req = self
+            .helix(Method::GET, &["users", "follows"])
+            .query_param("to_id", user_id)
+            .query_param("first", &first.to_string());
 
-        Paged {
-            request,
-            page: Some(initial.boxed()),
-        }
+        let res = self.execute(&req).await?.json::<Page<Follow>>()?;
+        Ok(res.data)
+    }
+
+    /// Get the follow relationship between two users, if one follows the
+    /// other.
+    pub async fn follow(&self, from_id: &str, to_id: &str) -> Result<Option<Follow>> {
+        let req = self
+            .helix(Method::GET, &["users", "follows"])
+            .query_param("from_id", from_id)
+            .query_param("to_id", to_id);
+
+        let res = self.execute(&req).await?.json::<Page<Follow>>()?;
+        Ok(res.data.into_iter().next())
     }
 
     /// Create a clip for the given broadcaster.
     pub async fn create_clip(&self, broadcaster_id: &str) -> Result<Option<Clip>> {
         let req = self
-            .new_api(Method::POST, &["clips"])
+            .helix(Method::POST, &["clips"])
             .query_param("broadcaster_id", broadcaster_id);
 
-        let res = req.execute().await?.json::<Data<Clip>>()?;
+        let res = self.execute(&req).await?.json::<Data<Clip>>()?;
+        Ok(res.data.into_iter().next())
+    }
+
+    /// Create a stream marker at the current position of the live stream of
+    /// the given user.
+    pub async fn create_stream_marker(
+        &self,
+        user_id: &str,
+        description: Option<&str>,
+    ) -> Result<Option<StreamMarker>> {
+        let request = CreateStreamMarker {
+            user_id,
+            description,
+        };
+
+        let body = Bytes::from(serde_json::to_vec(&request)?);
+
+        let req = self
+            .helix(Method::POST, &["streams", "markers"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
 
+        let res = self.execute(&req).await?.json::<Data<StreamMarker>>()?;
         Ok(res.data.into_iter().next())
     }
 
-    /// Get the channela associated with the current authentication.
-    pub async fn user(&self) -> Result<User> {
-        let req = self.v5(Method::GET, &["user"]);
-        req.execute().await?.json()
+    /// Start a commercial of the given length (in seconds) on the channel
+    /// of the given broadcaster.
+    pub async fn start_commercial(
+        &self,
+        broadcaster_id: &str,
+        length: u32,
+    ) -> Result<Option<Commercial>> {
+        let request = StartCommercial {
+            broadcaster_id,
+            length,
+        };
+
+        let body = Bytes::from(serde_json::to_vec(&request)?);
+
+        let req = self
+            .helix(Method::POST, &["channels", "commercial"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+
+        let res = self.execute(&req).await?.json::<Data<Commercial>>()?;
+        Ok(res.data.into_iter().next())
     }
 
-    /// Get the channela associated with the current authentication.
-    pub async fn channel(&self) -> Result<Channel> {
-        let req = self.v5(Method::GET, &["channel"]);
+    /// Remove a ban or timeout of the given user in the channel of the given
+    /// broadcaster.
+    pub async fn unban_user(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+        user_id: &str,
+    ) -> Result<()> {
+        let req = self
+            .helix(Method::DELETE, &["moderation", "bans"])
+            .query_param("broadcaster_id", broadcaster_id)
+            .query_param("moderator_id", moderator_id)
+            .query_param("user_id", user_id);
 
-        req.execute().await?.json::<Channel>()
+        self.execute(&req).await?.ok()
     }
 
-    /// Get the channela associated with the current authentication.
-    pub async fn channel_by_id(&self, channel_id: &str) -> Result<Channel> {
-        let req = self.v5(Method::GET, &["channels", channel_id]);
-        req.execute().await?.json::<Channel>()
+    /// Send an announcement to the chat of the given broadcaster, which is
+    /// highlighted with the given color.
+    ///
+    /// Requires the `moderator:manage:announcements` scope.
+    pub async fn send_chat_announcement(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+        message: &str,
+        color: AnnouncementColor,
+    ) -> Result<()> {
+        let request = SendChatAnnouncement { message, color };
+        let body = Bytes::from(serde_json::to_vec(&request)?);
+
+        let req = self
+            .helix(Method::POST, &["chat", "announcements"])
+            .query_param("broadcaster_id", broadcaster_id)
+            .query_param("moderator_id", moderator_id)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+
+        self.execute(&req).await?.ok()
     }
 
-    /// Get stream information.
-    pub async fn stream_by_id(&self, id: &str) -> Result<Option<Stream>> {
+    /// Create a poll in the channel of the given broadcaster, which runs for
+    /// the given number of seconds.
+    ///
+    /// Requires the `channel:manage:polls` scope.
+    pub async fn create_poll(
+        &self,
+        broadcaster_id: &str,
+        title: &str,
+        choices: &[String],
+        duration: u32,
+    ) -> Result<Option<Poll>> {
+        let request = CreatePoll {
+            broadcaster_id,
+            title,
+            choices: choices
+                .iter()
+                .map(|title| CreatePollChoice { title })
+                .collect(),
+            duration,
+        };
+
+        let body = Bytes::from(serde_json::to_vec(&request)?);
+
         let req = self
-            .new_api(Method::GET, &["streams"])
-            .query_param("user_id", id);
+            .helix(Method::POST, &["polls"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+
+        let res = self.execute(&req).await?.json::<Data<Poll>>()?;
+        Ok(res.data.into_iter().next())
+    }
 
-        let res = req.execute().await?.json::<Page<Stream>>()?;
+    /// End a poll in the channel of the given broadcaster, returning the
+    /// final results.
+    ///
+    /// Requires the `channel:manage:polls` scope.
+    pub async fn end_poll(&self, broadcaster_id: &str, id: &str) -> Result<Option<Poll>> {
+        let request = EndPoll {
+            broadcaster_id,
+            id,
+            status: "TERMINATED",
+        };
 
+        let body = Bytes::from(serde_json::to_vec(&request)?);
+
+        let req = self
+            .helix(Method::PATCH, &["polls"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+
+        let res = self.execute(&req).await?.json::<Data<Poll>>()?;
         Ok(res.data.into_iter().next())
     }
 
-    /// Get emotes by sets.
-    pub async fn chat_emoticon_images(&self, emote_sets: &str) -> Result<EmoticonSets> {
+    /// Get a poll in the channel of the given broadcaster.
+    ///
+    /// Requires the `channel:read:polls` scope.
+    pub async fn poll(&self, broadcaster_id: &str, id: &str) -> Result<Option<Poll>> {
         let req = self
-            .v5(Method::GET, &["chat", "emoticon_images"])
-            .query_param("emotesets", emote_sets);
-        req.execute().await?.json::<EmoticonSets>()
+            .helix(Method::GET, &["polls"])
+            .query_param("broadcaster_id", broadcaster_id)
+            .query_param("id", id);
+
+        let res = self.execute(&req).await?.json::<Data<Poll>>()?;
+        Ok(res.data.into_iter().next())
+    }
+
+    /// Get the global emotes, which are available to everyone.
+    pub async fn global_emotes(&self) -> Result<Vec<ChatEmote>> {
+        let req = self.helix(Method::GET, &["chat", "emotes", "global"]);
+        let res = self.execute(&req).await?.json::<Data<ChatEmote>>()?;
+        Ok(res.data)
+    }
+
+    /// Get the custom chat badges of the given broadcaster, like subscriber
+    /// badges.
+    pub async fn channel_chat_badges(&self, broadcaster_id: &str) -> Result<Vec<ChatBadgeSet>> {
+        let req = self
+            .helix(Method::GET, &["chat", "badges"])
+            .query_param("broadcaster_id", broadcaster_id);
+
+        let res = self.execute(&req).await?.json::<Data<ChatBadgeSet>>()?;
+        Ok(res.data)
+    }
+
+    /// Get the global chat badges, like moderator and broadcaster badges.
+    pub async fn global_chat_badges(&self) -> Result<Vec<ChatBadgeSet>> {
+        let req = self.helix(Method::GET, &["chat", "badges", "global"]);
+        let res = self.execute(&req).await?.json::<Data<ChatBadgeSet>>()?;
+        Ok(res.data)
     }
 
     /// Get chatters for the given channel using TMI.
@@ -245,28 +468,29 @@ impl Twitch {
             .context("validate token error")?)
     }
 
-    /// Get badge URLs for the specified channel.
-    pub async fn badges_display(&self, channel_id: &str) -> Result<Option<BadgesDisplay>> {
-        let req = self.badges_v1(Method::GET, &["badges", "channels", &channel_id, "display"]);
+    /// Revoke the specified token through twitch revocation API.
+    pub async fn revoke_token(&self) -> Result<()> {
+        let (client_id, access_token) = {
+            let token = self.token.read().await?;
+            (
+                token.client_id().to_string(),
+                token.access_token().to_string(),
+            )
+        };
 
-        Ok(req
-            .execute()
-            .await?
-            .not_found()
-            .json()
-            .context("request badges")?)
-    }
+        let mut url = self.id_url.clone();
 
-    /// Get all badge URLs for the given chat.
-    pub async fn chat_badges(&self, channel_id: &str) -> Result<Option<ChatBadges>> {
-        let req = self.v5(Method::GET, &["chat", &channel_id, "badges"]);
+        url.path_segments_mut()
+            .expect("bad base")
+            .extend(&["oauth2", "revoke"]);
 
-        Ok(req
+        RequestBuilder::new(self.client.clone(), Method::POST, url)
+            .query_param("client_id", &client_id)
+            .query_param("token", &access_token)
             .execute()
             .await?
-            .not_found()
-            .json()
-            .context("request chat badges")?)
+            .ok()
+            .context("revoke token error")
     }
 
     /// Get display badges through GQL.
@@ -294,8 +518,28 @@ impl Twitch {
     }
 }
 
+/// Execute a Helix request, waiting for the rate limit to allow it and
+/// retrying it if we were rate limited anyway.
+async fn execute(ratelimit: &RateLimit, req: &RequestBuilder) -> Result<Response<Bytes>> {
+    for _ in 0..RATELIMIT_RETRIES {
+        ratelimit.wait().await;
+
+        let res = req.execute().await?;
+        ratelimit.update(res.headers());
+
+        if res.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(res);
+        }
+
+        log::warn!("Rate limited by Twitch, retrying");
+    }
+
+    bail!("rate limited by Twitch")
+}
+
 /// A response that is paged as a stream of requests.
 pub struct Paged<T> {
+    ratelimit: RateLimit,
     request: RequestBuilder,
     page: Option<future::BoxFuture<'static, Result<Page<T>>>>,
 }
@@ -328,8 +572,11 @@ where
 
                             if let Some(cursor) = pagination.and_then(|p| p.cursor) {
                                 let req = self.request.clone().query_param("after", &cursor);
-                                self.as_mut().page =
-                                    Some(async move { req.execute().await?.json() }.boxed());
+                                let ratelimit = self.ratelimit.clone();
+
+                                self.as_mut().page = Some(
+                                    async move { execute(&ratelimit, &req).await?.json() }.boxed(),
+                                );
                             }
 
                             return Poll::Ready(Some(Ok(data)));
@@ -343,27 +590,24 @@ where
     }
 }
 
+/// Request to modify channel information. Fields which are not set are left
+/// unchanged.
 #[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
-pub struct UpdateChannelRequest {
-    pub channel: UpdateChannel,
-}
-
-#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
-pub struct UpdateChannel {
+pub struct ModifyChannelRequest {
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub status: Option<String>,
+    pub title: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub game: Option<String>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub delay: Option<String>,
+    pub game_id: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub channel_feed_enabled: Option<bool>,
+    pub broadcaster_language: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-pub struct NewUser {
+pub struct User {
     pub id: String,
-    pub login: String,
+    /// The login of the user.
+    #[serde(rename = "login")]
+    pub name: String,
     pub display_name: String,
     #[serde(rename = "type")]
     pub ty: String,
@@ -371,30 +615,10 @@ pub struct NewUser {
     pub description: String,
     pub profile_image_url: String,
     pub offline_image_url: String,
-    pub view_count: u64,
     #[serde(default)]
-    pub email: Option<String>,
-}
-
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-pub struct User {
-    #[serde(rename = "_id")]
-    pub id: String,
-    pub name: String,
-    pub display_name: String,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub created_at: Option<DateTime<Utc>>,
     #[serde(default)]
-    pub bio: Option<String>,
-    pub email: String,
-    pub email_verified: bool,
-    #[serde(default)]
-    pub logo: Option<String>,
-    pub notifications: HashMap<String, bool>,
-    pub partnered: bool,
-    pub twitter_connected: bool,
-    #[serde(rename = "type")]
-    pub ty: String,
+    pub email: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -409,9 +633,12 @@ pub struct Subscription {
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-pub struct StreamInfo {
-    pub started_at: DateTime<Utc>,
-    pub title: String,
+pub struct Follow {
+    pub from_id: String,
+    pub from_name: String,
+    pub to_id: String,
+    pub to_name: String,
+    pub followed_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -422,7 +649,7 @@ pub struct Stream {
     #[serde(default)]
     pub game_id: Option<String>,
     #[serde(default)]
-    pub community_ids: Vec<String>,
+    pub game_name: Option<String>,
     #[serde(rename = "type")]
     pub ty: String,
     pub title: String,
@@ -434,39 +661,50 @@ pub struct Stream {
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Channel {
-    pub mature: bool,
-    pub status: Option<String>,
+    #[serde(rename = "broadcaster_id")]
+    pub id: String,
+    /// The login of the broadcaster.
+    #[serde(rename = "broadcaster_login")]
+    pub name: String,
+    #[serde(rename = "broadcaster_name")]
+    pub display_name: String,
     #[serde(default)]
     pub broadcaster_language: Option<String>,
-    #[serde(default)]
-    pub display_name: Option<String>,
-    #[serde(default)]
-    pub game: Option<String>,
-    #[serde(default)]
-    pub language: Option<String>,
-    #[serde(rename = "_id")]
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub game_id: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub game_name: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub title: Option<String>,
+}
+
+impl Channel {
+    /// The URL of the channel.
+    pub fn url(&self) -> String {
+        format!("https://twitch.tv/{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Game {
     pub id: String,
     pub name: String,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-    pub partner: bool,
-    #[serde(default)]
-    pub logo: Option<String>,
+    /// Template URL for the box art of the game, with `{width}` and `{height}`
+    /// placeholders.
     #[serde(default)]
-    pub video_banner: Option<String>,
-    #[serde(default)]
-    pub profile_banner: Option<String>,
-    #[serde(default)]
-    pub profile_banner_background_color: Option<String>,
-    pub url: String,
-    pub views: u64,
-    pub followers: u64,
-    #[serde(default)]
-    pub broadcaster_type: Option<String>,
-    #[serde(default)]
-    pub stream_key: Option<String>,
-    #[serde(default)]
-    pub email: Option<String>,
+    pub box_art_url: Option<String>,
+}
+
+impl Game {
+    /// The URL of the box art of the game with the given dimensions.
+    pub fn box_art(&self, width: u32, height: u32) -> Option<String> {
+        let url = self.box_art_url.as_ref()?;
+
+        Some(
+            url.replace("{width}", &width.to_string())
+                .replace("{height}", &height.to_string()),
+        )
+    }
 }
 
 #[derive(serde::Deserialize)]
@@ -486,6 +724,97 @@ pub struct Clip {
     pub edit_url: String,
 }
 
+#[derive(serde::Serialize)]
+struct CreateStreamMarker<'a> {
+    user_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct StreamMarker {
+    pub id: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Position of the marker in the stream, in seconds.
+    pub position_seconds: u64,
+}
+
+#[derive(serde::Serialize)]
+struct StartCommercial<'a> {
+    broadcaster_id: &'a str,
+    length: u32,
+}
+
+/// The color used to highlight an announcement in chat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnouncementColor {
+    /// The accent color of the channel.
+    Primary,
+    Blue,
+    Green,
+    Orange,
+    Purple,
+}
+
+#[derive(serde::Serialize)]
+struct SendChatAnnouncement<'a> {
+    message: &'a str,
+    color: AnnouncementColor,
+}
+
+#[derive(serde::Serialize)]
+struct CreatePoll<'a> {
+    broadcaster_id: &'a str,
+    title: &'a str,
+    choices: Vec<CreatePollChoice<'a>>,
+    duration: u32,
+}
+
+#[derive(serde::Serialize)]
+struct CreatePollChoice<'a> {
+    title: &'a str,
+}
+
+#[derive(serde::Serialize)]
+struct EndPoll<'a> {
+    broadcaster_id: &'a str,
+    id: &'a str,
+    status: &'static str,
+}
+
+/// A native Twitch poll.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Poll {
+    pub id: String,
+    pub title: String,
+    pub choices: Vec<PollChoice>,
+    /// The status of the poll, like `ACTIVE` or `COMPLETED`.
+    pub status: String,
+}
+
+/// A choice in a native Twitch poll.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PollChoice {
+    pub id: String,
+    pub title: String,
+    /// Total number of votes for the choice.
+    #[serde(default)]
+    pub votes: u32,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Commercial {
+    /// Length of the commercial that was started, in seconds.
+    pub length: u32,
+    #[serde(default)]
+    pub message: String,
+    /// Seconds until the next commercial can be started.
+    pub retry_after: u32,
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Pagination {
     #[serde(default)]
@@ -504,6 +833,92 @@ pub struct Data<T> {
     pub data: Vec<T>,
 }
 
+/// Build the URL used to authorize a user with Twitch, so that they can log in
+/// through the authorization code flow.
+pub fn login_url(client_id: &str, redirect_uri: &str, state: &str) -> Result<Url> {
+    let mut url = str::parse::<Url>(ID_TWITCH_URL)?;
+
+    url.path_segments_mut()
+        .expect("bad base")
+        .extend(&["oauth2", "authorize"]);
+
+    url.query_pairs_mut()
+        .append_pair("client_id", client_id)
+        .append_pair("redirect_uri", redirect_uri)
+        .append_pair("response_type", "code")
+        .append_pair("scope", "")
+        .append_pair("state", state);
+
+    Ok(url)
+}
+
+/// Complete a login by exchanging the authorization code for a token and
+/// validating it, which tells us who the user is.
+///
+/// The token is only used to identify the user, so it is revoked right away.
+pub async fn login(
+    client_id: &str,
+    client_secret: &str,
+    redirect_uri: &str,
+    code: &str,
+) -> Result<ValidateToken> {
+    #[derive(serde::Deserialize)]
+    struct TokenResponse {
+        access_token: String,
+    }
+
+    let client = Client::new();
+    let mut url = str::parse::<Url>(ID_TWITCH_URL)?;
+
+    url.path_segments_mut()
+        .expect("bad base")
+        .extend(&["oauth2", "token"]);
+
+    let token: TokenResponse = RequestBuilder::new(client.clone(), Method::POST, url.clone())
+        .query_param("client_id", client_id)
+        .query_param("client_secret", client_secret)
+        .query_param("code", code)
+        .query_param("grant_type", "authorization_code")
+        .query_param("redirect_uri", redirect_uri)
+        .execute()
+        .await?
+        .json()
+        .context("exchange code error")?;
+
+    url.path_segments_mut()
+        .expect("bad base")
+        .pop()
+        .push("validate");
+
+    let validated = RequestBuilder::new(client.clone(), Method::GET, url.clone())
+        .header(
+            header::AUTHORIZATION,
+            &format!("OAuth {}", token.access_token),
+        )
+        .execute()
+        .await?
+        .json()
+        .context("validate token error")?;
+
+    url.path_segments_mut()
+        .expect("bad base")
+        .pop()
+        .push("revoke");
+
+    let result = RequestBuilder::new(client, Method::POST, url)
+        .query_param("client_id", client_id)
+        .query_param("token", &token.access_token)
+        .execute()
+        .await
+        .and_then(|r| r.ok());
+
+    if let Err(e) = result {
+        log_warn!(e, "failed to revoke login token");
+    }
+
+    Ok(validated)
+}
+
 /// Response from the validate token endpoint.
 #[derive(Debug, serde::Deserialize)]
 pub struct ValidateToken {
@@ -514,51 +929,64 @@ pub struct ValidateToken {
 }
 
 #[derive(Debug, serde::Deserialize)]
-pub struct Emote {
-    pub code: String,
-    pub id: u64,
-}
-
-#[derive(Debug, serde::Deserialize)]
-pub struct EmoticonSets {
-    pub emoticon_sets: HashMap<String, Vec<Emote>>,
+pub struct ChatEmote {
+    pub id: String,
+    /// The code used to insert the emote in chat.
+    pub name: String,
+    /// The formats the emote is available in, like `static` or `animated`.
+    #[serde(default)]
+    pub format: Vec<String>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-pub struct Badge {
+pub struct ChatBadge {
+    pub id: String,
     pub image_url_1x: String,
     pub image_url_2x: String,
     pub image_url_4x: String,
-    pub description: String,
-    pub title: String,
-    pub click_action: String,
-    pub click_url: String,
     #[serde(default)]
-    pub last_updated: Option<serde_json::Value>,
+    pub title: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-pub struct BadgeSet {
-    pub versions: HashMap<String, Badge>,
+pub struct ChatBadgeSet {
+    pub set_id: String,
+    pub versions: Vec<ChatBadge>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-pub struct BadgesDisplay {
-    pub badge_sets: HashMap<String, BadgeSet>,
-}
+/// Treat empty strings as absent, which is how Helix reports unset fields.
+fn empty_as_none<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize as _;
 
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-pub struct BadgeTypes {
-    #[serde(default)]
-    pub alpha: Option<String>,
-    #[serde(default)]
-    pub image: Option<String>,
-    #[serde(default)]
-    pub svg: Option<String>,
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.filter(|s| !s.is_empty()))
 }
 
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-pub struct ChatBadges {
-    #[serde(flatten)]
-    pub badges: HashMap<String, BadgeTypes>,
+#[cfg(test)]
+mod tests {
+    use super::Channel;
+
+    #[test]
+    fn test_channel_empty_fields() {
+        let channel = serde_json::from_str::<Channel>(
+            r#"{
+                "broadcaster_id": "141981764",
+                "broadcaster_login": "twitchdev",
+                "broadcaster_name": "TwitchDev",
+                "broadcaster_language": "en",
+                "game_id": "",
+                "game_name": "",
+                "title": "TwitchDev Monthly Update"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!("twitchdev", channel.name);
+        assert_eq!(None, channel.game_name);
+        assert_eq!(Some("TwitchDev Monthly Update"), channel.title.as_deref());
+        assert_eq!("https://twitch.tv/twitchdev", channel.url());
+    }
 }
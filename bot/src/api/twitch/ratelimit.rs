@@ -0,0 +1,137 @@
+//! Rate limiting based on the `Ratelimit-*` headers reported by Helix.
+//!
+//! Twitch uses a token bucket which is refilled at the time indicated by
+//! `Ratelimit-Reset`. Once `Ratelimit-Remaining` reaches zero, requests are
+//! held back until the bucket has been refilled.
+
+use parking_lot::Mutex;
+use reqwest::header;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const RATELIMIT_REMAINING: &str = "ratelimit-remaining";
+const RATELIMIT_RESET: &str = "ratelimit-reset";
+
+/// Shared rate limit state for a single token.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimit {
+    state: Arc<Mutex<State>>,
+}
+
+impl RateLimit {
+    /// Wait until we are allowed to send another request.
+    pub async fn wait(&self) {
+        loop {
+            let delay = self.state.lock().reserve(now());
+
+            match delay {
+                Some(delay) => {
+                    log::trace!("Twitch rate limit reached, waiting {:?}", delay);
+                    tokio::time::delay_for(delay).await;
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Update the rate limit state from the headers of a response.
+    pub fn update(&self, headers: &header::HeaderMap) {
+        let remaining = parse_header(headers, RATELIMIT_REMAINING);
+        let reset = parse_header(headers, RATELIMIT_RESET);
+
+        if let (Some(remaining), Some(reset)) = (remaining, reset) {
+            self.state.lock().update(remaining, reset);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Number of requests remaining until the bucket is refilled.
+    remaining: Option<u64>,
+    /// When the bucket is refilled, in seconds since the unix epoch.
+    reset: Option<u64>,
+}
+
+impl State {
+    /// Reserve a request, returning how long to wait before trying again if
+    /// the bucket is empty.
+    fn reserve(&mut self, now: u64) -> Option<Duration> {
+        if let Some(reset) = self.reset {
+            if reset <= now {
+                self.remaining = None;
+                self.reset = None;
+            }
+        }
+
+        match (self.remaining, self.reset) {
+            (Some(0), Some(reset)) => Some(Duration::from_secs(reset - now)),
+            (Some(remaining), _) => {
+                self.remaining = Some(remaining.saturating_sub(1));
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Update the state with the values reported by Twitch.
+    fn update(&mut self, remaining: u64, reset: u64) {
+        // NB: responses might arrive out of order, so only ever lower the
+        // number of remaining requests within the same window.
+        if self.reset == Some(reset) {
+            if let Some(current) = self.remaining {
+                if current < remaining {
+                    return;
+                }
+            }
+        }
+
+        self.remaining = Some(remaining);
+        self.reset = Some(reset);
+    }
+}
+
+/// Parse a numeric header.
+fn parse_header(headers: &header::HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// The current time in seconds since the unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::State;
+    use std::time::Duration;
+
+    #[test]
+    fn test_reserve() {
+        let mut state = State::default();
+        assert_eq!(None, state.reserve(100));
+
+        state.update(1, 110);
+        assert_eq!(None, state.reserve(100));
+        assert_eq!(Some(Duration::from_secs(10)), state.reserve(100));
+        assert_eq!(Some(Duration::from_secs(5)), state.reserve(105));
+
+        // bucket is refilled.
+        assert_eq!(None, state.reserve(110));
+        assert_eq!(None, state.remaining);
+    }
+
+    #[test]
+    fn test_update_out_of_order() {
+        let mut state = State::default();
+        state.update(5, 110);
+        state.update(7, 110);
+        assert_eq!(Some(5), state.remaining);
+
+        state.update(800, 170);
+        assert_eq!(Some(800), state.remaining);
+    }
+}
@@ -0,0 +1,213 @@
+//! Twitter API integration.
+//!
+//! Requests are signed with OAuth 1.0a, using the keys and the access token of
+//! an app created in the Twitter developer portal.
+
+use crate::api::RequestBuilder;
+use anyhow::Result;
+use chrono::Utc;
+use hmac::{Hmac, Mac as _};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::distributions::Alphanumeric;
+use rand::Rng as _;
+use reqwest::{header, Client, Method, Url};
+use sha1::Sha1;
+use std::iter;
+
+const API_URL: &str = "https://api.twitter.com/2";
+
+/// Characters which are encoded by OAuth 1.0a. Everything except the
+/// unreserved characters in RFC 3986.
+const OAUTH_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Credentials used to sign requests.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub consumer_key: String,
+    pub consumer_secret: String,
+    pub access_token: String,
+    pub access_token_secret: String,
+}
+
+/// API integration.
+#[derive(Clone, Debug)]
+pub struct Twitter {
+    client: Client,
+    api_url: Url,
+}
+
+impl Twitter {
+    /// Create a new API integration.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            api_url: str::parse::<Url>(API_URL)?,
+        })
+    }
+
+    /// Post a tweet with the given text.
+    pub async fn tweet(&self, credentials: &Credentials, text: &str) -> Result<()> {
+        let mut url = self.api_url.clone();
+        url.path_segments_mut().expect("bad base").push("tweets");
+
+        let nonce = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .collect::<String>();
+
+        // NB: JSON bodies are not part of the signature.
+        let authorization = authorization(
+            credentials,
+            &Method::POST,
+            &url,
+            &[],
+            &nonce,
+            Utc::now().timestamp(),
+        );
+
+        let body = serde_json::to_vec(&Tweet { text })?;
+
+        let req = RequestBuilder::new(self.client.clone(), Method::POST, url)
+            .header(header::AUTHORIZATION, &authorization)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+
+        req.execute().await?.ok()
+    }
+}
+
+#[derive(serde::Serialize)]
+struct Tweet<'a> {
+    text: &'a str,
+}
+
+/// Build the OAuth 1.0a `Authorization` header for a request.
+///
+/// `params` are the query and form parameters of the request, which are
+/// included in the signature.
+fn authorization(
+    credentials: &Credentials,
+    method: &Method,
+    url: &Url,
+    params: &[(&str, &str)],
+    nonce: &str,
+    timestamp: i64,
+) -> String {
+    let timestamp = timestamp.to_string();
+
+    let oauth = [
+        ("oauth_consumer_key", credentials.consumer_key.as_str()),
+        ("oauth_nonce", nonce),
+        ("oauth_signature_method", "HMAC-SHA1"),
+        ("oauth_timestamp", timestamp.as_str()),
+        ("oauth_token", credentials.access_token.as_str()),
+        ("oauth_version", "1.0"),
+    ];
+
+    let signature = signature(
+        credentials,
+        method,
+        url,
+        oauth.iter().chain(params.iter()).copied(),
+    );
+
+    let header = oauth
+        .iter()
+        .copied()
+        .chain(iter::once(("oauth_signature", signature.as_str())))
+        .map(|(key, value)| format!("{}=\"{}\"", encode(key), encode(value)))
+        .collect::<Vec<_>>();
+
+    format!("OAuth {}", header.join(", "))
+}
+
+/// Calculate the HMAC-SHA1 signature of a request.
+fn signature<'a>(
+    credentials: &Credentials,
+    method: &Method,
+    url: &Url,
+    params: impl Iterator<Item = (&'a str, &'a str)>,
+) -> String {
+    let mut params = params
+        .map(|(key, value)| (encode(key), encode(value)))
+        .collect::<Vec<_>>();
+
+    params.sort();
+
+    let params = params
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>();
+
+    let base = format!(
+        "{}&{}&{}",
+        method,
+        encode(url.as_str()),
+        encode(&params.join("&"))
+    );
+
+    let key = format!(
+        "{}&{}",
+        encode(&credentials.consumer_secret),
+        encode(&credentials.access_token_secret)
+    );
+
+    let mut mac = Hmac::<Sha1>::new_varkey(key.as_bytes()).expect("keys can be any size");
+    mac.input(base.as_bytes());
+    base64::encode(mac.result().code())
+}
+
+/// Percent-encode a string the way OAuth 1.0a expects.
+fn encode(s: &str) -> String {
+    utf8_percent_encode(s, OAUTH_ENCODE).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authorization, encode, Credentials};
+    use reqwest::{Method, Url};
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(
+            "Ladies%20%2B%20Gentlemen%2C%20a-b.c_d~e%21",
+            encode("Ladies + Gentlemen, a-b.c_d~e!")
+        );
+    }
+
+    #[test]
+    fn test_authorization() {
+        // Example from the "Creating a signature" guide in the Twitter
+        // developer documentation.
+        let credentials = Credentials {
+            consumer_key: String::from("xvz1evFS4wEEPTGEFPHBog"),
+            consumer_secret: String::from("kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw"),
+            access_token: String::from("370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb"),
+            access_token_secret: String::from("LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE"),
+        };
+
+        let url = str::parse::<Url>("https://api.twitter.com/1.1/statuses/update.json").unwrap();
+
+        let header = authorization(
+            &credentials,
+            &Method::POST,
+            &url,
+            &[
+                (
+                    "status",
+                    "Hello Ladies + Gentlemen, a signed OAuth request!",
+                ),
+                ("include_entities", "true"),
+            ],
+            "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg",
+            1318622958,
+        );
+
+        assert!(header.starts_with("OAuth oauth_consumer_key=\"xvz1evFS4wEEPTGEFPHBog\", "));
+        assert!(header.ends_with(", oauth_signature=\"hCtSmYh%2BiHYCEqBWrE7C7hYmtUk%3D\""));
+    }
+}
@@ -1,6 +1,6 @@
 //! Twitch API helpers.
 
-use crate::api::RequestBuilder;
+use crate::api::{RequestBuilder, Service};
 use crate::oauth2;
 use anyhow::{bail, Result};
 use chrono::{DateTime, Utc};
@@ -39,7 +39,9 @@ impl YouTube {
             url_path.extend(path);
         }
 
-        RequestBuilder::new(self.client.clone(), method, url).token(self.token.clone())
+        RequestBuilder::new(self.client.clone(), method, url)
+            .token(self.token.clone())
+            .service(Service::YouTube)
     }
 
     /// Update the channel information.
@@ -76,7 +78,8 @@ impl YouTube {
         url.query_pairs_mut()
             .append_pair("video_id", video_id.as_str());
 
-        let req = RequestBuilder::new(self.client.clone(), Method::GET, url);
+        let req =
+            RequestBuilder::new(self.client.clone(), Method::GET, url).service(Service::YouTube);
         let body = req.execute().await?.not_found().body()?;
 
         let body = match body {
@@ -194,6 +197,26 @@ pub struct ContentDetails {
     pub licensed_content: bool,
     #[serde(default)]
     pub projection: Option<String>,
+    #[serde(default)]
+    pub content_rating: Option<ContentRating>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentRating {
+    #[serde(default)]
+    pub yt_rating: Option<String>,
+}
+
+impl ContentDetails {
+    /// Test if the video is age restricted.
+    pub fn is_age_restricted(&self) -> bool {
+        self.content_rating
+            .as_ref()
+            .and_then(|r| r.yt_rating.as_deref())
+            .map(|r| r == "ytAgeRestricted")
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -1,6 +1,6 @@
 use crate::db;
 use crate::utils::{Cooldown, Duration};
-use anyhow::{Context as _, Error};
+use anyhow::{bail, Context as _, Error};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use std::collections::{HashMap, HashSet};
@@ -11,6 +11,18 @@ use tokio::sync::RwLock;
 
 const SCHEMA: &[u8] = include_bytes!("auth.yaml");
 
+/// Roles which make up the role hierarchy, from least to most privileged.
+///
+/// Used when granting a scope to a minimum role.
+const HIERARCHY: &[Role] = &[
+    Role::Everyone,
+    Role::Regular,
+    Role::Subscriber,
+    Role::Vip,
+    Role::Moderator,
+    Role::Streamer,
+];
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct Schema {
     roles: HashMap<Role, RoleData>,
@@ -77,20 +89,31 @@ pub struct Auth {
     pub grants: Arc<RwLock<HashSet<(Scope, Role)>>>,
     /// Temporary grants.
     temporary_grants: Arc<RwLock<Vec<TemporaryGrant>>>,
+    /// Scopes which have been explicitly allowed (`true`) or denied (`false`)
+    /// for specific users.
+    user_grants: Arc<RwLock<HashMap<(Scope, String), bool>>>,
 }
 
 impl Auth {
     pub async fn new(db: db::Database, schema: Schema) -> Result<Self, Error> {
-        use db::schema::grants::dsl;
+        use db::schema::{grants, user_grants};
 
-        let grants = db
+        let (grants, user_grants) = db
             .asyncify(move |c| {
-                let grants = dsl::grants
-                    .select((dsl::scope, dsl::role))
+                let grants = grants::table
+                    .select((grants::scope, grants::role))
                     .load::<(Scope, Role)>(c)?
                     .into_iter()
                     .collect::<HashSet<_>>();
-                Ok::<_, Error>(grants)
+
+                let user_grants = user_grants::table
+                    .select((user_grants::scope, user_grants::user, user_grants::allow))
+                    .load::<(Scope, String, bool)>(c)?
+                    .into_iter()
+                    .map(|(scope, user, allow)| ((scope, user), allow))
+                    .collect::<HashMap<_, _>>();
+
+                Ok::<_, Error>((grants, user_grants))
             })
             .await?;
 
@@ -99,6 +122,7 @@ impl Auth {
             schema: Arc::new(schema),
             grants: Arc::new(RwLock::new(grants)),
             temporary_grants: Default::default(),
+            user_grants: Arc::new(RwLock::new(user_grants)),
         };
 
         // perform default initialization based on auth.yaml
@@ -121,11 +145,23 @@ impl Auth {
         out
     }
 
-    /// Return all temporary scopes belonging to the specified user.
+    /// Return all scopes which have been granted to the specified user,
+    /// temporarily or not.
     pub async fn scopes_for_user(&self, user: &str) -> Vec<Scope> {
         let now = Utc::now();
-        self.temporary_scopes(&now, RoleOrUser::User(user.to_string()))
-            .await
+        let mut out = self
+            .temporary_scopes(&now, RoleOrUser::User(user.to_string()))
+            .await;
+
+        let user_grants = self.user_grants.read().await;
+
+        for ((scope, u), allow) in user_grants.iter() {
+            if *allow && u == user {
+                out.push(*scope);
+            }
+        }
+
+        out
     }
 
     /// Return all temporary scopes belonging to the specified user.
@@ -259,6 +295,119 @@ impl Auth {
         Ok(())
     }
 
+    /// Explicitly allow or deny the given scope for a specific user.
+    ///
+    /// This takes precedence over any grants given to the roles of the user.
+    pub async fn insert_user(&self, scope: Scope, user: &str, allow: bool) -> Result<(), Error> {
+        use db::schema::user_grants::dsl;
+
+        let user = user.to_string();
+
+        {
+            let user = user.clone();
+
+            self.db
+                .asyncify(move |c| {
+                    c.transaction(|| {
+                        diesel::delete(
+                            dsl::user_grants.filter(dsl::scope.eq(scope).and(dsl::user.eq(&user))),
+                        )
+                        .execute(c)?;
+
+                        diesel::insert_into(dsl::user_grants)
+                            .values((
+                                dsl::scope.eq(scope),
+                                dsl::user.eq(&user),
+                                dsl::allow.eq(allow),
+                            ))
+                            .execute(c)?;
+
+                        Ok::<_, Error>(())
+                    })
+                })
+                .await?;
+        }
+
+        self.user_grants.write().await.insert((scope, user), allow);
+        Ok(())
+    }
+
+    /// Delete an explicit grant for a specific user.
+    ///
+    /// Returns `true` if there was a grant to delete.
+    pub async fn delete_user(&self, scope: Scope, user: &str) -> Result<bool, Error> {
+        use db::schema::user_grants::dsl;
+
+        let user = user.to_string();
+
+        if self
+            .user_grants
+            .write()
+            .await
+            .remove(&(scope, user.clone()))
+            .is_none()
+        {
+            return Ok(false);
+        }
+
+        self.db
+            .asyncify(move |c| {
+                let _ = diesel::delete(
+                    dsl::user_grants.filter(dsl::scope.eq(scope).and(dsl::user.eq(user))),
+                )
+                .execute(c)?;
+                Ok::<_, Error>(())
+            })
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Forget all explicit grants for the given user which are in memory.
+    ///
+    /// They are removed from the database by [db::Database::forget_user].
+    pub async fn forget_user(&self, user: &str) {
+        self.user_grants.write().await.retain(|(_, u), _| u != user);
+    }
+
+    /// Test if the given scope has been explicitly allowed or denied for the
+    /// given user.
+    async fn test_user(&self, scope: Scope, user: &str) -> Option<bool> {
+        self.user_grants
+            .read()
+            .await
+            .get(&(scope, user.to_string()))
+            .copied()
+    }
+
+    /// Grant the given scope to a minimum role.
+    ///
+    /// The scope is granted to the role and every role above it in the role
+    /// hierarchy, and revoked from every role below it. If no role is
+    /// specified, the scope is revoked from every role in the hierarchy.
+    pub async fn set_minimum_role(&self, scope: Scope, role: Option<Role>) -> Result<(), Error> {
+        let rank = match role {
+            Some(role) => match role.rank() {
+                Some(rank) => rank,
+                None => bail!("{} is not part of the role hierarchy", role),
+            },
+            None => HIERARCHY.len(),
+        };
+
+        for (n, role) in HIERARCHY.iter().copied().enumerate() {
+            if n < rank {
+                self.delete(scope, role).await?;
+                continue;
+            }
+
+            if !self.grants.read().await.contains(&(scope, role)) {
+                self.insert(scope, role).await?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Test if there are any temporary grants matching the given user or role.
     async fn test_temporary(
         &self,
@@ -296,6 +445,10 @@ impl Auth {
 
     /// Test if the given assignment exists.
     pub async fn test(&self, scope: Scope, user: &str, role: Role) -> bool {
+        if let Some(allow) = self.test_user(scope, user).await {
+            return allow;
+        }
+
         if self.grants.read().await.contains(&(scope, role)) {
             return true;
         }
@@ -318,6 +471,21 @@ impl Auth {
         granted
     }
 
+    /// Test if the given role has been granted the given scope.
+    pub async fn test_role(&self, scope: Scope, role: Role) -> bool {
+        if self.grants.read().await.contains(&(scope, role)) {
+            return true;
+        }
+
+        let now = Utc::now();
+
+        let (granted, _) = self
+            .test_temporary(&now, scope, iter::once(RoleOrUser::Role(role)))
+            .await;
+
+        granted
+    }
+
     /// Test if the given assignment exists.
     pub async fn test_any(
         &self,
@@ -325,6 +493,10 @@ impl Auth {
         user: &str,
         roles: impl IntoIterator<Item = Role>,
     ) -> bool {
+        if let Some(allow) = self.test_user(scope, user).await {
+            return allow;
+        }
+
         let roles = roles.into_iter().collect::<HashSet<_>>();
 
         {
@@ -384,6 +556,7 @@ impl Auth {
 
             out.push(RoleInfo {
                 role,
+                rank: role.rank(),
                 data: data.clone(),
             });
         }
@@ -569,43 +742,100 @@ impl Default for Risk {
 scopes! {
     (BypassCooldowns, "bypass-cooldowns"),
     (PlayerDetachDetach, "player/attach-detach"),
+    (PlayerRead, "player/read"),
+    (PlayerControl, "player/control"),
+    (WebTokens, "web/tokens"),
+    (WebBundles, "web/bundles"),
+    (WebErrors, "web/errors"),
+    (WebNotifications, "web/notifications"),
+    (WebEdit, "web/edit"),
+    (WebSettings, "web/settings"),
+    (WebAfterStreams, "web/after-streams"),
+    (WebSessions, "web/sessions"),
+    (WebDonations, "web/donations"),
+    (WebTrivia, "web/trivia"),
+    (WebViewerQueue, "web/viewer-queue"),
+    (WebEightBall, "web/8ball"),
+    (WebChatSend, "web/chat/send"),
+    (WebChatLog, "web/chat/log"),
+    (WebModeration, "web/moderation"),
+    (WebCommandRun, "web/command/run"),
     (Admin, "admin"),
     (Song, "song"),
     (SongYouTube, "song/youtube"),
     (SongSpotify, "song/spotify"),
+    (SongBandcamp, "song/bandcamp"),
     (SongBypassConstraints, "song/bypass-constraints"),
     (SongTheme, "song/theme"),
     (SongEditQueue, "song/edit-queue"),
+    (SongBan, "song/ban"),
     (SongListLimit, "song/list-limit"),
     (SongVolume, "song/volume"),
     (SongPlaybackControl, "song/playback-control"),
+    (SongSkip, "song/skip"),
+    (SongVoteSkip, "song/voteskip"),
+    (SongPlaylist, "song/playlist"),
     (SwearJar, "swearjar"),
+    (Duel, "duel"),
+    (Heist, "heist"),
+    (Counter, "counter"),
+    (CounterEdit, "counter/edit"),
+    (Var, "var"),
+    (VarEdit, "var/edit"),
+    (Trivia, "trivia"),
+    (TriviaEdit, "trivia/edit"),
+    (ViewerQueue, "viewer-queue"),
+    (ViewerQueueEdit, "viewer-queue/edit"),
     (Uptime, "uptime"),
+    (Followage, "followage"),
+    (Schedule, "schedule"),
+    (AccountAge, "accountage"),
     (Game, "game"),
     (GameEdit, "game/edit"),
     (Title, "title"),
+    (Shoutout, "shoutout"),
     (TitleEdit, "title/edit"),
     (AfterStream, "afterstream"),
     (Clip, "clip"),
+    (Marker, "marker"),
+    (Commercial, "commercial"),
+    (PickWinner, "pickwinner"),
+    (TopDonor, "topdonor"),
+    (SubCount, "subcount"),
     (EightBall, "8ball"),
+    (EightBallEdit, "8ball/edit"),
+    (ChatStats, "chat-stats"),
+    (EmoteStats, "emote-stats"),
+    (ForgetMe, "forgetme"),
+    (Forget, "forget"),
+    (UserInfo, "user"),
+    (Greeting, "greeting"),
+    (Afk, "afk"),
     (Command, "command"),
     (CommandEdit, "command/edit"),
     (ThemeEdit, "theme/edit"),
     (PromoEdit, "promo/edit"),
+    (TriggerEdit, "trigger/edit"),
     (AliasEdit, "alias/edit"),
     (Countdown, "countdown"),
+    (Cooldown, "cooldown"),
+    (Obs, "obs"),
     (GtavBypassCooldown, "gtav/bypass-cooldown"),
     (GtavRaw, "gtav/raw"),
+    (InGame, "ingame"),
     (Speedrun, "speedrun"),
     (CurrencyShow, "currency/show"),
     (CurrencyBoost, "currency/boost"),
     (CurrencyWindfall, "currency/windfall"),
     (WaterUndo, "water/undo"),
+    (Reminders, "reminders"),
     (AuthPermit, "auth/permit"),
+    (AuthGrant, "auth/grant"),
     (ChatBypassUrlWhitelist, "chat/bypass-url-whitelist"),
     (Time, "time"),
     (Poll, "poll"),
     (Weather, "weather"),
+    (Tts, "tts"),
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -637,12 +867,16 @@ roles! {
     (Moderator, "@moderator"),
     (Subscriber, "@subscriber"),
     (Vip, "@vip"),
+    (Regular, "@regular"),
+    (Editor, "@editor"),
     (Everyone, "@everyone"),
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct RoleInfo {
     role: Role,
+    /// Rank of the role in the role hierarchy, if it's part of it.
+    rank: Option<usize>,
     #[serde(flatten)]
     data: RoleData,
 }
@@ -652,3 +886,13 @@ pub struct RoleData {
     /// Documentation for this role.
     pub doc: String,
 }
+
+impl Role {
+    /// Get the rank of the role in the role hierarchy, where a higher rank is
+    /// more privileged.
+    ///
+    /// Returns `None` if the role isn't part of the hierarchy.
+    pub fn rank(self) -> Option<usize> {
+        HIERARCHY.iter().position(|r| *r == self)
+    }
+}
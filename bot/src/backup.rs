@@ -0,0 +1,320 @@
+//! Scheduled backups of the database and storage.
+//!
+//! Every backup is a directory in the backups folder named after when it was
+//! taken, holding a copy of the database and an export of storage.
+
+use crate::db;
+use crate::prelude::*;
+use crate::storage::Storage;
+use crate::task;
+use crate::utils;
+use anyhow::{anyhow, bail, Context as _, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// How often we check if a backup is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 5);
+/// Format of the name of a backup directory.
+const NAME_FORMAT: &str = "%Y%m%d-%H%M%S";
+/// Name of the database file in a backup.
+const DATABASE: &str = "oxidize.sql";
+/// Name of the storage export in a backup.
+const STORAGE: &str = "storage.cbor";
+
+/// A backup which has been taken.
+#[derive(Debug, Clone)]
+pub struct Backup {
+    /// The name of the backup.
+    pub name: String,
+    /// The directory of the backup.
+    pub path: PathBuf,
+    /// When the backup was taken.
+    pub created_at: DateTime<Utc>,
+}
+
+impl Backup {
+    /// Parse a backup from its directory, if it is one.
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_string();
+        let created_at = parse_name(&name)?;
+
+        Some(Self {
+            name,
+            path,
+            created_at,
+        })
+    }
+}
+
+struct Inner {
+    dir: PathBuf,
+    db: db::Database,
+    storage: Storage,
+    enabled: settings::Var<bool>,
+    interval: settings::Var<utils::Duration>,
+    keep: settings::Var<u32>,
+    /// Held while a backup is being taken.
+    lock: Mutex<()>,
+}
+
+/// Backups of the database and storage.
+#[derive(Clone)]
+pub struct Backups {
+    inner: Arc<Inner>,
+}
+
+impl Backups {
+    /// Construct backups, stored in the given directory.
+    pub async fn new(
+        dir: PathBuf,
+        db: db::Database,
+        storage: Storage,
+        settings: settings::Settings,
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: Arc::new(Inner {
+                dir,
+                db,
+                storage,
+                enabled: settings.var("enabled", true).await?,
+                interval: settings.var("interval", utils::Duration::hours(24)).await?,
+                keep: settings.var("keep", 7).await?,
+                lock: Mutex::new(()),
+            }),
+        })
+    }
+
+    /// List all backups, oldest first.
+    pub async fn list(&self) -> Result<Vec<Backup>> {
+        let dir = self.inner.dir.clone();
+        task::asyncify(move || list(&dir)).await
+    }
+
+    /// Take a backup right now, removing old backups afterwards.
+    pub async fn backup(&self) -> Result<Backup> {
+        let _guard = self.inner.lock.lock().await;
+
+        let created_at = Utc::now();
+        let name = created_at.format(NAME_FORMAT).to_string();
+        let path = self.inner.dir.join(&name);
+        let partial = self.inner.dir.join(format!("{}.partial", name));
+
+        if path.exists() {
+            bail!("backup `{}` already exists", name);
+        }
+
+        {
+            let partial = partial.clone();
+            task::asyncify(move || {
+                if partial.exists() {
+                    fs::remove_dir_all(&partial)?;
+                }
+
+                fs::create_dir_all(&partial)?;
+                Ok::<_, anyhow::Error>(())
+            })
+            .await?;
+        }
+
+        if let Err(e) = self.write(&partial).await {
+            let _ = fs::remove_dir_all(&partial);
+            return Err(e);
+        }
+
+        fs::rename(&partial, &path)?;
+        log::info!("Backed up database and storage to: {}", path.display());
+
+        let keep = self.inner.keep.load().await.max(1) as usize;
+        let dir = self.inner.dir.clone();
+
+        let removed = task::asyncify(move || rotate(&dir, keep)).await?;
+
+        for backup in removed {
+            log::info!("Removed old backup: {}", backup.path.display());
+        }
+
+        Ok(Backup {
+            name,
+            path,
+            created_at,
+        })
+    }
+
+    /// Write the content of a backup to the given directory.
+    async fn write(&self, path: &Path) -> Result<()> {
+        let database = self
+            .inner
+            .db
+            .backup_to(&path.join(DATABASE))
+            .await
+            .context("failed to back up database")?;
+
+        if !database {
+            log::warn!("Database can't be backed up by the bot, use `pg_dump` to back it up");
+        }
+
+        let storage = self.inner.storage.clone();
+        let file = path.join(STORAGE);
+
+        task::asyncify(move || storage.export(&file))
+            .await
+            .context("failed to back up storage")?;
+
+        Ok(())
+    }
+
+    /// Run the backup loop, taking a backup whenever one is due.
+    ///
+    /// When a backup is due is based on the latest backup on disk, so that
+    /// restarting the bot doesn't delay backups.
+    pub async fn run(self) -> Result<()> {
+        let mut check = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            check.tick().await;
+
+            if !self.inner.enabled.load().await {
+                continue;
+            }
+
+            let interval = self.inner.interval.load().await;
+
+            let latest = match self.list().await {
+                Ok(backups) => backups.into_iter().last(),
+                Err(e) => {
+                    log_error!(e, "failed to list backups");
+                    continue;
+                }
+            };
+
+            if let Some(latest) = latest {
+                let elapsed = Utc::now().signed_duration_since(latest.created_at);
+
+                if elapsed < interval.as_chrono() {
+                    continue;
+                }
+            }
+
+            if let Err(e) = self.backup().await {
+                log_error!(e, "failed to take scheduled backup");
+            }
+        }
+    }
+}
+
+/// Restore the backup with the given name or path.
+///
+/// The current database and storage are kept next to the restored ones,
+/// with a suffix indicating when the restore happened. Backups taken while
+/// using PostgreSQL only hold storage, in which case only storage is
+/// restored. The `database` is `None` when using PostgreSQL, since an SQLite
+/// database can't be restored into it.
+pub fn restore(
+    backups: &Path,
+    backup: &Path,
+    database: Option<&Path>,
+    storage: &Path,
+) -> Result<()> {
+    let backup = if backup.is_dir() {
+        backup.to_owned()
+    } else {
+        backups.join(backup)
+    };
+
+    let backup_database = backup.join(DATABASE);
+    let backup_storage = backup.join(STORAGE);
+
+    if !backup_storage.is_file() {
+        bail!("not a backup: {}", backup.display());
+    }
+
+    let suffix = format!("before-restore-{}", Utc::now().format(NAME_FORMAT));
+
+    match database {
+        Some(database) if backup_database.is_file() => {
+            if database.is_file() {
+                let aside = PathBuf::from(format!("{}.{}", database.display(), suffix));
+                fs::rename(database, &aside)
+                    .with_context(|| anyhow!("failed to move database to: {}", aside.display()))?;
+                log::info!("Moved existing database to: {}", aside.display());
+            }
+
+            fs::copy(&backup_database, database).with_context(|| {
+                anyhow!("failed to restore database to: {}", database.display())
+            })?;
+        }
+        None if backup_database.is_file() => {
+            log::warn!("Can't restore an SQLite database into PostgreSQL, only restoring storage");
+        }
+        _ => {
+            log::warn!("Backup doesn't hold a database, only restoring storage");
+        }
+    }
+
+    Storage::restore(storage, &backup_storage, &suffix)?;
+
+    log::info!("Restored backup: {}", backup.display());
+    Ok(())
+}
+
+/// List all backups in the given directory, oldest first.
+fn list(dir: &Path) -> Result<Vec<Backup>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+
+    for e in fs::read_dir(dir)? {
+        let e = e?;
+
+        if !e.file_type()?.is_dir() {
+            continue;
+        }
+
+        if let Some(backup) = Backup::from_path(e.path()) {
+            backups.push(backup);
+        }
+    }
+
+    backups.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(backups)
+}
+
+/// Remove the oldest backups, so that only `keep` backups remain.
+fn rotate(dir: &Path, keep: usize) -> Result<Vec<Backup>> {
+    let backups = list(dir)?;
+    let remove = backups.len().saturating_sub(keep);
+    let removed = backups.into_iter().take(remove).collect::<Vec<_>>();
+
+    for backup in &removed {
+        fs::remove_dir_all(&backup.path)?;
+    }
+
+    Ok(removed)
+}
+
+/// Parse when a backup was taken from its name.
+fn parse_name(name: &str) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(name, NAME_FORMAT).ok()?;
+    Some(DateTime::from_utc(naive, Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_name;
+    use chrono::{TimeZone as _, Utc};
+
+    #[test]
+    fn test_parse_name() {
+        assert_eq!(
+            Some(Utc.ymd(2020, 11, 14).and_hms(18, 30, 5)),
+            parse_name("20201114-183005")
+        );
+        assert_eq!(None, parse_name("20201114-183005.partial"));
+        assert_eq!(None, parse_name("notes"));
+    }
+}
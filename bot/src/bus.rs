@@ -1,5 +1,9 @@
+use crate::auth::Role;
+use crate::countdowns;
+use crate::db;
 use crate::player;
 use crate::track_id::TrackId;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::broadcast;
@@ -89,6 +93,13 @@ pub enum YouTubeEvent {
         elapsed: u64,
         duration: u64,
     },
+    /// Play an audio file, like the stream of a Bandcamp track.
+    #[serde(rename = "play-audio")]
+    PlayAudio {
+        url: String,
+        elapsed: u64,
+        duration: u64,
+    },
     /// Pause the player.
     #[serde(rename = "pause")]
     Pause,
@@ -119,6 +130,40 @@ impl Message for YouTube {
     }
 }
 
+/// The kind of an alert raised in chat, or by a donation service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum AlertKind {
+    #[serde(rename = "sub")]
+    Sub,
+    #[serde(rename = "resub")]
+    Resub,
+    #[serde(rename = "subgift")]
+    SubGift,
+    #[serde(rename = "raid")]
+    Raid,
+    #[serde(rename = "follow")]
+    Follow,
+    #[serde(rename = "cheer")]
+    Cheer,
+    #[serde(rename = "donation")]
+    Donation,
+}
+
+impl AlertKind {
+    /// Get the name of the alert kind, as it's serialized.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlertKind::Sub => "sub",
+            AlertKind::Resub => "resub",
+            AlertKind::SubGift => "subgift",
+            AlertKind::Raid => "raid",
+            AlertKind::Follow => "follow",
+            AlertKind::Cheer => "cheer",
+            AlertKind::Donation => "donation",
+        }
+    }
+}
+
 /// Messages that go on the global bus.
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(tag = "type")]
@@ -145,6 +190,76 @@ pub enum Global {
     },
     #[serde(rename = "song/modified")]
     SongModified,
+    /// An alert was raised in chat, like a subscription or a raid.
+    #[serde(rename = "alert")]
+    Alert {
+        kind: AlertKind,
+        /// The user that raised the alert.
+        user: String,
+        /// Cumulative months subscribed.
+        months: Option<u32>,
+        /// Consecutive months subscribed, if shared by the subscriber.
+        streak: Option<u32>,
+        /// The recipient of a gifted subscription.
+        recipient: Option<String>,
+        /// The total number of subscriptions gifted by the user, if shared.
+        gifts: Option<u32>,
+        /// The number of viewers in a raid.
+        viewers: Option<u32>,
+        /// The number of bits cheered.
+        bits: Option<u32>,
+        /// The amount donated, including the currency.
+        amount: Option<String>,
+        /// The message which came with a donation.
+        message: Option<String>,
+    },
+    /// Rendered text of the current song overlay.
+    #[serde(rename = "overlay/current-song")]
+    OverlayCurrentSong { text: Option<String>, theme: String },
+    /// Rendered text of an alert to show on the alerts overlay.
+    #[serde(rename = "overlay/alert")]
+    OverlayAlert {
+        text: String,
+        theme: String,
+        /// How long the alert should be shown for in seconds.
+        duration: u64,
+    },
+    /// A channel points reward with a message was redeemed in chat.
+    #[serde(rename = "redemption")]
+    Redemption {
+        /// The ID of the custom reward.
+        reward_id: String,
+        /// The user that redeemed the reward.
+        user: String,
+        text: String,
+    },
+    /// A message to speak on the text-to-speech overlay.
+    #[serde(rename = "overlay/tts")]
+    OverlayTts {
+        text: String,
+        /// The voice to use with the browser's speech synthesis.
+        voice: Option<String>,
+        /// Synthesized audio as a data URL, which is played instead of using
+        /// the browser's speech synthesis if present.
+        audio: Option<String>,
+    },
+    /// Stop speaking the current message on the text-to-speech overlay.
+    #[serde(rename = "overlay/tts-skip")]
+    OverlayTtsSkip,
+    /// The countdowns which are running, sent every second while any of them
+    /// are.
+    #[serde(rename = "countdowns")]
+    Countdowns { countdowns: Vec<countdowns::Status> },
+    /// Current tally of a poll, sent whenever someone votes and when the poll
+    /// is closed.
+    #[serde(rename = "poll")]
+    Poll {
+        question: String,
+        results: Vec<db::PollResult>,
+        /// When the poll closes automatically, if it does.
+        closes_at: Option<DateTime<Utc>>,
+        closed: bool,
+    },
 }
 
 impl Message for Global {
@@ -155,6 +270,9 @@ impl Message for Global {
         match *self {
             SongProgress { .. } => Some("song/progress"),
             SongCurrent { .. } => Some("song/current"),
+            OverlayCurrentSong { .. } => Some("overlay/current-song"),
+            Poll { .. } => Some("poll"),
+            Countdowns { .. } => Some("countdowns"),
             _ => None,
         }
     }
@@ -215,6 +333,12 @@ pub enum Command {
     /// Run a raw command.
     #[serde(rename = "raw")]
     Raw { command: String },
+    /// Send a message to chat.
+    #[serde(rename = "send")]
+    Send { message: String },
+    /// Run a command on behalf of a web API caller with the given role.
+    #[serde(rename = "run")]
+    Run { command: String, role: Role },
 }
 
 impl Message for Command {
@@ -0,0 +1,629 @@
+//! Persistent per-user chat statistics.
+//!
+//! Statistics are collected in memory as messages arrive and periodically
+//! flushed into sled, so that chat activity doesn't add any write load to the
+//! primary database.
+//!
+//! Activity for the current stream is tracked separately and stored in the
+//! database once the stream stops, so that streams can be compared.
+
+use crate::bus;
+use crate::db;
+use crate::irc;
+use crate::prelude::*;
+use crate::storage::sled;
+use crate::task;
+use crate::utils;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use parking_lot::Mutex;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::Duration;
+
+/// How often pending statistics are flushed to storage.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+/// How often storage is compacted.
+const COMPACT_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+/// How many of the most used emotes are kept for a stream.
+const TOP_EMOTES: usize = 10;
+
+/// Statistics that have been collected but not yet flushed.
+#[derive(Debug, Default, Clone)]
+struct Pending {
+    messages: u64,
+    emotes: HashMap<String, u64>,
+    last_seen: Option<DateTime<Utc>>,
+}
+
+/// Statistics for a single user.
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UserStats {
+    /// Number of messages sent.
+    pub messages: u64,
+    /// Number of times each emote has been used.
+    #[serde(default)]
+    pub emotes: HashMap<String, u64>,
+    /// Days on which the user has been active in chat.
+    #[serde(default)]
+    pub active_days: BTreeSet<NaiveDate>,
+    /// When the user was last seen.
+    #[serde(default)]
+    pub last_seen: Option<DateTime<Utc>>,
+    /// If the user has been promoted to a regular, so that they aren't
+    /// promoted again if the streamer removes them.
+    #[serde(default)]
+    pub promoted: bool,
+}
+
+impl UserStats {
+    /// Get the most used emote and how many times it has been used.
+    pub fn favorite_emote(&self) -> Option<(&str, u64)> {
+        self.emotes
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(emote, count)| (emote.as_str(), *count))
+    }
+
+    /// Merge pending statistics into this one.
+    fn merge(&mut self, pending: &Pending) {
+        self.messages += pending.messages;
+
+        for (emote, count) in &pending.emotes {
+            *self.emotes.entry(emote.clone()).or_default() += *count;
+        }
+
+        if let Some(last_seen) = pending.last_seen {
+            self.active_days.insert(last_seen.naive_utc().date());
+            self.last_seen = Some(last_seen);
+        }
+    }
+}
+
+/// Thresholds for promoting users to regulars.
+#[derive(Debug, Default, Clone, Copy)]
+struct Thresholds {
+    /// Number of messages needed.
+    messages: Option<u64>,
+    /// Number of active days needed.
+    active_days: Option<u64>,
+}
+
+impl Thresholds {
+    /// Test if the given statistics reach every configured threshold.
+    ///
+    /// Nobody is promoted unless at least one threshold is configured.
+    fn reached(&self, stats: &UserStats) -> bool {
+        if self.messages.is_none() && self.active_days.is_none() {
+            return false;
+        }
+
+        let messages = self.messages.map_or(true, |m| stats.messages >= m);
+        let active_days = self
+            .active_days
+            .map_or(true, |d| stats.active_days.len() as u64 >= d);
+
+        messages && active_days
+    }
+}
+
+/// Chat activity for the current stream.
+#[derive(Debug, Default)]
+struct Stream {
+    started_at: Option<DateTime<Utc>>,
+    messages: u64,
+    chatters: HashSet<String>,
+    emotes: HashMap<String, u64>,
+    subs: SubStats,
+}
+
+impl Stream {
+    /// Summarize the activity of the stream.
+    fn summary(&self) -> StreamSummary {
+        StreamSummary {
+            started_at: self.started_at,
+            ended_at: None,
+            messages: self.messages,
+            chatters: self.chatters.len() as u64,
+            emotes: top_emotes(&self.emotes, TOP_EMOTES),
+            subs: self.subs,
+        }
+    }
+}
+
+/// Subscriptions made during a single stream.
+#[derive(Debug, Default, Clone, Copy, serde::Serialize)]
+pub struct SubStats {
+    /// Number of new subscriptions.
+    pub subs: u64,
+    /// Number of resubscriptions.
+    pub resubs: u64,
+    /// Number of gifted subscriptions.
+    pub gifted: u64,
+}
+
+/// Chat activity for a single stream.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StreamSummary {
+    /// When the stream started, if known.
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the stream ended. `None` for the current stream.
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Number of messages sent in chat.
+    pub messages: u64,
+    /// Number of unique chatters.
+    pub chatters: u64,
+    /// The most used emotes and how many times they were used.
+    pub emotes: Vec<(String, u64)>,
+    /// Subscriptions made during the stream.
+    pub subs: SubStats,
+}
+
+impl StreamSummary {
+    /// Convert stats stored in the database.
+    fn from_db(stats: db::models::StreamStats) -> Result<Self> {
+        Ok(Self {
+            started_at: stats.started_at.map(|at| DateTime::from_utc(at, Utc)),
+            ended_at: Some(DateTime::from_utc(stats.ended_at, Utc)),
+            messages: stats.messages as u64,
+            chatters: stats.chatters as u64,
+            emotes: serde_json::from_str(&stats.emotes)?,
+            subs: SubStats {
+                subs: stats.subs as u64,
+                resubs: stats.resubs as u64,
+                gifted: stats.gifted_subs as u64,
+            },
+        })
+    }
+}
+
+struct Inner {
+    db: db::Database,
+    tree: Arc<sled::Tree>,
+    /// Chat settings, where promoted regulars are stored.
+    chat_settings: settings::Settings,
+    enabled: settings::Var<bool>,
+    retention: settings::Var<utils::Duration>,
+    regular_messages: settings::Var<Option<u64>>,
+    regular_days: settings::Var<Option<u64>>,
+    pending: Mutex<HashMap<String, Pending>>,
+    stream: Mutex<Stream>,
+}
+
+/// Persistent chat statistics.
+#[derive(Clone)]
+pub struct ChatStats {
+    inner: Arc<Inner>,
+}
+
+impl ChatStats {
+    /// Construct new chat statistics backed by the given tree.
+    ///
+    /// Stream activity is stored in the given database, and users who reach
+    /// the configured thresholds are added to the regulars in `chat_settings`.
+    pub async fn new(
+        db: db::Database,
+        tree: Arc<sled::Tree>,
+        settings: settings::Settings,
+        chat_settings: settings::Settings,
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: Arc::new(Inner {
+                db,
+                tree,
+                chat_settings,
+                enabled: settings.var("enabled", false).await?,
+                retention: settings
+                    .var("retention", utils::Duration::hours(24 * 90))
+                    .await?,
+                regular_messages: settings.optional("regular/messages").await?,
+                regular_days: settings.optional("regular/active-days").await?,
+                pending: Mutex::new(HashMap::new()),
+                stream: Mutex::new(Stream::default()),
+            }),
+        })
+    }
+
+    /// Observe a single chat message.
+    pub async fn observe(&self, tags: &irc::Tags, name: &str, message: &str) {
+        if !self.inner.enabled.load().await {
+            return;
+        }
+
+        let emotes = tags
+            .emotes
+            .as_deref()
+            .map(|emotes| parse_emotes(emotes, message))
+            .unwrap_or_default();
+
+        let name = name.to_lowercase();
+
+        {
+            let mut stream = self.inner.stream.lock();
+            stream.messages += 1;
+            stream.chatters.insert(name.clone());
+
+            for (emote, count) in &emotes {
+                *stream.emotes.entry(emote.clone()).or_default() += *count;
+            }
+        }
+
+        let mut pending = self.inner.pending.lock();
+        let pending = pending.entry(name).or_default();
+        pending.messages += 1;
+        pending.last_seen = Some(Utc::now());
+
+        for (emote, count) in emotes {
+            *pending.emotes.entry(emote).or_default() += count;
+        }
+    }
+
+    /// Observe a subscription made in chat.
+    pub async fn observe_sub(&self, kind: bus::AlertKind) {
+        if !self.inner.enabled.load().await {
+            return;
+        }
+
+        let mut stream = self.inner.stream.lock();
+
+        match kind {
+            bus::AlertKind::Sub => stream.subs.subs += 1,
+            bus::AlertKind::Resub => stream.subs.resubs += 1,
+            bus::AlertKind::SubGift => stream.subs.gifted += 1,
+            _ => (),
+        }
+    }
+
+    /// Get chat activity for the current stream.
+    pub fn current_stream(&self) -> StreamSummary {
+        self.inner.stream.lock().summary()
+    }
+
+    /// Reset chat activity when a stream starts.
+    pub fn stream_started(&self) {
+        *self.inner.stream.lock() = Stream {
+            started_at: Some(Utc::now()),
+            ..Stream::default()
+        };
+    }
+
+    /// Store chat activity for the stream which just stopped and reset it.
+    ///
+    /// Returns the id of the stored activity, if there was any.
+    pub async fn stream_stopped(&self) -> Result<Option<i32>> {
+        let stream = std::mem::take(&mut *self.inner.stream.lock());
+
+        if stream.messages == 0 {
+            return Ok(None);
+        }
+
+        let summary = stream.summary();
+
+        let stats = db::models::AddStreamStats {
+            started_at: summary.started_at.map(|at| at.naive_utc()),
+            ended_at: Utc::now().naive_utc(),
+            messages: summary.messages as i32,
+            chatters: summary.chatters as i32,
+            emotes: serde_json::to_string(&summary.emotes)?,
+            subs: summary.subs.subs as i32,
+            resubs: summary.subs.resubs as i32,
+            gifted_subs: summary.subs.gifted as i32,
+        };
+
+        let id = self.inner.db.stream_stats_push(&stats).await?;
+        Ok(Some(id))
+    }
+
+    /// Get stored chat activity for a single stream.
+    pub async fn stream(&self, id: i32) -> Result<Option<StreamSummary>> {
+        match self.inner.db.stream_stats_get(id).await? {
+            Some(stats) => Ok(Some(StreamSummary::from_db(stats)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// List chat activity for past streams, most recent first.
+    pub async fn stream_history(&self, limit: i64) -> Result<Vec<StreamSummary>> {
+        let stats = self.inner.db.stream_stats_list(limit).await?;
+        stats.into_iter().map(StreamSummary::from_db).collect()
+    }
+
+    /// Get statistics for the given user, including ones not yet flushed.
+    pub async fn user(&self, name: &str) -> Result<Option<UserStats>> {
+        let name = name.to_lowercase();
+        let pending = self.inner.pending.lock().get(&name).cloned();
+        let tree = self.inner.tree.clone();
+
+        let stats = {
+            let name = name.clone();
+
+            task::asyncify(move || {
+                let stats = match tree.get(name.as_bytes())? {
+                    Some(bytes) => Some(serde_cbor::from_slice::<UserStats>(&bytes)?),
+                    None => None,
+                };
+
+                Ok::<_, anyhow::Error>(stats)
+            })
+            .await?
+        };
+
+        Ok(match (stats, pending) {
+            (Some(mut stats), Some(pending)) => {
+                stats.merge(&pending);
+                Some(stats)
+            }
+            (None, Some(pending)) => {
+                let mut stats = UserStats::default();
+                stats.merge(&pending);
+                Some(stats)
+            }
+            (stats, None) => stats,
+        })
+    }
+
+    /// Remove all statistics for the given user.
+    ///
+    /// Returns `true` if there were any statistics to remove.
+    pub async fn forget(&self, name: &str) -> Result<bool> {
+        let name = name.to_lowercase();
+        let pending = self.inner.pending.lock().remove(&name).is_some();
+        let tree = self.inner.tree.clone();
+
+        let stored = task::asyncify(move || {
+            let stored = tree.remove(name.as_bytes())?.is_some();
+            tree.flush()?;
+            Ok::<_, anyhow::Error>(stored)
+        })
+        .await?;
+
+        Ok(pending || stored)
+    }
+
+    /// Flush all pending statistics to storage.
+    ///
+    /// Users who reach the configured thresholds through this are promoted to
+    /// regulars.
+    pub async fn flush(&self) -> Result<()> {
+        let pending = std::mem::take(&mut *self.inner.pending.lock());
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let thresholds = Thresholds {
+            messages: self.inner.regular_messages.load().await,
+            active_days: self.inner.regular_days.load().await,
+        };
+
+        let tree = self.inner.tree.clone();
+
+        let promoted = task::asyncify(move || {
+            let mut promoted = Vec::new();
+
+            for (name, pending) in pending {
+                let update = |old: Option<&[u8]>| {
+                    let mut stats = match old.map(serde_cbor::from_slice::<UserStats>) {
+                        Some(Ok(stats)) => stats,
+                        Some(Err(e)) => {
+                            log::warn!("discarding bad chat stats for `{}`: {}", name, e);
+                            UserStats::default()
+                        }
+                        None => UserStats::default(),
+                    };
+
+                    let was_promoted = stats.promoted;
+                    stats.merge(&pending);
+                    stats.promoted = stats.promoted || thresholds.reached(&stats);
+                    (stats, was_promoted)
+                };
+
+                let old = tree.fetch_and_update(name.as_bytes(), |old| {
+                    serde_cbor::to_vec(&update(old).0).ok()
+                })?;
+
+                // NB: the update is deterministic, so repeating it on the
+                // value it was applied to tells us if it promoted the user.
+                let (stats, was_promoted) = update(old.as_deref());
+
+                if stats.promoted && !was_promoted {
+                    promoted.push(name);
+                }
+            }
+
+            tree.flush()?;
+            Ok::<_, anyhow::Error>(promoted)
+        })
+        .await?;
+
+        if promoted.is_empty() {
+            return Ok(());
+        }
+
+        let chat_settings = &self.inner.chat_settings;
+        let mut regulars = chat_settings
+            .get_or_default::<HashSet<String>>("regulars")
+            .await?;
+
+        for name in promoted {
+            log::info!("promoting {} to regular", name);
+            regulars.insert(name);
+        }
+
+        chat_settings.set("regulars", regulars).await?;
+        Ok(())
+    }
+
+    /// Compact storage by dropping active days which are older than the
+    /// configured retention.
+    pub async fn compact(&self) -> Result<()> {
+        let retention = self.inner.retention.load().await;
+        let cutoff = (Utc::now() - retention.as_chrono()).naive_utc().date();
+        let tree = self.inner.tree.clone();
+
+        let removed = task::asyncify(move || {
+            let mut removed = 0;
+
+            for entry in tree.iter() {
+                let (key, value) = entry?;
+
+                let mut stats = match serde_cbor::from_slice::<UserStats>(&value) {
+                    Ok(stats) => stats,
+                    Err(_) => {
+                        tree.remove(key)?;
+                        continue;
+                    }
+                };
+
+                let len = stats.active_days.len();
+                stats.active_days = stats.active_days.split_off(&cutoff);
+
+                if stats.active_days.len() != len {
+                    removed += len - stats.active_days.len();
+                    tree.insert(key, serde_cbor::to_vec(&stats)?)?;
+                }
+            }
+
+            tree.flush()?;
+            Ok::<_, anyhow::Error>(removed)
+        })
+        .await?;
+
+        log::trace!("compacted chat stats, removed {} active day(s)", removed);
+        Ok(())
+    }
+
+    /// Run the chat stats maintenance loop.
+    pub async fn run(self) -> Result<()> {
+        let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+        let mut compact = tokio::time::interval(COMPACT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = flush.tick() => {
+                    if let Err(e) = self.flush().await {
+                        log_error!(e, "failed to flush chat stats");
+                    }
+                }
+                _ = compact.tick() => {
+                    if let Err(e) = self.compact().await {
+                        log_error!(e, "failed to compact chat stats");
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Get the `n` most used emotes, most used first.
+fn top_emotes(emotes: &HashMap<String, u64>, n: usize) -> Vec<(String, u64)> {
+    let mut emotes = emotes
+        .iter()
+        .map(|(emote, count)| (emote.clone(), *count))
+        .collect::<Vec<_>>();
+
+    emotes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    emotes.truncate(n);
+    emotes
+}
+
+/// Parse the emotes tag of a message into the names of the emotes used and
+/// how many times they were used.
+///
+/// The tag is in the form `300354391:8-16/28087:0-6,18-24`, where each span
+/// refers to character offsets in the message.
+fn parse_emotes(emotes: &str, message: &str) -> Vec<(String, u64)> {
+    let mut out = Vec::new();
+
+    if emotes.is_empty() {
+        return out;
+    }
+
+    let message = message.chars().collect::<Vec<_>>();
+
+    for emote in emotes.split('/') {
+        let spans = match emote.split(':').nth(1) {
+            Some(spans) => spans,
+            None => continue,
+        };
+
+        let count = spans.split(',').count() as u64;
+
+        let span = spans.split(',').next().and_then(|span| {
+            let mut it = span.split('-');
+            let s = str::parse::<usize>(it.next()?).ok()?;
+            let e = str::parse::<usize>(it.next()?).ok()?;
+            Some((s, e))
+        });
+
+        let word = match span {
+            Some((s, e)) if s <= e && e < message.len() => message[s..=e].iter().collect(),
+            _ => continue,
+        };
+
+        out.push((word, count));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_emotes, top_emotes, Thresholds, UserStats};
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_thresholds() {
+        let stats = UserStats {
+            messages: 100,
+            active_days: vec![
+                NaiveDate::from_ymd(2021, 2, 14),
+                NaiveDate::from_ymd(2021, 2, 15),
+            ]
+            .into_iter()
+            .collect(),
+            ..UserStats::default()
+        };
+
+        let thresholds = |messages, active_days| Thresholds {
+            messages,
+            active_days,
+        };
+
+        assert!(!thresholds(None, None).reached(&stats));
+        assert!(thresholds(Some(100), None).reached(&stats));
+        assert!(!thresholds(Some(101), None).reached(&stats));
+        assert!(thresholds(None, Some(2)).reached(&stats));
+        assert!(thresholds(Some(50), Some(2)).reached(&stats));
+        assert!(!thresholds(Some(50), Some(3)).reached(&stats));
+    }
+
+    #[test]
+    fn test_parse_emotes() {
+        assert_eq!(Vec::<(String, u64)>::new(), parse_emotes("", "Kappa"));
+
+        assert_eq!(
+            vec![(String::from("Kappa"), 2), (String::from("PogChamp"), 1)],
+            parse_emotes("25:0-4,15-19/88:6-13", "Kappa PogChamp Kappa")
+        );
+
+        // out of bounds spans are ignored.
+        assert_eq!(
+            Vec::<(String, u64)>::new(),
+            parse_emotes("25:0-40", "Kappa")
+        );
+    }
+
+    #[test]
+    fn test_top_emotes() {
+        let mut emotes = HashMap::new();
+        emotes.insert(String::from("Kappa"), 2);
+        emotes.insert(String::from("PogChamp"), 5);
+        emotes.insert(String::from("LUL"), 2);
+
+        assert_eq!(
+            vec![(String::from("PogChamp"), 5), (String::from("Kappa"), 2)],
+            top_emotes(&emotes, 2)
+        );
+    }
+}
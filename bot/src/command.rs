@@ -1,7 +1,9 @@
 //! Traits and shared plumbing for bot commands (e.g. `!uptime`)
 
+use crate::api::twitch::AnnouncementColor;
 use crate::auth::Scope;
 use crate::irc;
+use crate::module;
 use crate::prelude::*;
 use crate::utils;
 use anyhow::Result;
@@ -11,7 +13,7 @@ use std::fmt;
 use std::num;
 use std::str;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::sync;
 
@@ -37,6 +39,15 @@ impl str::FromStr for HookId {
     }
 }
 
+/// Usage information for a command, as shown by `!help`.
+#[derive(Debug, Clone, Copy)]
+pub struct Usage {
+    /// How the command is used, like `!8ball <question>`.
+    pub usage: &'static str,
+    /// A short description of what the command does.
+    pub description: &'static str,
+}
+
 #[async_trait]
 /// The handler trait for a given command.
 pub trait Handler
@@ -48,6 +59,11 @@ where
         None
     }
 
+    /// Usage information for the command, as shown by `!help`.
+    fn usage(&self) -> Option<Usage> {
+        None
+    }
+
     /// Handle the command.
     async fn handle(&self, ctx: &mut Context) -> Result<()>;
 }
@@ -59,11 +75,155 @@ pub trait MessageHook: std::any::Any + Send + Sync {
     async fn peek(&self, user: &irc::User, m: &str) -> Result<()>;
 }
 
+/// Cooldowns enforced when running commands.
+///
+/// Scope cooldowns apply to everyone using a scope, while command cooldowns
+/// apply to each user separately.
+#[derive(Clone)]
+pub struct Cooldowns {
+    /// How long each user has to wait between running the same command.
+    command_cooldown: settings::Var<Option<utils::Duration>>,
+    inner: Arc<sync::Mutex<CooldownsInner>>,
+}
+
+struct CooldownsInner {
+    /// Active scope cooldowns.
+    scopes: HashMap<Scope, utils::Cooldown>,
+    /// When each user last ran a command, by command and user.
+    commands: HashMap<String, HashMap<String, Instant>>,
+}
+
+impl Cooldowns {
+    /// Construct a new set of cooldowns.
+    pub(crate) fn new(
+        scopes: HashMap<Scope, utils::Cooldown>,
+        command_cooldown: settings::Var<Option<utils::Duration>>,
+    ) -> Self {
+        Self {
+            command_cooldown,
+            inner: Arc::new(sync::Mutex::new(CooldownsInner {
+                scopes,
+                commands: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Check the cooldown of the given scope, and start it if it's not in
+    /// effect.
+    ///
+    /// Returns the time remaining if the cooldown is in effect.
+    pub async fn check_scope(&self, scope: Scope) -> Option<Duration> {
+        let mut inner = self.inner.lock().await;
+        let cooldown = inner.scopes.get_mut(&scope)?;
+        let now = Instant::now();
+
+        if let Some(remaining) = cooldown.check(now.clone()) {
+            return Some(remaining);
+        }
+
+        cooldown.poke(now);
+        None
+    }
+
+    /// Check the cooldown of the given command for a user, and start it if
+    /// it's not in effect.
+    ///
+    /// Returns the time remaining if the cooldown is in effect.
+    pub async fn check_command(&self, command: &str, user: &str) -> Option<Duration> {
+        let duration = self.command_cooldown.load().await?.as_std();
+        let mut inner = self.inner.lock().await;
+        let users = inner.commands.entry(command.to_string()).or_default();
+        let now = Instant::now();
+
+        // NB: forget about expired cooldowns so that they don't accumulate.
+        users.retain(|_, at| now.duration_since(*at) < duration);
+
+        if let Some(at) = users.get(user) {
+            return Some(duration - now.duration_since(*at));
+        }
+
+        users.insert(user.to_string(), now);
+        None
+    }
+
+    /// Test if the given scope has a cooldown.
+    pub async fn has_scope(&self, scope: Scope) -> bool {
+        self.inner.lock().await.scopes.contains_key(&scope)
+    }
+
+    /// Get the time remaining of the cooldown of the given scope, if it's in
+    /// effect.
+    pub async fn scope_remaining(&self, scope: Scope) -> Option<Duration> {
+        let mut inner = self.inner.lock().await;
+        inner.scopes.get_mut(&scope)?.check(Instant::now())
+    }
+
+    /// Get every user with the cooldown of the given command in effect, and
+    /// the time remaining for each of them.
+    pub async fn command_remaining(&self, command: &str) -> Vec<(String, Duration)> {
+        let duration = match self.command_cooldown.load().await {
+            Some(duration) => duration.as_std(),
+            None => return Vec::new(),
+        };
+
+        let inner = self.inner.lock().await;
+        let now = Instant::now();
+
+        let mut out = inner
+            .commands
+            .get(command)
+            .into_iter()
+            .flatten()
+            .filter_map(|(user, at)| {
+                let remaining = duration.checked_sub(now.duration_since(*at))?;
+                Some((user.clone(), remaining))
+            })
+            .collect::<Vec<_>>();
+
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+
+    /// Clear the cooldown of the given scope.
+    ///
+    /// Returns `true` if the cooldown was in effect.
+    pub async fn clear_scope(&self, scope: Scope) -> bool {
+        let mut inner = self.inner.lock().await;
+
+        let cooldown = match inner.scopes.get_mut(&scope) {
+            Some(cooldown) => cooldown,
+            None => return false,
+        };
+
+        let active = cooldown.check(Instant::now()).is_some();
+        cooldown.clear();
+        active
+    }
+
+    /// Clear the cooldown of the given command, either for a single user or
+    /// for everyone.
+    ///
+    /// Returns the number of users whose cooldown was cleared.
+    pub async fn clear_command(&self, command: &str, user: Option<&str>) -> usize {
+        let mut inner = self.inner.lock().await;
+
+        let users = match inner.commands.get_mut(command) {
+            Some(users) => users,
+            None => return 0,
+        };
+
+        match user {
+            Some(user) => users.remove(user).map(|_| 1).unwrap_or_default(),
+            None => users.drain().count(),
+        }
+    }
+}
+
 pub(crate) struct ContextInner {
     /// Sender associated with the command.
     pub(crate) sender: irc::Sender,
-    /// Active scope cooldowns.
-    pub(crate) scope_cooldowns: sync::Mutex<HashMap<Scope, utils::Cooldown>>,
+    /// Active cooldowns.
+    pub(crate) cooldowns: Cooldowns,
     /// A hook that can be installed to peek at all incoming messages.
     pub(crate) message_hooks: sync::RwLock<slab::Slab<Box<dyn MessageHook>>>,
     /// Shutdown handler.
@@ -76,6 +236,7 @@ pub struct Context {
     pub(crate) api_url: Arc<Option<String>>,
     pub(crate) user: irc::User,
     pub(crate) it: utils::Words,
+    pub(crate) handlers: Arc<module::Handlers>,
     pub(crate) inner: Arc<ContextInner>,
 }
 
@@ -90,6 +251,16 @@ impl Context {
         self.inner.sender.channel()
     }
 
+    /// Access all registered command handlers.
+    pub fn handlers(&self) -> &module::Handlers {
+        &self.handlers
+    }
+
+    /// Access the cooldowns enforced when running commands.
+    pub fn cooldowns(&self) -> &Cooldowns {
+        &self.inner.cooldowns
+    }
+
     /// Signal that the bot should try to shut down.
     pub async fn restart(&self) -> bool {
         self.inner.restart.restart().await
@@ -124,19 +295,11 @@ impl Context {
             return Ok(());
         }
 
-        let mut scope_cooldowns = self.inner.scope_cooldowns.lock().await;
-
-        if let Some(cooldown) = scope_cooldowns.get_mut(&scope) {
-            let now = Instant::now();
-
-            if let Some(duration) = cooldown.check(now.clone()) {
-                respond_bail!(
-                    "Cooldown in effect for {}",
-                    utils::compact_duration(duration),
-                )
-            }
-
-            cooldown.poke(now);
+        if let Some(remaining) = self.inner.cooldowns.check_scope(scope).await {
+            respond_bail!(
+                "Cooldown in effect for {}",
+                utils::compact_duration(remaining),
+            )
         }
 
         Ok(())
@@ -147,6 +310,28 @@ impl Context {
         self.user.respond(m).await;
     }
 
+    /// Send a message to the channel as an announcement with the given
+    /// color, or as a regular message if no color is given.
+    ///
+    /// Modules use this for important messages, with the color taken from a
+    /// setting so that streamers can opt into announcements.
+    pub async fn announce(&self, color: Option<AnnouncementColor>, m: impl fmt::Display) {
+        match color {
+            Some(color) => self.inner.sender.announce(color, m).await,
+            None => self.inner.sender.privmsg(m).await,
+        }
+    }
+
+    /// Whisper a message to the user, so that it doesn't clutter chat.
+    pub async fn whisper(&self, m: impl fmt::Display) {
+        self.user.whisper(m).await;
+    }
+
+    /// Reply to the message of the user in its thread.
+    pub async fn reply(&self, m: impl fmt::Display) {
+        self.user.reply(m).await;
+    }
+
     /// Render an iterable of results, that implements display.
     pub async fn respond_lines<I>(&self, results: I, empty: &str)
     where
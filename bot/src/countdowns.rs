@@ -0,0 +1,143 @@
+//! Keeps track of the named countdowns which are running.
+//!
+//! Countdowns are started through the `!countdown` command, and are stored in
+//! the database by the countdown module so that they survive restarts.
+
+use crate::template::Template;
+use crate::utils;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A running countdown.
+#[derive(Debug, Clone)]
+pub struct Countdown {
+    /// The name of the countdown, like `break`.
+    pub name: String,
+    /// The template used to render the countdown.
+    pub template: Template,
+    /// How long the countdown runs for.
+    pub duration: utils::Duration,
+    /// When the countdown ends.
+    pub ends_at: DateTime<Utc>,
+}
+
+impl Countdown {
+    /// How much time remains at the given point in time.
+    pub fn remaining(&self, now: DateTime<Utc>) -> utils::Duration {
+        let remaining = self.ends_at.signed_duration_since(now).num_seconds();
+        utils::Duration::seconds(remaining.max(0) as u64).min(self.duration)
+    }
+
+    /// Render the countdown at the given point in time.
+    pub fn render(&self, now: DateTime<Utc>) -> Result<String> {
+        let remaining = self.remaining(now);
+        let elapsed = self.duration.saturating_sub(remaining);
+
+        return self.template.render_to_string(Data {
+            name: &self.name,
+            remaining: remaining.as_digital(),
+            elapsed: elapsed.as_digital(),
+            duration: self.duration.as_digital(),
+        });
+
+        #[derive(serde::Serialize)]
+        struct Data<'a> {
+            name: &'a str,
+            remaining: String,
+            elapsed: String,
+            duration: String,
+        }
+    }
+
+    /// The status of the countdown at the given point in time.
+    pub fn status(&self, now: DateTime<Utc>) -> Result<Status> {
+        Ok(Status {
+            name: self.name.clone(),
+            text: self.render(now)?,
+            remaining: self.remaining(now),
+            duration: self.duration,
+            ends_at: self.ends_at,
+        })
+    }
+}
+
+/// The status of a countdown, as shown on overlays.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Status {
+    pub name: String,
+    /// The rendered text of the countdown.
+    pub text: String,
+    pub remaining: utils::Duration,
+    pub duration: utils::Duration,
+    pub ends_at: DateTime<Utc>,
+}
+
+/// Countdowns which are running, by name.
+#[derive(Debug, Clone, Default)]
+pub struct Countdowns {
+    countdowns: Arc<RwLock<HashMap<String, Countdown>>>,
+}
+
+impl Countdowns {
+    /// Construct a new, empty set of countdowns.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start the given countdown, replacing any countdown with the same name.
+    pub fn set(&self, countdown: Countdown) {
+        self.countdowns
+            .write()
+            .insert(countdown.name.clone(), countdown);
+    }
+
+    /// Remove the countdown with the given name.
+    pub fn remove(&self, name: &str) -> Option<Countdown> {
+        self.countdowns.write().remove(name)
+    }
+
+    /// Remove all countdowns which have ended at the given point in time.
+    pub fn remove_ended(&self, now: DateTime<Utc>) -> Vec<Countdown> {
+        let mut countdowns = self.countdowns.write();
+
+        let ended = countdowns
+            .values()
+            .filter(|c| c.remaining(now).is_empty())
+            .map(|c| c.name.clone())
+            .collect::<Vec<_>>();
+
+        ended
+            .into_iter()
+            .filter_map(|name| countdowns.remove(&name))
+            .collect()
+    }
+
+    /// Remove all countdowns.
+    pub fn clear(&self) -> Vec<Countdown> {
+        self.countdowns.write().drain().map(|(_, c)| c).collect()
+    }
+
+    /// List all countdowns, the one ending first first.
+    pub fn list(&self) -> Vec<Countdown> {
+        let mut countdowns = self.countdowns.read().values().cloned().collect::<Vec<_>>();
+        countdowns.sort_by(|a, b| a.ends_at.cmp(&b.ends_at).then(a.name.cmp(&b.name)));
+        countdowns
+    }
+
+    /// The status of all countdowns at the given point in time.
+    pub fn statuses(&self, now: DateTime<Utc>) -> Vec<Status> {
+        let mut statuses = Vec::new();
+
+        for countdown in self.list() {
+            match countdown.status(now) {
+                Ok(status) => statuses.push(status),
+                Err(e) => log_error!(e, "failed to render countdown `{}`", countdown.name),
+            }
+        }
+
+        statuses
+    }
+}
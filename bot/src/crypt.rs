@@ -0,0 +1,203 @@
+//! Encryption of secret settings, like OAuth tokens.
+//!
+//! The encryption key is derived from a passphrase. The passphrase is read
+//! from `secrets.key` in the root directory if that file exists, otherwise
+//! it's read from an entry in the OS keyring, which is created with a random
+//! passphrase the first time the bot starts. If no keyring is available, a
+//! random passphrase is written to `secrets.key` instead.
+
+use aes_gcm::aead::{generic_array::GenericArray, Aead as _, NewAead as _};
+use aes_gcm::Aes256Gcm;
+use anyhow::{anyhow, bail, Context as _, Result};
+use hmac::Hmac;
+use rand::RngCore as _;
+use sha2::Sha256;
+use std::borrow::Cow;
+use std::fs;
+use std::io::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Prefix of encrypted values, which can't be confused with JSON.
+const PREFIX: &str = "encrypted:v1:";
+/// Name of the passphrase file in the root directory.
+const KEY_FILE: &str = "secrets.key";
+/// Service and user of the keyring entry holding the passphrase.
+const KEYRING_SERVICE: &str = "OxidizeBot";
+const KEYRING_USER: &str = "secrets";
+/// Salt and number of rounds used when deriving the key from a passphrase.
+const SALT: &[u8] = b"oxidize/secrets";
+const ROUNDS: usize = 100_000;
+/// Length of the nonce stored in front of every encrypted value.
+const NONCE_LEN: usize = 12;
+
+/// A key used to encrypt and decrypt secrets.
+#[derive(Clone)]
+pub struct Key {
+    cipher: Arc<Aes256Gcm>,
+}
+
+impl Key {
+    /// Load the key to use for the given root directory, creating a new
+    /// passphrase if none exists.
+    pub fn load(root: &Path) -> Result<Key> {
+        let path = root.join(KEY_FILE);
+
+        if path.is_file() {
+            let passphrase = fs::read_to_string(&path)
+                .with_context(|| anyhow!("failed to read passphrase: {}", path.display()))?;
+
+            let passphrase = passphrase.trim();
+
+            if passphrase.is_empty() {
+                bail!("passphrase in {} is empty", path.display());
+            }
+
+            log::info!("Using passphrase for secrets from: {}", path.display());
+            return Ok(Self::from_passphrase(passphrase));
+        }
+
+        let keyring = keyring::Keyring::new(KEYRING_SERVICE, KEYRING_USER);
+
+        match keyring.get_password() {
+            Ok(passphrase) => {
+                log::info!("Using passphrase for secrets from OS keyring");
+                return Ok(Self::from_passphrase(&passphrase));
+            }
+            Err(keyring::KeyringError::NoPasswordFound) => {
+                let passphrase = generate_passphrase();
+
+                match keyring.set_password(&passphrase) {
+                    Ok(()) => {
+                        log::info!("Stored new passphrase for secrets in OS keyring");
+                        return Ok(Self::from_passphrase(&passphrase));
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to store passphrase in OS keyring: {}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                log::warn!("OS keyring is not available: {}", e);
+            }
+        }
+
+        let passphrase = generate_passphrase();
+        write_key_file(&path, &passphrase)
+            .with_context(|| anyhow!("failed to write passphrase: {}", path.display()))?;
+
+        log::info!("Stored new passphrase for secrets in: {}", path.display());
+        Ok(Self::from_passphrase(&passphrase))
+    }
+
+    /// Derive a key from the given passphrase.
+    pub fn from_passphrase(passphrase: &str) -> Key {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), SALT, ROUNDS, &mut key);
+
+        Key {
+            cipher: Arc::new(Aes256Gcm::new(GenericArray::from_slice(&key))),
+        }
+    }
+
+    /// Test if the given stored value is encrypted.
+    pub fn is_encrypted(value: &str) -> bool {
+        value.starts_with(PREFIX)
+    }
+
+    /// Encrypt the given value.
+    pub fn encrypt(&self, value: &str) -> Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+
+        let encrypted = self
+            .cipher
+            .encrypt(GenericArray::from_slice(&nonce), value.as_bytes())
+            .map_err(|_| anyhow!("failed to encrypt value"))?;
+
+        let mut data = nonce.to_vec();
+        data.extend(encrypted);
+        Ok(format!("{}{}", PREFIX, base64::encode(&data)))
+    }
+
+    /// Decrypt the given stored value.
+    ///
+    /// Values which aren't encrypted are returned as they are, so that
+    /// secrets stored before encryption was introduced can still be read.
+    pub fn decrypt<'a>(&self, value: &'a str) -> Result<Cow<'a, str>> {
+        let data = match value.strip_prefix(PREFIX) {
+            Some(data) => base64::decode(data)?,
+            None => return Ok(Cow::Borrowed(value)),
+        };
+
+        if data.len() < NONCE_LEN {
+            bail!("encrypted value is too short");
+        }
+
+        let (nonce, encrypted) = data.split_at(NONCE_LEN);
+
+        let decrypted = self
+            .cipher
+            .decrypt(GenericArray::from_slice(nonce), encrypted)
+            .map_err(|_| anyhow!("failed to decrypt value, the passphrase might have changed"))?;
+
+        Ok(Cow::Owned(String::from_utf8(decrypted)?))
+    }
+}
+
+/// Generate a new random passphrase.
+fn generate_passphrase() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    base64::encode(&bytes)
+}
+
+/// Write a passphrase to a file which is only readable by the current user.
+fn write_key_file(path: &Path, passphrase: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt as _;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", passphrase)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Key;
+
+    #[test]
+    fn test_roundtrip() {
+        let key = Key::from_passphrase("hunter2");
+        let encrypted = key
+            .encrypt(r#"{"token":"secret"}"#)
+            .expect("value to encrypt");
+
+        assert!(Key::is_encrypted(&encrypted));
+        assert!(!encrypted.contains("secret"));
+
+        let decrypted = key.decrypt(&encrypted).expect("value to decrypt");
+        assert_eq!(r#"{"token":"secret"}"#, decrypted);
+    }
+
+    #[test]
+    fn test_plaintext_passthrough() {
+        let key = Key::from_passphrase("hunter2");
+        assert_eq!("true", key.decrypt("true").expect("value to decrypt"));
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let encrypted = Key::from_passphrase("hunter2")
+            .encrypt("42")
+            .expect("value to encrypt");
+
+        assert!(Key::from_passphrase("hunter3").decrypt(&encrypted).is_err());
+    }
+}
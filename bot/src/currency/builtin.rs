@@ -1,7 +1,7 @@
 //! Module for the built-in currency which uses the regular databse support.
 
 use crate::currency::{BalanceOf, BalanceTransferError};
-use crate::db::{models, schema, user_id, Database};
+use crate::db::{self, models, schema, user_id, Database};
 
 use anyhow::Result;
 use diesel::prelude::*;
@@ -25,9 +25,8 @@ impl Backend {
         amount: i64,
         override_balance: bool,
     ) -> Result<(), BalanceTransferError> {
-        use self::schema::balances::dsl;
-
         let channel = channel_id(channel);
+
         let giver = giver.to_string();
         let taker = taker.to_string();
         let taker = user_id(&taker);
@@ -36,14 +35,7 @@ impl Backend {
         self.db
             .asyncify(move |c| {
                 c.transaction(move || {
-                    let giver_filter = dsl::balances
-                        .filter(dsl::channel.eq(channel.as_str()).and(dsl::user.eq(&giver)));
-
-                    let balance = giver_filter
-                        .select(dsl::amount)
-                        .first::<i64>(&*c)
-                        .optional()?
-                        .unwrap_or_default();
+                    let balance = lock_balances(c, &channel, &giver, &taker)?;
 
                     if balance < amount && !override_balance {
                         return Err(BalanceTransferError::NoBalance);
@@ -155,8 +147,6 @@ impl Backend {
         amount: i64,
         watch_time: i64,
     ) -> Result<()> {
-        use self::schema::balances::dsl;
-
         // NB: for legacy reasons, channel is stored with a hash.
         let channel = format!("#{}", channel);
 
@@ -164,34 +154,7 @@ impl Backend {
             .asyncify(move |c| {
                 for user in users {
                     let user = user_id(&user);
-
-                    let filter = dsl::balances
-                        .filter(dsl::channel.eq(channel.as_str()).and(dsl::user.eq(&user)));
-
-                    let b = filter.clone().first::<models::Balance>(&*c).optional()?;
-
-                    match b {
-                        None => {
-                            let balance = models::Balance {
-                                channel: channel.to_string(),
-                                user: user.clone(),
-                                amount,
-                                watch_time,
-                            };
-
-                            diesel::insert_into(dsl::balances)
-                                .values(&balance)
-                                .execute(&*c)?;
-                        }
-                        Some(b) => {
-                            let value = b.amount.saturating_add(amount);
-                            let watch_time = b.watch_time.saturating_add(watch_time);
-
-                            diesel::update(filter)
-                                .set((dsl::amount.eq(value), dsl::watch_time.eq(watch_time)))
-                                .execute(&*c)?;
-                        }
-                    }
+                    add_balance(c, &channel, &user, amount, watch_time)?;
                 }
 
                 Ok(())
@@ -200,19 +163,110 @@ impl Backend {
     }
 }
 
+/// Get the balance of the giver in a transfer.
+///
+/// The rows of both users are locked in a consistent order, so that the
+/// balance of the giver can't be spent by a concurrent transaction before it's
+/// debited, and so that transfers in opposite directions can't deadlock.
+#[cfg(feature = "postgres")]
+fn lock_balances(c: &db::Connection, channel: &str, giver: &str, taker: &str) -> Result<i64> {
+    use self::schema::balances::dsl;
+
+    let balances = dsl::balances
+        .select((dsl::user, dsl::amount))
+        .filter(dsl::channel.eq(channel))
+        .filter(dsl::user.eq_any(vec![giver, taker]))
+        .order(dsl::user)
+        .for_update()
+        .load::<(String, i64)>(c)?;
+
+    Ok(balances
+        .into_iter()
+        .find(|(user, _)| user == giver)
+        .map(|(_, amount)| amount)
+        .unwrap_or_default())
+}
+
+/// Get the balance of the giver in a transfer.
+///
+/// SQLite only uses a single connection, so nothing can change the balance
+/// before it's debited.
+#[cfg(not(feature = "postgres"))]
+fn lock_balances(c: &db::Connection, channel: &str, giver: &str, _: &str) -> Result<i64> {
+    use self::schema::balances::dsl;
+
+    let balance = dsl::balances
+        .select(dsl::amount)
+        .filter(dsl::channel.eq(channel).and(dsl::user.eq(giver)))
+        .first::<i64>(c)
+        .optional()?;
+
+    Ok(balance.unwrap_or_default())
+}
+
 /// Common function to modify the balance for the given user.
-fn modify_balance(c: &SqliteConnection, channel: &str, user: &str, amount: i64) -> Result<()> {
+fn modify_balance(c: &db::Connection, channel: &str, user: &str, amount: i64) -> Result<()> {
+    add_balance(c, channel, user, amount, 0)
+}
+
+/// Add to the balance and watch time of the given user.
+///
+/// The change is made in SQL as a single upsert, so that concurrent changes
+/// through other connections aren't lost.
+#[cfg(feature = "postgres")]
+fn add_balance(
+    c: &db::Connection,
+    channel: &str,
+    user: &str,
+    amount: i64,
+    watch_time: i64,
+) -> Result<()> {
+    use self::schema::balances::dsl;
+    use diesel::pg::upsert::excluded;
+
+    let balance = models::Balance {
+        channel: channel.to_string(),
+        user: user.to_string(),
+        amount,
+        watch_time,
+    };
+
+    diesel::insert_into(dsl::balances)
+        .values(&balance)
+        .on_conflict((dsl::channel, dsl::user))
+        .do_update()
+        .set((
+            dsl::amount.eq(dsl::amount + excluded(dsl::amount)),
+            dsl::watch_time.eq(dsl::watch_time + excluded(dsl::watch_time)),
+        ))
+        .execute(c)?;
+
+    Ok(())
+}
+
+/// Add to the balance and watch time of the given user.
+///
+/// SQLite only uses a single connection, so the balance can't change between
+/// reading and writing it.
+#[cfg(not(feature = "postgres"))]
+fn add_balance(
+    c: &db::Connection,
+    channel: &str,
+    user: &str,
+    amount: i64,
+    watch_time: i64,
+) -> Result<()> {
     use self::schema::balances::dsl;
 
     let filter = dsl::balances.filter(dsl::channel.eq(channel).and(dsl::user.eq(user)));
 
-    match filter.clone().first::<models::Balance>(&*c).optional()? {
+    match filter.clone().first::<models::Balance>(c).optional()? {
         None => {
             let balance = models::Balance {
                 channel: channel.to_string(),
                 user: user.to_string(),
                 amount,
-                watch_time: 0,
+                watch_time,
             };
 
             diesel::insert_into(dsl::balances)
@@ -221,9 +275,10 @@ fn modify_balance(c: &SqliteConnection, channel: &str, user: &str, amount: i64)
         }
         Some(b) => {
             let amount = b.amount.saturating_add(amount);
+            let watch_time = b.watch_time.saturating_add(watch_time);
 
             diesel::update(filter)
-                .set(dsl::amount.eq(amount))
+                .set((dsl::amount.eq(amount), dsl::watch_time.eq(watch_time)))
                 .execute(c)?;
         }
     }
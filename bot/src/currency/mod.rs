@@ -11,6 +11,9 @@ use thiserror::Error;
 
 mod builtin;
 mod mysql;
+mod rewards;
+
+pub use self::rewards::{Reward, RewardData, RewardSource, Rewards};
 
 /// Balance of a single user.
 #[derive(Default)]
@@ -0,0 +1,95 @@
+//! Rewards handed out in the stream currency, configured per source.
+
+use crate::settings;
+use crate::template;
+use anyhow::Result;
+
+/// A source of currency rewards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum RewardSource {
+    /// Reward for requesting a song.
+    #[serde(rename = "song-request")]
+    SongRequest,
+    /// Reward given to every viewer by the `!swearjar` command.
+    #[serde(rename = "swearjar")]
+    Swearjar,
+    /// Reward given to every viewer for watch time.
+    #[serde(rename = "viewer-reward")]
+    ViewerReward,
+    /// Reward for answering a trivia question correctly.
+    #[serde(rename = "trivia-win")]
+    TriviaWin,
+    /// Reward for winning a raffle through `!pickwinner`.
+    #[serde(rename = "raffle")]
+    Raffle,
+}
+
+/// Reward configuration for a single source.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Reward {
+    /// The source the reward applies to.
+    pub source: RewardSource,
+    /// The amount of currency to reward.
+    #[serde(default)]
+    pub amount: Option<i64>,
+    /// Message to send when the reward is handed out.
+    #[serde(default)]
+    pub message: Option<template::Template>,
+}
+
+/// Data available when rendering a reward message.
+#[derive(Debug, serde::Serialize)]
+pub struct RewardData<'a> {
+    /// The user being rewarded, if the reward is for a single user.
+    pub user: Option<&'a str>,
+    /// The amount rewarded.
+    pub amount: i64,
+    /// The name of the currency.
+    pub currency: &'a str,
+}
+
+/// Rewards for all sources.
+#[derive(Clone)]
+pub struct Rewards {
+    rewards: settings::Var<Vec<Reward>>,
+}
+
+impl Rewards {
+    /// Load reward configuration from settings.
+    pub async fn new(settings: &settings::Settings) -> Result<Self> {
+        Ok(Self {
+            rewards: settings.var("currency/rewards", Vec::new()).await?,
+        })
+    }
+
+    /// Get the reward configuration for the given source.
+    pub async fn get(&self, source: RewardSource) -> Option<Reward> {
+        self.rewards
+            .load()
+            .await
+            .into_iter()
+            .find(|r| r.source == source)
+    }
+
+    /// Get the amount rewarded for the given source, or the provided default
+    /// if it isn't configured.
+    pub async fn amount(&self, source: RewardSource, default: i64) -> i64 {
+        self.get(source)
+            .await
+            .and_then(|r| r.amount)
+            .unwrap_or(default)
+    }
+
+    /// Render the configured message for the given source, if present.
+    pub async fn message(&self, source: RewardSource, data: RewardData<'_>) -> Option<String> {
+        let message = self.get(source).await?.message?;
+
+        match message.render_to_string(&data) {
+            Ok(message) => Some(message),
+            Err(e) => {
+                log_error!(e, "failed to render reward message for {:?}", source);
+                None
+            }
+        }
+    }
+}
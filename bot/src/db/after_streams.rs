@@ -2,9 +2,42 @@ use crate::db;
 use crate::db::models;
 use crate::db::schema;
 use anyhow::Result;
+use chrono::Utc;
 use diesel::prelude::*;
+use std::collections::HashMap;
 
-pub use self::models::AfterStream;
+pub use self::models::{AfterStream, AfterStreamReply};
+
+/// The status of an afterstream message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AfterStreamStatus {
+    /// The message hasn't been handled yet.
+    Open,
+    /// The message has been handled.
+    Resolved,
+    /// The message has been dismissed.
+    Ignored,
+}
+
+impl AfterStreamStatus {
+    /// The stored representation of the status.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Resolved => "resolved",
+            Self::Ignored => "ignored",
+        }
+    }
+}
+
+/// An afterstream message together with its replies.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AfterStreamEntry {
+    #[serde(flatten)]
+    pub after_stream: AfterStream,
+    pub replies: Vec<AfterStreamReply>,
+}
 
 #[derive(Clone)]
 pub struct AfterStreams {
@@ -17,53 +50,182 @@ impl AfterStreams {
         Ok(Self { db })
     }
 
-    /// Push the given afterstream message.
-    pub async fn push(&self, channel: &str, user: &str, text: &str) -> Result<()> {
+    /// Push the given afterstream message, returning its id.
+    ///
+    /// `stream_id` is the id of the stream that is currently live, if any.
+    pub async fn push(
+        &self,
+        channel: &str,
+        user: &str,
+        text: &str,
+        stream_id: Option<&str>,
+    ) -> Result<i32> {
         use self::schema::after_streams::dsl;
 
         let channel = channel.to_string();
         let user = user.to_string();
         let text = text.to_string();
+        let stream_id = stream_id.map(String::from);
 
         self.db
             .asyncify(move |c| {
-                let after_stream = models::InsertAfterStream {
-                    channel: Some(String::from(channel)),
-                    user: String::from(user),
-                    text: String::from(text),
-                };
-
-                diesel::insert_into(dsl::after_streams)
-                    .values(&after_stream)
-                    .execute(c)?;
+                c.transaction(|| {
+                    let after_stream = models::InsertAfterStream {
+                        channel: Some(channel),
+                        user,
+                        text,
+                        stream_id,
+                    };
+
+                    diesel::insert_into(dsl::after_streams)
+                        .values(&after_stream)
+                        .execute(c)?;
 
-                Ok(())
+                    Ok(db::last_insert_id(c)?)
+                })
             })
             .await
     }
 
-    /// Delete the after stream with the given id.
+    /// Delete the after stream with the given id, and its replies.
     pub async fn delete(&self, id: i32) -> Result<bool> {
-        use self::schema::after_streams::dsl;
+        use self::schema::{after_stream_replies, after_streams};
 
         self.db
             .asyncify(move |c| {
-                let count = diesel::delete(dsl::after_streams.filter(dsl::id.eq(id))).execute(c)?;
-                Ok(count == 1)
+                c.transaction(|| {
+                    let filter = after_stream_replies::table
+                        .filter(after_stream_replies::after_stream_id.eq(id));
+                    diesel::delete(filter).execute(c)?;
+
+                    let filter = after_streams::table.filter(after_streams::id.eq(id));
+                    let count = diesel::delete(filter).execute(c)?;
+                    Ok(count == 1)
+                })
             })
             .await
     }
 
-    /// List all available after streams.
+    /// List all open after streams.
     pub async fn list(&self) -> Result<Vec<AfterStream>> {
         use self::schema::after_streams::dsl;
 
         self.db
             .asyncify(move |c| {
                 Ok(dsl::after_streams
+                    .filter(dsl::status.eq(AfterStreamStatus::Open.as_str()))
                     .order(dsl::added_at.asc())
                     .load::<models::AfterStream>(c)?)
             })
             .await
     }
+
+    /// List after streams together with their replies, optionally only the
+    /// ones with the given status.
+    pub async fn entries(
+        &self,
+        status: Option<AfterStreamStatus>,
+    ) -> Result<Vec<AfterStreamEntry>> {
+        use self::schema::{after_stream_replies, after_streams};
+
+        self.db
+            .asyncify(move |c| {
+                let mut query = after_streams::table.into_boxed();
+
+                if let Some(status) = status {
+                    query = query.filter(after_streams::status.eq(status.as_str()));
+                }
+
+                let list = query
+                    .order((after_streams::added_at.asc(), after_streams::id.asc()))
+                    .load::<models::AfterStream>(c)?;
+
+                let ids = list.iter().map(|a| a.id).collect::<Vec<_>>();
+
+                let replies = after_stream_replies::table
+                    .filter(after_stream_replies::after_stream_id.eq_any(ids))
+                    .order((
+                        after_stream_replies::added_at.asc(),
+                        after_stream_replies::id.asc(),
+                    ))
+                    .load::<models::AfterStreamReply>(c)?;
+
+                let mut by_id = HashMap::<_, Vec<_>>::new();
+
+                for reply in replies {
+                    by_id.entry(reply.after_stream_id).or_default().push(reply);
+                }
+
+                Ok(list
+                    .into_iter()
+                    .map(|after_stream| AfterStreamEntry {
+                        replies: by_id.remove(&after_stream.id).unwrap_or_default(),
+                        after_stream,
+                    })
+                    .collect())
+            })
+            .await
+    }
+
+    /// Set the status of the after streams with the given ids.
+    ///
+    /// Returns the number of after streams updated.
+    pub async fn set_status(&self, ids: Vec<i32>, status: AfterStreamStatus) -> Result<usize> {
+        use self::schema::after_streams::dsl;
+
+        let resolved_at = match status {
+            AfterStreamStatus::Open => None,
+            _ => Some(Utc::now().naive_utc()),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::update(dsl::after_streams.filter(dsl::id.eq_any(ids)))
+                    .set((
+                        dsl::status.eq(status.as_str()),
+                        dsl::resolved_at.eq(resolved_at),
+                    ))
+                    .execute(c)?;
+
+                Ok(count)
+            })
+            .await
+    }
+
+    /// Reply to the after stream with the given id.
+    ///
+    /// Returns `false` if there is no such after stream.
+    pub async fn reply(&self, id: i32, user: Option<&str>, text: &str) -> Result<bool> {
+        use self::schema::{after_stream_replies, after_streams};
+
+        let user = user.map(String::from);
+        let text = text.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    let count = after_streams::table
+                        .filter(after_streams::id.eq(id))
+                        .count()
+                        .get_result::<i64>(c)?;
+
+                    if count == 0 {
+                        return Ok(false);
+                    }
+
+                    let reply = models::InsertAfterStreamReply {
+                        after_stream_id: id,
+                        user,
+                        text,
+                    };
+
+                    diesel::insert_into(after_stream_replies::table)
+                        .values(&reply)
+                        .execute(c)?;
+
+                    Ok(true)
+                })
+            })
+            .await
+    }
 }
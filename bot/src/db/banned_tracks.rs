@@ -0,0 +1,190 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::{bail, Result};
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub use self::models::BannedTrack;
+
+/// The kind of a ban.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum BanKind {
+    /// A single track, identified by its track id.
+    #[serde(rename = "track")]
+    Track,
+    /// A Spotify artist, identified by its artist id.
+    #[serde(rename = "artist")]
+    Artist,
+    /// A YouTube channel, identified by its channel id.
+    #[serde(rename = "channel")]
+    Channel,
+}
+
+impl BanKind {
+    /// Get the string representation of the kind.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Track => "track",
+            Self::Artist => "artist",
+            Self::Channel => "channel",
+        }
+    }
+}
+
+impl fmt::Display for BanKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
+impl std::str::FromStr for BanKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "track" => Ok(Self::Track),
+            "artist" => Ok(Self::Artist),
+            "channel" => Ok(Self::Channel),
+            other => bail!("bad ban kind: {}", other),
+        }
+    }
+}
+
+/// Song requests that have been banned.
+#[derive(Clone)]
+pub struct BannedTracks {
+    inner: Arc<RwLock<HashMap<(BanKind, String), Arc<BannedTrack>>>>,
+    db: db::Database,
+}
+
+impl BannedTracks {
+    /// Load all bans from the database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        use self::schema::banned_tracks::dsl;
+
+        let bans = db
+            .asyncify(move |c| Ok::<_, anyhow::Error>(dsl::banned_tracks.load::<BannedTrack>(c)?))
+            .await?;
+
+        let mut inner = HashMap::new();
+
+        for ban in bans {
+            let kind = match str::parse::<BanKind>(&ban.kind) {
+                Ok(kind) => kind,
+                Err(e) => {
+                    log::warn!("ignoring banned track: {}", e);
+                    continue;
+                }
+            };
+
+            inner.insert((kind, ban.id.clone()), Arc::new(ban));
+        }
+
+        Ok(Self {
+            inner: Arc::new(RwLock::new(inner)),
+            db,
+        })
+    }
+
+    /// Ban the given thing.
+    pub async fn ban(
+        &self,
+        kind: BanKind,
+        id: &str,
+        name: Option<&str>,
+        reason: Option<&str>,
+        banned_by: Option<&str>,
+    ) -> Result<()> {
+        use self::schema::banned_tracks::dsl;
+
+        let ban = BannedTrack {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            name: name.map(|s| s.to_string()),
+            reason: reason.map(|s| s.to_string()),
+            banned_by: banned_by.map(|s| s.to_string()),
+            banned_at: Utc::now().naive_utc(),
+        };
+
+        let ban = Arc::new(ban);
+
+        {
+            let ban = ban.clone();
+
+            self.db
+                .asyncify(move |c| {
+                    c.transaction(|| {
+                        diesel::delete(
+                            dsl::banned_tracks
+                                .filter(dsl::kind.eq(&ban.kind).and(dsl::id.eq(&ban.id))),
+                        )
+                        .execute(c)?;
+
+                        diesel::insert_into(dsl::banned_tracks)
+                            .values(&*ban)
+                            .execute(c)?;
+
+                        Ok::<_, anyhow::Error>(())
+                    })
+                })
+                .await?;
+        }
+
+        self.inner.write().await.insert((kind, id.to_string()), ban);
+        Ok(())
+    }
+
+    /// Remove the given ban.
+    ///
+    /// Returns `true` if a ban was removed.
+    pub async fn unban(&self, kind: BanKind, id: &str) -> Result<bool> {
+        use self::schema::banned_tracks::dsl;
+
+        let key = (kind, id.to_string());
+
+        if self.inner.write().await.remove(&key).is_none() {
+            return Ok(false);
+        }
+
+        let (kind, id) = key;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(
+                    dsl::banned_tracks.filter(dsl::kind.eq(kind.as_str()).and(dsl::id.eq(&id))),
+                )
+                .execute(c)?;
+                Ok::<_, anyhow::Error>(())
+            })
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Get the ban matching the given kind and id, if present.
+    pub async fn get(&self, kind: BanKind, id: &str) -> Option<Arc<BannedTrack>> {
+        self.inner
+            .read()
+            .await
+            .get(&(kind, id.to_string()))
+            .cloned()
+    }
+
+    /// List all bans, ordered by when they were put in place.
+    pub async fn list(&self) -> Vec<Arc<BannedTrack>> {
+        let mut bans = self
+            .inner
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        bans.sort_by(|a, b| a.banned_at.cmp(&b.banned_at));
+        bans
+    }
+}
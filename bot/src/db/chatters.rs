@@ -0,0 +1,194 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use self::models::Chatter;
+
+/// How often observed messages are flushed to the database.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Activity observed since the last flush.
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    first_seen: NaiveDateTime,
+    last_seen: NaiveDateTime,
+    messages: i64,
+}
+
+/// When users were first and last seen in chat, and how much they've said.
+#[derive(Clone)]
+pub struct Chatters {
+    db: db::Database,
+    pending: Arc<Mutex<HashMap<db::Key, Pending>>>,
+}
+
+impl Chatters {
+    /// Construct chatter storage.
+    pub fn new(db: db::Database) -> Self {
+        Self {
+            db,
+            pending: Default::default(),
+        }
+    }
+
+    /// Observe a single chat message by the given user.
+    pub fn observe(&self, channel: &str, user: &str) {
+        let now = Utc::now().naive_utc();
+        let key = db::Key::new(channel, user);
+
+        let mut pending = self.pending.lock();
+
+        let pending = pending.entry(key).or_insert(Pending {
+            first_seen: now,
+            last_seen: now,
+            messages: 0,
+        });
+
+        pending.last_seen = now;
+        pending.messages += 1;
+    }
+
+    /// Get what is known about the given user, including activity which
+    /// hasn't been flushed yet.
+    pub async fn get(&self, channel: &str, user: &str) -> Result<Option<Chatter>> {
+        use self::schema::chatters::dsl;
+
+        let key = db::Key::new(channel, user);
+        let pending = self.pending.lock().get(&key).copied();
+
+        let stored = self
+            .db
+            .asyncify(move |c| {
+                Ok::<_, anyhow::Error>(
+                    dsl::chatters
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::user.eq(&key.name)))
+                        .first::<Chatter>(c)
+                        .optional()?,
+                )
+            })
+            .await?;
+
+        let pending = match pending {
+            Some(pending) => pending,
+            None => return Ok(stored),
+        };
+
+        let chatter = match stored {
+            Some(stored) => merge(stored, pending),
+            None => Chatter {
+                channel: channel.to_string(),
+                user: user.to_lowercase(),
+                first_seen: pending.first_seen,
+                last_seen: pending.last_seen,
+                messages: pending.messages,
+            },
+        };
+
+        Ok(Some(chatter))
+    }
+
+    /// Forget everything stored about the given user.
+    ///
+    /// Returns `true` if anything was removed.
+    pub async fn forget(&self, user: &str) -> Result<bool> {
+        use self::schema::chatters::dsl;
+
+        let user = user.to_lowercase();
+
+        let pending = {
+            let mut pending = self.pending.lock();
+            let before = pending.len();
+            pending.retain(|key, _| key.name != user);
+            pending.len() != before
+        };
+
+        let stored = self
+            .db
+            .asyncify(move |c| {
+                Ok::<_, anyhow::Error>(
+                    diesel::delete(dsl::chatters.filter(dsl::user.eq(&user))).execute(c)?,
+                )
+            })
+            .await?;
+
+        Ok(pending || stored > 0)
+    }
+
+    /// Flush all pending activity to the database.
+    pub async fn flush(&self) -> Result<()> {
+        use self::schema::chatters::dsl;
+
+        let pending = std::mem::take(&mut *self.pending.lock());
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    for (key, pending) in pending {
+                        let stored = dsl::chatters
+                            .filter(dsl::channel.eq(&key.channel).and(dsl::user.eq(&key.name)))
+                            .first::<Chatter>(c)
+                            .optional()?;
+
+                        let chatter = match stored {
+                            Some(stored) => merge(stored, pending),
+                            None => Chatter {
+                                channel: key.channel,
+                                user: key.name,
+                                first_seen: pending.first_seen,
+                                last_seen: pending.last_seen,
+                                messages: pending.messages,
+                            },
+                        };
+
+                        diesel::delete(
+                            dsl::chatters.filter(
+                                dsl::channel
+                                    .eq(&chatter.channel)
+                                    .and(dsl::user.eq(&chatter.user)),
+                            ),
+                        )
+                        .execute(c)?;
+
+                        diesel::insert_into(dsl::chatters)
+                            .values(&chatter)
+                            .execute(c)?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+
+    /// Run the loop which periodically flushes activity to the database.
+    pub async fn run(self) -> Result<()> {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = self.flush().await {
+                log_error!(e, "failed to flush chatters");
+            }
+        }
+    }
+}
+
+/// Merge pending activity into what is stored.
+fn merge(mut stored: Chatter, pending: Pending) -> Chatter {
+    stored.first_seen = std::cmp::min(stored.first_seen, pending.first_seen);
+    stored.last_seen = std::cmp::max(stored.last_seen, pending.last_seen);
+    stored.messages += pending.messages;
+    stored
+}
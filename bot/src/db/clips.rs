@@ -0,0 +1,46 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::{AddClip, Clip};
+
+/// Storage for clips created through the `!clip` command.
+#[derive(Clone)]
+pub struct Clips {
+    db: db::Database,
+}
+
+impl Clips {
+    /// Construct clip storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Store the given clip.
+    pub async fn push(&self, clip: AddClip) -> Result<()> {
+        use self::schema::clips::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::clips).values(&clip).execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// List the most recent clips, newest first.
+    pub async fn list(&self, limit: i64) -> Result<Vec<Clip>> {
+        use self::schema::clips::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::clips
+                    .order((dsl::created_at.desc(), dsl::id.desc()))
+                    .limit(limit)
+                    .load::<models::Clip>(c)?)
+            })
+            .await
+    }
+}
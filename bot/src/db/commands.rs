@@ -268,6 +268,13 @@ impl Command {
     pub fn has_var(&self, var: &str) -> bool {
         self.vars.contains(var)
     }
+
+    /// Test if the rendered command has the given var, or any field of it
+    /// like `{{counters.deaths}}`.
+    pub fn has_var_or_field(&self, var: &str) -> bool {
+        let prefix = format!("{}.", var);
+        self.vars.iter().any(|v| v == var || v.starts_with(&prefix))
+    }
 }
 
 impl db::Matchable for Command {
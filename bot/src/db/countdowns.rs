@@ -0,0 +1,81 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::Countdown;
+
+/// Countdowns which are running, so that they survive restarts.
+#[derive(Clone)]
+pub struct Countdowns {
+    db: db::Database,
+}
+
+impl Countdowns {
+    /// Construct countdown storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// List all countdowns in the given channel.
+    pub async fn list(&self, channel: &str) -> Result<Vec<Countdown>> {
+        use self::schema::countdowns::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::countdowns
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::ends_at)
+                    .load::<Countdown>(c)?)
+            })
+            .await
+    }
+
+    /// Store the given countdown, replacing any countdown with the same name.
+    pub async fn put(&self, countdown: Countdown) -> Result<()> {
+        use self::schema::countdowns::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    diesel::delete(
+                        dsl::countdowns.filter(
+                            dsl::channel
+                                .eq(&countdown.channel)
+                                .and(dsl::name.eq(&countdown.name)),
+                        ),
+                    )
+                    .execute(c)?;
+
+                    diesel::insert_into(dsl::countdowns)
+                        .values(&countdown)
+                        .execute(c)?;
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+
+    /// Delete the countdown with the given name.
+    pub async fn delete(&self, channel: &str, name: &str) -> Result<()> {
+        use self::schema::countdowns::dsl;
+
+        let channel = channel.to_string();
+        let name = name.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(
+                    dsl::countdowns.filter(dsl::channel.eq(channel).and(dsl::name.eq(name))),
+                )
+                .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+}
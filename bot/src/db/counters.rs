@@ -0,0 +1,262 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub use self::models::{Counter, CounterChange};
+
+/// Named counters, like the number of deaths in a playthrough.
+///
+/// The current value of each counter is kept in memory, since they are looked
+/// up for every command that is run.
+#[derive(Clone)]
+pub struct Counters {
+    inner: Arc<RwLock<HashMap<db::Key, i64>>>,
+    db: db::Database,
+}
+
+impl Counters {
+    /// Load all counters from the database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        use self::schema::counters::dsl;
+
+        let counters = db
+            .asyncify(move |c| Ok::<_, anyhow::Error>(dsl::counters.load::<Counter>(c)?))
+            .await?;
+
+        let inner = counters
+            .into_iter()
+            .map(|c| (db::Key::new(&c.channel, &c.name), c.count))
+            .collect();
+
+        Ok(Self {
+            inner: Arc::new(RwLock::new(inner)),
+            db,
+        })
+    }
+
+    /// Create a new counter, starting at zero.
+    ///
+    /// Returns `false` if the counter already exists.
+    pub async fn create(&self, channel: &str, name: &str) -> Result<bool> {
+        use self::schema::counters::dsl;
+
+        let key = db::Key::new(channel, name);
+        let mut inner = self.inner.write().await;
+
+        if inner.contains_key(&key) {
+            return Ok(false);
+        }
+
+        let counter = models::AddCounter {
+            channel: key.channel.clone(),
+            name: key.name.clone(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::counters)
+                    .values(&counter)
+                    .execute(c)?;
+                Ok::<_, anyhow::Error>(())
+            })
+            .await?;
+
+        inner.insert(key, 0);
+        Ok(true)
+    }
+
+    /// Delete a counter, including its history.
+    ///
+    /// Returns `false` if the counter doesn't exist.
+    pub async fn delete(&self, channel: &str, name: &str) -> Result<bool> {
+        use self::schema::{counter_history, counters};
+
+        let key = db::Key::new(channel, name);
+        let mut inner = self.inner.write().await;
+
+        if !inner.contains_key(&key) {
+            return Ok(false);
+        }
+
+        let k = key.clone();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction::<_, anyhow::Error, _>(|| {
+                    diesel::delete(
+                        counters::table.filter(
+                            counters::channel
+                                .eq(&k.channel)
+                                .and(counters::name.eq(&k.name)),
+                        ),
+                    )
+                    .execute(c)?;
+
+                    diesel::delete(
+                        counter_history::table.filter(
+                            counter_history::channel
+                                .eq(&k.channel)
+                                .and(counter_history::name.eq(&k.name)),
+                        ),
+                    )
+                    .execute(c)?;
+
+                    Ok(())
+                })
+            })
+            .await?;
+
+        inner.remove(&key);
+        Ok(true)
+    }
+
+    /// Get the current value of a counter.
+    pub async fn get(&self, channel: &str, name: &str) -> Option<i64> {
+        let key = db::Key::new(channel, name);
+        self.inner.read().await.get(&key).copied()
+    }
+
+    /// Get the current value of every counter in the given channel, by name.
+    pub async fn values(&self, channel: &str) -> HashMap<String, i64> {
+        self.inner
+            .read()
+            .await
+            .iter()
+            .filter(|(key, _)| key.channel == channel)
+            .map(|(key, count)| (key.name.clone(), *count))
+            .collect()
+    }
+
+    /// List all counters in the given channel, ordered by name.
+    pub async fn list(&self, channel: &str) -> Result<Vec<Counter>> {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::counters
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::name)
+                    .load::<Counter>(c)?)
+            })
+            .await
+    }
+
+    /// Add to the value of a counter.
+    ///
+    /// Returns the new value, or `None` if the counter doesn't exist.
+    pub async fn add(
+        &self,
+        channel: &str,
+        name: &str,
+        amount: i64,
+        user: Option<&str>,
+    ) -> Result<Option<i64>> {
+        self.modify(channel, name, user, move |count| {
+            count.saturating_add(amount)
+        })
+        .await
+    }
+
+    /// Set the value of a counter.
+    ///
+    /// Returns the new value, or `None` if the counter doesn't exist.
+    pub async fn set(
+        &self,
+        channel: &str,
+        name: &str,
+        count: i64,
+        user: Option<&str>,
+    ) -> Result<Option<i64>> {
+        self.modify(channel, name, user, move |_| count).await
+    }
+
+    /// List the most recent changes to a counter, most recent first.
+    pub async fn history(
+        &self,
+        channel: &str,
+        name: &str,
+        limit: i64,
+    ) -> Result<Vec<CounterChange>> {
+        use self::schema::counter_history::dsl;
+
+        let key = db::Key::new(channel, name);
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::counter_history
+                    .filter(dsl::channel.eq(key.channel).and(dsl::name.eq(key.name)))
+                    .order(dsl::id.desc())
+                    .limit(limit)
+                    .load::<CounterChange>(c)?)
+            })
+            .await
+    }
+
+    /// Modify the value of a counter, and record the change in its history.
+    async fn modify<F>(
+        &self,
+        channel: &str,
+        name: &str,
+        user: Option<&str>,
+        f: F,
+    ) -> Result<Option<i64>>
+    where
+        F: FnOnce(i64) -> i64,
+    {
+        use self::schema::{counter_history, counters};
+
+        let key = db::Key::new(channel, name);
+        let mut inner = self.inner.write().await;
+
+        let count = match inner.get_mut(&key) {
+            Some(count) => count,
+            None => return Ok(None),
+        };
+
+        let change = models::AddCounterChange {
+            channel: key.channel,
+            name: key.name,
+            count: f(*count),
+            user: user.map(str::to_lowercase),
+        };
+
+        let new = change.count;
+        let now = Utc::now().naive_utc();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction::<_, anyhow::Error, _>(|| {
+                    diesel::update(
+                        counters::table.filter(
+                            counters::channel
+                                .eq(&change.channel)
+                                .and(counters::name.eq(&change.name)),
+                        ),
+                    )
+                    .set((
+                        counters::count.eq(change.count),
+                        counters::updated_at.eq(now),
+                    ))
+                    .execute(c)?;
+
+                    diesel::insert_into(counter_history::table)
+                        .values(&change)
+                        .execute(c)?;
+
+                    Ok(())
+                })
+            })
+            .await?;
+
+        *count = new;
+        Ok(Some(new))
+    }
+}
@@ -0,0 +1,241 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use std::collections::HashMap;
+
+pub use self::models::{AddDonation, Donation};
+
+/// The total amount donated in a single currency.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DonationTotal {
+    /// The donor, if this is the total of a single donor.
+    pub user: Option<String>,
+    pub amount: f64,
+    pub currency: String,
+    /// The number of donations.
+    pub count: u32,
+}
+
+/// Storage for donations.
+#[derive(Clone)]
+pub struct Donations {
+    db: db::Database,
+}
+
+impl Donations {
+    /// Construct donation storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Store the given donation.
+    pub async fn push(&self, donation: AddDonation) -> Result<()> {
+        use self::schema::donations::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::donations)
+                    .values(&donation)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// List the most recent donations, newest first.
+    pub async fn list(&self, limit: i64) -> Result<Vec<Donation>> {
+        use self::schema::donations::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::donations
+                    .order((dsl::donated_at.desc(), dsl::id.desc()))
+                    .limit(limit)
+                    .load::<models::Donation>(c)?)
+            })
+            .await
+    }
+
+    /// When the most recent stream that received donations started.
+    pub async fn latest_stream(&self) -> Result<Option<NaiveDateTime>> {
+        use self::schema::donations::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::donations
+                    .select(dsl::stream_started_at)
+                    .filter(dsl::stream_started_at.is_not_null())
+                    .order(dsl::stream_started_at.desc())
+                    .first::<Option<NaiveDateTime>>(c)
+                    .optional()?
+                    .flatten())
+            })
+            .await
+    }
+
+    /// Totals for each currency.
+    ///
+    /// If `stream` is specified, only donations made during the stream which
+    /// started at that time are counted.
+    pub async fn totals(&self, stream: Option<NaiveDateTime>) -> Result<Vec<DonationTotal>> {
+        let donations = self.load(stream).await?;
+        Ok(totals(&donations, false))
+    }
+
+    /// The donors which have donated the most in each currency.
+    ///
+    /// At most `limit` donors are included for each currency. If `stream` is
+    /// specified, only donations made during the stream which started at that
+    /// time are counted.
+    pub async fn top(
+        &self,
+        stream: Option<NaiveDateTime>,
+        limit: usize,
+    ) -> Result<Vec<DonationTotal>> {
+        let donations = self.load(stream).await?;
+        Ok(limit_per_currency(totals(&donations, true), limit))
+    }
+
+    /// Load donations, optionally limited to a single stream.
+    async fn load(&self, stream: Option<NaiveDateTime>) -> Result<Vec<Donation>> {
+        use self::schema::donations::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                let donations = match stream {
+                    Some(stream) => dsl::donations
+                        .filter(dsl::stream_started_at.eq(stream))
+                        .load::<models::Donation>(c)?,
+                    None => dsl::donations.load::<models::Donation>(c)?,
+                };
+
+                Ok(donations)
+            })
+            .await
+    }
+}
+
+/// Sum up donations by currency, and optionally by user.
+///
+/// Since amounts in different currencies can't be compared, totals are grouped
+/// by currency, starting with the currency which has received the most
+/// donations. Within each currency, totals are sorted by amount, largest
+/// first.
+fn totals(donations: &[Donation], by_user: bool) -> Vec<DonationTotal> {
+    let mut counts = HashMap::<_, u32>::new();
+
+    for d in donations {
+        *counts.entry(d.currency.clone()).or_default() += 1;
+    }
+
+    let mut totals = HashMap::<_, DonationTotal>::new();
+
+    for d in donations {
+        let user = if by_user {
+            Some(d.user.to_lowercase())
+        } else {
+            None
+        };
+
+        let total = totals
+            .entry((user, d.currency.clone()))
+            .or_insert_with(|| DonationTotal {
+                user: if by_user { Some(d.user.clone()) } else { None },
+                amount: 0f64,
+                currency: d.currency.clone(),
+                count: 0,
+            });
+
+        total.amount += d.amount;
+        total.count += 1;
+    }
+
+    let mut totals = totals.into_iter().map(|(_, t)| t).collect::<Vec<_>>();
+
+    totals.sort_by(|a, b| {
+        counts[&b.currency]
+            .cmp(&counts[&a.currency])
+            .then_with(|| a.currency.cmp(&b.currency))
+            .then_with(|| {
+                b.amount
+                    .partial_cmp(&a.amount)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .then_with(|| a.user.cmp(&b.user))
+    });
+
+    totals
+}
+
+/// Keep at most `limit` of the sorted totals for each currency.
+fn limit_per_currency(totals: Vec<DonationTotal>, limit: usize) -> Vec<DonationTotal> {
+    let mut counts = HashMap::<_, usize>::new();
+
+    totals
+        .into_iter()
+        .filter(|t| {
+            let count = counts.entry(t.currency.clone()).or_default();
+            *count += 1;
+            *count <= limit
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{limit_per_currency, totals, Donation};
+
+    fn donation(user: &str, amount: f64, currency: &str) -> Donation {
+        Donation {
+            id: 0,
+            donated_at: chrono::Utc::now().naive_utc(),
+            provider: String::from("streamlabs"),
+            user: String::from(user),
+            amount,
+            currency: String::from(currency),
+            message: None,
+            stream_started_at: None,
+        }
+    }
+
+    #[test]
+    fn test_totals() {
+        let donations = vec![
+            donation("Alice", 5.0, "USD"),
+            donation("bob", 2.5, "USD"),
+            donation("alice", 10.0, "USD"),
+            donation("bob", 20.0, "SEK"),
+        ];
+
+        let all = totals(&donations, false);
+        assert_eq!(2, all.len());
+        assert_eq!(("USD", 3), (all[0].currency.as_str(), all[0].count));
+        assert_eq!(("SEK", 1), (all[1].currency.as_str(), all[1].count));
+
+        // NB: 20 SEK is less than $15, so it's ranked separately.
+        let top = totals(&donations, true);
+        assert_eq!(3, top.len());
+        assert_eq!(
+            (Some("Alice"), "USD"),
+            (top[0].user.as_deref(), top[0].currency.as_str())
+        );
+        assert_eq!(2, top[0].count);
+        assert_eq!(
+            (Some("bob"), "USD"),
+            (top[1].user.as_deref(), top[1].currency.as_str())
+        );
+        assert_eq!(
+            (Some("bob"), "SEK"),
+            (top[2].user.as_deref(), top[2].currency.as_str())
+        );
+
+        let top = limit_per_currency(top, 1);
+        assert_eq!(2, top.len());
+        assert_eq!(Some("Alice"), top[0].user.as_deref());
+        assert_eq!(Some("bob"), top[1].user.as_deref());
+        assert_eq!("SEK", top[1].currency);
+    }
+}
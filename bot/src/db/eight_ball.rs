@@ -0,0 +1,81 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub use self::models::EightBallAnswer;
+
+/// Custom answers of the magic 8 ball.
+#[derive(Clone)]
+pub struct EightBallAnswers {
+    db: db::Database,
+}
+
+impl EightBallAnswers {
+    /// The largest weight an answer can have.
+    pub const MAX_WEIGHT: u32 = 1000;
+
+    /// Construct 8 ball answer storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// List all answers in the given channel.
+    pub async fn list(&self, channel: &str) -> Result<Vec<EightBallAnswer>> {
+        use self::schema::eight_ball_answers::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::eight_ball_answers
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::id)
+                    .load::<EightBallAnswer>(c)?)
+            })
+            .await
+    }
+
+    /// Add an answer to the given channel.
+    pub async fn add(&self, channel: &str, answer: &str, weight: u32) -> Result<()> {
+        use self::schema::eight_ball_answers::dsl;
+
+        let answer = models::AddEightBallAnswer {
+            channel: channel.to_string(),
+            answer: answer.to_string(),
+            weight: i32::try_from(weight)?,
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::eight_ball_answers)
+                    .values(&answer)
+                    .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Remove the answer with the given id.
+    ///
+    /// Returns `false` if there is no such answer in the given channel.
+    pub async fn remove(&self, channel: &str, id: i32) -> Result<bool> {
+        use self::schema::eight_ball_answers::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::eight_ball_answers.filter(dsl::channel.eq(channel).and(dsl::id.eq(id))),
+                )
+                .execute(c)?;
+
+                Ok(count > 0)
+            })
+            .await
+    }
+}
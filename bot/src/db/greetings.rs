@@ -0,0 +1,91 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::Greeting;
+
+/// Custom greetings for individual users.
+#[derive(Clone)]
+pub struct Greetings {
+    db: db::Database,
+}
+
+impl Greetings {
+    /// Construct greetings storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Get the custom greeting of the given user, if any.
+    pub async fn get(&self, channel: &str, user: &str) -> Result<Option<Greeting>> {
+        use self::schema::greetings::dsl;
+
+        let key = db::Key::new(channel, user);
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::greetings
+                    .filter(dsl::channel.eq(&key.channel).and(dsl::user.eq(&key.name)))
+                    .first::<Greeting>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Set the custom greeting of the given user.
+    pub async fn set(&self, channel: &str, user: &str, text: &str) -> Result<()> {
+        use self::schema::greetings::dsl;
+
+        let key = db::Key::new(channel, user);
+
+        let greeting = Greeting {
+            channel: key.channel,
+            user: key.name,
+            text: text.to_string(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    diesel::delete(
+                        dsl::greetings.filter(
+                            dsl::channel
+                                .eq(&greeting.channel)
+                                .and(dsl::user.eq(&greeting.user)),
+                        ),
+                    )
+                    .execute(c)?;
+
+                    diesel::insert_into(dsl::greetings)
+                        .values(&greeting)
+                        .execute(c)?;
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+
+    /// Delete the custom greeting of the given user.
+    ///
+    /// Returns `false` if the user doesn't have a custom greeting.
+    pub async fn delete(&self, channel: &str, user: &str) -> Result<bool> {
+        use self::schema::greetings::dsl;
+
+        let key = db::Key::new(channel, user);
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::greetings
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::user.eq(&key.name))),
+                )
+                .execute(c)?;
+
+                Ok(count > 0)
+            })
+            .await
+    }
+}
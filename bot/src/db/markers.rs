@@ -0,0 +1,48 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::{AddMarker, Marker};
+
+/// Storage for stream markers created through the `!marker` command.
+#[derive(Clone)]
+pub struct Markers {
+    db: db::Database,
+}
+
+impl Markers {
+    /// Construct marker storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Store the given marker.
+    pub async fn push(&self, marker: AddMarker) -> Result<()> {
+        use self::schema::markers::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::markers)
+                    .values(&marker)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// List the most recent markers, newest first.
+    pub async fn list(&self, limit: i64) -> Result<Vec<Marker>> {
+        use self::schema::markers::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::markers
+                    .order((dsl::created_at.desc(), dsl::id.desc()))
+                    .limit(limit)
+                    .load::<models::Marker>(c)?)
+            })
+            .await
+    }
+}
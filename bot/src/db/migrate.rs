@@ -0,0 +1,493 @@
+//! Migrating an existing SQLite database to PostgreSQL.
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use std::path::Path;
+
+/// How many rows are inserted with a single statement.
+const CHUNK: usize = 500;
+
+/// Tables with an automatically assigned `id`, whose sequences have to be
+/// moved past the copied rows.
+const SERIAL: &[&str] = &[
+    "after_streams",
+    "after_stream_replies",
+    "songs",
+    "staged_songs",
+    "song_history",
+    "audit_log",
+    "stream_events",
+    "stream_stats",
+    "stream_sessions",
+    "notifications",
+    "clips",
+    "markers",
+    "donations",
+    "trivia_questions",
+    "trivia_wins",
+    "counter_history",
+    "moderation_log",
+    "polls",
+    "eight_ball_answers",
+];
+
+mod sqlite {
+    embed_migrations!("./migrations");
+    pub use self::embedded_migrations::run;
+}
+
+/// Copy every row of a table, which must be empty in the target database.
+macro_rules! copy {
+    ($from:expr, $to:expr, $table:ident { $($column:ident: $ty:ty,)* }) => {{
+        use crate::db::schema::$table::dsl;
+
+        let existing = dsl::$table.count().get_result::<i64>($to)?;
+
+        if existing > 0 {
+            bail!(
+                "table `{}` already has {} rows, refusing to migrate into a database which is in use",
+                stringify!($table),
+                existing
+            );
+        }
+
+        let rows = dsl::$table
+            .select(($(dsl::$column,)*))
+            .load::<($($ty,)*)>($from)?;
+
+        for chunk in rows.chunks(CHUNK) {
+            let values = chunk
+                .iter()
+                .cloned()
+                .map(|($($column,)*)| ($(dsl::$column.eq($column),)*))
+                .collect::<Vec<_>>();
+
+            diesel::insert_into(dsl::$table).values(values).execute($to)?;
+        }
+
+        log::info!("Copied {} rows of `{}`", rows.len(), stringify!($table));
+    }};
+}
+
+/// Copy all data from the SQLite database at the given path into the
+/// PostgreSQL database with the given url.
+///
+/// The SQLite database is migrated to the latest schema before it's copied,
+/// and nothing is copied unless all tables in PostgreSQL are empty.
+pub fn from_sqlite(path: &Path, url: &str) -> Result<()> {
+    if !path.is_file() {
+        bail!("no such database: {}", path.display());
+    }
+
+    let from = SqliteConnection::establish(&path.display().to_string())?;
+    sqlite::run(&from).context("failed to migrate SQLite database")?;
+
+    let to = PgConnection::establish(url)?;
+
+    to.transaction::<_, anyhow::Error, _>(|| {
+        copy_tables(&from, &to)?;
+
+        for table in SERIAL {
+            let query = format!(
+                "SELECT setval(pg_get_serial_sequence('{0}', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM {0}",
+                table
+            );
+
+            diesel::sql_query(query)
+                .execute(&to)
+                .with_context(|| anyhow!("failed to update sequence of `{}`", table))?;
+        }
+
+        Ok(())
+    })?;
+
+    log::info!("Migrated {} to PostgreSQL", path.display());
+    Ok(())
+}
+
+/// Copy the content of every table.
+fn copy_tables(from: &SqliteConnection, to: &PgConnection) -> Result<()> {
+    copy!(
+        from,
+        to,
+        balances {
+            channel: String,
+            user: String,
+            amount: i64,
+            watch_time: i64,
+        }
+    );
+    copy!(from, to, commands {
+        channel: String,
+        name: String,
+        pattern: Option<String>,
+        count: i32,
+        text: String,
+        group: Option<String>,
+        disabled: bool,
+    });
+    copy!(from, to, after_streams {
+        id: i32,
+        channel: Option<String>,
+        added_at: NaiveDateTime,
+        user: String,
+        text: String,
+        stream_id: Option<String>,
+        status: String,
+        resolved_at: Option<NaiveDateTime>,
+    });
+    copy!(from, to, after_stream_replies {
+        id: i32,
+        after_stream_id: i32,
+        added_at: NaiveDateTime,
+        user: Option<String>,
+        text: String,
+    });
+    copy!(from, to, bad_words {
+        word: String,
+        why: Option<String>,
+    });
+    copy!(from, to, songs {
+        id: i32,
+        deleted: bool,
+        track_id: String,
+        added_at: NaiveDateTime,
+        promoted_at: Option<NaiveDateTime>,
+        promoted_by: Option<String>,
+        user: Option<String>,
+    });
+    copy!(from, to, staged_songs {
+        id: i32,
+        track_id: String,
+        added_at: NaiveDateTime,
+        user: Option<String>,
+    });
+    copy!(
+        from,
+        to,
+        settings {
+            key: String,
+            value: String,
+        }
+    );
+    copy!(from, to, aliases {
+        channel: String,
+        name: String,
+        pattern: Option<String>,
+        text: String,
+        group: Option<String>,
+        disabled: bool,
+    });
+    copy!(from, to, promotions {
+        channel: String,
+        name: String,
+        frequency: i32,
+        promoted_at: Option<NaiveDateTime>,
+        text: String,
+        group: Option<String>,
+        disabled: bool,
+    });
+    copy!(from, to, themes {
+        channel: String,
+        name: String,
+        track_id: String,
+        start: i32,
+        end: Option<i32>,
+        group: Option<String>,
+        disabled: bool,
+    });
+    copy!(
+        from,
+        to,
+        initialized_grants {
+            scope: String,
+            version: String,
+        }
+    );
+    copy!(
+        from,
+        to,
+        grants {
+            scope: String,
+            role: String,
+        }
+    );
+    copy!(
+        from,
+        to,
+        user_grants {
+            scope: String,
+            user: String,
+            allow: bool,
+        }
+    );
+    copy!(from, to, script_keys {
+        channel: String,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    });
+    copy!(from, to, banned_tracks {
+        kind: String,
+        id: String,
+        name: Option<String>,
+        reason: Option<String>,
+        banned_by: Option<String>,
+        banned_at: NaiveDateTime,
+    });
+    copy!(from, to, song_history {
+        id: i32,
+        track_id: String,
+        name: String,
+        user: Option<String>,
+        played_at: NaiveDateTime,
+        skipped: bool,
+    });
+    copy!(from, to, audit_log {
+        id: i32,
+        action: String,
+        actor: Option<String>,
+        target: Option<String>,
+        details: Option<String>,
+        at: NaiveDateTime,
+    });
+    copy!(from, to, stream_events {
+        id: i32,
+        kind: String,
+        user: String,
+        amount: Option<i32>,
+        recipient: Option<String>,
+        at: NaiveDateTime,
+    });
+    copy!(from, to, stream_stats {
+        id: i32,
+        started_at: Option<NaiveDateTime>,
+        ended_at: NaiveDateTime,
+        messages: i32,
+        chatters: i32,
+        emotes: String,
+        subs: i32,
+        resubs: i32,
+        gifted_subs: i32,
+    });
+    copy!(from, to, stream_sessions {
+        id: i32,
+        stream_id: String,
+        title: String,
+        game: Option<String>,
+        started_at: NaiveDateTime,
+        ended_at: Option<NaiveDateTime>,
+        stream_stats_id: Option<i32>,
+    });
+    copy!(
+        from,
+        to,
+        stream_session_commands {
+            session_id: i32,
+            command: String,
+            uses: i32,
+        }
+    );
+    copy!(from, to, notifications {
+        id: i32,
+        added_at: NaiveDateTime,
+        icon: String,
+        title: Option<String>,
+        message: String,
+        read: bool,
+    });
+    copy!(from, to, clips {
+        id: i32,
+        created_at: NaiveDateTime,
+        clip_id: String,
+        title: String,
+        user: Option<String>,
+        game: Option<String>,
+        song: Option<String>,
+    });
+    copy!(from, to, markers {
+        id: i32,
+        created_at: NaiveDateTime,
+        marker_id: String,
+        position_seconds: i64,
+        description: Option<String>,
+        user: Option<String>,
+        song: Option<String>,
+    });
+    copy!(from, to, donations {
+        id: i32,
+        donated_at: NaiveDateTime,
+        provider: String,
+        user: String,
+        amount: f64,
+        currency: String,
+        message: Option<String>,
+        stream_started_at: Option<NaiveDateTime>,
+    });
+    copy!(
+        from,
+        to,
+        subscribers {
+            user: String,
+            months: i32,
+            streak: i32,
+            gifts: i32,
+            updated_at: NaiveDateTime,
+        }
+    );
+    copy!(
+        from,
+        to,
+        trivia_questions {
+            id: i32,
+            added_at: NaiveDateTime,
+            category: String,
+            question: String,
+            answers: String,
+        }
+    );
+    copy!(from, to, trivia_wins {
+        id: i32,
+        won_at: NaiveDateTime,
+        user: String,
+        question_id: i32,
+        stream_started_at: Option<NaiveDateTime>,
+    });
+    copy!(from, to, counter_history {
+        id: i32,
+        channel: String,
+        name: String,
+        count: i64,
+        user: Option<String>,
+        changed_at: NaiveDateTime,
+    });
+    copy!(
+        from,
+        to,
+        counters {
+            channel: String,
+            name: String,
+            count: i64,
+            created_at: NaiveDateTime,
+            updated_at: NaiveDateTime,
+        }
+    );
+    copy!(from, to, variables {
+        channel: String,
+        name: String,
+        value: String,
+        role: Option<String>,
+        updated_by: Option<String>,
+        updated_at: NaiveDateTime,
+    });
+    copy!(from, to, viewer_queue {
+        channel: String,
+        user: String,
+        note: Option<String>,
+        subscriber: bool,
+        joined_at: NaiveDateTime,
+    });
+    copy!(
+        from,
+        to,
+        chatters {
+            channel: String,
+            user: String,
+            first_seen: NaiveDateTime,
+            last_seen: NaiveDateTime,
+            messages: i64,
+        }
+    );
+    copy!(from, to, moderation_log {
+        id: i32,
+        channel: String,
+        action: String,
+        user: String,
+        user_id: Option<String>,
+        message: Option<String>,
+        rule: Option<String>,
+        duration: Option<i64>,
+        undone_at: Option<NaiveDateTime>,
+        at: NaiveDateTime,
+    });
+    copy!(
+        from,
+        to,
+        greetings {
+            channel: String,
+            user: String,
+            text: String,
+        }
+    );
+    copy!(
+        from,
+        to,
+        countdowns {
+            channel: String,
+            name: String,
+            template: String,
+            duration: i64,
+            ends_at: NaiveDateTime,
+        }
+    );
+    copy!(
+        from,
+        to,
+        polls {
+            id: i32,
+            channel: String,
+            question: String,
+            results: String,
+            created_at: NaiveDateTime,
+            closed_at: NaiveDateTime,
+        }
+    );
+    copy!(
+        from,
+        to,
+        reminder_stats {
+            channel: String,
+            name: String,
+            fired: i64,
+            done: i64,
+            last_fired_at: Option<NaiveDateTime>,
+            last_done_at: Option<NaiveDateTime>,
+        }
+    );
+    copy!(
+        from,
+        to,
+        weather_locations {
+            channel: String,
+            user: String,
+            location: String,
+        }
+    );
+    copy!(
+        from,
+        to,
+        eight_ball_answers {
+            id: i32,
+            channel: String,
+            answer: String,
+            weight: i32,
+            added_at: NaiveDateTime,
+        }
+    );
+    copy!(
+        from,
+        to,
+        triggers {
+            channel: String,
+            name: String,
+            pattern: Option<String>,
+            text: String,
+            cooldown: i32,
+            probability: i32,
+            group: Option<String>,
+            disabled: bool,
+        }
+    );
+
+    Ok(())
+}
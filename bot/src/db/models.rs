@@ -1,6 +1,12 @@
 use super::schema::{
-    after_streams, aliases, bad_words, balances, commands, promotions, script_keys, songs, themes,
+    after_stream_replies, after_streams, aliases, audit_log, bad_words, balances, banned_tracks,
+    chatters, clips, commands, countdowns, counter_history, counters, donations,
+    eight_ball_answers, greetings, markers, moderation_log, notifications, polls, promotions,
+    reminder_stats, script_keys, song_history, songs, staged_songs, stream_events,
+    stream_session_commands, stream_sessions, stream_stats, subscribers, themes, triggers,
+    trivia_questions, trivia_wins, variables, viewer_queue, weather_locations,
 };
+use crate::auth::Role;
 use crate::track_id::TrackId;
 use chrono::NaiveDateTime;
 
@@ -98,6 +104,12 @@ pub struct AfterStream {
     pub user: String,
     /// The text of the afterstream.
     pub text: String,
+    /// The id of the stream the afterstream was added during, if any.
+    pub stream_id: Option<String>,
+    /// The status of the afterstream, like `open`, `resolved`, or `ignored`.
+    pub status: String,
+    /// When the afterstream was resolved or ignored.
+    pub resolved_at: Option<NaiveDateTime>,
 }
 
 /// Insert model for afterstreams.
@@ -107,6 +119,30 @@ pub struct InsertAfterStream {
     pub channel: Option<String>,
     pub user: String,
     pub text: String,
+    pub stream_id: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, diesel::Queryable)]
+pub struct AfterStreamReply {
+    /// The unique identifier of the reply.
+    pub id: i32,
+    /// The afterstream being replied to.
+    pub after_stream_id: i32,
+    /// When the reply was added.
+    pub added_at: NaiveDateTime,
+    /// The user that added the reply, if known.
+    pub user: Option<String>,
+    /// The text of the reply.
+    pub text: String,
+}
+
+/// Insert model for afterstream replies.
+#[derive(diesel::Insertable)]
+#[table_name = "after_stream_replies"]
+pub struct InsertAfterStreamReply {
+    pub after_stream_id: i32,
+    pub user: Option<String>,
+    pub text: String,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, diesel::Queryable, diesel::Insertable)]
@@ -144,6 +180,29 @@ pub struct AddSong {
     pub user: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, diesel::Queryable)]
+pub struct StagedSong {
+    /// ID of the staged song request.
+    pub id: i32,
+    /// The track id of the song.
+    pub track_id: TrackId,
+    /// When the song was added.
+    pub added_at: NaiveDateTime,
+    /// The user that requested the song.
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, diesel::Insertable)]
+#[table_name = "staged_songs"]
+pub struct AddStagedSong {
+    /// The track id of the song.
+    pub track_id: TrackId,
+    /// When the song was added.
+    pub added_at: NaiveDateTime,
+    /// The user that requested the song.
+    pub user: Option<String>,
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, diesel::Queryable, diesel::Insertable)]
 pub struct Promotion {
     /// The channel the promotion belongs to.
@@ -212,3 +271,643 @@ pub struct ScriptKey {
 pub struct SetScriptKeyValue<'a> {
     pub value: &'a [u8],
 }
+
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, diesel::Queryable, diesel::Insertable,
+)]
+#[table_name = "banned_tracks"]
+pub struct BannedTrack {
+    /// The kind of the ban, like `track`, `artist`, or `channel`.
+    pub kind: String,
+    /// The identifier of the thing that is banned.
+    pub id: String,
+    /// Human readable name of the thing that is banned, if known.
+    pub name: Option<String>,
+    /// Why the ban was put in place.
+    pub reason: Option<String>,
+    /// The user that put the ban in place.
+    pub banned_by: Option<String>,
+    /// When the ban was put in place.
+    pub banned_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct SongHistory {
+    /// ID of the history entry.
+    pub id: i32,
+    /// The track id of the song.
+    pub track_id: TrackId,
+    /// Human readable name of the song.
+    pub name: String,
+    /// The user that requested the song.
+    pub user: Option<String>,
+    /// When the song started playing.
+    pub played_at: NaiveDateTime,
+    /// If the song was skipped before it completed.
+    pub skipped: bool,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "song_history"]
+pub struct AddSongHistory {
+    /// The track id of the song.
+    pub track_id: TrackId,
+    /// Human readable name of the song.
+    pub name: String,
+    /// The user that requested the song.
+    pub user: Option<String>,
+    /// When the song started playing.
+    pub played_at: NaiveDateTime,
+    /// If the song was skipped before it completed.
+    pub skipped: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct AuditLog {
+    /// ID of the audit entry.
+    pub id: i32,
+    /// The action which was performed, like `forget`.
+    pub action: String,
+    /// The user who performed the action.
+    pub actor: Option<String>,
+    /// The user or thing the action was performed on.
+    pub target: Option<String>,
+    /// Additional details about the action.
+    pub details: Option<String>,
+    /// When the action was performed.
+    pub at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "audit_log"]
+pub struct AddAuditLog {
+    /// The action which was performed, like `forget`.
+    pub action: String,
+    /// The user who performed the action.
+    pub actor: Option<String>,
+    /// The user or thing the action was performed on.
+    pub target: Option<String>,
+    /// Additional details about the action.
+    pub details: Option<String>,
+    /// When the action was performed.
+    pub at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct StreamEvent {
+    /// ID of the stream event.
+    pub id: i32,
+    /// The kind of the event, like `follow` or `cheer`.
+    pub kind: String,
+    /// The user that caused the event.
+    pub user: String,
+    /// Months subscribed, viewers in a raid, or bits cheered.
+    pub amount: Option<i32>,
+    /// The recipient of a gifted subscription.
+    pub recipient: Option<String>,
+    /// When the event happened.
+    pub at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "stream_events"]
+pub struct AddStreamEvent {
+    /// The kind of the event, like `follow` or `cheer`.
+    pub kind: String,
+    /// The user that caused the event.
+    pub user: String,
+    /// Months subscribed, viewers in a raid, or bits cheered.
+    pub amount: Option<i32>,
+    /// The recipient of a gifted subscription.
+    pub recipient: Option<String>,
+    /// When the event happened.
+    pub at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Queryable)]
+pub struct StreamStats {
+    /// ID of the stream stats.
+    pub id: i32,
+    /// When the stream started, if known.
+    pub started_at: Option<NaiveDateTime>,
+    /// When the stream ended.
+    pub ended_at: NaiveDateTime,
+    /// Number of messages sent in chat.
+    pub messages: i32,
+    /// Number of unique chatters.
+    pub chatters: i32,
+    /// The most used emotes, as a JSON array of `[emote, count]` pairs.
+    pub emotes: String,
+    /// Number of new subscriptions.
+    pub subs: i32,
+    /// Number of resubscriptions.
+    pub resubs: i32,
+    /// Number of gifted subscriptions.
+    pub gifted_subs: i32,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "stream_stats"]
+pub struct AddStreamStats {
+    /// When the stream started, if known.
+    pub started_at: Option<NaiveDateTime>,
+    /// When the stream ended.
+    pub ended_at: NaiveDateTime,
+    /// Number of messages sent in chat.
+    pub messages: i32,
+    /// Number of unique chatters.
+    pub chatters: i32,
+    /// The most used emotes, as a JSON array of `[emote, count]` pairs.
+    pub emotes: String,
+    /// Number of new subscriptions.
+    pub subs: i32,
+    /// Number of resubscriptions.
+    pub resubs: i32,
+    /// Number of gifted subscriptions.
+    pub gifted_subs: i32,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct Notification {
+    /// ID of the notification.
+    pub id: i32,
+    /// When the notification was added.
+    pub added_at: NaiveDateTime,
+    /// The icon of the notification, like `info` or `error`.
+    pub icon: String,
+    /// The title of the notification.
+    pub title: Option<String>,
+    /// The message of the notification.
+    pub message: String,
+    /// If the notification has been read.
+    pub read: bool,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "notifications"]
+pub struct AddNotification {
+    /// The icon of the notification.
+    pub icon: String,
+    /// The title of the notification.
+    pub title: Option<String>,
+    /// The message of the notification.
+    pub message: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct Clip {
+    /// ID of the stored clip.
+    pub id: i32,
+    /// When the clip was created.
+    pub created_at: NaiveDateTime,
+    /// The Twitch id of the clip.
+    pub clip_id: String,
+    /// The title of the clip.
+    pub title: String,
+    /// The user who created the clip.
+    pub user: Option<String>,
+    /// The game being played when the clip was created.
+    pub game: Option<String>,
+    /// The song playing when the clip was created.
+    pub song: Option<String>,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "clips"]
+pub struct AddClip {
+    /// The Twitch id of the clip.
+    pub clip_id: String,
+    /// The title of the clip.
+    pub title: String,
+    /// The user who created the clip.
+    pub user: Option<String>,
+    /// The game being played when the clip was created.
+    pub game: Option<String>,
+    /// The song playing when the clip was created.
+    pub song: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct Marker {
+    /// ID of the stored marker.
+    pub id: i32,
+    /// When the marker was created.
+    pub created_at: NaiveDateTime,
+    /// The Twitch id of the marker.
+    pub marker_id: String,
+    /// Position of the marker in the stream, in seconds.
+    pub position_seconds: i64,
+    /// The description of the marker.
+    pub description: Option<String>,
+    /// The user who created the marker.
+    pub user: Option<String>,
+    /// The song playing when the marker was created.
+    pub song: Option<String>,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "markers"]
+pub struct AddMarker {
+    /// The Twitch id of the marker.
+    pub marker_id: String,
+    /// Position of the marker in the stream, in seconds.
+    pub position_seconds: i64,
+    /// The description of the marker.
+    pub description: Option<String>,
+    /// The user who created the marker.
+    pub user: Option<String>,
+    /// The song playing when the marker was created.
+    pub song: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct Donation {
+    /// ID of the stored donation.
+    pub id: i32,
+    /// When the donation was made.
+    pub donated_at: NaiveDateTime,
+    /// The service the donation was made through, like `streamlabs`.
+    pub provider: String,
+    /// The name of the donor.
+    pub user: String,
+    /// The amount donated.
+    pub amount: f64,
+    /// The currency of the donation.
+    pub currency: String,
+    /// The message which came with the donation.
+    pub message: Option<String>,
+    /// When the stream the donation was made during started.
+    pub stream_started_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "donations"]
+pub struct AddDonation {
+    /// The service the donation was made through, like `streamlabs`.
+    pub provider: String,
+    /// The name of the donor.
+    pub user: String,
+    /// The amount donated.
+    pub amount: f64,
+    /// The currency of the donation.
+    pub currency: String,
+    /// The message which came with the donation.
+    pub message: Option<String>,
+    /// When the stream the donation was made during started.
+    pub stream_started_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "subscribers"]
+pub struct Subscriber {
+    /// The login of the subscriber.
+    pub user: String,
+    /// Cumulative number of months subscribed.
+    pub months: i32,
+    /// Number of consecutive months subscribed, if shared.
+    pub streak: i32,
+    /// Number of subscriptions gifted to others.
+    pub gifts: i32,
+    /// When the subscriber was last updated.
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Queryable)]
+pub struct TriviaQuestion {
+    /// ID of the question.
+    pub id: i32,
+    /// When the question was added.
+    pub added_at: NaiveDateTime,
+    /// The category of the question.
+    pub category: String,
+    /// The question to ask.
+    pub question: String,
+    /// Accepted answers, as a JSON array of strings.
+    pub answers: String,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "trivia_questions"]
+pub struct AddTriviaQuestion {
+    /// The category of the question.
+    pub category: String,
+    /// The question to ask.
+    pub question: String,
+    /// Accepted answers, as a JSON array of strings.
+    pub answers: String,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "trivia_wins"]
+pub struct AddTriviaWin {
+    /// The user who answered correctly.
+    pub user: String,
+    /// The question which was answered.
+    pub question_id: i32,
+    /// When the stream the question was answered during started.
+    pub stream_started_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct Counter {
+    pub channel: String,
+    /// The name of the counter, like `deaths`.
+    pub name: String,
+    /// The current value of the counter.
+    pub count: i64,
+    /// When the counter was created.
+    pub created_at: NaiveDateTime,
+    /// When the counter was last changed.
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "counters"]
+pub struct AddCounter {
+    pub channel: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct CounterChange {
+    /// ID of the change.
+    pub id: i32,
+    pub channel: String,
+    /// The name of the counter which changed.
+    pub name: String,
+    /// The value of the counter after the change.
+    pub count: i64,
+    /// The user who changed the counter, if any.
+    pub user: Option<String>,
+    /// When the counter was changed.
+    pub changed_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "counter_history"]
+pub struct AddCounterChange {
+    pub channel: String,
+    pub name: String,
+    pub count: i64,
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "variables"]
+pub struct Variable {
+    pub channel: String,
+    /// The name of the variable, like `greetings`.
+    pub name: String,
+    /// The current value of the variable.
+    pub value: String,
+    /// A role which is allowed to set the variable, in addition to users with
+    /// the `var/edit` scope.
+    pub role: Option<Role>,
+    /// The user who last set the variable, if any.
+    pub updated_by: Option<String>,
+    /// When the variable was last set.
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "viewer_queue"]
+pub struct ViewerQueueEntry {
+    pub channel: String,
+    /// The user waiting in the queue.
+    pub user: String,
+    /// A note left when joining, like a friend code.
+    pub note: Option<String>,
+    /// If the user was a subscriber when they joined.
+    pub subscriber: bool,
+    /// When the user joined the queue.
+    pub joined_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "chatters"]
+pub struct Chatter {
+    pub channel: String,
+    /// The user who has been seen in chat.
+    pub user: String,
+    /// When the user first sent a message.
+    pub first_seen: NaiveDateTime,
+    /// When the user most recently sent a message.
+    pub last_seen: NaiveDateTime,
+    /// Total number of messages sent by the user.
+    pub messages: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct ModerationAction {
+    /// ID of the moderation action.
+    pub id: i32,
+    pub channel: String,
+    /// The action which was taken, like `delete`, `timeout`, `ban`, or `permit`.
+    pub action: String,
+    /// The user the action was taken against.
+    pub user: String,
+    /// The Twitch ID of the user, if known.
+    pub user_id: Option<String>,
+    /// The offending message, if any.
+    pub message: Option<String>,
+    /// The rule which triggered the action, if it was taken by the bot.
+    pub rule: Option<String>,
+    /// How long the user was timed out for, in seconds.
+    pub duration: Option<i64>,
+    /// When the action was undone, if it has been.
+    pub undone_at: Option<NaiveDateTime>,
+    /// When the action was taken.
+    pub at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "moderation_log"]
+pub struct AddModerationAction {
+    pub channel: String,
+    /// The action which was taken, like `delete`, `timeout`, `ban`, or `permit`.
+    pub action: String,
+    /// The user the action was taken against.
+    pub user: String,
+    /// The Twitch ID of the user, if known.
+    pub user_id: Option<String>,
+    /// The offending message, if any.
+    pub message: Option<String>,
+    /// The rule which triggered the action, if it was taken by the bot.
+    pub rule: Option<String>,
+    /// How long the user was timed out for, in seconds.
+    pub duration: Option<i64>,
+    /// When the action was taken.
+    pub at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "greetings"]
+pub struct Greeting {
+    pub channel: String,
+    /// The user who is greeted.
+    pub user: String,
+    /// The template of the greeting.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, diesel::Queryable)]
+pub struct Poll {
+    /// ID of the poll.
+    pub id: i32,
+    pub channel: String,
+    /// The question which was asked.
+    pub question: String,
+    /// Results of the poll, as a JSON array.
+    pub results: String,
+    /// When the poll was started.
+    pub created_at: NaiveDateTime,
+    /// When the poll was closed.
+    pub closed_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "polls"]
+pub struct AddPoll {
+    pub channel: String,
+    /// The question which was asked.
+    pub question: String,
+    /// Results of the poll, as a JSON array.
+    pub results: String,
+    /// When the poll was started.
+    pub created_at: NaiveDateTime,
+    /// When the poll was closed.
+    pub closed_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Queryable, diesel::Insertable)]
+#[table_name = "countdowns"]
+pub struct Countdown {
+    pub channel: String,
+    /// The name of the countdown, like `break`.
+    pub name: String,
+    /// The template used to render the countdown.
+    pub template: String,
+    /// How long the countdown runs for, in seconds.
+    pub duration: i64,
+    /// When the countdown ends.
+    pub ends_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "reminder_stats"]
+pub struct ReminderStats {
+    pub channel: String,
+    /// The name of the reminder, like `posture`.
+    pub name: String,
+    /// The number of times the reminder has fired.
+    pub fired: i64,
+    /// The number of times the reminder has been marked as done.
+    pub done: i64,
+    /// When the reminder last fired.
+    pub last_fired_at: Option<NaiveDateTime>,
+    /// When the reminder was last marked as done.
+    pub last_done_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "weather_locations"]
+pub struct WeatherLocation {
+    pub channel: String,
+    /// The user who saved the location.
+    pub user: String,
+    /// The saved location, like `Berlin`.
+    pub location: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct EightBallAnswer {
+    pub id: i32,
+    pub channel: String,
+    /// The template of the answer.
+    pub answer: String,
+    /// How likely the answer is to be picked, relative to other answers.
+    pub weight: i32,
+    pub added_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "eight_ball_answers"]
+pub struct AddEightBallAnswer {
+    pub channel: String,
+    /// The template of the answer.
+    pub answer: String,
+    /// How likely the answer is to be picked, relative to other answers.
+    pub weight: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, diesel::Queryable, diesel::Insertable)]
+pub struct Trigger {
+    /// The channel the trigger belongs to.
+    pub channel: String,
+    /// The name of the trigger.
+    pub name: String,
+    /// The regular expression which triggers a response.
+    ///
+    /// Triggers without a pattern never fire.
+    pub pattern: Option<String>,
+    /// The response template.
+    pub text: String,
+    /// The cooldown in seconds between each time the trigger fires.
+    pub cooldown: i32,
+    /// The chance in percent that the trigger fires when matched.
+    pub probability: i32,
+    /// The group the trigger is part of, if any.
+    pub group: Option<String>,
+    /// If the trigger is disabled.
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, Default, diesel::AsChangeset)]
+#[table_name = "triggers"]
+pub struct UpdateTrigger<'a> {
+    pub text: Option<&'a str>,
+    pub cooldown: Option<i32>,
+    pub probability: Option<i32>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct StreamSession {
+    /// ID of the session.
+    pub id: i32,
+    /// The Twitch id of the stream.
+    pub stream_id: String,
+    /// The title of the stream when it started.
+    pub title: String,
+    /// The game of the stream when it started.
+    pub game: Option<String>,
+    /// When the stream started.
+    pub started_at: NaiveDateTime,
+    /// When the stream ended. `None` for the current stream.
+    pub ended_at: Option<NaiveDateTime>,
+    /// Chat statistics stored when the stream ended.
+    pub stream_stats_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "stream_sessions"]
+pub struct AddStreamSession {
+    /// The Twitch id of the stream.
+    pub stream_id: String,
+    /// The title of the stream when it started.
+    pub title: String,
+    /// The game of the stream when it started.
+    pub game: Option<String>,
+    /// When the stream started.
+    pub started_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+pub struct StreamSessionCommand {
+    /// The session the command was used in.
+    #[serde(skip)]
+    pub session_id: i32,
+    /// The name of the command, without the leading `!`.
+    pub command: String,
+    /// How many times the command was used.
+    pub uses: i32,
+}
@@ -0,0 +1,142 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::Utc;
+use diesel::prelude::*;
+
+pub use self::models::{AddModerationAction, ModerationAction};
+
+/// The default number of actions listed.
+const DEFAULT_LIMIT: i64 = 100;
+
+/// Filter used when listing moderation actions.
+#[derive(Debug, Default, Clone, serde::Deserialize)]
+pub struct ModerationFilter {
+    /// Only list actions of the given kind, like `timeout`.
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Only list actions taken against the given user.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// The maximum number of actions to list.
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+/// A log of deletions, timeouts, bans, and permits in chat.
+#[derive(Clone)]
+pub struct ModerationLog {
+    db: db::Database,
+}
+
+impl ModerationLog {
+    /// Construct moderation log storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Record a moderation action.
+    pub async fn push(&self, action: &AddModerationAction) -> Result<()> {
+        use self::schema::moderation_log::dsl;
+
+        let action = action.clone();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::moderation_log)
+                    .values(action)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// List moderation actions in the given channel, most recent first.
+    pub async fn list(
+        &self,
+        channel: &str,
+        filter: ModerationFilter,
+    ) -> Result<Vec<ModerationAction>> {
+        use self::schema::moderation_log::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let mut query = dsl::moderation_log
+                    .filter(dsl::channel.eq(channel))
+                    .into_boxed();
+
+                if let Some(action) = filter.action {
+                    query = query.filter(dsl::action.eq(action));
+                }
+
+                if let Some(user) = filter.user {
+                    query = query.filter(dsl::user.eq(user.to_lowercase()));
+                }
+
+                let actions = query
+                    .order((dsl::at.desc(), dsl::id.desc()))
+                    .limit(filter.limit.unwrap_or(DEFAULT_LIMIT))
+                    .load::<ModerationAction>(c)?;
+
+                Ok(actions)
+            })
+            .await
+    }
+
+    /// Get a single moderation action by id.
+    pub async fn get(&self, channel: &str, id: i32) -> Result<Option<ModerationAction>> {
+        use self::schema::moderation_log::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::moderation_log
+                    .filter(dsl::channel.eq(channel).and(dsl::id.eq(id)))
+                    .first::<ModerationAction>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Mark the given action as undone.
+    pub async fn mark_undone(&self, id: i32) -> Result<()> {
+        use self::schema::moderation_log::dsl;
+
+        let now = Utc::now().naive_utc();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::update(dsl::moderation_log.filter(dsl::id.eq(id)))
+                    .set(dsl::undone_at.eq(now))
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Count the number of timeouts and bans of the given user.
+    pub async fn counts(&self, channel: &str, user: &str) -> Result<(i64, i64)> {
+        use self::schema::moderation_log::dsl;
+
+        let channel = channel.to_string();
+        let user = user.to_lowercase();
+
+        self.db
+            .asyncify(move |c| {
+                let count = |action: &str| {
+                    dsl::moderation_log
+                        .filter(dsl::channel.eq(&channel))
+                        .filter(dsl::user.eq(&user).and(dsl::action.eq(action)))
+                        .count()
+                        .get_result::<i64>(c)
+                };
+
+                Ok::<_, anyhow::Error>((count("timeout")?, count("ban")?))
+            })
+            .await
+    }
+}
@@ -0,0 +1,101 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use crate::sys;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::Notification;
+
+/// Storage for system notifications, so that they can be seen through the
+/// web UI on installs without a system tray.
+#[derive(Clone)]
+pub struct Notifications {
+    db: db::Database,
+}
+
+impl Notifications {
+    /// Construct notification storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Store the given notification.
+    pub async fn push(&self, n: &sys::Notification) -> Result<()> {
+        use self::schema::notifications::dsl;
+
+        let notification = models::AddNotification {
+            icon: n.icon.id().to_string(),
+            title: n.title.clone(),
+            message: n.message.clone(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::notifications)
+                    .values(&notification)
+                    .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// List the most recent notifications, newest first.
+    pub async fn list(&self, limit: i64) -> Result<Vec<Notification>> {
+        use self::schema::notifications::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::notifications
+                    .order((dsl::added_at.desc(), dsl::id.desc()))
+                    .limit(limit)
+                    .load::<models::Notification>(c)?)
+            })
+            .await
+    }
+
+    /// Count the number of unread notifications.
+    pub async fn unread(&self) -> Result<i64> {
+        use self::schema::notifications::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::notifications
+                    .filter(dsl::read.eq(false))
+                    .count()
+                    .get_result::<i64>(c)?)
+            })
+            .await
+    }
+
+    /// Mark the notification with the given id as read.
+    pub async fn mark_read(&self, id: i32) -> Result<bool> {
+        use self::schema::notifications::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::update(dsl::notifications.filter(dsl::id.eq(id)))
+                    .set(dsl::read.eq(true))
+                    .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Mark all notifications as read.
+    pub async fn mark_all_read(&self) -> Result<usize> {
+        use self::schema::notifications::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::update(dsl::notifications.filter(dsl::read.eq(false)))
+                    .set(dsl::read.eq(true))
+                    .execute(c)?;
+
+                Ok(count)
+            })
+            .await
+    }
+}
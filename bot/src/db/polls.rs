@@ -0,0 +1,106 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+/// The number of votes an option got in a poll.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PollResult {
+    /// The keyword used to vote for the option.
+    pub key: String,
+    /// The description of the option, if any.
+    pub description: Option<String>,
+    /// The number of votes, including any vote weights.
+    pub votes: u32,
+}
+
+/// A poll which has been closed.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Poll {
+    pub id: i32,
+    pub channel: String,
+    pub question: String,
+    /// Results, ordered by the number of votes.
+    pub results: Vec<PollResult>,
+    pub created_at: NaiveDateTime,
+    pub closed_at: NaiveDateTime,
+}
+
+impl Poll {
+    /// Convert a poll stored in the database.
+    fn from_db(poll: models::Poll) -> Result<Self> {
+        Ok(Self {
+            id: poll.id,
+            channel: poll.channel,
+            question: poll.question,
+            results: serde_json::from_str(&poll.results)?,
+            created_at: poll.created_at,
+            closed_at: poll.closed_at,
+        })
+    }
+}
+
+/// Storage for the results of closed polls.
+#[derive(Clone)]
+pub struct Polls {
+    db: db::Database,
+}
+
+impl Polls {
+    /// Construct poll storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Store the results of a closed poll.
+    pub async fn push(
+        &self,
+        channel: &str,
+        question: &str,
+        results: &[PollResult],
+        created_at: NaiveDateTime,
+        closed_at: NaiveDateTime,
+    ) -> Result<()> {
+        use self::schema::polls::dsl;
+
+        let poll = models::AddPoll {
+            channel: channel.to_string(),
+            question: question.to_string(),
+            results: serde_json::to_string(results)?,
+            created_at,
+            closed_at,
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::polls).values(poll).execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// List the most recently closed polls in the given channel, most recent
+    /// first.
+    pub async fn list(&self, channel: &str, limit: i64) -> Result<Vec<Poll>> {
+        use self::schema::polls::dsl;
+
+        let channel = channel.to_string();
+
+        let polls = self
+            .db
+            .asyncify(move |c| {
+                Ok::<_, anyhow::Error>(
+                    dsl::polls
+                        .filter(dsl::channel.eq(channel))
+                        .order((dsl::closed_at.desc(), dsl::id.desc()))
+                        .limit(limit)
+                        .load::<models::Poll>(c)?,
+                )
+            })
+            .await?;
+
+        polls.into_iter().map(Poll::from_db).collect()
+    }
+}
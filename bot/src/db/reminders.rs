@@ -0,0 +1,112 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+pub use self::models::ReminderStats;
+
+/// Statistics for how often reminders fire and are acted on.
+#[derive(Clone)]
+pub struct Reminders {
+    db: db::Database,
+}
+
+impl Reminders {
+    /// Construct reminder statistics storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// List statistics for all reminders in the given channel.
+    pub async fn list(&self, channel: &str) -> Result<Vec<ReminderStats>> {
+        use self::schema::reminder_stats::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::reminder_stats
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::name)
+                    .load::<ReminderStats>(c)?)
+            })
+            .await
+    }
+
+    /// Record that the given reminder fired.
+    pub async fn fired(&self, channel: &str, name: &str, at: NaiveDateTime) -> Result<()> {
+        use self::schema::reminder_stats::dsl;
+
+        let channel = channel.to_string();
+        let name = name.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    let filter = dsl::reminder_stats
+                        .filter(dsl::channel.eq(&channel).and(dsl::name.eq(&name)));
+
+                    let updated = diesel::update(filter)
+                        .set((
+                            dsl::fired.eq(dsl::fired + 1),
+                            dsl::last_fired_at.eq(Some(at)),
+                        ))
+                        .execute(c)?;
+
+                    if updated == 0 {
+                        diesel::insert_into(dsl::reminder_stats)
+                            .values(&ReminderStats {
+                                channel: channel.clone(),
+                                name: name.clone(),
+                                fired: 1,
+                                done: 0,
+                                last_fired_at: Some(at),
+                                last_done_at: None,
+                            })
+                            .execute(c)?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+
+    /// Record that the given reminder was acted on.
+    pub async fn done(&self, channel: &str, name: &str, at: NaiveDateTime) -> Result<()> {
+        use self::schema::reminder_stats::dsl;
+
+        let channel = channel.to_string();
+        let name = name.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    let filter = dsl::reminder_stats
+                        .filter(dsl::channel.eq(&channel).and(dsl::name.eq(&name)));
+
+                    let updated = diesel::update(filter)
+                        .set((dsl::done.eq(dsl::done + 1), dsl::last_done_at.eq(Some(at))))
+                        .execute(c)?;
+
+                    if updated == 0 {
+                        diesel::insert_into(dsl::reminder_stats)
+                            .values(&ReminderStats {
+                                channel: channel.clone(),
+                                name: name.clone(),
+                                fired: 0,
+                                done: 1,
+                                last_fired_at: None,
+                                last_done_at: Some(at),
+                            })
+                            .execute(c)?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+}
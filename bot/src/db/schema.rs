@@ -26,6 +26,19 @@ table! {
         added_at -> Timestamp,
         user -> Text,
         text -> Text,
+        stream_id -> Nullable<Text>,
+        status -> Text,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    after_stream_replies (id) {
+        id -> Integer,
+        after_stream_id -> Integer,
+        added_at -> Timestamp,
+        user -> Nullable<Text>,
+        text -> Text,
     }
 }
 
@@ -48,6 +61,15 @@ table! {
     }
 }
 
+table! {
+    staged_songs (id) {
+        id -> Integer,
+        track_id -> Text,
+        added_at -> Timestamp,
+        user -> Nullable<Text>,
+    }
+}
+
 table! {
     settings (key) {
         key -> Text,
@@ -106,6 +128,15 @@ table! {
     }
 }
 
+// Grants which allow or deny scopes for specific users.
+table! {
+    user_grants (scope, user) {
+        scope -> Text,
+        user -> Text,
+        allow -> Bool,
+    }
+}
+
 table! {
     script_keys (channel, key) {
         channel -> Text,
@@ -113,3 +144,297 @@ table! {
         value -> Binary,
     }
 }
+
+table! {
+    banned_tracks (kind, id) {
+        kind -> Text,
+        id -> Text,
+        name -> Nullable<Text>,
+        reason -> Nullable<Text>,
+        banned_by -> Nullable<Text>,
+        banned_at -> Timestamp,
+    }
+}
+
+table! {
+    song_history (id) {
+        id -> Integer,
+        track_id -> Text,
+        name -> Text,
+        user -> Nullable<Text>,
+        played_at -> Timestamp,
+        skipped -> Bool,
+    }
+}
+
+table! {
+    audit_log (id) {
+        id -> Integer,
+        action -> Text,
+        actor -> Nullable<Text>,
+        target -> Nullable<Text>,
+        details -> Nullable<Text>,
+        at -> Timestamp,
+    }
+}
+
+table! {
+    stream_events (id) {
+        id -> Integer,
+        kind -> Text,
+        user -> Text,
+        amount -> Nullable<Integer>,
+        recipient -> Nullable<Text>,
+        at -> Timestamp,
+    }
+}
+
+table! {
+    stream_stats (id) {
+        id -> Integer,
+        started_at -> Nullable<Timestamp>,
+        ended_at -> Timestamp,
+        messages -> Integer,
+        chatters -> Integer,
+        emotes -> Text,
+        subs -> Integer,
+        resubs -> Integer,
+        gifted_subs -> Integer,
+    }
+}
+
+table! {
+    notifications (id) {
+        id -> Integer,
+        added_at -> Timestamp,
+        icon -> Text,
+        title -> Nullable<Text>,
+        message -> Text,
+        read -> Bool,
+    }
+}
+
+table! {
+    clips (id) {
+        id -> Integer,
+        created_at -> Timestamp,
+        clip_id -> Text,
+        title -> Text,
+        user -> Nullable<Text>,
+        game -> Nullable<Text>,
+        song -> Nullable<Text>,
+    }
+}
+
+table! {
+    markers (id) {
+        id -> Integer,
+        created_at -> Timestamp,
+        marker_id -> Text,
+        position_seconds -> BigInt,
+        description -> Nullable<Text>,
+        user -> Nullable<Text>,
+        song -> Nullable<Text>,
+    }
+}
+
+table! {
+    donations (id) {
+        id -> Integer,
+        donated_at -> Timestamp,
+        provider -> Text,
+        user -> Text,
+        amount -> Double,
+        currency -> Text,
+        message -> Nullable<Text>,
+        stream_started_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    subscribers (user) {
+        user -> Text,
+        months -> Integer,
+        streak -> Integer,
+        gifts -> Integer,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    trivia_questions (id) {
+        id -> Integer,
+        added_at -> Timestamp,
+        category -> Text,
+        question -> Text,
+        answers -> Text,
+    }
+}
+
+table! {
+    trivia_wins (id) {
+        id -> Integer,
+        won_at -> Timestamp,
+        user -> Text,
+        question_id -> Integer,
+        stream_started_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    counter_history (id) {
+        id -> Integer,
+        channel -> Text,
+        name -> Text,
+        count -> BigInt,
+        user -> Nullable<Text>,
+        changed_at -> Timestamp,
+    }
+}
+
+table! {
+    counters (channel, name) {
+        channel -> Text,
+        name -> Text,
+        count -> BigInt,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    variables (channel, name) {
+        channel -> Text,
+        name -> Text,
+        value -> Text,
+        role -> Nullable<Text>,
+        updated_by -> Nullable<Text>,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    viewer_queue (channel, user) {
+        channel -> Text,
+        user -> Text,
+        note -> Nullable<Text>,
+        subscriber -> Bool,
+        joined_at -> Timestamp,
+    }
+}
+
+table! {
+    chatters (channel, user) {
+        channel -> Text,
+        user -> Text,
+        first_seen -> Timestamp,
+        last_seen -> Timestamp,
+        messages -> BigInt,
+    }
+}
+
+table! {
+    moderation_log (id) {
+        id -> Integer,
+        channel -> Text,
+        action -> Text,
+        user -> Text,
+        user_id -> Nullable<Text>,
+        message -> Nullable<Text>,
+        rule -> Nullable<Text>,
+        duration -> Nullable<BigInt>,
+        undone_at -> Nullable<Timestamp>,
+        at -> Timestamp,
+    }
+}
+
+table! {
+    greetings (channel, user) {
+        channel -> Text,
+        user -> Text,
+        text -> Text,
+    }
+}
+
+table! {
+    polls (id) {
+        id -> Integer,
+        channel -> Text,
+        question -> Text,
+        results -> Text,
+        created_at -> Timestamp,
+        closed_at -> Timestamp,
+    }
+}
+
+table! {
+    countdowns (channel, name) {
+        channel -> Text,
+        name -> Text,
+        template -> Text,
+        duration -> BigInt,
+        ends_at -> Timestamp,
+    }
+}
+
+table! {
+    reminder_stats (channel, name) {
+        channel -> Text,
+        name -> Text,
+        fired -> BigInt,
+        done -> BigInt,
+        last_fired_at -> Nullable<Timestamp>,
+        last_done_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    weather_locations (channel, user) {
+        channel -> Text,
+        user -> Text,
+        location -> Text,
+    }
+}
+
+table! {
+    eight_ball_answers (id) {
+        id -> Integer,
+        channel -> Text,
+        answer -> Text,
+        weight -> Integer,
+        added_at -> Timestamp,
+    }
+}
+
+table! {
+    triggers (channel, name) {
+        channel -> Text,
+        name -> Text,
+        pattern -> Nullable<Text>,
+        text -> Text,
+        cooldown -> Integer,
+        probability -> Integer,
+        group -> Nullable<Text>,
+        disabled -> Bool,
+    }
+}
+
+table! {
+    stream_sessions (id) {
+        id -> Integer,
+        stream_id -> Text,
+        title -> Text,
+        game -> Nullable<Text>,
+        started_at -> Timestamp,
+        ended_at -> Nullable<Timestamp>,
+        stream_stats_id -> Nullable<Integer>,
+    }
+}
+
+table! {
+    stream_session_commands (session_id, command) {
+        session_id -> Integer,
+        command -> Text,
+        uses -> Integer,
+    }
+}
@@ -0,0 +1,329 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use self::models::StreamSession;
+
+/// How often command usage is flushed to the database.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Kinds of stream events which count as subscriptions.
+const SUB_KINDS: &[&str] = &["sub", "resub", "subgift"];
+
+/// Everything recorded during a single stream session.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SessionReport {
+    /// The session being reported on.
+    pub session: StreamSession,
+    /// New followers during the stream.
+    pub follows: Vec<models::StreamEvent>,
+    /// New subscriptions, resubscriptions and gifted subscriptions during
+    /// the stream.
+    pub subs: Vec<models::StreamEvent>,
+    /// Commands used during the stream, most used first.
+    pub commands: Vec<models::StreamSessionCommand>,
+    /// Songs played during the stream.
+    pub songs: Vec<models::SongHistory>,
+    /// Afterstream messages left during the stream.
+    pub after_streams: Vec<models::AfterStream>,
+}
+
+struct Inner {
+    /// The session of the current stream, if we are live.
+    current: Option<i32>,
+    /// Command usage which hasn't been flushed yet.
+    pending: HashMap<(i32, String), i32>,
+}
+
+/// Stream sessions, which group everything that happened during a stream.
+#[derive(Clone)]
+pub struct StreamSessions {
+    db: db::Database,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl StreamSessions {
+    /// Open the stream sessions database.
+    ///
+    /// A session which was never ended is picked up as the current one, so
+    /// that restarting the bot during a stream continues its session.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        use self::schema::stream_sessions::dsl;
+
+        let current = db
+            .asyncify(move |c| {
+                Ok::<_, anyhow::Error>(
+                    dsl::stream_sessions
+                        .select(dsl::id)
+                        .filter(dsl::ended_at.is_null())
+                        .order(dsl::id.desc())
+                        .first::<i32>(c)
+                        .optional()?,
+                )
+            })
+            .await?;
+
+        Ok(Self {
+            db,
+            inner: Arc::new(Mutex::new(Inner {
+                current,
+                pending: HashMap::new(),
+            })),
+        })
+    }
+
+    /// Start the session for the given stream, returning its id.
+    ///
+    /// If a session for the same stream is already open it's continued, any
+    /// other open session is ended.
+    pub async fn start(
+        &self,
+        stream_id: &str,
+        title: &str,
+        game: Option<&str>,
+        started_at: NaiveDateTime,
+    ) -> Result<i32> {
+        use self::schema::stream_sessions::dsl;
+
+        let session = models::AddStreamSession {
+            stream_id: stream_id.to_string(),
+            title: title.to_string(),
+            game: game.map(String::from),
+            started_at,
+        };
+
+        let id = self
+            .db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    let now = Utc::now().naive_utc();
+
+                    let stale = dsl::stream_sessions.filter(
+                        dsl::ended_at
+                            .is_null()
+                            .and(dsl::stream_id.ne(&session.stream_id)),
+                    );
+
+                    diesel::update(stale)
+                        .set(dsl::ended_at.eq(now))
+                        .execute(c)?;
+
+                    let existing = dsl::stream_sessions
+                        .select(dsl::id)
+                        .filter(
+                            dsl::ended_at
+                                .is_null()
+                                .and(dsl::stream_id.eq(&session.stream_id)),
+                        )
+                        .first::<i32>(c)
+                        .optional()?;
+
+                    if let Some(id) = existing {
+                        return Ok(id);
+                    }
+
+                    diesel::insert_into(dsl::stream_sessions)
+                        .values(&session)
+                        .execute(c)?;
+
+                    Ok::<_, anyhow::Error>(db::last_insert_id(c)?)
+                })
+            })
+            .await?;
+
+        self.inner.lock().current = Some(id);
+        Ok(id)
+    }
+
+    /// End the current session, returning its id if there was one.
+    ///
+    /// `stream_stats_id` refers to the chat statistics stored for the stream.
+    pub async fn end(&self, stream_stats_id: Option<i32>) -> Result<Option<i32>> {
+        use self::schema::stream_sessions::dsl;
+
+        self.flush().await?;
+
+        let id = match self.inner.lock().current.take() {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        let now = Utc::now().naive_utc();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::update(dsl::stream_sessions.filter(dsl::id.eq(id)))
+                    .set((
+                        dsl::ended_at.eq(now),
+                        dsl::stream_stats_id.eq(stream_stats_id),
+                    ))
+                    .execute(c)?;
+
+                Ok::<_, anyhow::Error>(())
+            })
+            .await?;
+
+        Ok(Some(id))
+    }
+
+    /// The id of the current session, if we are live.
+    pub fn current(&self) -> Option<i32> {
+        self.inner.lock().current
+    }
+
+    /// Observe that the given command was used.
+    ///
+    /// Does nothing unless we are live.
+    pub fn command_used(&self, command: &str) {
+        let mut inner = self.inner.lock();
+
+        let id = match inner.current {
+            Some(id) => id,
+            None => return,
+        };
+
+        *inner
+            .pending
+            .entry((id, command.to_lowercase()))
+            .or_default() += 1;
+    }
+
+    /// List sessions, most recent first.
+    pub async fn list(&self, limit: i64) -> Result<Vec<StreamSession>> {
+        use self::schema::stream_sessions::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::stream_sessions
+                    .order((dsl::started_at.desc(), dsl::id.desc()))
+                    .limit(limit)
+                    .load::<StreamSession>(c)?)
+            })
+            .await
+    }
+
+    /// Build a report of everything recorded during the given session.
+    pub async fn report(&self, id: i32) -> Result<Option<SessionReport>> {
+        use self::schema::{
+            after_streams, song_history, stream_events, stream_session_commands, stream_sessions,
+        };
+
+        self.flush().await?;
+
+        self.db
+            .asyncify(move |c| {
+                let session = stream_sessions::table
+                    .filter(stream_sessions::id.eq(id))
+                    .first::<StreamSession>(c)
+                    .optional()?;
+
+                let session = match session {
+                    Some(session) => session,
+                    None => return Ok(None),
+                };
+
+                let started_at = session.started_at;
+                let ended_at = session.ended_at.unwrap_or_else(|| Utc::now().naive_utc());
+
+                let events = stream_events::table
+                    .filter(stream_events::at.between(started_at, ended_at))
+                    .order((stream_events::at.asc(), stream_events::id.asc()))
+                    .load::<models::StreamEvent>(c)?;
+
+                let (follows, subs): (Vec<_>, Vec<_>) = events
+                    .into_iter()
+                    .filter(|e| e.kind == "follow" || SUB_KINDS.contains(&e.kind.as_str()))
+                    .partition(|e| e.kind == "follow");
+
+                let commands = stream_session_commands::table
+                    .filter(stream_session_commands::session_id.eq(id))
+                    .order((
+                        stream_session_commands::uses.desc(),
+                        stream_session_commands::command.asc(),
+                    ))
+                    .load::<models::StreamSessionCommand>(c)?;
+
+                let songs = song_history::table
+                    .filter(song_history::played_at.between(started_at, ended_at))
+                    .order((song_history::played_at.asc(), song_history::id.asc()))
+                    .load::<models::SongHistory>(c)?;
+
+                let after_streams = after_streams::table
+                    .filter(after_streams::stream_id.eq(&session.stream_id))
+                    .order((after_streams::added_at.asc(), after_streams::id.asc()))
+                    .load::<models::AfterStream>(c)?;
+
+                Ok(Some(SessionReport {
+                    session,
+                    follows,
+                    subs,
+                    commands,
+                    songs,
+                    after_streams,
+                }))
+            })
+            .await
+    }
+
+    /// Flush all pending command usage to the database.
+    pub async fn flush(&self) -> Result<()> {
+        use self::schema::stream_session_commands::dsl;
+
+        let pending = std::mem::take(&mut self.inner.lock().pending);
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    for ((session_id, command), uses) in pending {
+                        let filter = dsl::stream_session_commands.filter(
+                            dsl::session_id
+                                .eq(session_id)
+                                .and(dsl::command.eq(&command)),
+                        );
+
+                        let updated = diesel::update(filter)
+                            .set(dsl::uses.eq(dsl::uses + uses))
+                            .execute(c)?;
+
+                        if updated == 0 {
+                            diesel::insert_into(dsl::stream_session_commands)
+                                .values(&models::StreamSessionCommand {
+                                    session_id,
+                                    command,
+                                    uses,
+                                })
+                                .execute(c)?;
+                        }
+                    }
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+
+    /// Run the loop which periodically flushes command usage to the
+    /// database.
+    pub async fn run(self) -> Result<()> {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = self.flush().await {
+                log_error!(e, "failed to flush stream session commands");
+            }
+        }
+    }
+}
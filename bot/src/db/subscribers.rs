@@ -0,0 +1,162 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+
+pub use self::models::Subscriber;
+
+/// Storage for subscription streaks and gift counts.
+#[derive(Clone)]
+pub struct Subscribers {
+    db: db::Database,
+}
+
+impl Subscribers {
+    /// Construct subscriber storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Record a subscription or resubscription by the given user.
+    ///
+    /// `months` and `streak` are the values reported by Twitch, if any.
+    pub async fn record_sub(
+        &self,
+        user: &str,
+        months: Option<u32>,
+        streak: Option<u32>,
+    ) -> Result<Subscriber> {
+        let user = user.to_lowercase();
+        let now = Utc::now().naive_utc();
+
+        self.modify(user, move |s| sub(s, months, streak, now))
+            .await
+    }
+
+    /// Record subscriptions gifted by the given user.
+    ///
+    /// `total` is the total number of gifts reported by Twitch, if shared.
+    pub async fn record_gift(&self, user: &str, total: Option<u32>) -> Result<Subscriber> {
+        let user = user.to_lowercase();
+        let now = Utc::now().naive_utc();
+        self.modify(user, move |s| gift(s, total, now)).await
+    }
+
+    /// Get the subscriber with the given name.
+    pub async fn get(&self, user: &str) -> Result<Option<Subscriber>> {
+        use self::schema::subscribers::dsl;
+
+        let user = user.to_lowercase();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::subscribers
+                    .filter(dsl::user.eq(user))
+                    .first::<models::Subscriber>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Modify the stored subscriber with the given name.
+    async fn modify<F>(&self, user: String, f: F) -> Result<Subscriber>
+    where
+        F: FnOnce(&mut Subscriber) + Send + 'static,
+    {
+        use self::schema::subscribers::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    let existing = dsl::subscribers
+                        .filter(dsl::user.eq(&user))
+                        .first::<models::Subscriber>(c)
+                        .optional()?;
+
+                    let mut subscriber = existing.unwrap_or_else(|| Subscriber {
+                        user,
+                        months: 0,
+                        streak: 0,
+                        gifts: 0,
+                        updated_at: Utc::now().naive_utc(),
+                    });
+
+                    f(&mut subscriber);
+
+                    diesel::delete(dsl::subscribers.filter(dsl::user.eq(&subscriber.user)))
+                        .execute(c)?;
+
+                    diesel::insert_into(dsl::subscribers)
+                        .values(&subscriber)
+                        .execute(c)?;
+
+                    Ok(subscriber)
+                })
+            })
+            .await
+    }
+}
+
+/// Update a subscriber after a subscription.
+///
+/// Twitch only reports the streak if the user decided to share it. If they
+/// didn't, we assume it continues from the last one we know of.
+fn sub(s: &mut Subscriber, months: Option<u32>, streak: Option<u32>, now: NaiveDateTime) {
+    s.months = match months {
+        Some(months) => months as i32,
+        None => s.months + 1,
+    };
+
+    s.streak = match streak {
+        Some(streak) => streak as i32,
+        None => s.streak + 1,
+    };
+
+    // NB: a streak can't be longer than the cumulative number of months.
+    s.streak = s.streak.min(s.months);
+    s.updated_at = now;
+}
+
+/// Update a subscriber after gifting a subscription.
+fn gift(s: &mut Subscriber, total: Option<u32>, now: NaiveDateTime) {
+    s.gifts = (s.gifts + 1).max(total.unwrap_or_default() as i32);
+    s.updated_at = now;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gift, sub, Subscriber};
+
+    #[test]
+    fn test_subscriber() {
+        let now = chrono::Utc::now().naive_utc();
+
+        let mut s = Subscriber {
+            user: String::from("setbac"),
+            months: 0,
+            streak: 0,
+            gifts: 0,
+            updated_at: now,
+        };
+
+        sub(&mut s, Some(1), None, now);
+        assert_eq!((1, 1), (s.months, s.streak));
+
+        sub(&mut s, Some(5), Some(3), now);
+        assert_eq!((5, 3), (s.months, s.streak));
+
+        sub(&mut s, None, None, now);
+        assert_eq!((6, 4), (s.months, s.streak));
+
+        gift(&mut s, None, now);
+        assert_eq!(1, s.gifts);
+
+        gift(&mut s, Some(10), now);
+        assert_eq!(10, s.gifts);
+
+        gift(&mut s, Some(3), now);
+        assert_eq!(11, s.gifts);
+    }
+}
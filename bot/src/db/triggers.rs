@@ -0,0 +1,308 @@
+use crate::db;
+use crate::template;
+use crate::utils;
+use anyhow::{anyhow, Context as _, Error};
+use diesel::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// The default cooldown of new triggers, in seconds.
+const DEFAULT_COOLDOWN: i32 = 60;
+
+/// Local database wrapper.
+#[derive(Clone)]
+struct Database(db::Database);
+
+impl Database {
+    private_database_group_fns!(triggers, Trigger, db::Key);
+
+    /// Edit the response of the given trigger, creating it if it doesn't
+    /// exist.
+    async fn edit(&self, key: &db::Key, text: &str) -> Result<(), Error> {
+        use db::schema::triggers::dsl;
+
+        let key = key.clone();
+        let text = text.to_string();
+
+        self.0
+            .asyncify(move |c| {
+                let filter = dsl::triggers
+                    .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name)));
+
+                match filter.clone().first::<db::models::Trigger>(c).optional()? {
+                    None => {
+                        let trigger = db::models::Trigger {
+                            channel: key.channel.to_string(),
+                            name: key.name.to_string(),
+                            pattern: None,
+                            text,
+                            cooldown: DEFAULT_COOLDOWN,
+                            probability: 100,
+                            group: None,
+                            disabled: false,
+                        };
+
+                        diesel::insert_into(dsl::triggers)
+                            .values(&trigger)
+                            .execute(c)?;
+                    }
+                    Some(..) => {
+                        let mut set = db::models::UpdateTrigger::default();
+                        set.text = Some(&text);
+                        diesel::update(filter).set(&set).execute(c)?;
+                    }
+                }
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Update the given trigger.
+    ///
+    /// Returns `false` if there is no such trigger.
+    async fn update(
+        &self,
+        key: &db::Key,
+        cooldown: Option<i32>,
+        probability: Option<i32>,
+    ) -> Result<bool, Error> {
+        use db::schema::triggers::dsl;
+
+        let key = key.clone();
+
+        self.0
+            .asyncify(move |c| {
+                let mut set = db::models::UpdateTrigger::default();
+                set.cooldown = cooldown;
+                set.probability = probability;
+
+                let count = diesel::update(
+                    dsl::triggers
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name))),
+                )
+                .set(&set)
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Edit the pattern of a trigger.
+    ///
+    /// Returns `false` if there is no such trigger.
+    async fn edit_pattern(&self, key: &db::Key, pattern: &regex::Regex) -> Result<bool, Error> {
+        use db::schema::triggers::dsl;
+
+        let key = key.clone();
+        let pattern = pattern.as_str().to_string();
+
+        self.0
+            .asyncify(move |c| {
+                let count = diesel::update(
+                    dsl::triggers
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name))),
+                )
+                .set(dsl::pattern.eq(Some(pattern)))
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+}
+
+/// Triggers which respond to patterns in chat.
+#[derive(Clone)]
+pub struct Triggers {
+    inner: Arc<RwLock<HashMap<db::Key, Arc<Trigger>>>>,
+    db: Database,
+}
+
+impl Triggers {
+    database_group_fns!(Trigger, db::Key);
+
+    /// Construct a new triggers store with a db.
+    pub async fn load(db: db::Database) -> Result<Triggers, Error> {
+        let db = Database(db);
+
+        let mut inner = HashMap::new();
+
+        for trigger in db.list().await? {
+            let trigger = Trigger::from_db(&trigger)?;
+            inner.insert(trigger.key.clone(), Arc::new(trigger));
+        }
+
+        Ok(Triggers {
+            inner: Arc::new(RwLock::new(inner)),
+            db,
+        })
+    }
+
+    /// Edit the response of the given trigger, creating it if it doesn't
+    /// exist.
+    pub async fn edit(
+        &self,
+        channel: &str,
+        name: &str,
+        template: template::Template,
+    ) -> Result<(), Error> {
+        let key = db::Key::new(channel, name);
+        self.db.edit(&key, template.source()).await?;
+        self.refresh(key).await
+    }
+
+    /// Edit the pattern of the given trigger.
+    pub async fn edit_pattern(
+        &self,
+        channel: &str,
+        name: &str,
+        pattern: regex::Regex,
+    ) -> Result<bool, Error> {
+        let key = db::Key::new(channel, name);
+
+        if !self.db.edit_pattern(&key, &pattern).await? {
+            return Ok(false);
+        }
+
+        self.refresh(key).await?;
+        Ok(true)
+    }
+
+    /// Edit the cooldown of the given trigger.
+    pub async fn edit_cooldown(
+        &self,
+        channel: &str,
+        name: &str,
+        cooldown: utils::Duration,
+    ) -> Result<bool, Error> {
+        let key = db::Key::new(channel, name);
+        let cooldown = cooldown.num_seconds() as i32;
+
+        if !self.db.update(&key, Some(cooldown), None).await? {
+            return Ok(false);
+        }
+
+        self.refresh(key).await?;
+        Ok(true)
+    }
+
+    /// Edit the probability of the given trigger.
+    pub async fn edit_probability(
+        &self,
+        channel: &str,
+        name: &str,
+        probability: u32,
+    ) -> Result<bool, Error> {
+        let key = db::Key::new(channel, name);
+
+        if !self.db.update(&key, None, Some(probability as i32)).await? {
+            return Ok(false);
+        }
+
+        self.refresh(key).await?;
+        Ok(true)
+    }
+
+    /// Reload the given trigger from the database.
+    async fn refresh(&self, key: db::Key) -> Result<(), Error> {
+        let mut inner = self.inner.write().await;
+
+        match self.db.fetch(&key).await? {
+            Some(trigger) if !trigger.disabled => {
+                inner.insert(key, Arc::new(Trigger::from_db(&trigger)?));
+            }
+            _ => {
+                inner.remove(&key);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    /// Key for the trigger.
+    pub key: db::Key,
+    /// Pattern which causes the trigger to fire.
+    pub pattern: Option<regex::Regex>,
+    pub template: template::Template,
+    vars: HashSet<String>,
+    /// Cooldown between each time the trigger fires.
+    pub cooldown: utils::Duration,
+    /// The chance in percent that the trigger fires when matched.
+    pub probability: u32,
+    pub group: Option<String>,
+    pub disabled: bool,
+}
+
+impl Trigger {
+    pub const NAME: &'static str = "trigger";
+
+    /// Load a trigger from the database.
+    pub fn from_db(trigger: &db::models::Trigger) -> Result<Trigger, Error> {
+        let template = template::Template::compile(&trigger.text)
+            .with_context(|| anyhow!("failed to compile trigger `{:?}` from db", trigger))?;
+
+        let pattern = match trigger.pattern.as_ref() {
+            Some(pattern) => Some(
+                regex::Regex::new(pattern)
+                    .with_context(|| anyhow!("bad pattern for trigger `{:?}`", trigger))?,
+            ),
+            None => None,
+        };
+
+        let vars = template.vars();
+
+        Ok(Trigger {
+            key: db::Key::new(&trigger.channel, &trigger.name),
+            pattern,
+            template,
+            vars,
+            cooldown: utils::Duration::seconds(trigger.cooldown.max(0) as u64),
+            probability: trigger.probability.max(0) as u32,
+            group: trigger.group.clone(),
+            disabled: trigger.disabled,
+        })
+    }
+
+    /// Test if the given message matches the trigger.
+    pub fn is_match(&self, message: &str) -> bool {
+        match self.pattern.as_ref() {
+            Some(pattern) => pattern.is_match(message),
+            None => false,
+        }
+    }
+
+    /// Render the response of the trigger.
+    pub fn render<T>(&self, data: &T) -> Result<String, Error>
+    where
+        T: serde::Serialize,
+    {
+        Ok(self.template.render_to_string(data)?)
+    }
+
+    /// Test if the response of the trigger uses the given var.
+    pub fn has_var(&self, var: &str) -> bool {
+        self.vars.contains(var)
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "pattern = {pattern}, template = \"{template}\", cooldown = {cooldown}, probability = {probability}%, group = {group}, disabled = {disabled}",
+            pattern = self.pattern.as_ref().map(|p| p.as_str()).unwrap_or("*none*"),
+            template = self.template,
+            cooldown = self.cooldown,
+            probability = self.probability,
+            group = self.group.as_deref().unwrap_or("*none*"),
+            disabled = self.disabled,
+        )
+    }
+}
@@ -0,0 +1,277 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rand::seq::SliceRandom as _;
+use std::collections::HashMap;
+
+pub use self::models::AddTriviaWin;
+
+/// A trivia question.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TriviaQuestion {
+    pub id: i32,
+    pub added_at: NaiveDateTime,
+    pub category: String,
+    pub question: String,
+    /// Accepted answers, the first one being the one shown when nobody
+    /// answers correctly.
+    pub answers: Vec<String>,
+}
+
+impl TriviaQuestion {
+    /// Convert a question stored in the database.
+    fn from_db(q: models::TriviaQuestion) -> Result<Self> {
+        Ok(Self {
+            id: q.id,
+            added_at: q.added_at,
+            category: q.category,
+            question: q.question,
+            answers: serde_json::from_str(&q.answers)?,
+        })
+    }
+}
+
+/// A question to add to the question bank.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct NewTriviaQuestion {
+    pub category: String,
+    pub question: String,
+    pub answers: Vec<String>,
+}
+
+/// The number of questions a user has answered correctly.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct TriviaScore {
+    pub user: String,
+    pub wins: u32,
+}
+
+/// Storage for trivia questions and the users who answered them.
+#[derive(Clone)]
+pub struct Trivia {
+    db: db::Database,
+}
+
+impl Trivia {
+    /// Construct trivia storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Add the given questions to the question bank.
+    ///
+    /// Returns the number of questions added.
+    pub async fn import(&self, questions: Vec<NewTriviaQuestion>) -> Result<usize> {
+        use self::schema::trivia_questions::dsl;
+
+        let mut values = Vec::new();
+
+        for q in questions {
+            values.push(models::AddTriviaQuestion {
+                category: q.category,
+                question: q.question,
+                answers: serde_json::to_string(&q.answers)?,
+            });
+        }
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    for value in &values {
+                        diesel::insert_into(dsl::trivia_questions)
+                            .values(value)
+                            .execute(c)?;
+                    }
+
+                    Ok(values.len())
+                })
+            })
+            .await
+    }
+
+    /// List all questions, ordered by category.
+    pub async fn list(&self) -> Result<Vec<TriviaQuestion>> {
+        use self::schema::trivia_questions::dsl;
+
+        let questions = self
+            .db
+            .asyncify(move |c| {
+                Ok::<_, anyhow::Error>(
+                    dsl::trivia_questions
+                        .order((dsl::category, dsl::id))
+                        .load::<models::TriviaQuestion>(c)?,
+                )
+            })
+            .await?;
+
+        questions.into_iter().map(TriviaQuestion::from_db).collect()
+    }
+
+    /// List all categories which have questions.
+    pub async fn categories(&self) -> Result<Vec<String>> {
+        use self::schema::trivia_questions::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::trivia_questions
+                    .select(dsl::category)
+                    .distinct()
+                    .order(dsl::category)
+                    .load::<String>(c)?)
+            })
+            .await
+    }
+
+    /// Pick up to `limit` random questions, optionally from a single category.
+    pub async fn pick(
+        &self,
+        category: Option<String>,
+        limit: usize,
+    ) -> Result<Vec<TriviaQuestion>> {
+        use self::schema::trivia_questions::dsl;
+
+        let questions = self
+            .db
+            .asyncify(move |c| {
+                let mut ids = match category {
+                    Some(category) => dsl::trivia_questions
+                        .select(dsl::id)
+                        .filter(dsl::category.eq(category))
+                        .load::<i32>(c)?,
+                    None => dsl::trivia_questions.select(dsl::id).load::<i32>(c)?,
+                };
+
+                ids.shuffle(&mut rand::thread_rng());
+                ids.truncate(limit);
+
+                let mut questions = dsl::trivia_questions
+                    .filter(dsl::id.eq_any(ids.clone()))
+                    .load::<models::TriviaQuestion>(c)?;
+
+                // NB: keep the shuffled order.
+                questions.sort_by_key(|q| ids.iter().position(|id| *id == q.id));
+                Ok::<_, anyhow::Error>(questions)
+            })
+            .await?;
+
+        questions.into_iter().map(TriviaQuestion::from_db).collect()
+    }
+
+    /// Delete the question with the given id.
+    ///
+    /// Returns `true` if the question was deleted.
+    pub async fn delete(&self, id: i32) -> Result<bool> {
+        use self::schema::trivia_questions::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                let count =
+                    diesel::delete(dsl::trivia_questions.filter(dsl::id.eq(id))).execute(c)?;
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Record that a user answered a question correctly.
+    pub async fn push_win(&self, win: AddTriviaWin) -> Result<()> {
+        use self::schema::trivia_wins::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::trivia_wins)
+                    .values(&win)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// When the most recent stream that had correct answers started.
+    pub async fn latest_stream(&self) -> Result<Option<NaiveDateTime>> {
+        use self::schema::trivia_wins::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::trivia_wins
+                    .select(dsl::stream_started_at)
+                    .filter(dsl::stream_started_at.is_not_null())
+                    .order(dsl::stream_started_at.desc())
+                    .first::<Option<NaiveDateTime>>(c)
+                    .optional()?
+                    .flatten())
+            })
+            .await
+    }
+
+    /// The users with the most correct answers during the stream which
+    /// started at the given time, most correct answers first.
+    pub async fn scoreboard(
+        &self,
+        stream: NaiveDateTime,
+        limit: usize,
+    ) -> Result<Vec<TriviaScore>> {
+        use self::schema::trivia_wins::dsl;
+
+        let users = self
+            .db
+            .asyncify(move |c| {
+                Ok::<_, anyhow::Error>(
+                    dsl::trivia_wins
+                        .select(dsl::user)
+                        .filter(dsl::stream_started_at.eq(stream))
+                        .load::<String>(c)?,
+                )
+            })
+            .await?;
+
+        let mut scores = scoreboard(&users);
+        scores.truncate(limit);
+        Ok(scores)
+    }
+}
+
+/// Count the number of wins of each user, most wins first.
+fn scoreboard(users: &[String]) -> Vec<TriviaScore> {
+    let mut wins = HashMap::<_, u32>::new();
+
+    for user in users {
+        *wins.entry(user.as_str()).or_default() += 1;
+    }
+
+    let mut scores = wins
+        .into_iter()
+        .map(|(user, wins)| TriviaScore {
+            user: user.to_string(),
+            wins,
+        })
+        .collect::<Vec<_>>();
+
+    scores.sort_by(|a, b| b.wins.cmp(&a.wins).then_with(|| a.user.cmp(&b.user)));
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scoreboard, TriviaScore};
+
+    #[test]
+    fn test_scoreboard() {
+        let users = ["bob", "alice", "bob", "carol", "alice", "bob"]
+            .iter()
+            .map(|u| u.to_string())
+            .collect::<Vec<_>>();
+
+        let score = |user: &str, wins| TriviaScore {
+            user: user.to_string(),
+            wins,
+        };
+
+        assert_eq!(
+            vec![score("bob", 3), score("alice", 2), score("carol", 1)],
+            scoreboard(&users)
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::auth::Role;
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub use self::models::Variable;
+
+/// Persistent variables, like the number of greetings, which can be set from
+/// chat and used in custom commands.
+///
+/// Variables are kept in memory, since they are looked up for every command
+/// that is run.
+#[derive(Clone)]
+pub struct Variables {
+    inner: Arc<RwLock<HashMap<db::Key, Variable>>>,
+    db: db::Database,
+}
+
+impl Variables {
+    /// Load all variables from the database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        use self::schema::variables::dsl;
+
+        let variables = db
+            .asyncify(move |c| Ok::<_, anyhow::Error>(dsl::variables.load::<Variable>(c)?))
+            .await?;
+
+        let inner = variables
+            .into_iter()
+            .map(|v| (db::Key::new(&v.channel, &v.name), v))
+            .collect();
+
+        Ok(Self {
+            inner: Arc::new(RwLock::new(inner)),
+            db,
+        })
+    }
+
+    /// Get a single variable.
+    pub async fn get(&self, channel: &str, name: &str) -> Option<Variable> {
+        let key = db::Key::new(channel, name);
+        self.inner.read().await.get(&key).cloned()
+    }
+
+    /// Get the current value of every variable in the given channel, by name.
+    pub async fn values(&self, channel: &str) -> HashMap<String, String> {
+        self.inner
+            .read()
+            .await
+            .iter()
+            .filter(|(key, _)| key.channel == channel)
+            .map(|(key, v)| (key.name.clone(), v.value.clone()))
+            .collect()
+    }
+
+    /// List all variables in the given channel, ordered by name.
+    pub async fn list(&self, channel: &str) -> Vec<Variable> {
+        let mut out = self
+            .inner
+            .read()
+            .await
+            .values()
+            .filter(|v| v.channel == channel)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        out.sort_by(|a, b| a.name.cmp(&b.name));
+        out
+    }
+
+    /// Set the value of a variable, creating it if it doesn't exist.
+    ///
+    /// The role which is allowed to set an existing variable is preserved.
+    pub async fn set(
+        &self,
+        channel: &str,
+        name: &str,
+        value: &str,
+        user: Option<&str>,
+    ) -> Result<()> {
+        let key = db::Key::new(channel, name);
+        let mut inner = self.inner.write().await;
+
+        let variable = Variable {
+            channel: key.channel.clone(),
+            name: key.name.clone(),
+            value: value.to_string(),
+            role: inner.get(&key).and_then(|v| v.role),
+            updated_by: user.map(str::to_lowercase),
+            updated_at: Utc::now().naive_utc(),
+        };
+
+        self.store(variable.clone()).await?;
+        inner.insert(key, variable);
+        Ok(())
+    }
+
+    /// Set the role which is allowed to set the given variable.
+    ///
+    /// Returns `false` if the variable doesn't exist.
+    pub async fn set_role(&self, channel: &str, name: &str, role: Option<Role>) -> Result<bool> {
+        let key = db::Key::new(channel, name);
+        let mut inner = self.inner.write().await;
+
+        let variable = match inner.get_mut(&key) {
+            Some(variable) => variable,
+            None => return Ok(false),
+        };
+
+        let mut updated = variable.clone();
+        updated.role = role;
+        self.store(updated.clone()).await?;
+        *variable = updated;
+        Ok(true)
+    }
+
+    /// Delete a variable.
+    ///
+    /// Returns `false` if the variable doesn't exist.
+    pub async fn delete(&self, channel: &str, name: &str) -> Result<bool> {
+        use self::schema::variables::dsl;
+
+        let key = db::Key::new(channel, name);
+        let mut inner = self.inner.write().await;
+
+        if !inner.contains_key(&key) {
+            return Ok(false);
+        }
+
+        let k = key.clone();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(
+                    dsl::variables.filter(dsl::channel.eq(&k.channel).and(dsl::name.eq(&k.name))),
+                )
+                .execute(c)?;
+
+                Ok::<_, anyhow::Error>(())
+            })
+            .await?;
+
+        inner.remove(&key);
+        Ok(true)
+    }
+
+    /// Store the given variable in the database.
+    async fn store(&self, variable: Variable) -> Result<()> {
+        use self::schema::variables::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    diesel::delete(
+                        dsl::variables.filter(
+                            dsl::channel
+                                .eq(&variable.channel)
+                                .and(dsl::name.eq(&variable.name)),
+                        ),
+                    )
+                    .execute(c)?;
+
+                    diesel::insert_into(dsl::variables)
+                        .values(&variable)
+                        .execute(c)?;
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+}
@@ -0,0 +1,144 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::Utc;
+use diesel::prelude::*;
+
+pub use self::models::ViewerQueueEntry;
+
+/// A lobby of viewers waiting to play with the streamer.
+#[derive(Clone)]
+pub struct ViewerQueue {
+    db: db::Database,
+}
+
+impl ViewerQueue {
+    /// Construct viewer queue storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Add a user to the end of the queue.
+    ///
+    /// Returns `false` if the user is already in the queue.
+    pub async fn join(
+        &self,
+        channel: &str,
+        user: &str,
+        note: Option<&str>,
+        subscriber: bool,
+    ) -> Result<bool> {
+        use self::schema::viewer_queue::dsl;
+
+        let entry = ViewerQueueEntry {
+            channel: channel.to_string(),
+            user: user.to_string(),
+            note: note.map(String::from),
+            subscriber,
+            joined_at: Utc::now().naive_utc(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                let filter = dsl::viewer_queue.filter(
+                    dsl::channel
+                        .eq(&entry.channel)
+                        .and(dsl::user.eq(&entry.user)),
+                );
+
+                let exists = filter.first::<ViewerQueueEntry>(c).optional()?.is_some();
+
+                if exists {
+                    return Ok(false);
+                }
+
+                diesel::insert_into(dsl::viewer_queue)
+                    .values(&entry)
+                    .execute(c)?;
+
+                Ok(true)
+            })
+            .await
+    }
+
+    /// Remove a user from the queue.
+    ///
+    /// Returns `false` if the user isn't in the queue.
+    pub async fn leave(&self, channel: &str, user: &str) -> Result<bool> {
+        use self::schema::viewer_queue::dsl;
+
+        let channel = channel.to_string();
+        let user = user.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::viewer_queue.filter(dsl::channel.eq(&channel).and(dsl::user.eq(&user))),
+                )
+                .execute(c)?;
+
+                Ok(count > 0)
+            })
+            .await
+    }
+
+    /// List everyone in the queue, in the order they will be picked.
+    ///
+    /// If `sub_priority` is set, subscribers are placed ahead of everyone
+    /// else.
+    pub async fn list(&self, channel: &str, sub_priority: bool) -> Result<Vec<ViewerQueueEntry>> {
+        use self::schema::viewer_queue::dsl;
+
+        let channel = channel.to_string();
+
+        let mut entries = self
+            .db
+            .asyncify(move |c| {
+                Ok::<_, anyhow::Error>(
+                    dsl::viewer_queue
+                        .filter(dsl::channel.eq(channel))
+                        .order(dsl::joined_at)
+                        .load::<ViewerQueueEntry>(c)?,
+                )
+            })
+            .await?;
+
+        if sub_priority {
+            // NB: sorting is stable, so the join order is kept within each
+            // group.
+            entries.sort_by_key(|e| !e.subscriber);
+        }
+
+        Ok(entries)
+    }
+
+    /// Remove and return the next user in the queue, if any.
+    pub async fn next(
+        &self,
+        channel: &str,
+        sub_priority: bool,
+    ) -> Result<Option<ViewerQueueEntry>> {
+        let entry = match self.list(channel, sub_priority).await?.into_iter().next() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        self.leave(&entry.channel, &entry.user).await?;
+        Ok(Some(entry))
+    }
+
+    /// Remove everyone from the queue.
+    pub async fn clear(&self, channel: &str) -> Result<()> {
+        use self::schema::viewer_queue::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(dsl::viewer_queue.filter(dsl::channel.eq(channel))).execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+}
@@ -0,0 +1,91 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::WeatherLocation;
+
+/// Weather locations saved by individual users.
+#[derive(Clone)]
+pub struct WeatherLocations {
+    db: db::Database,
+}
+
+impl WeatherLocations {
+    /// Construct weather location storage.
+    pub fn new(db: db::Database) -> Self {
+        Self { db }
+    }
+
+    /// Get the location saved by the given user, if any.
+    pub async fn get(&self, channel: &str, user: &str) -> Result<Option<WeatherLocation>> {
+        use self::schema::weather_locations::dsl;
+
+        let key = db::Key::new(channel, user);
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::weather_locations
+                    .filter(dsl::channel.eq(&key.channel).and(dsl::user.eq(&key.name)))
+                    .first::<WeatherLocation>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Save the location of the given user.
+    pub async fn set(&self, channel: &str, user: &str, location: &str) -> Result<()> {
+        use self::schema::weather_locations::dsl;
+
+        let key = db::Key::new(channel, user);
+
+        let location = WeatherLocation {
+            channel: key.channel,
+            user: key.name,
+            location: location.to_string(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    diesel::delete(
+                        dsl::weather_locations.filter(
+                            dsl::channel
+                                .eq(&location.channel)
+                                .and(dsl::user.eq(&location.user)),
+                        ),
+                    )
+                    .execute(c)?;
+
+                    diesel::insert_into(dsl::weather_locations)
+                        .values(&location)
+                        .execute(c)?;
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+
+    /// Delete the location saved by the given user.
+    ///
+    /// Returns `false` if the user hasn't saved a location.
+    pub async fn delete(&self, channel: &str, user: &str) -> Result<bool> {
+        use self::schema::weather_locations::dsl;
+
+        let key = db::Key::new(channel, user);
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::weather_locations
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::user.eq(&key.name))),
+                )
+                .execute(c)?;
+
+                Ok(count > 0)
+            })
+            .await
+    }
+}
@@ -128,6 +128,11 @@ impl Words {
         })
     }
 
+    /// List all words in the bad words list.
+    pub async fn list(&self) -> Result<Vec<db::models::BadWord>, anyhow::Error> {
+        self.db.list().await
+    }
+
     /// Insert a word into the bad words list.
     pub async fn edit(&self, word: &str, why: Option<&str>) -> Result<(), anyhow::Error> {
         self.db.edit(word, why).await?;
@@ -0,0 +1,314 @@
+//! Posts notifications to Discord webhooks when the stream goes live, and
+//! optionally the afterstream messages when it ends.
+//!
+//! This doesn't require a full Discord bot, only webhook URLs which are
+//! configured in `discord/webhooks`.
+
+use crate::api::discord::{Embed, EmbedField, EmbedImage, WebhookMessage};
+use crate::api::{self, twitch};
+use crate::db;
+use crate::injector::Injector;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::template::Template;
+use anyhow::Result;
+use chrono::Utc;
+
+/// The default template for going live messages.
+const DEFAULT_LIVE_TEMPLATE: &str = "{{name}} is now live! {{url}}";
+/// The maximum length of the content of a Discord message.
+const MAX_CONTENT: usize = 2000;
+/// The color of embeds, which is Twitch purple.
+const EMBED_COLOR: u32 = 0x9146ff;
+
+/// An event which is posted to Discord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The stream started.
+    StreamStarted,
+    /// The stream stopped.
+    StreamStopped,
+}
+
+/// Handle used to send events to Discord.
+#[derive(Clone)]
+pub struct Discord {
+    tx: mpsc::UnboundedSender<Event>,
+}
+
+impl Discord {
+    /// Send the given event to Discord.
+    pub fn send(&self, event: Event) {
+        let _ = self.tx.unbounded_send(event);
+    }
+}
+
+/// Set up Discord notifications.
+pub fn setup(
+    injector: &Injector,
+    settings: settings::Settings,
+) -> (Discord, impl Future<Output = Result<()>>) {
+    let (tx, rx) = mpsc::unbounded();
+    let future = run(injector.clone(), settings, rx);
+    (Discord { tx }, future)
+}
+
+async fn run(
+    injector: Injector,
+    settings: settings::Settings,
+    rx: mpsc::UnboundedReceiver<Event>,
+) -> Result<()> {
+    let settings = settings.scoped("discord");
+    let discord = api::Discord::new();
+
+    let (mut enabled_stream, mut enabled) = settings.stream("enabled").or_default().await?;
+    let (mut webhooks_stream, mut webhooks) = settings
+        .stream::<Vec<String>>("webhooks")
+        .or_default()
+        .await?;
+    let (mut live_template_stream, mut live_template) = settings
+        .stream("live-template")
+        .or_with(Template::compile(DEFAULT_LIVE_TEMPLATE)?)
+        .await?;
+    let (mut post_after_streams_stream, mut post_after_streams) =
+        settings.stream("after-streams").or_default().await?;
+    let (mut channel_stream, mut channel) = injector.stream::<stream_info::Channel>().await;
+    let (mut after_streams_stream, mut after_streams) = injector.stream::<db::AfterStreams>().await;
+
+    let mut rx = rx.fuse();
+
+    loop {
+        let event = futures::select! {
+            update = enabled_stream.select_next_some() => {
+                enabled = update;
+                continue;
+            }
+            update = webhooks_stream.select_next_some() => {
+                webhooks = update;
+                continue;
+            }
+            update = live_template_stream.select_next_some() => {
+                live_template = update;
+                continue;
+            }
+            update = post_after_streams_stream.select_next_some() => {
+                post_after_streams = update;
+                continue;
+            }
+            update = channel_stream.select_next_some() => {
+                channel = update;
+                continue;
+            }
+            update = after_streams_stream.select_next_some() => {
+                after_streams = update;
+                continue;
+            }
+            event = rx.select_next_some() => event,
+        };
+
+        if !enabled || webhooks.is_empty() {
+            continue;
+        }
+
+        let message = match event {
+            Event::StreamStarted => {
+                let channel = match channel.as_ref() {
+                    Some(channel) => channel,
+                    None => continue,
+                };
+
+                match live_message(channel, &live_template).await {
+                    Ok(message) => message,
+                    Err(e) => {
+                        log_error!(e, "failed to build going live message for discord");
+                        continue;
+                    }
+                }
+            }
+            Event::StreamStopped => {
+                if !post_after_streams {
+                    continue;
+                }
+
+                let after_streams = match after_streams.as_ref() {
+                    Some(after_streams) => after_streams,
+                    None => continue,
+                };
+
+                let list = match after_streams.list().await {
+                    Ok(list) => list,
+                    Err(e) => {
+                        log_error!(e, "failed to list afterstreams for discord");
+                        continue;
+                    }
+                };
+
+                let content = match after_streams_content(&list) {
+                    Some(content) => content,
+                    None => continue,
+                };
+
+                WebhookMessage {
+                    content,
+                    ..Default::default()
+                }
+            }
+        };
+
+        for url in &webhooks {
+            let discord = discord.clone();
+            let url = url.clone();
+            let message = message.clone();
+
+            tokio::spawn(async move {
+                if let Err(e) = discord.execute_webhook(&url, &message).await {
+                    log_error!(e, "failed to post to discord webhook");
+                }
+            });
+        }
+    }
+}
+
+/// Build the message posted when the stream goes live.
+async fn live_message(
+    channel: &stream_info::Channel,
+    template: &Template,
+) -> Result<WebhookMessage> {
+    let user = channel.user();
+    let url = format!("https://twitch.tv/{}", user.name);
+    let stream = channel.stream();
+
+    let game = match stream.as_ref().and_then(|s| s.game_id.as_deref()) {
+        Some(id) if !id.is_empty() => channel.game_by_id(id).await?,
+        _ => None,
+    };
+
+    let title = stream.as_ref().map(|s| s.title.as_str());
+    let game_name = game
+        .as_ref()
+        .map(|g| g.name.as_str())
+        .or_else(|| stream.as_ref().and_then(|s| s.game_name.as_deref()));
+
+    let content = template.render_to_string(&LiveVars {
+        name: &user.display_name,
+        url: &url,
+        title,
+        game: game_name,
+    })?;
+
+    let embed = Embed {
+        title: Some(title.unwrap_or(&user.display_name).to_string()),
+        url: Some(url),
+        timestamp: Some(Utc::now()),
+        color: Some(EMBED_COLOR),
+        thumbnail: game
+            .as_ref()
+            .and_then(|g| g.box_art(285, 380))
+            .map(|url| EmbedImage { url }),
+        image: stream.as_ref().map(thumbnail).map(|url| EmbedImage { url }),
+        fields: game_name
+            .map(|game| EmbedField {
+                name: String::from("Game"),
+                value: game.to_string(),
+                inline: true,
+            })
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    };
+
+    Ok(WebhookMessage {
+        content,
+        embeds: vec![embed],
+        ..Default::default()
+    })
+}
+
+/// Variables available in going live messages.
+#[derive(serde::Serialize)]
+struct LiveVars<'a> {
+    name: &'a str,
+    url: &'a str,
+    title: Option<&'a str>,
+    game: Option<&'a str>,
+}
+
+/// Get the thumbnail of the stream.
+fn thumbnail(stream: &twitch::Stream) -> String {
+    stream
+        .thumbnail_url
+        .replace("{width}", "1280")
+        .replace("{height}", "720")
+}
+
+/// Format the afterstream messages to post, truncated to fit in a single
+/// Discord message.
+///
+/// Returns `None` if there are no afterstream messages.
+fn after_streams_content(after_streams: &[db::AfterStream]) -> Option<String> {
+    if after_streams.is_empty() {
+        return None;
+    }
+
+    let mut content = format!("Afterstream messages ({}):", after_streams.len());
+
+    for (n, a) in after_streams.iter().enumerate() {
+        let line = format!("\n**{}**: {}", a.user, a.text);
+        let more = format!("\n.. and {} more", after_streams.len() - n);
+
+        if content.len() + line.len() + more.len() > MAX_CONTENT {
+            content.push_str(&more);
+            break;
+        }
+
+        content.push_str(&line);
+    }
+
+    Some(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{after_streams_content, MAX_CONTENT};
+    use crate::db::AfterStream;
+    use chrono::NaiveDateTime;
+
+    fn after_stream(user: &str, text: &str) -> AfterStream {
+        AfterStream {
+            id: 0,
+            channel: None,
+            added_at: NaiveDateTime::from_timestamp(0, 0),
+            user: user.to_string(),
+            text: text.to_string(),
+            stream_id: None,
+            status: String::from("open"),
+            resolved_at: None,
+        }
+    }
+
+    #[test]
+    fn test_after_streams_content() {
+        assert_eq!(None, after_streams_content(&[]));
+
+        let content = after_streams_content(&[
+            after_stream("turtle", "great stream!"),
+            after_stream("setbac", "play more gtav"),
+        ]);
+
+        assert_eq!(
+            Some(
+                "Afterstream messages (2):\n**turtle**: great stream!\n**setbac**: play more gtav"
+            ),
+            content.as_deref()
+        );
+
+        let long = "x".repeat(500);
+        let many = (0..10)
+            .map(|_| after_stream("turtle", &long))
+            .collect::<Vec<_>>();
+
+        let content = after_streams_content(&many).unwrap();
+        assert!(content.len() <= MAX_CONTENT);
+        assert!(content.ends_with(".. and 7 more"));
+    }
+}
@@ -0,0 +1,336 @@
+//! Emote usage statistics per channel.
+//!
+//! Usage is counted in memory as messages arrive and periodically flushed into
+//! sled, bucketed by channel and day. Only the last [WINDOW_DAYS] days are
+//! kept around, so looking up the most used emotes only has to merge a handful
+//! of small entries.
+
+use crate::api::twitch::Channel;
+use crate::emotes::{Emotes, Provider};
+use crate::irc;
+use crate::prelude::*;
+use crate::storage::sled;
+use crate::task;
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How many days of emote usage are kept.
+pub const WINDOW_DAYS: i64 = 30;
+/// How often pending usage is flushed to storage.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+/// How often storage is compacted.
+const COMPACT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Format of the day in storage keys.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Usage of a single emote.
+#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
+struct Usage {
+    /// Where the emote comes from, if known.
+    #[serde(default)]
+    provider: Option<Provider>,
+    /// Number of times the emote has been used.
+    count: u64,
+}
+
+/// Usage of all emotes by their code.
+type Counts = HashMap<String, Usage>;
+
+/// How many times an emote has been used in the rolling window.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct EmoteUsage {
+    /// The code of the emote.
+    pub emote: String,
+    /// Where the emote comes from, if known.
+    pub provider: Option<Provider>,
+    /// Number of times the emote has been used.
+    pub count: u64,
+}
+
+struct Inner {
+    tree: Arc<sled::Tree>,
+    enabled: settings::Var<bool>,
+    /// Usage which hasn't been flushed yet, by channel and day.
+    pending: Mutex<HashMap<(String, NaiveDate), Counts>>,
+}
+
+/// Emote usage statistics.
+#[derive(Clone)]
+pub struct EmoteStats {
+    inner: Arc<Inner>,
+}
+
+impl EmoteStats {
+    /// Construct new emote statistics backed by the given tree.
+    pub async fn new(tree: Arc<sled::Tree>, settings: settings::Settings) -> Result<Self> {
+        Ok(Self {
+            inner: Arc::new(Inner {
+                tree,
+                enabled: settings.var("enabled", false).await?,
+                pending: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    /// Test if emote usage is being counted.
+    pub async fn is_enabled(&self) -> bool {
+        self.inner.enabled.load().await
+    }
+
+    /// Observe a single chat message in the given channel.
+    ///
+    /// Callers should check [EmoteStats::is_enabled] first.
+    pub async fn observe(
+        &self,
+        emotes: &Emotes,
+        tags: &irc::Tags,
+        channel: &Channel,
+        message: &str,
+    ) -> Result<()> {
+        let found = emotes.find(tags, channel, message).await?;
+
+        if found.is_empty() {
+            return Ok(());
+        }
+
+        let today = Utc::now().naive_utc().date();
+
+        let mut pending = self.inner.pending.lock();
+        let counts = pending
+            .entry((channel.name.to_lowercase(), today))
+            .or_default();
+
+        for (emote, provider) in found {
+            add(counts, emote, provider, 1);
+        }
+
+        Ok(())
+    }
+
+    /// Get the `limit` most used emotes in the given channel over the last
+    /// [WINDOW_DAYS] days, including usage which hasn't been flushed yet.
+    pub async fn top(&self, channel: &str, limit: usize) -> Result<Vec<EmoteUsage>> {
+        let channel = channel.to_lowercase();
+        let cutoff = cutoff(Utc::now().naive_utc().date());
+
+        let mut counts = Counts::new();
+
+        for ((c, day), pending) in self.inner.pending.lock().iter() {
+            if *c == channel && *day >= cutoff {
+                merge(&mut counts, pending);
+            }
+        }
+
+        let tree = self.inner.tree.clone();
+
+        let counts = task::asyncify(move || {
+            let prefix = format!("{}/", channel);
+
+            for entry in tree.scan_prefix(prefix.as_bytes()) {
+                let (key, value) = entry?;
+
+                match parse_day(&key) {
+                    Some(day) if day >= cutoff => (),
+                    _ => continue,
+                }
+
+                match serde_cbor::from_slice::<Counts>(&value) {
+                    Ok(stored) => merge(&mut counts, &stored),
+                    Err(e) => log::warn!("ignoring bad emote stats: {}", e),
+                }
+            }
+
+            Ok::<_, anyhow::Error>(counts)
+        })
+        .await?;
+
+        Ok(rank(counts, limit))
+    }
+
+    /// Flush all pending usage to storage.
+    pub async fn flush(&self) -> Result<()> {
+        let pending = std::mem::take(&mut *self.inner.pending.lock());
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let tree = self.inner.tree.clone();
+
+        task::asyncify(move || {
+            for ((channel, day), pending) in pending {
+                let key = key(&channel, day);
+
+                tree.update_and_fetch(key.as_bytes(), |old| {
+                    let mut counts = match old.map(serde_cbor::from_slice::<Counts>) {
+                        Some(Ok(counts)) => counts,
+                        Some(Err(e)) => {
+                            log::warn!("discarding bad emote stats for `{}`: {}", key, e);
+                            Counts::new()
+                        }
+                        None => Counts::new(),
+                    };
+
+                    merge(&mut counts, &pending);
+                    serde_cbor::to_vec(&counts).ok()
+                })?;
+            }
+
+            tree.flush()?;
+            Ok::<_, anyhow::Error>(())
+        })
+        .await
+    }
+
+    /// Compact storage by dropping days which have fallen out of the rolling
+    /// window.
+    pub async fn compact(&self) -> Result<()> {
+        let cutoff = cutoff(Utc::now().naive_utc().date());
+        let tree = self.inner.tree.clone();
+
+        let removed = task::asyncify(move || {
+            let mut removed = 0;
+
+            for entry in tree.iter() {
+                let (key, _) = entry?;
+
+                match parse_day(&key) {
+                    Some(day) if day >= cutoff => continue,
+                    _ => (),
+                }
+
+                tree.remove(key)?;
+                removed += 1;
+            }
+
+            tree.flush()?;
+            Ok::<_, anyhow::Error>(removed)
+        })
+        .await?;
+
+        log::trace!("compacted emote stats, removed {} day(s)", removed);
+        Ok(())
+    }
+
+    /// Run the emote stats maintenance loop.
+    pub async fn run(self) -> Result<()> {
+        let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+        let mut compact = tokio::time::interval(COMPACT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = flush.tick() => {
+                    if let Err(e) = self.flush().await {
+                        log_error!(e, "failed to flush emote stats");
+                    }
+                }
+                _ = compact.tick() => {
+                    if let Err(e) = self.compact().await {
+                        log_error!(e, "failed to compact emote stats");
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The first day which is part of the rolling window ending `today`.
+fn cutoff(today: NaiveDate) -> NaiveDate {
+    today - chrono::Duration::days(WINDOW_DAYS - 1)
+}
+
+/// Build the storage key for the given channel and day.
+fn key(channel: &str, day: NaiveDate) -> String {
+    format!("{}/{}", channel, day.format(DATE_FORMAT))
+}
+
+/// Parse the day out of a storage key.
+fn parse_day(key: &[u8]) -> Option<NaiveDate> {
+    let key = std::str::from_utf8(key).ok()?;
+    let day = &key[key.rfind('/')? + 1..];
+    NaiveDate::parse_from_str(day, DATE_FORMAT).ok()
+}
+
+/// Add usage of a single emote.
+fn add(counts: &mut Counts, emote: String, provider: Option<Provider>, count: u64) {
+    let usage = counts.entry(emote).or_default();
+    usage.count += count;
+
+    if provider.is_some() {
+        usage.provider = provider;
+    }
+}
+
+/// Merge usage into the given counts.
+fn merge(counts: &mut Counts, other: &Counts) {
+    for (emote, usage) in other {
+        add(counts, emote.clone(), usage.provider, usage.count);
+    }
+}
+
+/// Get the `limit` most used emotes, most used first.
+fn rank(counts: Counts, limit: usize) -> Vec<EmoteUsage> {
+    let mut emotes = counts
+        .into_iter()
+        .map(|(emote, usage)| EmoteUsage {
+            emote,
+            provider: usage.provider,
+            count: usage.count,
+        })
+        .collect::<Vec<_>>();
+
+    emotes.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.emote.cmp(&b.emote)));
+    emotes.truncate(limit);
+    emotes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add, cutoff, key, merge, parse_day, rank, Counts, EmoteUsage};
+    use crate::emotes::Provider;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_keys() {
+        let day = NaiveDate::from_ymd(2021, 2, 14);
+
+        assert_eq!("setbac/2021-02-14", key("setbac", day));
+        assert_eq!(Some(day), parse_day(b"setbac/2021-02-14"));
+        assert_eq!(None, parse_day(b"setbac"));
+        assert_eq!(None, parse_day(b"setbac/yesterday"));
+
+        assert_eq!(NaiveDate::from_ymd(2021, 1, 16), cutoff(day));
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut a = Counts::new();
+        add(&mut a, String::from("Kappa"), Some(Provider::Twitch), 2);
+        add(&mut a, String::from("monkaS"), None, 3);
+
+        let mut b = Counts::new();
+        add(&mut b, String::from("monkaS"), Some(Provider::Bttv), 1);
+        add(&mut b, String::from("OMEGALUL"), Some(Provider::Ffz), 1);
+
+        merge(&mut a, &b);
+
+        assert_eq!(
+            vec![
+                EmoteUsage {
+                    emote: String::from("monkaS"),
+                    provider: Some(Provider::Bttv),
+                    count: 4,
+                },
+                EmoteUsage {
+                    emote: String::from("Kappa"),
+                    provider: Some(Provider::Twitch),
+                    count: 2,
+                },
+            ],
+            rank(a, 2)
+        );
+    }
+}
@@ -1,14 +1,19 @@
-use crate::api::{bttv, ffz, twitch::Channel, BetterTTV, FrankerFaceZ, Tduva, Twitch};
+use crate::api::{
+    bttv, ffz, seventv, twitch::Channel, BetterTTV, FrankerFaceZ, SevenTv, Tduva, Twitch,
+};
 use crate::irc;
 use crate::prelude::*;
 use crate::storage::Cache;
 use crate::template;
 use anyhow::Error;
 use futures_cache as cache;
+use parking_lot::Mutex;
 use smallvec::SmallVec;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::mem;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 /// Number of badges inlined for performance reasons.
@@ -16,6 +21,23 @@ use tokio::sync::RwLock;
 const INLINED_BADGES: usize = 8;
 const DEFAULT_BADGE_SIZE: u32 = 18;
 const BTTV_BOT_BADGE: &str = "https://cdn.betterttv.net/tags/bot.png";
+/// BTTV emotes which are drawn on top of the preceding emote.
+///
+/// BTTV doesn't flag these in its API, so they are known by code. 7TV flags
+/// its zero-width emotes.
+const BTTV_ZERO_WIDTH: &[&str] = &[
+    "SoSnowy",
+    "IceCold",
+    "SantaHat",
+    "TopHat",
+    "ReinDeer",
+    "CandyCane",
+    "cvMask",
+    "cvHazmat",
+];
+/// How long emotes are shared between messages in a channel before they are
+/// looked up in the cache again.
+const CHANNEL_STATE_TTL: Duration = Duration::from_secs(10);
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Url {
@@ -80,9 +102,80 @@ pub struct Size {
     height: u32,
 }
 
+/// The service an emote comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    Twitch,
+    Ffz,
+    Bttv,
+    #[serde(rename = "7tv")]
+    SevenTv,
+}
+
+impl Provider {
+    /// Get the name of the provider.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Twitch => "twitch",
+            Self::Ffz => "ffz",
+            Self::Bttv => "bttv",
+            Self::SevenTv => "7tv",
+        }
+    }
+}
+
+impl fmt::Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/// The format of the frames of an emote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Png,
+    Gif,
+    Webp,
+}
+
+impl Format {
+    /// Get the format from a BTTV image type, like `png` or `gif`.
+    fn from_image_type(image_type: &str) -> Option<Self> {
+        match image_type {
+            "png" => Some(Self::Png),
+            "gif" => Some(Self::Gif),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Emote {
     urls: Urls,
+    /// Where the emote comes from. Missing for emotes cached before it was
+    /// recorded.
+    #[serde(default)]
+    provider: Option<Provider>,
+    /// If the emote is animated.
+    #[serde(default)]
+    animated: bool,
+    /// The format of the frames of the emote, if known.
+    #[serde(default)]
+    format: Option<Format>,
+    /// If the emote is zero-width, meaning that it's drawn on top of the
+    /// emote preceding it instead of next to it.
+    #[serde(default)]
+    zero_width: bool,
+}
+
+impl Emote {
+    /// Where the emote comes from, if known.
+    pub fn provider(&self) -> Option<Provider> {
+        self.provider
+    }
 }
 
 type EmoteByCode = HashMap<String, Arc<Emote>>;
@@ -90,12 +183,12 @@ type EmoteByCode = HashMap<String, Arc<Emote>>;
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "k", content = "c")]
 enum Key<'a> {
-    /// Twitch badges for the given room.
-    TwitchSubscriberBadges { target: &'a str },
+    /// Custom Twitch badges for the given room.
+    TwitchChannelBadges { target: &'a str },
     /// GQL Twitch badges for the given chat (channel/name).
     GqlTwitchChatBadges { target: &'a str, name: &'a str },
-    /// Twitch badges for the given chat (channel).
-    TwitchChatBadges { target: &'a str },
+    /// Global Twitch badges.
+    TwitchGlobalBadges,
     /// FFZ information for a given user.
     FfzUser { name: &'a str },
     /// All badges for the given room and name combo.
@@ -125,13 +218,26 @@ struct TduvaData {
     chatty: Vec<TduvaBadge>,
 }
 
+/// Emotes which are shared by all messages in a channel.
+///
+/// Looking these up in the cache requires deserializing them, which is too
+/// expensive to do for every message in a busy chat.
+#[derive(Clone)]
+struct ChannelState {
+    room_emotes: Arc<EmoteByCode>,
+    global_emotes: Arc<EmoteByCode>,
+    expires_at: Instant,
+}
+
 struct Inner {
     cache: Cache,
     ffz: FrankerFaceZ,
     bttv: BetterTTV,
+    seventv: SevenTv,
     tduva: Tduva,
     tduva_data: RwLock<Option<TduvaData>>,
     twitch: Twitch,
+    channel_states: Mutex<HashMap<String, ChannelState>>,
 }
 
 #[derive(Clone)]
@@ -147,9 +253,11 @@ impl Emotes {
                 cache: cache.namespaced(&"emotes")?,
                 ffz: FrankerFaceZ::new()?,
                 bttv: BetterTTV::new()?,
+                seventv: SevenTv::new()?,
                 tduva: Tduva::new()?,
                 tduva_data: Default::default(),
                 twitch,
+                channel_states: Default::default(),
             }),
         })
     }
@@ -157,8 +265,22 @@ impl Emotes {
     /// Extend the given emote set.
     fn extend_ffz_set(emotes: &mut EmoteByCode, s: ffz::Set) {
         for e in s.emoticons {
-            let urls = (e.width, e.height, e.urls).into();
-            emotes.insert(e.name, Arc::new(Emote { urls }));
+            // NB: animated emotes are served as webp.
+            let (urls, animated, format) = match e.animated {
+                Some(urls) => (urls, true, Format::Webp),
+                None => (e.urls, false, Format::Png),
+            };
+
+            emotes.insert(
+                e.name,
+                Arc::new(Emote {
+                    urls: (e.width, e.height, urls).into(),
+                    provider: Some(Provider::Ffz),
+                    animated,
+                    format: Some(format),
+                    zero_width: e.modifier,
+                }),
+            );
         }
     }
 
@@ -213,7 +335,19 @@ impl Emotes {
                 *dest = Some(Url { url, size: None });
             }
 
-            out.insert(e.code, Arc::new(Emote { urls }));
+            let format = Format::from_image_type(&e.image_type);
+            let zero_width = BTTV_ZERO_WIDTH.contains(&e.code.as_str());
+
+            out.insert(
+                e.code,
+                Arc::new(Emote {
+                    urls,
+                    provider: Some(Provider::Bttv),
+                    animated: format == Some(Format::Gif),
+                    format,
+                    zero_width,
+                }),
+            );
         }
 
         return Ok(out);
@@ -285,8 +419,79 @@ impl Emotes {
         self.convert_emotes_from_bttv(channel.emotes, channel.url_template)
     }
 
+    /// Convert emotes from 7TV.
+    fn convert_emotes_from_seventv(emotes: Vec<seventv::ActiveEmote>) -> EmoteByCode {
+        let mut out = EmoteByCode::default();
+
+        for e in emotes {
+            let zero_width = e.is_zero_width();
+
+            let data = match e.data {
+                Some(data) => data,
+                None => continue,
+            };
+
+            let mut urls = Urls::default();
+
+            let options: SmallVec<[(&mut Option<Url>, &str); 3]> = smallvec![
+                (&mut urls.small, "1x.webp"),
+                (&mut urls.medium, "2x.webp"),
+                (&mut urls.large, "3x.webp"),
+            ];
+
+            for (dest, name) in options {
+                if let Some(file) = data.host.files.iter().find(|f| f.name == name) {
+                    *dest = Some(Url {
+                        url: format!("{}/{}", data.host.url, file.name),
+                        size: Some(Size {
+                            width: file.width,
+                            height: file.height,
+                        }),
+                    });
+                }
+            }
+
+            out.insert(
+                e.name,
+                Arc::new(Emote {
+                    urls,
+                    provider: Some(Provider::SevenTv),
+                    animated: data.animated,
+                    format: Some(Format::Webp),
+                    zero_width,
+                }),
+            );
+        }
+
+        out
+    }
+
+    /// Construct a set of room emotes from 7tv.
+    async fn room_emotes_from_seventv(&self, channel: &Channel) -> Result<EmoteByCode, Error> {
+        let user = match self.inner.seventv.twitch_user(&channel.id).await? {
+            Some(user) => user,
+            None => return Ok(Default::default()),
+        };
+
+        let emotes = match user.emote_set {
+            Some(set) => set.emotes,
+            None => return Ok(Default::default()),
+        };
+
+        Ok(Self::convert_emotes_from_seventv(emotes))
+    }
+
+    /// Construct the set of global emotes from 7tv.
+    async fn global_emotes_from_seventv(&self) -> Result<EmoteByCode, Error> {
+        let set = self.inner.seventv.global_emote_set().await?;
+        Ok(Self::convert_emotes_from_seventv(set.emotes))
+    }
+
     /// Construct a twitch emote.
-    fn twitch_emote(id: u64) -> Arc<Emote> {
+    ///
+    /// `animated` is `None` if it's not known whether the emote is animated,
+    /// in which case it's served in whichever format it's available in.
+    fn twitch_emote(id: impl fmt::Display, animated: Option<bool>) -> Arc<Emote> {
         let mut urls = Urls::default();
 
         let options: SmallVec<[(&mut Option<Url>, &str); 3]> = smallvec![
@@ -296,23 +501,31 @@ impl Emotes {
         ];
 
         for (dest, size) in options.into_iter() {
-            let url = format!("//static-cdn.jtvnw.net/emoticons/v1/{}/{}", id, size);
+            let url = format!(
+                "//static-cdn.jtvnw.net/emoticons/v2/{}/default/dark/{}",
+                id, size
+            );
             *dest = Some(Url { url, size: None });
         }
 
-        Arc::new(Emote { urls })
+        Arc::new(Emote {
+            urls,
+            provider: Some(Provider::Twitch),
+            animated: animated.unwrap_or_default(),
+            format: animated.map(|animated| if animated { Format::Gif } else { Format::Png }),
+            zero_width: false,
+        })
     }
 
-    /// Construct a set of room emotes from twitch.
-    async fn emote_sets_from_twitch(&self, emote_sets: &str) -> Result<EmoteByCode, Error> {
-        let result = self.inner.twitch.chat_emoticon_images(emote_sets).await?;
+    /// Construct the set of global emotes from twitch.
+    async fn global_emotes_from_twitch(&self) -> Result<EmoteByCode, Error> {
+        let result = self.inner.twitch.global_emotes().await?;
 
         let mut emotes = EmoteByCode::default();
 
-        for (_, set) in result.emoticon_sets {
-            for e in set {
-                emotes.insert(e.code, Self::twitch_emote(e.id));
-            }
+        for e in result {
+            let animated = e.format.iter().any(|f| f == "animated");
+            emotes.insert(e.name, Self::twitch_emote(e.id, Some(animated)));
         }
 
         Ok(emotes)
@@ -335,13 +548,15 @@ impl Emotes {
                 chrono::Duration::hours(6),
                 async move {
                     let mut emotes = EmoteByCode::default();
-                    let (a, b) = future::try_join(
+                    let (a, b, c) = future::try_join3(
                         self.room_emotes_from_ffz(channel),
                         self.room_emotes_from_bttv(channel),
+                        self.room_emotes_from_seventv(channel),
                     )
                     .await?;
                     emotes.extend(a);
                     emotes.extend(b);
+                    emotes.extend(c);
                     Ok(Arc::new(emotes))
                 },
             )
@@ -385,7 +600,7 @@ impl Emotes {
                 None => continue,
             };
 
-            out.insert(word, Self::twitch_emote(id));
+            out.insert(word, Self::twitch_emote(id, None));
         }
 
         return Ok(out);
@@ -408,15 +623,17 @@ impl Emotes {
         self.inner
             .cache
             .wrap(Key::GlobalEmotes, chrono::Duration::hours(72), async move {
-                let (twitch, bttv) = future::try_join(
-                    self.emote_sets_from_twitch("0"),
+                let (twitch, bttv, seventv) = future::try_join3(
+                    self.global_emotes_from_twitch(),
                     self.emote_sets_from_bttv(),
+                    self.global_emotes_from_seventv(),
                 )
                 .await?;
 
                 let mut emotes = EmoteByCode::default();
                 emotes.extend(twitch);
                 emotes.extend(bttv);
+                emotes.extend(seventv);
                 Ok(Arc::new(emotes))
             })
             .await
@@ -432,28 +649,23 @@ impl Emotes {
             .inner
             .cache
             .wrap(
-                Key::TwitchSubscriberBadges {
+                Key::TwitchChannelBadges {
                     target: &channel.name,
                 },
                 chrono::Duration::hours(24),
-                self.inner.twitch.badges_display(&channel.id),
+                self.inner.twitch.channel_chat_badges(&channel.id),
             )
             .await?;
 
-        let mut badges = match badges {
-            Some(badges) => badges,
-            None => return Ok(None),
-        };
-
-        let subscriber = match badges.badge_sets.remove("subscriber") {
+        let subscriber = match badges.into_iter().find(|b| b.set_id == "subscriber") {
             Some(subscriber) => subscriber,
             None => return Ok(None),
         };
 
         let mut best = None;
 
-        for (version, badge) in subscriber.versions {
-            let version = match str::parse::<u32>(&version).ok() {
+        for badge in subscriber.versions {
+            let version = match str::parse::<u32>(&badge.id).ok() {
                 Some(version) => version,
                 None => continue,
             };
@@ -472,7 +684,7 @@ impl Emotes {
             urls.large = Some(Url::from(badge.image_url_4x));
 
             return Ok(Some(Badge {
-                title: badge.title,
+                title: badge.title.unwrap_or_else(|| String::from("Subscriber")),
                 badge_url: None,
                 urls,
                 bg_color: None,
@@ -591,29 +803,17 @@ impl Emotes {
             .inner
             .cache
             .wrap(
-                Key::TwitchChatBadges {
-                    target: &channel.name,
-                },
+                Key::TwitchGlobalBadges,
                 chrono::Duration::hours(72),
-                self.inner.twitch.chat_badges(&channel.id),
+                self.inner.twitch.global_chat_badges(),
             )
             .await?;
 
         let mut out = SmallVec::new();
 
-        let mut badges = match badges {
-            Some(badges) => badges,
-            None => return Ok(out),
-        };
-
         for (name, version) in split_badges(chat_badges) {
-            let name = match name {
-                "admin" => "admin",
-                "broadcaster" => "broadcaster",
-                "global_mod" => "global_mod",
-                "moderator" => "mod",
-                "staff" => "staff",
-                "turbo" => "turbo",
+            match name {
+                "admin" | "broadcaster" | "global_mod" | "moderator" | "staff" | "turbo" => (),
                 "subscriber" => {
                     // NB: subscriber badges are handled separately.
                     out.extend(self.twitch_subscriber_badge(channel, version).await?);
@@ -628,23 +828,28 @@ impl Emotes {
                     log::trace!("Unsupported badge: {}", name);
                     continue;
                 }
-            };
+            }
 
-            let badge = match badges.badges.remove(name) {
-                Some(badge) => badge,
-                None => continue,
-            };
+            let version = version.to_string();
 
-            let image = match badge.image {
-                Some(image) => image,
+            let badge = badges
+                .iter()
+                .filter(|b| b.set_id == name)
+                .flat_map(|b| b.versions.iter())
+                .find(|b| b.id == version);
+
+            let badge = match badge {
+                Some(badge) => badge,
                 None => continue,
             };
 
             let mut urls = Urls::default();
-            urls.small = Some(image.into());
+            urls.small = Some(Url::from(badge.image_url_1x.clone()));
+            urls.medium = Some(Url::from(badge.image_url_2x.clone()));
+            urls.large = Some(Url::from(badge.image_url_4x.clone()));
 
             out.push(Badge {
-                title: name.to_string(),
+                title: badge.title.clone().unwrap_or_else(|| name.to_string()),
                 badge_url: None,
                 urls,
                 bg_color: None,
@@ -815,6 +1020,37 @@ impl Emotes {
             .await
     }
 
+    /// Get the emotes shared by all messages in the given channel.
+    async fn channel_state(&self, channel: &Channel) -> Result<ChannelState, Error> {
+        let now = Instant::now();
+
+        // NB: the lock must not be held across await points.
+        let state = self.inner.channel_states.lock().get(&channel.name).cloned();
+
+        if let Some(state) = state {
+            if state.expires_at > now {
+                return Ok(state);
+            }
+        }
+
+        let (room_emotes, global_emotes) =
+            future::try_join(self.room_emotes(channel), self.global_emotes()).await?;
+
+        let state = ChannelState {
+            room_emotes,
+            global_emotes,
+            expires_at: now + CHANNEL_STATE_TTL,
+        };
+
+        self.inner
+            .channel_states
+            .lock()
+            .insert(channel.name.clone(), state.clone());
+
+        Ok(state)
+    }
+
+    /// Render the given message, resolving badges and emotes concurrently.
     pub async fn render(
         &self,
         tags: &irc::Tags,
@@ -822,22 +1058,68 @@ impl Emotes {
         name: &str,
         message: &str,
     ) -> Result<Rendered, Error> {
-        let (badges, room_emotes, global_emotes) = future::try_join3(
+        let message_emotes = async { self.message_emotes_twitch(tags, message) };
+
+        let (badges, state, message_emotes) = future::try_join3(
             self.room_badges(channel, name),
-            self.room_emotes(channel),
-            self.global_emotes(),
+            self.channel_state(channel),
+            message_emotes,
         )
         .await?;
-        let message_emotes = self.message_emotes_twitch(tags, message)?;
 
         Ok(Rendered::render(
             badges,
             message,
-            &*room_emotes,
+            &*state.room_emotes,
             &message_emotes,
-            &*global_emotes,
+            &*state.global_emotes,
         ))
     }
+
+    /// Find the emotes used in the given message, in the order they are
+    /// used.
+    ///
+    /// Emotes are resolved the same way as when rendering a message.
+    pub async fn find(
+        &self,
+        tags: &irc::Tags,
+        channel: &Channel,
+        message: &str,
+    ) -> Result<Vec<(String, Option<Provider>)>, Error> {
+        let state = self.channel_state(channel).await?;
+        let message_emotes = self.message_emotes_twitch(tags, message)?;
+
+        let emotes = Words::new(message)
+            .filter_map(|(_, word)| {
+                let emote = lookup(
+                    word,
+                    &*state.room_emotes,
+                    &message_emotes,
+                    &*state.global_emotes,
+                )?;
+
+                Some((word.to_string(), emote.provider))
+            })
+            .collect();
+
+        Ok(emotes)
+    }
+}
+
+/// Look up the emote for the given word.
+///
+/// Room emotes take precedence over emotes in the message, which take
+/// precedence over global emotes.
+fn lookup<'a>(
+    word: &str,
+    room_emotes: &'a EmoteByCode,
+    message_emotes: &'a EmoteByCode,
+    global_emotes: &'a EmoteByCode,
+) -> Option<&'a Arc<Emote>> {
+    room_emotes
+        .get(word)
+        .or_else(|| message_emotes.get(word))
+        .or_else(|| global_emotes.get(word))
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -846,7 +1128,12 @@ enum Item {
     #[serde(rename = "text")]
     Text { text: String },
     #[serde(rename = "emote")]
-    Emote { emote: String },
+    Emote {
+        emote: String,
+        /// Zero-width emotes drawn on top of this emote, in order.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        overlays: Vec<String>,
+    },
     #[serde(rename = "url")]
     Url { url: String },
 }
@@ -904,12 +1191,7 @@ impl Rendered {
         let mut emotes = HashMap::new();
         let mut items = Vec::new();
 
-        let emote = |word| {
-            room_emotes
-                .get(word)
-                .or_else(|| message_emotes.get(word))
-                .or_else(|| global_emotes.get(word))
-        };
+        let emote = |word| lookup(word, room_emotes, message_emotes, global_emotes);
 
         'outer: loop {
             let mut it = Words::new(buf);
@@ -922,6 +1204,16 @@ impl Rendered {
 
                     let text = &buf[..idx];
 
+                    // NB: zero-width emotes modify the emote preceding them, as
+                    // long as they are only separated by whitespace.
+                    if emote.zero_width && text.trim().is_empty() {
+                        if let Some(Item::Emote { overlays, .. }) = items.last_mut() {
+                            overlays.push(word.to_string());
+                            buf = &buf[(idx + word.len())..];
+                            continue 'outer;
+                        }
+                    }
+
                     if !text.is_empty() {
                         items.push(Item::Text {
                             text: text.to_string(),
@@ -930,6 +1222,7 @@ impl Rendered {
 
                     items.push(Item::Emote {
                         emote: word.to_string(),
+                        overlays: Vec::new(),
                     });
 
                     buf = &buf[(idx + word.len())..];
@@ -1021,7 +1314,157 @@ impl<'a> Iterator for Words<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::Words;
+    use super::{lookup, Emote, EmoteByCode, Emotes, Format, Item, Provider, Rendered, Words};
+    use smallvec::SmallVec;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn emotes(codes: &[(&str, u64)]) -> EmoteByCode {
+        codes
+            .iter()
+            .map(|(code, id)| (code.to_string(), Emotes::twitch_emote(*id, None)))
+            .collect()
+    }
+
+    fn bttv_emote(animated: bool, zero_width: bool) -> Arc<Emote> {
+        Arc::new(Emote {
+            urls: Default::default(),
+            provider: Some(Provider::Bttv),
+            animated,
+            format: Some(if animated { Format::Gif } else { Format::Png }),
+            zero_width,
+        })
+    }
+
+    fn items(rendered: &Rendered) -> Vec<String> {
+        rendered
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Text { text } => format!("text:{}", text),
+                Item::Emote { emote, overlays } if overlays.is_empty() => {
+                    format!("emote:{}", emote)
+                }
+                Item::Emote { emote, overlays } => {
+                    format!("emote:{}+{}", emote, overlays.join("+"))
+                }
+                Item::Url { url } => format!("url:{}", url),
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn test_render() {
+        let room = emotes(&[("setmodPog", 1)]);
+        let message = emotes(&[("Kappa", 25)]);
+        let global = emotes(&[("LUL", 425618)]);
+
+        let rendered = Rendered::render(
+            SmallVec::new(),
+            "Kappa hello setmodPog https://setbac.tv/ LUL",
+            &room,
+            &message,
+            &global,
+        );
+
+        assert_eq!(3, rendered.emotes.len());
+
+        assert_eq!(
+            vec![
+                "emote:Kappa",
+                "text: hello ",
+                "emote:setmodPog",
+                "text: ",
+                "url:https://setbac.tv/",
+                "text: ",
+                "emote:LUL",
+            ],
+            items(&rendered)
+        );
+    }
+
+    #[test]
+    pub fn test_render_zero_width() {
+        let mut room = EmoteByCode::default();
+        room.insert(String::from("catJAM"), bttv_emote(true, false));
+        room.insert(String::from("SoSnowy"), bttv_emote(false, true));
+        room.insert(String::from("IceCold"), bttv_emote(false, true));
+        let message = emotes(&[("Kappa", 25)]);
+        let global = EmoteByCode::default();
+
+        let rendered = Rendered::render(
+            SmallVec::new(),
+            "SoSnowy catJAM SoSnowy  IceCold hi SoSnowy Kappa SoSnowy",
+            &room,
+            &message,
+            &global,
+        );
+
+        assert_eq!(4, rendered.emotes.len());
+        assert!(rendered.emotes["catJAM"].animated);
+
+        assert_eq!(
+            vec![
+                "emote:SoSnowy",
+                "text: ",
+                "emote:catJAM+SoSnowy+IceCold",
+                "text: hi ",
+                "emote:SoSnowy",
+                "text: ",
+                "emote:Kappa+SoSnowy",
+            ],
+            items(&rendered)
+        );
+    }
+
+    #[test]
+    pub fn test_lookup() {
+        let mut room = EmoteByCode::default();
+        room.insert(String::from("Kappa"), bttv_emote(false, false));
+
+        let message = emotes(&[("Kappa", 25), ("PogChamp", 88)]);
+        let global = emotes(&[("LUL", 425618)]);
+
+        let provider = |word| lookup(word, &room, &message, &global).and_then(|e| e.provider);
+
+        assert_eq!(Some(Provider::Bttv), provider("Kappa"));
+        assert_eq!(Some(Provider::Twitch), provider("PogChamp"));
+        assert_eq!(Some(Provider::Twitch), provider("LUL"));
+        assert!(lookup("hello", &room, &message, &global).is_none());
+    }
+
+    /// Benchmark for rendering messages, run with:
+    /// `cargo test --release bench_render -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    pub fn bench_render() {
+        const ITERATIONS: u32 = 100_000;
+
+        let room = (0..1000)
+            .map(|n| (format!("room{}", n), Emotes::twitch_emote(n, None)))
+            .collect::<EmoteByCode>();
+        let global = (0..1000)
+            .map(|n| (format!("global{}", n), Emotes::twitch_emote(n, None)))
+            .collect::<EmoteByCode>();
+        let message = emotes(&[("Kappa", 25)]);
+
+        let text = "Kappa this is a pretty average room10 message global999 with some emotes Kappa";
+
+        let start = Instant::now();
+
+        for _ in 0..ITERATIONS {
+            let rendered = Rendered::render(SmallVec::new(), text, &room, &message, &global);
+            assert_eq!(3, rendered.emotes.len());
+        }
+
+        let elapsed = start.elapsed();
+        println!(
+            "rendered {} messages in {:?} ({:?} per message)",
+            ITERATIONS,
+            elapsed,
+            elapsed / ITERATIONS
+        );
+    }
 
     #[test]
     pub fn test_words() {
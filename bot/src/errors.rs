@@ -0,0 +1,152 @@
+//! Storage of recent internal errors, keyed by a short reference code.
+//!
+//! The code is shown in chat so that users have something to report, while
+//! the full error can be looked up through `/api/errors/<code>`.
+
+use crate::metrics;
+use chrono::{DateTime, Utc};
+use rand::Rng as _;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// The default number of errors to keep around.
+const DEFAULT_LIMIT: usize = 256;
+/// The length of a reference code.
+const CODE_LENGTH: usize = 6;
+/// Characters used in a reference code.
+const CODE_CHARACTERS: &[u8] = b"0123456789abcdef";
+
+/// A single recorded error.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Entry {
+    /// The reference code of the error.
+    pub code: String,
+    /// When the error happened.
+    pub timestamp: DateTime<Utc>,
+    /// What was being done when the error happened, like the command that
+    /// was run.
+    pub context: String,
+    /// The chain of errors, starting with the outermost one.
+    pub chain: Vec<String>,
+}
+
+struct Inner {
+    limit: usize,
+    entries: VecDeque<Entry>,
+}
+
+impl Inner {
+    /// Insert the given entry, evicting the oldest ones if we're at the limit.
+    fn insert(&mut self, entry: Entry) {
+        while self.entries.len() >= self.limit {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    /// Find the entry with the given code.
+    fn get(&self, code: &str) -> Option<&Entry> {
+        self.entries.iter().rev().find(|e| e.code == code)
+    }
+}
+
+/// A capped store of recent errors.
+#[derive(Clone)]
+pub struct Errors {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl Default for Errors {
+    fn default() -> Self {
+        Self::new(DEFAULT_LIMIT)
+    }
+}
+
+impl Errors {
+    /// Construct a new store, keeping at most `limit` errors.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Inner {
+                limit: usize::max(limit, 1),
+                entries: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Record the given error and return the reference code for it.
+    pub async fn record(&self, context: impl Into<String>, e: &anyhow::Error) -> String {
+        metrics::ERRORS.inc();
+        let mut inner = self.inner.write().await;
+
+        let code = loop {
+            let code = random_code();
+
+            if inner.get(&code).is_none() {
+                break code;
+            }
+        };
+
+        inner.insert(Entry {
+            code: code.clone(),
+            timestamp: Utc::now(),
+            context: context.into(),
+            chain: e.chain().map(|e| e.to_string()).collect(),
+        });
+
+        code
+    }
+
+    /// Get the error with the given reference code.
+    pub async fn get(&self, code: &str) -> Option<Entry> {
+        self.inner.read().await.get(&code.to_lowercase()).cloned()
+    }
+}
+
+/// Generate a random reference code.
+fn random_code() -> String {
+    let mut rng = rand::thread_rng();
+
+    (0..CODE_LENGTH)
+        .map(|_| CODE_CHARACTERS[rng.gen_range(0, CODE_CHARACTERS.len())] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{random_code, Entry, Inner, CODE_LENGTH};
+    use chrono::Utc;
+
+    fn entry(code: &str) -> Entry {
+        Entry {
+            code: code.to_string(),
+            timestamp: Utc::now(),
+            context: String::new(),
+            chain: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_evicts_oldest() {
+        let mut inner = Inner {
+            limit: 2,
+            entries: Default::default(),
+        };
+
+        inner.insert(entry("a"));
+        inner.insert(entry("b"));
+        inner.insert(entry("c"));
+
+        assert!(inner.get("a").is_none());
+        assert!(inner.get("b").is_some());
+        assert!(inner.get("c").is_some());
+    }
+
+    #[test]
+    fn test_random_code() {
+        let code = random_code();
+        assert_eq!(CODE_LENGTH, code.len());
+        assert!(code.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+}
@@ -0,0 +1,200 @@
+//! Feature flags which gate experimental modules and features.
+//!
+//! Flags are published remotely through setbac.tv so that they can be rolled
+//! out per installation, and can be forced on or off locally through the
+//! `flags/enabled` and `flags/disabled` settings.
+
+use crate::api::setbac::{self, Setbac};
+use crate::injector::Injector;
+use crate::prelude::*;
+use crate::settings::Settings;
+use crate::utils::Duration;
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+
+/// The state of a single flag.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FlagStatus {
+    /// Identifier of the flag.
+    pub id: String,
+    /// Documentation for the flag, if published remotely.
+    pub doc: Option<String>,
+    /// The value published remotely, if any.
+    pub remote: Option<bool>,
+    /// The local override, if any.
+    pub local: Option<bool>,
+    /// If the flag is in effect.
+    pub enabled: bool,
+}
+
+/// A snapshot of all feature flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Flags {
+    remote: BTreeMap<String, setbac::Flag>,
+    enabled: HashSet<String>,
+    disabled: HashSet<String>,
+}
+
+impl Flags {
+    /// Test if the given flag is enabled.
+    ///
+    /// Local overrides take precedence, and unknown flags are disabled.
+    pub fn is_enabled(&self, id: &str) -> bool {
+        if self.disabled.contains(id) {
+            return false;
+        }
+
+        if self.enabled.contains(id) {
+            return true;
+        }
+
+        self.remote.get(id).map(|f| f.enabled).unwrap_or_default()
+    }
+
+    /// List the state of all known flags.
+    pub fn list(&self) -> Vec<FlagStatus> {
+        let mut ids = self
+            .remote
+            .keys()
+            .chain(self.enabled.iter())
+            .chain(self.disabled.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        ids.sort();
+        ids.dedup();
+
+        ids.into_iter()
+            .map(|id| {
+                let remote = self.remote.get(&id);
+
+                let local = if self.disabled.contains(&id) {
+                    Some(false)
+                } else if self.enabled.contains(&id) {
+                    Some(true)
+                } else {
+                    None
+                };
+
+                FlagStatus {
+                    doc: remote.and_then(|f| f.doc.clone()),
+                    remote: remote.map(|f| f.enabled),
+                    local,
+                    enabled: self.is_enabled(&id),
+                    id,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Run the loop which keeps feature flags in sync with the remote API.
+pub async fn run(
+    settings: &Settings,
+    injector: &Injector,
+) -> Result<impl Future<Output = Result<()>>> {
+    let settings = settings.scoped("flags");
+
+    let (mut enabled_stream, enabled) = settings.stream("enabled").or_default().await?;
+    let (mut disabled_stream, disabled) = settings.stream("disabled").or_default().await?;
+    let (mut sync_interval_stream, sync_interval) = settings
+        .stream::<Duration>("sync-interval")
+        .or_with(Duration::seconds(60 * 10))
+        .await?;
+    let (mut setbac_stream, mut setbac) = injector.stream::<Setbac>().await;
+
+    let mut flags = Flags {
+        remote: BTreeMap::new(),
+        enabled,
+        disabled,
+    };
+
+    injector.update(flags.clone()).await;
+
+    let injector = injector.clone();
+    let mut sync_interval = tokio::time::interval(sync_interval.as_std()).fuse();
+
+    Ok(async move {
+        loop {
+            let mut update = flags.clone();
+
+            futures::select! {
+                update_setbac = setbac_stream.select_next_some() => {
+                    setbac = update_setbac;
+
+                    if let Some(remote) = sync(setbac.as_ref()).await {
+                        update.remote = remote;
+                    }
+                }
+                _ = sync_interval.select_next_some() => {
+                    if let Some(remote) = sync(setbac.as_ref()).await {
+                        update.remote = remote;
+                    }
+                }
+                enabled = enabled_stream.select_next_some() => {
+                    update.enabled = enabled;
+                }
+                disabled = disabled_stream.select_next_some() => {
+                    update.disabled = disabled;
+                }
+                interval = sync_interval_stream.select_next_some() => {
+                    sync_interval = tokio::time::interval(interval.as_std()).fuse();
+                }
+            }
+
+            if update != flags {
+                flags = update;
+                injector.update(flags.clone()).await;
+            }
+        }
+    })
+}
+
+/// Fetch flags from the remote API.
+///
+/// Flags are cleared if the remote API isn't configured, and `None` is
+/// returned if it can't be reached so that the last known flags are kept.
+async fn sync(setbac: Option<&Setbac>) -> Option<BTreeMap<String, setbac::Flag>> {
+    let setbac = match setbac {
+        Some(setbac) => setbac,
+        None => return Some(BTreeMap::new()),
+    };
+
+    match setbac.flags().await {
+        Ok(flags) => Some(flags.into_iter().map(|f| (f.id.clone(), f)).collect()),
+        Err(e) => {
+            log_warn!(e, "Failed to sync feature flags");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Flags;
+    use crate::api::setbac::Flag;
+
+    #[test]
+    fn test_overrides() {
+        let mut flags = Flags::default();
+
+        for (id, enabled) in &[("a", true), ("b", false), ("c", true)] {
+            let flag = Flag {
+                id: id.to_string(),
+                enabled: *enabled,
+                doc: None,
+            };
+
+            flags.remote.insert(id.to_string(), flag);
+        }
+
+        flags.enabled.insert(String::from("b"));
+        flags.disabled.insert(String::from("c"));
+
+        assert!(flags.is_enabled("a"));
+        assert!(flags.is_enabled("b"));
+        assert!(!flags.is_enabled("c"));
+        assert!(!flags.is_enabled("d"));
+        assert_eq!(3, flags.list().len());
+    }
+}
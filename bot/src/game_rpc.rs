@@ -0,0 +1,597 @@
+//! A local RPC channel which game integrations connect to, so that they can
+//! be driven from chat.
+//!
+//! Integrations connect over TCP to the address in `game-rpc/bind` (by default
+//! `127.0.0.1:7292`) and communicate using JSON messages, one per line.
+//!
+//! The first message sent by an integration must be a `hello`, which
+//! authenticates it using a token configured in `game-rpc/integrations`. It can
+//! also declare which commands it supports. If it doesn't, any command is
+//! routed to it:
+//!
+//! ```json
+//! {"type": "hello", "integration": "minecraft", "token": "hunter2", "commands": ["creeper"]}
+//! ```
+//!
+//! The bot responds with either `welcome` or an `error`, after which the
+//! connection is closed:
+//!
+//! ```json
+//! {"type": "welcome"}
+//! {"type": "error", "message": "bad token"}
+//! ```
+//!
+//! Commands are then sent to the integration as they are issued in chat:
+//!
+//! ```json
+//! {"type": "command", "id": 1, "user": "setbac", "command": "creeper", "args": "3"}
+//! ```
+//!
+//! The integration can respond to a command with a `result`, where `message`
+//! is optionally relayed to chat:
+//!
+//! ```json
+//! {"type": "result", "id": 1, "ok": true, "message": "Spawned 3 creepers!"}
+//! ```
+//!
+//! Only one connection per integration is permitted. A new connection
+//! replaces the old one.
+
+use crate::prelude::*;
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// The default address to listen on.
+const DEFAULT_BIND: &str = "127.0.0.1:7292";
+/// How long an integration has to say hello after connecting.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An integration which is permitted to connect.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Integration {
+    name: String,
+    token: String,
+}
+
+/// A message sent to an integration.
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Outgoing<'a> {
+    Welcome,
+    Error {
+        message: &'a str,
+    },
+    Command {
+        id: u64,
+        user: Option<&'a str>,
+        command: &'a str,
+        args: &'a str,
+    },
+}
+
+/// A message received from an integration.
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Incoming {
+    Hello {
+        integration: String,
+        token: String,
+        #[serde(default)]
+        commands: Vec<String>,
+    },
+    Result {
+        id: u64,
+        #[serde(default = "default_ok")]
+        ok: bool,
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+fn default_ok() -> bool {
+    true
+}
+
+/// The reply from an integration to a command.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    /// If the command was successful.
+    pub ok: bool,
+    /// Message to relay to chat.
+    pub message: Option<String>,
+}
+
+/// Error raised when trying to send a command to an integration.
+#[derive(Debug, thiserror::Error)]
+pub enum SendError {
+    #[error("integration is not connected")]
+    NotConnected,
+    #[error("integration doesn't support the command")]
+    NoSuchCommand,
+}
+
+/// A connected integration.
+struct Connection {
+    /// Identifies the connection, so that a replaced connection doesn't
+    /// unregister the new one.
+    serial: usize,
+    /// Commands supported by the integration, or empty if any command is
+    /// supported.
+    commands: Vec<String>,
+    /// Serialized messages to send.
+    tx: mpsc::UnboundedSender<String>,
+    /// Commands waiting for a reply.
+    pending: HashMap<u64, oneshot::Sender<Reply>>,
+}
+
+struct Inner {
+    integrations: Mutex<Vec<Integration>>,
+    connections: Mutex<HashMap<String, Connection>>,
+    ids: AtomicU64,
+    serials: AtomicUsize,
+}
+
+/// Handle used to send commands to connected game integrations.
+#[derive(Clone)]
+pub struct GameRpc {
+    inner: Arc<Inner>,
+}
+
+impl GameRpc {
+    /// Names of all connected integrations, in alphabetical order.
+    pub fn connected(&self) -> Vec<String> {
+        let mut names = self
+            .inner
+            .connections
+            .lock()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// The commands supported by the given integration.
+    ///
+    /// Returns `None` if the integration is not connected, and an empty list
+    /// if it supports any command.
+    pub fn commands(&self, integration: &str) -> Option<Vec<String>> {
+        let connections = self.inner.connections.lock();
+        Some(connections.get(integration)?.commands.clone())
+    }
+
+    /// Send a command to the given integration without waiting for a reply.
+    pub fn send(
+        &self,
+        integration: &str,
+        user: Option<&str>,
+        command: &str,
+        args: &str,
+    ) -> Result<(), SendError> {
+        self.dispatch(integration, user, command, args, None)?;
+        Ok(())
+    }
+
+    /// Send a command to the given integration, returning the id of the
+    /// command and a channel which receives the reply.
+    ///
+    /// The channel is closed without a reply if the integration disconnects.
+    /// Callers which stop waiting for the reply must [GameRpc::cancel] the
+    /// command.
+    pub fn call(
+        &self,
+        integration: &str,
+        user: Option<&str>,
+        command: &str,
+        args: &str,
+    ) -> Result<(u64, oneshot::Receiver<Reply>), SendError> {
+        let (tx, rx) = oneshot::channel();
+        let id = self.dispatch(integration, user, command, args, Some(tx))?;
+        Ok((id, rx))
+    }
+
+    /// Stop waiting for the reply to the command with the given id.
+    pub fn cancel(&self, integration: &str, id: u64) {
+        if let Some(connection) = self.inner.connections.lock().get_mut(integration) {
+            connection.pending.remove(&id);
+        }
+    }
+
+    fn dispatch(
+        &self,
+        integration: &str,
+        user: Option<&str>,
+        command: &str,
+        args: &str,
+        reply: Option<oneshot::Sender<Reply>>,
+    ) -> Result<u64, SendError> {
+        let mut connections = self.inner.connections.lock();
+
+        let connection = match connections.get_mut(integration) {
+            Some(connection) => connection,
+            None => return Err(SendError::NotConnected),
+        };
+
+        if !supports(&connection.commands, command) {
+            return Err(SendError::NoSuchCommand);
+        }
+
+        let id = self.inner.ids.fetch_add(1, Ordering::SeqCst);
+
+        let message = Outgoing::Command {
+            id,
+            user,
+            command,
+            args,
+        };
+
+        let message = match serde_json::to_string(&message) {
+            Ok(message) => message,
+            Err(e) => {
+                log_error!(e, "failed to serialize command");
+                return Err(SendError::NotConnected);
+            }
+        };
+
+        if connection.tx.unbounded_send(message).is_err() {
+            return Err(SendError::NotConnected);
+        }
+
+        if let Some(reply) = reply {
+            connection.pending.insert(id, reply);
+        }
+
+        Ok(id)
+    }
+
+    /// Register a newly authenticated connection, returning its serial.
+    fn register(
+        &self,
+        integration: &str,
+        commands: Vec<String>,
+        tx: mpsc::UnboundedSender<String>,
+    ) -> usize {
+        let serial = self.inner.serials.fetch_add(1, Ordering::SeqCst);
+
+        let connection = Connection {
+            serial,
+            commands,
+            tx,
+            pending: HashMap::new(),
+        };
+
+        // NB: dropping the old connection causes it to disconnect.
+        if self
+            .inner
+            .connections
+            .lock()
+            .insert(integration.to_string(), connection)
+            .is_some()
+        {
+            log::warn!("game integration `{}` was replaced", integration);
+        }
+
+        serial
+    }
+
+    /// Unregister the connection with the given serial, unless it has already
+    /// been replaced.
+    fn unregister(&self, integration: &str, serial: usize) {
+        let mut connections = self.inner.connections.lock();
+
+        if let Some(connection) = connections.get(integration) {
+            if connection.serial == serial {
+                connections.remove(integration);
+            }
+        }
+    }
+
+    /// Deliver a reply to a pending command.
+    fn reply(&self, integration: &str, id: u64, reply: Reply) {
+        let mut connections = self.inner.connections.lock();
+
+        let sender = match connections.get_mut(integration) {
+            Some(connection) => connection.pending.remove(&id),
+            None => None,
+        };
+
+        if let Some(sender) = sender {
+            let _ = sender.send(reply);
+        }
+    }
+
+    /// Test if the given integration and token is permitted to connect.
+    fn authenticate(&self, integration: &str, token: &str) -> bool {
+        authenticate(&*self.inner.integrations.lock(), integration, token)
+    }
+}
+
+/// Test if the given set of commands supports the given command.
+fn supports(commands: &[String], command: &str) -> bool {
+    commands.is_empty() || commands.iter().any(|c| c == command)
+}
+
+/// Test if the given integration and token matches a configured integration.
+fn authenticate(integrations: &[Integration], integration: &str, token: &str) -> bool {
+    integrations
+        .iter()
+        .any(|i| !i.token.is_empty() && i.name == integration && i.token == token)
+}
+
+/// Set up the game RPC channel.
+pub fn setup(settings: settings::Settings) -> (GameRpc, impl Future<Output = Result<()>>) {
+    let rpc = GameRpc {
+        inner: Arc::new(Inner {
+            integrations: Mutex::new(Vec::new()),
+            connections: Mutex::new(HashMap::new()),
+            ids: AtomicU64::new(1),
+            serials: AtomicUsize::new(0),
+        }),
+    };
+
+    let future = run(settings, rpc.clone());
+    (rpc, future)
+}
+
+async fn run(settings: settings::Settings, rpc: GameRpc) -> Result<()> {
+    let settings = settings.scoped("game-rpc");
+
+    let (mut enabled_stream, mut enabled) = settings.stream("enabled").or_default().await?;
+    let (mut bind_stream, mut bind) = settings
+        .stream("bind")
+        .or_with_else(|| String::from(DEFAULT_BIND))
+        .await?;
+    let (mut integrations_stream, integrations) = settings
+        .stream::<Vec<Integration>>("integrations")
+        .or_default()
+        .await?;
+
+    *rpc.inner.integrations.lock() = integrations;
+
+    let mut listener = listen(enabled, &bind).await;
+
+    loop {
+        futures::select! {
+            update = enabled_stream.select_next_some() => {
+                enabled = update;
+                listener = listen(enabled, &bind).await;
+            }
+            update = bind_stream.select_next_some() => {
+                bind = update;
+                listener = listen(enabled, &bind).await;
+            }
+            update = integrations_stream.select_next_some() => {
+                *rpc.inner.integrations.lock() = update;
+            }
+            result = accept(&mut listener).fuse() => {
+                let (stream, addr) = match result {
+                    Ok(result) => result,
+                    Err(e) => {
+                        log_error!(e, "failed to accept game integration");
+                        continue;
+                    }
+                };
+
+                let rpc = rpc.clone();
+
+                tokio::spawn(async move {
+                    if let Err(e) = handle(rpc, stream).await {
+                        log_error!(e, "game integration from {} failed", addr);
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Bind a listener, if enabled.
+async fn listen(enabled: bool, bind: &str) -> Option<TcpListener> {
+    if !enabled {
+        return None;
+    }
+
+    let addr = match str::parse::<SocketAddr>(bind) {
+        Ok(addr) => addr,
+        Err(e) => {
+            log::error!("bad game RPC address `{}`: {}", bind, e);
+            return None;
+        }
+    };
+
+    match TcpListener::bind(addr).await {
+        Ok(listener) => {
+            log::info!("listening for game integrations on {}", addr);
+            Some(listener)
+        }
+        Err(e) => {
+            log_error!(e, "failed to bind game RPC to {}", addr);
+            None
+        }
+    }
+}
+
+/// Accept a connection, if we have a listener.
+async fn accept(listener: &mut Option<TcpListener>) -> io::Result<(TcpStream, SocketAddr)> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => future::pending().await,
+    }
+}
+
+/// Handle a single connection from an integration.
+async fn handle(rpc: GameRpc, stream: TcpStream) -> Result<()> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines().fuse();
+
+    let hello = match tokio::time::timeout(HELLO_TIMEOUT, lines.next()).await {
+        Ok(Some(line)) => serde_json::from_str::<Incoming>(&line?),
+        Ok(None) => return Ok(()),
+        Err(_) => bail!("timed out waiting for hello"),
+    };
+
+    let (integration, commands) = match hello {
+        Ok(Incoming::Hello {
+            integration,
+            token,
+            commands,
+        }) if rpc.authenticate(&integration, &token) => (integration, commands),
+        Ok(Incoming::Hello { integration, .. }) => {
+            send(
+                &mut writer,
+                &Outgoing::Error {
+                    message: "bad token",
+                },
+            )
+            .await?;
+            bail!("bad token for integration `{}`", integration);
+        }
+        Ok(..) | Err(..) => {
+            let message = "expected hello";
+            send(&mut writer, &Outgoing::Error { message }).await?;
+            bail!("{}", message);
+        }
+    };
+
+    send(&mut writer, &Outgoing::Welcome).await?;
+
+    let (tx, mut rx) = mpsc::unbounded();
+    let serial = rpc.register(&integration, commands, tx);
+    log::info!("game integration `{}` connected", integration);
+
+    let result = async {
+        loop {
+            futures::select! {
+                message = rx.next() => {
+                    let message = match message {
+                        Some(message) => message,
+                        None => break,
+                    };
+
+                    writer.write_all(message.as_bytes()).await?;
+                    writer.write_all(b"\n").await?;
+                }
+                line = lines.next() => {
+                    let line = match line {
+                        Some(line) => line?,
+                        None => break,
+                    };
+
+                    match serde_json::from_str::<Incoming>(&line) {
+                        Ok(Incoming::Result { id, ok, message }) => {
+                            rpc.reply(&integration, id, Reply { ok, message });
+                        }
+                        Ok(Incoming::Hello { .. }) => {
+                            log::warn!(
+                                "game integration `{}` said hello twice",
+                                integration
+                            );
+                        }
+                        Err(e) => {
+                            log::warn!(
+                                "bad message from game integration `{}`: {}",
+                                integration,
+                                e
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+
+    rpc.unregister(&integration, serial);
+    log::info!("game integration `{}` disconnected", integration);
+    result
+}
+
+/// Send a single message.
+async fn send<W>(writer: &mut W, message: &Outgoing<'_>) -> Result<()>
+where
+    W: Unpin + tokio::io::AsyncWrite,
+{
+    let mut bytes = serde_json::to_vec(message)?;
+    bytes.push(b'\n');
+    writer.write_all(&bytes).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticate, supports, Incoming, Integration, Outgoing};
+
+    #[test]
+    fn test_authenticate() {
+        let integrations = vec![
+            Integration {
+                name: String::from("minecraft"),
+                token: String::from("hunter2"),
+            },
+            Integration {
+                name: String::from("skyrim"),
+                token: String::new(),
+            },
+        ];
+
+        assert!(authenticate(&integrations, "minecraft", "hunter2"));
+        assert!(!authenticate(&integrations, "minecraft", "hunter3"));
+        assert!(!authenticate(&integrations, "gtav", "hunter2"));
+        // NB: empty tokens are never accepted.
+        assert!(!authenticate(&integrations, "skyrim", ""));
+    }
+
+    #[test]
+    fn test_supports() {
+        let commands = vec![String::from("creeper")];
+        assert!(supports(&commands, "creeper"));
+        assert!(!supports(&commands, "zombie"));
+        assert!(supports(&[], "zombie"));
+    }
+
+    #[test]
+    fn test_protocol() {
+        let command = Outgoing::Command {
+            id: 1,
+            user: Some("setbac"),
+            command: "creeper",
+            args: "3",
+        };
+
+        assert_eq!(
+            r#"{"type":"command","id":1,"user":"setbac","command":"creeper","args":"3"}"#,
+            serde_json::to_string(&command).unwrap()
+        );
+
+        let hello = r#"{"type": "hello", "integration": "minecraft", "token": "hunter2"}"#;
+
+        match serde_json::from_str::<Incoming>(hello).unwrap() {
+            Incoming::Hello {
+                integration,
+                commands,
+                ..
+            } => {
+                assert_eq!("minecraft", integration);
+                assert!(commands.is_empty());
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        match serde_json::from_str::<Incoming>(r#"{"type": "result", "id": 2}"#).unwrap() {
+            Incoming::Result { id, ok, message } => {
+                assert_eq!(2, id);
+                assert!(ok);
+                assert!(message.is_none());
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+}
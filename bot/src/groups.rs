@@ -0,0 +1,236 @@
+//! Groups of commands, aliases, promotions, themes, and triggers which can be
+//! enabled or disabled together.
+//!
+//! This is useful for streamers who play different games, and want to switch
+//! between sets of commands at once.
+
+use crate::db;
+use crate::injector;
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// The kind of a group member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Alias,
+    Command,
+    Promotion,
+    Theme,
+    Trigger,
+}
+
+/// A single member of a group.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Member {
+    pub kind: Kind,
+    pub name: String,
+    pub disabled: bool,
+}
+
+/// A group and all of its members.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Group {
+    pub name: String,
+    pub members: Vec<Member>,
+}
+
+impl Group {
+    /// Test if all members of the group are disabled.
+    pub fn is_disabled(&self) -> bool {
+        self.members.iter().all(|m| m.disabled)
+    }
+}
+
+/// Collect grouped things into a sorted list of groups.
+///
+/// Things which don't belong to a group are ignored.
+fn collect<'a>(things: impl IntoIterator<Item = (Option<&'a str>, Member)>) -> Vec<Group> {
+    let mut groups = BTreeMap::<&str, Vec<Member>>::new();
+
+    for (group, member) in things {
+        if let Some(group) = group {
+            groups.entry(group).or_default().push(member);
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(name, mut members)| {
+            members.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+
+            Group {
+                name: name.to_string(),
+                members,
+            }
+        })
+        .collect()
+}
+
+/// Helper to manage groups across every kind of thing which can be grouped.
+#[derive(Clone)]
+pub struct Groups {
+    aliases: injector::Var<Option<db::Aliases>>,
+    commands: injector::Var<Option<db::Commands>>,
+    promotions: injector::Var<Option<db::Promotions>>,
+    themes: injector::Var<Option<db::Themes>>,
+    triggers: injector::Var<Option<db::Triggers>>,
+}
+
+impl Groups {
+    /// Construct a new groups helper from the injector.
+    pub async fn new(injector: &injector::Injector) -> Result<Self> {
+        Ok(Self {
+            aliases: injector.var().await?,
+            commands: injector.var().await?,
+            promotions: injector.var().await?,
+            themes: injector.var().await?,
+            triggers: injector.var().await?,
+        })
+    }
+
+    /// List all groups in the given channel.
+    pub async fn list(&self, channel: &str) -> Result<Vec<Group>> {
+        let aliases = match self.aliases.load().await {
+            Some(aliases) => aliases.list_all(channel).await?,
+            None => Vec::new(),
+        };
+
+        let commands = match self.commands.load().await {
+            Some(commands) => commands.list_all(channel).await?,
+            None => Vec::new(),
+        };
+
+        let promotions = match self.promotions.load().await {
+            Some(promotions) => promotions.list_all(channel).await?,
+            None => Vec::new(),
+        };
+
+        let themes = match self.themes.load().await {
+            Some(themes) => themes.list_all(channel).await?,
+            None => Vec::new(),
+        };
+
+        let triggers = match self.triggers.load().await {
+            Some(triggers) => triggers.list_all(channel).await?,
+            None => Vec::new(),
+        };
+
+        macro_rules! members {
+            ($things:expr, $kind:ident) => {
+                $things.iter().map(|t| {
+                    let member = Member {
+                        kind: Kind::$kind,
+                        name: t.key.name.clone(),
+                        disabled: t.disabled,
+                    };
+
+                    (t.group.as_deref(), member)
+                })
+            };
+        }
+
+        Ok(collect(
+            members!(aliases, Alias)
+                .chain(members!(commands, Command))
+                .chain(members!(promotions, Promotion))
+                .chain(members!(themes, Theme))
+                .chain(members!(triggers, Trigger)),
+        ))
+    }
+
+    /// Enable everything in the given group.
+    pub async fn enable(&self, channel: &str, group: &str) -> Result<()> {
+        if let Some(aliases) = self.aliases.load().await {
+            aliases.enable_group(channel, group).await?;
+        }
+
+        if let Some(commands) = self.commands.load().await {
+            commands.enable_group(channel, group).await?;
+        }
+
+        if let Some(promotions) = self.promotions.load().await {
+            promotions.enable_group(channel, group).await?;
+        }
+
+        if let Some(themes) = self.themes.load().await {
+            themes.enable_group(channel, group).await?;
+        }
+
+        if let Some(triggers) = self.triggers.load().await {
+            triggers.enable_group(channel, group).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Disable everything in the given group.
+    pub async fn disable(&self, channel: &str, group: &str) -> Result<()> {
+        if let Some(aliases) = self.aliases.load().await {
+            aliases.disable_group(channel, group).await?;
+        }
+
+        if let Some(commands) = self.commands.load().await {
+            commands.disable_group(channel, group).await?;
+        }
+
+        if let Some(promotions) = self.promotions.load().await {
+            promotions.disable_group(channel, group).await?;
+        }
+
+        if let Some(themes) = self.themes.load().await {
+            themes.disable_group(channel, group).await?;
+        }
+
+        if let Some(triggers) = self.triggers.load().await {
+            triggers.disable_group(channel, group).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect, Group, Kind, Member};
+
+    fn member(kind: Kind, name: &str, disabled: bool) -> Member {
+        Member {
+            kind,
+            name: name.to_string(),
+            disabled,
+        }
+    }
+
+    #[test]
+    fn test_collect() {
+        let groups = collect(vec![
+            (Some("rust"), member(Kind::Command, "crates", false)),
+            (None, member(Kind::Command, "ungrouped", false)),
+            (Some("gtav"), member(Kind::Promotion, "chaos", true)),
+            (Some("rust"), member(Kind::Alias, "cargo", true)),
+            (Some("rust"), member(Kind::Command, "book", false)),
+        ]);
+
+        assert_eq!(
+            vec![
+                Group {
+                    name: String::from("gtav"),
+                    members: vec![member(Kind::Promotion, "chaos", true)],
+                },
+                Group {
+                    name: String::from("rust"),
+                    members: vec![
+                        member(Kind::Alias, "cargo", true),
+                        member(Kind::Command, "book", false),
+                        member(Kind::Command, "crates", false),
+                    ],
+                },
+            ],
+            groups
+        );
+
+        assert!(groups[0].is_disabled());
+        assert!(!groups[1].is_disabled());
+    }
+}
@@ -1,8 +1,17 @@
 //! Idle detection for incoming messages.
+//!
+//! Besides counting messages, this keeps track of when chat was last active so
+//! that engagement prompts can be posted once chat has been quiet for a while.
 
 use crate::settings;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The window in which the number of posted prompts is capped.
+const PROMPT_WINDOW: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Clone)]
 pub struct Idle {
@@ -10,6 +19,8 @@ pub struct Idle {
     seen: Arc<AtomicUsize>,
     /// Last time we saw enough messages to not be considered idle.
     last: Arc<AtomicUsize>,
+    /// When the last message was seen.
+    last_seen: Arc<Mutex<Instant>>,
     threshold: settings::Var<u32>,
 }
 
@@ -19,6 +30,7 @@ impl Idle {
         Idle {
             seen: Arc::new(AtomicUsize::new(0)),
             last: Arc::new(AtomicUsize::new(0)),
+            last_seen: Arc::new(Mutex::new(Instant::now())),
             threshold,
         }
     }
@@ -26,6 +38,7 @@ impl Idle {
     /// Indicate that a message has been seen.
     pub fn seen(&self) {
         self.seen.fetch_add(1, Ordering::SeqCst);
+        *self.last_seen.lock() = Instant::now();
     }
 
     /// Test if there is enough messages to not bee considered "idle".
@@ -39,4 +52,153 @@ impl Idle {
 
         true
     }
+
+    /// How long chat has been quiet, as of `now`.
+    pub fn quiet_for(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(*self.last_seen.lock())
+    }
+}
+
+/// A prompt posted to engage chat when it has been quiet.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Prompt {
+    /// The message to post.
+    pub text: String,
+    /// How likely the prompt is to be picked, relative to other prompts.
+    /// Defaults to 1.
+    #[serde(default)]
+    pub weight: Option<u32>,
+}
+
+impl Prompt {
+    /// The weight of the prompt.
+    pub fn weight(&self) -> u32 {
+        self.weight.unwrap_or(1)
+    }
+}
+
+/// Decides when engagement prompts are posted, and which one.
+#[derive(Debug, Default)]
+pub struct Engagement {
+    /// When prompts were posted during the last hour.
+    posted: VecDeque<Instant>,
+    /// The index of the last prompt posted.
+    last_prompt: Option<usize>,
+}
+
+impl Engagement {
+    /// Test if a prompt should be posted at `now`.
+    ///
+    /// Chat must have been quiet for at least `quiet_time`, which also has to
+    /// have passed since the last prompt, and fewer than `max_per_hour`
+    /// prompts may have been posted during the last hour.
+    pub fn should_post(
+        &mut self,
+        now: Instant,
+        quiet_for: Duration,
+        quiet_time: Duration,
+        max_per_hour: u32,
+    ) -> bool {
+        while let Some(at) = self.posted.front() {
+            if now.saturating_duration_since(*at) < PROMPT_WINDOW {
+                break;
+            }
+
+            self.posted.pop_front();
+        }
+
+        if quiet_for < quiet_time || self.posted.len() >= max_per_hour as usize {
+            return false;
+        }
+
+        match self.posted.back() {
+            Some(at) => now.saturating_duration_since(*at) >= quiet_time,
+            None => true,
+        }
+    }
+
+    /// Weights used to pick the next prompt.
+    ///
+    /// The last prompt posted is excluded so that prompts rotate, unless it's
+    /// the only one which can be picked.
+    pub fn weights(&self, prompts: &[Prompt]) -> Vec<u32> {
+        let mut weights = prompts.iter().map(Prompt::weight).collect::<Vec<_>>();
+
+        if let Some(last) = self.last_prompt {
+            if weights.iter().enumerate().any(|(i, w)| i != last && *w > 0) {
+                if let Some(weight) = weights.get_mut(last) {
+                    *weight = 0;
+                }
+            }
+        }
+
+        weights
+    }
+
+    /// Record that the prompt with the given index was posted at `now`.
+    pub fn posted(&mut self, now: Instant, index: usize) {
+        self.posted.push_back(now);
+        self.last_prompt = Some(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Engagement, Prompt};
+    use std::time::{Duration, Instant};
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn prompt(text: &str, weight: Option<u32>) -> Prompt {
+        Prompt {
+            text: text.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn test_should_post() {
+        let start = Instant::now();
+        let mut engagement = Engagement::default();
+
+        assert!(!engagement.should_post(start, MINUTE * 5, MINUTE * 10, 2));
+        assert!(engagement.should_post(start, MINUTE * 10, MINUTE * 10, 2));
+        engagement.posted(start, 0);
+
+        // Too soon after the last prompt, even though chat is still quiet.
+        let now = start + MINUTE * 5;
+        assert!(!engagement.should_post(now, MINUTE * 15, MINUTE * 10, 2));
+
+        let now = start + MINUTE * 10;
+        assert!(engagement.should_post(now, MINUTE * 20, MINUTE * 10, 2));
+        engagement.posted(now, 1);
+
+        // Capped at two prompts per hour.
+        let now = start + MINUTE * 30;
+        assert!(!engagement.should_post(now, MINUTE * 40, MINUTE * 10, 2));
+
+        // The first prompt falls out of the window.
+        let now = start + MINUTE * 60;
+        assert!(engagement.should_post(now, MINUTE * 70, MINUTE * 10, 2));
+    }
+
+    #[test]
+    fn test_weights() {
+        let prompts = [
+            prompt("a", None),
+            prompt("b", Some(3)),
+            prompt("c", Some(0)),
+        ];
+        let mut engagement = Engagement::default();
+
+        assert_eq!(vec![1, 3, 0], engagement.weights(&prompts));
+
+        engagement.posted(Instant::now(), 1);
+        assert_eq!(vec![1, 0, 0], engagement.weights(&prompts));
+
+        // The last prompt is kept if it's the only one available.
+        let prompts = [prompt("a", Some(2))];
+        engagement.posted(Instant::now(), 0);
+        assert_eq!(vec![2], engagement.weights(&prompts));
+    }
 }
@@ -11,11 +11,12 @@ pub struct Builder {
     twitch: Twitch,
     pub(crate) message_log: message_log::MessageLog,
     pub(crate) cache_stream: injector::Stream<Cache>,
-    pub(crate) cache: Option<Cache>,
     pub(crate) enabled_stream: settings::Stream<bool>,
     pub(crate) enabled: bool,
     pub(crate) emotes_enabled_stream: settings::Stream<bool>,
     pub(crate) emotes_enabled: bool,
+    /// Emotes shared with everything else which needs to look them up.
+    pub(crate) emotes: Option<emotes::Emotes>,
 }
 
 impl Builder {
@@ -34,27 +35,42 @@ impl Builder {
 
         message_log.enabled(enabled).await;
 
+        let emotes = match cache {
+            Some(cache) => Some(emotes::Emotes::new(cache, twitch.clone())?),
+            None => None,
+        };
+
         Ok(Self {
             twitch,
             message_log,
             cache_stream,
-            cache,
             enabled_stream,
             enabled,
             emotes_enabled_stream,
             emotes_enabled,
+            emotes,
         })
     }
 
+    /// Update the cache, which requires emotes to be constructed again.
+    pub fn set_cache(&mut self, cache: Option<Cache>) -> Result<()> {
+        self.emotes = match cache {
+            Some(cache) => Some(emotes::Emotes::new(cache, self.twitch.clone())?),
+            None => None,
+        };
+
+        Ok(())
+    }
+
     /// Construct a new chat log with the specified configuration.
     pub fn build(&self) -> Result<Option<ChatLog>> {
         if !self.enabled {
             return Ok(None);
         }
 
-        let emotes = match (self.emotes_enabled, self.cache.as_ref()) {
-            (true, Some(cache)) => Some(emotes::Emotes::new(cache.clone(), self.twitch.clone())?),
-            _ => None,
+        let emotes = match self.emotes_enabled {
+            true => self.emotes.clone(),
+            false => None,
         };
 
         Ok(Some(ChatLog {
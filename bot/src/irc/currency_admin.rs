@@ -3,14 +3,16 @@ use crate::command;
 use crate::currency::{BalanceTransferError, Currency};
 use crate::db;
 use crate::injector::Injector;
+use crate::messages::Messages;
 use crate::prelude::*;
 use crate::utils;
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use std::sync::Arc;
 
 /// Handler for the !admin command.
 pub struct Handler {
     pub currency: injector::Var<Option<Currency>>,
+    pub messages: Messages,
 }
 
 impl Handler {
@@ -25,19 +27,27 @@ impl Handler {
 
 #[async_trait]
 impl command::Handler for Handler {
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!<currency> [show <user>|give <user> <amount>|boost <user> <amount>|windfall <amount>]",
+            description: "Check your balance of stream currency, or give it to others.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), Error> {
-        let currency = self
-            .currency
-            .load()
-            .await
-            .ok_or_else(|| respond_err!("No currency configured"))?;
+        let currency = self.currency.load().await.ok_or_else(|| {
+            respond_err!("{}", message!(self.messages, "currency/not-configured"))
+        })?;
 
         match ctx.next().as_deref() {
             None => {
                 let user = match ctx.user.real() {
                     Some(user) => user,
                     None => {
-                        respond!(ctx, "Only real users can check their balance");
+                        respond!(
+                            ctx,
+                            message!(self.messages, "currency/balance-only-real-users")
+                        );
                         return Ok(());
                     }
                 };
@@ -51,14 +61,17 @@ impl command::Handler for Handler {
 
                         respond!(
                             user,
-                            "You have {balance} {name} [{watch_time}].",
-                            balance = balance.balance,
-                            name = currency.name,
-                            watch_time = watch_time,
+                            message!(
+                                self.messages,
+                                "currency/balance",
+                                balance = balance.balance,
+                                name = currency.name,
+                                watch_time = watch_time,
+                            )
                         );
                     }
                     Err(e) => {
-                        respond!(user, "Could not get balance, sorry :(");
+                        respond!(user, message!(self.messages, "currency/balance-failed"));
                         log_error!(e, "failed to get balance");
                     }
                 }
@@ -74,15 +87,18 @@ impl command::Handler for Handler {
 
                         respond!(
                             ctx,
-                            "{user} has {balance} {name} [{watch_time}].",
-                            user = to_show,
-                            balance = balance.balance,
-                            name = currency.name,
-                            watch_time = watch_time,
+                            message!(
+                                self.messages,
+                                "currency/show",
+                                user = to_show,
+                                balance = balance.balance,
+                                name = currency.name,
+                                watch_time = watch_time,
+                            )
                         );
                     }
                     Err(e) => {
-                        respond!(ctx, "Count not get balance, sorry :(");
+                        respond!(ctx, message!(self.messages, "currency/balance-failed"));
                         log_error!(e, "failed to get balance");
                     }
                 }
@@ -94,21 +110,27 @@ impl command::Handler for Handler {
                 let user = match ctx.user.real() {
                     Some(user) => user,
                     None => {
-                        respond!(ctx, "Only real users can give currency");
+                        respond!(
+                            ctx,
+                            message!(self.messages, "currency/give-only-real-users")
+                        );
                         return Ok(());
                     }
                 };
 
                 if ctx.user.is(&taker) {
-                    respond!(ctx, "Giving to... yourself? But WHY?");
+                    respond!(ctx, message!(self.messages, "currency/give-self"));
                     return Ok(());
                 }
 
                 if amount <= 0 {
                     respond!(
                         ctx,
-                        "Can't give negative or zero {currency} LUL",
-                        currency = currency.name
+                        message!(
+                            self.messages,
+                            "currency/give-non-positive",
+                            currency = currency.name
+                        )
                     );
                     return Ok(());
                 }
@@ -127,25 +149,34 @@ impl command::Handler for Handler {
                     Ok(()) => {
                         respond!(
                             user,
-                            "Gave {user} {amount} {currency}!",
-                            user = taker,
-                            amount = amount,
-                            currency = currency.name
+                            message!(
+                                self.messages,
+                                "currency/gave",
+                                user = taker,
+                                amount = amount,
+                                currency = currency.name,
+                            )
                         );
                     }
                     Err(BalanceTransferError::NoBalance) => {
                         respond!(
                             user,
-                            "Not enough {currency} to transfer {amount}",
-                            currency = currency.name,
-                            amount = amount,
+                            message!(
+                                self.messages,
+                                "currency/not-enough",
+                                currency = currency.name,
+                                amount = amount,
+                            )
                         );
                     }
                     Err(BalanceTransferError::Other(e)) => {
                         respond!(
                             user,
-                            "Failed to give {currency}, sorry :(",
-                            currency = currency.name
+                            message!(
+                                self.messages,
+                                "currency/give-failed",
+                                currency = currency.name
+                            )
                         );
                         log_error!(e, "failed to modify currency");
                     }
@@ -158,10 +189,7 @@ impl command::Handler for Handler {
                 let amount: i64 = ctx.next_parse("<user> <amount>")?;
 
                 if !ctx.user.is_streamer() && ctx.user.is(&boosted_user) {
-                    respond!(
-                        ctx,
-                        "You gonna have to play by the rules (or ask another mod) :("
-                    );
+                    respond!(ctx, message!(self.messages, "currency/boost-self"));
                     return Ok(());
                 }
 
@@ -172,18 +200,24 @@ impl command::Handler for Handler {
                 if amount >= 0 {
                     respond!(
                         ctx,
-                        "Gave {user} {amount} {currency}!",
-                        user = boosted_user,
-                        amount = amount,
-                        currency = currency.name
+                        message!(
+                            self.messages,
+                            "currency/gave",
+                            user = boosted_user,
+                            amount = amount,
+                            currency = currency.name,
+                        )
                     );
                 } else {
                     respond!(
                         ctx,
-                        "Took away {amount} {currency} from {user}!",
-                        user = boosted_user,
-                        amount = -amount,
-                        currency = currency.name
+                        message!(
+                            self.messages,
+                            "currency/took",
+                            user = boosted_user,
+                            amount = -amount,
+                            currency = currency.name,
+                        )
                     );
                 }
             }
@@ -196,21 +230,19 @@ impl command::Handler for Handler {
                     .add_channel_all(ctx.user.channel(), amount, 0)
                     .await?;
 
-                if amount >= 0 {
-                    ctx.privmsg(format!(
-                        "/me gave {amount} {currency} to EVERYONE!",
-                        amount = amount,
-                        currency = currency.name
-                    ))
-                    .await;
+                let key = if amount >= 0 {
+                    "currency/windfall-gave"
                 } else {
-                    ctx.privmsg(format!(
-                        "/me took away {amount} {currency} from EVERYONE!",
-                        amount = amount,
-                        currency = currency.name
-                    ))
-                    .await;
-                }
+                    "currency/windfall-took"
+                };
+
+                ctx.privmsg(message!(
+                    self.messages,
+                    key,
+                    amount = amount,
+                    currency = currency.name,
+                ))
+                .await;
             }
             Some(..) => {
                 let mut alts = Vec::new();
@@ -245,6 +277,13 @@ impl command::Handler for Handler {
 
 pub async fn setup(injector: &Injector) -> Result<Arc<Handler>, Error> {
     let currency = injector.var::<Currency>().await?;
-    let handler = Handler { currency };
+
+    let messages = injector
+        .get::<Messages>()
+        .await
+        .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+    let handler = Handler { currency, messages };
+
     Ok(Arc::new(handler))
 }
@@ -1,27 +1,38 @@
+use crate::activity;
 use crate::api::{self, twitch};
 use crate::auth::{Auth, Role, Scope};
 use crate::bus;
+use crate::chat_stats::ChatStats;
 use crate::command;
-use crate::currency::CurrencyBuilder;
+use crate::currency::{CurrencyBuilder, RewardData, RewardSource, Rewards};
 use crate::db;
+use crate::emote_stats::EmoteStats;
+use crate::emotes;
+use crate::errors::Errors;
+use crate::flags::Flags;
 use crate::idle;
 use crate::injector::{self, Injector, Key};
 use crate::message_log::MessageLog;
+use crate::messages::Messages;
+use crate::metrics;
 use crate::module;
 use crate::oauth2;
 use crate::prelude::*;
 use crate::script;
 use crate::stream_info;
 use crate::task;
+use crate::template;
 use crate::utils::{self, Cooldown, Duration};
+use crate::webhooks::{self, Webhooks};
 use anyhow::{anyhow, bail, Context as _, Error, Result};
+use chrono::Utc;
 use irc::client::{self, Client};
 use irc::proto::command::{CapSubCommand, Command};
 use irc::proto::message::{Message, Tag};
 use leaky_bucket::LeakyBuckets;
 use notify::{RecommendedWatcher, Watcher};
 use parking_lot::RwLock;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::mem;
 use std::path::PathBuf;
@@ -32,15 +43,21 @@ use tracing::trace_span;
 use tracing_futures::Instrument as _;
 
 // re-exports
+pub use self::rate_limit::Priority;
 pub use self::sender::Sender;
 
 mod chat_log;
 mod currency_admin;
+mod rate_limit;
 mod sender;
 
 const SERVER: &str = "irc.chat.twitch.tv";
 const TWITCH_TAGS_CAP: &str = "twitch.tv/tags";
 const TWITCH_COMMANDS_CAP: &str = "twitch.tv/commands";
+/// Delay before the first attempt to reconnect to chat.
+const RECONNECT_MIN_DELAY: time::Duration = time::Duration::from_secs(5);
+/// The longest we wait between attempts to reconnect to chat.
+const RECONNECT_MAX_DELAY: time::Duration = time::Duration::from_secs(60 * 5);
 
 struct TwitchSetup {
     streamer_stream: injector::Stream<oauth2::SyncToken>,
@@ -175,7 +192,9 @@ pub struct Irc {
     pub injector: Injector,
     pub stream_state_tx: mpsc::Sender<stream_info::StreamState>,
     pub message_log: MessageLog,
+    pub errors: Errors,
     pub script_dirs: Vec<PathBuf>,
+    pub webhooks: Webhooks,
 }
 
 impl Irc {
@@ -193,7 +212,9 @@ impl Irc {
             injector,
             stream_state_tx,
             message_log,
+            errors,
             script_dirs,
+            webhooks,
         } = self;
 
         let (streamer_stream, streamer) = injector
@@ -217,10 +238,31 @@ impl Irc {
             bot_user: None,
         };
 
+        let (mut flags_stream, mut flags) = injector.stream::<Flags>().await;
+
+        let mut enabled = Vec::with_capacity(modules.len());
+        let mut enabled_streams = Vec::new();
+
+        for (index, module) in modules.iter().enumerate() {
+            match module.enabled_setting() {
+                Some((key, default)) => {
+                    let (stream, value) = settings.stream(key).or_with(default).await?;
+                    enabled.push(value);
+                    enabled_streams.push(stream.map(move |value| (index, value)).boxed());
+                }
+                None => enabled.push(true),
+            }
+        }
+
+        let mut enabled_stream = stream::select_all(enabled_streams);
+
         'outer: loop {
             let (bot, bot_twitch, streamer, streamer_twitch) = twitch_setup.setup().await?;
 
-            let channel = Arc::new(streamer_twitch.channel().await?);
+            let channel = match streamer_twitch.channel_by_id(&streamer.id).await? {
+                Some(channel) => Arc::new(channel),
+                None => bail!("no channel information for streamer: {}", streamer.name),
+            };
 
             log::trace!("Channel: {:?}", channel);
             log::trace!("Streamer: {:?}", streamer);
@@ -229,28 +271,26 @@ impl Irc {
             let chat_channel = format!("#{}", channel.name);
             *global_channel.write().await = Some(chat_channel.clone());
 
-            let access_token = bot_twitch.token.read().await?.access_token().to_string();
+            let connection = Connection::connect(&bot, &bot_twitch.token, &chat_channel).await;
 
-            let irc_client_config = client::data::config::Config {
-                nickname: Some(bot.name.to_string()),
-                channels: vec![chat_channel.clone()],
-                password: Some(format!("oauth:{}", access_token)),
-                server: Some(String::from(SERVER)),
-                port: Some(6697),
-                use_tls: Some(true),
-                ..client::data::config::Config::default()
+            let connection = match connection {
+                Ok(connection) => connection,
+                Err(e) => {
+                    log_warn!(e, "Failed to connect to chat, retrying in 5 seconds");
+                    tokio::time::delay_for(time::Duration::from_secs(5)).await;
+                    continue 'outer;
+                }
             };
 
-            let mut client = Client::from_config(irc_client_config).await?;
-            client.identify()?;
-
             let chat_settings = settings.scoped("chat");
 
             let url_whitelist_enabled = chat_settings.var("url-whitelist/enabled", true).await?;
             let bad_words_enabled = chat_settings.var("bad-words/enabled", false).await?;
             let sender_ty = chat_settings.var("sender-type", sender::Type::Chat).await?;
+            let verified_bot = chat_settings.var("verified-bot", false).await?;
             let threshold = chat_settings.var("idle-detection/threshold", 5).await?;
             let idle = idle::Idle::new(threshold);
+            let activity = activity::Activity::new();
 
             let nightbot = injector.var::<api::NightBot>().await?;
 
@@ -258,9 +298,11 @@ impl Irc {
 
             let sender = Sender::new(
                 sender_ty,
+                verified_bot,
                 chat_channel.clone(),
-                client.sender(),
+                connection.client.sender(),
                 nightbot.clone(),
+                sender::Announcer::new(bot_twitch.clone(), streamer.id.clone(), bot.id.clone()),
                 &buckets,
             )?;
 
@@ -279,6 +321,14 @@ impl Irc {
                     .boxed(),
             );
 
+            futures.push(
+                sender
+                    .clone()
+                    .run()
+                    .instrument(trace_span!(target: "futures", "chat-sender",))
+                    .boxed(),
+            );
+
             let stream_info = {
                 let (stream_info, mut stream_state_rx, future) =
                     stream_info::setup(streamer.clone(), streamer_twitch.clone());
@@ -306,6 +356,13 @@ impl Irc {
                         .boxed(),
                 );
 
+                injector
+                    .update(stream_info::Channel::new(
+                        streamer_twitch.clone(),
+                        stream_info.clone(),
+                    ))
+                    .await;
+
                 stream_info
             };
 
@@ -315,9 +372,16 @@ impl Irc {
                     .boxed(),
             );
 
-            let mut handlers = module::Handlers::default();
-
-            let scripts = script::load_dir(channel.name.clone(), db.clone(), &script_dirs).await?;
+            let scripts = script::load_dir(
+                channel.name.clone(),
+                db.clone(),
+                injector.var().await?,
+                injector.var().await?,
+                injector.var().await?,
+                settings.clone(),
+                &script_dirs,
+            )
+            .await?;
 
             let (scripts_watch_tx, scripts_watch_rx) = sync::mpsc::unbounded_channel();
             let mut scripts_watch_rx = scripts_watch_rx.fuse();
@@ -338,31 +402,30 @@ impl Irc {
                 None
             };
 
-            for module in modules.iter() {
-                if log::log_enabled!(log::Level::Trace) {
-                    log::trace!("initializing module: {}", module.ty());
-                }
-
-                let result = module
-                    .hook(module::HookContext {
-                        handlers: &mut handlers,
-                        futures: &mut futures,
-                        stream_info: &stream_info,
-                        idle: &idle,
-                        twitch: &bot_twitch,
-                        streamer_twitch: &streamer_twitch,
-                        sender: &sender,
-                        settings: &settings,
-                        injector: &injector,
-                        auth: &auth,
-                    })
-                    .await;
+            let module_setup = ModuleSetup {
+                stream_info: &stream_info,
+                idle: &idle,
+                activity: &activity,
+                twitch: &bot_twitch,
+                streamer_twitch: &streamer_twitch,
+                sender: &sender,
+                settings: &settings,
+                injector: &injector,
+                auth: &auth,
+                global_bus: &global_bus,
+            };
 
-                result.with_context(|| anyhow!("failed to initialize module: {}", module.ty()))?;
-            }
+            let mut hooked = module_setup
+                .hook_all(&modules, flags.as_ref(), &enabled)
+                .await?;
 
             let currency_handler = currency_admin::setup(&injector).await?;
 
+            let messages = injector
+                .get::<Messages>()
+                .await
+                .ok_or_else(|| anyhow!("messages are not configured"))?;
+
             let future = currency_loop(
                 streamer_twitch.clone(),
                 channel.clone(),
@@ -385,6 +448,9 @@ impl Irc {
                 .or_default()
                 .await?;
 
+            let (mut regulars_stream, regulars) =
+                chat_settings.stream("regulars").or_default().await?;
+
             let (mut moderator_cooldown_stream, moderator_cooldown) = chat_settings
                 .stream("moderator-cooldown")
                 .optional()
@@ -396,9 +462,8 @@ impl Irc {
             let join_message = chat_settings.get::<String>("join-message").await?;
 
             let leave_message = chat_settings
-                .get::<String>("leave-message")
-                .await?
-                .unwrap_or_else(|| String::from("Leaving chat... VoHiYo"));
+                .get_or_default::<String>("leave-message")
+                .await?;
 
             let mut chat_log_builder = chat_log::Builder::new(
                 bot_twitch.clone(),
@@ -408,6 +473,15 @@ impl Irc {
             )
             .await?;
 
+            let chat_stats = injector.var().await?;
+            let emote_stats = injector.var().await?;
+            let stream_sessions = injector.var().await?;
+            let chatters = injector.var().await?;
+            let moderation_log = injector.var().await?;
+            let subscribers = injector.var().await?;
+            let counters = injector.var().await?;
+            let variables = injector.var().await?;
+            let lookup = api::Lookup::new(injector.var().await?)?;
             let (mut commands_stream, commands) = injector.stream().await;
             let (mut aliases_stream, aliases) = injector.stream().await;
 
@@ -418,6 +492,7 @@ impl Irc {
                 sender: sender.clone(),
                 moderators: Default::default(),
                 vips: Default::default(),
+                regulars: Arc::new(RwLock::new(normalize_users(regulars))),
                 whitelisted_hosts,
                 commands,
                 bad_words: &bad_words,
@@ -425,50 +500,58 @@ impl Irc {
                 aliases,
                 api_url: Arc::new(api_url),
                 moderator_cooldown,
-                handlers,
+                handlers: Arc::new(merge_handlers(&hooked)),
                 scripts,
                 idle: &idle,
+                activity: &activity,
                 pong_timeout: &mut pong_timeout,
                 token: &bot_twitch.token,
-                handler_shutdown: false,
+                reconnect: false,
                 stream_info: &stream_info,
                 auth: &auth,
                 currency_handler,
                 url_whitelist_enabled,
                 bad_words_enabled,
                 chat_log: chat_log_builder.build()?,
+                emotes: chat_log_builder.emotes.clone(),
+                chat_stats,
+                emote_stats,
+                stream_sessions,
+                chatters,
+                moderation_log,
+                subscribers,
+                counters,
+                variables,
+                lookup,
                 channel,
                 context_inner: Arc::new(command::ContextInner {
                     sender: sender.clone(),
-                    scope_cooldowns: sync::Mutex::new(auth.scope_cooldowns()),
+                    cooldowns: command::Cooldowns::new(
+                        auth.scope_cooldowns(),
+                        chat_settings.optional("command-cooldown").await?,
+                    ),
                     message_hooks: sync::RwLock::new(Default::default()),
                     restart: restart.clone(),
                 }),
+                errors: errors.clone(),
+                webhooks: webhooks.clone(),
+                messages,
             };
 
-            let mut outgoing = client
-                .outgoing()
-                .ok_or_else(|| anyhow!("missing outgoing future for irc client"))?;
-
-            let mut client_stream = client.stream()?;
+            // NB: the stream and outgoing future are `None` while we are
+            // reconnecting.
+            let mut _client = connection.client;
+            let mut client_stream = Some(connection.stream);
+            let mut outgoing = Some(connection.outgoing);
+            let mut reconnecting: Option<future::BoxFuture<'static, Connection>> = None;
+            let mut disconnected: Option<Error> = None;
 
             let mut ping_interval = tokio::time::interval(time::Duration::from_secs(10)).fuse();
             let mut commands = command_bus.subscribe().fuse();
 
             let mut leave = None;
 
-            let sender = handler.sender.clone();
-
-            // Things to do when joining.
-            let mut join_task = Some(Box::pin(async move {
-                sender.cap_req(TWITCH_TAGS_CAP).await;
-                sender.cap_req(TWITCH_COMMANDS_CAP).await;
-
-                if let Some(join_message) = join_message.as_ref() {
-                    // greeting when bot joins.
-                    sender.privmsg_immediate(join_message);
-                }
-            }));
+            let mut join_task = Some(join(handler.sender.clone(), join_message, false).boxed());
 
             #[allow(clippy::unnecessary_mut_passed)]
             while leave.is_none() {
@@ -476,6 +559,21 @@ impl Irc {
                     _ = join_task.current() => {
                         log::trace!("Done sending capabilities request and join message");
                     }
+                    connection = reconnecting.current() => {
+                        log::info!("Reconnected to chat");
+
+                        reconnecting = None;
+                        handler.sender.reconnect(connection.client.sender());
+                        _client = connection.client;
+                        client_stream = Some(connection.stream);
+                        outgoing = Some(connection.outgoing);
+
+                        // NB: re-request capabilities and resynchronize
+                        // everything we might have missed while we were
+                        // disconnected.
+                        join_task = Some(join(handler.sender.clone(), None, true).boxed());
+                        stream_info.resync();
+                    }
                     ev = scripts_watch_rx.select_next_some() => {
                         if let Ok(ev) = ev {
                             if let Err(e) = handler.handle_script_filesystem_event(ev) {
@@ -494,6 +592,17 @@ impl Irc {
                                     log_error!(e, "Failed to handle message");
                                 }
                             }
+                            bus::Command::Send { message } => {
+                                log::trace!("Send message: {}", message);
+                                handler.sender.privmsg(message).await;
+                            }
+                            bus::Command::Run { command, role } => {
+                                log::trace!("Run command as {}: {}", role, command);
+
+                                if let Err(e) = handler.run(command, role).await {
+                                    log_error!(e, "Failed to run command");
+                                }
+                            }
                         }
                     }
                     future = futures.select_next_some() => {
@@ -509,6 +618,19 @@ impl Irc {
                             }
                         }
                     }
+                    future = next_module_future(&mut hooked).fuse() => {
+                        match future {
+                            Ok(..) => {
+                                log::warn!("Module component exited, exiting...");
+                                break 'outer;
+                            }
+                            Err(e) => {
+                                log_warn!(e, "Module component errored, restarting in 5 seconds");
+                                tokio::time::delay_for(time::Duration::from_secs(5)).await;
+                                continue 'outer;
+                            }
+                        }
+                    }
                     update = twitch_setup.streamer_stream.select_next_some() => {
                         if twitch_setup.update_streamer(update).await? {
                             leave = Some(tokio::time::delay_for(time::Duration::from_secs(1)));
@@ -526,8 +648,9 @@ impl Irc {
                         handler.aliases = update;
                     }
                     cache = chat_log_builder.cache_stream.select_next_some() => {
-                        chat_log_builder.cache = cache;
+                        chat_log_builder.set_cache(cache)?;
                         handler.chat_log = chat_log_builder.build()?;
+                        handler.emotes = chat_log_builder.emotes.clone();
                     }
                     update = chat_log_builder.enabled_stream.select_next_some() => {
                         chat_log_builder.enabled = update;
@@ -545,34 +668,119 @@ impl Irc {
                         handler.moderator_cooldown = update;
                     }
                     _ = ping_interval.select_next_some() => {
-                        handler.send_ping()?;
+                        if client_stream.is_some() {
+                            handler.send_ping()?;
+                        }
                     }
                     _ = handler.pong_timeout.current() => {
-                        bail!("server not responding");
+                        disconnected = Some(anyhow!("server not responding"));
                     }
                     update = whitelisted_hosts_stream.next() => {
                         if let Some(update) = update {
                             handler.whitelisted_hosts = update;
                         }
                     },
-                    message = client_stream.next() => {
-                        if let Some(m) = message.transpose()? {
-                            if let Err(e) = handler.handle(m).await {
-                                log_error!(e, "Failed to handle message");
+                    update = regulars_stream.select_next_some() => {
+                        *handler.regulars.write() = normalize_users(update);
+                    },
+                    update = flags_stream.select_next_some() => {
+                        // NB: set up modules again if their flag changed.
+                        let changed = modules
+                            .iter()
+                            .enumerate()
+                            .filter_map(|(index, m)| Some((index, m.flag()?)))
+                            .filter(|(_, flag)| {
+                                let is_enabled = |f: &Option<Flags>| {
+                                    f.as_ref().map(|f| f.is_enabled(flag)).unwrap_or_default()
+                                };
+
+                                is_enabled(&flags) != is_enabled(&update)
+                            })
+                            .map(|(index, _)| index)
+                            .collect::<Vec<_>>();
+
+                        flags = update;
+
+                        for &index in &changed {
+                            log::info!(
+                                "Flag for module `{}` changed, setting it up again",
+                                modules[index].ty()
+                            );
+
+                            // NB: tear down the module before setting it up
+                            // again.
+                            hooked[index] = None;
+                            hooked[index] = module_setup
+                                .hook(&*modules[index], flags.as_ref(), enabled[index])
+                                .await?;
+                        }
+
+                        if !changed.is_empty() {
+                            handler.handlers = Arc::new(merge_handlers(&hooked));
+                        }
+                    },
+                    update = enabled_stream.select_next_some() => {
+                        let (index, update) = update;
+
+                        if mem::replace(&mut enabled[index], update) != update {
+                            log::info!(
+                                "Module `{}` {}",
+                                modules[index].ty(),
+                                if update { "enabled" } else { "disabled" },
+                            );
+
+                            // NB: tear down the module before setting it up
+                            // again.
+                            hooked[index] = None;
+                            hooked[index] = module_setup
+                                .hook(&*modules[index], flags.as_ref(), update)
+                                .await?;
+
+                            handler.handlers = Arc::new(merge_handlers(&hooked));
+                        }
+                    },
+                    message = next_message(&mut client_stream).fuse() => {
+                        match message {
+                            Some(Ok(m)) => {
+                                if let Err(e) = handler.handle(m).await {
+                                    log_error!(e, "Failed to handle message");
+                                }
+                            }
+                            Some(Err(e)) => {
+                                disconnected = Some(Error::from(e));
+                            }
+                            None => {
+                                disconnected = Some(anyhow!("connection closed by server"));
                             }
                         }
 
-                        if handler.handler_shutdown {
-                            bail!("handler forcibly shut down");
+                        if mem::take(&mut handler.reconnect) {
+                            disconnected = Some(anyhow!("reconnect requested"));
                         }
                     }
-                    _ = outgoing => {
-                        bail!("outgoing future ended unexpectedly");
+                    result = outgoing.current() => {
+                        disconnected = Some(match result {
+                            Ok(()) => anyhow!("outgoing connection closed"),
+                            Err(e) => Error::from(e),
+                        });
                     }
                     _ = leave.current() => {
                         break;
                     }
                 }
+
+                if let Some(e) = disconnected.take() {
+                    log_warn!(e, "Disconnected from chat, reconnecting");
+
+                    client_stream = None;
+                    outgoing = None;
+                    *handler.pong_timeout = None;
+
+                    reconnecting = Some(
+                        reconnect(bot.clone(), bot_twitch.token.clone(), chat_channel.clone())
+                            .boxed(),
+                    );
+                }
             }
 
             handler.sender.privmsg_immediate(leave_message);
@@ -580,8 +788,9 @@ impl Irc {
             #[allow(clippy::never_loop, clippy::unnecessary_mut_passed)]
             loop {
                 futures::select! {
-                    _ = outgoing => {
-                        bail!("outgoing future ended unexpectedly");
+                    _ = outgoing.current() => {
+                        log::warn!("Disconnected from chat while leaving");
+                        break;
                     }
                     _ = leave.current() => {
                         break;
@@ -594,6 +803,224 @@ impl Irc {
     }
 }
 
+/// A connection to chat.
+struct Connection {
+    /// The client which the connection belongs to.
+    client: Client,
+    /// Stream of incoming messages.
+    stream: client::ClientStream,
+    /// Future which sends outgoing messages.
+    outgoing: client::Outgoing,
+}
+
+impl Connection {
+    /// Connect to chat as the given bot and join the given channel.
+    async fn connect(
+        bot: &twitch::User,
+        token: &oauth2::SyncToken,
+        channel: &str,
+    ) -> Result<Connection> {
+        let access_token = token.read().await?.access_token().to_string();
+
+        let config = client::data::config::Config {
+            nickname: Some(bot.name.to_string()),
+            channels: vec![channel.to_string()],
+            password: Some(format!("oauth:{}", access_token)),
+            server: Some(String::from(SERVER)),
+            port: Some(6697),
+            use_tls: Some(true),
+            ..client::data::config::Config::default()
+        };
+
+        let mut client = Client::from_config(config).await?;
+        client.identify()?;
+
+        let outgoing = client
+            .outgoing()
+            .ok_or_else(|| anyhow!("missing outgoing future for irc client"))?;
+
+        let stream = client.stream()?;
+
+        Ok(Connection {
+            client,
+            stream,
+            outgoing,
+        })
+    }
+}
+
+/// Reconnect to chat, backing off exponentially between failed attempts.
+async fn reconnect(
+    bot: Arc<twitch::User>,
+    token: oauth2::SyncToken,
+    channel: String,
+) -> Connection {
+    let mut delay = RECONNECT_MIN_DELAY;
+
+    loop {
+        tokio::time::delay_for(delay).await;
+
+        match Connection::connect(&bot, &token, &channel).await {
+            Ok(connection) => return connection,
+            Err(e) => {
+                delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+                log_warn!(
+                    e,
+                    "Failed to reconnect to chat, retrying in {} seconds",
+                    delay.as_secs()
+                );
+            }
+        }
+    }
+}
+
+/// Get the next message from chat, or wait forever while disconnected.
+async fn next_message(
+    stream: &mut Option<client::ClientStream>,
+) -> Option<Result<Message, irc::error::Error>> {
+    match stream {
+        Some(stream) => stream.next().await,
+        None => future::pending().await,
+    }
+}
+
+/// Things to do when joining chat.
+///
+/// When rejoining after a reconnect, the join message isn't sent again but
+/// moderators and VIPs are refreshed.
+async fn join(sender: Sender, join_message: Option<String>, rejoin: bool) {
+    sender.cap_req(TWITCH_TAGS_CAP).await;
+    sender.cap_req(TWITCH_COMMANDS_CAP).await;
+
+    if let Some(join_message) = join_message.as_ref() {
+        // greeting when bot joins.
+        sender.privmsg_immediate(join_message);
+    }
+
+    if rejoin {
+        sender.mods();
+        sender.vips();
+    }
+}
+
+/// Everything needed to set up modules.
+struct ModuleSetup<'a> {
+    stream_info: &'a stream_info::StreamInfo,
+    idle: &'a idle::Idle,
+    activity: &'a activity::Activity,
+    twitch: &'a api::Twitch,
+    streamer_twitch: &'a api::Twitch,
+    sender: &'a Sender,
+    settings: &'a settings::Settings,
+    injector: &'a Injector,
+    auth: &'a Auth,
+    global_bus: &'a Arc<bus::Bus<bus::Global>>,
+}
+
+impl ModuleSetup<'_> {
+    /// Set up all modules which are active, returning what was set up for
+    /// each module.
+    async fn hook_all(
+        &self,
+        modules: &[Box<dyn module::Module>],
+        flags: Option<&Flags>,
+        enabled: &[bool],
+    ) -> Result<Vec<Option<Hooked>>> {
+        let mut hooked = Vec::with_capacity(modules.len());
+
+        for (module, enabled) in modules.iter().zip(enabled) {
+            hooked.push(self.hook(&**module, flags, *enabled).await?);
+        }
+
+        Ok(hooked)
+    }
+
+    /// Set up a single module if it is active, returning its command
+    /// handlers and background futures.
+    ///
+    /// Dropping what is returned tears the module down again.
+    async fn hook(
+        &self,
+        module: &dyn module::Module,
+        flags: Option<&Flags>,
+        enabled: bool,
+    ) -> Result<Option<Hooked>> {
+        if let Some(flag) = module.flag() {
+            if !flags.map(|f| f.is_enabled(flag)).unwrap_or_default() {
+                log::trace!(
+                    "skipping module `{}`, flag `{}` disabled",
+                    module.ty(),
+                    flag
+                );
+                return Ok(None);
+            }
+        }
+
+        if !enabled {
+            log::trace!("skipping module `{}`, disabled", module.ty());
+            return Ok(None);
+        }
+
+        if log::log_enabled!(log::Level::Trace) {
+            log::trace!("initializing module: {}", module.ty());
+        }
+
+        let mut handlers = module::Handlers::default();
+        let mut futures = utils::Futures::new();
+
+        let result = module
+            .hook(module::HookContext {
+                handlers: &mut handlers,
+                futures: &mut futures,
+                stream_info: self.stream_info,
+                idle: self.idle,
+                activity: self.activity,
+                twitch: self.twitch,
+                streamer_twitch: self.streamer_twitch,
+                sender: self.sender,
+                settings: self.settings,
+                injector: self.injector,
+                auth: self.auth,
+                global_bus: self.global_bus,
+            })
+            .await;
+
+        result.with_context(|| anyhow!("failed to initialize module: {}", module.ty()))?;
+        Ok(Some(Hooked { handlers, futures }))
+    }
+}
+
+/// A module which has been set up.
+struct Hooked {
+    handlers: module::Handlers,
+    futures: utils::Futures,
+}
+
+/// Merge the command handlers of all modules which are set up.
+fn merge_handlers(hooked: &[Option<Hooked>]) -> module::Handlers {
+    let mut handlers = module::Handlers::default();
+
+    for hooked in hooked.iter().flatten() {
+        handlers.extend(&hooked.handlers);
+    }
+
+    handlers
+}
+
+/// Wait for the next background future of any module which is set up to
+/// complete.
+fn next_module_future(hooked: &mut [Option<Hooked>]) -> impl Future<Output = Result<()>> + '_ {
+    future::poll_fn(move |cx| {
+        for hooked in hooked.iter_mut().flatten() {
+            if let Poll::Ready(Some(result)) = hooked.futures.poll_next_unpin(cx) {
+                return Poll::Ready(result);
+            }
+        }
+
+        Poll::Pending
+    })
+}
+
 /// Set up a reward loop.
 async fn currency_loop(
     twitch: api::Twitch,
@@ -606,6 +1033,11 @@ async fn currency_loop(
 ) -> Result<impl Future<Output = Result<()>>> {
     log::trace!("Setting up currency loop");
 
+    let messages = injector
+        .get::<Messages>()
+        .await
+        .ok_or_else(|| anyhow!("messages are not configured"))?;
+
     let reward = 10;
     let default_interval = Duration::seconds(60 * 10);
 
@@ -615,6 +1047,7 @@ async fn currency_loop(
         .await?;
 
     let reward_percentage = chat_settings.var("viewer-reward%", 100).await?;
+    let rewards = Rewards::new(&settings).await?;
     let (mut viewer_reward_stream, viewer_reward) = chat_settings
         .stream("viewer-reward/enabled")
         .or_with(false)
@@ -712,15 +1145,31 @@ async fn currency_loop(
                     log::trace!("running reward loop");
 
                     let reward = (reward * reward_percentage.load().await as i64) / 100i64;
+                    let reward = rewards.amount(RewardSource::ViewerReward, reward).await;
                     let count = currency
                         .add_channel_all(&channel.name, reward, seconds)
                         .await?;
 
                     if notify_rewards && count > 0 && !idle.is_idle().await {
-                        sender.privmsg(format!(
-                            "/me has given {} {} to all viewers!",
-                            reward, currency.name
-                        )).await;
+                        let data = RewardData {
+                            user: None,
+                            amount: reward,
+                            currency: &currency.name,
+                        };
+
+                        let message = rewards.message(RewardSource::ViewerReward, data).await;
+
+                        let message = match message {
+                            Some(message) => message,
+                            None => message!(
+                                messages,
+                                "currency/viewer-reward",
+                                amount = reward,
+                                currency = currency.name,
+                            ),
+                        };
+
+                        sender.privmsg(message).await;
                     }
                 }
             }
@@ -738,6 +1187,8 @@ struct Handler<'a> {
     moderators: Arc<RwLock<HashSet<String>>>,
     /// VIPs.
     vips: Arc<RwLock<HashSet<String>>>,
+    /// Regulars, as configured in `chat/regulars`.
+    regulars: Arc<RwLock<HashSet<String>>>,
     /// Whitelisted hosts for links.
     whitelisted_hosts: HashSet<String>,
     /// All registered commands.
@@ -753,17 +1204,19 @@ struct Handler<'a> {
     /// Active moderator cooldown.
     moderator_cooldown: Option<Cooldown>,
     /// Handlers for specific commands like `!skip`.
-    handlers: module::Handlers,
+    handlers: Arc<module::Handlers>,
     /// Dynamic handlers.
     scripts: script::Scripts,
     /// Build idle detection.
     idle: &'a idle::Idle,
+    /// Tracking of users active in chat.
+    activity: &'a activity::Activity,
     /// Pong timeout currently running.
     pong_timeout: &'a mut Option<tokio::time::Delay>,
     /// OAuth 2.0 Token used to authenticate with IRC.
     token: &'a oauth2::SyncToken,
-    /// Force a shutdown.
-    handler_shutdown: bool,
+    /// Reconnect to chat, like after the token has been refreshed.
+    reconnect: bool,
     /// Stream information.
     stream_info: &'a stream_info::StreamInfo,
     /// Information about auth.
@@ -774,10 +1227,36 @@ struct Handler<'a> {
     url_whitelist_enabled: settings::Var<bool>,
     /// Handler for chat logs.
     chat_log: Option<chat_log::ChatLog>,
+    /// Emotes used to find emotes in messages.
+    emotes: Option<emotes::Emotes>,
+    /// Persistent chat statistics.
+    chat_stats: injector::Var<Option<ChatStats>>,
+    /// Emote usage statistics.
+    emote_stats: injector::Var<Option<EmoteStats>>,
+    /// Sessions which group what happens during each stream.
+    stream_sessions: injector::Var<Option<db::StreamSessions>>,
+    /// When users were first and last seen in chat.
+    chatters: injector::Var<Option<db::Chatters>>,
+    /// Log of deletions, timeouts, and bans in chat.
+    moderation_log: injector::Var<Option<db::ModerationLog>>,
+    /// Subscription streaks and gift counts.
+    subscribers: injector::Var<Option<db::Subscribers>>,
+    /// Counters which can be used in custom commands.
+    counters: injector::Var<Option<db::Counters>>,
+    /// Variables which can be used in custom commands.
+    variables: injector::Var<Option<db::Variables>>,
+    /// Lookups for the `api` helper in custom commands.
+    lookup: api::Lookup,
     /// Information on the current channel.
     channel: Arc<twitch::Channel>,
     /// Shared context paramters.
     context_inner: Arc<command::ContextInner>,
+    /// Storage for errors raised by command handlers.
+    errors: Errors,
+    /// Outgoing webhooks.
+    webhooks: Webhooks,
+    /// Messages to respond with.
+    messages: Messages,
 }
 
 impl Handler<'_> {
@@ -839,10 +1318,14 @@ async fn process_command(
     currency_handler: &Arc<currency_admin::Handler>,
     handlers: &module::Handlers,
     scripts: &script::Scripts,
+    errors: &Errors,
+    webhooks: &Webhooks,
+    stream_sessions: &injector::Var<Option<db::StreamSessions>>,
+    messages: &Messages,
 ) -> Result<()> {
     match command {
         "ping" => {
-            respond!(ctx, "What do you want?");
+            respond!(ctx, message!(messages, "chat/ping"));
             global_bus.send(bus::Global::Ping).await;
         }
         other => {
@@ -855,7 +1338,7 @@ async fn process_command(
                 (other, Some(ref name)) if other == **name => {
                     Some(currency_handler.clone() as Arc<dyn command::Handler>)
                 }
-                (other, Some(..)) | (other, None) => handlers.get(other),
+                (other, Some(..)) | (other, None) => handlers.resolve(ctx.channel(), other).await,
             };
 
             if let Some(handler) = handler {
@@ -870,22 +1353,45 @@ async fn process_command(
                 if let Some(scope) = scope {
                     if !ctx.user.has_scope(scope).await {
                         if ctx.user.is_moderator() {
-                            respond!(ctx, "You are not allowed to run that command");
+                            respond!(ctx, message!(messages, "chat/not-allowed"));
                         } else {
-                            respond!(ctx, "Do you think this is a democracy? LUL");
+                            respond!(ctx, message!(messages, "chat/not-allowed-user"));
                         }
 
                         return Ok(());
                     }
                 }
 
+                if !ctx.user.has_scope(Scope::BypassCooldowns).await {
+                    if let Some(name) = ctx.user.name() {
+                        if let Some(remaining) = ctx.cooldowns().check_command(other, name).await {
+                            respond!(
+                                ctx,
+                                message!(
+                                    messages,
+                                    "chat/cooldown",
+                                    remaining = utils::compact_duration(remaining),
+                                )
+                            );
+                            return Ok(());
+                        }
+                    }
+                }
+
+                metrics::COMMANDS.inc();
+                command_executed(webhooks, stream_sessions, other, &ctx).await;
+                let errors = errors.clone();
+                let messages = messages.clone();
+                let context = format!("!{}", other);
+
                 task::spawn(async move {
                     if let Err(e) = handler.handle(&mut ctx).await {
                         if let Some(command::Respond(respond)) = e.downcast_ref() {
                             respond!(ctx, respond);
                         } else {
-                            respond!(ctx, "Sorry, something went wrong :(");
-                            log_error!(e, "Error when processing command");
+                            let code = errors.record(context, &e).await;
+                            respond!(ctx, message!(messages, "chat/error", code = code));
+                            log_error!(e, "Error when processing command (ref: {})", code);
                         }
                     }
                 });
@@ -894,9 +1400,14 @@ async fn process_command(
             }
 
             if let Some(handler) = scripts.get(other) {
+                metrics::COMMANDS.inc();
+                command_executed(webhooks, stream_sessions, other, &ctx).await;
+
                 if let Err(e) = handler.call(ctx.clone()).await {
-                    ctx.respond("Sorry, something went wrong :(").await;
-                    log_error!(e, "Error when processing command");
+                    let code = errors.record(format!("!{}", other), &e).await;
+                    ctx.respond(message!(messages, "chat/error", code = code))
+                        .await;
+                    log_error!(e, "Error when processing command (ref: {})", code);
                 }
 
                 return Ok(());
@@ -907,6 +1418,24 @@ async fn process_command(
     Ok(())
 }
 
+/// Notify webhooks that a command was executed and count it towards the
+/// current stream session.
+async fn command_executed(
+    webhooks: &Webhooks,
+    stream_sessions: &injector::Var<Option<db::StreamSessions>>,
+    command: &str,
+    ctx: &command::Context,
+) {
+    webhooks.send(webhooks::Event::CommandExecuted {
+        command: command.to_string(),
+        user: ctx.user.name().map(String::from),
+    });
+
+    if let Some(stream_sessions) = stream_sessions.read().await.as_ref() {
+        stream_sessions.command_used(command);
+    }
+}
+
 impl<'a> Handler<'a> {
     /// Delete the given message.
     fn delete_message(&self, user: &User) -> Result<()> {
@@ -920,11 +1449,22 @@ impl<'a> Handler<'a> {
         Ok(())
     }
 
+    /// Record an action in the moderation log.
+    async fn log_moderation(&self, action: db::AddModerationAction) {
+        if let Some(moderation_log) = self.moderation_log.load().await {
+            if let Err(e) = moderation_log.push(&action).await {
+                log_error!(e, "failed to record moderation action");
+            }
+        }
+    }
+
     /// Test if the message should be deleted.
-    async fn should_be_deleted(&self, user: &User, message: &str) -> bool {
+    ///
+    /// Returns a description of the rule that the message broke.
+    async fn should_be_deleted(&self, user: &User, message: &str) -> Option<String> {
         // Moderators can say whatever they want.
         if user.is_moderator() {
-            return false;
+            return None;
         }
 
         if self.bad_words_enabled.load().await {
@@ -945,7 +1485,7 @@ impl<'a> Handler<'a> {
                     }
                 }
 
-                return true;
+                return Some(format!("bad word `{}`", word.word));
             }
         }
 
@@ -954,13 +1494,13 @@ impl<'a> Handler<'a> {
             if !user.has_scope(Scope::ChatBypassUrlWhitelist).await
                 && self.url_whitelist_enabled.load().await
             {
-                if self.has_bad_link(message) {
-                    return true;
+                if let Some(host) = self.bad_link(message) {
+                    return Some(format!("link to `{}`", host));
                 }
             }
         }
 
-        false
+        None
     }
 
     /// Test the message for bad words.
@@ -976,17 +1516,18 @@ impl<'a> Handler<'a> {
         None
     }
 
-    /// Check if the given iterator has URLs that need to be
-    fn has_bad_link(&self, message: &str) -> bool {
+    /// Find the host of the first link in the message which isn't
+    /// whitelisted.
+    fn bad_link(&self, message: &str) -> Option<String> {
         for url in utils::Urls::new(message) {
             if let Some(host) = url.host_str() {
                 if !self.whitelisted_hosts.contains(host) {
-                    return true;
+                    return Some(host.to_string());
                 }
             }
         }
 
-        false
+        None
     }
 
     /// Send a ping to the remote server.
@@ -999,28 +1540,79 @@ impl<'a> Handler<'a> {
         Ok(())
     }
 
+    /// Record subscription streaks, gifts, and per-stream statistics.
+    async fn record_sub(&self, tags: &UserNoticeTags) {
+        let kind = match tags.kind() {
+            Some(kind) => kind,
+            None => return,
+        };
+
+        if let Some(chat_stats) = self.chat_stats.load().await {
+            chat_stats.observe_sub(kind).await;
+        }
+
+        let subscribers = match self.subscribers.load().await {
+            Some(subscribers) => subscribers,
+            None => return,
+        };
+
+        // NB: anonymous gifters are reported with a placeholder login.
+        let login = match &tags.login {
+            Some(login) if tags.msg_id != "anonsubgift" => login,
+            _ => return,
+        };
+
+        let result = match kind {
+            bus::AlertKind::Sub | bus::AlertKind::Resub => {
+                subscribers
+                    .record_sub(login, tags.months, tags.streak)
+                    .await
+            }
+            bus::AlertKind::SubGift => subscribers.record_gift(login, tags.gifts).await,
+            _ => return,
+        };
+
+        if let Err(e) = result {
+            log_error!(e, "failed to record subscription by {}", login);
+        }
+    }
+
     /// Process the given command.
     pub async fn process_message(&mut self, user: &User, mut message: Arc<String>) -> Result<()> {
-        // Run message hooks.
-        let _ = task::spawn({
-            let user = user.clone();
-            let context_inner = self.context_inner.clone();
-            let message = message.clone();
-
-            async move {
-                let message_hooks = context_inner.message_hooks.read().await;
-
-                for (key, hook) in &*message_hooks {
-                    if let Err(e) = hook.peek(&user, &*message).await {
-                        log_error!(e, "Hook `{}` failed", key);
+        if !user.is_whisper() {
+            // Run message hooks. Whispers are skipped since hooks respond in
+            // the channel.
+            let _ = task::spawn({
+                let user = user.clone();
+                let context_inner = self.context_inner.clone();
+                let handlers = self.handlers.clone();
+                let message = message.clone();
+
+                async move {
+                    let message_hooks = context_inner.message_hooks.read().await;
+
+                    for (key, hook) in &*message_hooks {
+                        if let Err(e) = hook.peek(&user, &*message).await {
+                            log_error!(e, "Hook `{}` failed", key);
+                        }
+                    }
+
+                    for hook in handlers.hooks() {
+                        if let Err(e) = hook.peek(&user, &*message).await {
+                            log_error!(e, "Module hook failed");
+                        }
                     }
                 }
+            });
+
+            // only non-moderators and non-streamer bumps the idle counter.
+            if !user.is_streamer() {
+                self.idle.seen();
             }
-        });
 
-        // only non-moderators and non-streamer bumps the idle counter.
-        if !user.is_streamer() {
-            self.idle.seen();
+            if let Some(name) = user.name() {
+                self.activity.seen(name);
+            }
         }
 
         // NB: declared here to be in scope.
@@ -1034,8 +1626,11 @@ impl<'a> Handler<'a> {
                 if !seen.insert(key.clone()) {
                     respond!(
                         user,
-                        "Recursion found in alias expansion: {} :(",
-                        path.join(" -> ")
+                        message!(
+                            self.messages,
+                            "chat/alias-recursion",
+                            path = path.join(" -> "),
+                        )
                     );
                     return Ok(());
                 }
@@ -1056,15 +1651,57 @@ impl<'a> Handler<'a> {
                     commands.increment(&*command).await?;
                 }
 
+                let uses_counters =
+                    command.has_var_or_field("counters") || command.has_var("counter");
+
+                let counters = match self.counters.load().await {
+                    Some(counters) if uses_counters => counters.values(user.channel()).await,
+                    _ => HashMap::new(),
+                };
+
+                let var = match self.variables.load().await {
+                    Some(variables) if command.has_var_or_field("var") => {
+                        variables.values(user.channel()).await
+                    }
+                    _ => HashMap::new(),
+                };
+
                 let vars = CommandVars {
                     name: user.display_name(),
                     target: user.channel(),
                     count: command.count(),
+                    counters,
+                    var,
+                    api: HashMap::new(),
                     captures,
                 };
 
-                let response = command.render(&vars)?;
-                self.sender.privmsg(response).await;
+                let urls = command.template.api_urls();
+
+                if urls.is_empty() {
+                    let response = command.render(&vars)?;
+                    respond_command(user, &self.sender, response).await;
+                } else {
+                    // NB: looking up APIs might be slow, so the command is
+                    // rendered in the background to not hold up chat.
+                    let mut data = serde_json::to_value(&vars)?;
+                    let lookup = self.lookup.clone();
+                    let sender = self.sender.clone();
+                    let user = user.clone();
+
+                    task::spawn(async move {
+                        let api = lookup.get_all(urls).await;
+
+                        if let serde_json::Value::Object(data) = &mut data {
+                            data.insert(template::API_DATA.to_string(), serde_json::json!(api));
+                        }
+
+                        match command.render(&data) {
+                            Ok(response) => respond_command(&user, &sender, response).await,
+                            Err(e) => log_error!(e, "failed to render command"),
+                        }
+                    });
+                }
             }
         }
 
@@ -1076,6 +1713,7 @@ impl<'a> Handler<'a> {
                     api_url: self.api_url.clone(),
                     user: user.clone(),
                     it,
+                    handlers: self.handlers.clone(),
                     inner: self.context_inner.clone(),
                 };
 
@@ -1086,6 +1724,10 @@ impl<'a> Handler<'a> {
                     &self.currency_handler,
                     &self.handlers,
                     &self.scripts,
+                    &self.errors,
+                    &self.webhooks,
+                    &self.stream_sessions,
+                    &self.messages,
                 );
 
                 if let Err(e) = result.await {
@@ -1094,8 +1736,26 @@ impl<'a> Handler<'a> {
             }
         }
 
-        if self.should_be_deleted(&user, &*message).await {
+        if user.is_whisper() {
+            return Ok(());
+        }
+
+        if let Some(rule) = self.should_be_deleted(&user, &*message).await {
             self.delete_message(&user)?;
+
+            if let Some(name) = user.name() {
+                self.log_moderation(db::AddModerationAction {
+                    channel: user.channel().to_string(),
+                    action: String::from("delete"),
+                    user: name.to_lowercase(),
+                    user_id: user.tags().user_id.clone(),
+                    message: Some(message.to_string()),
+                    rule: Some(rule),
+                    duration: None,
+                    at: Utc::now().naive_utc(),
+                })
+                .await;
+            }
         }
 
         Ok(())
@@ -1103,21 +1763,73 @@ impl<'a> Handler<'a> {
 
     /// Run the given raw command.
     pub async fn raw(&mut self, message: String) -> Result<()> {
+        let user = self.injected_user(Principal::Injected);
+        self.process_message(&user, Arc::new(message)).await
+    }
+
+    /// Run the given command on behalf of a web API caller with the given
+    /// role.
+    ///
+    /// The command is subject to the scope system like in chat, but tested
+    /// against the role of the caller.
+    pub async fn run(&mut self, command: String, role: Role) -> Result<()> {
+        let user = self.injected_user(Principal::Api { role });
+        let command = format!("!{}", command.trim_start_matches('!'));
+        self.process_message(&user, Arc::new(command)).await
+    }
+
+    /// Construct a user which doesn't correspond to anyone in chat.
+    fn injected_user(&self, principal: Principal) -> User {
         let tags = Tags::default();
 
-        let user = User {
+        User {
             inner: Arc::new(UserInner {
                 tags,
                 sender: self.sender.clone(),
-                principal: Principal::Injected,
+                principal,
+                whisper: false,
                 streamer: self.streamer.clone(),
                 moderators: self.moderators.clone(),
                 vips: self.vips.clone(),
+                regulars: self.regulars.clone(),
                 stream_info: self.stream_info.clone(),
                 auth: self.auth.clone(),
+                messages: self.messages.clone(),
             }),
-        };
+        }
+    }
+
+    /// Construct a user who sent a message in chat, or whispered it to the
+    /// bot.
+    fn chat_user(&self, tags: Tags, name: String, whisper: bool) -> User {
+        User {
+            inner: Arc::new(UserInner {
+                tags,
+                sender: self.sender.clone(),
+                principal: Principal::User { name },
+                whisper,
+                streamer: self.streamer.clone(),
+                moderators: self.moderators.clone(),
+                vips: self.vips.clone(),
+                regulars: self.regulars.clone(),
+                stream_info: self.stream_info.clone(),
+                auth: self.auth.clone(),
+                messages: self.messages.clone(),
+            }),
+        }
+    }
 
+    /// Handle a message whispered to the bot.
+    ///
+    /// Only commands are handled, and responses to them are whispered back.
+    async fn handle_whisper(&mut self, tags: Tags, name: String, message: String) -> Result<()> {
+        if !message.starts_with('!') {
+            return Ok(());
+        }
+
+        log::trace!("Whisper from {}: {}", name, message);
+
+        let user = self.chat_user(tags, name, true);
         self.process_message(&user, Arc::new(message)).await
     }
 
@@ -1125,6 +1837,7 @@ impl<'a> Handler<'a> {
     pub async fn handle(&mut self, mut m: Message) -> Result<()> {
         match m.command {
             Command::PRIVMSG(_, ref mut message) => {
+                metrics::MESSAGES.inc();
                 let message = Arc::new(mem::replace(message, String::new()));
                 let tags = Tags::from_tags(m.tags.take());
 
@@ -1144,19 +1857,64 @@ impl<'a> Handler<'a> {
                     }));
                 }
 
-                let user = User {
-                    inner: Arc::new(UserInner {
-                        tags,
-                        sender: self.sender.clone(),
-                        principal: Principal::User { name },
-                        streamer: self.streamer.clone(),
-                        moderators: self.moderators.clone(),
-                        vips: self.vips.clone(),
-                        stream_info: self.stream_info.clone(),
-                        auth: self.auth.clone(),
-                    }),
+                if let Some(chat_stats) = self.chat_stats.load().await {
+                    chat_stats.observe(&tags, &name, &*message).await;
+                }
+
+                let emote_stats = match self.emote_stats.load().await {
+                    Some(emote_stats) if emote_stats.is_enabled().await => Some(emote_stats),
+                    _ => None,
                 };
 
+                if let (Some(emotes), Some(emote_stats)) = (self.emotes.clone(), emote_stats) {
+                    let tags = tags.clone();
+                    let channel = self.channel.clone();
+                    let message = message.clone();
+
+                    task::spawn(Box::pin(async move {
+                        if let Err(e) = emote_stats
+                            .observe(&emotes, &tags, &*channel, &*message)
+                            .await
+                        {
+                            log::warn!("failed to count emotes: {}", e);
+                        }
+                    }));
+                }
+
+                if let Some(chatters) = self.chatters.load().await {
+                    chatters.observe(self.sender.channel(), &name);
+                }
+
+                if let Some(bits) = tags.bits {
+                    let user = tags.display_name.clone().unwrap_or_else(|| name.clone());
+
+                    self.global_bus
+                        .send(bus::Global::Alert {
+                            kind: bus::AlertKind::Cheer,
+                            user,
+                            months: None,
+                            streak: None,
+                            recipient: None,
+                            gifts: None,
+                            viewers: None,
+                            bits: Some(bits),
+                            amount: None,
+                            message: None,
+                        })
+                        .await;
+                }
+
+                if let Some(reward_id) = tags.custom_reward_id.clone() {
+                    self.global_bus
+                        .send(bus::Global::Redemption {
+                            reward_id,
+                            user: name.clone(),
+                            text: message.to_string(),
+                        })
+                        .await;
+                }
+
+                let user = self.chat_user(tags, name, false);
                 self.process_message(&user, message).await?;
             }
             Command::CAP(_, CapSubCommand::ACK, _, ref what) => {
@@ -1199,7 +1957,7 @@ impl<'a> Handler<'a> {
                 match tags.msg_id.as_deref() {
                     _ if message == "Login authentication failed" => {
                         self.token.force_refresh().await?;
-                        self.handler_shutdown = true;
+                        self.reconnect = true;
                     }
                     Some("no_mods") => {
                         self.moderators.write().clear();
@@ -1231,7 +1989,52 @@ impl<'a> Handler<'a> {
                         }
                     }
                 }
+                "WHISPER" => {
+                    let name = m.source_nickname().map(String::from);
+
+                    if let (Some(name), Some(message)) = (name, tail.get(1)) {
+                        let tags = Tags::from_tags(m.tags);
+                        self.handle_whisper(tags, name, message.clone()).await?;
+                    }
+                }
+                "USERSTATE" => {
+                    // NB: sent when joining and after every message we send,
+                    // with the badges of the bot.
+                    let tags = Tags::from_tags(m.tags);
+                    let moderator = tags.badges.as_deref().map(has_moderator_badge);
+                    self.sender.set_moderator(moderator.unwrap_or_default());
+                }
+                "USERNOTICE" => {
+                    if let Some(tags) = UserNoticeTags::from_tags(m.tags) {
+                        self.record_sub(&tags).await;
+
+                        if let Some(alert) = tags.into_alert() {
+                            self.global_bus.send(alert).await;
+                        }
+                    }
+                }
                 "CLEARCHAT" => {
+                    if let Some(user) = tail.first() {
+                        let tags = ClearChatTags::from_tags(m.tags);
+
+                        let action = match tags.ban_duration {
+                            Some(..) => "timeout",
+                            None => "ban",
+                        };
+
+                        self.log_moderation(db::AddModerationAction {
+                            channel: self.sender.channel().to_string(),
+                            action: action.to_string(),
+                            user: user.to_lowercase(),
+                            user_id: tags.target_user_id,
+                            message: None,
+                            rule: None,
+                            duration: tags.ban_duration,
+                            at: Utc::now().naive_utc(),
+                        })
+                        .await;
+                    }
+
                     if let Some(chat_log) = self.chat_log.as_ref() {
                         match tail.first() {
                             Some(user) => {
@@ -1266,6 +2069,7 @@ pub struct RealUser<'a> {
     streamer: &'a twitch::User,
     moderators: &'a RwLock<HashSet<String>>,
     vips: &'a RwLock<HashSet<String>>,
+    regulars: &'a RwLock<HashSet<String>>,
     stream_info: &'a stream_info::StreamInfo,
     auth: &'a Auth,
 }
@@ -1321,8 +2125,13 @@ impl<'a> RealUser<'a> {
         self.vips.read().contains(self.name)
     }
 
+    /// Test if regular.
+    fn is_regular(&self) -> bool {
+        self.regulars.read().contains(self.name)
+    }
+
     /// Get a list of all roles the current requester belongs to.
-    pub fn roles(&self) -> smallvec::SmallVec<[Role; 4]> {
+    pub fn roles(&self) -> smallvec::SmallVec<[Role; 6]> {
         let mut roles = smallvec::SmallVec::new();
 
         if self.is_streamer() {
@@ -1341,6 +2150,10 @@ impl<'a> RealUser<'a> {
             roles.push(Role::Vip);
         }
 
+        if self.is_regular() {
+            roles.push(Role::Regular);
+        }
+
         roles.push(Role::Everyone);
         roles
     }
@@ -1353,8 +2166,14 @@ impl<'a> RealUser<'a> {
 
 /// Information about the user.
 pub enum Principal {
-    User { name: String },
+    User {
+        name: String,
+    },
     Injected,
+    /// A web API caller with the given role.
+    Api {
+        role: Role,
+    },
 }
 
 /// Inner struct for User to make it cheaper to clone.
@@ -1362,11 +2181,16 @@ struct UserInner {
     tags: Tags,
     sender: Sender,
     principal: Principal,
+    /// If the message from the user was whispered to the bot, in which case
+    /// responses are whispered back.
+    whisper: bool,
     streamer: Arc<twitch::User>,
     moderators: Arc<RwLock<HashSet<String>>>,
     vips: Arc<RwLock<HashSet<String>>>,
+    regulars: Arc<RwLock<HashSet<String>>>,
     stream_info: stream_info::StreamInfo,
     auth: Auth,
+    messages: Messages,
 }
 
 #[derive(Clone)]
@@ -1385,10 +2209,11 @@ impl User {
                 streamer: &*self.inner.streamer,
                 moderators: &*self.inner.moderators,
                 vips: &*self.inner.vips,
+                regulars: &*self.inner.regulars,
                 stream_info: &self.inner.stream_info,
                 auth: &self.inner.auth,
             }),
-            Principal::Injected => None,
+            Principal::Injected | Principal::Api { .. } => None,
         }
     }
 
@@ -1401,7 +2226,7 @@ impl User {
     pub fn name(&self) -> Option<&str> {
         match self.inner.principal {
             Principal::User { ref name, .. } => Some(name),
-            Principal::Injected => None,
+            Principal::Injected | Principal::Api { .. } => None,
         }
     }
 
@@ -1434,18 +2259,43 @@ impl User {
         self.real().map(|u| u.is(name)).unwrap_or(false)
     }
 
+    /// Test if the user with the given name is a moderator in the channel.
+    pub fn is_moderator_in_channel(&self, name: &str) -> bool {
+        self.inner.moderators.read().contains(name)
+    }
+
     /// Test if streamer.
     fn is_streamer(&self) -> bool {
-        self.real().map(|u| u.is_streamer()).unwrap_or(true)
+        match self.inner.principal {
+            Principal::Api { role } => role == Role::Streamer,
+            _ => self.real().map(|u| u.is_streamer()).unwrap_or(true),
+        }
     }
 
     /// Test if moderator.
     fn is_moderator(&self) -> bool {
-        self.real().map(|u| u.is_moderator()).unwrap_or(true)
+        match self.inner.principal {
+            Principal::Api { role } => role == Role::Streamer || role == Role::Moderator,
+            _ => self.real().map(|u| u.is_moderator()).unwrap_or(true),
+        }
+    }
+
+    /// Test if the message from the user was whispered to the bot.
+    pub fn is_whisper(&self) -> bool {
+        self.inner.whisper
     }
 
     /// Respond to the user with a message.
+    ///
+    /// If the user whispered to the bot, the response is whispered back.
     pub async fn respond(&self, m: impl fmt::Display) {
+        if self.inner.whisper {
+            if let Some(name) = self.name() {
+                self.inner.sender.whisper(name, m).await;
+                return;
+            }
+        }
+
         match self.display_name() {
             Some(name) => {
                 self.inner
@@ -1459,6 +2309,27 @@ impl User {
         }
     }
 
+    /// Whisper a message to the user, so that it doesn't clutter chat.
+    ///
+    /// Users which aren't in chat get a regular response instead.
+    pub async fn whisper(&self, m: impl fmt::Display) {
+        match self.name() {
+            Some(name) => self.inner.sender.whisper(name, m).await,
+            None => self.respond(m).await,
+        }
+    }
+
+    /// Reply to the message of the user in its thread.
+    ///
+    /// Whispers, and messages which can't be replied to, get a regular
+    /// response instead.
+    pub async fn reply(&self, m: impl fmt::Display) {
+        match self.inner.tags.id.as_deref() {
+            Some(id) if !self.inner.whisper => self.inner.sender.reply(id, m).await,
+            _ => self.respond(m).await,
+        }
+    }
+
     /// Render an iterable of results, that implements display.
     pub async fn respond_lines<I>(&self, results: I, empty: &str)
     where
@@ -1471,8 +2342,13 @@ impl User {
             let count = output.count();
 
             if count > 0 {
-                self.respond(format!("{} ... {} line(s) not shown", line, count))
-                    .await;
+                self.respond(message!(
+                    self.inner.messages,
+                    "chat/lines-not-shown",
+                    line = line,
+                    count = count,
+                ))
+                .await;
             } else {
                 self.respond(line).await;
             }
@@ -1482,15 +2358,27 @@ impl User {
     }
 
     /// Get a list of all roles the current requester belongs to.
-    pub fn roles(&self) -> smallvec::SmallVec<[Role; 4]> {
+    pub fn roles(&self) -> smallvec::SmallVec<[Role; 6]> {
+        if let Principal::Api { role } = self.inner.principal {
+            let mut roles = smallvec::SmallVec::<[Role; 6]>::default();
+            roles.push(role);
+
+            if role != Role::Everyone {
+                roles.push(Role::Everyone);
+            }
+
+            return roles;
+        }
+
         match self.real().map(|u| u.roles()) {
             Some(roles) => roles,
             None => {
-                let mut roles = smallvec::SmallVec::<[Role; 4]>::default();
+                let mut roles = smallvec::SmallVec::<[Role; 6]>::default();
                 roles.push(Role::Streamer);
                 roles.push(Role::Moderator);
                 roles.push(Role::Subscriber);
                 roles.push(Role::Vip);
+                roles.push(Role::Regular);
                 roles
             }
         }
@@ -1498,6 +2386,16 @@ impl User {
 
     /// Test if the current user has the given scope.
     pub async fn has_scope(&self, scope: Scope) -> bool {
+        if let Principal::Api { .. } = self.inner.principal {
+            for role in self.roles() {
+                if self.inner.auth.test_role(scope, role).await {
+                    return true;
+                }
+            }
+
+            return false;
+        }
+
         let user = match self.real() {
             Some(user) => user,
             None => return false,
@@ -1608,6 +2506,12 @@ pub struct Tags {
     pub emotes: Option<String>,
     /// Badges part of the message.
     pub badges: Option<String>,
+    /// Number of bits cheered with the message.
+    pub bits: Option<u32>,
+    /// The ID of the channel points reward redeemed with the message.
+    pub custom_reward_id: Option<String>,
+    /// If this is the first message the user has sent in the channel.
+    pub first_msg: bool,
 }
 
 impl Tags {
@@ -1621,6 +2525,9 @@ impl Tags {
         let mut color = None;
         let mut emotes = None;
         let mut badges = None;
+        let mut bits = None;
+        let mut custom_reward_id = None;
+        let mut first_msg = false;
 
         if let Some(tags) = tags {
             for t in tags {
@@ -1633,6 +2540,9 @@ impl Tags {
                         "color" => color = Some(value),
                         "emotes" => emotes = Some(value),
                         "badges" => badges = Some(value),
+                        "bits" => bits = str::parse(&value).ok(),
+                        "custom-reward-id" => custom_reward_id = Some(value),
+                        "first-msg" => first_msg = value == "1",
                         _ => (),
                     },
                     _ => (),
@@ -1648,10 +2558,102 @@ impl Tags {
             color,
             emotes,
             badges,
+            bits,
+            custom_reward_id,
+            first_msg,
         }
     }
 }
 
+/// Tags associated with a USERNOTICE.
+struct UserNoticeTags {
+    msg_id: String,
+    display_name: Option<String>,
+    login: Option<String>,
+    months: Option<u32>,
+    streak: Option<u32>,
+    recipient: Option<String>,
+    gifts: Option<u32>,
+    viewers: Option<u32>,
+}
+
+impl UserNoticeTags {
+    /// Extract tags from message.
+    #[allow(clippy::single_match)]
+    fn from_tags(tags: Option<Vec<Tag>>) -> Option<UserNoticeTags> {
+        let mut msg_id = None;
+        let mut display_name = None;
+        let mut login = None;
+        let mut months = None;
+        let mut streak = None;
+        let mut share_streak = false;
+        let mut recipient = None;
+        let mut gifts = None;
+        let mut viewers = None;
+
+        if let Some(tags) = tags {
+            for t in tags {
+                match t {
+                    Tag(name, Some(value)) => match name.as_str() {
+                        "msg-id" => msg_id = Some(value),
+                        "display-name" => display_name = Some(value),
+                        "login" => login = Some(value),
+                        "msg-param-cumulative-months" => months = str::parse(&value).ok(),
+                        "msg-param-streak-months" => streak = str::parse(&value).ok(),
+                        "msg-param-should-share-streak" => share_streak = value == "1",
+                        "msg-param-recipient-display-name" => recipient = Some(value),
+                        // NB: zero if the gifter doesn't share their total.
+                        "msg-param-sender-count" => {
+                            gifts = str::parse::<u32>(&value).ok().filter(|n| *n > 0)
+                        }
+                        "msg-param-viewerCount" => viewers = str::parse(&value).ok(),
+                        _ => (),
+                    },
+                    _ => (),
+                }
+            }
+        }
+
+        Some(UserNoticeTags {
+            msg_id: msg_id?,
+            display_name,
+            login,
+            months,
+            streak: streak.filter(|_| share_streak),
+            recipient,
+            gifts,
+            viewers,
+        })
+    }
+
+    /// Get the kind of alert the notice corresponds to, if it's one we know of.
+    fn kind(&self) -> Option<bus::AlertKind> {
+        Some(match self.msg_id.as_str() {
+            "sub" => bus::AlertKind::Sub,
+            "resub" => bus::AlertKind::Resub,
+            "subgift" | "anonsubgift" => bus::AlertKind::SubGift,
+            "raid" => bus::AlertKind::Raid,
+            _ => return None,
+        })
+    }
+
+    /// Convert into an alert on the global bus, if it's an alert we know of.
+    fn into_alert(self) -> Option<bus::Global> {
+        Some(bus::Global::Alert {
+            kind: self.kind()?,
+            user: self.display_name.or(self.login)?,
+            months: self.months,
+            streak: self.streak,
+            recipient: self.recipient,
+            gifts: self.gifts,
+            viewers: self.viewers,
+            bits: None,
+            amount: None,
+            message: None,
+        })
+    }
+}
+
 /// Tags associated with a CLEARMSG.
 struct ClearMsgTags {
     target_msg_id: String,
@@ -1681,6 +2683,42 @@ impl ClearMsgTags {
     }
 }
 
+/// Tags associated with a CLEARCHAT.
+struct ClearChatTags {
+    /// How long the user was timed out for in seconds, or `None` if they were
+    /// banned.
+    ban_duration: Option<i64>,
+    /// The ID of the user who was timed out or banned.
+    target_user_id: Option<String>,
+}
+
+impl ClearChatTags {
+    /// Extract tags from message.
+    #[allow(clippy::single_match)]
+    fn from_tags(tags: Option<Vec<Tag>>) -> ClearChatTags {
+        let mut ban_duration = None;
+        let mut target_user_id = None;
+
+        if let Some(tags) = tags {
+            for t in tags {
+                match t {
+                    Tag(name, Some(value)) => match name.as_str() {
+                        "ban-duration" => ban_duration = str::parse(&value).ok(),
+                        "target-user-id" => target_user_id = Some(value),
+                        _ => (),
+                    },
+                    _ => (),
+                }
+            }
+        }
+
+        ClearChatTags {
+            ban_duration,
+            target_user_id,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum SenderThreadItem {
     Exit,
@@ -1698,10 +2736,26 @@ pub struct CommandVars<'a> {
     name: Option<&'a str>,
     target: &'a str,
     count: i32,
+    counters: HashMap<String, i64>,
+    var: HashMap<String, String>,
+    /// Responses used by the `api` helper.
+    ///
+    /// NB: has to be named the same as `template::API_DATA`.
+    #[serde(rename = "_api")]
+    api: HashMap<String, serde_json::Value>,
     #[serde(flatten)]
     captures: db::Captures<'a>,
 }
 
+/// Send the response of a command, privately if it was whispered.
+async fn respond_command(user: &User, sender: &Sender, response: String) {
+    if user.is_whisper() {
+        user.respond(response).await;
+    } else {
+        sender.privmsg(response).await;
+    }
+}
+
 // Future to refresh moderators every 5 minutes.
 async fn refresh_mods_future(sender: Sender) -> Result<()> {
     let mut interval = tokio::time::interval(time::Duration::from_secs(60 * 5));
@@ -1735,9 +2789,28 @@ fn parse_room_members(message: &str) -> HashSet<String> {
     out
 }
 
+/// Normalize a configured set of users into login names, so that entries like
+/// `@Setbac` match `setbac`.
+fn normalize_users(users: HashSet<String>) -> HashSet<String> {
+    users
+        .into_iter()
+        .map(|u| u.trim().trim_start_matches('@').to_lowercase())
+        .filter(|u| !u.is_empty())
+        .collect()
+}
+
+/// Test if the given badges make the bot a moderator or the broadcaster,
+/// which lets it send more messages.
+fn has_moderator_badge(badges: &str) -> bool {
+    badges
+        .split(',')
+        .filter_map(|badge| badge.split('/').next())
+        .any(|badge| badge == "moderator" || badge == "broadcaster")
+}
+
 #[cfg(test)]
 mod tests {
-    use super::parse_room_members;
+    use super::{has_moderator_badge, normalize_users, parse_room_members, ClearChatTags, Tag};
     use std::collections::HashSet;
 
     #[test]
@@ -1761,4 +2834,41 @@ mod tests {
             parse_room_members("The moderators of this channel are:")
         );
     }
+
+    #[test]
+    fn test_normalize_users() {
+        let users = vec!["Setbac", "@setbac", " @Udoprog ", "@", ""]
+            .into_iter()
+            .map(String::from)
+            .collect::<HashSet<String>>();
+
+        assert_eq!(
+            vec![String::from("setbac"), String::from("udoprog")]
+                .into_iter()
+                .collect::<HashSet<String>>(),
+            normalize_users(users)
+        );
+    }
+
+    #[test]
+    fn test_clear_chat_tags() {
+        let tags = ClearChatTags::from_tags(Some(vec![
+            Tag(String::from("ban-duration"), Some(String::from("600"))),
+            Tag(String::from("target-user-id"), Some(String::from("1234"))),
+        ]));
+
+        assert_eq!(Some(600), tags.ban_duration);
+        assert_eq!(Some("1234"), tags.target_user_id.as_deref());
+
+        let tags = ClearChatTags::from_tags(None);
+        assert_eq!(None, tags.ban_duration);
+    }
+
+    #[test]
+    fn test_has_moderator_badge() {
+        assert!(has_moderator_badge("moderator/1"));
+        assert!(has_moderator_badge("broadcaster/1,subscriber/12"));
+        assert!(!has_moderator_badge("vip/1,subscriber/0"));
+        assert!(!has_moderator_badge(""));
+    }
 }
@@ -0,0 +1,312 @@
+//! Rate limiting of messages sent to chat.
+//!
+//! Twitch only allows 20 messages to be sent every 30 seconds, or 100 if the
+//! bot is a moderator or broadcaster in the channel or a verified bot.
+//! Going over these limits gets the bot muted, so messages are queued up
+//! and sent at a pace which stays within them.
+
+use irc::proto::command::Command;
+use irc::proto::message::Message;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Period over which Twitch counts sent messages.
+const PERIOD: Duration = Duration::from_secs(30);
+/// How many messages can be sent per period by regular users.
+const REGULAR_LIMIT: u32 = 20;
+/// How many messages can be sent per period by moderators, broadcasters and
+/// verified bots.
+const ELEVATED_LIMIT: u32 = 100;
+/// The longest message Twitch accepts.
+const MAX_MESSAGE_LEN: usize = 500;
+/// How many low priority messages are held on to before the oldest one is
+/// dropped.
+const LOW_PRIORITY_CAPACITY: usize = 10;
+/// Separator used when batching low priority messages together.
+const SEPARATOR: &str = " | ";
+/// The shortest we wait for a token, to avoid spinning on rounding errors.
+const MIN_WAIT: Duration = Duration::from_millis(1);
+
+/// The priority of a message sent to chat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Messages sent in response to something, like command responses.
+    ///
+    /// These are always sent, and before any messages of low priority.
+    High,
+    /// Messages sent periodically, like promotions.
+    ///
+    /// These are batched together when we can't keep up, and dropped if too
+    /// many of them are waiting.
+    Low,
+}
+
+/// What to do next with the queue of messages.
+#[derive(Debug)]
+pub enum Next {
+    /// Send the given message right away.
+    Send(Message),
+    /// Wait for the given duration until a message can be sent.
+    Wait(Duration),
+    /// Nothing is waiting to be sent.
+    Idle,
+}
+
+/// A token bucket.
+///
+/// The bucket only holds a quarter of the limit and is refilled with the
+/// rest over the period, so that no more than the limit can be sent during
+/// any period, even when starting out with a full bucket.
+struct Bucket {
+    limit: u32,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: u32, now: Instant) -> Self {
+        let mut bucket = Self {
+            limit,
+            tokens: 0.0,
+            updated: now,
+        };
+
+        bucket.tokens = bucket.capacity();
+        bucket
+    }
+
+    /// The most tokens the bucket can hold.
+    fn capacity(&self) -> f64 {
+        f64::from(self.limit / 4)
+    }
+
+    /// Tokens added to the bucket every second.
+    fn rate(&self) -> f64 {
+        f64::from(self.limit - self.limit / 4) / PERIOD.as_secs_f64()
+    }
+
+    /// Change the limit of the bucket.
+    fn set_limit(&mut self, limit: u32, now: Instant) {
+        if self.limit == limit {
+            return;
+        }
+
+        self.refill(now);
+        self.limit = limit;
+        self.tokens = self.tokens.min(self.capacity());
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate()).min(self.capacity());
+        self.updated = now;
+    }
+
+    /// Take a token, or return how long to wait until one is available.
+    fn acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let wait = Duration::from_secs_f64((1.0 - self.tokens) / self.rate());
+        Err(wait.max(MIN_WAIT))
+    }
+
+    /// Take a token even if none is available, which delays what is sent
+    /// after it.
+    fn force(&mut self, now: Instant) {
+        self.refill(now);
+        self.tokens -= 1.0;
+    }
+}
+
+/// Queue of messages to send to chat, which decides when they can be sent.
+pub struct Limiter {
+    target: String,
+    bucket: Bucket,
+    high: VecDeque<Message>,
+    low: VecDeque<String>,
+}
+
+impl Limiter {
+    /// Construct a new limiter for messages sent to the given channel.
+    pub fn new(target: String, now: Instant) -> Self {
+        Self {
+            target,
+            bucket: Bucket::new(REGULAR_LIMIT, now),
+            high: VecDeque::new(),
+            low: VecDeque::new(),
+        }
+    }
+
+    /// Set if the bot is allowed to send more messages, because it's a
+    /// moderator, the broadcaster or a verified bot.
+    pub fn set_elevated(&mut self, elevated: bool, now: Instant) {
+        let limit = if elevated {
+            ELEVATED_LIMIT
+        } else {
+            REGULAR_LIMIT
+        };
+
+        self.bucket.set_limit(limit, now);
+    }
+
+    /// Queue up a message of high priority.
+    pub fn push(&mut self, m: Message) {
+        self.high.push_back(m);
+    }
+
+    /// Queue up a message of low priority.
+    ///
+    /// Returns the oldest low priority message if it was dropped to make
+    /// room for this one.
+    pub fn push_low(&mut self, text: String) -> Option<String> {
+        let dropped = if self.low.len() >= LOW_PRIORITY_CAPACITY {
+            self.low.pop_front()
+        } else {
+            None
+        };
+
+        self.low.push_back(text);
+        dropped
+    }
+
+    /// Record that a message was sent without going through the queue.
+    pub fn sent_immediate(&mut self, now: Instant) {
+        self.bucket.force(now);
+    }
+
+    /// Decide what to do next.
+    pub fn next(&mut self, now: Instant) -> Next {
+        if self.high.is_empty() && self.low.is_empty() {
+            return Next::Idle;
+        }
+
+        if let Err(wait) = self.bucket.acquire(now) {
+            return Next::Wait(wait);
+        }
+
+        if let Some(m) = self.high.pop_front() {
+            return Next::Send(m);
+        }
+
+        let mut text = match self.low.pop_front() {
+            Some(text) => text,
+            None => return Next::Idle,
+        };
+
+        // NB: batch up as many low priority messages as possible, since they
+        // have been piling up while we were waiting.
+        while let Some(next) = self.low.front() {
+            let len = text.chars().count() + SEPARATOR.len() + next.chars().count();
+
+            if len > MAX_MESSAGE_LEN {
+                break;
+            }
+
+            if let Some(next) = self.low.pop_front() {
+                text.push_str(SEPARATOR);
+                text.push_str(&next);
+            }
+        }
+
+        Next::Send(Command::PRIVMSG(self.target.clone(), text).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Limiter, Next, PERIOD, REGULAR_LIMIT};
+    use irc::proto::command::Command;
+    use irc::proto::message::Message;
+    use std::time::{Duration, Instant};
+
+    fn privmsg(text: &str) -> Message {
+        Command::PRIVMSG(String::from("#setbac"), String::from(text)).into()
+    }
+
+    fn sent(next: Next) -> String {
+        match next {
+            Next::Send(m) => match m.command {
+                Command::PRIVMSG(_, text) => text,
+                command => panic!("unexpected command: {:?}", command),
+            },
+            next => panic!("expected a message to be sent: {:?}", next),
+        }
+    }
+
+    #[test]
+    fn test_stays_within_limit() {
+        let start = Instant::now();
+        let mut limiter = Limiter::new(String::from("#setbac"), start);
+
+        for _ in 0..100 {
+            limiter.push(privmsg("hello"));
+        }
+
+        let mut now = start;
+        let mut count = 0;
+
+        while now < start + PERIOD {
+            match limiter.next(now) {
+                Next::Send(..) => count += 1,
+                Next::Wait(wait) => now += wait,
+                Next::Idle => break,
+            }
+        }
+
+        assert!(count <= REGULAR_LIMIT, "sent {} messages", count);
+        assert!(count >= REGULAR_LIMIT - 1, "sent {} messages", count);
+    }
+
+    #[test]
+    fn test_high_priority_first() {
+        let now = Instant::now();
+        let mut limiter = Limiter::new(String::from("#setbac"), now);
+
+        limiter.push_low(String::from("promotion"));
+        limiter.push(privmsg("response"));
+
+        assert_eq!("response", sent(limiter.next(now)));
+        assert_eq!("promotion", sent(limiter.next(now)));
+        assert!(matches!(limiter.next(now), Next::Idle));
+    }
+
+    #[test]
+    fn test_batch_and_drop_low_priority() {
+        let now = Instant::now();
+        let mut limiter = Limiter::new(String::from("#setbac"), now);
+
+        // use up the bucket.
+        for _ in 0..REGULAR_LIMIT / 4 {
+            limiter.push(privmsg("spam"));
+            assert!(matches!(limiter.next(now), Next::Send(..)));
+        }
+
+        assert_eq!(None, limiter.push_low(String::from("first")));
+
+        for n in 1..10 {
+            assert_eq!(None, limiter.push_low(format!("message {}", n)));
+        }
+
+        assert_eq!(
+            Some(String::from("first")),
+            limiter.push_low(String::from("last"))
+        );
+
+        let wait = match limiter.next(now) {
+            Next::Wait(wait) => wait,
+            next => panic!("expected to wait: {:?}", next),
+        };
+
+        assert!(wait > Duration::from_secs(0));
+
+        let text = sent(limiter.next(now + wait));
+        assert!(text.starts_with("message 1 | message 2 | "));
+        assert!(text.ends_with(" | last"));
+        assert!(matches!(limiter.next(now + wait), Next::Idle));
+    }
+}
@@ -1,14 +1,23 @@
-use crate::api;
+use super::rate_limit::{Limiter, Next, Priority};
+use crate::api::{self, twitch::AnnouncementColor};
 use crate::injector;
 use crate::settings;
 use anyhow::Result;
 use irc::client;
 use irc::proto::command::{CapSubCommand, Command};
-use irc::proto::message::Message;
+use irc::proto::message::{Message, Tag};
 use leaky_bucket::{LeakyBucket, LeakyBuckets};
+use parking_lot::{Mutex, RwLock};
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time;
+use tokio::sync::Notify;
+
+/// Channel which whispers are sent through.
+const WHISPER_CHANNEL: &str = "#jtv";
+/// Tag used to reply in the thread of another message.
+const REPLY_PARENT_MSG_ID: &str = "reply-parent-msg-id";
 
 #[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize)]
 pub enum Type {
@@ -24,17 +33,44 @@ impl Default for Type {
     }
 }
 
+/// Sends announcements to chat through the Twitch API.
+pub struct Announcer {
+    twitch: api::Twitch,
+    broadcaster_id: String,
+    moderator_id: String,
+}
+
+impl Announcer {
+    /// Construct an announcer which sends announcements to the channel of the
+    /// given broadcaster, on behalf of the given moderator.
+    pub fn new(twitch: api::Twitch, broadcaster_id: String, moderator_id: String) -> Self {
+        Self {
+            twitch,
+            broadcaster_id,
+            moderator_id,
+        }
+    }
+}
+
 struct Inner {
     target: String,
-    sender: client::Sender,
-    limiter: LeakyBucket,
+    /// Sender of the current connection, replaced when reconnecting.
+    sender: RwLock<client::Sender>,
+    /// Queue of messages waiting to be sent to chat.
+    limiter: Mutex<Limiter>,
+    /// Notified when a message is queued up.
+    queued: Notify,
+    /// If the bot is a moderator or the broadcaster in the channel.
+    moderator: AtomicBool,
     nightbot_limiter: LeakyBucket,
     nightbot: injector::Var<Option<api::NightBot>>,
+    announcer: Announcer,
 }
 
 #[derive(Clone)]
 pub struct Sender {
     ty: settings::Var<Type>,
+    verified: settings::Var<bool>,
     inner: Arc<Inner>,
 }
 
@@ -42,18 +78,14 @@ impl Sender {
     /// Create a new sender.
     pub fn new(
         ty: settings::Var<Type>,
+        verified: settings::Var<bool>,
         target: String,
         sender: client::Sender,
         nightbot: injector::Var<Option<api::NightBot>>,
+        announcer: Announcer,
         buckets: &LeakyBuckets,
     ) -> Result<Sender> {
-        // limiter to use for IRC chat messages.
-        let limiter = buckets
-            .rate_limiter()
-            .refill_amount(10)
-            .refill_interval(time::Duration::from_secs(1))
-            .max(95)
-            .build()?;
+        let limiter = Limiter::new(target.clone(), time::Instant::now());
 
         let nightbot_limiter = buckets
             .rate_limiter()
@@ -63,16 +95,61 @@ impl Sender {
 
         Ok(Sender {
             ty,
+            verified,
             inner: Arc::new(Inner {
                 target,
-                sender,
-                limiter,
+                sender: RwLock::new(sender),
+                limiter: Mutex::new(limiter),
+                queued: Notify::new(),
+                moderator: AtomicBool::new(false),
                 nightbot_limiter,
                 nightbot,
+                announcer,
             }),
         })
     }
 
+    /// Start sending through a new connection.
+    pub(crate) fn reconnect(&self, sender: client::Sender) {
+        *self.inner.sender.write() = sender;
+    }
+
+    /// Set if the bot is a moderator or the broadcaster in the channel, which
+    /// allows it to send more messages.
+    pub(crate) fn set_moderator(&self, moderator: bool) {
+        self.inner.moderator.store(moderator, Ordering::SeqCst);
+    }
+
+    /// Run the loop which sends queued up messages to chat, as fast as
+    /// Twitch allows.
+    pub async fn run(self) -> Result<()> {
+        loop {
+            let verified = self.verified.load().await;
+            let elevated = verified || self.inner.moderator.load(Ordering::SeqCst);
+
+            let next = {
+                let mut limiter = self.inner.limiter.lock();
+                let now = time::Instant::now();
+                limiter.set_elevated(elevated, now);
+                limiter.next(now)
+            };
+
+            match next {
+                Next::Send(m) => {
+                    if let Err(e) = self.inner.sender.read().send(m) {
+                        log_error!(e, "failed to send message");
+                    }
+                }
+                Next::Wait(wait) => {
+                    tokio::time::delay_for(wait).await;
+                }
+                Next::Idle => {
+                    self.inner.queued.notified().await;
+                }
+            }
+        }
+    }
+
     /// Get the channel this sender is associated with.
     pub fn channel(&self) -> &str {
         self.inner.target.as_str()
@@ -94,37 +171,119 @@ impl Sender {
     }
 
     /// Only send to chat, with rate limiting.
+    ///
+    /// The message is queued up with high priority.
     pub async fn send(&self, m: impl Into<Message>) {
-        let m = m.into();
-
-        if let Err(e) = self.inner.limiter.acquire(1).await {
-            log_error!(e, "error in limiter");
-            return;
-        }
-
-        if let Err(e) = self.inner.sender.send(m) {
-            log_error!(e, "failed to send message");
-        }
+        self.inner.limiter.lock().push(m.into());
+        self.inner.queued.notify();
     }
 
     /// Send an immediate message, without taking rate limiting into account.
+    ///
+    /// Messages sent to chat this way still count towards the rate limit.
     pub fn send_immediate(&self, m: impl Into<Message>) {
-        if let Err(e) = self.inner.sender.send(m) {
+        let m = m.into();
+
+        if let Command::PRIVMSG(..) = m.command {
+            self.inner
+                .limiter
+                .lock()
+                .sent_immediate(time::Instant::now());
+        }
+
+        if let Err(e) = self.inner.sender.read().send(m) {
             log_error!(e, "failed to send message");
         }
     }
 
     /// Send a PRIVMSG.
     pub async fn privmsg(&self, f: impl fmt::Display) {
+        self.privmsg_with(Priority::High, f).await;
+    }
+
+    /// Send a PRIVMSG with the given priority.
+    pub async fn privmsg_with(&self, priority: Priority, f: impl fmt::Display) {
         match self.ty.load().await {
             Type::NightBot => {
                 self.send_nightbot(&*self.inner, f.to_string()).await;
             }
-            Type::Chat => {
-                self.send(Command::PRIVMSG(self.inner.target.clone(), f.to_string()))
-                    .await;
-            }
+            Type::Chat => match priority {
+                Priority::High => {
+                    self.send(Command::PRIVMSG(self.inner.target.clone(), f.to_string()))
+                        .await;
+                }
+                Priority::Low => {
+                    let dropped = self.inner.limiter.lock().push_low(f.to_string());
+                    self.inner.queued.notify();
+
+                    if let Some(dropped) = dropped {
+                        log::warn!(
+                            "Too many messages are being sent to chat, dropped: {}",
+                            dropped
+                        );
+                    }
+                }
+            },
+        }
+    }
+
+    /// Send a message as an announcement, which is highlighted in chat with
+    /// the given color.
+    ///
+    /// If the announcement can't be sent, like when the bot isn't a
+    /// moderator in the channel, a regular message is sent instead.
+    pub async fn announce(&self, color: AnnouncementColor, f: impl fmt::Display) {
+        let message = f.to_string();
+        let announcer = &self.inner.announcer;
+
+        let result = announcer
+            .twitch
+            .send_chat_announcement(
+                &announcer.broadcaster_id,
+                &announcer.moderator_id,
+                &message,
+                color,
+            )
+            .await;
+
+        if let Err(e) = result {
+            log_warn!(e, "Failed to send announcement, sending a regular message");
+            self.privmsg(message).await;
+        }
+    }
+
+    /// Whisper the given message to a user.
+    ///
+    /// Whispers can't be sent through NightBot, so they're always sent
+    /// through chat.
+    pub async fn whisper(&self, user: &str, f: impl fmt::Display) {
+        self.send(Command::PRIVMSG(
+            String::from(WHISPER_CHANNEL),
+            format!("/w {} {}", user, f),
+        ))
+        .await;
+    }
+
+    /// Reply in the thread of the message with the given id.
+    ///
+    /// Replies can't be sent through NightBot, so a regular message is sent
+    /// instead.
+    pub async fn reply(&self, parent_id: &str, f: impl fmt::Display) {
+        if let Type::NightBot = self.ty.load().await {
+            self.send_nightbot(&*self.inner, f.to_string()).await;
+            return;
         }
+
+        let m = Message {
+            tags: Some(vec![Tag(
+                String::from(REPLY_PARENT_MSG_ID),
+                Some(parent_id.to_string()),
+            )]),
+            prefix: None,
+            command: Command::PRIVMSG(self.inner.target.clone(), f.to_string()),
+        };
+
+        self.send(m).await;
     }
 
     /// Send a PRIVMSG without rate limiting.
@@ -28,32 +28,52 @@ pub const VERSION: &str = version_str!();
 
 #[macro_use]
 mod macros;
+mod activity;
+pub mod afk;
 pub mod api;
 pub mod auth;
 mod backoff;
+pub mod backup;
 pub mod bus;
+pub mod chat_stats;
 mod command;
+pub mod countdowns;
+pub mod crypt;
 pub mod currency;
 pub mod db;
+pub mod discord;
+pub mod emote_stats;
 pub mod emotes;
+pub mod errors;
+pub mod flags;
+pub mod game_rpc;
+pub mod groups;
 mod idle;
 pub mod irc;
 pub mod message_log;
+pub mod messages;
+pub mod metrics;
 pub mod module;
 pub mod oauth2;
+pub mod overlay;
 mod panic_logger;
 pub mod player;
 pub mod prelude;
+pub mod profiles;
+pub mod scheduler;
 #[cfg(feature = "scripting")]
 mod script;
 #[cfg(not(feature = "scripting"))]
 #[path = "script/mock.rs"]
 mod script;
 pub mod settings;
+pub mod social;
 mod song_file;
 mod spotify_id;
 pub mod storage;
 pub mod stream_info;
+pub mod stream_schedule;
+pub mod stream_session;
 pub mod sys;
 mod task;
 pub mod template;
@@ -63,6 +83,7 @@ pub mod updater;
 mod uri;
 pub mod utils;
 pub mod web;
+pub mod webhooks;
 
 pub use self::panic_logger::panic_logger;
 use self::uri::Uri;
@@ -99,6 +99,26 @@ macro_rules! respond {
     }};
 }
 
+/// Helper macro to render a message from the message catalog, with the given
+/// variables.
+///
+/// # Examples
+///
+/// ```ignore
+/// oxidize::message!(messages, "song/player-full");
+/// oxidize::message!(messages, "song/added", what = item.what(), pos = pos);
+/// ```
+#[macro_export]
+macro_rules! message {
+    ($messages:expr, $key:expr) => {
+        $messages.render($key, serde_json::Value::Null)
+    };
+
+    ($messages:expr, $key:expr, $($var:ident = $value:expr),* $(,)?) => {
+        $messages.render($key, serde_json::json!({ $((stringify!($var)): $value),* }))
+    };
+}
+
 /// Backoff and retry until the given action has been successfully executed.
 #[macro_export]
 macro_rules! retry_until_ok {
@@ -1,11 +1,26 @@
+//! In-memory log of chat messages, which is shown in the web chat view.
+//!
+//! Messages can optionally be persisted to sled, in which case the most recent
+//! ones are reloaded on startup and older ones can be searched.
+
 use crate::bus;
 use crate::emotes;
 use crate::irc;
+use crate::prelude::*;
+use crate::storage::sled;
+use crate::task;
+use crate::utils;
+use anyhow::Result;
 use chrono::{DateTime, Utc};
-use std::collections::VecDeque;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
 use tokio::sync::{RwLock, RwLockReadGuard};
 
+/// How often pending messages are flushed to storage.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+/// How often messages older than the retention are removed from storage.
+const COMPACT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type")]
 pub enum Event {
@@ -41,6 +56,7 @@ impl bus::Message for Event {
 pub struct Builder {
     limit: Option<usize>,
     bus: Option<Arc<bus::Bus<Event>>>,
+    tree: Option<Arc<sled::Tree>>,
 }
 
 impl Builder {
@@ -60,6 +76,14 @@ impl Builder {
         }
     }
 
+    /// Storage to persist messages to, if enabled.
+    pub fn tree(self, tree: Arc<sled::Tree>) -> Self {
+        Self {
+            tree: Some(tree),
+            ..self
+        }
+    }
+
     /// Construct a new message log.
     pub fn build(self) -> MessageLog {
         MessageLog {
@@ -68,7 +92,11 @@ impl Builder {
                 limit: self.limit,
                 bus: self.bus,
                 messages: Default::default(),
+                persist: false,
+                pending: Default::default(),
+                deletions: Default::default(),
             })),
+            tree: self.tree,
         }
     }
 }
@@ -78,12 +106,117 @@ pub struct Inner {
     limit: Option<usize>,
     bus: Option<Arc<bus::Bus<Event>>>,
     messages: VecDeque<Message>,
+    /// If messages are persisted to storage.
+    persist: bool,
+    /// Messages which have been added or modified, but not yet flushed to
+    /// storage.
+    pending: HashMap<Vec<u8>, Message>,
+    /// Deletions which have not yet been applied to storage.
+    deletions: Vec<Deletion>,
+}
+
+impl Inner {
+    /// Queue the given message to be written to storage, if enabled.
+    fn queue(&mut self, m: &Message) {
+        if self.persist {
+            self.pending.insert(m.key(), m.clone());
+        }
+    }
+
+    /// Mark all messages affected by the given deletion as deleted, and
+    /// queue the deletion to be applied to storage if enabled.
+    fn delete(&mut self, deletion: Deletion) {
+        let mut modified = Vec::new();
+
+        for m in &mut self.messages {
+            if deletion.matches(m) {
+                m.deleted = true;
+                modified.push(m.clone());
+            }
+        }
+
+        for m in &modified {
+            self.queue(m);
+        }
+
+        if self.persist {
+            // NB: pending messages might no longer be in memory.
+            for m in self.pending.values_mut() {
+                if deletion.matches(m) {
+                    m.deleted = true;
+                }
+            }
+
+            self.deletions.push(deletion);
+        }
+    }
+}
+
+/// A deletion of messages.
+#[derive(Debug, Clone)]
+enum Deletion {
+    /// The message with the given ID.
+    Id(String),
+    /// All messages by the given user.
+    User(String),
+    /// All messages.
+    All,
+}
+
+impl Deletion {
+    /// Test if the given message is affected by the deletion.
+    fn matches(&self, m: &Message) -> bool {
+        match self {
+            Self::Id(id) => m.id == *id,
+            Self::User(name) => m.user.name == *name,
+            Self::All => true,
+        }
+    }
+}
+
+/// Restrictions on messages returned by a search.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct Search {
+    /// Text which must be part of the message, ignoring case.
+    #[serde(default)]
+    pub q: Option<String>,
+    /// The name of the user who sent the message, ignoring case.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// The maximum number of messages to return.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl Search {
+    /// The default number of messages returned by a search.
+    const DEFAULT_LIMIT: usize = 100;
+
+    /// Test if the given message matches the search.
+    fn matches(&self, m: &Message) -> bool {
+        if let Some(user) = self.user.as_deref() {
+            let user = user.trim_start_matches('@');
+
+            if !m.user.name.eq_ignore_ascii_case(user) {
+                return false;
+            }
+        }
+
+        if let Some(q) = self.q.as_deref() {
+            if !m.text.to_lowercase().contains(&q.to_lowercase()) {
+                return false;
+            }
+        }
+
+        true
+    }
 }
 
 /// In-memory log of commands.
 #[derive(Clone)]
 pub struct MessageLog {
     inner: Arc<RwLock<Inner>>,
+    tree: Option<Arc<sled::Tree>>,
 }
 
 impl MessageLog {
@@ -97,6 +230,30 @@ impl MessageLog {
         RwLockReadGuard::map(self.inner.read().await, |i| &i.messages)
     }
 
+    /// Get every user who has sent a message since the given time, most
+    /// recently active first.
+    ///
+    /// Users whose messages have all been deleted are left out.
+    pub async fn users_since(&self, since: DateTime<Utc>) -> Vec<User> {
+        let inner = self.inner.read().await;
+        let mut seen = HashSet::new();
+        let mut users = Vec::new();
+
+        for m in inner.messages.iter().rev() {
+            if m.timestamp < since {
+                break;
+            }
+
+            if m.deleted || !seen.insert(m.user.name.as_str()) {
+                continue;
+            }
+
+            users.push(m.user.clone());
+        }
+
+        users
+    }
+
     /// Indicate if the log is enabled or not.
     pub async fn enabled(&self, enabled: bool) {
         if let Some(bus) = self.inner.read().await.bus.as_ref() {
@@ -107,12 +264,7 @@ impl MessageLog {
     /// Mark the given message as deleted.
     pub async fn delete_by_id(&self, id: &str) {
         let mut inner = self.inner.write().await;
-
-        for m in &mut inner.messages {
-            if m.id == id {
-                m.deleted = true;
-            }
-        }
+        inner.delete(Deletion::Id(id.to_string()));
 
         if let Some(bus) = inner.bus.as_ref() {
             bus.send(Event::DeleteById { id: id.to_string() }).await;
@@ -122,12 +274,24 @@ impl MessageLog {
     /// Mark all messages by the given user as deleted.
     pub async fn delete_by_user(&self, name: &str) {
         let mut inner = self.inner.write().await;
+        inner.delete(Deletion::User(name.to_string()));
 
-        for m in &mut inner.messages {
-            if m.user.name == name {
-                m.deleted = true;
-            }
+        if let Some(bus) = inner.bus.as_ref() {
+            bus.send(Event::DeleteByUser {
+                name: name.to_string(),
+            })
+            .await;
         }
+    }
+
+    /// Remove all messages by the given user, both from memory and from
+    /// storage.
+    ///
+    /// Returns the number of messages removed from storage.
+    pub async fn forget_user(&self, name: &str) -> Result<usize> {
+        let mut inner = self.inner.write().await;
+        inner.messages.retain(|m| m.user.name != name);
+        inner.pending.retain(|_, m| m.user.name != name);
 
         if let Some(bus) = inner.bus.as_ref() {
             bus.send(Event::DeleteByUser {
@@ -135,15 +299,41 @@ impl MessageLog {
             })
             .await;
         }
+
+        drop(inner);
+
+        let tree = match self.tree.as_ref() {
+            Some(tree) => tree.clone(),
+            None => return Ok(0),
+        };
+
+        let name = name.to_string();
+
+        task::asyncify(move || {
+            let mut removed = 0;
+
+            for entry in tree.iter() {
+                let (key, value) = entry?;
+
+                match serde_cbor::from_slice::<Message>(&value) {
+                    Ok(m) if m.user.name == name => (),
+                    _ => continue,
+                }
+
+                tree.remove(key)?;
+                removed += 1;
+            }
+
+            tree.flush()?;
+            Ok(removed)
+        })
+        .await
     }
 
     /// Delete all messages in chat.
     pub async fn delete_all(&self) {
         let mut inner = self.inner.write().await;
-
-        for m in &mut inner.messages {
-            m.deleted = true;
-        }
+        inner.delete(Deletion::All);
 
         if let Some(bus) = inner.bus.as_ref() {
             bus.send(Event::DeleteAll).await;
@@ -205,16 +395,257 @@ impl MessageLog {
             bus.send(Event::Message(m.clone())).await;
         }
 
+        inner.queue(&m);
         inner.messages.push_back(m);
     }
+
+    /// Search for messages, most recent first.
+    ///
+    /// Searches storage if messages are persisted, otherwise only the
+    /// messages in memory are searched.
+    pub async fn search(&self, search: Search) -> Result<Vec<Message>> {
+        let limit = search.limit.unwrap_or(Search::DEFAULT_LIMIT);
+
+        let inner = self.inner.read().await;
+
+        let tree = match (inner.persist, self.tree.as_ref()) {
+            (true, Some(tree)) => tree.clone(),
+            _ => {
+                return Ok(inner
+                    .messages
+                    .iter()
+                    .rev()
+                    .filter(|m| search.matches(m))
+                    .take(limit)
+                    .cloned()
+                    .collect());
+            }
+        };
+
+        // NB: pending messages are the most recent ones, so they go first.
+        let mut pending = inner
+            .pending
+            .values()
+            .filter(|m| search.matches(m))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let deletions = inner.deletions.clone();
+        drop(inner);
+
+        pending.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        pending.truncate(limit);
+
+        task::asyncify(move || {
+            let mut messages = pending;
+            let seen = messages.iter().map(Message::key).collect::<HashSet<_>>();
+
+            for entry in tree.iter().rev() {
+                if messages.len() >= limit {
+                    break;
+                }
+
+                let (key, value) = entry?;
+
+                if seen.contains(&*key) {
+                    continue;
+                }
+
+                let mut m = match serde_cbor::from_slice::<Message>(&value) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        log::warn!("skipping bad message in chat log: {}", e);
+                        continue;
+                    }
+                };
+
+                if deletions.iter().any(|d| d.matches(&m)) {
+                    m.deleted = true;
+                }
+
+                if search.matches(&m) {
+                    messages.push(m);
+                }
+            }
+
+            Ok(messages)
+        })
+        .await
+    }
+
+    /// Load the most recent messages from storage, if messages are persisted
+    /// and none have been received yet.
+    pub async fn load(&self) -> Result<()> {
+        let tree = match self.tree.as_ref() {
+            Some(tree) => tree.clone(),
+            None => return Ok(()),
+        };
+
+        let mut inner = self.inner.write().await;
+
+        if !inner.persist || !inner.messages.is_empty() {
+            return Ok(());
+        }
+
+        let limit = inner.limit.unwrap_or(Search::DEFAULT_LIMIT);
+
+        let messages = task::asyncify(move || {
+            let mut messages = Vec::new();
+
+            for entry in tree.iter().rev().take(limit) {
+                let (_, value) = entry?;
+
+                match serde_cbor::from_slice::<Message>(&value) {
+                    Ok(m) => messages.push(m),
+                    Err(e) => log::warn!("skipping bad message in chat log: {}", e),
+                }
+            }
+
+            Ok::<_, anyhow::Error>(messages)
+        })
+        .await?;
+
+        inner.messages.extend(messages.into_iter().rev());
+        Ok(())
+    }
+
+    /// Flush all pending messages and deletions to storage.
+    pub async fn flush(&self) -> Result<()> {
+        let tree = match self.tree.as_ref() {
+            Some(tree) => tree.clone(),
+            None => return Ok(()),
+        };
+
+        let (pending, deletions) = {
+            let mut inner = self.inner.write().await;
+            let pending = std::mem::take(&mut inner.pending);
+            let deletions = std::mem::take(&mut inner.deletions);
+            (pending, deletions)
+        };
+
+        if pending.is_empty() && deletions.is_empty() {
+            return Ok(());
+        }
+
+        task::asyncify(move || {
+            for (key, m) in pending {
+                tree.insert(key, serde_cbor::to_vec(&m)?)?;
+            }
+
+            if !deletions.is_empty() {
+                for entry in tree.iter() {
+                    let (key, value) = entry?;
+
+                    let mut m = match serde_cbor::from_slice::<Message>(&value) {
+                        Ok(m) => m,
+                        Err(_) => continue,
+                    };
+
+                    if !m.deleted && deletions.iter().any(|d| d.matches(&m)) {
+                        m.deleted = true;
+                        tree.insert(key, serde_cbor::to_vec(&m)?)?;
+                    }
+                }
+            }
+
+            tree.flush()?;
+            Ok::<_, anyhow::Error>(())
+        })
+        .await
+    }
+
+    /// Remove messages older than the given retention from storage.
+    pub async fn compact(&self, retention: utils::Duration) -> Result<()> {
+        let tree = match self.tree.as_ref() {
+            Some(tree) => tree.clone(),
+            None => return Ok(()),
+        };
+
+        let cutoff = timestamp_key(Utc::now() - retention.as_chrono());
+
+        let removed = task::asyncify(move || {
+            let mut removed = 0;
+
+            for entry in tree.range(..cutoff) {
+                let (key, _) = entry?;
+                tree.remove(key)?;
+                removed += 1;
+            }
+
+            tree.flush()?;
+            Ok::<_, anyhow::Error>(removed)
+        })
+        .await?;
+
+        log::trace!("compacted chat log, removed {} message(s)", removed);
+        Ok(())
+    }
+
+    /// Run the maintenance loop, which flushes messages to storage if they
+    /// are persisted.
+    pub async fn run(self, settings: settings::Settings) -> Result<()> {
+        let (mut persist_stream, persist) = settings.stream("persist").or_default().await?;
+
+        let retention = settings
+            .var("retention", utils::Duration::hours(24 * 30))
+            .await?;
+
+        self.set_persist(persist).await?;
+
+        let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+        let mut compact = tokio::time::interval(COMPACT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                persist = persist_stream.select_next_some() => {
+                    self.set_persist(persist).await?;
+                }
+                _ = flush.tick() => {
+                    if let Err(e) = self.flush().await {
+                        log_error!(e, "failed to flush chat log");
+                    }
+                }
+                _ = compact.tick() => {
+                    let persist = self.inner.read().await.persist;
+
+                    if persist {
+                        if let Err(e) = self.compact(retention.load().await).await {
+                            log_error!(e, "failed to compact chat log");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Enable or disable persistence.
+    async fn set_persist(&self, persist: bool) -> Result<()> {
+        {
+            let mut inner = self.inner.write().await;
+            inner.persist = persist;
+
+            if !persist {
+                inner.pending.clear();
+                inner.deletions.clear();
+            }
+        }
+
+        self.load().await
+    }
+}
+
+/// Construct the key prefix for messages sent at the given time, which sorts
+/// in chronological order.
+fn timestamp_key(timestamp: DateTime<Utc>) -> [u8; 8] {
+    (timestamp.timestamp_millis().max(0) as u64).to_be_bytes()
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct User {
-    user_id: String,
-    name: String,
-    display_name: String,
-    color: Option<String>,
+    pub user_id: String,
+    pub name: String,
+    pub display_name: String,
+    pub color: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -226,3 +657,85 @@ pub struct Message {
     rendered: Option<emotes::Rendered>,
     deleted: bool,
 }
+
+impl Message {
+    /// The key the message is stored under.
+    fn key(&self) -> Vec<u8> {
+        let mut key = timestamp_key(self.timestamp).to_vec();
+        key.extend(self.id.as_bytes());
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Deletion, Message, Search, User};
+    use chrono::{TimeZone as _, Utc};
+
+    fn message(name: &str, text: &str) -> Message {
+        Message {
+            timestamp: Utc.ymd(2020, 1, 1).and_hms(12, 0, 0),
+            id: String::from("id"),
+            user: User {
+                user_id: String::from("1"),
+                name: name.to_string(),
+                display_name: name.to_string(),
+                color: None,
+            },
+            text: text.to_string(),
+            rendered: None,
+            deleted: false,
+        }
+    }
+
+    #[test]
+    fn test_search_matches() {
+        let m = message("setbac", "Hello World");
+
+        assert!(Search::default().matches(&m));
+
+        let search = Search {
+            q: Some(String::from("hello")),
+            user: Some(String::from("@SetBac")),
+            limit: None,
+        };
+
+        assert!(search.matches(&m));
+
+        let search = Search {
+            q: Some(String::from("goodbye")),
+            ..Search::default()
+        };
+
+        assert!(!search.matches(&m));
+
+        let search = Search {
+            user: Some(String::from("setmod")),
+            ..Search::default()
+        };
+
+        assert!(!search.matches(&m));
+    }
+
+    #[test]
+    fn test_deletion_matches() {
+        let m = message("setbac", "Hello World");
+
+        assert!(Deletion::Id(String::from("id")).matches(&m));
+        assert!(!Deletion::Id(String::from("other")).matches(&m));
+        assert!(Deletion::User(String::from("setbac")).matches(&m));
+        assert!(!Deletion::User(String::from("setmod")).matches(&m));
+        assert!(Deletion::All.matches(&m));
+    }
+
+    #[test]
+    fn test_key_order() {
+        let mut a = message("setbac", "first");
+        a.id = String::from("b");
+        let mut b = message("setbac", "second");
+        b.id = String::from("a");
+        b.timestamp = b.timestamp + chrono::Duration::milliseconds(1);
+
+        assert!(a.key() < b.key());
+    }
+}
@@ -0,0 +1,356 @@
+//! Catalog of chat messages used by built-in modules, keyed by language.
+//!
+//! Messages are handlebars templates, see [Template]. The language is picked
+//! through the `language` setting, and each message can be overridden through
+//! a setting derived from its key, like `song/messages/added` for
+//! `song/added`. See [setting_key].
+
+use crate::prelude::*;
+use crate::template::Template;
+use anyhow::{Context as _, Result};
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+const CATALOG: &[u8] = include_bytes!("messages.yaml");
+
+/// The language used when a message is missing a translation.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Get the key of the setting used to override the given message.
+///
+/// The setting lives under the same prefix as the module the message belongs
+/// to, so `song/added` is overridden through `song/messages/added`.
+pub fn setting_key(key: &str) -> String {
+    match key.find('/') {
+        Some(index) => format!("{}/messages/{}", &key[..index], &key[index + 1..]),
+        None => format!("messages/{}", key),
+    }
+}
+
+/// A single message in the catalog.
+#[derive(Debug, serde::Deserialize)]
+pub struct Entry {
+    /// Documentation for when the message is used.
+    pub doc: String,
+    /// Variables available in the message.
+    #[serde(default)]
+    pub vars: Vec<String>,
+    /// The message in each supported language.
+    #[serde(flatten)]
+    pub translations: BTreeMap<String, Template>,
+}
+
+/// The catalog of all messages.
+#[derive(Debug, serde::Deserialize)]
+pub struct Catalog {
+    /// Supported languages, by id.
+    pub languages: BTreeMap<String, String>,
+    /// Messages, by key.
+    pub messages: BTreeMap<String, Entry>,
+}
+
+impl Catalog {
+    /// Load the catalog which is built into the bot.
+    pub fn load_static() -> Result<Catalog> {
+        Ok(serde_yaml::from_slice(CATALOG).context("failed to load messages.yaml")?)
+    }
+
+    /// Register a setting to override each message in the given schema.
+    pub fn register(&self, schema: &mut settings::Schema) {
+        for (key, entry) in &self.messages {
+            let default = entry
+                .translations
+                .get(DEFAULT_LANGUAGE)
+                .map(|t| t.source().to_string());
+
+            let mut doc = format!("Override for the `{}` message. {}", key, entry.doc);
+
+            if !entry.vars.is_empty() {
+                doc.push_str(" Available variables:");
+
+                for var in &entry.vars {
+                    let _ = write!(doc, " `{{{{{}}}}}`", var);
+                }
+
+                doc.push('.');
+            }
+
+            let ty = settings::SchemaType {
+                doc,
+                scope: None,
+                ty: settings::Type {
+                    optional: true,
+                    kind: settings::Kind::String {
+                        placeholder: default,
+                        format: Default::default(),
+                    },
+                },
+                secret: false,
+                feature: false,
+                title: None,
+                default: None,
+            };
+
+            schema.insert(setting_key(key), ty);
+        }
+    }
+}
+
+/// A message as presented in the web UI.
+#[derive(Debug, serde::Serialize)]
+pub struct Message<'a> {
+    pub key: &'a str,
+    pub doc: &'a str,
+    pub vars: &'a [String],
+    /// The message in the current language.
+    pub default: Option<&'a str>,
+    /// The override of the message, if any.
+    #[serde(rename = "override")]
+    pub override_: Option<String>,
+}
+
+struct Inner {
+    language: String,
+    overrides: HashMap<String, Template>,
+}
+
+/// Renders messages from the catalog in the configured language.
+#[derive(Clone)]
+pub struct Messages {
+    catalog: Arc<Catalog>,
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl Messages {
+    /// Construct messages from the given catalog.
+    pub fn new(catalog: Catalog) -> Self {
+        Self {
+            catalog: Arc::new(catalog),
+            inner: Arc::new(RwLock::new(Inner {
+                language: DEFAULT_LANGUAGE.to_string(),
+                overrides: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Access the underlying catalog.
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// The language messages are currently rendered in.
+    pub fn language(&self) -> String {
+        self.inner.read().language.clone()
+    }
+
+    /// Run the loop which keeps the language and overrides up to date.
+    pub async fn run(self, settings: settings::Settings) -> Result<()> {
+        let (mut language_stream, language) = settings
+            .stream("language")
+            .or_with(DEFAULT_LANGUAGE.to_string())
+            .await?;
+
+        self.set_language(language);
+
+        let mut override_streams = Vec::new();
+
+        for key in self.catalog.messages.keys() {
+            let (stream, value) = settings.stream(&setting_key(key)).optional().await?;
+
+            self.set_override(key, value);
+
+            let key = key.clone();
+            override_streams.push(stream.map(move |value| (key.clone(), value)).boxed());
+        }
+
+        let mut override_stream = stream::select_all(override_streams);
+
+        loop {
+            tokio::select! {
+                language = language_stream.select_next_some() => {
+                    self.set_language(language);
+                }
+                (key, value) = override_stream.select_next_some() => {
+                    self.set_override(&key, value);
+                }
+            }
+        }
+    }
+
+    fn set_language(&self, language: String) {
+        if !self.catalog.languages.contains_key(&language) {
+            log::warn!(
+                "unsupported language `{}`, falling back to `{}`",
+                language,
+                DEFAULT_LANGUAGE
+            );
+        }
+
+        self.inner.write().language = language;
+    }
+
+    /// Set or clear the override for a single message.
+    ///
+    /// Empty overrides are treated as cleared.
+    fn set_override(&self, key: &str, source: Option<String>) {
+        let source = match source {
+            Some(source) if !source.trim().is_empty() => source,
+            _ => {
+                self.inner.write().overrides.remove(key);
+                return;
+            }
+        };
+
+        match Template::compile(&source) {
+            Ok(template) => {
+                self.inner
+                    .write()
+                    .overrides
+                    .insert(key.to_string(), template);
+            }
+            Err(e) => log_error!(e, "bad override for message `{}`", key),
+        }
+    }
+
+    /// Get the template for the given message in the given language, without
+    /// overrides.
+    fn translation(&self, key: &str, language: &str) -> Option<&Template> {
+        let entry = self.catalog.messages.get(key)?;
+
+        entry
+            .translations
+            .get(language)
+            .or_else(|| entry.translations.get(DEFAULT_LANGUAGE))
+    }
+
+    /// Render the given message.
+    ///
+    /// Falls back to the key of the message if it's missing or fails to
+    /// render, so that a bad override doesn't silence the bot.
+    pub fn render(&self, key: &str, data: impl serde::Serialize) -> String {
+        let result = {
+            let inner = self.inner.read();
+
+            match inner.overrides.get(key) {
+                Some(template) => Some(template.render_to_string(data)),
+                None => self
+                    .translation(key, &inner.language)
+                    .map(|t| t.render_to_string(data)),
+            }
+        };
+
+        match result {
+            Some(Ok(message)) => message,
+            Some(Err(e)) => {
+                log_error!(e, "failed to render message `{}`", key);
+                key.to_string()
+            }
+            None => {
+                log::warn!("missing message `{}`", key);
+                key.to_string()
+            }
+        }
+    }
+
+    /// List all messages in the current language, with their overrides.
+    pub fn list(&self) -> Vec<Message<'_>> {
+        let inner = self.inner.read();
+
+        self.catalog
+            .messages
+            .iter()
+            .map(|(key, entry)| Message {
+                key,
+                doc: &entry.doc,
+                vars: &entry.vars,
+                default: self.translation(key, &inner.language).map(Template::source),
+                override_: inner.overrides.get(key).map(|t| t.source().to_string()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{setting_key, Catalog, Messages, DEFAULT_LANGUAGE};
+
+    /// Helpers used in the catalog, which show up as variables.
+    const HELPERS: &[&str] = &["if", "eq"];
+
+    #[test]
+    fn test_load_static() {
+        let catalog = Catalog::load_static().expect("catalog to load");
+
+        for (key, entry) in &catalog.messages {
+            assert!(
+                entry.translations.contains_key(DEFAULT_LANGUAGE),
+                "message `{}` is missing a default translation",
+                key
+            );
+
+            for (language, template) in &entry.translations {
+                assert!(
+                    catalog.languages.contains_key(language),
+                    "message `{}` has unsupported language `{}`",
+                    key,
+                    language
+                );
+
+                for var in template.vars() {
+                    if HELPERS.contains(&var.as_str()) {
+                        continue;
+                    }
+
+                    assert!(
+                        entry.vars.contains(&var),
+                        "message `{}` in `{}` uses undocumented variable `{}`",
+                        key,
+                        language,
+                        var
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let messages = Messages::new(Catalog::load_static().expect("catalog to load"));
+
+        assert_eq!(
+            "Player is full, try again later!",
+            message!(messages, "song/player-full")
+        );
+
+        messages.set_language(String::from("sv"));
+        assert_eq!(
+            "Spelaren är full, försök igen senare!",
+            message!(messages, "song/player-full")
+        );
+
+        messages.set_override("song/added", Some(String::from("{{what}} it is")));
+        assert_eq!(
+            "Queen it is",
+            message!(messages, "song/added", what = "Queen")
+        );
+
+        messages.set_override("song/added", Some(String::from(" ")));
+        assert_ne!(
+            "Queen it is",
+            message!(messages, "song/added", what = "Queen")
+        );
+
+        assert_eq!("no/such-message", message!(messages, "no/such-message"));
+    }
+
+    #[test]
+    fn test_setting_key() {
+        assert_eq!("song/messages/added", setting_key("song/added"));
+        assert_eq!(
+            "song/messages/voteskip/passed",
+            setting_key("song/voteskip/passed")
+        );
+        assert_eq!("messages/added", setting_key("added"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Process-wide metrics, exported in the Prometheus text format.
+//!
+//! Metrics are plain atomics which are cheap to update from anywhere, and are
+//! only formatted when scraped through the `/metrics` endpoint.
+
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Number of chat messages processed.
+pub static MESSAGES: Counter = Counter::new();
+/// Number of commands executed.
+pub static COMMANDS: Counter = Counter::new();
+/// Number of errors raised while executing commands.
+pub static ERRORS: Counter = Counter::new();
+/// Number of open WebSocket connections.
+pub static WEBSOCKETS: Gauge = Gauge::new();
+
+lazy_static::lazy_static! {
+    /// Latency of API calls, by the host being called.
+    pub static ref API_LATENCY: Summary = Summary::default();
+}
+
+/// A counter which only goes up.
+#[derive(Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    /// Construct a new counter.
+    pub const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    /// Increment the counter.
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get the current value of the counter.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A gauge which can go up and down.
+#[derive(Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    /// Construct a new gauge.
+    pub const fn new() -> Self {
+        Self(AtomicI64::new(0))
+    }
+
+    /// Increment the gauge, decrementing it again when the returned guard is
+    /// dropped.
+    pub fn guard(&'static self) -> GaugeGuard {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        GaugeGuard(self)
+    }
+
+    /// Get the current value of the gauge.
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Guard returned by [Gauge::guard].
+pub struct GaugeGuard(&'static Gauge);
+
+impl Drop for GaugeGuard {
+    fn drop(&mut self) {
+        (self.0).0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A summary of observed durations, by label.
+#[derive(Default)]
+pub struct Summary {
+    observed: Mutex<BTreeMap<String, (u64, f64)>>,
+}
+
+impl Summary {
+    /// Observe a duration for the given label.
+    pub fn observe(&self, label: &str, duration: Duration) {
+        let mut observed = self.observed.lock();
+        let (count, sum) = observed.entry(label.to_string()).or_default();
+        *count += 1;
+        *sum += duration.as_secs_f64();
+    }
+
+    /// Get a snapshot of the count and sum of all observations, by label.
+    fn snapshot(&self) -> Vec<(String, u64, f64)> {
+        self.observed
+            .lock()
+            .iter()
+            .map(|(label, (count, sum))| (label.clone(), *count, *sum))
+            .collect()
+    }
+}
+
+/// An encoder for the Prometheus text format.
+#[derive(Default)]
+pub struct Encoder {
+    out: String,
+}
+
+impl Encoder {
+    /// Encode a counter.
+    pub fn counter(&mut self, name: &str, help: &str, value: u64) {
+        self.header(name, help, "counter");
+        let _ = writeln!(self.out, "{} {}", name, value);
+    }
+
+    /// Encode a gauge.
+    pub fn gauge(&mut self, name: &str, help: &str, value: i64) {
+        self.header(name, help, "gauge");
+        let _ = writeln!(self.out, "{} {}", name, value);
+    }
+
+    /// Encode a summary, where each observed label is stored under `label`.
+    pub fn summary(&mut self, name: &str, help: &str, label: &str, summary: &Summary) {
+        self.header(name, help, "summary");
+
+        for (value, count, sum) in summary.snapshot() {
+            let value = escape_label(&value);
+            let _ = writeln!(
+                self.out,
+                "{}_count{{{}=\"{}\"}} {}",
+                name, label, value, count
+            );
+            let _ = writeln!(self.out, "{}_sum{{{}=\"{}\"}} {}", name, label, value, sum);
+        }
+    }
+
+    /// Encode all process-wide metrics.
+    pub fn process(&mut self) {
+        self.counter(
+            "oxidize_messages_total",
+            "Number of chat messages processed.",
+            MESSAGES.get(),
+        );
+        self.counter(
+            "oxidize_commands_total",
+            "Number of commands executed.",
+            COMMANDS.get(),
+        );
+        self.counter(
+            "oxidize_command_errors_total",
+            "Number of errors raised while executing commands.",
+            ERRORS.get(),
+        );
+        self.gauge(
+            "oxidize_websocket_connections",
+            "Number of open WebSocket connections.",
+            WEBSOCKETS.get(),
+        );
+        self.summary(
+            "oxidize_api_request_duration_seconds",
+            "Latency of API calls, by host.",
+            "host",
+            &API_LATENCY,
+        );
+    }
+
+    /// Finish encoding, returning the encoded metrics.
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    fn header(&mut self, name: &str, help: &str, ty: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, ty);
+    }
+}
+
+/// Escape a label value.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encoder, Summary};
+    use std::time::Duration;
+
+    #[test]
+    fn test_encode() {
+        let summary = Summary::default();
+        summary.observe("api.twitch.tv", Duration::from_millis(500));
+        summary.observe("api.twitch.tv", Duration::from_millis(250));
+        summary.observe("a\"b", Duration::from_secs(1));
+
+        let mut encoder = Encoder::default();
+        encoder.counter("foo_total", "Foo.", 42);
+        encoder.summary("bar_seconds", "Bar.", "host", &summary);
+
+        let expected = concat!(
+            "# HELP foo_total Foo.\n",
+            "# TYPE foo_total counter\n",
+            "foo_total 42\n",
+            "# HELP bar_seconds Bar.\n",
+            "# TYPE bar_seconds summary\n",
+            "bar_seconds_count{host=\"a\\\"b\"} 1\n",
+            "bar_seconds_sum{host=\"a\\\"b\"} 1\n",
+            "bar_seconds_count{host=\"api.twitch.tv\"} 2\n",
+            "bar_seconds_sum{host=\"api.twitch.tv\"} 0.75\n",
+        );
+
+        assert_eq!(expected, encoder.finish());
+    }
+}
@@ -1,18 +1,26 @@
 use crate::auth;
+use crate::backup::Backups;
 use crate::command;
-use crate::db;
+use crate::groups::Groups;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
+use crate::profiles::Profiles;
 use crate::settings;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+
+/// Usage of the `!admin` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!admin <version|refresh-mods|refresh-vips|shutdown|settings|push|delete|toggle|profile|backup|group|...>",
+    description: "Administer the bot.",
+};
 
 /// Handler for the !admin command.
 pub struct Handler {
     settings: settings::Settings,
-    aliases: injector::Var<Option<db::Aliases>>,
-    commands: injector::Var<Option<db::Commands>>,
-    promotions: injector::Var<Option<db::Promotions>>,
-    themes: injector::Var<Option<db::Themes>>,
+    groups: Groups,
+    backups: injector::Var<Option<Backups>>,
+    messages: Messages,
 }
 
 impl Handler {
@@ -31,9 +39,9 @@ impl Handler {
             let value = serde_json::to_string(&setting.value)?;
 
             let value = if value.len() > 20 {
-                "*too long*"
+                message!(self.messages, "admin/too-long")
             } else {
-                &value
+                value
             };
 
             results.push(format!(
@@ -44,16 +52,20 @@ impl Handler {
         }
 
         if results.is_empty() {
-            respond!(ctx, "No settings starting with `{}`", key);
+            respond!(
+                ctx,
+                message!(self.messages, "admin/no-settings", prefix = key)
+            );
         } else {
-            let mut response = results.join(", ");
-
-            if results.len() < settings.len() {
-                let more = settings.len() - results.len();
-                response = format!("{} .. and {} more", response, more);
-            }
-
-            ctx.respond(response).await;
+            respond!(
+                ctx,
+                message!(
+                    self.messages,
+                    "admin/settings",
+                    settings = results.join(", "),
+                    more = settings.len() - results.len(),
+                )
+            );
         }
 
         Ok(())
@@ -66,34 +78,41 @@ impl command::Handler for Handler {
         Some(auth::Scope::Admin)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
         match ctx.next().as_deref() {
             Some("refresh-mods") => {
                 ctx.privmsg("/mods").await;
-                respond!(ctx, "Refreshed information on mods");
+                respond!(ctx, message!(self.messages, "admin/refreshed-mods"));
             }
             Some("refresh-vips") => {
                 ctx.privmsg("/vips").await;
-                respond!(ctx, "Refreshed information on vips");
+                respond!(ctx, message!(self.messages, "admin/refreshed-vips"));
             }
             Some("refresh") => {
                 ctx.privmsg("/mods").await;
                 ctx.privmsg("/vips").await;
-                respond!(ctx, "Refreshed information on mods and vips");
+                respond!(ctx, message!(self.messages, "admin/refreshed"));
             }
             Some("version") => {
-                respond!(ctx, "OxidizeBot Version {}", crate::VERSION);
+                respond!(
+                    ctx,
+                    message!(self.messages, "admin/version", version = crate::VERSION)
+                );
             }
             Some("shutdown") | Some("restart") => {
                 if ctx.restart().await {
-                    respond!(ctx, "Restarting...");
+                    respond!(ctx, message!(self.messages, "admin/restarting"));
                 } else {
-                    respond!(ctx, "Already restarting...");
+                    respond!(ctx, message!(self.messages, "admin/already-restarting"));
                 }
             }
             // Insert a value into a setting.
             Some("push") => {
-                let key = key(ctx)?;
+                let key = key(&self.messages, ctx)?;
                 let value = self.edit_value_in_set(ctx, &key).await?;
 
                 let mut values = self
@@ -104,11 +123,11 @@ impl command::Handler for Handler {
 
                 values.push(value);
                 self.settings.set(&key, values).await?;
-                respond!(ctx, "Updated the {} setting", key);
+                respond!(ctx, message!(self.messages, "admin/updated", key = key));
             }
             // Delete a value from a setting.
             Some("delete") => {
-                let key = key(ctx)?;
+                let key = key(&self.messages, ctx)?;
                 let value = self.edit_value_in_set(ctx, &key).await?;
 
                 let mut values = self
@@ -119,60 +138,45 @@ impl command::Handler for Handler {
 
                 values.retain(|v| v != &value);
                 self.settings.set(&key, values).await?;
-                respond!(ctx, "Updated the {} setting", key);
+                respond!(ctx, message!(self.messages, "admin/updated", key = key));
             }
             Some("toggle") => {
                 self.toggle(ctx).await?;
             }
+            Some("profile") => {
+                self.profile(ctx).await?;
+            }
+            Some("backup") => {
+                self.backup(ctx).await?;
+            }
+            Some("group") => {
+                self.group(ctx).await?;
+            }
             Some("enable-group") => {
                 let group = ctx
                     .next()
                     .ok_or_else(|| respond_err!("Expected <group> to enable"))?;
 
-                if let Some(aliases) = &*self.aliases.read().await {
-                    aliases.enable_group(ctx.channel(), &group).await?;
-                }
-
-                if let Some(commands) = &*self.commands.read().await {
-                    commands.enable_group(ctx.channel(), &group).await?;
-                }
-
-                if let Some(promotions) = &*self.promotions.read().await {
-                    promotions.enable_group(ctx.channel(), &group).await?;
-                }
-
-                if let Some(themes) = &*self.themes.read().await {
-                    themes.enable_group(ctx.channel(), &group).await?;
-                }
-
-                respond!(ctx, "Enabled group {}", group);
+                self.groups.enable(ctx.channel(), &group).await?;
+                respond!(
+                    ctx,
+                    message!(self.messages, "admin/group-enabled", group = group)
+                );
             }
             Some("disable-group") => {
                 let group = ctx
                     .next()
                     .ok_or_else(|| respond_err!("Expected <group> to disable"))?;
 
-                if let Some(aliases) = &*self.aliases.read().await {
-                    aliases.disable_group(ctx.channel(), &group).await?;
-                }
-
-                if let Some(commands) = &*self.commands.read().await {
-                    commands.disable_group(ctx.channel(), &group).await?;
-                }
-
-                if let Some(promotions) = &*self.promotions.read().await {
-                    promotions.disable_group(ctx.channel(), &group).await?;
-                }
-
-                if let Some(themes) = &*self.themes.read().await {
-                    themes.disable_group(ctx.channel(), &group).await?;
-                }
-
-                respond!(ctx, "Disabled group {}", group);
+                self.groups.disable(ctx.channel(), &group).await?;
+                respond!(
+                    ctx,
+                    message!(self.messages, "admin/group-disabled", group = group)
+                );
             }
             // Get or set settings.
             Some("settings") => {
-                let key = key(ctx)?;
+                let key = key(&self.messages, ctx)?;
 
                 match ctx.rest().trim() {
                     "" => {
@@ -186,69 +190,268 @@ impl command::Handler for Handler {
                         };
 
                         if setting.schema.secret {
-                            respond_bail!("Cannot show secret setting `{}`", key);
+                            respond_bail!(
+                                "{}",
+                                message!(self.messages, "admin/secret-setting", key = key)
+                            );
                         }
 
                         respond!(ctx, "{} = {}", key, serde_json::to_string(&setting.value)?);
                     }
                     value => {
-                        let schema = self
-                            .settings
-                            .lookup(&key)
-                            .ok_or_else(|| respond_err!("No such setting"))?;
-
-                        let value = schema.ty.parse_as_json(value).map_err(|e| {
-                            respond_err!("Value is not a valid {} type: {}", schema.ty, e)
+                        let schema = self.settings.lookup(&key).ok_or_else(|| {
+                            respond_err!("{}", message!(self.messages, "admin/no-such-setting"))
                         })?;
 
+                        let value = schema
+                            .ty
+                            .parse_as_json(value)
+                            .map_err(|e| self.bad_value(&schema.ty, e))?;
+
                         if let Some(scope) = schema.scope {
                             if !ctx.user.has_scope(scope).await {
-                                respond_bail!(
-                                    "You are not permitted to modify that setting, sorry :(",
-                                );
+                                respond_bail!("{}", message!(self.messages, "admin/not-permitted"));
                             }
                         }
 
                         let value_string = serde_json::to_string(&value)?;
                         self.settings.set_json(&key, value).await?;
-                        respond!(ctx, "Updated setting {} = {}", key, value_string);
+                        respond!(
+                            ctx,
+                            message!(self.messages, "admin/set", key = key, value = value_string,)
+                        );
                     }
                 }
             }
             _ => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Handler {
+    /// Build the response for a value which can't be parsed as the given type.
+    fn bad_value(&self, ty: &settings::Type, e: settings::Error) -> command::Respond {
+        respond_err!(
+            "{}",
+            message!(
+                self.messages,
+                "admin/bad-value",
+                ty = ty.to_string(),
+                error = e.to_string(),
+            )
+        )
+    }
+
+    /// Handler for the profile command, which lists or activates settings
+    /// profiles.
+    async fn profile(&self, ctx: &mut command::Context) -> Result<()> {
+        let profiles = Profiles::new(self.settings.clone());
+
+        let name = match ctx.next() {
+            Some(name) => name,
+            None => {
+                let names = profiles.list().await?.into_iter().map(|(name, _)| name);
+                let names = names.collect::<Vec<_>>();
+
+                if names.is_empty() {
+                    respond!(ctx, message!(self.messages, "admin/no-profiles"));
+                    return Ok(());
+                }
+
+                let active = profiles.active().await?;
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "admin/profiles",
+                        profiles = names.join(", "),
+                        active = active,
+                    )
+                );
+                return Ok(());
+            }
+        };
+
+        if !profiles.list().await?.contains_key(&name) {
+            respond_bail!(
+                "{}",
+                message!(self.messages, "admin/no-such-profile", name = name)
+            );
+        }
+
+        let import = profiles.activate(&name).await?;
+
+        if !import.errors.is_empty() {
+            let keys = import.errors.keys().cloned().collect::<Vec<_>>();
+            respond_bail!(
+                "{}",
+                message!(
+                    self.messages,
+                    "admin/invalid-profile",
+                    name = name,
+                    keys = keys.join(", "),
+                )
+            );
+        }
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "admin/profile-activated",
+                name = name,
+                count = import.imported.len(),
+            )
+        );
+        Ok(())
+    }
+
+    /// Handler for the group command, which lists, enables, or disables groups
+    /// of commands, aliases, promotions, themes, and triggers.
+    async fn group(&self, ctx: &mut command::Context) -> Result<()> {
+        match ctx.next().as_deref() {
+            Some("enable") => {
+                let group = ctx.next_str("<group>")?;
+                self.groups.enable(ctx.channel(), &group).await?;
+                respond!(
+                    ctx,
+                    message!(self.messages, "admin/group-enabled", group = group)
+                );
+            }
+            Some("disable") => {
+                let group = ctx.next_str("<group>")?;
+                self.groups.disable(ctx.channel(), &group).await?;
+                respond!(
+                    ctx,
+                    message!(self.messages, "admin/group-disabled", group = group)
+                );
+            }
+            Some("list") => {
+                let groups = self.groups.list(ctx.channel()).await?;
+
+                if groups.is_empty() {
+                    respond!(ctx, message!(self.messages, "admin/no-groups"));
+                    return Ok(());
+                }
+
+                let groups = groups
+                    .iter()
+                    .map(|g| {
+                        message!(
+                            self.messages,
+                            "admin/group",
+                            name = g.name,
+                            members = g.members.len(),
+                            disabled = g.is_disabled(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                respond!(
+                    ctx,
+                    message!(self.messages, "admin/groups", groups = groups.join(", "))
+                );
+            }
+            Some("show") => {
+                let name = ctx.next_str("<group>")?;
+                let groups = self.groups.list(ctx.channel()).await?;
+
+                let group = match groups.into_iter().find(|g| g.name == name) {
+                    Some(group) => group,
+                    None => respond_bail!(
+                        "{}",
+                        message!(self.messages, "admin/no-such-group", name = name)
+                    ),
+                };
+
+                let members = group
+                    .members
+                    .iter()
+                    .map(|m| {
+                        message!(
+                            self.messages,
+                            "admin/group-member",
+                            name = m.name,
+                            disabled = m.disabled,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                respond!(ctx, "{}: {}", group.name, members.join(", "));
+            }
+            _ => {
+                respond!(ctx, "Expected: list, show, enable, or disable");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handler for the backup command.
+    async fn backup(&self, ctx: &mut command::Context) -> Result<()> {
+        let backups = match self.backups.read().await.clone() {
+            Some(backups) => backups,
+            None => respond_bail!("{}", message!(self.messages, "admin/no-backups")),
+        };
+
+        match ctx.next().as_deref() {
+            Some("now") => {
+                let backup = backups.backup().await?;
                 respond!(
                     ctx,
-                    "Expected one of: \
-                     refresh-mods, \
-                     refresh-vips, \
-                     version, \
-                     shutdown, \
-                     settings.",
+                    message!(self.messages, "admin/backup-taken", name = backup.name)
                 );
             }
+            None => match backups.list().await?.pop() {
+                Some(latest) => {
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "admin/latest-backup",
+                            name = latest.name,
+                            taken = latest
+                                .created_at
+                                .format("%Y-%m-%d %H:%M:%S UTC")
+                                .to_string(),
+                        )
+                    );
+                }
+                None => {
+                    respond!(ctx, message!(self.messages, "admin/no-backups-taken"));
+                }
+            },
+            _ => {
+                respond!(ctx, "Expected: now");
+            }
         }
 
         Ok(())
     }
-}
 
-impl Handler {
     /// Handler for the toggle command.
     async fn toggle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
-        let key = key(ctx)?;
+        let key = key(&self.messages, ctx)?;
 
         let setting = self
             .settings
             .setting::<serde_json::Value>(&key)
             .await?
-            .ok_or_else(|| respond_err!("No setting matching key: {}", key))?;
+            .ok_or_else(|| {
+                respond_err!(
+                    "{}",
+                    message!(self.messages, "admin/no-matching-setting", key = key)
+                )
+            })?;
 
         if let Some(scope) = setting.schema.scope {
             if !ctx.user.has_scope(scope).await {
-                respond!(
-                    ctx,
-                    "You are not permitted to modify that setting, sorry :("
-                );
+                respond!(ctx, message!(self.messages, "admin/not-permitted"));
                 return Ok(());
             }
         }
@@ -266,9 +469,12 @@ impl Handler {
             other => {
                 respond!(
                     ctx,
-                    "Can only toggle bool settings, but {} is a {}",
-                    key,
-                    other
+                    message!(
+                        self.messages,
+                        "admin/not-bool",
+                        key = key,
+                        ty = other.to_string(),
+                    )
                 );
                 return Ok(());
             }
@@ -276,7 +482,10 @@ impl Handler {
 
         let value_string = serde_json::to_string(&toggled)?;
         self.settings.set_json(&key, toggled).await?;
-        respond!(ctx, "Updated setting {} = {}", key, value_string);
+        respond!(
+            ctx,
+            message!(self.messages, "admin/set", key = key, value = value_string,)
+        );
         Ok(())
     }
 
@@ -291,14 +500,12 @@ impl Handler {
         let schema = self
             .settings
             .lookup(key)
-            .ok_or_else(|| respond_err!("No such setting"))?;
+            .ok_or_else(|| respond_err!("{}", message!(self.messages, "admin/no-such-setting")))?;
 
         // Test schema permissions.
         if let Some(scope) = schema.scope {
             if !ctx.user.has_scope(scope).await {
-                return Err(
-                    respond_err!("You are not permitted to modify that setting, sorry :(").into(),
-                );
+                respond_bail!("{}", message!(self.messages, "admin/not-permitted"));
             }
         }
 
@@ -308,26 +515,27 @@ impl Handler {
                 ..
             } => value,
             ref other => {
-                return Err(
-                    respond_err!("Configuration is a {}, but expected a set", other).into(),
+                respond_bail!(
+                    "{}",
+                    message!(self.messages, "admin/not-a-set", ty = other.to_string())
                 );
             }
         };
 
         let value = ty
             .parse_as_json(ctx.rest())
-            .map_err(|e| respond_err!("Value is not a valid {} type: {}", ty, e))?;
+            .map_err(|e| self.bad_value(ty, e))?;
 
         Ok(value)
     }
 }
 
 /// Extract a settings key from the context.
-fn key(ctx: &mut command::Context) -> Result<String> {
+fn key(messages: &Messages, ctx: &mut command::Context) -> Result<String> {
     let key = ctx.next().ok_or_else(|| respond_err!("Expected <key>"))?;
 
     if key.starts_with("secrets/") {
-        respond_bail!("Cannot access secrets through chat!");
+        respond_bail!("{}", message!(messages, "admin/secrets"));
     }
 
     Ok(key)
@@ -350,14 +558,18 @@ impl super::Module for Module {
             ..
         }: module::HookContext<'_>,
     ) -> Result<()> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
         handlers.insert(
             "admin",
             Handler {
                 settings: settings.clone(),
-                aliases: injector.var().await?,
-                commands: injector.var().await?,
-                promotions: injector.var().await?,
-                themes: injector.var().await?,
+                groups: Groups::new(injector).await?,
+                backups: injector.var().await?,
+                messages,
             },
         );
 
@@ -0,0 +1,310 @@
+//! Let the streamer and moderators mark themselves as away from keyboard.
+//!
+//! While someone is away, the bot answers mentions of them with their status.
+//! They're marked as back as soon as they say something in chat.
+
+use crate::afk::{Afk, Status};
+use crate::api::Obs;
+use crate::auth;
+use crate::command;
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// State shared between the `!afk` command and the hook detecting returns.
+struct Shared {
+    afk: injector::Var<Option<Afk>>,
+    obs: injector::Var<Option<Obs>>,
+    messages: Messages,
+    scene: settings::Var<Option<String>>,
+    mention_cooldown: settings::Var<utils::Duration>,
+    /// The scene OBS was on before the streamer went away.
+    previous_scene: Mutex<Option<String>>,
+    /// When the status of each user was last posted in response to a mention.
+    mentioned: Mutex<HashMap<String, Instant>>,
+}
+
+impl Shared {
+    /// Switch OBS to the away scene, if configured.
+    async fn switch_away(&self) {
+        let (obs, scene) = match (self.obs.load().await, self.scene.load().await) {
+            (Some(obs), Some(scene)) if !scene.is_empty() => (obs, scene),
+            _ => return,
+        };
+
+        match obs.scenes().await {
+            Ok(list) if list.current_scene != scene => {
+                *self.previous_scene.lock() = Some(list.current_scene);
+            }
+            Ok(..) => (),
+            Err(e) => log_error!(e, "failed to get current scene"),
+        }
+
+        if let Err(e) = obs.set_current_scene(&scene).await {
+            log_error!(e, "failed to switch to scene `{}`", scene);
+        }
+    }
+
+    /// Switch OBS back to the scene used before going away.
+    async fn switch_back(&self) {
+        let scene = match self.previous_scene.lock().take() {
+            Some(scene) => scene,
+            None => return,
+        };
+
+        let obs = match self.obs.load().await {
+            Some(obs) => obs,
+            None => return,
+        };
+
+        if let Err(e) = obs.set_current_scene(&scene).await {
+            log_error!(e, "failed to switch back to scene `{}`", scene);
+        }
+    }
+
+    /// Test if the status of the given user should be posted in response to
+    /// a mention.
+    async fn should_mention(&self, user: &str) -> bool {
+        let cooldown = self.mention_cooldown.load().await.as_std();
+        let now = Instant::now();
+        let mut mentioned = self.mentioned.lock();
+
+        if let Some(last) = mentioned.get(user) {
+            if now.duration_since(*last) < cooldown {
+                return false;
+            }
+        }
+
+        mentioned.insert(user.to_string(), now);
+        true
+    }
+}
+
+/// Test if the given message mentions the given user, with or without a
+/// leading `@`.
+fn mentions(message: &str, user: &str) -> bool {
+    message
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word.eq_ignore_ascii_case(user))
+}
+
+/// Test if the given message is a command to go away.
+///
+/// The hook sees these messages too, and must not treat them as a return.
+fn is_afk_command(message: &str) -> bool {
+    match message.split_whitespace().next() {
+        Some(command) => {
+            command.eq_ignore_ascii_case("!afk") || command.eq_ignore_ascii_case("!brb")
+        }
+        None => false,
+    }
+}
+
+/// Peeks at chat messages to detect returns and mentions of away users.
+struct Returns {
+    shared: Arc<Shared>,
+}
+
+#[async_trait]
+impl command::MessageHook for Returns {
+    async fn peek(&self, user: &irc::User, m: &str) -> Result<()> {
+        let afk = match self.shared.afk.load().await {
+            Some(afk) => afk,
+            None => return Ok(()),
+        };
+
+        let name = match user.real() {
+            Some(user) => user.name().to_string(),
+            None => return Ok(()),
+        };
+
+        if !is_afk_command(m) {
+            if let Some(status) = afk.clear(&name) {
+                if name == user.streamer().name {
+                    self.shared.switch_back().await;
+                }
+
+                let away = (Utc::now() - status.since).to_std().unwrap_or_default();
+
+                let response = message!(
+                    self.shared.messages,
+                    "afk/back",
+                    user = status.display_name,
+                    duration = utils::compact_duration(away),
+                );
+
+                user.sender().privmsg(response).await;
+
+                return Ok(());
+            }
+        }
+
+        for status in afk.list() {
+            if status.user == name || !mentions(m, &status.user) {
+                continue;
+            }
+
+            if !self.shared.should_mention(&status.user).await {
+                continue;
+            }
+
+            let away = (Utc::now() - status.since).to_std().unwrap_or_default();
+
+            let response = message!(
+                self.shared.messages,
+                "afk/away",
+                user = status.display_name,
+                reason = status.reason,
+                duration = utils::compact_duration(away),
+            );
+
+            user.sender().privmsg(response).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Handler for the `!afk` command.
+pub struct Handler {
+    shared: Arc<Shared>,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Afk)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!afk [reason] or !brb [reason]",
+            description: "Let chat know that you are away, until you talk again.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        let afk = match self.shared.afk.load().await {
+            Some(afk) => afk,
+            None => return Ok(()),
+        };
+
+        let (name, display_name) = match ctx.user.real() {
+            Some(user) => (user.name().to_string(), user.display_name().to_string()),
+            None => respond_bail!("{}", message!(self.shared.messages, "afk/only-real-users")),
+        };
+
+        let reason = match ctx.rest().trim() {
+            "" => None,
+            reason => Some(reason.to_string()),
+        };
+
+        let response = message!(
+            self.shared.messages,
+            "afk/now-away",
+            user = display_name,
+            reason = reason,
+        );
+
+        afk.set(Status {
+            user: name.clone(),
+            display_name,
+            reason,
+            since: Utc::now(),
+        });
+
+        self.shared.mentioned.lock().remove(&name);
+
+        if name == ctx.user.streamer().name {
+            self.shared.switch_away().await;
+        }
+
+        ctx.privmsg(response).await;
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "afk"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("afk/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("afk");
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        let shared = Arc::new(Shared {
+            afk: injector.var().await?,
+            obs: injector.var().await?,
+            messages,
+            scene: settings.optional("obs/scene").await?,
+            mention_cooldown: settings
+                .var("mention-cooldown", utils::Duration::seconds(60))
+                .await?,
+            previous_scene: Default::default(),
+            mentioned: Default::default(),
+        });
+
+        handlers.insert_hook(Returns {
+            shared: shared.clone(),
+        });
+
+        handlers.insert(
+            "afk",
+            Handler {
+                shared: shared.clone(),
+            },
+        );
+
+        handlers.insert("brb", Handler { shared });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_afk_command, mentions};
+
+    #[test]
+    fn test_mentions() {
+        assert!(mentions("hey @setbac are you there?", "setbac"));
+        assert!(mentions("SETBAC, hello", "setbac"));
+        assert!(!mentions("setbac2 is here", "setbac"));
+        assert!(!mentions("nothing to see here", "setbac"));
+    }
+
+    #[test]
+    fn test_is_afk_command() {
+        assert!(is_afk_command("!afk getting food"));
+        assert!(is_afk_command("!BRB"));
+        assert!(!is_afk_command("!afkish"));
+        assert!(!is_afk_command("back from !afk"));
+    }
+}
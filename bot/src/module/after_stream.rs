@@ -1,15 +1,21 @@
 use crate::auth;
 use crate::command;
 use crate::db;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
+use crate::stream_info;
 use crate::utils;
+use crate::webhooks::{self, Webhooks};
 
 /// Handler for the `!afterstream` command.
 pub struct AfterStream {
     pub enabled: settings::Var<bool>,
     pub cooldown: settings::Var<utils::Cooldown>,
     pub after_streams: injector::Var<Option<db::AfterStreams>>,
+    pub webhooks: injector::Var<Option<Webhooks>>,
+    pub messages: Messages,
+    pub stream_info: stream_info::StreamInfo,
 }
 
 #[async_trait]
@@ -18,6 +24,13 @@ impl command::Handler for AfterStream {
         Some(auth::Scope::AfterStream)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!afterstream <message>",
+            description: "Leave a message for the streamer to read after the stream.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
         if !self.enabled.load().await {
             return Ok(());
@@ -26,7 +39,7 @@ impl command::Handler for AfterStream {
         let user = match ctx.user.real() {
             Some(user) => user,
             None => {
-                respond!(ctx, "Only real users can add after stream messages");
+                respond!(ctx, message!(self.messages, "afterstream/only-real-users"));
                 return Ok(());
             }
         };
@@ -37,23 +50,31 @@ impl command::Handler for AfterStream {
         };
 
         if !self.cooldown.write().await.is_open() {
-            respond!(ctx, "An afterstream was already created recently.");
+            respond!(ctx, message!(self.messages, "afterstream/cooldown"));
             return Ok(());
         }
 
         if ctx.rest().trim().is_empty() {
-            respond!(
-                ctx,
-                "You add a reminder by calling !afterstream <reminder>, \
-                 like \"!afterstream remember that you are awesome <3\"",
-            );
+            respond!(ctx, message!(self.messages, "afterstream/usage"));
             return Ok(());
         }
 
-        after_streams
-            .push(ctx.channel(), user.name(), ctx.rest())
+        let stream_id = self.stream_info.stream_id();
+
+        let id = after_streams
+            .push(ctx.channel(), user.name(), ctx.rest(), stream_id.as_deref())
             .await?;
-        respond!(ctx, "Reminder added.");
+
+        if let Some(webhooks) = self.webhooks.load().await {
+            webhooks.send(webhooks::Event::AfterStreamAdded {
+                user: user.name().to_string(),
+                text: ctx.rest().to_string(),
+            });
+        }
+
+        // NB: whispered to avoid cluttering chat with confirmations.
+        ctx.whisper(message!(self.messages, "afterstream/added", id = id))
+            .await;
         Ok(())
     }
 }
@@ -66,6 +87,10 @@ impl super::Module for Module {
         "afterstream"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("afterstream/enabled", true))
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
@@ -73,11 +98,17 @@ impl super::Module for Module {
             injector,
             handlers,
             settings,
+            stream_info,
             ..
         }: module::HookContext<'_>,
     ) -> Result<(), anyhow::Error> {
         let settings = settings.scoped("afterstream");
 
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("messages are not configured"))?;
+
         handlers.insert(
             "afterstream",
             AfterStream {
@@ -89,6 +120,9 @@ impl super::Module for Module {
                     )
                     .await?,
                 after_streams: injector.var().await?,
+                webhooks: injector.var().await?,
+                messages,
+                stream_info: stream_info.clone(),
             },
         );
 
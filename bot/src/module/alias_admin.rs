@@ -1,24 +1,36 @@
 use crate::auth;
 use crate::command;
 use crate::db;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+
+/// Usage of the `!alias` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!alias <show|list|edit|pattern|rename|delete|enable|disable|group|clear-group>",
+    description: "Manage command aliases.",
+};
 
 /// Handler for the !alias command.
 pub struct Handler {
     pub aliases: injector::Var<Option<db::Aliases>>,
+    pub messages: Messages,
 }
 
 #[async_trait]
 impl command::Handler for Handler {
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         let aliases = match self.aliases.load().await {
             Some(aliases) => aliases,
             None => return Ok(()),
         };
 
-        let next = command_base!(ctx, aliases, "alias", AliasEdit);
+        let next = command_base!(ctx, self.messages, aliases, "alias", AliasEdit);
 
         match next.as_deref() {
             Some("edit") => {
@@ -28,7 +40,10 @@ impl command::Handler for Handler {
                 let template = ctx.rest_parse("<name> <template>")?;
                 aliases.edit(ctx.channel(), &name, template).await?;
 
-                respond!(ctx, "Edited alias");
+                respond!(
+                    ctx,
+                    message!(self.messages, "manage/edited", what = "alias")
+                );
             }
             Some("pattern") => {
                 ctx.check_scope(auth::Scope::AliasEdit).await?;
@@ -40,27 +55,32 @@ impl command::Handler for Handler {
                     pattern => match regex::Regex::new(pattern) {
                         Ok(pattern) => Some(pattern),
                         Err(e) => {
-                            ctx.user
-                                .respond(format!("Bad pattern provided: {}", e))
-                                .await;
+                            let error = e.to_string();
+                            respond!(
+                                ctx,
+                                message!(self.messages, "manage/bad-pattern", error = error)
+                            );
                             return Ok(());
                         }
                     },
                 };
 
                 if !aliases.edit_pattern(ctx.channel(), &name, pattern).await? {
-                    respond!(ctx, format!("No such alias: `{}`", name));
+                    respond!(
+                        ctx,
+                        message!(self.messages, "manage/missing", what = "alias", name = name)
+                    );
                     return Ok(());
                 }
 
-                respond!(ctx, "Edited pattern for alias.");
-            }
-            None | Some(..) => {
                 respond!(
                     ctx,
-                    "Expected: show, list, edit, delete, enable, disable, or group."
+                    message!(self.messages, "manage/pattern-edited", what = "alias")
                 );
             }
+            None | Some(..) => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
         }
 
         Ok(())
@@ -81,10 +101,16 @@ impl super::Module for Module {
             injector, handlers, ..
         }: module::HookContext<'_>,
     ) -> Result<()> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
         handlers.insert(
             "alias",
             Handler {
                 aliases: injector.var().await?,
+                messages,
             },
         );
         Ok(())
@@ -1,18 +1,32 @@
 use crate::auth;
 use crate::command;
+use crate::db;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
 use crate::utils::Duration;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::Utc;
 
+/// Usage of the `!auth` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!auth <scopes|permit|grant|deny|revoke>",
+    description: "Manage who is allowed to do what.",
+};
+
 /// Handler for the !auth command.
 pub struct Handler {
     auth: auth::Auth,
+    moderation_log: injector::Var<Option<db::ModerationLog>>,
+    messages: Messages,
 }
 
 #[async_trait]
 impl command::Handler for Handler {
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         match ctx.next().as_deref() {
             Some("scopes") => {
@@ -22,7 +36,7 @@ impl command::Handler for Handler {
                 let user = match ctx.user.real() {
                     Some(user) => user,
                     None => {
-                        respond!(ctx, "Can only get scopes for real users");
+                        respond!(ctx, message!(self.messages, "auth/only-real-users"));
                         return Ok(());
                     }
                 };
@@ -40,10 +54,11 @@ impl command::Handler for Handler {
                 let mut result = Vec::new();
 
                 if !by_user.is_empty() {
-                    result.push(format!(
-                        "Your ({}): {}",
-                        user.display_name(),
-                        by_user.join(", ")
+                    result.push(message!(
+                        self.messages,
+                        "auth/your-scopes",
+                        user = user.display_name(),
+                        scopes = by_user.join(", ")
                     ));
                 }
 
@@ -55,7 +70,8 @@ impl command::Handler for Handler {
                     }
                 }
 
-                ctx.respond_lines(result, "*no scopes*").await;
+                ctx.respond_lines(result, &message!(self.messages, "auth/no-scopes"))
+                    .await;
             }
             Some("permit") => {
                 ctx.check_scope(auth::Scope::AuthPermit).await?;
@@ -67,8 +83,11 @@ impl command::Handler for Handler {
                 if !ctx.user.has_scope(scope).await {
                     respond!(
                         ctx,
-                        "Trying to grant scope `{}` that you don't have :(",
-                        scope
+                        message!(
+                            self.messages,
+                            "auth/missing-scope",
+                            scope = scope.to_string()
+                        )
                     );
                     return Ok(());
                 }
@@ -78,18 +97,92 @@ impl command::Handler for Handler {
 
                 respond!(
                     ctx,
-                    "Gave: {scope} to {principal} for {duration}",
-                    duration = duration,
-                    principal = principal,
-                    scope = scope
+                    message!(
+                        self.messages,
+                        "auth/permitted",
+                        scope = scope.to_string(),
+                        principal = principal.to_string(),
+                        duration = duration.to_string(),
+                    )
                 );
 
                 self.auth
-                    .insert_temporary(scope, principal, expires_at)
+                    .insert_temporary(scope, principal.clone(), expires_at)
                     .await;
+
+                if let Some(moderation_log) = self.moderation_log.load().await {
+                    let action = db::AddModerationAction {
+                        channel: ctx.channel().to_string(),
+                        action: String::from("permit"),
+                        user: principal.to_string(),
+                        user_id: None,
+                        message: Some(format!(
+                            "{} granted by {}",
+                            scope,
+                            ctx.user.display_name().unwrap_or("unknown")
+                        )),
+                        rule: None,
+                        duration: Some(duration.num_seconds() as i64),
+                        at: now.naive_utc(),
+                    };
+
+                    if let Err(e) = moderation_log.push(&action).await {
+                        log_error!(e, "failed to record permit");
+                    }
+                }
+            }
+            Some(action @ "grant") | Some(action @ "deny") => {
+                ctx.check_scope(auth::Scope::AuthGrant).await?;
+
+                let user = db::user_id(&ctx.next_str("<user> <scope>")?);
+                let scope = ctx.next_parse("<user> <scope>")?;
+
+                if let auth::Scope::Unknown = scope {
+                    respond!(ctx, message!(self.messages, "auth/unknown-scope"));
+                    return Ok(());
+                }
+
+                if !ctx.user.has_scope(scope).await {
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "auth/missing-scope",
+                            scope = scope.to_string()
+                        )
+                    );
+                    return Ok(());
+                }
+
+                let allow = action == "grant";
+                self.auth.insert_user(scope, &user, allow).await?;
+
+                let key = if allow { "auth/granted" } else { "auth/denied" };
+
+                respond!(
+                    ctx,
+                    message!(self.messages, key, scope = scope.to_string(), user = user)
+                );
+            }
+            Some("revoke") => {
+                ctx.check_scope(auth::Scope::AuthGrant).await?;
+
+                let user = db::user_id(&ctx.next_str("<user> <scope>")?);
+                let scope = ctx.next_parse("<user> <scope>")?;
+
+                let key = if self.auth.delete_user(scope, &user).await? {
+                    "auth/revoked"
+                } else {
+                    "auth/not-revoked"
+                };
+
+                respond!(
+                    ctx,
+                    message!(self.messages, key, scope = scope.to_string(), user = user)
+                );
             }
             _ => {
-                respond!(ctx, "Expected: scopes, permit");
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
             }
         }
 
@@ -113,9 +206,27 @@ impl super::Module for Module {
 
     async fn hook(
         &self,
-        module::HookContext { handlers, auth, .. }: module::HookContext<'_>,
+        module::HookContext {
+            handlers,
+            auth,
+            injector,
+            ..
+        }: module::HookContext<'_>,
     ) -> Result<()> {
-        handlers.insert("auth", Handler { auth: auth.clone() });
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "auth",
+            Handler {
+                auth: auth.clone(),
+                moderation_log: injector.var().await?,
+                messages,
+            },
+        );
+
         Ok(())
     }
 }
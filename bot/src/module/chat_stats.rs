@@ -0,0 +1,155 @@
+use crate::auth;
+use crate::chat_stats::ChatStats;
+use crate::command;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use anyhow::anyhow;
+
+/// Handler for the !chatstats command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    chat_stats: injector::Var<Option<ChatStats>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::ChatStats)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!chatstats [user|stream]",
+            description: "Show chat activity for a user, or for the current stream.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let chat_stats = match self.chat_stats.load().await {
+            Some(chat_stats) => chat_stats,
+            None => return Ok(()),
+        };
+
+        let (name, user) = match ctx.next() {
+            Some(arg) if arg == "stream" => {
+                let current = chat_stats.current_stream();
+                let last = chat_stats.stream_history(1).await?.into_iter().next();
+
+                let emotes = current
+                    .emotes
+                    .iter()
+                    .take(3)
+                    .map(|(emote, count)| format!("{} ({})", emote, count))
+                    .collect::<Vec<_>>();
+
+                let (last_messages, last_chatters) = match &last {
+                    Some(last) => (last.messages, last.chatters),
+                    None => Default::default(),
+                };
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "chat-stats/stream",
+                        messages = current.messages,
+                        chatters = current.chatters,
+                        emotes = emotes.join(", "),
+                        last = last.is_some(),
+                        last_messages = last_messages,
+                        last_chatters = last_chatters,
+                    )
+                );
+
+                return Ok(());
+            }
+            Some(user) => {
+                let user = user.trim_start_matches('@').to_string();
+                (user.clone(), user)
+            }
+            // NB: stats are stored by login name, which can differ from the
+            // display name in more than just casing.
+            None => match (ctx.user.name(), ctx.user.display_name()) {
+                (Some(name), Some(user)) => (name.to_string(), user.to_string()),
+                _ => respond_bail!("Expected: <user>"),
+            },
+        };
+
+        let stats = match chat_stats.user(&name).await? {
+            Some(stats) => stats,
+            None => {
+                respond!(
+                    ctx,
+                    message!(self.messages, "chat-stats/not-seen", user = user)
+                );
+                return Ok(());
+            }
+        };
+
+        let (emote, emote_count) = match stats.favorite_emote() {
+            Some((emote, count)) => (Some(emote), count),
+            None => (None, Default::default()),
+        };
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "chat-stats/user",
+                user = user,
+                messages = stats.messages,
+                days = stats.active_days.len(),
+                emote = emote,
+                emote_count = emote_count,
+            )
+        );
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "chat-stats"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("chat-stats/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<(), anyhow::Error> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "chatstats",
+            Handler {
+                enabled: settings.var("chat-stats/enabled", false).await?,
+                chat_stats: injector.var().await?,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -1,18 +1,70 @@
 use crate::api;
 use crate::auth;
 use crate::command;
+use crate::db;
+use crate::messages::Messages;
 use crate::module;
+use crate::player;
 use crate::prelude::*;
 use crate::stream_info;
-use crate::utils::{Cooldown, Duration};
-use anyhow::Result;
+use crate::template::Template;
+use crate::utils::{self, Cooldown, Duration};
+use anyhow::{anyhow, Result};
+use std::time::Instant;
+
+/// Default template used for clip titles.
+const DEFAULT_TITLE_TEMPLATE: &str =
+    "{{title}}{{#if game}} - {{game}}{{/if}}{{#if song}} (playing {{song}}){{/if}}";
+
+/// The number of clips listed by `!clip list`.
+const LIST_LIMIT: i64 = 3;
+
+/// A cooldown which applies to users with the given role.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RoleCooldown {
+    role: auth::Role,
+    cooldown: Duration,
+}
+
+#[derive(serde::Serialize)]
+struct TitleData<'a> {
+    title: Option<&'a str>,
+    game: Option<&'a str>,
+    song: Option<&'a str>,
+    user: Option<&'a str>,
+}
 
 /// Handler for the `!clip` command.
 pub struct Clip {
     pub enabled: settings::Var<bool>,
     pub stream_info: stream_info::StreamInfo,
     pub clip_cooldown: settings::Var<Cooldown>,
+    pub role_cooldowns: settings::Var<Vec<RoleCooldown>>,
+    pub title_template: settings::Var<Template>,
+    pub clips: injector::Var<Option<db::Clips>>,
+    pub song: injector::Var<Option<player::Song>>,
     pub twitch: api::Twitch,
+    pub messages: Messages,
+}
+
+impl Clip {
+    /// List the most recently created clips.
+    async fn list(&self, ctx: &mut command::Context) -> Result<()> {
+        let clips = match self.clips.load().await {
+            Some(clips) => clips,
+            None => respond_bail!("{}", message!(self.messages, "clip/not-available")),
+        };
+
+        let results = clips
+            .list(LIST_LIMIT)
+            .await?
+            .into_iter()
+            .map(|c| format!("\"{}\" {}/{}", c.title, api::twitch::CLIPS_URL, c.clip_id));
+
+        ctx.respond_lines(results, &message!(self.messages, "clip/list-empty"))
+            .await;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -21,40 +73,101 @@ impl command::Handler for Clip {
         Some(auth::Scope::Clip)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!clip [title]|list",
+            description: "Create a clip of the stream, or list recently created clips.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
         }
 
-        if !self.clip_cooldown.write().await.is_open() {
-            respond!(ctx, "A clip was already created recently");
-            return Ok(());
+        if ctx.rest().trim() == "list" {
+            return self.list(ctx).await;
+        }
+
+        {
+            let role_cooldowns = self.role_cooldowns.load().await;
+            let mut clip_cooldown = self.clip_cooldown.write().await;
+            let now = Instant::now();
+
+            let mut check = clip_cooldown.clone();
+
+            if let Some(cooldown) = role_cooldown(&role_cooldowns, &ctx.user.roles()) {
+                check.cooldown = cooldown;
+            }
+
+            if let Some(remaining) = check.check(now) {
+                respond_bail!(
+                    "{}",
+                    message!(
+                        self.messages,
+                        "clip/cooldown",
+                        remaining = utils::compact_duration(remaining)
+                    )
+                );
+            }
+
+            clip_cooldown.poke(now);
         }
 
         let stream_user = self.stream_info.user.clone();
 
-        let title = match ctx.rest().trim() {
+        let requested = match ctx.rest().trim() {
             "" => None,
             other => Some(other.to_string()),
         };
 
+        let (stream_title, game) = {
+            let data = self.stream_info.data.read();
+            (data.title.clone(), data.game.clone())
+        };
+
+        let song = self.song.load().await.map(|song| song.item.what());
+
+        let data = TitleData {
+            title: requested.as_deref().or_else(|| stream_title.as_deref()),
+            game: game.as_deref(),
+            song: song.as_deref(),
+            user: ctx.user.name(),
+        };
+
+        let title = self.title_template.load().await.render_to_string(&data)?;
+        let title = title.trim().to_string();
+
         let twitch = self.twitch.clone();
 
         match twitch.create_clip(&stream_user.id).await? {
             Some(clip) => {
                 respond!(
                     ctx,
-                    "Created clip at {}/{}",
-                    api::twitch::CLIPS_URL,
-                    clip.id
+                    message!(
+                        self.messages,
+                        "clip/created",
+                        title = title,
+                        url = format!("{}/{}", api::twitch::CLIPS_URL, clip.id),
+                    )
                 );
 
-                if let Some(_title) = title {
-                    log::warn!("Title was requested, but it can't be set (right now)")
+                if let Some(clips) = self.clips.load().await {
+                    let clip = db::AddClip {
+                        clip_id: clip.id,
+                        title,
+                        user: ctx.user.name().map(String::from),
+                        game,
+                        song,
+                    };
+
+                    if let Err(e) = clips.push(clip).await {
+                        log_error!(e, "Failed to store clip");
+                    }
                 }
             }
             None => {
-                respond!(ctx, "Failed to create clip, sorry :(");
+                respond!(ctx, message!(self.messages, "clip/failed"));
                 log::error!("created clip, but API returned nothing");
             }
         }
@@ -63,6 +176,15 @@ impl command::Handler for Clip {
     }
 }
 
+/// Find the shortest cooldown configured for any of the given roles.
+fn role_cooldown(role_cooldowns: &[RoleCooldown], roles: &[auth::Role]) -> Option<Duration> {
+    role_cooldowns
+        .iter()
+        .filter(|c| roles.contains(&c.role))
+        .map(|c| c.cooldown)
+        .min()
+}
+
 pub struct Module;
 
 #[async_trait]
@@ -71,12 +193,17 @@ impl super::Module for Module {
         "clip"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("clip/enabled", true))
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
         module::HookContext {
             handlers,
             settings,
+            injector,
             stream_info,
             twitch,
             ..
@@ -84,6 +211,11 @@ impl super::Module for Module {
     ) -> Result<()> {
         let settings = settings.scoped("clip");
 
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
         handlers.insert(
             "clip",
             Clip {
@@ -92,10 +224,48 @@ impl super::Module for Module {
                 clip_cooldown: settings
                     .var("cooldown", Cooldown::from_duration(Duration::seconds(30)))
                     .await?,
+                role_cooldowns: settings.var("role-cooldowns", Vec::new()).await?,
+                title_template: settings
+                    .var("title-template", Template::compile(DEFAULT_TITLE_TEMPLATE)?)
+                    .await?,
+                clips: injector.var().await?,
+                song: injector.var().await?,
                 twitch: twitch.clone(),
+                messages,
             },
         );
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{role_cooldown, RoleCooldown};
+    use crate::auth::Role;
+    use crate::utils::Duration;
+
+    #[test]
+    fn test_role_cooldown() {
+        let cooldowns = vec![
+            RoleCooldown {
+                role: Role::Subscriber,
+                cooldown: Duration::seconds(60),
+            },
+            RoleCooldown {
+                role: Role::Moderator,
+                cooldown: Duration::seconds(10),
+            },
+        ];
+
+        assert_eq!(None, role_cooldown(&cooldowns, &[Role::Everyone]));
+        assert_eq!(
+            Some(Duration::seconds(60)),
+            role_cooldown(&cooldowns, &[Role::Subscriber, Role::Everyone])
+        );
+        assert_eq!(
+            Some(Duration::seconds(10)),
+            role_cooldown(&cooldowns, &[Role::Moderator, Role::Subscriber])
+        );
+    }
+}
@@ -1,12 +1,21 @@
 use crate::auth;
 use crate::command;
 use crate::db;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
+use anyhow::anyhow;
+
+/// Usage of the `!command` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!command <show|list|edit|pattern|rename|delete|enable|disable|group|clear-group>",
+    description: "Manage custom commands.",
+};
 
 pub struct Handler {
     pub enabled: settings::Var<bool>,
     pub commands: injector::Var<Option<db::Commands>>,
+    pub messages: Messages,
 }
 
 #[async_trait]
@@ -15,6 +24,10 @@ impl command::Handler for Handler {
         Some(auth::Scope::Command)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
         if !self.enabled.load().await {
             return Ok(());
@@ -25,7 +38,7 @@ impl command::Handler for Handler {
             None => return Ok(()),
         };
 
-        let next = command_base!(ctx, commands, "command", CommandEdit);
+        let next = command_base!(ctx, self.messages, commands, "command", CommandEdit);
 
         match next.as_deref() {
             Some("edit") => {
@@ -35,7 +48,10 @@ impl command::Handler for Handler {
                 let template = ctx.rest_parse("<name> <template>")?;
                 commands.edit(ctx.channel(), &name, template).await?;
 
-                respond!(ctx, "Edited command.");
+                respond!(
+                    ctx,
+                    message!(self.messages, "manage/edited", what = "command")
+                );
             }
             Some("pattern") => {
                 ctx.check_scope(auth::Scope::CommandEdit).await?;
@@ -47,27 +63,37 @@ impl command::Handler for Handler {
                     pattern => match regex::Regex::new(pattern) {
                         Ok(pattern) => Some(pattern),
                         Err(e) => {
-                            ctx.user
-                                .respond(format!("Bad pattern provided: {}", e))
-                                .await;
+                            let error = e.to_string();
+                            respond!(
+                                ctx,
+                                message!(self.messages, "manage/bad-pattern", error = error)
+                            );
                             return Ok(());
                         }
                     },
                 };
 
                 if !commands.edit_pattern(ctx.channel(), &name, pattern).await? {
-                    respond!(ctx, format!("No such command: `{}`", name));
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "manage/missing",
+                            what = "command",
+                            name = name
+                        )
+                    );
                     return Ok(());
                 }
 
-                respond!(ctx, "Edited pattern for command.");
-            }
-            None | Some(..) => {
                 respond!(
                     ctx,
-                    "Expected: show, list, edit, delete, enable, disable, or group."
+                    message!(self.messages, "manage/pattern-edited", what = "command")
                 );
             }
+            None | Some(..) => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
         }
 
         Ok(())
@@ -82,6 +108,10 @@ impl super::Module for Module {
         "command"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("command/enabled", true))
+    }
+
     async fn hook(
         &self,
         module::HookContext {
@@ -93,7 +123,20 @@ impl super::Module for Module {
     ) -> Result<(), anyhow::Error> {
         let enabled = settings.var("command/enabled", true).await?;
         let commands = injector.var().await?;
-        handlers.insert("command", Handler { enabled, commands });
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "command",
+            Handler {
+                enabled,
+                commands,
+                messages,
+            },
+        );
         Ok(())
     }
 }
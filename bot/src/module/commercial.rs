@@ -0,0 +1,354 @@
+use crate::api::{self, Obs};
+use crate::auth;
+use crate::command;
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::template::Template;
+use crate::utils::Duration;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use std::time;
+
+/// Default message announcing a commercial break.
+const DEFAULT_MESSAGE: &str =
+    "Taking a {{length}} second commercial break, thanks for supporting the stream!";
+
+/// Usage of the `!commercial` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!commercial [length]",
+    description: "Run a commercial break of the given length in seconds.",
+};
+
+/// Commercial breaks run on the channel of the streamer.
+#[derive(Clone)]
+struct Breaks {
+    twitch: api::Twitch,
+    stream_info: stream_info::StreamInfo,
+    sender: irc::Sender,
+    obs: injector::Var<Option<Obs>>,
+    message: settings::Var<Template>,
+    mute: settings::Var<Vec<String>>,
+    show: settings::Var<Vec<String>>,
+    /// When the last commercial break was started.
+    last: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// Notifies the module of a started break, so that OBS can be restored
+    /// once it's over.
+    started: mpsc::UnboundedSender<u32>,
+}
+
+impl Breaks {
+    /// Start a commercial break of the given length.
+    async fn start(&self, length: u32) -> Result<u32> {
+        let commercial = self
+            .twitch
+            .start_commercial(&self.stream_info.user.id, length)
+            .await?;
+
+        let length = match commercial {
+            Some(commercial) if commercial.length > 0 => commercial.length,
+            Some(commercial) if !commercial.message.is_empty() => {
+                anyhow::bail!("{}", commercial.message)
+            }
+            _ => anyhow::bail!("Twitch didn't start a commercial"),
+        };
+
+        *self.last.lock() = Some(Utc::now());
+
+        let message = self
+            .message
+            .load()
+            .await
+            .render_to_string(&MessageData { length })?;
+
+        if !message.is_empty() {
+            self.sender.privmsg(message).await;
+        }
+
+        self.break_obs(true).await;
+        let _ = self.started.unbounded_send(length);
+        Ok(length)
+    }
+
+    /// Mute and show the configured OBS sources during a break, or restore
+    /// them once it's over.
+    async fn break_obs(&self, active: bool) {
+        let obs = match self.obs.load().await {
+            Some(obs) => obs,
+            None => return,
+        };
+
+        for source in self.mute.load().await {
+            if let Err(e) = obs.set_mute(&source, active).await {
+                log_error!(e, "failed to mute source `{}`", source);
+            }
+        }
+
+        for source in self.show.load().await {
+            if let Err(e) = obs.set_visible(None, &source, active).await {
+                log_error!(e, "failed to change visibility of source `{}`", source);
+            }
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct MessageData {
+    length: u32,
+}
+
+/// Handler for the `!commercial` command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    length: settings::Var<u32>,
+    breaks: Breaks,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Commercial)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let length = match ctx.next() {
+            Some(length) => match str::parse::<u32>(&length) {
+                Ok(length) => length,
+                Err(_) => respond_bail!("{}", message!(self.messages, "commercial/bad-length")),
+            },
+            None => self.length.load().await,
+        };
+
+        if !is_valid_length(length) {
+            respond_bail!("{}", message!(self.messages, "commercial/invalid-length"));
+        }
+
+        if self.breaks.stream_info.started_at().is_none() {
+            respond_bail!("{}", message!(self.messages, "commercial/not-live"));
+        }
+
+        // NB: the failure is shown to the user, so that they see why it failed.
+        let length = match self.breaks.start(length).await {
+            Ok(length) => length,
+            Err(e) => respond_bail!(
+                "{}",
+                message!(self.messages, "commercial/failed", error = e.to_string())
+            ),
+        };
+
+        respond!(
+            ctx,
+            message!(self.messages, "commercial/started", length = length)
+        );
+
+        Ok(())
+    }
+}
+
+/// Test if the given length is one supported by Twitch.
+fn is_valid_length(length: u32) -> bool {
+    (30..=180).contains(&length) && length % 30 == 0
+}
+
+/// Test if an automatic commercial break is due, given when the stream
+/// started and when the last break was run.
+fn is_due(
+    now: DateTime<Utc>,
+    started_at: DateTime<Utc>,
+    last: Option<DateTime<Utc>>,
+    interval: chrono::Duration,
+) -> bool {
+    let since = match last {
+        Some(last) if last > started_at => last,
+        _ => started_at,
+    };
+
+    now.signed_duration_since(since) >= interval
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "commercial"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("commercial/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            futures,
+            settings,
+            injector,
+            stream_info,
+            streamer_twitch,
+            sender,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("commercial");
+
+        let (started, mut started_rx) = mpsc::unbounded();
+
+        let breaks = Breaks {
+            twitch: streamer_twitch.clone(),
+            stream_info: stream_info.clone(),
+            sender: sender.clone(),
+            obs: injector.var().await?,
+            message: settings
+                .var("message", Template::compile(DEFAULT_MESSAGE)?)
+                .await?,
+            mute: settings.var("obs/mute", Vec::new()).await?,
+            show: settings.var("obs/show", Vec::new()).await?,
+            last: Arc::new(Mutex::new(None)),
+            started,
+        };
+
+        let length = settings.var("length", 60).await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "commercial",
+            Handler {
+                enabled: settings.var("enabled", false).await?,
+                length: length.clone(),
+                breaks: breaks.clone(),
+                messages,
+            },
+        );
+
+        let auto_enabled = settings.var("auto/enabled", false).await?;
+
+        let (mut interval_stream, interval) = settings
+            .stream("auto/interval")
+            .or_with_else(|| Duration::hours(1))
+            .await?;
+
+        let mut interval = interval.as_chrono();
+        let mut check = tokio::time::interval(time::Duration::from_secs(60)).fuse();
+
+        let future = async move {
+            let mut restore = Option::<tokio::time::Delay>::None;
+
+            loop {
+                futures::select! {
+                    update = interval_stream.select_next_some() => {
+                        interval = update.as_chrono();
+                    }
+                    length = started_rx.select_next_some() => {
+                        let length = time::Duration::from_secs(u64::from(length));
+                        restore = Some(tokio::time::delay_for(length));
+                    }
+                    _ = restore.current() => {
+                        restore = None;
+                        breaks.break_obs(false).await;
+                    }
+                    _ = check.select_next_some() => {
+                        if !auto_enabled.load().await {
+                            continue;
+                        }
+
+                        let started_at = match breaks.stream_info.started_at() {
+                            Some(started_at) => started_at,
+                            None => continue,
+                        };
+
+                        let last = *breaks.last.lock();
+
+                        if !is_due(Utc::now(), started_at, last, interval) {
+                            continue;
+                        }
+
+                        if let Err(e) = breaks.start(length.load().await).await {
+                            log_error!(e, "failed to start automatic commercial");
+                            // NB: back off so that we don't retry every check.
+                            *breaks.last.lock() = Some(Utc::now());
+                        }
+                    }
+                }
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_due, is_valid_length};
+    use chrono::{Duration, TimeZone as _, Utc};
+
+    #[test]
+    fn test_is_valid_length() {
+        assert!(is_valid_length(30));
+        assert!(is_valid_length(180));
+        assert!(!is_valid_length(0));
+        assert!(!is_valid_length(45));
+        assert!(!is_valid_length(210));
+    }
+
+    #[test]
+    fn test_is_due() {
+        let started_at = Utc.ymd(2020, 1, 1).and_hms(12, 0, 0);
+        let interval = Duration::hours(1);
+
+        assert!(!is_due(
+            started_at + Duration::minutes(59),
+            started_at,
+            None,
+            interval
+        ));
+        assert!(is_due(
+            started_at + Duration::hours(1),
+            started_at,
+            None,
+            interval
+        ));
+
+        let last = Some(started_at + Duration::minutes(30));
+        assert!(!is_due(
+            started_at + Duration::hours(1),
+            started_at,
+            last,
+            interval
+        ));
+        assert!(is_due(
+            started_at + Duration::minutes(90),
+            started_at,
+            last,
+            interval
+        ));
+
+        // a break from a previous stream doesn't count.
+        let last = Some(started_at - Duration::minutes(10));
+        assert!(is_due(
+            started_at + Duration::hours(1),
+            started_at,
+            last,
+            interval
+        ));
+    }
+}
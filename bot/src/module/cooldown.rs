@@ -0,0 +1,221 @@
+use crate::auth;
+use crate::command;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::{anyhow, Result};
+
+/// Usage of the `!cooldown` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!cooldown <status|clear> <command> [user]",
+    description: "Inspect and clear command cooldowns.",
+};
+
+/// Handler for the !cooldown command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Cooldown)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        match ctx.next().as_deref() {
+            Some("status") => {
+                let command = ctx.next_str("<command> [user]")?;
+                let user = ctx.next().map(|u| normalize_user(&u));
+
+                // NB: scopes have cooldowns which apply to everyone.
+                if let Some(scope) = scope_with_cooldown(ctx.cooldowns(), &command).await {
+                    match ctx.cooldowns().scope_remaining(scope).await {
+                        Some(remaining) => respond!(
+                            ctx,
+                            message!(
+                                self.messages,
+                                "cooldown/scope",
+                                scope = scope.to_string(),
+                                remaining = utils::compact_duration(remaining)
+                            )
+                        ),
+                        None => respond!(
+                            ctx,
+                            message!(
+                                self.messages,
+                                "cooldown/scope-none",
+                                scope = scope.to_string()
+                            )
+                        ),
+                    }
+
+                    return Ok(());
+                }
+
+                let command = command.trim_start_matches('!');
+                let remaining = ctx.cooldowns().command_remaining(command).await;
+
+                let remaining = match user {
+                    Some(user) => remaining.into_iter().filter(|(u, _)| *u == user).collect(),
+                    None => remaining,
+                };
+
+                let lines = remaining
+                    .into_iter()
+                    .map(|(user, remaining)| {
+                        format!("{} ({})", user, utils::compact_duration(remaining))
+                    })
+                    .collect::<Vec<_>>();
+
+                if lines.is_empty() {
+                    respond!(
+                        ctx,
+                        message!(self.messages, "cooldown/command-none", command = command)
+                    );
+                } else {
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "cooldown/command",
+                            command = command,
+                            users = lines.join(", ")
+                        )
+                    );
+                }
+            }
+            Some("clear") => {
+                let command = ctx.next_str("<command> [user]")?;
+                let user = ctx.next().map(|u| normalize_user(&u));
+
+                if let Some(scope) = scope_with_cooldown(ctx.cooldowns(), &command).await {
+                    if ctx.cooldowns().clear_scope(scope).await {
+                        respond!(
+                            ctx,
+                            message!(
+                                self.messages,
+                                "cooldown/scope-cleared",
+                                scope = scope.to_string()
+                            )
+                        );
+                    } else {
+                        respond!(
+                            ctx,
+                            message!(
+                                self.messages,
+                                "cooldown/scope-none",
+                                scope = scope.to_string()
+                            )
+                        );
+                    }
+
+                    return Ok(());
+                }
+
+                let command = command.trim_start_matches('!');
+                let cleared = ctx
+                    .cooldowns()
+                    .clear_command(command, user.as_deref())
+                    .await;
+
+                match (cleared, user) {
+                    (0, _) => respond!(
+                        ctx,
+                        message!(self.messages, "cooldown/command-none", command = command)
+                    ),
+                    (_, Some(user)) => respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "cooldown/user-cleared",
+                            command = command,
+                            user = user
+                        )
+                    ),
+                    (n, None) => respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "cooldown/command-cleared",
+                            command = command,
+                            count = n
+                        )
+                    ),
+                }
+            }
+            _ => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the given name as a scope, as long as the scope has a cooldown.
+///
+/// Otherwise it's treated as the name of a command.
+async fn scope_with_cooldown(cooldowns: &command::Cooldowns, name: &str) -> Option<auth::Scope> {
+    let scope = str::parse::<auth::Scope>(name).ok()?;
+
+    if cooldowns.has_scope(scope).await {
+        Some(scope)
+    } else {
+        None
+    }
+}
+
+/// Normalize a user name, like `@SetMod` into `setmod`.
+fn normalize_user(user: &str) -> String {
+    user.trim_start_matches('@').to_lowercase()
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "cooldown"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("cooldown/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "cooldown",
+            Handler {
+                enabled: settings.var("cooldown/enabled", false).await?,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
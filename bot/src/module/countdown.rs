@@ -1,25 +1,85 @@
+use crate::api::Obs;
 use crate::auth;
+use crate::bus;
 use crate::command;
+use crate::countdowns::{self, Countdown, Countdowns};
+use crate::db;
+use crate::irc;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
 use crate::template;
 use crate::utils;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time;
 
-enum Event {
-    /// Set the countdown.
-    Set(utils::Duration, template::Template),
-    /// Clear the countdown.
-    Clear,
-}
+/// Usage of the `!countdown` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!countdown <start <name> <duration> [template]|stop <name>|set <duration> <template>|clear|list>",
+    description: "Run countdowns.",
+};
+
+/// The name of the countdown started with `!countdown set`.
+const DEFAULT_NAME: &str = "default";
+/// The template used for countdowns started without one.
+const DEFAULT_TEMPLATE: &str = "{{name}}: {{remaining}}";
 
 pub struct Handler {
-    sender: mpsc::UnboundedSender<Event>,
     enabled: Arc<RwLock<bool>>,
+    template: settings::Var<template::Template>,
+    countdowns: Countdowns,
+    db: injector::Var<Option<db::Countdowns>>,
+    messages: Messages,
+}
+
+impl Handler {
+    /// Start a countdown, replacing any countdown with the same name.
+    async fn start(
+        &self,
+        channel: &str,
+        name: String,
+        duration: utils::Duration,
+        template: template::Template,
+    ) -> Result<()> {
+        let countdown = Countdown {
+            name,
+            template,
+            duration,
+            ends_at: Utc::now() + duration.as_chrono(),
+        };
+
+        if let Some(db) = self.db.load().await {
+            db.put(db::Countdown {
+                channel: channel.to_string(),
+                name: countdown.name.clone(),
+                template: countdown.template.source().to_string(),
+                duration: duration.num_seconds() as i64,
+                ends_at: countdown.ends_at.naive_utc(),
+            })
+            .await?;
+        }
+
+        self.countdowns.set(countdown);
+        Ok(())
+    }
+
+    /// Stop the countdown with the given name.
+    ///
+    /// Returns `false` if no such countdown is running.
+    async fn stop(&self, channel: &str, name: &str) -> Result<bool> {
+        let stopped = self.countdowns.remove(name).is_some();
+
+        if let Some(db) = self.db.load().await {
+            db.delete(channel, name).await?;
+        }
+
+        Ok(stopped)
+    }
 }
 
 #[async_trait]
@@ -28,41 +88,80 @@ impl command::Handler for Handler {
         Some(auth::Scope::Countdown)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
         if !*self.enabled.read() {
             return Ok(());
         }
 
         match ctx.next().as_deref() {
+            Some("start") => {
+                let name = ctx.next_str("<name> <duration> [template]")?.to_lowercase();
+                let duration = ctx.next_parse("<name> <duration> [template]")?;
+
+                let template = if ctx.rest().trim().is_empty() {
+                    self.template.load().await
+                } else {
+                    ctx.rest_parse("<name> <duration> [template]")?
+                };
+
+                self.start(ctx.channel(), name.clone(), duration, template)
+                    .await?;
+
+                respond!(
+                    ctx,
+                    message!(self.messages, "countdown/started", name = name)
+                );
+            }
+            Some("stop") => {
+                let name = ctx.next_str("<name>")?.to_lowercase();
+
+                if !self.stop(ctx.channel(), &name).await? {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "countdown/not-running", name = name)
+                    );
+                }
+
+                respond!(
+                    ctx,
+                    message!(self.messages, "countdown/stopped", name = name)
+                );
+            }
             Some("set") => {
                 let duration = ctx.next_parse("<duration> <template>")?;
                 let template = ctx.rest_parse("<duration> <template>")?;
 
-                match self.sender.unbounded_send(Event::Set(duration, template)) {
-                    Ok(()) => {
-                        respond!(ctx, "Countdown set!");
-                    }
-                    Err(_) => {
-                        respond!(ctx, "Could not set countdown :(");
-                        return Ok(());
-                    }
-                }
+                self.start(ctx.channel(), DEFAULT_NAME.to_string(), duration, template)
+                    .await?;
+
+                respond!(ctx, message!(self.messages, "countdown/set"));
             }
-            Some("clear") => match self.sender.unbounded_send(Event::Clear) {
-                Ok(()) => {
-                    respond!(ctx, "Countdown cleared!");
+            Some("clear") => {
+                for countdown in self.countdowns.clear() {
+                    self.stop(ctx.channel(), &countdown.name).await?;
                 }
-                Err(_) => {
-                    respond!(ctx, "Could not clear countdown :(");
-                    return Ok(());
-                }
-            },
+
+                respond!(ctx, message!(self.messages, "countdown/cleared"));
+            }
+            Some("list") => {
+                let now = Utc::now();
+
+                let countdowns = self
+                    .countdowns
+                    .list()
+                    .into_iter()
+                    .map(|c| format!("{} ({})", c.name, c.remaining(now).as_digital()))
+                    .collect::<Vec<_>>();
+
+                ctx.respond_lines(countdowns, &message!(self.messages, "countdown/list-empty"))
+                    .await;
+            }
             _ => {
-                respond!(
-                    ctx,
-                    "Expected: !countdown set <duration> <template>, or !countdown clear"
-                );
-                return Ok(());
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
             }
         }
 
@@ -85,6 +184,9 @@ impl super::Module for Module {
             handlers,
             futures,
             settings,
+            injector,
+            sender,
+            global_bus,
             ..
         }: module::HookContext<'_>,
     ) -> Result<(), anyhow::Error> {
@@ -95,63 +197,64 @@ impl super::Module for Module {
 
         let (mut path_stream, path) = settings.stream::<PathBuf>("path").optional().await?;
 
-        let mut writer = FileWriter::default();
-        writer.path = path;
+        let countdowns = injector.get::<Countdowns>().await.unwrap_or_default();
+
+        let db = injector.var::<db::Countdowns>().await?;
 
-        let (sender, mut receiver) = mpsc::unbounded();
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
 
         handlers.insert(
             "countdown",
             Handler {
-                sender,
                 enabled: enabled.clone(),
+                template: settings
+                    .var("template", template::Template::compile(DEFAULT_TEMPLATE)?)
+                    .await?,
+                countdowns: countdowns.clone(),
+                db: db.clone(),
+                messages: messages.clone(),
             },
         );
 
+        let mut runner = Runner {
+            channel: sender.channel().to_string(),
+            sender: sender.clone(),
+            global_bus: global_bus.clone(),
+            countdowns,
+            db,
+            checkpoints: settings.var("checkpoints", Vec::new()).await?,
+            scene: settings.optional("scene").await?,
+            obs: injector.var().await?,
+            writer: FileWriter { path },
+            messages,
+            running: false,
+        };
+
         let future = async move {
-            let mut timer = Option::<Timer>::None;
+            runner.restore().await;
+
+            let mut interval = tokio::time::interval(time::Duration::from_secs(1)).fuse();
+            let mut last = Utc::now();
 
             loop {
                 futures::select! {
                     update = path_stream.select_next_some() => {
-                        writer.path = update;
+                        runner.writer.path = update;
                     }
                     update = enabled_stream.select_next_some() => {
-                        if (!update) {
-                            timer.take();
-                            writer.clear_log();
+                        if !update {
+                            runner.clear().await;
                         }
 
                         *enabled.write() = update;
                     }
-                    out = timer.next() => {
-                        match out {
-                            Some(()) => if let Some(timer) = timer.as_ref() {
-                                writer.write_log(timer);
-                            },
-                            None => {
-                                writer.clear_log();
-                            },
-                        }
-                    },
-                    event = receiver.select_next_some() => {
-                        match event {
-                            Event::Set(duration, template) => {
-                                let t = Timer {
-                                    duration,
-                                    elapsed: Default::default(),
-                                    interval: tokio::time::interval(time::Duration::from_secs(1)),
-                                };
-
-                                writer.template = Some(template);
-                                writer.write_log(&t);
-                                timer = Some(t);
-                            }
-                            Event::Clear => {
-                                timer.take();
-                                writer.clear_log();
-                            }
-                        }
+                    _ = interval.select_next_some() => {
+                        let now = Utc::now();
+                        runner.tick(last, now).await;
+                        last = now;
                     }
                 }
             }
@@ -162,49 +265,200 @@ impl super::Module for Module {
     }
 }
 
-#[derive(Default)]
+/// Drives running countdowns and everything which displays them.
+struct Runner {
+    channel: String,
+    sender: irc::Sender,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+    countdowns: Countdowns,
+    db: injector::Var<Option<db::Countdowns>>,
+    checkpoints: settings::Var<Vec<utils::Duration>>,
+    scene: settings::Var<Option<String>>,
+    obs: injector::Var<Option<Obs>>,
+    writer: FileWriter,
+    messages: Messages,
+    /// If any countdowns were running at the last tick.
+    running: bool,
+}
+
+impl Runner {
+    /// Restore countdowns which were running when the bot was stopped.
+    async fn restore(&self) {
+        let db = match self.db.load().await {
+            Some(db) => db,
+            None => return,
+        };
+
+        let stored = match db.list(&self.channel).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                log_error!(e, "failed to load countdowns");
+                return;
+            }
+        };
+
+        let now = Utc::now();
+
+        for c in stored {
+            let ends_at = DateTime::<Utc>::from_utc(c.ends_at, Utc);
+
+            // NB: countdowns which ended while the bot was stopped are
+            // silently dropped.
+            if ends_at <= now {
+                self.delete(&db, &c.name).await;
+                continue;
+            }
+
+            let template = match template::Template::compile(&c.template) {
+                Ok(template) => template,
+                Err(e) => {
+                    log_error!(e, "bad template for countdown `{}`", c.name);
+                    continue;
+                }
+            };
+
+            self.countdowns.set(Countdown {
+                name: c.name,
+                template,
+                duration: utils::Duration::seconds(c.duration.max(0) as u64),
+                ends_at,
+            });
+        }
+    }
+
+    /// Update countdowns from the last tick up until now.
+    async fn tick(&mut self, last: DateTime<Utc>, now: DateTime<Utc>) {
+        let checkpoints = self.checkpoints.load().await;
+
+        for c in self.countdowns.list() {
+            let checkpoint = match crossed(&checkpoints, c.remaining(last), c.remaining(now)) {
+                Some(checkpoint) => checkpoint,
+                None => continue,
+            };
+
+            let message = if checkpoint.is_empty() {
+                message!(self.messages, "countdown/over", name = c.name)
+            } else {
+                message!(
+                    self.messages,
+                    "countdown/checkpoint",
+                    name = c.name,
+                    remaining = checkpoint.to_string()
+                )
+            };
+
+            self.sender.privmsg(message).await;
+        }
+
+        let ended = self.countdowns.remove_ended(now);
+
+        if !ended.is_empty() {
+            if let Some(db) = self.db.load().await {
+                for c in &ended {
+                    self.delete(&db, &c.name).await;
+                }
+            }
+
+            switch_scene(&self.scene, &self.obs).await;
+        }
+
+        self.update(now).await;
+    }
+
+    /// Stop all countdowns.
+    async fn clear(&mut self) {
+        let cleared = self.countdowns.clear();
+
+        if let Some(db) = self.db.load().await {
+            for c in &cleared {
+                self.delete(&db, &c.name).await;
+            }
+        }
+
+        self.update(Utc::now()).await;
+    }
+
+    /// Write the current state of all countdowns to the file and overlays.
+    async fn update(&mut self, now: DateTime<Utc>) {
+        let statuses = self.countdowns.statuses(now);
+
+        if statuses.is_empty() {
+            if !self.running {
+                return;
+            }
+
+            self.writer.clear_log();
+        } else {
+            self.writer.write_log(&statuses);
+        }
+
+        self.running = !statuses.is_empty();
+
+        self.global_bus
+            .send(bus::Global::Countdowns {
+                countdowns: statuses,
+            })
+            .await;
+    }
+
+    /// Delete a stored countdown, logging on errors.
+    async fn delete(&self, db: &db::Countdowns, name: &str) {
+        if let Err(e) = db.delete(&self.channel, name).await {
+            log_error!(e, "failed to delete countdown `{}`", name);
+        }
+    }
+}
+
+/// The checkpoint which was passed when the remaining time of a countdown
+/// went from `before` to `remaining`, if any.
+///
+/// If several checkpoints were passed, the one closest to the end is used.
+fn crossed(
+    checkpoints: &[utils::Duration],
+    before: utils::Duration,
+    remaining: utils::Duration,
+) -> Option<utils::Duration> {
+    checkpoints
+        .iter()
+        .copied()
+        .filter(|c| *c < before && *c >= remaining)
+        .min()
+}
+
+/// Switch to the configured scene in OBS, if any.
+async fn switch_scene(scene: &settings::Var<Option<String>>, obs: &injector::Var<Option<Obs>>) {
+    let (scene, obs) = match (scene.load().await, obs.load().await) {
+        (Some(scene), Some(obs)) => (scene, obs),
+        _ => return,
+    };
+
+    if let Err(e) = obs.set_current_scene(&scene).await {
+        log_error!(e, "failed to switch to scene `{}`", scene);
+    }
+}
+
 struct FileWriter {
     path: Option<PathBuf>,
-    template: Option<template::Template>,
 }
 
 impl FileWriter {
-    fn write(&self, timer: &Timer) -> Result<(), anyhow::Error> {
+    /// Write the rendered countdowns, one per line.
+    fn write(&self, statuses: &[countdowns::Status]) -> Result<(), anyhow::Error> {
         let path = match &self.path {
             Some(path) => path,
             None => return Ok(()),
         };
 
-        let template = match &self.template {
-            Some(template) => template,
-            None => return Ok(()),
-        };
-
         log::trace!("Writing to log: {}", path.display());
 
-        let mut f = fs::File::create(path)?;
-        let remaining = timer.duration.saturating_sub(timer.elapsed.clone());
-        let remaining = remaining.as_digital();
-        let elapsed = timer.elapsed.as_digital();
-        let duration = timer.duration.as_digital();
-
-        template.render(
-            &mut f,
-            Data {
-                remaining,
-                elapsed,
-                duration,
-            },
-        )?;
+        let text = statuses
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
 
-        return Ok(());
-
-        #[derive(serde::Serialize)]
-        struct Data {
-            remaining: String,
-            elapsed: String,
-            duration: String,
-        }
+        fs::write(path, text)?;
+        Ok(())
     }
 
     fn clear(&self) -> Result<(), anyhow::Error> {
@@ -224,8 +478,8 @@ impl FileWriter {
     }
 
     /// Attempt to write an update and log on errors.
-    fn write_log(&self, timer: &Timer) {
-        if let Err(e) = self.write(timer) {
+    fn write_log(&self, statuses: &[countdowns::Status]) {
+        if let Err(e) = self.write(statuses) {
             log_error!(e, "failed to write");
         }
     }
@@ -238,32 +492,28 @@ impl FileWriter {
     }
 }
 
-struct Timer {
-    duration: utils::Duration,
-    elapsed: utils::Duration,
-    interval: tokio::time::Interval,
-}
-
-impl Stream for Timer {
-    type Item = ();
-
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        if let Poll::Ready(Some(_)) = Pin::new(&mut self.interval).poll_next(cx) {
-            self.as_mut().elapsed += utils::Duration::seconds(1);
-
-            if self.as_ref().elapsed >= self.as_ref().duration {
-                return Poll::Ready(None);
-            }
-
-            return Poll::Ready(Some(()));
-        }
-
-        Poll::Pending
-    }
-}
-
-impl stream::FusedStream for Timer {
-    fn is_terminated(&self) -> bool {
-        self.elapsed >= self.duration
+#[cfg(test)]
+mod tests {
+    use super::crossed;
+    use crate::utils::Duration;
+
+    #[test]
+    fn test_crossed() {
+        let checkpoints = vec![
+            Duration::seconds(300),
+            Duration::seconds(60),
+            Duration::seconds(0),
+        ];
+
+        let s = Duration::seconds;
+
+        assert_eq!(Some(s(300)), crossed(&checkpoints, s(301), s(300)));
+        assert_eq!(None, crossed(&checkpoints, s(300), s(299)));
+        assert_eq!(Some(s(60)), crossed(&checkpoints, s(61), s(59)));
+        assert_eq!(Some(s(0)), crossed(&checkpoints, s(1), s(0)));
+        // a countdown which just started at a checkpoint doesn't announce it.
+        assert_eq!(None, crossed(&checkpoints, s(300), s(300)));
+        // closest to the end wins if the bot fell behind.
+        assert_eq!(Some(s(60)), crossed(&checkpoints, s(400), s(30)));
     }
 }
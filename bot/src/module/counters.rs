@@ -0,0 +1,351 @@
+//! Named counters, like deaths or wins.
+//!
+//! Counters are created with `!counter create deaths`, after which `!deaths`
+//! shows the current count, `!deaths+` and `!deaths-` change it, and
+//! `!deaths reset` resets it. Custom commands can show the value of a counter
+//! with `{{counters.deaths}}`.
+
+use crate::auth::Scope;
+use crate::command;
+use crate::db;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+
+/// Usage of the `!counter` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!counter <list|create <name>|delete <name>|set <name> <count>|history <name>>",
+    description: "Manage counters, like deaths or wins.",
+};
+
+/// Number of changes listed by `!counter history`.
+const HISTORY_LIMIT: i64 = 5;
+
+/// Handler for the `!counter` command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    counters: injector::Var<Option<db::Counters>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<Scope> {
+        Some(Scope::Counter)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let counters = match self.counters.load().await {
+            Some(counters) => counters,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            Some("list") => {
+                let list = counters.list(ctx.channel()).await?;
+
+                let list = list
+                    .into_iter()
+                    .map(|c| format!("{} ({})", c.name, c.count))
+                    .collect::<Vec<_>>();
+
+                match utils::human_list(&list) {
+                    Some(list) => respond!(
+                        ctx,
+                        message!(self.messages, "counters/list", counters = list)
+                    ),
+                    None => respond!(ctx, message!(self.messages, "counters/list-empty")),
+                }
+            }
+            Some("create") => {
+                ctx.check_scope(Scope::CounterEdit).await?;
+
+                let name = ctx.next_str("<name>")?.to_lowercase();
+
+                if !is_valid_name(&name) {
+                    respond_bail!("{}", message!(self.messages, "counters/bad-name"));
+                }
+
+                if ctx.handlers().get(&name).is_some() {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "counters/command-conflict", name = name)
+                    );
+                }
+
+                if !counters.create(ctx.channel(), &name).await? {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "counters/conflict", name = name)
+                    );
+                }
+
+                respond!(
+                    ctx,
+                    message!(self.messages, "counters/created", name = name)
+                );
+            }
+            Some("delete") => {
+                ctx.check_scope(Scope::CounterEdit).await?;
+
+                let name = ctx.next_str("<name>")?;
+
+                if !counters.delete(ctx.channel(), &name).await? {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "counters/missing", name = name)
+                    );
+                }
+
+                respond!(
+                    ctx,
+                    message!(self.messages, "counters/deleted", name = name)
+                );
+            }
+            Some("set") => {
+                ctx.check_scope(Scope::CounterEdit).await?;
+
+                let name = ctx.next_str("<name> <count>")?;
+                let count = ctx.next_parse::<i64, _>("<name> <count>")?;
+
+                match counters
+                    .set(ctx.channel(), &name, count, ctx.user.name())
+                    .await?
+                {
+                    Some(count) => respond!(ctx, "{}: {}", name, count),
+                    None => respond!(
+                        ctx,
+                        message!(self.messages, "counters/missing", name = name)
+                    ),
+                }
+            }
+            Some("history") => {
+                let name = ctx.next_str("<name>")?;
+
+                if counters.get(ctx.channel(), &name).await.is_none() {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "counters/missing", name = name)
+                    );
+                }
+
+                let history = counters
+                    .history(ctx.channel(), &name, HISTORY_LIMIT)
+                    .await?;
+
+                if history.is_empty() {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "counters/no-history", name = name)
+                    );
+                }
+
+                let now = Utc::now().naive_utc();
+
+                let history = history
+                    .into_iter()
+                    .map(|change| {
+                        let ago = (now - change.changed_at).to_std().unwrap_or_default();
+                        let ago = utils::compact_duration(ago);
+
+                        message!(
+                            self.messages,
+                            "counters/change",
+                            count = change.count,
+                            ago = ago,
+                            user = change.user,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                respond!(ctx, "{}: {}", name, history.join(", "));
+            }
+            _ => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Handler for a single counter, like `!deaths` or `!deaths+`.
+struct Counter {
+    counters: db::Counters,
+    name: String,
+    /// The direction to change the counter in, if it's being changed.
+    change: Option<i64>,
+}
+
+#[async_trait]
+impl command::Handler for Counter {
+    fn scope(&self) -> Option<Scope> {
+        match self.change {
+            Some(..) => Some(Scope::CounterEdit),
+            None => Some(Scope::Counter),
+        }
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        match self.change {
+            Some(..) => Some(command::Usage {
+                usage: "!<counter>+ [amount] or !<counter>- [amount]",
+                description: "Change a counter by the given amount, or by one.",
+            }),
+            None => Some(command::Usage {
+                usage: "!<counter> [reset]",
+                description: "Show the value of a counter, or reset it.",
+            }),
+        }
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        let count = match self.change {
+            Some(sign) => {
+                let amount = ctx.next_parse_optional::<i64>()?.unwrap_or(1);
+
+                self.counters
+                    .add(ctx.channel(), &self.name, sign * amount, ctx.user.name())
+                    .await?
+            }
+            None => match ctx.next().as_deref() {
+                Some("reset") => {
+                    ctx.check_scope(Scope::CounterEdit).await?;
+
+                    self.counters
+                        .set(ctx.channel(), &self.name, 0, ctx.user.name())
+                        .await?
+                }
+                _ => self.counters.get(ctx.channel(), &self.name).await,
+            },
+        };
+
+        if let Some(count) = count {
+            respond!(ctx, "{}: {}", self.name, count);
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves commands for counters.
+struct Resolver {
+    enabled: settings::Var<bool>,
+    counters: injector::Var<Option<db::Counters>>,
+}
+
+#[async_trait]
+impl module::Resolver for Resolver {
+    async fn resolve(&self, channel: &str, command: &str) -> Option<Arc<dyn command::Handler>> {
+        if !self.enabled.load().await {
+            return None;
+        }
+
+        let counters = self.counters.load().await?;
+        let (name, change) = parse_command(command);
+
+        // NB: only resolve counters which exist.
+        counters.get(channel, name).await?;
+
+        Some(Arc::new(Counter {
+            counters,
+            name: name.to_lowercase(),
+            change,
+        }))
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "counters"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("counters/enabled", false))
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let enabled = settings.var("counters/enabled", false).await?;
+        let counters = injector.var().await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "counter",
+            Handler {
+                enabled: enabled.clone(),
+                counters: counters.clone(),
+                messages,
+            },
+        );
+
+        handlers.insert_resolver(Resolver { enabled, counters });
+        Ok(())
+    }
+}
+
+/// Test if the given name can be used for a counter.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Parse a counter command, like `deaths+`, into the name of the counter and
+/// the direction it should be changed in.
+fn parse_command(command: &str) -> (&str, Option<i64>) {
+    if let Some(name) = command.strip_suffix('+') {
+        return (name, Some(1));
+    }
+
+    if let Some(name) = command.strip_suffix('-') {
+        return (name, Some(-1));
+    }
+
+    (command, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_valid_name, parse_command};
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(("deaths", Some(1)), parse_command("deaths+"));
+        assert_eq!(("deaths", Some(-1)), parse_command("deaths-"));
+        assert_eq!(("deaths", None), parse_command("deaths"));
+        assert_eq!(("", Some(1)), parse_command("+"));
+    }
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(is_valid_name("deaths"));
+        assert!(is_valid_name("boss_wins2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("deaths+"));
+        assert!(!is_valid_name("boss-wins"));
+    }
+}
@@ -0,0 +1,430 @@
+//! Donations made through Streamlabs or StreamElements.
+//!
+//! Both services are polled for new donations, which are broadcast as alerts
+//! on the global bus, stored, and thanked for in chat if configured. Donors
+//! can optionally be rewarded with stream currency.
+
+use crate::api;
+use crate::auth;
+use crate::bus;
+use crate::command;
+use crate::currency::Currency;
+use crate::db;
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::template::Template;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::time::Duration;
+
+/// How often to check for new donations.
+const DONATIONS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Number of donations to fetch every time we check for new ones.
+const DONATIONS_PAGE: u32 = 20;
+
+/// Number of donors listed by `!topdonor` for each currency.
+const TOP_DONORS: usize = 3;
+
+/// Data available when rendering thank-you messages.
+#[derive(serde::Serialize)]
+struct ThankYouData<'a> {
+    user: &'a str,
+    amount: &'a str,
+    message: Option<&'a str>,
+}
+
+/// A donation, regardless of which service it was made through.
+#[derive(Debug, Clone)]
+struct Donation {
+    provider: &'static str,
+    user: String,
+    amount: f64,
+    currency: String,
+    message: Option<String>,
+}
+
+impl Donation {
+    /// The amount donated, including the currency.
+    fn display_amount(&self) -> String {
+        format!("{:.2} {}", self.amount, self.currency)
+    }
+
+    /// The currency reward for the donation, if it was made in the currency
+    /// which rewards are given for.
+    fn reward(&self, per_unit: u32, currency: &str) -> Option<i64> {
+        if !self.currency.eq_ignore_ascii_case(currency) {
+            return None;
+        }
+
+        Some((self.amount * per_unit as f64).floor() as i64)
+    }
+}
+
+/// Which donations we've already seen for each service.
+#[derive(Default)]
+struct Seen {
+    /// The id of the last seen Streamlabs donation.
+    streamlabs: Option<u64>,
+    /// The id of the StreamElements channel, and the last seen tip.
+    streamelements: Option<(String, DateTime<Utc>)>,
+}
+
+/// When the current stream started, if we are live.
+fn stream_started_at(stream_info: &stream_info::StreamInfo) -> Option<NaiveDateTime> {
+    let data = stream_info.data.read();
+    data.stream.as_ref().map(|s| s.started_at.naive_utc())
+}
+
+/// Format donation totals for chat.
+fn format_totals(totals: &[db::DonationTotal]) -> String {
+    let mut out = Vec::new();
+
+    for total in totals {
+        let amount = format!("{:.2} {}", total.amount, total.currency);
+
+        match &total.user {
+            Some(user) => out.push(format!("{} ({})", user, amount)),
+            None => out.push(amount),
+        }
+    }
+
+    out.join(", ")
+}
+
+/// Handler for the `!topdonor` command.
+pub struct TopDonor {
+    enabled: settings::Var<bool>,
+    donations: injector::Var<Option<db::Donations>>,
+    stream_info: stream_info::StreamInfo,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for TopDonor {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::TopDonor)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!topdonor",
+            description: "Show who has donated the most, this stream and of all time.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let donations = match self.donations.load().await {
+            Some(donations) => donations,
+            None => respond_bail!("{}", message!(self.messages, "donations/not-configured")),
+        };
+
+        let all_time = donations.top(None, TOP_DONORS).await?;
+
+        if all_time.is_empty() {
+            respond!(ctx, message!(self.messages, "donations/none"));
+            return Ok(());
+        }
+
+        let stream = match stream_started_at(&self.stream_info) {
+            Some(started_at) => donations.top(Some(started_at), TOP_DONORS).await?,
+            None => Vec::new(),
+        };
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "donations/top",
+                stream = format_totals(&stream),
+                all_time = format_totals(&all_time),
+            )
+        );
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "donations"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("donations/enabled", false))
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            futures,
+            stream_info,
+            sender,
+            settings,
+            global_bus,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("donations");
+        let enabled = settings.var("enabled", false).await?;
+        let currency_per_unit = settings.var("currency-per-unit", 0u32).await?;
+        let reward_currency = settings.var("reward-currency", String::from("USD")).await?;
+        let thank_you = settings.optional::<Template>("thank-you-message").await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "topdonor",
+            TopDonor {
+                enabled: enabled.clone(),
+                donations: injector.var().await?,
+                stream_info: stream_info.clone(),
+                messages,
+            },
+        );
+
+        let streamlabs = injector.var::<api::Streamlabs>().await?;
+        let streamelements = injector.var::<api::StreamElements>().await?;
+        let currency = injector.var::<Currency>().await?;
+        let storage = injector.var::<db::Donations>().await?;
+        let channel = format!("#{}", stream_info.user.name);
+        let stream_info = stream_info.clone();
+        let sender = sender.clone();
+        let global_bus = global_bus.clone();
+
+        let future = async move {
+            let mut interval = tokio::time::interval(DONATIONS_INTERVAL);
+            let mut seen = Seen::default();
+
+            loop {
+                interval.tick().await;
+
+                if !enabled.load().await {
+                    continue;
+                }
+
+                let mut donations = Vec::new();
+
+                if let Some(streamlabs) = streamlabs.load().await {
+                    match poll_streamlabs(&streamlabs, &mut seen.streamlabs).await {
+                        Ok(new) => donations.extend(new),
+                        Err(e) => log_warn!(e, "failed to check for Streamlabs donations"),
+                    }
+                }
+
+                if let Some(streamelements) = streamelements.load().await {
+                    match poll_streamelements(&streamelements, &mut seen.streamelements).await {
+                        Ok(new) => donations.extend(new),
+                        Err(e) => {
+                            log_warn!(e, "failed to check for StreamElements tips");
+                            // NB: look up the channel again in case it changed.
+                            seen.streamelements = None;
+                        }
+                    }
+                }
+
+                let per_unit = currency_per_unit.load().await;
+                let reward_currency = reward_currency.load().await;
+                let currency = currency.load().await;
+                let storage = storage.load().await;
+                let thank_you = thank_you.load().await;
+
+                for donation in donations {
+                    let amount = donation.display_amount();
+
+                    if let Some(storage) = storage.as_ref() {
+                        let d = db::AddDonation {
+                            provider: donation.provider.to_string(),
+                            user: donation.user.clone(),
+                            amount: donation.amount,
+                            currency: donation.currency.clone(),
+                            message: donation.message.clone(),
+                            stream_started_at: stream_started_at(&stream_info),
+                        };
+
+                        if let Err(e) = storage.push(d).await {
+                            log_error!(e, "failed to store donation");
+                        }
+                    }
+
+                    if let Some(template) = thank_you.as_ref() {
+                        thank(&sender, template, &donation, &amount).await;
+                    }
+
+                    if per_unit > 0 {
+                        if let Some(currency) = currency.as_ref() {
+                            match donation.reward(per_unit, &reward_currency) {
+                                Some(reward) => {
+                                    let user = donation.user.to_lowercase();
+
+                                    if let Err(e) =
+                                        currency.balance_add(&channel, &user, reward).await
+                                    {
+                                        log_error!(
+                                            e,
+                                            "failed to reward donation from {}",
+                                            donation.user
+                                        );
+                                    }
+                                }
+                                None => {
+                                    log::warn!(
+                                        "not rewarding donation from {} made in {}, \
+                                         only donations in {} are rewarded",
+                                        donation.user,
+                                        donation.currency,
+                                        reward_currency
+                                    );
+                                }
+                            }
+                        }
+                    }
+
+                    global_bus
+                        .send(bus::Global::Alert {
+                            kind: bus::AlertKind::Donation,
+                            amount: Some(amount),
+                            user: donation.user,
+                            months: None,
+                            streak: None,
+                            recipient: None,
+                            gifts: None,
+                            viewers: None,
+                            bits: None,
+                            message: donation.message,
+                        })
+                        .await;
+                }
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
+
+/// Check for new Streamlabs donations.
+///
+/// The first check only establishes which donations we already know of, so
+/// that we don't raise alerts for donations made before the bot started.
+async fn poll_streamlabs(
+    streamlabs: &api::Streamlabs,
+    last: &mut Option<u64>,
+) -> Result<Vec<Donation>> {
+    let mut donations = match *last {
+        Some(id) => streamlabs.donations(Some(id), DONATIONS_PAGE).await?,
+        None => {
+            let donations = streamlabs.donations(None, 1).await?;
+            *last = Some(donations.first().map(|d| d.donation_id).unwrap_or_default());
+            return Ok(Vec::new());
+        }
+    };
+
+    // NB: oldest first, so that alerts are raised in the order people donated.
+    donations.sort_by_key(|d| d.donation_id);
+
+    if let Some(d) = donations.last() {
+        *last = Some(d.donation_id);
+    }
+
+    Ok(donations
+        .into_iter()
+        .map(|d| Donation {
+            provider: "streamlabs",
+            user: d.name,
+            amount: d.amount,
+            currency: d.currency,
+            message: d.message,
+        })
+        .collect())
+}
+
+/// Check for new StreamElements tips.
+///
+/// Like with Streamlabs, the first check only establishes the latest tip.
+async fn poll_streamelements(
+    streamelements: &api::StreamElements,
+    seen: &mut Option<(String, DateTime<Utc>)>,
+) -> Result<Vec<Donation>> {
+    let (channel_id, last) = match seen {
+        Some((channel_id, last)) => (channel_id, last),
+        None => {
+            let channel = streamelements.channel().await?;
+            let tips = streamelements.tips(&channel.id, 1).await?;
+            let last = tips.first().map(|t| t.created_at).unwrap_or_else(Utc::now);
+            *seen = Some((channel.id, last));
+            return Ok(Vec::new());
+        }
+    };
+
+    let mut tips = streamelements.tips(channel_id, DONATIONS_PAGE).await?;
+    tips.retain(|t| t.created_at > *last);
+    // NB: oldest first, so that alerts are raised in the order people donated.
+    tips.sort_by_key(|t| t.created_at);
+
+    if let Some(t) = tips.last() {
+        *last = t.created_at;
+    }
+
+    Ok(tips
+        .into_iter()
+        .map(|t| Donation {
+            provider: "streamelements",
+            user: t.donation.user.username,
+            amount: t.donation.amount,
+            currency: t.donation.currency,
+            message: t.donation.message,
+        })
+        .collect())
+}
+
+/// Send a thank-you message to chat.
+async fn thank(sender: &irc::Sender, template: &Template, donation: &Donation, amount: &str) {
+    let data = ThankYouData {
+        user: &donation.user,
+        amount,
+        message: donation.message.as_deref(),
+    };
+
+    match template.render_to_string(&data) {
+        Ok(message) => sender.privmsg(message).await,
+        Err(e) => log_error!(e, "failed to render donation thank-you message"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Donation;
+
+    #[test]
+    fn test_donation() {
+        let donation = Donation {
+            provider: "streamlabs",
+            user: String::from("setbac"),
+            amount: 4.5,
+            currency: String::from("USD"),
+            message: None,
+        };
+
+        assert_eq!("4.50 USD", donation.display_amount());
+        assert_eq!(Some(0), donation.reward(0, "USD"));
+        assert_eq!(Some(45), donation.reward(10, "USD"));
+        assert_eq!(Some(4), donation.reward(1, "usd"));
+        assert_eq!(None, donation.reward(10, "SEK"));
+    }
+}
@@ -1,60 +1,239 @@
 use crate::auth;
 use crate::command;
+use crate::db;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
+use crate::template::Template;
+use crate::utils;
+use anyhow::{anyhow, Result};
 
+/// Usage of the `!8ball` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!8ball <question>",
+    description: "Ask the magic 8 ball a question.",
+};
+
+/// Usage of the `!8ball` command for users who can edit answers.
+const EDIT_USAGE: &str = "!8ball <add [--weight <n>] <answer>|remove <id>|list>";
+
+/// Messages of the built-in answers, used until custom answers are added.
 static MAGIC_8BALL_ANSWER: &[&str] = &[
-    "It is certain.",
-    "It is decidedly so.",
-    "Without a doubt.",
-    "Yes - definitely.",
-    "You may rely on it.",
-    "As I see it, yes.",
-    "Most likely.",
-    "Outlook good.",
-    "Yes.",
-    "Signs point to yes.",
-    "Reply hazy, try again.",
-    "Ask again later.",
-    "Better not tell you now.",
-    "Cannot predict now.",
-    "Concentrate and ask again.",
-    "Don't count on it.",
-    "My reply is no.",
-    "My sources say no.",
-    "Outlook not so good.",
-    "Very doubtful.",
+    "8ball/answer/certain",
+    "8ball/answer/decidedly-so",
+    "8ball/answer/without-a-doubt",
+    "8ball/answer/definitely",
+    "8ball/answer/rely-on-it",
+    "8ball/answer/as-i-see-it",
+    "8ball/answer/most-likely",
+    "8ball/answer/outlook-good",
+    "8ball/answer/yes",
+    "8ball/answer/signs-point-to-yes",
+    "8ball/answer/reply-hazy",
+    "8ball/answer/ask-again-later",
+    "8ball/answer/better-not-tell",
+    "8ball/answer/cannot-predict",
+    "8ball/answer/concentrate",
+    "8ball/answer/dont-count-on-it",
+    "8ball/answer/reply-no",
+    "8ball/answer/sources-say-no",
+    "8ball/answer/outlook-not-so-good",
+    "8ball/answer/very-doubtful",
 ];
 
 /// Handler for the !8ball command.
 pub struct EightBall {
     enabled: settings::Var<bool>,
+    template: settings::Var<Option<Template>>,
+    answers: injector::Var<Option<db::EightBallAnswers>>,
+    messages: Messages,
 }
 
-#[async_trait]
-impl command::Handler for EightBall {
-    fn scope(&self) -> Option<auth::Scope> {
-        Some(auth::Scope::EightBall)
+impl EightBall {
+    /// Access answer storage.
+    async fn answers(&self) -> Result<db::EightBallAnswers> {
+        match self.answers.load().await {
+            Some(answers) => Ok(answers),
+            None => respond_bail!("{}", message!(self.messages, "8ball/not-available")),
+        }
     }
 
-    async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
+    /// Answer a question.
+    async fn answer(&self, ctx: &mut command::Context) -> Result<()> {
         use rand::Rng as _;
 
-        if !self.enabled.load().await {
+        let question = ctx.rest().trim().to_string();
+
+        if question.is_empty() {
+            respond!(ctx, message!(self.messages, "8ball/no-question"));
             return Ok(());
         }
 
-        let rest = ctx.rest();
+        let custom = match self.answers.load().await {
+            Some(answers) => answers.list(ctx.channel()).await?,
+            None => Vec::new(),
+        };
+
+        // NB: the built-in answers are used until custom answers are added.
+        let answer = if custom.is_empty() {
+            let index = rand::thread_rng().gen_range(0, MAGIC_8BALL_ANSWER.len());
+            message!(self.messages, MAGIC_8BALL_ANSWER[index])
+        } else {
+            let weights = custom
+                .iter()
+                .map(|a| (a.weight.max(0) as u32).min(db::EightBallAnswers::MAX_WEIGHT))
+                .collect::<Vec<_>>();
+
+            let index = match utils::weighted_pick(&weights) {
+                Some(index) => index,
+                None => return Ok(()),
+            };
+
+            custom[index].answer.clone()
+        };
+
+        let name = ctx.user.display_name().unwrap_or("someone").to_string();
+
+        let data = Data {
+            name: &name,
+            question: &question,
+            answer: "",
+        };
+
+        let answer = Template::compile(&answer)?.render_to_string(&data)?;
+
+        match self.template.load().await {
+            Some(template) => {
+                let response = template.render_to_string(&Data {
+                    answer: &answer,
+                    ..data
+                })?;
+
+                ctx.respond(response).await;
+            }
+            None => {
+                respond!(ctx, answer);
+            }
+        }
+
+        return Ok(());
+
+        #[derive(Clone, Copy, serde::Serialize)]
+        struct Data<'a> {
+            name: &'a str,
+            question: &'a str,
+            answer: &'a str,
+        }
+    }
+
+    /// Add a custom answer.
+    async fn add(&self, ctx: &mut command::Context) -> Result<()> {
+        let answers = self.answers().await?;
+
+        let mut weight = 1;
+
+        if ctx.rest().trim_start().starts_with("--weight") {
+            ctx.next();
+            weight = ctx.next_parse("--weight <n>")?;
+        }
+
+        if weight == 0 || weight > db::EightBallAnswers::MAX_WEIGHT {
+            respond_bail!(
+                "{}",
+                message!(
+                    self.messages,
+                    "8ball/bad-weight",
+                    max = db::EightBallAnswers::MAX_WEIGHT
+                )
+            );
+        }
+
+        let answer = ctx.rest().trim().to_string();
+
+        if answer.is_empty() {
+            respond_bail!("{}", message!(self.messages, "usage", usage = EDIT_USAGE));
+        }
+
+        if let Err(e) = Template::compile(&answer) {
+            respond_bail!(
+                "{}",
+                message!(self.messages, "8ball/bad-answer", error = e.to_string())
+            );
+        }
+
+        answers.add(ctx.channel(), &answer, weight).await?;
+        respond!(ctx, message!(self.messages, "8ball/added"));
+        Ok(())
+    }
+
+    /// Remove a custom answer.
+    async fn remove(&self, ctx: &mut command::Context) -> Result<()> {
+        let answers = self.answers().await?;
+        let id = ctx.next_parse("<id>")?;
+
+        if answers.remove(ctx.channel(), id).await? {
+            respond!(ctx, message!(self.messages, "8ball/removed", id = id));
+        } else {
+            respond!(ctx, message!(self.messages, "8ball/missing", id = id));
+        }
+
+        Ok(())
+    }
+
+    /// List custom answers.
+    async fn list(&self, ctx: &mut command::Context) -> Result<()> {
+        let answers = self.answers().await?.list(ctx.channel()).await?;
+
+        let lines = answers.iter().map(|a| {
+            if a.weight == 1 {
+                format!("#{}: {}", a.id, a.answer)
+            } else {
+                format!("#{} (x{}): {}", a.id, a.weight, a.answer)
+            }
+        });
 
-        if rest.trim().is_empty() {
-            respond!(ctx, "Ask a question.");
+        ctx.respond_lines(lines, &message!(self.messages, "8ball/list-empty"))
+            .await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl command::Handler for EightBall {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::EightBall)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
+        if !self.enabled.load().await {
             return Ok(());
         }
 
-        let index = rand::thread_rng().gen_range(0, MAGIC_8BALL_ANSWER.len());
+        // NB: `add`, `remove`, and `list` are only subcommands for users who
+        // can edit answers, for everyone else they're part of the question.
+        let edit = ctx.user.has_scope(auth::Scope::EightBallEdit).await;
+        let first = ctx.rest().split_whitespace().next().map(str::to_lowercase);
 
-        if let Some(answer) = MAGIC_8BALL_ANSWER.get(index) {
-            respond!(ctx, answer);
+        match first.as_deref() {
+            Some("add") if edit => {
+                ctx.next();
+                self.add(ctx).await?;
+            }
+            Some("remove") if edit => {
+                ctx.next();
+                self.remove(ctx).await?;
+            }
+            Some("list") if edit => {
+                ctx.next();
+                self.list(ctx).await?;
+            }
+            _ => {
+                self.answer(ctx).await?;
+            }
         }
 
         Ok(())
@@ -69,17 +248,32 @@ impl super::Module for Module {
         "8ball"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("8ball/enabled", true))
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
         module::HookContext {
-            handlers, settings, ..
+            handlers,
+            settings,
+            injector,
+            ..
         }: module::HookContext<'_>,
     ) -> Result<(), anyhow::Error> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
         handlers.insert(
             "8ball",
             EightBall {
                 enabled: settings.var("8ball/enabled", true).await?,
+                template: settings.optional("8ball/template").await?,
+                answers: injector.var().await?,
+                messages,
             },
         );
 
@@ -0,0 +1,118 @@
+use crate::auth;
+use crate::command;
+use crate::emote_stats::{EmoteStats, WINDOW_DAYS};
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use anyhow::anyhow;
+
+/// How many emotes to show by default.
+const DEFAULT_LIMIT: usize = 5;
+/// The most emotes that can be shown at once.
+const MAX_LIMIT: usize = 10;
+
+/// Handler for the !topemotes command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    emote_stats: injector::Var<Option<EmoteStats>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::EmoteStats)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!topemotes [count]",
+            description: "Show the most used emotes in chat over the last 30 days.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let emote_stats = match self.emote_stats.load().await {
+            Some(emote_stats) => emote_stats,
+            None => return Ok(()),
+        };
+
+        let limit = ctx
+            .next_parse_optional::<usize>()?
+            .unwrap_or(DEFAULT_LIMIT)
+            .max(1)
+            .min(MAX_LIMIT);
+
+        let channel = ctx.channel().trim_start_matches('#');
+        let emotes = emote_stats.top(channel, limit).await?;
+
+        if emotes.is_empty() {
+            respond!(
+                ctx,
+                message!(self.messages, "emote-stats/empty", days = WINDOW_DAYS)
+            );
+            return Ok(());
+        }
+
+        let emotes = emotes
+            .iter()
+            .map(|e| format!("{} ({})", e.emote, e.count))
+            .collect::<Vec<_>>();
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "emote-stats/top",
+                days = WINDOW_DAYS,
+                emotes = emotes.join(", ")
+            )
+        );
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "emote-stats"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("emote-stats/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<(), anyhow::Error> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "topemotes",
+            Handler {
+                enabled: settings.var("emote-stats/enabled", false).await?,
+                emote_stats: injector.var().await?,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
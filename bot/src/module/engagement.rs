@@ -0,0 +1,99 @@
+//! Posts engagement prompts when chat has been quiet for a while during a
+//! stream.
+
+use crate::idle::{Engagement, Prompt};
+use crate::irc;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+/// How often we check if chat has gone quiet.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "engagement"
+    }
+
+    fn flag(&self) -> Option<&'static str> {
+        Some("engagement")
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("engagement/enabled", false))
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            futures,
+            sender,
+            settings,
+            stream_info,
+            idle,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("engagement");
+        let enabled = settings.var("enabled", false).await?;
+        let quiet_time = settings
+            .var("quiet-time", utils::Duration::seconds(10 * 60))
+            .await?;
+        let max_per_hour = settings.var("max-per-hour", 2).await?;
+        let prompts = settings.var::<Vec<Prompt>>("prompts", Vec::new()).await?;
+
+        let sender = sender.clone();
+        let stream_info = stream_info.clone();
+        let idle = idle.clone();
+
+        let future = async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            let mut engagement = Engagement::default();
+
+            loop {
+                interval.tick().await;
+
+                if !enabled.load().await || !stream_info.is_live() {
+                    continue;
+                }
+
+                let now = Instant::now();
+                let quiet_time = quiet_time.load().await.as_std();
+
+                if !engagement.should_post(
+                    now,
+                    idle.quiet_for(now),
+                    quiet_time,
+                    max_per_hour.load().await,
+                ) {
+                    continue;
+                }
+
+                let prompts = prompts.load().await;
+                let weights = engagement.weights(&prompts);
+
+                let index = match utils::weighted_pick(&weights) {
+                    Some(index) => index,
+                    None => {
+                        log::trace!("no engagement prompts to post");
+                        continue;
+                    }
+                };
+
+                sender
+                    .privmsg_with(irc::Priority::Low, &prompts[index].text)
+                    .await;
+
+                engagement.posted(now, index);
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
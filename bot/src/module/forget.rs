@@ -0,0 +1,295 @@
+use crate::auth::{self, Auth};
+use crate::chat_stats::ChatStats;
+use crate::command;
+use crate::db;
+use crate::message_log::MessageLog;
+use crate::messages::Messages;
+use crate::module;
+use crate::player::Player;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a request to forget a user has to be confirmed.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Handler for the `!forgetme` and `!forget` commands.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    /// If the handler forgets the calling user, rather than a named user.
+    me: bool,
+    auth: Auth,
+    db: injector::Var<Option<db::Database>>,
+    player: injector::Var<Option<Player>>,
+    chat_stats: injector::Var<Option<ChatStats>>,
+    chatters: injector::Var<Option<db::Chatters>>,
+    message_log: injector::Var<Option<MessageLog>>,
+    messages: Messages,
+    /// Requests waiting to be confirmed, keyed by the requesting user.
+    pending: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl Handler {
+    /// The name of the command.
+    fn command(&self) -> &'static str {
+        if self.me {
+            "forgetme"
+        } else {
+            "forget"
+        }
+    }
+
+    /// Forget everything stored about the given user and record it in the
+    /// audit log.
+    ///
+    /// Returns a summary of what was forgotten.
+    async fn forget(&self, actor: &str, user: &str) -> Result<String> {
+        let db = match self.db.load().await {
+            Some(db) => db,
+            None => bail!("database not configured"),
+        };
+
+        // NB: remove queued songs first, so that they are anonymized below.
+        let queued = match self.player.load().await {
+            Some(player) => player.remove_all_by_user(user).await?.len(),
+            None => 0,
+        };
+
+        // NB: moderation actions are only purged by moderators, so that
+        // viewers can't erase their own history of timeouts and bans.
+        let forgotten = db.forget_user(user, !self.me).await?;
+        self.auth.forget_user(user).await;
+
+        let chat_stats = match self.chat_stats.load().await {
+            Some(chat_stats) => chat_stats.forget(user).await?,
+            None => false,
+        };
+
+        let chatters = match self.chatters.load().await {
+            Some(chatters) => chatters.forget(user).await?,
+            None => false,
+        };
+
+        let messages = match self.message_log.load().await {
+            Some(message_log) => message_log.forget_user(user).await?,
+            None => 0,
+        };
+
+        let mut removed = summarize(&[
+            (forgotten.balances, "balance(s)"),
+            (forgotten.after_streams, "after stream(s)"),
+            (queued, "queued song(s)"),
+            (forgotten.donations, "donation(s)"),
+            (forgotten.subscribers, "subscription(s)"),
+            (forgotten.trivia_wins, "trivia win(s)"),
+            (forgotten.viewer_queue, "viewer queue spot(s)"),
+            (forgotten.moderation_log, "moderation action(s)"),
+            (forgotten.greetings, "greeting(s)"),
+            (forgotten.weather_locations, "weather location(s)"),
+            (forgotten.stream_events, "stream event(s)"),
+            (forgotten.user_grants, "grant(s)"),
+            (messages, "logged chat message(s)"),
+        ]);
+
+        if chat_stats {
+            removed.push(String::from("chat stats"));
+        }
+
+        if chatters {
+            removed.push(String::from("chat history"));
+        }
+
+        let anonymized = summarize(&[
+            (forgotten.songs, "song request(s)"),
+            (forgotten.staged_songs, "staged song(s)"),
+            (forgotten.song_history, "played song(s)"),
+            (forgotten.banned_tracks, "ban(s)"),
+            (forgotten.clips, "clip(s)"),
+            (forgotten.moderation_messages, "moderation action(s)"),
+            (forgotten.markers, "marker(s)"),
+            (forgotten.after_stream_replies, "after stream comment(s)"),
+        ]);
+
+        let details = format!("removed {}; anonymized {}", list(removed), list(anonymized));
+
+        db.audit_push(&db::models::AddAuditLog {
+            action: String::from("forget"),
+            actor: Some(actor.to_string()),
+            target: Some(user.to_string()),
+            details: Some(details.clone()),
+            at: Utc::now().naive_utc(),
+        })
+        .await?;
+
+        log::info!("{} forgot {}: {}", actor, user, details);
+        Ok(details)
+    }
+}
+
+/// Describe every non-zero count of forgotten things.
+fn summarize(counts: &[(usize, &str)]) -> Vec<String> {
+    counts
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{} {}", count, what))
+        .collect()
+}
+
+/// Join a list of forgotten things for display.
+fn list(items: Vec<String>) -> String {
+    if items.is_empty() {
+        String::from("nothing")
+    } else {
+        items.join(", ")
+    }
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        if self.me {
+            Some(auth::Scope::ForgetMe)
+        } else {
+            Some(auth::Scope::Forget)
+        }
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        if self.me {
+            Some(command::Usage {
+                usage: "!forgetme [confirm]",
+                description: "Have the bot forget everything it has stored about you.",
+            })
+        } else {
+            Some(command::Usage {
+                usage: "!forget <user>",
+                description: "Have the bot forget everything it has stored about a user.",
+            })
+        }
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let actor = match ctx.user.name() {
+            Some(name) => name.to_lowercase(),
+            None => respond_bail!("{}", message!(self.messages, "forget/only-real-users")),
+        };
+
+        let arg = ctx.next();
+
+        match arg.as_deref() {
+            Some("confirm") => {
+                let pending = self.pending.lock().await.remove(&actor);
+
+                let user = match pending {
+                    Some((user, at)) if at.elapsed() < CONFIRM_TIMEOUT => user,
+                    _ => respond_bail!(
+                        "{}",
+                        message!(
+                            self.messages,
+                            "forget/nothing-to-confirm",
+                            command = self.command()
+                        )
+                    ),
+                };
+
+                let details = self.forget(&actor, &user).await?;
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "forget/done",
+                        me = self.me,
+                        user = user,
+                        details = details
+                    )
+                );
+            }
+            user => {
+                let user = match (self.me, user) {
+                    (true, None) => actor.clone(),
+                    (false, Some(user)) => user.trim_start_matches('@').to_lowercase(),
+                    (true, Some(..)) => respond_bail!("Expected: !forgetme [confirm]"),
+                    (false, None) => respond_bail!("Expected: <user>"),
+                };
+
+                let message = message!(
+                    self.messages,
+                    "forget/confirm",
+                    me = self.me,
+                    user = user,
+                    command = self.command(),
+                    timeout = utils::compact_duration(CONFIRM_TIMEOUT),
+                );
+
+                self.pending
+                    .lock()
+                    .await
+                    .insert(actor, (user, Instant::now()));
+
+                respond!(ctx, message);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "forget"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("forget/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            auth,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let enabled = settings.var("forget/enabled", false).await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        for &me in &[true, false] {
+            let handler = Handler {
+                enabled: enabled.clone(),
+                me,
+                auth: auth.clone(),
+                db: injector.var().await?,
+                player: injector.var().await?,
+                chat_stats: injector.var().await?,
+                chatters: injector.var().await?,
+                message_log: injector.var().await?,
+                messages: messages.clone(),
+                pending: Mutex::new(HashMap::new()),
+            };
+
+            handlers.insert(handler.command(), handler);
+        }
+
+        Ok(())
+    }
+}
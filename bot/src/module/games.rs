@@ -0,0 +1,663 @@
+//! Chat games which are played with stream currency.
+//!
+//! * `!duel <user> <amount>` challenges another user to a duel, which they
+//!   have to accept or decline before it times out. The winner takes the
+//!   wagered amount from the loser.
+//! * `!heist <amount>` starts a heist that others can join. Once everyone has
+//!   joined, each member of the crew either gets away with a payout or loses
+//!   what they put in, with odds and payouts depending on the size of the
+//!   crew.
+
+use crate::auth;
+use crate::command;
+use crate::currency::{BalanceTransferError, Currency};
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::utils::{self, Cooldown, Duration};
+use anyhow::{anyhow, Result};
+use rand::Rng as _;
+use std::collections::HashMap;
+use std::convert::TryFrom as _;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+/// How often pending duels and heists are checked.
+const TICK: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Usage of the `!duel` command.
+const DUEL_USAGE: command::Usage = command::Usage {
+    usage: "!duel <user> <amount>|accept|decline",
+    description: "Challenge another user to a duel for stream currency.",
+};
+
+/// Usage of the `!heist` command.
+const HEIST_USAGE: command::Usage = command::Usage {
+    usage: "!heist <amount>",
+    description: "Start or join a heist, putting stream currency on the line.",
+};
+
+/// Odds and payouts of a heist, which apply to crews of at least the given
+/// size.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Tier {
+    /// The smallest crew the tier applies to.
+    crew: u32,
+    /// The chance of each member getting away, in percent.
+    chance: u32,
+    /// The payout of members who get away, in percent of what they put in.
+    payout: u32,
+}
+
+/// The default heist tiers.
+fn default_tiers() -> Vec<Tier> {
+    vec![
+        Tier {
+            crew: 1,
+            chance: 40,
+            payout: 150,
+        },
+        Tier {
+            crew: 3,
+            chance: 50,
+            payout: 175,
+        },
+        Tier {
+            crew: 5,
+            chance: 60,
+            payout: 200,
+        },
+        Tier {
+            crew: 10,
+            chance: 70,
+            payout: 250,
+        },
+    ]
+}
+
+/// Find the tier which applies to a crew of the given size.
+fn tier(tiers: &[Tier], crew: usize) -> Option<Tier> {
+    tiers
+        .iter()
+        .filter(|t| t.crew as usize <= crew)
+        .max_by_key(|t| t.crew)
+        .copied()
+}
+
+/// Calculate the payout of the given amount, saturating on overflow.
+fn payout(amount: i64, percentage: u32) -> i64 {
+    let payout = i128::from(amount) * i128::from(percentage) / 100;
+    i64::try_from(payout).unwrap_or(if payout < 0 { i64::MIN } else { i64::MAX })
+}
+
+/// A duel waiting to be accepted.
+struct Duel {
+    challenger: String,
+    challenger_display: String,
+    target_display: String,
+    amount: i64,
+    expires_at: Instant,
+}
+
+/// A member of a heist crew.
+struct Member {
+    name: String,
+    display_name: String,
+    amount: i64,
+}
+
+/// A heist waiting for its crew to join.
+struct Heist {
+    /// The currency the crew put their stakes up in.
+    currency: Currency,
+    crew: Vec<Member>,
+    starts_at: Instant,
+}
+
+impl Heist {
+    /// Give every member of the crew back what they put in.
+    async fn refund(self, channel: &str) {
+        for member in self.crew {
+            if let Err(e) = self
+                .currency
+                .balance_add(channel, &member.name, member.amount)
+                .await
+            {
+                log_error!(e, "failed to refund heist stake to {}", member.name);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Pending duels, by the name of the challenged user.
+    duels: HashMap<String, Duel>,
+    /// The heist currently being planned, if any.
+    heist: Option<Heist>,
+}
+
+/// Get the name and display name of the user running a command.
+fn user(messages: &Messages, ctx: &command::Context) -> Result<(String, String)> {
+    match ctx.user.real() {
+        Some(user) => Ok((user.name().to_string(), user.display_name().to_string())),
+        None => respond_bail!("{}", message!(messages, "games/only-real-users")),
+    }
+}
+
+/// Make sure the given user can afford to put up the given amount.
+async fn check_balance(
+    messages: &Messages,
+    currency: &Currency,
+    channel: &str,
+    user: &str,
+    amount: i64,
+) -> Result<()> {
+    let balance = currency
+        .balance_of(channel, user)
+        .await?
+        .unwrap_or_default()
+        .balance;
+
+    if balance < amount {
+        respond_bail!(
+            "{}",
+            message!(
+                messages,
+                "games/insufficient-balance",
+                amount = amount,
+                currency = currency.name,
+                balance = balance,
+            )
+        );
+    }
+
+    Ok(())
+}
+
+/// Handler for the `!duel` command.
+pub struct DuelHandler {
+    enabled: settings::Var<bool>,
+    timeout: settings::Var<Duration>,
+    currency: injector::Var<Option<Currency>>,
+    state: Arc<Mutex<State>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for DuelHandler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Duel)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(DUEL_USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => respond_bail!("{}", message!(self.messages, "games/no-currency")),
+        };
+
+        let (name, display_name) = user(&self.messages, ctx)?;
+
+        match ctx.next().as_deref() {
+            Some("accept") => {
+                let duel = match self.state.lock().await.duels.remove(&name) {
+                    Some(duel) if duel.expires_at > Instant::now() => duel,
+                    _ => respond_bail!("{}", message!(self.messages, "games/no-duel")),
+                };
+
+                let challenger = (duel.challenger.as_str(), duel.challenger_display.as_str());
+                let target = (name.as_str(), display_name.as_str());
+
+                let (winner, loser) = if rand::thread_rng().gen_bool(0.5) {
+                    (challenger, target)
+                } else {
+                    (target, challenger)
+                };
+
+                let result = currency
+                    .balance_transfer(ctx.channel(), loser.0, winner.0, duel.amount, false)
+                    .await;
+
+                match result {
+                    Ok(()) => {
+                        ctx.privmsg(message!(
+                            self.messages,
+                            "games/duel-won",
+                            winner = winner.1,
+                            loser = loser.1,
+                            amount = duel.amount,
+                            currency = currency.name,
+                        ))
+                        .await;
+                    }
+                    Err(BalanceTransferError::NoBalance) => {
+                        respond!(
+                            ctx,
+                            message!(self.messages, "games/duel-unaffordable", user = loser.1)
+                        );
+                    }
+                    Err(BalanceTransferError::Other(e)) => return Err(e),
+                }
+            }
+            Some("decline") => match self.state.lock().await.duels.remove(&name) {
+                Some(duel) => {
+                    ctx.privmsg(message!(
+                        self.messages,
+                        "games/duel-declined",
+                        target = display_name,
+                        challenger = duel.challenger_display,
+                    ))
+                    .await;
+                }
+                None => respond!(ctx, message!(self.messages, "games/no-duel")),
+            },
+            Some(target) => {
+                let target_display = target.trim_start_matches('@').to_string();
+                let target = target_display.to_lowercase();
+                let amount = ctx.next_parse::<i64, _>("<user> <amount>")?;
+
+                if amount <= 0 {
+                    respond_bail!("{}", message!(self.messages, "games/bad-amount"));
+                }
+
+                if target == name {
+                    respond_bail!("{}", message!(self.messages, "games/duel-self"));
+                }
+
+                check_balance(&self.messages, &currency, ctx.channel(), &name, amount).await?;
+
+                let mut state = self.state.lock().await;
+                let now = Instant::now();
+
+                if let Some(duel) = state.duels.get(&target) {
+                    if duel.expires_at > now {
+                        respond_bail!(
+                            "{}",
+                            message!(self.messages, "games/duel-pending", user = target_display)
+                        );
+                    }
+                }
+
+                let timeout = self.timeout.load().await;
+
+                ctx.privmsg(message!(
+                    self.messages,
+                    "games/duel-challenge",
+                    target = target_display,
+                    challenger = display_name,
+                    amount = amount,
+                    currency = currency.name,
+                    timeout = timeout.to_string(),
+                ))
+                .await;
+
+                state.duels.insert(
+                    target,
+                    Duel {
+                        challenger: name,
+                        challenger_display: display_name,
+                        target_display,
+                        amount,
+                        expires_at: now + timeout.as_std(),
+                    },
+                );
+            }
+            None => respond!(
+                ctx,
+                message!(self.messages, "usage", usage = DUEL_USAGE.usage)
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+/// Handler for the `!heist` command.
+pub struct HeistHandler {
+    enabled: settings::Var<bool>,
+    join_duration: settings::Var<Duration>,
+    cooldown: settings::Var<Cooldown>,
+    currency: injector::Var<Option<Currency>>,
+    state: Arc<Mutex<State>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for HeistHandler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Heist)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(HEIST_USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => respond_bail!("{}", message!(self.messages, "games/no-currency")),
+        };
+
+        let (name, display_name) = user(&self.messages, ctx)?;
+        let amount = ctx.next_parse::<i64, _>("<amount>")?;
+
+        if amount <= 0 {
+            respond_bail!("{}", message!(self.messages, "games/bad-amount"));
+        }
+
+        let mut state = self.state.lock().await;
+
+        match &mut state.heist {
+            Some(heist) => {
+                if heist.crew.iter().any(|m| m.name == name) {
+                    respond_bail!("{}", message!(self.messages, "games/heist-joined-already"));
+                }
+
+                check_balance(
+                    &self.messages,
+                    &heist.currency,
+                    ctx.channel(),
+                    &name,
+                    amount,
+                )
+                .await?;
+                heist
+                    .currency
+                    .balance_add(ctx.channel(), &name, -amount)
+                    .await?;
+
+                heist.crew.push(Member {
+                    name,
+                    display_name: display_name.clone(),
+                    amount,
+                });
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "games/heist-joined",
+                        amount = amount,
+                        currency = heist.currency.name,
+                        crew = heist.crew.len(),
+                    )
+                );
+            }
+            None => {
+                if let Some(remaining) = self.cooldown.write().await.check(Instant::now()) {
+                    respond_bail!(
+                        "{}",
+                        message!(
+                            self.messages,
+                            "games/heist-cooldown",
+                            remaining = utils::compact_duration(remaining)
+                        )
+                    );
+                }
+
+                check_balance(&self.messages, &currency, ctx.channel(), &name, amount).await?;
+                currency.balance_add(ctx.channel(), &name, -amount).await?;
+
+                let join_duration = self.join_duration.load().await;
+
+                state.heist = Some(Heist {
+                    currency,
+                    crew: vec![Member {
+                        name,
+                        display_name: display_name.clone(),
+                        amount,
+                    }],
+                    starts_at: Instant::now() + join_duration.as_std(),
+                });
+
+                self.cooldown.write().await.poke(Instant::now());
+
+                ctx.privmsg(message!(
+                    self.messages,
+                    "games/heist-planned",
+                    user = display_name,
+                    duration = join_duration.to_string(),
+                ))
+                .await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "games"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("games/enabled", false))
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            futures,
+            sender,
+            settings,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("games");
+        let enabled = settings.var("enabled", false).await?;
+        let timeout = settings.var("duel/timeout", Duration::seconds(60)).await?;
+        let tiers = settings.var("heist/tiers", default_tiers()).await?;
+
+        let join_duration = settings
+            .var("heist/join-duration", Duration::seconds(60))
+            .await?;
+
+        let (mut cooldown_stream, cooldown) = settings
+            .stream("heist/cooldown")
+            .or_with(Duration::seconds(60 * 10))
+            .await?;
+
+        let cooldown = settings::Var::new(Cooldown::from_duration(cooldown));
+        let currency = injector.var::<Currency>().await?;
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "duel",
+            DuelHandler {
+                enabled: enabled.clone(),
+                timeout,
+                currency: currency.clone(),
+                state: state.clone(),
+                messages: messages.clone(),
+            },
+        );
+
+        handlers.insert(
+            "heist",
+            HeistHandler {
+                enabled,
+                join_duration,
+                cooldown: cooldown.clone(),
+                currency,
+                state: state.clone(),
+                messages: messages.clone(),
+            },
+        );
+
+        let sender = sender.clone();
+
+        let future = async move {
+            // NB: the stakes of a pending heist would be lost if the module is
+            // torn down, so the crew gets them back.
+            let _teardown = module::Teardown::new({
+                let state = state.clone();
+                let sender = sender.clone();
+                let messages = messages.clone();
+
+                async move {
+                    let heist = state.lock().await.heist.take();
+
+                    if let Some(heist) = heist {
+                        heist.refund(sender.channel()).await;
+                        sender
+                            .privmsg(message!(messages, "games/heist-called-off"))
+                            .await;
+                    }
+                }
+            });
+
+            let mut interval = tokio::time::interval(TICK).fuse();
+
+            loop {
+                futures::select! {
+                    update = cooldown_stream.select_next_some() => {
+                        cooldown.write().await.cooldown = update;
+                    }
+                    _ = interval.select_next_some() => {
+                        let now = Instant::now();
+                        let mut state = state.lock().await;
+
+                        let expired = state
+                            .duels
+                            .iter()
+                            .filter(|(_, d)| d.expires_at <= now)
+                            .map(|(target, _)| target.clone())
+                            .collect::<Vec<_>>();
+
+                        for target in expired {
+                            if let Some(duel) = state.duels.remove(&target) {
+                                let message = message!(
+                                    messages,
+                                    "games/duel-expired",
+                                    target = duel.target_display,
+                                    challenger = duel.challenger_display,
+                                );
+
+                                sender.privmsg(message).await;
+                            }
+                        }
+
+                        let heist = match state.heist.take() {
+                            Some(heist) if heist.starts_at <= now => heist,
+                            heist => {
+                                state.heist = heist;
+                                continue;
+                            }
+                        };
+
+                        drop(state);
+
+                        let tiers = tiers.load().await;
+                        run_heist(&sender, &messages, &tiers, heist).await;
+                    }
+                }
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
+
+/// Run a heist once its crew has been assembled.
+async fn run_heist(sender: &irc::Sender, messages: &Messages, tiers: &[Tier], heist: Heist) {
+    // NB: no tier applies to a crew this small, so the heist can't run.
+    let tier = match tier(tiers, heist.crew.len()) {
+        Some(tier) => tier,
+        None => {
+            heist.refund(sender.channel()).await;
+            sender
+                .privmsg(message!(messages, "games/heist-called-off"))
+                .await;
+            return;
+        }
+    };
+
+    let Heist { currency, crew, .. } = heist;
+
+    let mut survivors = Vec::new();
+
+    for member in crew {
+        if rand::thread_rng().gen_range(0, 100) >= tier.chance {
+            continue;
+        }
+
+        let amount = payout(member.amount, tier.payout);
+
+        if let Err(e) = currency
+            .balance_add(sender.channel(), &member.name, amount)
+            .await
+        {
+            log_error!(e, "failed to pay out heist to {}", member.name);
+            continue;
+        }
+
+        survivors.push(format!("{} ({})", member.display_name, amount));
+    }
+
+    if survivors.is_empty() {
+        sender
+            .privmsg(message!(messages, "games/heist-caught"))
+            .await;
+        return;
+    }
+
+    sender
+        .privmsg(message!(
+            messages,
+            "games/heist-over",
+            currency = currency.name,
+            survivors = survivors.join(", "),
+        ))
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_tiers, payout, tier};
+
+    #[test]
+    fn test_tier() {
+        let tiers = default_tiers();
+
+        assert_eq!(None, tier(&tiers, 0));
+        assert_eq!(Some(1), tier(&tiers, 1).map(|t| t.crew));
+        assert_eq!(Some(1), tier(&tiers, 2).map(|t| t.crew));
+        assert_eq!(Some(5), tier(&tiers, 9).map(|t| t.crew));
+        assert_eq!(Some(10), tier(&tiers, 100).map(|t| t.crew));
+        assert_eq!(None, tier(&[], 10));
+    }
+
+    #[test]
+    fn test_payout() {
+        assert_eq!(150, payout(100, 150));
+        assert_eq!(0, payout(100, 0));
+        assert_eq!(37, payout(25, 150));
+        assert_eq!(i64::MAX, payout(i64::MAX, 150));
+        assert_eq!(i64::MAX / 2, payout(i64::MAX, 50));
+    }
+}
@@ -0,0 +1,337 @@
+//! Pick a random winner among the users who are active in chat.
+//!
+//! Activity is determined by the chat log, so it has to be enabled. Winners
+//! are remembered for the duration of the stream so that nobody wins twice.
+
+use crate::api::twitch::AnnouncementColor;
+use crate::auth;
+use crate::command;
+use crate::currency::{Currency, RewardData, RewardSource, Rewards};
+use crate::message_log::{self, MessageLog};
+use crate::messages::Messages;
+use crate::module;
+use crate::module::misc::CachedTwitch;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::utils::Duration;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use rand::seq::SliceRandom as _;
+use std::collections::HashSet;
+
+/// Usage of the `!pickwinner` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!pickwinner [subs] [followers] [mods]|reset",
+    description: "Pick a random winner among the users active in chat.",
+};
+
+/// Restrictions on who can be picked.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    /// Only pick subscribers.
+    subscribers: bool,
+    /// Only pick followers.
+    followers: bool,
+    /// Include moderators, even if they are excluded by default.
+    moderators: bool,
+}
+
+impl Options {
+    /// Parse options from the arguments of the command.
+    fn parse<'a>(args: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut options = Self::default();
+
+        for arg in args {
+            match arg {
+                "sub" | "subs" => options.subscribers = true,
+                "follower" | "followers" => options.followers = true,
+                "mod" | "mods" => options.moderators = true,
+                other => return Err(other.to_string()),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Users who have won during a stream.
+#[derive(Default)]
+struct Winners {
+    /// When the stream the winners belong to started.
+    stream: Option<DateTime<Utc>>,
+    users: HashSet<String>,
+}
+
+/// Handler for the `!pickwinner` command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    active_within: settings::Var<Duration>,
+    exclude_mods: settings::Var<bool>,
+    ignored: settings::Var<Vec<String>>,
+    announce: settings::Var<Option<AnnouncementColor>>,
+    reward: settings::Var<i64>,
+    rewards: Rewards,
+    currency: injector::Var<Option<Currency>>,
+    message_log: injector::Var<Option<MessageLog>>,
+    stream_info: stream_info::StreamInfo,
+    twitch: CachedTwitch,
+    winners: Mutex<Winners>,
+    messages: Messages,
+}
+
+impl Handler {
+    /// Get the winners of the current stream, forgetting the winners of
+    /// previous streams.
+    fn winners(&self) -> HashSet<String> {
+        let started_at = self.stream_info.started_at();
+        let mut winners = self.winners.lock();
+
+        if winners.stream != started_at {
+            winners.stream = started_at;
+            winners.users.clear();
+        }
+
+        winners.users.clone()
+    }
+
+    /// Reward the winner of a raffle, returning the message to send about it.
+    async fn reward(&self, ctx: &command::Context, winner: &message_log::User) -> Option<String> {
+        let reward = self.reward.load().await;
+        let reward = self.rewards.amount(RewardSource::Raffle, reward).await;
+
+        if reward <= 0 {
+            return None;
+        }
+
+        let currency = self.currency.load().await?;
+
+        if let Err(e) = currency
+            .balance_add(ctx.channel(), &winner.name, reward)
+            .await
+        {
+            log_error!(e, "failed to reward {} for winning a raffle", winner.name);
+            return None;
+        }
+
+        let data = RewardData {
+            user: Some(&winner.display_name),
+            amount: reward,
+            currency: &currency.name,
+        };
+
+        match self.rewards.message(RewardSource::Raffle, data).await {
+            Some(message) => Some(message),
+            None => Some(message!(
+                self.messages,
+                "giveaway/rewarded",
+                user = winner.display_name,
+                amount = reward,
+                currency = currency.name,
+            )),
+        }
+    }
+
+    /// Test if the given user follows the streamer.
+    async fn is_follower(&self, ctx: &command::Context, user: &message_log::User) -> bool {
+        let streamer = ctx.user.streamer();
+
+        match self.twitch.follow(&user.user_id, &streamer.id).await {
+            Ok(follow) => follow.is_some(),
+            Err(e) => {
+                log_error!(e, "failed to check if {} is following", user.name);
+                false
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::PickWinner)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        if ctx.rest().trim() == "reset" {
+            self.winners.lock().users.clear();
+            respond!(ctx, message!(self.messages, "giveaway/reset"));
+            return Ok(());
+        }
+
+        let options = match Options::parse(ctx.rest().split_whitespace()) {
+            Ok(options) => options,
+            Err(option) => respond_bail!(
+                "{}",
+                message!(
+                    self.messages,
+                    "giveaway/unknown-option",
+                    option = option,
+                    usage = USAGE.usage
+                )
+            ),
+        };
+
+        let message_log = match self.message_log.load().await {
+            Some(message_log) => message_log,
+            None => respond_bail!("{}", message!(self.messages, "giveaway/no-chat-log")),
+        };
+
+        let since = Utc::now() - self.active_within.load().await.as_chrono();
+
+        let mut excluded = self.winners();
+        excluded.extend(
+            self.ignored
+                .load()
+                .await
+                .into_iter()
+                .map(|u| u.to_lowercase()),
+        );
+        excluded.insert(ctx.user.streamer().name.to_lowercase());
+
+        let exclude_mods = self.exclude_mods.load().await && !options.moderators;
+
+        let mut candidates = message_log
+            .users_since(since)
+            .await
+            .into_iter()
+            .filter(|u| !excluded.contains(&u.name))
+            .filter(|u| !exclude_mods || !ctx.user.is_moderator_in_channel(&u.name))
+            .filter(|u| !options.subscribers || self.stream_info.is_subscriber(&u.name))
+            .collect::<Vec<_>>();
+
+        candidates.shuffle(&mut rand::thread_rng());
+
+        let mut winner = None;
+
+        for user in candidates {
+            if options.followers && !self.is_follower(ctx, &user).await {
+                continue;
+            }
+
+            winner = Some(user);
+            break;
+        }
+
+        let winner = match winner {
+            Some(winner) => winner,
+            None => respond_bail!("{}", message!(self.messages, "giveaway/no-candidates")),
+        };
+
+        self.winners.lock().users.insert(winner.name.clone());
+
+        let announce = self.announce.load().await;
+
+        ctx.announce(
+            announce,
+            message!(self.messages, "giveaway/winner", user = winner.display_name),
+        )
+        .await;
+
+        if let Some(message) = self.reward(ctx, &winner).await {
+            ctx.privmsg(message).await;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "giveaway"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("giveaway/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            stream_info,
+            streamer_twitch,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let rewards = Rewards::new(settings).await?;
+        let settings = settings.scoped("giveaway");
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "pickwinner",
+            Handler {
+                enabled: settings.var("enabled", false).await?,
+                active_within: settings
+                    .var("active-within", Duration::seconds(10 * 60))
+                    .await?,
+                exclude_mods: settings.var("exclude-mods", true).await?,
+                ignored: settings.var("ignored", default_ignored()).await?,
+                announce: settings.optional("announce").await?,
+                reward: settings.var("reward", 0).await?,
+                rewards,
+                currency: injector.var().await?,
+                message_log: injector.var().await?,
+                stream_info: stream_info.clone(),
+                twitch: CachedTwitch::new(injector.var().await?, streamer_twitch.clone()),
+                winners: Default::default(),
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+/// Well-known chat bots which should never win.
+fn default_ignored() -> Vec<String> {
+    [
+        "nightbot",
+        "streamelements",
+        "streamlabs",
+        "moobot",
+        "fossabot",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Options;
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(Ok(Options::default()), Options::parse(vec![]));
+
+        assert_eq!(
+            Ok(Options {
+                subscribers: true,
+                followers: true,
+                moderators: false,
+            }),
+            Options::parse(vec!["subs", "followers"])
+        );
+
+        assert_eq!(Err(String::from("vips")), Options::parse(vec!["vips"]));
+    }
+}
@@ -0,0 +1,360 @@
+//! Greet users the first time they chat during a stream.
+//!
+//! First-time chatters are detected through the `first-msg` tag, or through
+//! the first-seen tracking in [db::Chatters]. Moderators can set custom
+//! greetings for individual users with `!greeting`.
+
+use crate::auth;
+use crate::command;
+use crate::db;
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::template::Template;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Window in which at most `greetings/limit` greetings are sent.
+const LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Default greeting for first-time chatters.
+const DEFAULT_FIRST_TIME: &str =
+    "Welcome to the stream {{name}}, it looks like this is your first time chatting here!";
+
+/// Usage of the `!greeting` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!greeting <show|set|delete> <user> [greeting...]",
+    description: "Manage custom greetings for users.",
+};
+
+/// Variables available in greetings.
+#[derive(serde::Serialize)]
+struct Vars<'a> {
+    name: &'a str,
+    target: &'a str,
+}
+
+/// Limits how many greetings are sent within a window of time, to avoid
+/// spamming chat during raids.
+#[derive(Default)]
+struct RateLimit {
+    sent: VecDeque<Instant>,
+}
+
+impl RateLimit {
+    /// Try to send a greeting at the given time.
+    ///
+    /// Returns `false` if `limit` greetings have already been sent within
+    /// `window`.
+    fn try_acquire(&mut self, now: Instant, limit: usize, window: Duration) -> bool {
+        while let Some(front) = self.sent.front() {
+            if now.duration_since(*front) < window {
+                break;
+            }
+
+            self.sent.pop_front();
+        }
+
+        if self.sent.len() >= limit {
+            return false;
+        }
+
+        self.sent.push_back(now);
+        true
+    }
+}
+
+/// Users who have been seen during a stream.
+#[derive(Default)]
+struct Seen {
+    /// When the stream the users belong to started.
+    stream: Option<DateTime<Utc>>,
+    users: HashSet<String>,
+}
+
+/// Peeks at chat messages to greet users.
+struct Greeter {
+    first_time: settings::Var<Template>,
+    returning: settings::Var<Template>,
+    limit: settings::Var<u32>,
+    chatters: injector::Var<Option<db::Chatters>>,
+    greetings: injector::Var<Option<db::Greetings>>,
+    stream_info: stream_info::StreamInfo,
+    seen: Mutex<Seen>,
+    rate_limit: Mutex<RateLimit>,
+}
+
+impl Greeter {
+    /// Mark the given user as seen during the current stream.
+    ///
+    /// Returns `false` if they've already been seen.
+    fn see(&self, name: &str) -> bool {
+        let started_at = self.stream_info.started_at();
+        let mut seen = self.seen.lock();
+
+        if seen.stream != started_at {
+            seen.stream = started_at;
+            seen.users.clear();
+        }
+
+        seen.users.insert(name.to_string())
+    }
+
+    /// Unmark the given user as seen, so that they are greeted the next time
+    /// they chat.
+    fn unsee(&self, name: &str) {
+        self.seen.lock().users.remove(name);
+    }
+
+    /// Greet the given user.
+    ///
+    /// Returns `false` if the greeting couldn't be sent because too many
+    /// greetings have been sent recently.
+    async fn greet(&self, user: &irc::User, name: &str, display_name: &str) -> Result<bool> {
+        let greeting = self.template(user, name).await?.render_to_string(Vars {
+            name: display_name,
+            target: user.channel(),
+        })?;
+
+        if greeting.is_empty() {
+            return Ok(true);
+        }
+
+        let limit = self.limit.load().await as usize;
+
+        if !self
+            .rate_limit
+            .lock()
+            .try_acquire(Instant::now(), limit, LIMIT_WINDOW)
+        {
+            log::trace!("not greeting {}, too many greetings", name);
+            return Ok(false);
+        }
+
+        user.sender().privmsg(greeting).await;
+        Ok(true)
+    }
+
+    /// Test if this is the first time the given user chats in the channel.
+    async fn is_first_time(&self, user: &irc::User, name: &str) -> Result<bool> {
+        if user.tags().first_msg {
+            return Ok(true);
+        }
+
+        let chatters = match self.chatters.load().await {
+            Some(chatters) => chatters,
+            None => return Ok(false),
+        };
+
+        // NB: the current message has already been observed.
+        let chatter = chatters.get(user.channel(), name).await?;
+        Ok(chatter.map(|c| c.messages <= 1).unwrap_or(true))
+    }
+
+    /// Get the template to greet the given user with.
+    async fn template(&self, user: &irc::User, name: &str) -> Result<Template> {
+        if let Some(greetings) = self.greetings.load().await {
+            if let Some(greeting) = greetings.get(user.channel(), name).await? {
+                return Template::compile(&greeting.text);
+            }
+        }
+
+        if self.is_first_time(user, name).await? {
+            Ok(self.first_time.load().await)
+        } else {
+            Ok(self.returning.load().await)
+        }
+    }
+}
+
+#[async_trait]
+impl command::MessageHook for Greeter {
+    async fn peek(&self, user: &irc::User, _: &str) -> Result<()> {
+        let (name, display_name) = match user.real() {
+            Some(user) => (user.name().to_string(), user.display_name().to_string()),
+            None => return Ok(()),
+        };
+
+        // NB: the user is marked as seen up front so that they aren't greeted
+        // twice if they send several messages at once.
+        if name == user.streamer().name || !self.see(&name) {
+            return Ok(());
+        }
+
+        let result = self.greet(user, &name, &display_name).await;
+
+        if !matches!(result, Ok(true)) {
+            self.unsee(&name);
+        }
+
+        result?;
+        Ok(())
+    }
+}
+
+/// Handler for the `!greeting` command.
+pub struct Handler {
+    greetings: injector::Var<Option<db::Greetings>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Greeting)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        let greetings = match self.greetings.load().await {
+            Some(greetings) => greetings,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            Some("show") => {
+                let user = ctx.next_str("<user>")?;
+                let user = user.trim_start_matches('@').to_lowercase();
+
+                match greetings.get(ctx.channel(), &user).await? {
+                    Some(greeting) => respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "greetings/show",
+                            user = user,
+                            greeting = greeting.text
+                        )
+                    ),
+                    None => respond!(
+                        ctx,
+                        message!(self.messages, "greetings/missing", user = user)
+                    ),
+                }
+            }
+            Some("set") => {
+                let user = ctx.next_str("<user> <greeting...>")?;
+                let user = user.trim_start_matches('@').to_lowercase();
+                let text = ctx.rest().trim();
+
+                if text.is_empty() {
+                    respond_bail!("Expected: <user> <greeting...>");
+                }
+
+                if let Err(e) = Template::compile(text) {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "greetings/bad", error = e.to_string())
+                    );
+                }
+
+                greetings.set(ctx.channel(), &user, text).await?;
+                respond!(ctx, message!(self.messages, "greetings/set", user = user));
+            }
+            Some("delete") => {
+                let user = ctx.next_str("<user>")?;
+                let user = user.trim_start_matches('@').to_lowercase();
+
+                if !greetings.delete(ctx.channel(), &user).await? {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "greetings/missing", user = user)
+                    );
+                }
+
+                respond!(
+                    ctx,
+                    message!(self.messages, "greetings/deleted", user = user)
+                );
+            }
+            _ => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "greetings"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("greetings/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            stream_info,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("greetings");
+        let greetings = injector.var().await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert_hook(Greeter {
+            first_time: settings
+                .var("first-time", Template::compile(DEFAULT_FIRST_TIME)?)
+                .await?,
+            returning: settings.var("returning", Template::compile("")?).await?,
+            limit: settings.var("limit", 5).await?,
+            chatters: injector.var().await?,
+            greetings: greetings.clone(),
+            stream_info: stream_info.clone(),
+            seen: Default::default(),
+            rate_limit: Default::default(),
+        });
+
+        handlers.insert(
+            "greeting",
+            Handler {
+                greetings,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimit;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_rate_limit() {
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut limit = RateLimit::default();
+
+        assert!(limit.try_acquire(start, 2, window));
+        assert!(limit.try_acquire(start + Duration::from_secs(1), 2, window));
+        assert!(!limit.try_acquire(start + Duration::from_secs(2), 2, window));
+        assert!(limit.try_acquire(start + Duration::from_secs(60), 2, window));
+        assert!(!limit.try_acquire(start + Duration::from_secs(60), 2, window));
+    }
+}
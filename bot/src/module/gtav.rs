@@ -1,12 +1,14 @@
 use crate::auth::Scope;
 use crate::command;
 use crate::currency;
+use crate::game_rpc;
 use crate::irc;
+use crate::messages::Messages;
 use crate::module;
 use crate::player;
 use crate::prelude::*;
 use crate::utils::{compact_duration, Cooldown, Duration};
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use std::collections::{hash_map, HashMap};
 use std::fmt;
 use std::net::SocketAddr;
@@ -16,20 +18,22 @@ use tokio::net::UdpSocket;
 use tokio::sync::Mutex;
 
 const VEHICLE_URL: &str = "http://bit.ly/gtavvehicles";
+/// The name ChaosMod uses when connecting through the game RPC channel.
+const INTEGRATION: &str = "gtav";
+/// How long to wait for the catalog to download, so that an unreachable
+/// catalog doesn't hold up the module.
+const CATALOG_TIMEOUT: time::Duration = time::Duration::from_secs(10);
 
+mod catalog;
 mod vehicle;
 mod weapon;
 
+use self::catalog::{Catalog, Item};
 use self::vehicle::Vehicle;
-use self::weapon::Weapon;
 
 macro_rules! vehicle {
-    ($ctx:expr, $pfx:expr) => {
-        match $ctx
-            .next()
-            .map(|s| s.to_lowercase())
-            .and_then(Vehicle::from_id)
-        {
+    ($ctx:expr, $messages:expr, $catalog:expr, $pfx:expr) => {
+        match $ctx.next().and_then(|s| $catalog.vehicle(&s)) {
             Some(vehicle) => vehicle,
             None => {
                 let vehicles = Vehicle::categories()
@@ -38,14 +42,17 @@ macro_rules! vehicle {
                     .collect::<Vec<String>>()
                     .join(", ");
 
+                let added = $catalog.added_vehicles();
+
                 respond!(
                     $ctx,
-                    "You give the streamer a vehicle using for example `random`. \
-                     You can pick a vehicle by its name or a category. \
-                     Available names are listed here: {url} - \
-                     Available categories are: {vehicles}. ",
-                    url = VEHICLE_URL,
-                    vehicles = vehicles,
+                    message!(
+                        $messages,
+                        "gtav/vehicles",
+                        url = VEHICLE_URL,
+                        vehicles = vehicles,
+                        added = added.join(", "),
+                    )
                 );
 
                 return Ok(None);
@@ -54,7 +61,7 @@ macro_rules! vehicle {
     };
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 struct CommandConfig {
     name: String,
     #[serde(default)]
@@ -86,10 +93,12 @@ impl Default for CommandSetting {
 }
 
 impl CommandsConfig {
-    fn into_map(self) -> HashMap<String, CommandSetting> {
+    /// Build command settings, where the configuration in `self` takes
+    /// precedence over the configuration from the catalog.
+    fn into_map(self, catalog: &Catalog) -> HashMap<String, CommandSetting> {
         let mut m = HashMap::new();
 
-        for c in self.0 {
+        for c in catalog.commands().iter().cloned().chain(self.0) {
             let s = CommandSetting {
                 enabled: c.enabled.unwrap_or(true),
                 cooldown: c
@@ -107,9 +116,9 @@ impl CommandsConfig {
 
 enum Command {
     /// Spawn a vehicle.
-    SpawnVehicle(Vehicle),
+    SpawnVehicle(Item),
     /// Spawn a random vehicle.
-    SpawnRandomVehicle(Vehicle),
+    SpawnRandomVehicle(Item),
     /// Destroy the engine of the current vehicle.
     KillEngine,
     /// Fix the engine of the current vehicle.
@@ -117,7 +126,7 @@ enum Command {
     /// Repair the current vehicle.
     Repair,
     /// Give a random weapon.
-    GiveWeapon(Weapon),
+    GiveWeapon(Item),
     /// Take weapon.
     TakeWeapon,
     /// Take all weapons.
@@ -199,7 +208,7 @@ enum Command {
     /// Make the current car leak all its fuel in 30 seconds.
     FuelLeakage,
     /// Change the current vehicle of the player.
-    ChangeCurrentVehicle(Vehicle),
+    ChangeCurrentVehicle(Item),
     /// Randomize doors of the current vehicle.
     RandomizeDoors,
     /// Shoot the player up in the air with a parachute.
@@ -344,7 +353,7 @@ impl Command {
         use self::Command::*;
 
         match *self {
-            SpawnRandomVehicle(vehicle) | SpawnVehicle(vehicle) => {
+            SpawnRandomVehicle(ref vehicle) | SpawnVehicle(ref vehicle) => {
                 format!("spawn-vehicle {}", vehicle)
             }
             Repair => "repair".to_string(),
@@ -417,7 +426,7 @@ impl Command {
             SpawnRandomVehicle(..) => 10,
             SpawnVehicle(ref vehicle, ..) => vehicle.cost(),
             Repair => 10,
-            GiveWeapon(weapon) => weapon.cost(),
+            GiveWeapon(ref weapon) => weapon.cost(),
             Wanted(0) => 15,
             Wanted(n) => 10 + 5 * n,
             GiveHealth => 10,
@@ -471,13 +480,13 @@ impl fmt::Display for Command {
         use self::Command::*;
 
         match *self {
-            SpawnRandomVehicle(vehicle) | SpawnVehicle(vehicle) => {
+            SpawnRandomVehicle(ref vehicle) | SpawnVehicle(ref vehicle) => {
                 write!(fmt, "giving them {}", vehicle.display())
             }
             Repair => "repairing their car TakeNRG".fmt(fmt),
             KillEngine => "killing their engine PepeHands".fmt(fmt),
             BlowTires => "blowing their tires monkaMegaS".fmt(fmt),
-            GiveWeapon(weapon) => write!(fmt, "giving them {} TakeNRG", weapon.display()),
+            GiveWeapon(ref weapon) => write!(fmt, "giving them {} TakeNRG", weapon.display()),
             TakeWeapon => "taking their weapon SwiftRage".fmt(fmt),
             TakeAllWeapons => "taking ALL their weapons SwiftRage".fmt(fmt),
             Stumble => "making them stumble SwiftRage".fmt(fmt),
@@ -561,6 +570,8 @@ pub struct Handler {
     per_user_cooldowns: Mutex<HashMap<String, Cooldown>>,
     per_command_cooldowns: Mutex<HashMap<&'static str, Cooldown>>,
     per_command_configs: settings::Var<HashMap<String, CommandSetting>>,
+    catalog: settings::Var<Catalog>,
+    messages: Messages,
 }
 
 impl Handler {
@@ -658,20 +669,40 @@ impl Handler {
         let mut remaining = smallvec::SmallVec::<[_; 4]>::new();
 
         if let Some(user_cooldown) = user_cooldown.as_mut() {
-            remaining.extend(user_cooldown.check(now.clone()).map(|d| ("User", d)));
+            remaining.extend(
+                user_cooldown
+                    .check(now.clone())
+                    .map(|d| ("gtav/user-cooldown", d)),
+            );
         }
 
         if let Some(command_specific) = command_specific.as_ref() {
             let mut cooldown = command_specific.write().await;
 
-            remaining.extend(cooldown.check(now.clone()).map(|d| ("Command specific", d)));
+            remaining.extend(
+                cooldown
+                    .check(now.clone())
+                    .map(|d| ("gtav/command-specific-cooldown", d)),
+            );
         } else {
-            remaining.extend(cooldown.check(now.clone()).map(|d| ("Global", d)));
-            remaining.extend(command_cooldown.check(now.clone()).map(|d| ("Command", d)));
+            remaining.extend(
+                cooldown
+                    .check(now.clone())
+                    .map(|d| ("gtav/global-cooldown", d)),
+            );
+            remaining.extend(
+                command_cooldown
+                    .check(now.clone())
+                    .map(|d| ("gtav/command-cooldown", d)),
+            );
 
             if let Some(category_cooldown) = category_cooldown.as_ref() {
                 let mut cooldown = category_cooldown.write().await;
-                remaining.extend(cooldown.check(now.clone()).map(|d| ("Category", d)));
+                remaining.extend(
+                    cooldown
+                        .check(now.clone())
+                        .map(|d| ("gtav/category-cooldown", d)),
+                );
             }
         }
 
@@ -708,7 +739,7 @@ impl Handler {
             Some("randomize-weather") => Command::RandomizeWeather,
             Some("randomize-character") => Command::RandomizeCharacter,
             Some("randomize-doors") => Command::RandomizeDoors,
-            Some("license") => match license(ctx.rest(), &ctx).await {
+            Some("license") => match license(&self.messages, ctx.rest(), &ctx).await {
                 Some(license) => Command::License(license),
                 None => return Ok(None),
             },
@@ -717,15 +748,7 @@ impl Handler {
                 Command::Raw(ctx.rest().to_string())
             }
             Some(..) | None => {
-                respond!(
-                    ctx,
-                    "Available mods are: \
-                     randomize-color, \
-                     randomize-weather, \
-                     randomize-character, \
-                     license <license>. \
-                     See !chaos% for more details.",
-                );
+                respond!(ctx, message!(self.messages, "gtav/other-help"));
 
                 return Ok(None);
             }
@@ -747,7 +770,7 @@ impl Handler {
             Some("wanted") => match ctx.next().map(|s| str::parse(&s)) {
                 Some(Ok(n)) if n >= 1 && n <= 5 => Command::Wanted(n),
                 _ => {
-                    respond!(ctx, "Expected number between 1 and 5");
+                    respond!(ctx, message!(self.messages, "gtav/bad-wanted"));
                     return Ok(None);
                 }
             },
@@ -757,11 +780,11 @@ impl Handler {
                 None => Command::SpawnEnemy(1),
                 Some(Ok(n)) if n > 0 && n <= 5 => Command::SpawnEnemy(n),
                 Some(Ok(0)) => {
-                    respond!(ctx, "Please specify more than 0 enemies to spawn.");
+                    respond!(ctx, message!(self.messages, "gtav/no-enemies"));
                     return Ok(None);
                 }
                 Some(Ok(_)) => {
-                    respond!(ctx, "Cannot spawn more than 5 enemies.");
+                    respond!(ctx, message!(self.messages, "gtav/too-many-enemies"));
                     return Ok(None);
                 }
                 Some(Err(_)) => {
@@ -787,9 +810,7 @@ impl Handler {
 
                         respond!(
                             ctx,
-                            "You disable controls like `steering`. \
-                             Available controls to disable are: {controls}. ",
-                            controls = controls,
+                            message!(self.messages, "gtav/controls", controls = controls)
                         );
 
                         return Ok(None);
@@ -803,7 +824,7 @@ impl Handler {
             Some("taze") => Command::Taze,
             Some("taze-others") => Command::TazeOthers,
             _ => {
-                respond!(ctx, "See !chaos% for available punishments.");
+                respond!(ctx, message!(self.messages, "gtav/punish-help"));
 
                 return Ok(None);
             }
@@ -814,20 +835,28 @@ impl Handler {
 
     /// Handle the reward command.
     async fn handle_reward(&self, ctx: &mut command::Context) -> Result<Option<(Command, u32)>> {
+        let catalog = self.catalog.read().await;
+
         let command = match ctx.next().as_deref() {
-            Some("car") => Command::SpawnRandomVehicle(Vehicle::random_car()),
+            Some("car") => Command::SpawnRandomVehicle(Item::from(Vehicle::random_car())),
             Some("vehicle") => {
-                let vehicle = vehicle!(ctx, "!gtav reward vehicle");
+                let vehicle = vehicle!(ctx, self.messages, catalog, "!gtav reward vehicle");
                 Command::SpawnVehicle(vehicle)
             }
             Some("repair") => Command::Repair,
             Some("wanted") => Command::Wanted(0),
-            Some("parachute") => Command::GiveWeapon(Weapon::Parachute),
+            Some("parachute") => match catalog.weapon("parachute") {
+                Some(weapon) => Command::GiveWeapon(weapon),
+                None => {
+                    respond!(ctx, message!(self.messages, "gtav/no-parachute"));
+                    return Ok(None);
+                }
+            },
             Some("weapon") => {
-                let weapon = match ctx.next().and_then(Weapon::from_id) {
+                let weapon = match ctx.next().and_then(|s| catalog.weapon(&s)) {
                     Some(weapon) => weapon,
                     None => {
-                        respond!(ctx, "No such weapon, sorry :(.");
+                        respond!(ctx, message!(self.messages, "gtav/no-weapon"));
 
                         return Ok(None);
                     }
@@ -864,12 +893,7 @@ impl Handler {
                             .collect::<Vec<String>>()
                             .join(", ");
 
-                        respond!(
-                            ctx,
-                            "You give the streamer vehicle mods using for example `random`. \
-                             Available mods are: {mods}. ",
-                            mods = mods,
-                        );
+                        respond!(ctx, message!(self.messages, "gtav/mods", mods = mods));
 
                         return Ok(None);
                     }
@@ -882,13 +906,18 @@ impl Handler {
             Some("slow-down-time") => Command::SlowDownTime,
             Some("fire-proof") => Command::MakeFireProof(30f32),
             Some("change-current-vehicle") => {
-                let vehicle = vehicle!(ctx, "!gtav reward change-current-vehicle");
+                let vehicle = vehicle!(
+                    ctx,
+                    self.messages,
+                    catalog,
+                    "!gtav reward change-current-vehicle"
+                );
                 Command::ChangeCurrentVehicle(vehicle)
             }
             Some("skyfall") => Command::Skyfall,
             Some("reduce-gravity") => Command::ReduceGravity,
             _ => {
-                respond!(ctx, "See !chaos% for available rewards.");
+                respond!(ctx, message!(self.messages, "gtav/reward-help"));
                 return Ok(None);
             }
         };
@@ -899,6 +928,13 @@ impl Handler {
 
 #[async_trait]
 impl command::Handler for Handler {
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!gtav <reward|punish|other> <action>",
+            description: "Interact with the streamer's game of GTA V.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
         if !self.enabled.load().await {
             return Ok(());
@@ -908,7 +944,7 @@ impl command::Handler for Handler {
             .currency
             .load()
             .await
-            .ok_or_else(|| respond_err!("No currency configured for stream, sorry :("))?;
+            .ok_or_else(|| respond_err!("{}", message!(self.messages, "gtav/no-currency")))?;
 
         let (result, category_cooldown) = match ctx.next().as_deref() {
             Some("other") => {
@@ -926,13 +962,7 @@ impl command::Handler for Handler {
                 (command, Some(cooldown))
             }
             _ => {
-                respond!(
-                    ctx,
-                    "You have the following actions available: \
-                    reward - To reward the streamer, \
-                    punish - To punish the streamer,
-                    other - To do other kinds of modifications.",
-                );
+                respond!(ctx, message!(self.messages, "gtav/help"));
 
                 return Ok(());
             }
@@ -962,15 +992,11 @@ impl command::Handler for Handler {
         let bypass_cooldown = ctx.user.has_scope(Scope::GtavBypassCooldown).await;
 
         if !bypass_cooldown {
-            if let Some((what, remaining)) =
+            if let Some((key, remaining)) =
                 self.check_cooldown(&ctx, &command, category_cooldown).await
             {
-                respond!(
-                    ctx,
-                    "{} cooldown in effect, please wait at least {}!",
-                    what,
-                    compact_duration(remaining),
-                );
+                let remaining = compact_duration(remaining);
+                respond!(ctx, message!(self.messages, key, remaining = remaining));
 
                 return Ok(());
             }
@@ -998,14 +1024,15 @@ impl command::Handler for Handler {
             if balance < cost {
                 respond!(
                     ctx,
-                    "{prefix}\
-                        You need at least {limit} {currency} to reward the streamer, \
-                        you currently have {balance} {currency}. \
-                        Keep watching to earn more!",
-                    prefix = prefix,
-                    limit = cost,
-                    currency = currency.name,
-                    balance = balance,
+                    "{}{}",
+                    prefix,
+                    message!(
+                        self.messages,
+                        "gtav/insufficient-balance",
+                        limit = cost,
+                        currency = currency.name,
+                        balance = balance,
+                    )
                 );
 
                 return Ok(());
@@ -1017,22 +1044,17 @@ impl command::Handler for Handler {
         }
 
         if self.success_feedback.load().await {
-            let who = match ctx.user.display_name() {
-                Some(name) => name,
-                None => "Someone",
-            };
-
-            sender
-                .privmsg(format!(
-                    "{prefix}{user} {what} the streamer for {cost} {currency} by {command}",
-                    prefix = prefix,
-                    user = who,
-                    what = command.what(),
-                    command = command,
-                    cost = cost,
-                    currency = currency.name,
-                ))
-                .await;
+            let message = message!(
+                self.messages,
+                "gtav/success",
+                user = ctx.user.display_name(),
+                what = command.what(),
+                command = command.to_string(),
+                cost = cost,
+                currency = currency.name,
+            );
+
+            sender.privmsg(format!("{}{}", prefix, message)).await;
         }
 
         if tx.unbounded_send((ctx.user.clone(), id, command)).is_err() {
@@ -1044,15 +1066,15 @@ impl command::Handler for Handler {
 }
 
 /// Parse a license plate.Arc
-async fn license(input: &str, ctx: &command::Context) -> Option<String> {
+async fn license(messages: &Messages, input: &str, ctx: &command::Context) -> Option<String> {
     match input {
         "" => None,
         license if license.len() > 8 => {
-            respond!(ctx, "License plates only support up to 8 characters.");
+            respond!(ctx, message!(messages, "gtav/license-too-long"));
             None
         }
         license if !license.is_ascii() => {
-            respond!(ctx, "License plate can only contain ASCII characters.");
+            respond!(ctx, message!(messages, "gtav/license-not-ascii"));
             None
         }
         license => Some(license.to_string()),
@@ -1081,6 +1103,11 @@ impl super::Module for Module {
         let currency = injector.var().await?;
         let settings = settings.scoped("gtav");
 
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
         let default_reward_cooldown = Cooldown::from_duration(Duration::seconds(60));
         let default_punish_cooldown = Cooldown::from_duration(Duration::seconds(60));
         let default_per_user_cooldown = Cooldown::from_duration(Duration::seconds(60));
@@ -1117,10 +1144,34 @@ impl super::Module for Module {
             .or_default()
             .await?;
 
+        let (mut catalog_source_stream, mut catalog_source) =
+            settings.stream::<String>("catalog").optional().await?;
+        let (mut catalog_reload_stream, catalog_reload) = settings
+            .stream("catalog-reload")
+            .or_with(Duration::seconds(60 * 5))
+            .await?;
+
+        let client = reqwest::Client::builder()
+            .timeout(CATALOG_TIMEOUT)
+            .build()?;
+
+        let catalog = match catalog_source.as_deref() {
+            Some(source) => match Catalog::load(&client, source).await {
+                Ok(catalog) => catalog,
+                Err(e) => {
+                    log_error!(e, "failed to load gtav catalog from `{}`", source);
+                    Catalog::default()
+                }
+            },
+            None => Catalog::default(),
+        };
+
         let per_command_configs = settings::Var::new(HashMap::new());
-        *per_command_configs.write().await = commands_config.into_map();
+        *per_command_configs.write().await = commands_config.clone().into_map(&catalog);
+        let catalog = settings::Var::new(catalog);
 
         let player = injector.var().await?;
+        let game_rpc = injector.var::<game_rpc::GameRpc>().await?;
 
         let (tx, mut rx) = mpsc::unbounded();
 
@@ -1138,6 +1189,7 @@ impl super::Module for Module {
                 per_command_cooldown,
                 per_command_cooldowns: Mutex::new(Default::default()),
                 per_command_configs: per_command_configs.clone(),
+                catalog: catalog.clone(),
                 prefix,
                 other_percentage,
                 punish_percentage,
@@ -1145,6 +1197,7 @@ impl super::Module for Module {
                 success_feedback,
                 id_counter: AtomicUsize::new(0),
                 tx,
+                messages,
             },
         );
 
@@ -1153,7 +1206,23 @@ impl super::Module for Module {
             .connect(&str::parse::<SocketAddr>("127.0.0.1:7291")?)
             .await?;
 
+        let new_timer = |source: &Option<String>, reload: &Duration| {
+            if source.is_some() && !reload.is_empty() {
+                Some(tokio::time::interval_at(
+                    tokio::time::Instant::now() + reload.as_std(),
+                    reload.as_std(),
+                ))
+            } else {
+                None
+            }
+        };
+
+        let mut catalog_timer = new_timer(&catalog_source, &catalog_reload);
+
         let future = async move {
+            let mut commands_config = commands_config;
+            let mut catalog_reload = catalog_reload;
+
             let mut receiver = if enabled.load().await {
                 Some(&mut rx)
             } else {
@@ -1163,7 +1232,66 @@ impl super::Module for Module {
             loop {
                 futures::select! {
                     update = commands_config_stream.select_next_some() => {
-                        *per_command_configs.write().await = update.into_map();
+                        commands_config = update;
+                        let current = catalog.read().await;
+                        let update = commands_config.clone().into_map(&*current);
+                        *per_command_configs.write().await = update;
+                    }
+                    update = catalog_source_stream.select_next_some() => {
+                        catalog_source = update;
+                        catalog_timer = new_timer(&catalog_source, &catalog_reload);
+
+                        let update = match catalog_source.as_deref() {
+                            Some(source) => match Catalog::load(&client, source).await {
+                                Ok(update) => update,
+                                Err(e) => {
+                                    log_error!(
+                                        e,
+                                        "failed to load gtav catalog from `{}`",
+                                        source
+                                    );
+                                    continue;
+                                }
+                            },
+                            None => Catalog::default(),
+                        };
+
+                        let configs = commands_config.clone().into_map(&update);
+                        *per_command_configs.write().await = configs;
+                        *catalog.write().await = update;
+                    }
+                    update = catalog_reload_stream.select_next_some() => {
+                        catalog_reload = update;
+                        catalog_timer = new_timer(&catalog_source, &catalog_reload);
+                    }
+                    _ = catalog_timer.select_next_some() => {
+                        let source = match catalog_source.as_deref() {
+                            Some(source) => source,
+                            None => continue,
+                        };
+
+                        let update = match Catalog::load(&client, source).await {
+                            Ok(update) => update,
+                            Err(e) => {
+                                log_error!(
+                                    e,
+                                    "failed to reload gtav catalog from `{}`",
+                                    source
+                                );
+                                continue;
+                            }
+                        };
+
+                        // NB: only swap out the catalog if it changed, since
+                        // rebuilding command settings resets their cooldowns.
+                        if *catalog.read().await == update {
+                            continue;
+                        }
+
+                        log::info!("reloaded gtav catalog from `{}`", source);
+                        let configs = commands_config.clone().into_map(&update);
+                        *per_command_configs.write().await = configs;
+                        *catalog.write().await = update;
                     }
                     update = enabled_stream.select_next_some() => {
                         receiver = match update {
@@ -1175,8 +1303,29 @@ impl super::Module for Module {
                     }
                     command = receiver.next() => {
                         if let Some((user, id, command)) = command {
+                            let command = command.command();
+
+                            // NB: prefer ChaosMod connected through the game RPC channel,
+                            // falling back to the legacy UDP socket.
+                            if let Some(game_rpc) = game_rpc.load().await {
+                                let mut it = command.splitn(2, ' ');
+                                let name = it.next().unwrap_or_default();
+                                let args = it.next().unwrap_or_default();
+
+                                match game_rpc.send(INTEGRATION, user.name(), name, args) {
+                                    Ok(()) => {
+                                        log::info!("sent through game rpc: {}", command);
+                                        continue;
+                                    }
+                                    Err(game_rpc::SendError::NotConnected) => (),
+                                    Err(e) => {
+                                        log_warn!(e, "failed to send through game rpc, using udp");
+                                    }
+                                }
+                            }
+
                             let who = user.name().unwrap_or("unknown");
-                            let message = format!("{} {} {}", who, id, command.command());
+                            let message = format!("{} {} {}", who, id, command);
                             log::info!("sent: {}", message);
 
                             match socket.send(message.as_bytes()).await {
@@ -0,0 +1,242 @@
+//! A catalog of vehicles, weapons, and command overrides which is loaded from
+//! a JSON file or a remote URL.
+//!
+//! This permits adding new vehicles and weapons, like the ones introduced in
+//! DLCs, and tweaking prices and cooldowns without a new release.
+//!
+//! An example catalog looks like this:
+//!
+//! ```json
+//! {
+//!     "vehicles": [{"id": "toreador", "name": "Toreador", "cost": 80}],
+//!     "weapons": [{"id": "minigun", "enabled": false}],
+//!     "commands": [{"name": "Wanted", "cost": 100, "cooldown": "5m"}]
+//! }
+//! ```
+
+use super::vehicle::Vehicle;
+use super::weapon::Weapon;
+use super::CommandConfig;
+use anyhow::{bail, Result};
+use std::fmt;
+
+/// The cost of entries added by the catalog which does not specify a cost.
+const DEFAULT_COST: u32 = 50;
+
+/// A catalog loaded from a data file.
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize)]
+pub struct Catalog {
+    /// Vehicles to add or override.
+    #[serde(default)]
+    vehicles: Vec<Entry>,
+    /// Weapons to add or override.
+    #[serde(default)]
+    weapons: Vec<Entry>,
+    /// Command-specific configuration.
+    ///
+    /// These are overridden by the `gtav/command-configs` setting.
+    #[serde(default)]
+    commands: Vec<CommandConfig>,
+}
+
+/// A single vehicle or weapon in the catalog.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+struct Entry {
+    /// The identifier sent to ChaosMod.
+    id: String,
+    /// Human-readable name of the entry.
+    #[serde(default)]
+    name: Option<String>,
+    /// The cost of the entry.
+    #[serde(default)]
+    cost: Option<u32>,
+    /// Set to `false` to make a built-in entry unavailable.
+    #[serde(default)]
+    enabled: Option<bool>,
+}
+
+/// A vehicle or weapon resolved through the catalog.
+#[derive(Debug, Clone)]
+pub struct Item {
+    id: String,
+    display: String,
+    cost: u32,
+}
+
+impl Item {
+    /// Human-readable display of this item.
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+
+    /// Get the cost of the item.
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+}
+
+impl From<Vehicle> for Item {
+    fn from(vehicle: Vehicle) -> Self {
+        Self {
+            id: vehicle.to_string(),
+            display: vehicle.display(),
+            cost: vehicle.cost(),
+        }
+    }
+}
+
+impl From<Weapon> for Item {
+    fn from(weapon: Weapon) -> Self {
+        Self {
+            id: weapon.to_string(),
+            display: weapon.display(),
+            cost: weapon.cost(),
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.id.fmt(fmt)
+    }
+}
+
+impl Catalog {
+    /// Parse a catalog from JSON.
+    pub fn parse(bytes: &[u8]) -> Result<Catalog> {
+        let catalog = serde_json::from_slice::<Catalog>(bytes)?;
+
+        for entry in catalog.vehicles.iter().chain(catalog.weapons.iter()) {
+            if entry.id.is_empty() || entry.id.contains(char::is_whitespace) {
+                bail!("bad catalog id `{}`", entry.id);
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    /// Load a catalog from the given source, which is either a path to a local
+    /// file or an `http://` or `https://` URL.
+    pub async fn load(client: &reqwest::Client, source: &str) -> Result<Catalog> {
+        let bytes = if source.starts_with("http://") || source.starts_with("https://") {
+            let res = client.get(source).send().await?;
+
+            if !res.status().is_success() {
+                bail!("bad response: {}", res.status());
+            }
+
+            res.bytes().await?.to_vec()
+        } else {
+            tokio::fs::read(source).await?
+        };
+
+        Self::parse(&bytes)
+    }
+
+    /// Look up the vehicle with the given id.
+    pub fn vehicle(&self, id: &str) -> Option<Item> {
+        lookup(&self.vehicles, id, |id| Vehicle::from_id(id))
+    }
+
+    /// Look up the weapon with the given id.
+    pub fn weapon(&self, id: &str) -> Option<Item> {
+        lookup(&self.weapons, id, |id| Weapon::from_id(id))
+    }
+
+    /// Vehicles which are added by the catalog.
+    pub fn added_vehicles(&self) -> Vec<&str> {
+        self.vehicles
+            .iter()
+            .filter(|e| e.enabled.unwrap_or(true))
+            .filter(|e| Vehicle::from_id(e.id.to_lowercase()).is_none())
+            .map(|e| e.id.as_str())
+            .collect()
+    }
+
+    /// Command-specific configuration from the catalog.
+    pub(super) fn commands(&self) -> &[CommandConfig] {
+        &self.commands
+    }
+}
+
+/// Look up an entry, falling back to the built-in entry if it's not in the
+/// catalog.
+fn lookup<T>(entries: &[Entry], id: &str, builtin: impl FnOnce(&str) -> Option<T>) -> Option<Item>
+where
+    T: Into<Item>,
+{
+    let id = id.to_lowercase();
+    let builtin = builtin(&id).map(Into::into);
+
+    let entry = match entries.iter().find(|e| e.id.to_lowercase() == id) {
+        Some(entry) => entry,
+        None => return builtin,
+    };
+
+    if !entry.enabled.unwrap_or(true) {
+        return None;
+    }
+
+    let mut item = builtin.unwrap_or_else(|| Item {
+        id,
+        display: String::new(),
+        cost: DEFAULT_COST,
+    });
+
+    if let Some(name) = entry.name.as_ref() {
+        item.display = format!("a {}!", name);
+    } else if item.display.is_empty() {
+        item.display = format!("a {}!", item.id);
+    }
+
+    if let Some(cost) = entry.cost {
+        item.cost = cost;
+    }
+
+    Some(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Catalog;
+
+    #[test]
+    fn test_lookup() {
+        let catalog = Catalog::parse(
+            br#"{
+                "vehicles": [
+                    {"id": "toreador", "name": "Toreador", "cost": 80},
+                    {"id": "adder", "cost": 10},
+                    {"id": "tank", "enabled": false}
+                ],
+                "weapons": [{"id": "Minigun", "cost": 200}]
+            }"#,
+        )
+        .unwrap();
+
+        let toreador = catalog.vehicle("Toreador").unwrap();
+        assert_eq!("toreador", toreador.to_string());
+        assert_eq!("a Toreador!", toreador.display());
+        assert_eq!(80, toreador.cost());
+
+        let adder = catalog.vehicle("adder").unwrap();
+        assert_eq!("a adder!", adder.display());
+        assert_eq!(10, adder.cost());
+
+        assert!(catalog.vehicle("tank").is_none());
+        assert_eq!(50, catalog.vehicle("akula").unwrap().cost());
+        assert!(catalog.vehicle("nope").is_none());
+
+        assert_eq!(200, catalog.weapon("minigun").unwrap().cost());
+        // aliases resolve to the built-in id.
+        assert_eq!("assaultrifle", catalog.weapon("ak47").unwrap().to_string());
+
+        assert_eq!(vec!["toreador"], catalog.added_vehicles());
+    }
+
+    #[test]
+    fn test_parse_bad_id() {
+        assert!(Catalog::parse(br#"{"vehicles": [{"id": "a b"}]}"#).is_err());
+        assert!(Catalog::parse(br#"{"weapons": [{"id": ""}]}"#).is_err());
+    }
+}
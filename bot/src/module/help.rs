@@ -1,38 +1,105 @@
 use crate::command;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use url::Url;
 
 const DEFAULT_URL: &str = "https://setbac.tv/help";
+/// The number of commands listed on each page.
+const PAGE_SIZE: usize = 10;
 
 /// Handler for the !help command.
 pub struct Help {
     enabled: settings::Var<bool>,
+    messages: Messages,
     url: settings::Var<Url>,
 }
 
+impl Help {
+    /// List the commands available to the user on the given page.
+    async fn list(&self, ctx: &mut command::Context, page: usize) -> Result<()> {
+        let mut commands = Vec::new();
+
+        for (command, handler) in ctx.handlers().iter() {
+            if is_allowed(ctx, &**handler).await {
+                commands.push(format!("!{}", command));
+            }
+        }
+
+        commands.sort();
+
+        let (commands, pages) = match paginate(&commands, page, PAGE_SIZE) {
+            Some(result) => result,
+            None => respond_bail!("{}", message!(self.messages, "help/no-such-page")),
+        };
+
+        let next = if page < pages { Some(page + 1) } else { None };
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "help/list",
+                page = page,
+                pages = pages,
+                commands = commands.join(", "),
+                url = self.url.load().await.to_string(),
+                next = next,
+            )
+        );
+
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl command::Handler for Help {
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!help [command|page]",
+            description: "List available commands, or show how a command is used.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
         }
 
         let next = ctx.next();
-        let mut url = self.url.load().await;
 
-        match next.as_deref() {
-            None => {
-                respond!(
-                    ctx,
-                    "You can find documentation for each command at {}",
-                    url
-                );
+        let command = match next.as_deref() {
+            None => return self.list(ctx, 1).await,
+            Some(page) if page.chars().all(|c| c.is_ascii_digit()) => {
+                let page = str::parse::<usize>(page)
+                    .map_err(|_| respond_err!("{}", message!(self.messages, "help/bad-page")))?;
+
+                return self.list(ctx, page).await;
+            }
+            Some(command) => command.trim_start_matches('!'),
+        };
+
+        let usage = match ctx.handlers().resolve(ctx.channel(), command).await {
+            Some(handler) => {
+                if is_allowed(ctx, &*handler).await {
+                    handler.usage()
+                } else {
+                    None
+                }
             }
-            Some(command) => {
+            None => None,
+        };
+
+        match usage {
+            Some(usage) => {
+                respond!(ctx, "{} - {}", usage.usage, usage.description);
+            }
+            None => {
+                let mut url = self.url.load().await;
                 url.query_pairs_mut().append_pair("q", command);
-                respond!(ctx, format!("For help on that, go to {}", url));
+                let url = url.to_string();
+                respond!(ctx, message!(self.messages, "help/see-url", url = url));
             }
         }
 
@@ -40,6 +107,28 @@ impl command::Handler for Help {
     }
 }
 
+/// Test if the user in the given context is allowed to use the handler.
+async fn is_allowed(ctx: &command::Context, handler: &dyn command::Handler) -> bool {
+    match handler.scope() {
+        Some(scope) => ctx.user.has_scope(scope).await,
+        None => true,
+    }
+}
+
+/// Get the given page of items, counting from one, together with the total
+/// number of pages.
+fn paginate<T>(items: &[T], page: usize, size: usize) -> Option<(&[T], usize)> {
+    let pages = usize::max((items.len() + size - 1) / size, 1);
+
+    if page == 0 || page > pages {
+        return None;
+    }
+
+    let start = (page - 1) * size;
+    let end = usize::min(start + size, items.len());
+    Some((&items[start..end], pages))
+}
+
 pub struct Module;
 
 #[async_trait]
@@ -48,18 +137,30 @@ impl super::Module for Module {
         "help"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("help/enabled", true))
+    }
+
     async fn hook(
         &self,
         module::HookContext {
-            handlers, settings, ..
+            handlers,
+            settings,
+            injector,
+            ..
         }: module::HookContext<'_>,
     ) -> Result<()> {
         let default_url = Url::parse(DEFAULT_URL)?;
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
 
         handlers.insert(
             "help",
             Help {
                 enabled: settings.var("help/enabled", true).await?,
+                messages,
                 url: settings.var("help/url", default_url).await?,
             },
         );
@@ -67,3 +168,21 @@ impl super::Module for Module {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::paginate;
+
+    #[test]
+    fn test_paginate() {
+        let items = [1, 2, 3, 4, 5];
+
+        assert_eq!(Some((&items[..2], 3)), paginate(&items, 1, 2));
+        assert_eq!(Some((&items[4..], 3)), paginate(&items, 3, 2));
+        assert_eq!(None, paginate(&items, 4, 2));
+        assert_eq!(None, paginate(&items, 0, 2));
+
+        let empty: [u32; 0] = [];
+        assert_eq!(Some((&empty[..], 1)), paginate(&empty, 1, 2));
+    }
+}
@@ -0,0 +1,199 @@
+//! Drive connected game integrations from chat through the game RPC channel.
+
+use crate::auth;
+use crate::command;
+use crate::game_rpc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+
+/// How long to wait for an integration to reply to a command.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Handler for the `!ingame` command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    game_rpc: injector::Var<Option<game_rpc::GameRpc>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::InGame)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!ingame [integration] [command] [args]",
+            description: "Send a command to a game integration.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let game_rpc = match self.game_rpc.load().await {
+            Some(game_rpc) => game_rpc,
+            None => return Ok(()),
+        };
+
+        let integration = match ctx.next() {
+            Some(integration) => integration,
+            None => {
+                let connected = game_rpc.connected();
+
+                if connected.is_empty() {
+                    respond!(ctx, message!(self.messages, "ingame/none-connected"));
+                } else {
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "ingame/connected",
+                            games = connected.join(", ")
+                        )
+                    );
+                }
+
+                return Ok(());
+            }
+        };
+
+        let command = match ctx.next() {
+            Some(command) => command,
+            None => {
+                match game_rpc.commands(&integration) {
+                    None => respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "ingame/not-connected",
+                            integration = integration
+                        )
+                    ),
+                    Some(commands) if commands.is_empty() => respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "ingame/no-commands",
+                            integration = integration
+                        )
+                    ),
+                    Some(commands) => respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "ingame/commands",
+                            integration = integration,
+                            commands = commands.join(", ")
+                        )
+                    ),
+                }
+
+                return Ok(());
+            }
+        };
+
+        let (id, reply) = match game_rpc.call(&integration, ctx.user.name(), &command, ctx.rest()) {
+            Ok(call) => call,
+            Err(game_rpc::SendError::NotConnected) => respond_bail!(
+                "{}",
+                message!(
+                    self.messages,
+                    "ingame/not-connected",
+                    integration = integration
+                )
+            ),
+            Err(game_rpc::SendError::NoSuchCommand) => respond_bail!(
+                "{}",
+                message!(
+                    self.messages,
+                    "ingame/unsupported",
+                    integration = integration,
+                    command = command
+                )
+            ),
+        };
+
+        let reply = match tokio::time::timeout(REPLY_TIMEOUT, reply).await {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(..)) => respond_bail!(
+                "{}",
+                message!(
+                    self.messages,
+                    "ingame/disconnected",
+                    integration = integration
+                )
+            ),
+            Err(..) => {
+                game_rpc.cancel(&integration, id);
+                respond_bail!(
+                    "{}",
+                    message!(self.messages, "ingame/timeout", integration = integration)
+                )
+            }
+        };
+
+        match reply.message {
+            Some(message) => respond!(ctx, "{}", message),
+            None if !reply.ok => respond!(
+                ctx,
+                message!(self.messages, "ingame/failed", command = command)
+            ),
+            None => (),
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "ingame"
+    }
+
+    fn flag(&self) -> Option<&'static str> {
+        Some("ingame")
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("ingame/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("ingame");
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "ingame",
+            Handler {
+                enabled: settings.var("enabled", false).await?,
+                game_rpc: injector.var().await?,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -1,78 +1,99 @@
 /// Helper macro for constructing an enable command.
 macro_rules! command_enable {
-    ($ctx:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
+    ($ctx:expr, $messages:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
         $ctx.check_scope(crate::auth::Scope::$edit_scope).await?;
 
         let name = match $ctx.next() {
             Some(name) => name,
             None => {
-                respond!($ctx, "Expected <name> to enable");
+                respond!($ctx, message!($messages, "manage/expected-name-enable"));
                 return Ok(());
             }
         };
 
         if !$db.enable($ctx.channel(), &name).await? {
-            respond!($ctx, "No {} named `{}`.", $what, name);
+            respond!(
+                $ctx,
+                message!($messages, "manage/missing", what = $what, name = name)
+            );
             return Ok(());
         }
 
-        respond!($ctx, "Enabled {} `{}`", $what, name);
+        respond!(
+            $ctx,
+            message!($messages, "manage/enabled", what = $what, name = name)
+        );
     }};
 }
 
 /// Helper macro for constructing an disable command.
 macro_rules! command_disable {
-    ($ctx:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
+    ($ctx:expr, $messages:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
         $ctx.check_scope(crate::auth::Scope::$edit_scope).await?;
 
         let name = match $ctx.next() {
             Some(name) => name,
             None => {
-                respond!($ctx, "Expected <name> to disable");
+                respond!($ctx, message!($messages, "manage/expected-name-disable"));
                 return Ok(());
             }
         };
 
         if !$db.disable($ctx.channel(), &name).await? {
-            respond!($ctx, "No {} named `{}`.", $what, name);
+            respond!(
+                $ctx,
+                message!($messages, "manage/missing", what = $what, name = name)
+            );
             return Ok(());
         }
 
-        respond!($ctx, "Disabled {} `{}`", $what, name);
+        respond!(
+            $ctx,
+            message!($messages, "manage/disabled", what = $what, name = name)
+        );
     }};
 }
 
 /// Helper macro for constructing a clear-group command.
 macro_rules! command_clear_group {
-    ($ctx:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
+    ($ctx:expr, $messages:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
         $ctx.check_scope(crate::auth::Scope::$edit_scope).await?;
 
         let name = match $ctx.next() {
             Some(name) => name,
             None => {
-                respond!($ctx, "Expected <name> to remove from a group");
+                respond!(
+                    $ctx,
+                    message!($messages, "manage/expected-name-clear-group")
+                );
                 return Ok(());
             }
         };
 
         if !$db.edit_group($ctx.channel(), &name, None).await? {
-            respond!($ctx, "No {} named `{}`.", $what, name);
+            respond!(
+                $ctx,
+                message!($messages, "manage/missing", what = $what, name = name)
+            );
             return Ok(());
         }
 
-        respond!($ctx, "Removed {} `{}` from its group", $what, name);
+        respond!(
+            $ctx,
+            message!($messages, "manage/group-cleared", what = $what, name = name)
+        );
     }};
 }
 
 /// Helper macro for constructing a build command.
 macro_rules! command_group {
-    ($ctx:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
+    ($ctx:expr, $messages:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
         $ctx.check_scope(crate::auth::Scope::$edit_scope).await?;
 
         let name = match $ctx.next() {
             Some(name) => name,
             None => {
-                respond!($ctx, "Expected <name> to add to a group");
+                respond!($ctx, message!($messages, "manage/expected-name-group"));
                 return Ok(());
             }
         };
@@ -83,7 +104,10 @@ macro_rules! command_group {
                 let thing = match $db.get($ctx.channel(), &name).await {
                     Some(thing) => thing,
                     None => {
-                        respond!($ctx, "No {} named `{}`", $what, name);
+                        respond!(
+                            $ctx,
+                            message!($messages, "manage/missing", what = $what, name = name)
+                        );
                         return Ok(());
                     }
                 };
@@ -92,18 +116,24 @@ macro_rules! command_group {
                     Some(group) => {
                         respond!(
                             $ctx,
-                            "{} `{}` belongs to group: {}",
-                            $what,
-                            thing.key.name,
-                            group
+                            message!(
+                                $messages,
+                                "manage/group",
+                                what = $what,
+                                name = thing.key.name,
+                                group = group
+                            )
                         );
                     }
                     None => {
                         respond!(
                             $ctx,
-                            "{} `{}` does not belong to a group",
-                            $what,
-                            thing.key.name
+                            message!(
+                                $messages,
+                                "manage/no-group",
+                                what = $what,
+                                name = thing.key.name
+                            )
                         );
                     }
                 }
@@ -116,16 +146,25 @@ macro_rules! command_group {
             .edit_group($ctx.channel(), &name, Some(group.clone()))
             .await?
         {
-            respond!($ctx, "no such {}", $what);
+            respond!($ctx, message!($messages, "manage/no-such", what = $what));
             return Ok(());
         }
 
-        respond!($ctx, "set group for {} `{}` to {}", $what, name, group);
+        respond!(
+            $ctx,
+            message!(
+                $messages,
+                "manage/group-set",
+                what = $what,
+                name = name,
+                group = group
+            )
+        );
     }};
 }
 
 macro_rules! command_list {
-    ($ctx:expr, $db:expr, $what:expr) => {{
+    ($ctx:expr, $messages:expr, $db:expr, $what:expr) => {{
         let mut names = $db
             .list($ctx.channel())
             .await
@@ -134,7 +173,7 @@ macro_rules! command_list {
             .collect::<Vec<_>>();
 
         if names.is_empty() {
-            respond!($ctx, "No custom {}.", $what);
+            respond!($ctx, message!($messages, "manage/list-empty", what = $what));
         } else {
             names.sort();
             respond!($ctx, "{}", names.join(", "));
@@ -143,57 +182,75 @@ macro_rules! command_list {
 }
 
 macro_rules! command_delete {
-    ($ctx:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
+    ($ctx:expr, $messages:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
         $ctx.check_scope(crate::auth::Scope::$edit_scope).await?;
 
         let name = match $ctx.next() {
             Some(name) => name,
             None => {
-                respond!($ctx, "Expected <name>");
+                respond!($ctx, message!($messages, "manage/expected-name"));
                 return Ok(());
             }
         };
 
         if $db.delete($ctx.channel(), &name).await? {
-            respond!($ctx, "Deleted {} `{}`", $what, name);
+            respond!(
+                $ctx,
+                message!($messages, "manage/deleted", what = $what, name = name)
+            );
         } else {
-            respond!($ctx, "No such {}", $what);
+            respond!($ctx, message!($messages, "manage/no-such", what = $what));
         }
     }};
 }
 
 macro_rules! command_rename {
-    ($ctx:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
+    ($ctx:expr, $messages:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
         $ctx.check_scope(crate::auth::Scope::$edit_scope).await?;
 
         let (from, to) = match ($ctx.next(), $ctx.next()) {
             (Some(from), Some(to)) => (from, to),
             _ => {
-                respond!($ctx, "Expected <from> <to>");
+                respond!($ctx, message!($messages, "manage/expected-rename"));
                 return Ok(());
             }
         };
 
         match $db.rename($ctx.channel(), &from, &to).await {
             Ok(()) => {
-                respond!($ctx, "Renamed {} {} -> {}.", $what, from, to);
+                respond!(
+                    $ctx,
+                    message!(
+                        $messages,
+                        "manage/renamed",
+                        what = $what,
+                        from = from,
+                        to = to
+                    )
+                );
             }
             Err(crate::db::RenameError::Conflict) => {
-                respond!($ctx, "Already an {} named `{}`.", $what, to);
+                respond!(
+                    $ctx,
+                    message!($messages, "manage/conflict", what = $what, name = to)
+                );
             }
             Err(crate::db::RenameError::Missing) => {
-                respond!($ctx, "No {} named `{}`.", $what, from);
+                respond!(
+                    $ctx,
+                    message!($messages, "manage/missing", what = $what, name = from)
+                );
             }
         }
     }};
 }
 
 macro_rules! command_show {
-    ($ctx:expr, $db:expr, $what:expr) => {{
+    ($ctx:expr, $messages:expr, $db:expr, $what:expr) => {{
         let name = match $ctx.next() {
             Some(name) => name,
             None => {
-                respond!($ctx, "Expected <name> to show");
+                respond!($ctx, message!($messages, "manage/expected-name-show"));
                 return Ok(());
             }
         };
@@ -205,47 +262,50 @@ macro_rules! command_show {
                 respond!($ctx, format!("{} -> {}", thing.key.name, thing));
             }
             None => {
-                respond!($ctx, format!("No {} named `{}`.", $what, name));
+                respond!(
+                    $ctx,
+                    message!($messages, "manage/missing", what = $what, name = name)
+                );
             }
         }
     }};
 }
 
 macro_rules! command_base {
-    ($ctx:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
+    ($ctx:expr, $messages:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
         let arg = $ctx.next();
 
         match arg.as_deref() {
             Some("clear-group") => {
-                command_clear_group!($ctx, $db, $what, $edit_scope);
+                command_clear_group!($ctx, $messages, $db, $what, $edit_scope);
                 return Ok(());
             }
             Some("group") => {
-                command_group!($ctx, $db, $what, $edit_scope);
+                command_group!($ctx, $messages, $db, $what, $edit_scope);
                 return Ok(());
             }
             Some("enable") => {
-                command_enable!($ctx, $db, $what, $edit_scope);
+                command_enable!($ctx, $messages, $db, $what, $edit_scope);
                 return Ok(());
             }
             Some("disable") => {
-                command_disable!($ctx, $db, $what, $edit_scope);
+                command_disable!($ctx, $messages, $db, $what, $edit_scope);
                 return Ok(());
             }
             Some("list") => {
-                command_list!($ctx, $db, $what);
+                command_list!($ctx, $messages, $db, $what);
                 return Ok(());
             }
             Some("delete") => {
-                command_delete!($ctx, $db, $what, $edit_scope);
+                command_delete!($ctx, $messages, $db, $what, $edit_scope);
                 return Ok(());
             }
             Some("rename") => {
-                command_rename!($ctx, $db, $what, $edit_scope);
+                command_rename!($ctx, $messages, $db, $what, $edit_scope);
                 return Ok(());
             }
             Some("show") => {
-                command_show!($ctx, $db, $what);
+                command_show!($ctx, $messages, $db, $what);
                 return Ok(());
             }
             _ => arg,
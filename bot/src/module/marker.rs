@@ -0,0 +1,134 @@
+use crate::api;
+use crate::auth;
+use crate::command;
+use crate::db;
+use crate::messages::Messages;
+use crate::module;
+use crate::player;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::utils;
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+
+/// Handler for the `!marker` command.
+pub struct Marker {
+    pub enabled: settings::Var<bool>,
+    pub stream_info: stream_info::StreamInfo,
+    pub markers: injector::Var<Option<db::Markers>>,
+    pub song: injector::Var<Option<player::Song>>,
+    pub streamer_twitch: api::Twitch,
+    pub messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Marker {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Marker)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!marker [description]",
+            description: "Create a stream marker, to make it easier to find highlights later.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let description = match ctx.rest().trim() {
+            "" => None,
+            other => Some(other.to_string()),
+        };
+
+        let stream_user = self.stream_info.user.clone();
+
+        let marker = self
+            .streamer_twitch
+            .create_stream_marker(&stream_user.id, description.as_deref())
+            .await?;
+
+        let marker = match marker {
+            Some(marker) => marker,
+            None => {
+                respond!(ctx, message!(self.messages, "marker/failed"));
+                log::error!("created marker, but API returned nothing");
+                return Ok(());
+            }
+        };
+
+        let position = utils::compact_duration(Duration::from_secs(marker.position_seconds));
+        respond!(
+            ctx,
+            message!(self.messages, "marker/created", position = position)
+        );
+
+        if let Some(markers) = self.markers.load().await {
+            let song = self.song.load().await.map(|song| song.item.what());
+
+            let marker = db::AddMarker {
+                marker_id: marker.id,
+                position_seconds: marker.position_seconds as i64,
+                description,
+                user: ctx.user.name().map(String::from),
+                song,
+            };
+
+            if let Err(e) = markers.push(marker).await {
+                log_error!(e, "Failed to store marker");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "marker"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("marker/enabled", true))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            stream_info,
+            streamer_twitch,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("marker");
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "marker",
+            Marker {
+                enabled: settings.var("enabled", true).await?,
+                stream_info: stream_info.clone(),
+                markers: injector.var().await?,
+                song: injector.var().await?,
+                streamer_twitch: streamer_twitch.clone(),
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -4,17 +4,24 @@ use crate::api;
 use crate::auth;
 use crate::command;
 use crate::irc;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
+use crate::storage::Cache;
 use crate::stream_info;
+use crate::template::Template;
 use crate::utils;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::Utc;
 
+static DEFAULT_SHOUTOUT_TEMPLATE: &str =
+    "Go check out {{name}} at {{url}}{{#if game}}, they were last playing {{game}}{{/if}}!";
+
 /// Handler for the `!uptime` command.
 pub struct Uptime {
     pub enabled: settings::Var<bool>,
     pub stream_info: stream_info::StreamInfo,
+    pub messages: Messages,
 }
 
 #[async_trait]
@@ -23,6 +30,13 @@ impl command::Handler for Uptime {
         Some(auth::Scope::Uptime)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!uptime",
+            description: "Show how long the stream has been live.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
@@ -44,13 +58,169 @@ impl command::Handler for Uptime {
                 let uptime =
                     utils::compact_duration((now - *started_at).to_std().unwrap_or_default());
 
-                respond!(ctx, "Stream has been live for {uptime}.", uptime = uptime);
+                respond!(ctx, message!(self.messages, "uptime/live", uptime = uptime));
             }
             Some(_) => {
-                respond!(ctx, "Stream is live, but start time is weird!");
+                respond!(ctx, message!(self.messages, "uptime/weird-start"));
+            }
+            None => {
+                respond!(ctx, message!(self.messages, "uptime/not-live"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Key used for cached Twitch lookups.
+#[derive(serde::Serialize)]
+#[serde(tag = "method")]
+enum Key<'a> {
+    UserByLogin { login: &'a str },
+    Follow { from_id: &'a str, to_id: &'a str },
+}
+
+/// Twitch lookups for `!followage`, `!accountage`, and `!pickwinner`, which
+/// are cached since they tend to be spammed.
+#[derive(Clone)]
+pub struct CachedTwitch {
+    cache: injector::Var<Option<Cache>>,
+    twitch: api::Twitch,
+}
+
+impl CachedTwitch {
+    /// How long users are cached for.
+    const USER_TTL_HOURS: i64 = 24;
+    /// How long follows are cached for.
+    ///
+    /// NB: kept short since users who are told that they aren't following
+    /// are likely to follow and try again.
+    const FOLLOW_TTL_MINUTES: i64 = 10;
+
+    /// Construct cached lookups using the given Twitch client.
+    pub fn new(cache: injector::Var<Option<Cache>>, twitch: api::Twitch) -> Self {
+        Self { cache, twitch }
+    }
+
+    /// Get cached information on a user.
+    async fn user_by_login(&self, login: &str) -> Result<Option<api::twitch::User>> {
+        self.wrap(
+            Key::UserByLogin { login },
+            chrono::Duration::hours(Self::USER_TTL_HOURS),
+            self.twitch.user_by_login(login),
+        )
+        .await
+    }
+
+    /// Get the cached follow relationship between two users.
+    pub async fn follow(&self, from_id: &str, to_id: &str) -> Result<Option<api::twitch::Follow>> {
+        self.wrap(
+            Key::Follow { from_id, to_id },
+            chrono::Duration::minutes(Self::FOLLOW_TTL_MINUTES),
+            self.twitch.follow(from_id, to_id),
+        )
+        .await
+    }
+
+    /// Wrap the given future in the cache, if it's available.
+    async fn wrap<T>(
+        &self,
+        key: Key<'_>,
+        age: chrono::Duration,
+        future: impl Future<Output = Result<T>>,
+    ) -> Result<T>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        match self.cache.load().await {
+            Some(cache) => cache.namespaced(&"twitch")?.wrap(key, age, future).await,
+            None => future.await,
+        }
+    }
+}
+
+/// Get the login of the user given as the next argument, or the user running
+/// the command if there is none.
+fn target_login(ctx: &mut command::Context) -> Result<String> {
+    if let Some(login) = ctx.next() {
+        return Ok(login.trim_start_matches('@').to_lowercase());
+    }
+
+    match ctx.user.name() {
+        Some(name) => Ok(name.to_string()),
+        None => respond_bail!("Expected: <user>"),
+    }
+}
+
+/// Handler for the `!followage` command.
+pub struct Followage {
+    pub enabled: settings::Var<bool>,
+    pub twitch: CachedTwitch,
+    pub messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Followage {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Followage)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!followage [user]",
+            description: "Show how long you or another user has been following.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let login = target_login(ctx)?;
+        let streamer = ctx.user.streamer();
+
+        if login == streamer.name {
+            respond_bail!(
+                "{}",
+                message!(
+                    self.messages,
+                    "followage/streamer",
+                    user = streamer.display_name
+                )
+            );
+        }
+
+        let user = match self.twitch.user_by_login(&login).await? {
+            Some(user) => user,
+            None => respond_bail!(
+                "{}",
+                message!(self.messages, "followage/no-such-user", user = login)
+            ),
+        };
+
+        match self.twitch.follow(&user.id, &streamer.id).await? {
+            Some(follow) => {
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "followage/following",
+                        user = user.display_name,
+                        age = utils::human_age(follow.followed_at, Utc::now()),
+                        since = follow.followed_at.format("%Y-%m-%d").to_string(),
+                    )
+                );
             }
             None => {
-                respond!(ctx, "Stream is not live right now, try again later!");
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "followage/not-following",
+                        user = user.display_name
+                    )
+                );
             }
         }
 
@@ -58,24 +228,86 @@ impl command::Handler for Uptime {
     }
 }
 
+/// Handler for the `!accountage` command.
+pub struct AccountAge {
+    pub enabled: settings::Var<bool>,
+    pub twitch: CachedTwitch,
+    pub messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for AccountAge {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::AccountAge)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!accountage [user]",
+            description: "Show how old your or another user's account is.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let login = target_login(ctx)?;
+
+        let user = match self.twitch.user_by_login(&login).await? {
+            Some(user) => user,
+            None => respond_bail!(
+                "{}",
+                message!(self.messages, "accountage/no-such-user", user = login)
+            ),
+        };
+
+        let created_at = match user.created_at {
+            Some(created_at) => created_at,
+            None => respond_bail!(
+                "{}",
+                message!(
+                    self.messages,
+                    "accountage/unknown",
+                    user = user.display_name
+                )
+            ),
+        };
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "accountage/created",
+                user = user.display_name,
+                age = utils::human_age(created_at, Utc::now()),
+                date = created_at.format("%Y-%m-%d").to_string(),
+            )
+        );
+
+        Ok(())
+    }
+}
+
 /// Handler for the `!title` command.
 pub struct Title {
     pub enabled: settings::Var<bool>,
-    pub stream_info: stream_info::StreamInfo,
-    pub twitch: api::Twitch,
+    pub channel: stream_info::Channel,
+    pub messages: Messages,
 }
 
 impl Title {
     /// Handle the title command.
     async fn show(&self, user: &irc::User) {
-        let title = self.stream_info.data.read().title.clone();
+        let title = self.channel.info().title;
 
         match title {
             Some(title) => {
                 user.respond(title).await;
             }
             None => {
-                user.respond("Stream is not live right now, try again later!")
+                user.respond(message!(self.messages, "title/not-live"))
                     .await;
             }
         }
@@ -88,6 +320,13 @@ impl command::Handler for Title {
         Some(auth::Scope::Title)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!title [title]",
+            description: "Show or set the title of the stream.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
@@ -99,42 +338,32 @@ impl command::Handler for Title {
             self.show(&ctx.user).await;
         } else {
             ctx.check_scope(auth::Scope::TitleEdit).await?;
-
-            let user = ctx.user.clone();
-
-            let mut request = api::twitch::UpdateChannelRequest::default();
-            request.channel.status = Some(rest.to_string());
-            self.twitch
-                .update_channel(&user.streamer().id, request)
-                .await?;
-            self.stream_info
-                .refresh_channel(&self.twitch, user.streamer())
-                .await?;
+            self.channel.set_title(rest).await?;
+            respond!(ctx, message!(self.messages, "title/updated", title = rest));
         }
 
         Ok(())
     }
 }
 
-/// Handler for the `!title` command.
+/// Handler for the `!game` command.
 pub struct Game {
     pub enabled: settings::Var<bool>,
-    pub stream_info: stream_info::StreamInfo,
-    pub twitch: api::Twitch,
+    pub channel: stream_info::Channel,
+    pub messages: Messages,
 }
 
 impl Game {
     /// Handle the game command.
     async fn show(&self, user: &irc::User) {
-        let game = self.stream_info.data.read().game.clone();
+        let game = self.channel.info().game;
 
         match game {
             Some(game) => {
                 user.respond(game).await;
             }
             None => {
-                user.respond("Unfortunately I don't know the game, sorry!")
-                    .await;
+                user.respond(message!(self.messages, "game/unknown")).await;
             }
         };
     }
@@ -146,6 +375,13 @@ impl command::Handler for Game {
         Some(auth::Scope::Game)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!game [game]",
+            description: "Show or set the game of the stream.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
@@ -160,20 +396,81 @@ impl command::Handler for Game {
 
         ctx.check_scope(auth::Scope::GameEdit).await?;
 
-        let twitch = self.twitch.clone();
-        let game = rest.to_string();
-        let stream_info = self.stream_info.clone();
+        match self.channel.set_game(rest).await? {
+            Some(game) => respond!(
+                ctx,
+                message!(self.messages, "game/updated", game = game.name)
+            ),
+            None => respond!(ctx, message!(self.messages, "game/missing", game = rest)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Handler for the `!so` command.
+pub struct Shoutout {
+    pub enabled: settings::Var<bool>,
+    pub template: settings::Var<Template>,
+    pub twitch: api::Twitch,
+    pub messages: Messages,
+}
+
+/// Data available when rendering a shoutout.
+#[derive(serde::Serialize)]
+struct ShoutoutData<'a> {
+    name: &'a str,
+    game: Option<&'a str>,
+    url: &'a str,
+}
+
+#[async_trait]
+impl command::Handler for Shoutout {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Shoutout)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!so <user>",
+            description: "Give a shoutout to another streamer.",
+        })
+    }
 
-        let mut request = api::twitch::UpdateChannelRequest::default();
-        request.channel.game = Some(game);
-        twitch
-            .update_channel(&ctx.user.streamer().id, request)
-            .await?;
-        stream_info
-            .refresh_channel(&twitch, ctx.user.streamer())
-            .await?;
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let login = ctx.next_str("<user>")?;
+        let login = login.trim_start_matches('@');
+
+        let user = match self.twitch.user_by_login(login).await? {
+            Some(user) => user,
+            None => respond_bail!(
+                "{}",
+                message!(self.messages, "shoutout/no-such-user", user = login)
+            ),
+        };
 
-        respond!(ctx, "Game updated!");
+        let channel = match self.twitch.channel_by_id(&user.id).await? {
+            Some(channel) => channel,
+            None => respond_bail!(
+                "{}",
+                message!(self.messages, "shoutout/no-such-channel", channel = login)
+            ),
+        };
+
+        let url = channel.url();
+
+        let data = ShoutoutData {
+            name: &user.display_name,
+            game: channel.game_name.as_deref(),
+            url: &url,
+        };
+
+        let shoutout = self.template.load().await.render_to_string(&data)?;
+        ctx.privmsg(shoutout).await;
         Ok(())
     }
 }
@@ -194,15 +491,25 @@ impl super::Module for Module {
             stream_info,
             streamer_twitch,
             settings,
+            injector,
             ..
         }: module::HookContext<'_>,
     ) -> Result<()> {
+        let twitch = CachedTwitch::new(injector.var().await?, streamer_twitch.clone());
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        let channel = stream_info::Channel::new(streamer_twitch.clone(), stream_info.clone());
+
         handlers.insert(
             "title",
             Title {
                 enabled: settings.var("title/enabled", true).await?,
-                stream_info: stream_info.clone(),
-                twitch: streamer_twitch.clone(),
+                channel: channel.clone(),
+                messages: messages.clone(),
             },
         );
 
@@ -210,8 +517,8 @@ impl super::Module for Module {
             "game",
             Game {
                 enabled: settings.var("game/enabled", true).await?,
-                stream_info: stream_info.clone(),
-                twitch: streamer_twitch.clone(),
+                channel,
+                messages: messages.clone(),
             },
         );
 
@@ -220,6 +527,40 @@ impl super::Module for Module {
             Uptime {
                 enabled: settings.var("uptime/enabled", true).await?,
                 stream_info: stream_info.clone(),
+                messages: messages.clone(),
+            },
+        );
+
+        handlers.insert(
+            "followage",
+            Followage {
+                enabled: settings.var("followage/enabled", false).await?,
+                twitch: twitch.clone(),
+                messages: messages.clone(),
+            },
+        );
+
+        handlers.insert(
+            "accountage",
+            AccountAge {
+                enabled: settings.var("accountage/enabled", false).await?,
+                twitch,
+                messages: messages.clone(),
+            },
+        );
+
+        handlers.insert(
+            "so",
+            Shoutout {
+                enabled: settings.var("shoutout/enabled", false).await?,
+                template: settings
+                    .var(
+                        "shoutout/template",
+                        Template::compile(DEFAULT_SHOUTOUT_TEMPLATE)?,
+                    )
+                    .await?,
+                twitch: streamer_twitch.clone(),
+                messages,
             },
         );
 
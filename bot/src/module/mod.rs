@@ -1,4 +1,6 @@
+use crate::activity;
 use crate::api;
+use crate::bus;
 use crate::command;
 use crate::idle;
 use crate::injector;
@@ -6,35 +8,73 @@ use crate::irc;
 use crate::settings;
 use crate::stream_info;
 use crate::utils;
+use futures::future::{BoxFuture, FutureExt as _};
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 
 #[macro_use]
 mod macros;
 pub mod admin;
+pub mod afk;
 pub mod after_stream;
 pub mod alias_admin;
 pub mod auth;
+pub mod chat_stats;
 pub mod clip;
 pub mod command_admin;
+pub mod commercial;
+pub mod cooldown;
 pub mod countdown;
+pub mod counters;
+pub mod donations;
 pub mod eight_ball;
+pub mod emote_stats;
+pub mod engagement;
+pub mod forget;
+pub mod games;
+pub mod giveaway;
+pub mod greetings;
 pub mod gtav;
 pub mod help;
+pub mod ingame;
+pub mod marker;
 pub mod misc;
+pub mod obs;
 pub mod poll;
 pub mod promotions;
+pub mod reminders;
+pub mod schedule;
 pub mod song;
 pub mod speedrun;
+pub mod stream_events;
 pub mod swearjar;
 pub mod theme_admin;
 pub mod time;
-pub mod water;
+pub mod triggers;
+pub mod trivia;
+pub mod tts;
+pub mod user_info;
+pub mod variables;
+pub mod viewer_queue;
 pub mod weather;
 
+/// Resolves handlers for commands which are only known at runtime, like
+/// the ones used to update counters.
+#[async_trait::async_trait]
+pub trait Resolver
+where
+    Self: 'static + Send + Sync,
+{
+    /// Resolve the handler for the given command in the given channel, if any.
+    async fn resolve(&self, channel: &str, command: &str) -> Option<Arc<dyn command::Handler>>;
+}
+
 #[derive(Default)]
 pub struct Handlers {
     handlers: HashMap<String, Arc<dyn command::Handler>>,
+    resolvers: Vec<Arc<dyn Resolver>>,
+    hooks: Vec<Arc<dyn command::MessageHook>>,
 }
 
 impl Handlers {
@@ -44,10 +84,61 @@ impl Handlers {
             .insert(command.as_ref().to_string(), Arc::new(handler));
     }
 
+    /// Insert a resolver for commands which don't have a handler registered.
+    pub fn insert_resolver(&mut self, resolver: impl Resolver) {
+        self.resolvers.push(Arc::new(resolver));
+    }
+
+    /// Insert a hook which peeks at all chat messages for as long as the
+    /// module is set up.
+    pub fn insert_hook(&mut self, hook: impl command::MessageHook) {
+        self.hooks.push(Arc::new(hook));
+    }
+
+    /// Extend these handlers with everything installed in `other`.
+    pub fn extend(&mut self, other: &Handlers) {
+        self.handlers.extend(
+            other
+                .handlers
+                .iter()
+                .map(|(command, handler)| (command.clone(), handler.clone())),
+        );
+        self.resolvers.extend(other.resolvers.iter().cloned());
+        self.hooks.extend(other.hooks.iter().cloned());
+    }
+
+    /// Iterate over all installed message hooks.
+    pub fn hooks(&self) -> impl Iterator<Item = &dyn command::MessageHook> {
+        self.hooks.iter().map(|hook| &**hook)
+    }
+
     /// Lookup the given command mutably.
     pub fn get(&self, command: &str) -> Option<Arc<dyn command::Handler>> {
         self.handlers.get(command).cloned()
     }
+
+    /// Lookup the given command, falling back to resolvers if there's no
+    /// handler registered for it.
+    pub async fn resolve(&self, channel: &str, command: &str) -> Option<Arc<dyn command::Handler>> {
+        if let Some(handler) = self.get(command) {
+            return Some(handler);
+        }
+
+        for resolver in &self.resolvers {
+            if let Some(handler) = resolver.resolve(channel, command).await {
+                return Some(handler);
+            }
+        }
+
+        None
+    }
+
+    /// Iterate over all handlers, by the command they are registered for.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<dyn command::Handler>)> {
+        self.handlers
+            .iter()
+            .map(|(command, handler)| (command.as_str(), handler))
+    }
 }
 
 /// Context for a hook.
@@ -57,11 +148,37 @@ pub struct HookContext<'a> {
     pub futures: &'a mut utils::Futures,
     pub stream_info: &'a stream_info::StreamInfo,
     pub idle: &'a idle::Idle,
+    pub activity: &'a activity::Activity,
     pub twitch: &'a api::Twitch,
     pub streamer_twitch: &'a api::Twitch,
     pub sender: &'a irc::Sender,
     pub settings: &'a settings::Settings,
     pub auth: &'a crate::auth::Auth,
+    pub global_bus: &'a Arc<bus::Bus<bus::Global>>,
+}
+
+/// A guard which runs the given future in the background once dropped.
+///
+/// Modules keep this around in their background futures to clean up state
+/// which would otherwise be lost when they are torn down.
+pub struct Teardown(Option<BoxFuture<'static, ()>>);
+
+impl Teardown {
+    /// Construct a guard which runs the given future once dropped.
+    pub fn new<F>(future: F) -> Self
+    where
+        F: 'static + Send + Future<Output = ()>,
+    {
+        Self(Some(future.boxed()))
+    }
+}
+
+impl Drop for Teardown {
+    fn drop(&mut self) {
+        if let Some(future) = self.0.take() {
+            tokio::spawn(future);
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -72,6 +189,21 @@ where
     /// Type of the module as a string to help with diagnostics.
     fn ty(&self) -> &'static str;
 
+    /// The feature flag which has to be enabled for the module to be set up.
+    ///
+    /// Used for experimental modules, see [`crate::flags`].
+    fn flag(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The setting which enables the module, and its default value.
+    ///
+    /// If set, the module is only set up while the setting is enabled, and is
+    /// torn down and set up again from scratch whenever it is toggled.
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        None
+    }
+
     /// Set up command handlers for this module.
     async fn hook(&self, _: HookContext<'_>) -> Result<(), anyhow::Error>;
 }
@@ -0,0 +1,181 @@
+use crate::api::Obs;
+use crate::auth;
+use crate::command;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use anyhow::{anyhow, Result};
+
+/// Usage of the `!obs` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!obs <scene [name]|show|hide|text|mute|unmute|record|stream>",
+    description: "Control OBS.",
+};
+
+/// Handler for the !obs command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    obs: injector::Var<Option<Obs>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Obs)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let obs = match self.obs.load().await {
+            Some(obs) => obs,
+            None => respond_bail!("{}", message!(self.messages, "obs/not-configured")),
+        };
+
+        match ctx.next().as_deref() {
+            Some("scene") => match ctx.next() {
+                Some(scene) => {
+                    obs.set_current_scene(&scene)
+                        .await
+                        .map_err(|e| failed(&self.messages, e))?;
+                    respond!(ctx, message!(self.messages, "obs/scene-set", scene = scene));
+                }
+                None => {
+                    let list = obs.scenes().await.map_err(|e| failed(&self.messages, e))?;
+                    let scenes = list.scenes.into_iter().map(|s| s.name).collect::<Vec<_>>();
+
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "obs/scenes",
+                            current = list.current_scene,
+                            scenes = scenes.join(", "),
+                        )
+                    );
+                }
+            },
+            Some(action @ "show") | Some(action @ "hide") => {
+                let source = ctx.next_str("<source> [scene]")?;
+                let scene = ctx.next();
+                let visible = action == "show";
+
+                obs.set_visible(scene.as_deref(), &source, visible)
+                    .await
+                    .map_err(|e| failed(&self.messages, e))?;
+                let key = if visible { "obs/shown" } else { "obs/hidden" };
+                respond!(ctx, message!(self.messages, key, source = source));
+            }
+            Some("text") => {
+                let source = ctx.next_str("<source> <text>")?;
+                let text = ctx.rest().trim().to_string();
+                obs.set_text(&source, &text)
+                    .await
+                    .map_err(|e| failed(&self.messages, e))?;
+                respond!(
+                    ctx,
+                    message!(self.messages, "obs/text-updated", source = source)
+                );
+            }
+            Some(action @ "mute") | Some(action @ "unmute") => {
+                let source = ctx.next_str("<source>")?;
+                let mute = action == "mute";
+
+                obs.set_mute(&source, mute)
+                    .await
+                    .map_err(|e| failed(&self.messages, e))?;
+                let key = if mute { "obs/muted" } else { "obs/unmuted" };
+                respond!(ctx, message!(self.messages, key, source = source));
+            }
+            Some("record") => match ctx.next().as_deref() {
+                Some("start") => {
+                    obs.start_recording()
+                        .await
+                        .map_err(|e| failed(&self.messages, e))?;
+                    respond!(ctx, message!(self.messages, "obs/recording-started"));
+                }
+                Some("stop") => {
+                    obs.stop_recording()
+                        .await
+                        .map_err(|e| failed(&self.messages, e))?;
+                    respond!(ctx, message!(self.messages, "obs/recording-stopped"));
+                }
+                _ => respond!(ctx, "Expected: start, or stop."),
+            },
+            Some("stream") => match ctx.next().as_deref() {
+                Some("start") => {
+                    obs.start_streaming()
+                        .await
+                        .map_err(|e| failed(&self.messages, e))?;
+                    respond!(ctx, message!(self.messages, "obs/streaming-started"));
+                }
+                Some("stop") => {
+                    obs.stop_streaming()
+                        .await
+                        .map_err(|e| failed(&self.messages, e))?;
+                    respond!(ctx, message!(self.messages, "obs/streaming-stopped"));
+                }
+                _ => respond!(ctx, "Expected: start, or stop."),
+            },
+            _ => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Convert a failed request into a response, so that the user sees why it
+/// failed.
+fn failed(messages: &Messages, e: anyhow::Error) -> command::Respond {
+    let error = e.to_string();
+    respond_err!("{}", message!(messages, "obs/failed", error = error))
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "obs"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("obs/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "obs",
+            Handler {
+                enabled: settings.var("obs/enabled", false).await?,
+                obs: injector.var().await?,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -1,18 +1,48 @@
+use crate::api;
+use crate::api::twitch::AnnouncementColor;
 use crate::auth;
+use crate::bus;
 use crate::command;
+use crate::db;
 use crate::irc;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
+use crate::stream_info;
 use crate::utils;
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use chrono::{DateTime, Utc};
 use std::collections::{HashMap, HashSet};
+use std::time;
 use tokio::sync::Mutex;
 
+/// Usage of the `!poll` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!poll <run|close|last>",
+    description: "Run a poll in chat.",
+};
+
+/// How often we check if a poll should be closed.
+const CHECK_INTERVAL: time::Duration = time::Duration::from_secs(1);
+/// The longest question a native Twitch poll can have.
+const NATIVE_MAX_TITLE: usize = 60;
+/// The longest choice a native Twitch poll can have.
+const NATIVE_MAX_CHOICE: usize = 25;
+/// How many choices a native Twitch poll can have.
+const NATIVE_MIN_CHOICES: usize = 2;
+const NATIVE_MAX_CHOICES: usize = 5;
+/// How long a native Twitch poll can run for, in seconds.
+const NATIVE_MIN_DURATION: u64 = 15;
+const NATIVE_MAX_DURATION: u64 = 1800;
+
 /// Handler for the !poll command.
 pub struct Poll {
     enabled: settings::Var<bool>,
-    polls: Mutex<HashMap<command::HookId, ActivePoll>>,
+    native: settings::Var<bool>,
+    duration: settings::Var<Option<utils::Duration>>,
+    subscriber_weight: settings::Var<u32>,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+    polls: Polls,
 }
 
 #[async_trait]
@@ -21,6 +51,10 @@ impl command::Handler for Poll {
         Some(auth::Scope::Poll)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
         if !self.enabled.load().await {
             return Ok(());
@@ -28,146 +62,506 @@ impl command::Handler for Poll {
 
         match ctx.next().as_deref() {
             Some("run") => {
-                let question = ctx.next_str("<question> <options...>")?;
+                let first = ctx.next_str("[duration] <question> <options...>")?;
+
+                let (duration, question) = match str::parse::<utils::Duration>(&first) {
+                    Ok(duration) => (
+                        Some(duration),
+                        ctx.next_str("[duration] <question> <options...>")?,
+                    ),
+                    Err(_) => (self.duration.load().await, first),
+                };
 
-                let mut options = HashMap::new();
+                let mut options = Vec::new();
 
                 while let Some(option) = ctx.next() {
-                    let (key, description) = match option.find('=') {
-                        Some(i) => {
-                            let (keyword, description) = option.split_at(i);
-                            (keyword.to_string(), Some(description[1..].to_string()))
-                        }
-                        None => (option, None),
-                    };
+                    match PollOption::parse(&option) {
+                        Some(option) => options.push(option),
+                        None => respond_bail!(
+                            "{}",
+                            message!(self.polls.messages, "poll/bad-option", option = option)
+                        ),
+                    }
+                }
 
-                    options.insert(key.to_lowercase(), description);
+                if options.is_empty() {
+                    respond_bail!("{}", message!(self.polls.messages, "poll/no-options"));
                 }
 
+                let created_at = Utc::now();
+                let votes = vec![0; options.len()];
+
                 let poll = ActivePoll {
                     question: question.clone(),
-                    created_at: Utc::now(),
-                    options,
+                    created_at,
+                    closes_at: duration.as_ref().map(|d| created_at + d.as_chrono()),
+                    keywords: Arc::new(keywords(&options)),
+                    options: Arc::new(options),
+                    subscriber_weight: self.subscriber_weight.load().await.max(1),
+                    stream_info: self.polls.stream_info.clone(),
+                    global_bus: self.global_bus.clone(),
                     inner: settings::Var::new(Inner {
                         voted: Default::default(),
-                        votes: Default::default(),
+                        votes,
                     }),
                 };
 
+                let native = if self.native.load().await {
+                    self.polls.start_native(&poll, duration.as_ref()).await
+                } else {
+                    None
+                };
+
                 let hook_id = ctx.insert_hook(poll.clone()).await;
-                self.polls.lock().await.insert(hook_id, poll);
-                ctx.respond(format!("Started poll `{}` (id: {})", question, hook_id))
-                    .await;
+                poll.publish(false).await;
+
+                self.polls.running.lock().await.insert(
+                    hook_id,
+                    Running {
+                        poll,
+                        ctx: ctx.clone(),
+                        native,
+                    },
+                );
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.polls.messages,
+                        "poll/started",
+                        question = question,
+                        id = hook_id.to_string(),
+                        duration = duration.map(|d| d.to_string()),
+                    )
+                );
             }
             Some("close") => {
-                let mut polls = self.polls.lock().await;
-
-                let id = match ctx.next() {
-                    Some(id) => str::parse::<command::HookId>(&id)
-                        .map_err(|_| respond_err!("Bad id `{}`", id))?,
-                    None => {
-                        *polls
-                            .iter()
-                            .max_by_key(|e| e.1.created_at)
-                            .ok_or_else(|| respond_err!("No running polls"))?
-                            .0
-                    }
+                let (id, running) = {
+                    let mut running = self.polls.running.lock().await;
+
+                    let id = match ctx.next() {
+                        Some(id) => str::parse::<command::HookId>(&id).map_err(|_| {
+                            respond_err!(
+                                "{}",
+                                message!(self.polls.messages, "poll/bad-id", id = id)
+                            )
+                        })?,
+                        None => {
+                            *running
+                                .iter()
+                                .max_by_key(|e| e.1.poll.created_at)
+                                .ok_or_else(|| {
+                                    respond_err!("{}", message!(self.polls.messages, "poll/none"))
+                                })?
+                                .0
+                        }
+                    };
+
+                    let poll = running.remove(&id).ok_or_else(|| {
+                        respond_err!(
+                            "{}",
+                            message!(self.polls.messages, "poll/missing", id = id.to_string())
+                        )
+                    })?;
+
+                    (id, poll)
                 };
 
+                self.polls.close_and_report(vec![(id, running)]).await;
+            }
+            Some("last") => {
+                let polls = self.polls.db.load().await.ok_or_else(|| {
+                    respond_err!("{}", message!(self.polls.messages, "poll/not-available"))
+                })?;
+
                 let poll = polls
-                    .remove(&id)
-                    .ok_or_else(|| respond_err!("No poll with id `{}`!", id))?;
+                    .list(ctx.channel(), 1)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        respond_err!("{}", message!(self.polls.messages, "poll/none-closed"))
+                    })?;
+
+                respond!(
+                    ctx,
+                    "{} -> {}.",
+                    poll.question,
+                    format_results(&self.polls.messages, &poll.results)
+                );
+            }
+            _ => {
+                respond!(
+                    ctx,
+                    message!(self.polls.messages, "usage", usage = USAGE.usage)
+                );
+            }
+        }
 
-                ctx.remove_hook(id).await;
-                let results = poll.close().await;
+        Ok(())
+    }
+}
 
-                let total = results.iter().map(|(_, c)| c).sum::<u32>();
+/// An option which can be voted for.
+#[derive(Debug, Clone, PartialEq)]
+struct PollOption {
+    key: String,
+    /// Other keywords which vote for the same option.
+    aliases: Vec<String>,
+    description: Option<String>,
+}
 
-                let mut formatted = Vec::new();
+impl PollOption {
+    /// Parse an option, like `1|one|first=GTA 5`.
+    fn parse(s: &str) -> Option<Self> {
+        let (keys, description) = match s.find('=') {
+            Some(i) => (&s[..i], Some(s[(i + 1)..].to_string())),
+            None => (s, None),
+        };
 
-                for (key, votes) in results {
-                    let p = utils::percentage(votes, total);
+        let mut keys = keys
+            .split('|')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(str::to_lowercase);
+
+        Some(Self {
+            key: keys.next()?,
+            aliases: keys.collect(),
+            description: description.filter(|d| !d.is_empty()),
+        })
+    }
 
-                    let votes = match votes {
-                        0 => "no votes".to_string(),
-                        1 => "one vote".to_string(),
-                        n => format!("{} votes", n),
-                    };
+    /// How the option is presented.
+    fn title(&self) -> &str {
+        self.description.as_deref().unwrap_or(&self.key)
+    }
+}
 
-                    formatted.push(format!("{} = {} ({})", key, votes, p));
-                }
+/// Map every keyword to the index of the option it votes for.
+fn keywords(options: &[PollOption]) -> HashMap<String, usize> {
+    let mut keywords = HashMap::new();
 
-                respond!(ctx, "{} -> {}.", poll.question, formatted.join(", "));
-            }
-            _ => {
-                ctx.respond("Expected: run, close.").await;
-            }
+    for (index, o) in options.iter().enumerate() {
+        for keyword in std::iter::once(&o.key).chain(&o.aliases) {
+            keywords.entry(keyword.clone()).or_insert(index);
         }
+    }
 
-        Ok(())
+    keywords
+}
+
+/// Tally up the votes for each option, ordered by the number of votes.
+fn tally(options: &[PollOption], votes: &[u32]) -> Vec<db::PollResult> {
+    let mut results = options
+        .iter()
+        .zip(votes)
+        .map(|(o, votes)| db::PollResult {
+            key: o.key.clone(),
+            description: o.description.clone(),
+            votes: *votes,
+        })
+        .collect::<Vec<_>>();
+
+    results.sort_by(|a, b| b.votes.cmp(&a.votes));
+    results
+}
+
+/// Format results to show in chat.
+fn format_results(messages: &Messages, results: &[db::PollResult]) -> String {
+    let total = results.iter().map(|r| r.votes).sum::<u32>();
+
+    let mut formatted = Vec::new();
+
+    for r in results {
+        let p = utils::percentage(r.votes, total);
+
+        let votes = message!(messages, "poll/votes", count = r.votes);
+
+        let title = r.description.as_deref().unwrap_or(&r.key);
+        formatted.push(format!("{} = {} ({})", title, votes, p));
     }
+
+    formatted.join(", ")
+}
+
+/// The choices to use for a native Twitch poll, if the poll fits within
+/// what Twitch allows.
+fn native_choices(question: &str, options: &[PollOption]) -> Option<Vec<String>> {
+    if question.chars().count() > NATIVE_MAX_TITLE {
+        return None;
+    }
+
+    if options.len() < NATIVE_MIN_CHOICES || options.len() > NATIVE_MAX_CHOICES {
+        return None;
+    }
+
+    let mut choices = Vec::new();
+
+    for o in options {
+        let title = o.title();
+
+        if title.chars().count() > NATIVE_MAX_CHOICE {
+            return None;
+        }
+
+        choices.push(title.to_string());
+    }
+
+    Some(choices)
 }
 
 struct Inner {
     voted: HashSet<String>,
-    votes: HashMap<String, u32>,
+    /// Votes for each option, in the order the options were given.
+    votes: Vec<u32>,
 }
 
 #[derive(Clone)]
 struct ActivePoll {
     question: String,
     created_at: DateTime<Utc>,
-    options: HashMap<String, Option<String>>,
+    closes_at: Option<DateTime<Utc>>,
+    options: Arc<Vec<PollOption>>,
+    keywords: Arc<HashMap<String, usize>>,
+    subscriber_weight: u32,
+    stream_info: stream_info::StreamInfo,
+    global_bus: Arc<bus::Bus<bus::Global>>,
     inner: settings::Var<Inner>,
 }
 
 impl ActivePoll {
-    /// Close the poll.
-    pub async fn close(&self) -> Vec<(String, u32)> {
-        let inner = self.inner.read().await;
-
-        let mut results = Vec::new();
-
-        for (o, description) in &self.options {
-            results.push((
-                description.clone().unwrap_or_else(|| o.to_string()),
-                inner.votes.get(o).cloned().unwrap_or_default(),
-            ));
-        }
+    /// Publish the current tally of the poll on the bus.
+    async fn publish(&self, closed: bool) {
+        let results = tally(&self.options, &self.inner.read().await.votes);
+        self.publish_results(results, closed).await;
+    }
 
-        results.sort_by(|a, b| b.1.cmp(&a.1));
-        results
+    /// Publish the given results of the poll on the bus.
+    async fn publish_results(&self, results: Vec<db::PollResult>, closed: bool) {
+        self.global_bus
+            .send(bus::Global::Poll {
+                question: self.question.clone(),
+                results,
+                closes_at: self.closes_at,
+                closed,
+            })
+            .await;
     }
 }
 
 #[async_trait]
 impl command::MessageHook for ActivePoll {
     async fn peek(&self, user: &irc::User, m: &str) -> Result<(), Error> {
-        let mut inner = self.inner.write().await;
+        if user.is_whisper() {
+            return Ok(());
+        }
 
         let user = match user.real() {
             Some(user) => user,
             None => return Ok(()),
         };
 
-        if inner.voted.contains(user.name()) {
-            return Ok(());
-        }
+        {
+            let mut inner = self.inner.write().await;
 
-        for word in utils::TrimmedWords::new(m) {
-            if self.options.get(&word.to_lowercase()).is_none() {
-                continue;
+            if inner.voted.contains(user.name()) {
+                return Ok(());
             }
 
-            *inner.votes.entry(word.to_string()).or_default() += 1;
+            let index = utils::TrimmedWords::new(m)
+                .find_map(|word| self.keywords.get(&word.to_lowercase()).copied());
+
+            let index = match index {
+                Some(index) => index,
+                None => return Ok(()),
+            };
+
+            let weight = if self.stream_info.is_subscriber(user.name()) {
+                self.subscriber_weight
+            } else {
+                1
+            };
+
+            inner.votes[index] += weight;
             inner.voted.insert(user.name().to_string());
-            break;
         }
 
+        self.publish(false).await;
         Ok(())
     }
 }
 
+/// A poll which is running.
+struct Running {
+    poll: ActivePoll,
+    /// Context the poll was started in, used to remove its hook when closed.
+    ctx: command::Context,
+    /// The ID of the native Twitch poll, if one was created.
+    native: Option<String>,
+}
+
+/// Polls which are running, and what's needed to close them.
+#[derive(Clone)]
+struct Polls {
+    running: Arc<Mutex<HashMap<command::HookId, Running>>>,
+    twitch: api::Twitch,
+    stream_info: stream_info::StreamInfo,
+    db: injector::Var<Option<db::Polls>>,
+    announce: settings::Var<Option<AnnouncementColor>>,
+    messages: Messages,
+}
+
+impl Polls {
+    /// Create a native Twitch poll mirroring the given poll, returning its
+    /// ID.
+    async fn start_native(
+        &self,
+        poll: &ActivePoll,
+        duration: Option<&utils::Duration>,
+    ) -> Option<String> {
+        let choices = match native_choices(&poll.question, &poll.options) {
+            Some(choices) => choices,
+            None => {
+                log::warn!("Poll doesn't fit in a Twitch poll, only running it in chat");
+                return None;
+            }
+        };
+
+        // NB: Twitch polls always have a duration, polls without one run for
+        // as long as Twitch allows unless closed before then.
+        let duration = duration
+            .map(|d| d.num_seconds())
+            .unwrap_or(NATIVE_MAX_DURATION)
+            .max(NATIVE_MIN_DURATION)
+            .min(NATIVE_MAX_DURATION);
+
+        let result = self
+            .twitch
+            .create_poll(
+                &self.stream_info.user.id,
+                &poll.question,
+                &choices,
+                duration as u32,
+            )
+            .await;
+
+        match result {
+            Ok(poll) => poll.map(|poll| poll.id),
+            Err(e) => {
+                log_error!(e, "Failed to create Twitch poll, only running it in chat");
+                None
+            }
+        }
+    }
+
+    /// End a native Twitch poll and get its final results.
+    async fn end_native(&self, id: &str) -> Result<Option<api::twitch::Poll>> {
+        let broadcaster_id = &self.stream_info.user.id;
+
+        match self.twitch.end_poll(broadcaster_id, id).await {
+            Ok(poll) => Ok(poll),
+            Err(e) => {
+                // NB: the poll might already have ended on its own.
+                log::trace!("Failed to end Twitch poll: {}", e);
+                self.twitch.poll(broadcaster_id, id).await
+            }
+        }
+    }
+
+    /// Close a running poll, returning its results formatted for chat.
+    async fn close(&self, id: command::HookId, running: Running) -> String {
+        let Running { poll, ctx, native } = running;
+
+        ctx.remove_hook(id).await;
+
+        let mut votes = poll.inner.read().await.votes.clone();
+
+        if let Some(native) = native {
+            match self.end_native(&native).await {
+                Ok(Some(native)) => {
+                    // NB: choices are in the same order as the options.
+                    for (votes, choice) in votes.iter_mut().zip(native.choices) {
+                        *votes += choice.votes;
+                    }
+                }
+                Ok(None) => {
+                    log::warn!("Twitch poll `{}` no longer exists", native);
+                }
+                Err(e) => {
+                    log_error!(e, "Failed to get results of Twitch poll");
+                }
+            }
+        }
+
+        let results = tally(&poll.options, &votes);
+        poll.publish_results(results.clone(), true).await;
+
+        if let Some(polls) = self.db.load().await {
+            let closed_at = Utc::now().naive_utc();
+
+            if let Err(e) = polls
+                .push(
+                    ctx.channel(),
+                    &poll.question,
+                    &results,
+                    poll.created_at.naive_utc(),
+                    closed_at,
+                )
+                .await
+            {
+                log_error!(e, "Failed to store results of poll");
+            }
+        }
+
+        format!(
+            "{} -> {}.",
+            poll.question,
+            format_results(&self.messages, &results)
+        )
+    }
+
+    /// Close all polls which are due to be closed.
+    async fn close_expired(&self) {
+        let now = Utc::now();
+
+        let expired = {
+            let mut running = self.running.lock().await;
+
+            let ids = running
+                .iter()
+                .filter(|(_, r)| r.poll.closes_at.map(|at| at <= now).unwrap_or_default())
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+
+            ids.into_iter()
+                .filter_map(|id| Some((id, running.remove(&id)?)))
+                .collect::<Vec<_>>()
+        };
+
+        self.close_and_report(expired).await;
+    }
+
+    /// Close all running polls.
+    async fn close_all(&self) {
+        let running = self.running.lock().await.drain().collect::<Vec<_>>();
+        self.close_and_report(running).await;
+    }
+
+    /// Close the given polls and report their results in chat, as
+    /// announcements if configured.
+    async fn close_and_report(&self, polls: Vec<(command::HookId, Running)>) {
+        let announce = self.announce.load().await;
+
+        for (id, running) in polls {
+            let ctx = running.ctx.clone();
+            let results = self.close(id, running).await;
+            let message = message!(self.messages, "poll/closed", results = results);
+            ctx.announce(announce, message).await;
+        }
+    }
+}
+
 pub struct Module;
 
 #[async_trait]
@@ -176,21 +570,134 @@ impl super::Module for Module {
         "poll"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("poll/enabled", false))
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
         module::HookContext {
-            handlers, settings, ..
+            handlers,
+            futures,
+            settings,
+            injector,
+            stream_info,
+            streamer_twitch,
+            global_bus,
+            ..
         }: module::HookContext<'_>,
     ) -> Result<(), anyhow::Error> {
+        let settings = settings.scoped("poll");
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        let polls = Polls {
+            running: Arc::new(Mutex::new(Default::default())),
+            twitch: streamer_twitch.clone(),
+            stream_info: stream_info.clone(),
+            db: injector.var().await?,
+            announce: settings.optional("announce").await?,
+            messages,
+        };
+
         handlers.insert(
             "poll",
             Poll {
-                polls: Mutex::new(Default::default()),
-                enabled: settings.var("poll/enabled", false).await?,
+                enabled: settings.var("enabled", false).await?,
+                native: settings.var("native", false).await?,
+                duration: settings.optional("duration").await?,
+                subscriber_weight: settings.var("subscriber-weight", 1).await?,
+                global_bus: global_bus.clone(),
+                polls: polls.clone(),
             },
         );
 
+        let future = async move {
+            // NB: polls are closed if the module is torn down, since their
+            // hooks are installed in the command context.
+            let _teardown = module::Teardown::new({
+                let polls = polls.clone();
+                async move { polls.close_all().await }
+            });
+
+            let mut check = tokio::time::interval(CHECK_INTERVAL);
+
+            loop {
+                check.tick().await;
+                polls.close_expired().await;
+            }
+        };
+
+        futures.push(future.boxed());
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{format_results, keywords, native_choices, tally, PollOption};
+    use crate::messages::{Catalog, Messages};
+
+    fn option(s: &str) -> PollOption {
+        PollOption::parse(s).expect("valid option")
+    }
+
+    #[test]
+    fn test_parse_option() {
+        assert_eq!(
+            PollOption {
+                key: String::from("1"),
+                aliases: vec![String::from("one"), String::from("gta5")],
+                description: Some(String::from("GTA 5")),
+            },
+            option("1|One|GTA5=GTA 5")
+        );
+
+        assert_eq!(
+            PollOption {
+                key: String::from("yes"),
+                aliases: vec![],
+                description: None,
+            },
+            option("Yes=")
+        );
+
+        assert_eq!(None, PollOption::parse("|=nothing"));
+    }
+
+    #[test]
+    fn test_tally() {
+        let options = vec![option("1|one=GTA 5"), option("2|two=GTA SA"), option("3")];
+        let keywords = keywords(&options);
+
+        assert_eq!(Some(&0), keywords.get("one"));
+        assert_eq!(Some(&1), keywords.get("2"));
+
+        let results = tally(&options, &[1, 3, 0]);
+        let messages = Messages::new(Catalog::load_static().expect("catalog to load"));
+
+        assert_eq!(
+            "GTA SA = 3 votes (75%), GTA 5 = one vote (25%), 3 = no votes (0%)",
+            format_results(&messages, &results)
+        );
+    }
+
+    #[test]
+    fn test_native_choices() {
+        let options = vec![option("1=GTA 5"), option("2")];
+
+        assert_eq!(
+            Some(vec![String::from("GTA 5"), String::from("2")]),
+            native_choices("Which game?", &options)
+        );
+
+        assert_eq!(None, native_choices("Which game?", &options[..1]));
+
+        let long = vec![option("1=Grand Theft Auto V: Premium Edition"), option("2")];
+        assert_eq!(None, native_choices("Which game?", &long));
+    }
+}
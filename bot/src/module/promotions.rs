@@ -1,19 +1,34 @@
 use crate::auth;
 use crate::command;
 use crate::db;
+use crate::idle;
 use crate::irc;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
+use crate::scheduler::{Schedule, Scheduler};
 use crate::utils;
+use anyhow::anyhow;
 use chrono::Utc;
 
+/// Usage of the `!promo` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!promo <show|list|edit|rename|delete|enable|disable|group|clear-group>",
+    description: "Manage promotions posted in chat.",
+};
+
 pub struct Handler {
     enabled: settings::Var<bool>,
     promotions: injector::Var<Option<db::Promotions>>,
+    messages: Messages,
 }
 
 #[async_trait]
 impl command::Handler for Handler {
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
         if !self.enabled.load().await {
             return Ok(());
@@ -24,7 +39,7 @@ impl command::Handler for Handler {
             None => return Ok(()),
         };
 
-        let next = command_base!(ctx, promotions, "promotion", PromoEdit);
+        let next = command_base!(ctx, self.messages, promotions, "promotion", PromoEdit);
 
         match next.as_deref() {
             Some("edit") => {
@@ -37,14 +52,14 @@ impl command::Handler for Handler {
                 promotions
                     .edit(ctx.channel(), &name, frequency, template)
                     .await?;
-                respond!(ctx, "Edited promo.");
-            }
-            None | Some(..) => {
                 respond!(
                     ctx,
-                    "Expected: show, list, edit, delete, enable, disable, or group."
+                    message!(self.messages, "manage/edited", what = "promotion")
                 );
             }
+            None | Some(..) => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
         }
 
         Ok(())
@@ -59,6 +74,10 @@ impl super::Module for Module {
         "promotions"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("promotions/enabled", false))
+    }
+
     async fn hook(
         &self,
         module::HookContext {
@@ -72,6 +91,12 @@ impl super::Module for Module {
         }: module::HookContext<'_>,
     ) -> Result<(), anyhow::Error> {
         let settings = settings.scoped("promotions");
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
         let enabled = settings.var("enabled", false).await?;
 
         let (mut setting, frequency) = settings
@@ -79,11 +104,15 @@ impl super::Module for Module {
             .or_with_else(|| utils::Duration::seconds(5 * 60))
             .await?;
 
+        let (mut schedule_stream, mut schedule) =
+            settings.stream::<Schedule>("schedule").optional().await?;
+
         handlers.insert(
             "promo",
             Handler {
                 enabled: enabled.clone(),
                 promotions: injector.var().await?,
+                messages,
             },
         );
 
@@ -92,6 +121,14 @@ impl super::Module for Module {
         let mut interval = tokio::time::interval(frequency.as_std()).fuse();
         let idle = idle.clone();
 
+        let scheduler = injector.get::<Scheduler>().await;
+
+        if let Some(scheduler) = scheduler.as_ref() {
+            scheduler.update("promotions", schedule.clone()).await;
+        }
+
+        let mut next_fire = schedule.as_ref().and_then(Schedule::delay);
+
         let future = async move {
             loop {
                 // TODO: check that this actually works.
@@ -104,27 +141,30 @@ impl super::Module for Module {
                             interval = tokio::time::interval(duration.as_std()).fuse();
                         }
                     }
-                    _ = interval.select_next_some() => {
-                        if !enabled.load().await {
-                            continue;
+                    update = schedule_stream.select_next_some() => {
+                        if let Some(scheduler) = scheduler.as_ref() {
+                            scheduler.update("promotions", update.clone()).await;
                         }
 
-                        let promotions = match promotions.as_ref() {
-                            Some(promotions) => promotions,
-                            None => continue,
-                        };
-
-                        if idle.is_idle().await {
-                            log::trace!("channel is too idle to send a promotion");
-                        } else {
-                            let promotions = promotions.clone();
-                            let sender = sender.clone();
+                        next_fire = update.as_ref().and_then(Schedule::delay);
+                        schedule = update;
+                    }
+                    _ = next_fire.current() => {
+                        next_fire = schedule.as_ref().and_then(Schedule::delay);
 
-                            if let Err(e) = promote(promotions, sender).await {
-                                log::error!("failed to send promotion: {}", e);
-                            }
+                        if enabled.load().await {
+                            try_promote(&idle, promotions.as_ref(), &sender).await;
                         }
                     }
+                    _ = interval.select_next_some() => {
+                        // NB: a configured schedule takes precedence over the
+                        // frequency.
+                        if schedule.is_some() || !enabled.load().await {
+                            continue;
+                        }
+
+                        try_promote(&idle, promotions.as_ref(), &sender).await;
+                    }
                 }
             }
         };
@@ -134,6 +174,23 @@ impl super::Module for Module {
     }
 }
 
+/// Run the next promotion, unless the channel is too idle.
+async fn try_promote(idle: &idle::Idle, promotions: Option<&db::Promotions>, sender: &irc::Sender) {
+    let promotions = match promotions {
+        Some(promotions) => promotions,
+        None => return,
+    };
+
+    if idle.is_idle().await {
+        log::trace!("channel is too idle to send a promotion");
+        return;
+    }
+
+    if let Err(e) = promote(promotions.clone(), sender.clone()).await {
+        log::error!("failed to send promotion: {}", e);
+    }
+}
+
 /// Run the next promotion.
 async fn promote(promotions: db::Promotions, sender: irc::Sender) -> Result<(), anyhow::Error> {
     let channel = sender.channel();
@@ -141,7 +198,7 @@ async fn promote(promotions: db::Promotions, sender: irc::Sender) -> Result<(),
     if let Some(p) = pick(promotions.list(channel).await) {
         let text = p.render(&PromoData { channel })?;
         promotions.bump_promoted_at(&*p).await?;
-        sender.privmsg(text).await;
+        sender.privmsg_with(irc::Priority::Low, text).await;
     }
 
     Ok(())
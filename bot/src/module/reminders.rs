@@ -0,0 +1,590 @@
+use crate::auth;
+use crate::command;
+use crate::currency::Currency;
+use crate::db;
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::sys;
+use crate::utils;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::time;
+use tokio::sync::Mutex;
+
+/// Usage of the `!water` command.
+const WATER_USAGE: command::Usage = command::Usage {
+    usage: "!water [undo]",
+    description: "Remind the streamer to drink some water.",
+};
+
+/// Usage of the `!reminder` command.
+const REMINDER_USAGE: command::Usage = command::Usage {
+    usage: "!reminder <list|stats|done <name>>",
+    description: "Show and acknowledge recurring reminders.",
+};
+
+/// Where a reminder is delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Target {
+    /// Post the reminder in chat.
+    Chat,
+    /// Show the reminder as a desktop notification to the streamer.
+    Notification,
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Target::Chat
+    }
+}
+
+/// A user-defined recurring reminder, like `posture` or `hydrate`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Reminder {
+    name: String,
+    text: String,
+    interval: utils::Duration,
+    #[serde(default)]
+    target: Target,
+}
+
+/// When each reminder last fired or was acknowledged, by name.
+type Schedule = Arc<RwLock<HashMap<String, DateTime<Utc>>>>;
+
+#[derive(Clone)]
+pub struct Reward {
+    user: String,
+    amount: i64,
+}
+
+pub struct WaterHandler {
+    enabled: settings::Var<bool>,
+    cooldown: settings::Var<utils::Cooldown>,
+    currency: injector::Var<Option<Currency>>,
+    waters: Mutex<Vec<(DateTime<Utc>, Option<Reward>)>>,
+    stream_info: stream_info::StreamInfo,
+    reward_multiplier: settings::Var<u32>,
+    messages: Messages,
+}
+
+impl WaterHandler {
+    async fn check_waters(
+        &self,
+        waters: &mut Vec<(DateTime<Utc>, Option<Reward>)>,
+    ) -> Result<(DateTime<Utc>, Option<Reward>)> {
+        if let Some((when, user)) = waters.last() {
+            return Ok((*when, user.clone()));
+        }
+
+        let started_at = self
+            .stream_info
+            .data
+            .read()
+            .stream
+            .as_ref()
+            .map(|s| s.started_at);
+
+        let started_at = started_at
+            .ok_or_else(|| respond_err!("{}", message!(self.messages, "water/not-available")))?;
+
+        waters.push((started_at, None));
+        Ok((started_at, None))
+    }
+}
+
+#[async_trait]
+impl command::Handler for WaterHandler {
+    fn usage(&self) -> Option<command::Usage> {
+        Some(WATER_USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => {
+                respond!(ctx, message!(self.messages, "water/no-currency"));
+                return Ok(());
+            }
+        };
+
+        if !self.cooldown.write().await.is_open() {
+            respond!(ctx, message!(self.messages, "water/cooldown"));
+            return Ok(());
+        }
+
+        let a = ctx.next();
+
+        match a.as_deref() {
+            Some("undo") => {
+                ctx.check_scope(auth::Scope::WaterUndo).await?;
+                let mut waters = self.waters.lock().await;
+                let (_, reward) = self.check_waters(&mut waters).await?;
+
+                waters.pop();
+
+                let reward = match reward {
+                    Some(reward) => reward,
+                    None => {
+                        respond!(ctx, message!(self.messages, "water/nothing-to-undo"));
+                        return Ok(());
+                    }
+                };
+
+                ctx.privmsg(message!(self.messages, "water/undone", user = reward.user))
+                    .await;
+
+                if let Err(e) = currency
+                    .balance_add(ctx.channel(), &reward.user, -reward.amount)
+                    .await
+                {
+                    log::error!("failed to undo water from database: {}", e);
+                }
+            }
+            None => {
+                let mut waters = self.waters.lock().await;
+                let (last, _) = self.check_waters(&mut waters).await?;
+
+                let user = match ctx.user.real() {
+                    Some(user) => user,
+                    None => {
+                        ctx.privmsg(message!(self.messages, "water/only-real-users"))
+                            .await;
+                        return Ok(());
+                    }
+                };
+
+                let now = Utc::now();
+                let diff = now - last;
+                let amount = i64::max(0i64, diff.num_minutes());
+                let amount = (amount * self.reward_multiplier.load().await as i64) / 100i64;
+
+                waters.push((
+                    now,
+                    Some(Reward {
+                        user: user.name().to_string(),
+                        amount,
+                    }),
+                ));
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "water/reminder",
+                        streamer = ctx.user.streamer().display_name,
+                        user = user.display_name(),
+                        amount = amount,
+                        currency = currency.name,
+                    )
+                );
+
+                if let Err(e) = currency
+                    .balance_add(ctx.channel(), user.name(), amount)
+                    .await
+                {
+                    log::error!("failed to appply water balance: {}", e);
+                }
+            }
+            Some(_) => {
+                respond!(
+                    ctx,
+                    message!(self.messages, "usage", usage = WATER_USAGE.usage)
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct ReminderHandler {
+    enabled: settings::Var<bool>,
+    reminders: settings::Var<Vec<Reminder>>,
+    schedule: Schedule,
+    db: injector::Var<Option<db::Reminders>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for ReminderHandler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Reminders)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(REMINDER_USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        match ctx.next().as_deref() {
+            Some("list") => {
+                let reminders = self.reminders.load().await;
+                let now = Utc::now();
+
+                let lines = {
+                    let schedule = self.schedule.read();
+
+                    reminders
+                        .iter()
+                        .map(|r| {
+                            let next = match schedule.get(&r.name) {
+                                Some(last) if !r.interval.is_empty() => {
+                                    Some(remaining(*last, r.interval, now).to_string())
+                                }
+                                _ => None,
+                            };
+
+                            message!(
+                                self.messages,
+                                "reminders/entry",
+                                name = r.name,
+                                interval = r.interval.to_string(),
+                                next = next,
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                };
+
+                ctx.respond_lines(lines, &message!(self.messages, "reminders/list-empty"))
+                    .await;
+            }
+            Some("stats") => {
+                let db = match self.db.load().await {
+                    Some(db) => db,
+                    None => respond_bail!("{}", message!(self.messages, "reminders/no-stats")),
+                };
+
+                let stats = db.list(ctx.channel()).await?;
+
+                let lines = stats.iter().map(|s| {
+                    message!(
+                        self.messages,
+                        "reminders/stats",
+                        name = s.name,
+                        fired = s.fired,
+                        done = s.done,
+                    )
+                });
+
+                ctx.respond_lines(lines, &message!(self.messages, "reminders/stats-empty"))
+                    .await;
+            }
+            Some("done") => {
+                let name = ctx.next_str("<name>")?;
+
+                let reminders = self.reminders.load().await;
+
+                if !reminders.iter().any(|r| r.name == name) {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "reminders/missing", name = name)
+                    );
+                }
+
+                let now = Utc::now();
+
+                // NB: acknowledging a reminder restarts its interval.
+                self.schedule.write().insert(name.clone(), now);
+
+                if let Some(db) = self.db.load().await {
+                    db.done(ctx.channel(), &name, now.naive_utc()).await?;
+                }
+
+                respond!(ctx, message!(self.messages, "reminders/done", name = name));
+            }
+            _ => {
+                respond!(
+                    ctx,
+                    message!(self.messages, "usage", usage = REMINDER_USAGE.usage)
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fires reminders which are due.
+struct Runner {
+    channel: String,
+    sender: irc::Sender,
+    stream_info: stream_info::StreamInfo,
+    reminders: settings::Var<Vec<Reminder>>,
+    only_live: settings::Var<bool>,
+    schedule: Schedule,
+    db: injector::Var<Option<db::Reminders>>,
+    system: injector::Var<Option<sys::System>>,
+    notifications: injector::Var<Option<db::Notifications>>,
+}
+
+impl Runner {
+    /// Fire all reminders which are due at the given point in time.
+    async fn tick(&self, now: DateTime<Utc>) {
+        if self.only_live.load().await && self.stream_info.data.read().stream.is_none() {
+            // NB: schedules start over when the stream starts.
+            self.schedule.write().clear();
+            return;
+        }
+
+        let reminders = self.reminders.load().await;
+
+        let due = {
+            let mut schedule = self.schedule.write();
+            schedule.retain(|name, _| reminders.iter().any(|r| r.name == *name));
+
+            let mut due = Vec::new();
+
+            for r in &reminders {
+                let last = schedule.entry(r.name.clone()).or_insert(now);
+
+                if is_due(*last, r.interval, now) {
+                    *last = now;
+                    due.push(r.clone());
+                }
+            }
+
+            due
+        };
+
+        for r in due {
+            self.fire(&r, now).await;
+        }
+    }
+
+    /// Deliver the given reminder and record that it fired.
+    async fn fire(&self, reminder: &Reminder, now: DateTime<Utc>) {
+        match reminder.target {
+            Target::Chat => {
+                self.sender
+                    .privmsg_with(irc::Priority::Low, &reminder.text)
+                    .await;
+            }
+            Target::Notification => {
+                let n = sys::Notification::new(&reminder.text)
+                    .title(format!("Reminder: {}", reminder.name));
+
+                if let Some(notifications) = self.notifications.load().await {
+                    if let Err(e) = notifications.push(&n).await {
+                        log_error!(e, "failed to store notification");
+                    }
+                }
+
+                match self.system.load().await {
+                    Some(system) => system.notification(n),
+                    None => {
+                        log::warn!(
+                            "cannot show reminder `{}`, desktop notifications are not available",
+                            reminder.name
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(db) = self.db.load().await {
+            if let Err(e) = db
+                .fired(&self.channel, &reminder.name, now.naive_utc())
+                .await
+            {
+                log_error!(
+                    e,
+                    "failed to store statistics for reminder `{}`",
+                    reminder.name
+                );
+            }
+        }
+    }
+}
+
+/// Test if a reminder with the given interval which last fired at `last` is
+/// due at `now`.
+fn is_due(last: DateTime<Utc>, interval: utils::Duration, now: DateTime<Utc>) -> bool {
+    !interval.is_empty() && now.signed_duration_since(last) >= interval.as_chrono()
+}
+
+/// The time remaining until a reminder with the given interval which last
+/// fired at `last` is due.
+fn remaining(
+    last: DateTime<Utc>,
+    interval: utils::Duration,
+    now: DateTime<Utc>,
+) -> utils::Duration {
+    let elapsed = now.signed_duration_since(last).num_seconds().max(0) as u64;
+    interval.saturating_sub(utils::Duration::seconds(elapsed))
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "reminders"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            futures,
+            stream_info,
+            sender,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let enabled = settings.var("water/enabled", false).await?;
+        let cooldown = settings
+            .var(
+                "water/cooldown",
+                utils::Cooldown::from_duration(utils::Duration::seconds(60)),
+            )
+            .await?;
+        let reward_multiplier = settings.var("water/reward%", 100).await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "water",
+            WaterHandler {
+                enabled,
+                cooldown,
+                currency: injector.var().await?,
+                waters: Mutex::new(Vec::new()),
+                stream_info: stream_info.clone(),
+                reward_multiplier,
+                messages: messages.clone(),
+            },
+        );
+
+        let settings = settings.scoped("reminders");
+        let reminders = settings.var("reminders", Vec::new()).await?;
+        let schedule = Schedule::default();
+        let db = injector.var().await?;
+
+        let (mut enabled_stream, enabled) = settings.stream("enabled").or_with(false).await?;
+
+        handlers.insert(
+            "reminder",
+            ReminderHandler {
+                enabled: settings.var("enabled", false).await?,
+                reminders: reminders.clone(),
+                schedule: schedule.clone(),
+                db: db.clone(),
+                messages,
+            },
+        );
+
+        let runner = Runner {
+            channel: sender.channel().to_string(),
+            sender: sender.clone(),
+            stream_info: stream_info.clone(),
+            reminders,
+            only_live: settings.var("only-live", true).await?,
+            schedule,
+            db,
+            system: injector.var().await?,
+            notifications: injector.var().await?,
+        };
+
+        let future = async move {
+            let new_interval = |enabled| {
+                if enabled {
+                    Some(tokio::time::interval(time::Duration::from_secs(10)))
+                } else {
+                    None
+                }
+            };
+
+            let mut interval = new_interval(enabled);
+
+            loop {
+                futures::select! {
+                    update = enabled_stream.select_next_some() => {
+                        if !update {
+                            runner.schedule.write().clear();
+                        }
+
+                        interval = new_interval(update);
+                    }
+                    _ = interval.select_next_some() => {
+                        runner.tick(Utc::now()).await;
+                    }
+                }
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_due, remaining};
+    use crate::utils::Duration;
+    use chrono::{TimeZone as _, Utc};
+
+    #[test]
+    fn test_is_due() {
+        let last = Utc.ymd(2021, 1, 3).and_hms(12, 0, 0);
+        let interval = Duration::seconds(30 * 60);
+
+        assert!(!is_due(last, interval, last));
+        assert!(!is_due(
+            last,
+            interval,
+            Utc.ymd(2021, 1, 3).and_hms(12, 29, 59)
+        ));
+        assert!(is_due(
+            last,
+            interval,
+            Utc.ymd(2021, 1, 3).and_hms(12, 30, 0)
+        ));
+        assert!(is_due(
+            last,
+            interval,
+            Utc.ymd(2021, 1, 3).and_hms(13, 0, 0)
+        ));
+        // an empty interval disables the reminder.
+        assert!(!is_due(
+            last,
+            Duration::default(),
+            Utc.ymd(2021, 1, 3).and_hms(13, 0, 0)
+        ));
+    }
+
+    #[test]
+    fn test_remaining() {
+        let last = Utc.ymd(2021, 1, 3).and_hms(12, 0, 0);
+        let interval = Duration::seconds(30 * 60);
+
+        assert_eq!(interval, remaining(last, interval, last));
+        assert_eq!(
+            Duration::seconds(10 * 60),
+            remaining(last, interval, Utc.ymd(2021, 1, 3).and_hms(12, 20, 0))
+        );
+        assert_eq!(
+            Duration::default(),
+            remaining(last, interval, Utc.ymd(2021, 1, 3).and_hms(13, 0, 0))
+        );
+    }
+}
@@ -0,0 +1,467 @@
+//! Answers `!schedule` and `!next` from the stream schedule, and posts
+//! announcements to chat and Discord before scheduled streams and at
+//! configured points in time.
+
+use crate::api;
+use crate::auth;
+use crate::command;
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::stream_schedule::{self, Announcement, Slot, StreamSchedule, Upcoming};
+use crate::template::Template;
+use crate::utils;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+
+/// The number of upcoming streams listed by `!schedule`.
+const UPCOMING_LIMIT: usize = 3;
+/// The default template for going live soon messages.
+const DEFAULT_REMINDER_TEMPLATE: &str = "Going live soon with {{title}} at {{time}}!";
+
+/// Handler for the `!schedule` command.
+pub struct Schedule {
+    enabled: settings::Var<bool>,
+    schedule: StreamSchedule,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Schedule {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Schedule)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!schedule",
+            description: "Show the upcoming streams.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let upcoming = self.schedule.upcoming(Utc::now(), UPCOMING_LIMIT);
+
+        if upcoming.is_empty() {
+            respond!(ctx, message!(self.messages, "schedule/empty"));
+            return Ok(());
+        }
+
+        let upcoming = upcoming
+            .iter()
+            .map(|u| format!("{} ({})", u.title, u.local_time()))
+            .collect::<Vec<_>>();
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "schedule/upcoming",
+                streams = upcoming.join(", ")
+            )
+        );
+        Ok(())
+    }
+}
+
+/// Handler for the `!next` command.
+pub struct Next {
+    enabled: settings::Var<bool>,
+    schedule: StreamSchedule,
+    stream_info: stream_info::StreamInfo,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Next {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Schedule)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!next",
+            description: "Show when the next stream starts.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+
+        let next = match self.schedule.next(now) {
+            Some(next) => next,
+            None => respond_bail!("{}", message!(self.messages, "schedule/empty")),
+        };
+
+        let remaining = (next.starts_at - now).to_std().unwrap_or_default();
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "schedule/next",
+                live = self.stream_info.started_at().is_some(),
+                title = next.title,
+                remaining = utils::compact_duration(remaining),
+                time = next.local_time(),
+            )
+        );
+
+        Ok(())
+    }
+}
+
+/// Something which is posted by the schedule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    /// A reminder that a scheduled stream is starting soon.
+    Reminder(Upcoming),
+    /// A one-shot announcement.
+    Announcement(Announcement),
+}
+
+/// Find the next event strictly after the given time.
+///
+/// Reminders are posted `reminder` ahead of each scheduled stream.
+fn next_event(
+    slots: &[Slot],
+    announcements: &[Announcement],
+    reminder: Option<utils::Duration>,
+    after: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, Event)> {
+    let reminder = reminder.and_then(|reminder| {
+        let lead = reminder.as_chrono();
+        let next = stream_schedule::upcoming(slots, after + lead, 1)
+            .into_iter()
+            .next()?;
+        Some((next.starts_at - lead, Event::Reminder(next)))
+    });
+
+    let announcement = announcements
+        .iter()
+        .filter(|a| a.at.as_utc() > after)
+        .min_by_key(|a| a.at.as_utc())
+        .map(|a| (a.at.as_utc(), Event::Announcement(a.clone())));
+
+    match (reminder, announcement) {
+        (Some(a), Some(b)) if b.0 < a.0 => Some(b),
+        (Some(a), _) => Some(a),
+        (None, b) => b,
+    }
+}
+
+/// Remove one-shot announcements which are due at or before `now`, returning
+/// how many were removed.
+fn prune_passed(announcements: &mut Vec<Announcement>, now: DateTime<Utc>) -> usize {
+    let before = announcements.len();
+    announcements.retain(|a| a.at.as_utc() > now);
+    before - announcements.len()
+}
+
+/// Posts announcements to chat and Discord.
+struct Announcer {
+    sender: irc::Sender,
+    stream_info: stream_info::StreamInfo,
+    discord: api::Discord,
+    reminder_template: Template,
+    /// If messages are also posted to the Discord webhooks.
+    post_to_discord: bool,
+    discord_webhooks: Vec<String>,
+}
+
+impl Announcer {
+    /// Post the given event.
+    async fn post(&self, event: &Event) -> Result<()> {
+        let message = match event {
+            Event::Reminder(upcoming) => {
+                if self.stream_info.started_at().is_some() {
+                    log::trace!("not posting reminder since we are already live");
+                    return Ok(());
+                }
+
+                self.reminder_template.render_to_string(&ReminderVars {
+                    title: &upcoming.title,
+                    game: upcoming.game.as_deref(),
+                    time: upcoming.local_time(),
+                })?
+            }
+            Event::Announcement(announcement) => announcement.message.clone(),
+        };
+
+        if message.is_empty() {
+            return Ok(());
+        }
+
+        self.sender.privmsg(&message).await;
+
+        if !self.post_to_discord {
+            return Ok(());
+        }
+
+        for url in &self.discord_webhooks {
+            let discord = self.discord.clone();
+            let url = url.clone();
+
+            let message = api::discord::WebhookMessage {
+                content: message.clone(),
+                ..Default::default()
+            };
+
+            tokio::spawn(async move {
+                if let Err(e) = discord.execute_webhook(&url, &message).await {
+                    log_error!(e, "failed to post announcement to discord");
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Variables available in going live soon messages.
+#[derive(serde::Serialize)]
+struct ReminderVars<'a> {
+    title: &'a str,
+    game: Option<&'a str>,
+    time: String,
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "schedule"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("schedule/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            futures,
+            settings,
+            injector,
+            sender,
+            stream_info,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let (mut discord_webhooks_stream, discord_webhooks) = settings
+            .stream::<Vec<String>>("discord/webhooks")
+            .or_default()
+            .await?;
+
+        let settings = settings.scoped("schedule");
+        let enabled = settings.var("enabled", false).await?;
+
+        let schedule = injector.get::<StreamSchedule>().await.unwrap_or_default();
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        let (mut slots_stream, mut slots) =
+            settings.stream::<Vec<Slot>>("streams").or_default().await?;
+
+        let (mut announcements_stream, mut announcements) = settings
+            .stream::<Vec<Announcement>>("announcements")
+            .or_default()
+            .await?;
+
+        let (mut reminder_stream, mut reminder) = settings
+            .stream::<utils::Duration>("reminder")
+            .optional()
+            .await?;
+
+        let (mut reminder_template_stream, reminder_template) = settings
+            .stream("reminder-template")
+            .or_with(Template::compile(DEFAULT_REMINDER_TEMPLATE)?)
+            .await?;
+
+        let (mut post_to_discord_stream, post_to_discord) =
+            settings.stream("discord").or_default().await?;
+
+        schedule.set(slots.clone());
+
+        handlers.insert(
+            "schedule",
+            Schedule {
+                enabled: enabled.clone(),
+                schedule: schedule.clone(),
+                messages: messages.clone(),
+            },
+        );
+
+        handlers.insert(
+            "next",
+            Next {
+                enabled,
+                schedule: schedule.clone(),
+                stream_info: stream_info.clone(),
+                messages,
+            },
+        );
+
+        let mut announcer = Announcer {
+            sender: sender.clone(),
+            stream_info: stream_info.clone(),
+            discord: api::Discord::new(),
+            reminder_template,
+            post_to_discord,
+            discord_webhooks,
+        };
+
+        let future = async move {
+            let mut last = Utc::now();
+
+            // NB: announcements which were due while the bot wasn't running
+            // are dropped instead of being posted late.
+            let pruned = prune_passed(&mut announcements, last);
+
+            if pruned > 0 {
+                log::info!("removing {} announcement(s) which are in the past", pruned);
+
+                if let Err(e) = settings.set("announcements", &announcements).await {
+                    log_error!(e, "failed to remove past announcements");
+                }
+            }
+
+            let mut next = next_event(&slots, &announcements, reminder, last);
+            let mut fire = next.as_ref().map(|(at, _)| delay_until(*at));
+
+            loop {
+                futures::select! {
+                    update = slots_stream.select_next_some() => {
+                        schedule.set(update.clone());
+                        slots = update;
+                    }
+                    update = announcements_stream.select_next_some() => {
+                        announcements = update;
+                    }
+                    update = reminder_stream.select_next_some() => {
+                        reminder = update;
+                    }
+                    update = reminder_template_stream.select_next_some() => {
+                        announcer.reminder_template = update;
+                    }
+                    update = post_to_discord_stream.select_next_some() => {
+                        announcer.post_to_discord = update;
+                    }
+                    update = discord_webhooks_stream.select_next_some() => {
+                        announcer.discord_webhooks = update;
+                    }
+                    _ = fire.current() => {
+                        if let Some((at, event)) = next.take() {
+                            last = at;
+
+                            if let Err(e) = announcer.post(&event).await {
+                                log_error!(e, "failed to post scheduled announcement");
+                            }
+
+                            // NB: one-shot announcements are removed once
+                            // they've been posted.
+                            if let Event::Announcement(announcement) = &event {
+                                announcements.retain(|a| a != announcement);
+
+                                let result = settings.set("announcements", &announcements).await;
+
+                                if let Err(e) = result {
+                                    log_error!(e, "failed to remove posted announcement");
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // NB: never fire anything at or before the last thing that
+                // fired.
+                let after = std::cmp::max(last, Utc::now());
+                next = next_event(&slots, &announcements, reminder, after);
+                fire = next.as_ref().map(|(at, _)| delay_until(*at));
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
+
+/// Construct a delay which resolves at the given point in time.
+fn delay_until(at: DateTime<Utc>) -> tokio::time::Delay {
+    let duration = (at - Utc::now()).to_std().unwrap_or_default();
+    tokio::time::delay_for(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_event, prune_passed, Event};
+    use crate::stream_schedule::{Announcement, Slot};
+    use crate::utils::Duration;
+    use chrono::{TimeZone as _, Utc};
+
+    #[test]
+    fn test_next_event() {
+        let slots = vec![Slot {
+            title: String::from("Rust"),
+            when: str::parse("0 20 * * *").unwrap(),
+            game: None,
+        }];
+
+        let announcements = vec![Announcement {
+            at: str::parse("2020-08-01 19:50").unwrap(),
+            message: String::from("Big announcement!"),
+        }];
+
+        let reminder = Some(Duration::seconds(15 * 60));
+        let now = Utc.ymd(2020, 8, 1).and_hms(10, 0, 0);
+
+        let (at, event) = next_event(&slots, &announcements, reminder, now).unwrap();
+        assert_eq!(Utc.ymd(2020, 8, 1).and_hms(19, 45, 0), at);
+        assert!(matches!(event, Event::Reminder(ref u) if u.title == "Rust"));
+
+        let (at, event) = next_event(&slots, &announcements, reminder, at).unwrap();
+        assert_eq!(Utc.ymd(2020, 8, 1).and_hms(19, 50, 0), at);
+        assert!(matches!(event, Event::Announcement(..)));
+
+        // the reminder for the stream which is about to start has passed.
+        let (at, _) = next_event(&slots, &announcements, reminder, at).unwrap();
+        assert_eq!(Utc.ymd(2020, 8, 2).and_hms(19, 45, 0), at);
+
+        assert!(next_event(&slots, &[], None, now).is_none());
+    }
+
+    #[test]
+    fn test_prune_passed() {
+        let announcement = |at: &str| Announcement {
+            at: str::parse(at).unwrap(),
+            message: String::from("Big announcement!"),
+        };
+
+        let mut announcements = vec![
+            announcement("2020-08-01 09:00"),
+            announcement("2020-08-01 10:00"),
+            announcement("2020-08-01 19:50"),
+        ];
+
+        let now = Utc.ymd(2020, 8, 1).and_hms(10, 0, 0);
+        assert_eq!(2, prune_passed(&mut announcements, now));
+        assert_eq!(vec![announcement("2020-08-01 19:50")], announcements);
+        assert_eq!(0, prune_passed(&mut announcements, now));
+    }
+}
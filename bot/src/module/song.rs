@@ -1,7 +1,10 @@
+use crate::activity;
 use crate::auth::Scope;
 use crate::command;
-use crate::currency::Currency;
+use crate::currency::{Currency, RewardData, RewardSource, Rewards};
+use crate::db;
 use crate::irc;
+use crate::messages::Messages;
 use crate::module;
 use crate::player;
 use crate::player::{AddTrackError, Event, Item, PlayThemeError, Player};
@@ -9,25 +12,156 @@ use crate::prelude::*;
 use crate::settings;
 use crate::track_id::{self, TrackId};
 use crate::utils::{self, Cooldown, Duration};
-use anyhow::{Context as _, Result};
+use crate::Uri;
+use anyhow::{anyhow, Context as _, Result};
 use chrono::Utc;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
 
 const EXAMPLE_SEARCH: &str = "queen we will rock you";
+/// Number of songs to show per page of `!song history`.
+const HISTORY_PAGE_SIZE: usize = 3;
+
+/// State of an ongoing vote to skip the current song.
+#[derive(Default)]
+struct VoteSkip {
+    /// The track being voted on.
+    track_id: Option<TrackId>,
+    /// When the vote was started.
+    started_at: Option<Instant>,
+    /// Users who have voted.
+    voters: HashSet<String>,
+}
+
+impl VoteSkip {
+    /// Register a vote from the given user.
+    ///
+    /// The vote is restarted if it is for a different track or if the window
+    /// has expired. Returns the number of votes, or `None` if the user has
+    /// already voted.
+    fn vote(
+        &mut self,
+        track_id: &TrackId,
+        user: &str,
+        window: std::time::Duration,
+    ) -> Option<usize> {
+        let now = Instant::now();
+
+        let expired = match self.started_at {
+            Some(started_at) => now.duration_since(started_at) > window,
+            None => true,
+        };
+
+        if expired || self.track_id.as_ref() != Some(track_id) {
+            self.track_id = Some(track_id.clone());
+            self.started_at = Some(now);
+            self.voters.clear();
+        }
+
+        if !self.voters.insert(user.to_string()) {
+            return None;
+        }
+
+        Some(self.voters.len())
+    }
+
+    /// Clear the current vote.
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
 
 /// Handler for the `!song` command.
 pub struct Handler {
     enabled: settings::Var<bool>,
+    messages: Messages,
     player: injector::Var<Option<Player>>,
     request_help_cooldown: Mutex<Cooldown>,
     request_reward: settings::Var<u32>,
+    rewards: Rewards,
     currency: injector::Var<Option<Currency>>,
+    banned_tracks: injector::Var<Option<db::BannedTracks>>,
     spotify: Constraint,
     youtube: Constraint,
+    bandcamp: Constraint,
+    activity: activity::Activity,
+    vote_skip: Mutex<VoteSkip>,
+    vote_skip_enabled: settings::Var<bool>,
+    vote_skip_threshold: settings::Var<u32>,
+    vote_skip_window: settings::Var<Duration>,
+    vote_skip_active_window: settings::Var<Duration>,
 }
 
 impl Handler {
+    /// Handle a vote to skip the current song.
+    async fn handle_vote_skip(&self, ctx: &mut command::Context, player: Player) -> Result<()> {
+        if !self.vote_skip_enabled.load().await {
+            respond_bail!("{}", message!(self.messages, "song/voteskip-disabled"));
+        }
+
+        let user = match ctx.user.name() {
+            Some(user) => user.to_lowercase(),
+            None => respond_bail!(
+                "{}",
+                message!(self.messages, "song/voteskip-only-real-users")
+            ),
+        };
+
+        let current = match player.current().await {
+            Some(current) => current,
+            None => respond_bail!("{}", message!(self.messages, "song/nothing-playing")),
+        };
+
+        let window = self.vote_skip_window.load().await.as_std();
+        let active_window = self.vote_skip_active_window.load().await.as_std();
+        let threshold = self.vote_skip_threshold.load().await as usize;
+
+        // NB: the voter always counts as active.
+        let active = usize::max(self.activity.active_within(active_window), 1);
+        let required = usize::max((active * threshold + 99) / 100, 1);
+
+        let votes = self
+            .vote_skip
+            .lock()
+            .await
+            .vote(&current.item.track_id, &user, window);
+
+        let votes = match votes {
+            Some(votes) => votes,
+            None => respond_bail!("{}", message!(self.messages, "song/voteskip-already-voted")),
+        };
+
+        if votes < required {
+            respond!(
+                ctx,
+                message!(
+                    self.messages,
+                    "song/voteskip-voted",
+                    what = current.item.what(),
+                    votes = votes,
+                    required = required,
+                )
+            );
+            return Ok(());
+        }
+
+        self.vote_skip.lock().await.clear();
+        player.skip().await?;
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "song/voteskip-passed",
+                what = current.item.what(),
+                votes = votes,
+                required = required,
+            )
+        );
+        Ok(())
+    }
+
     async fn handle_request(&self, ctx: &mut command::Context, player: Player) -> Result<()> {
         let q = ctx.rest().trim().to_string();
 
@@ -37,9 +171,14 @@ impl Handler {
         }
 
         let currency: Option<Currency> = self.currency.load().await;
-        let request_reward = self.request_reward.load().await;
+        let request_reward = self.request_reward.load().await as i64;
+        let request_reward = self
+            .rewards
+            .amount(RewardSource::SongRequest, request_reward)
+            .await;
         let spotify = self.spotify.clone();
         let youtube = self.youtube.clone();
+        let bandcamp = self.bandcamp.clone();
         let user = ctx.user.clone();
 
         let track_id = match TrackId::parse_with_urls(&q) {
@@ -65,7 +204,8 @@ impl Handler {
         let user = match user.real() {
             Some(user) => user,
             None => {
-                user.respond("Only real users can request songs").await;
+                user.respond(message!(self.messages, "song/request-only-real-users"))
+                    .await;
                 return Ok(());
             }
         };
@@ -78,10 +218,7 @@ impl Handler {
         let track_id = match track_id {
             Some(track_id) => track_id,
             None => {
-                respond!(
-                    user,
-                    "Could not find a track matching your request, sorry :("
-                );
+                respond!(user, message!(self.messages, "song/not-found"));
                 return Ok(());
             }
         };
@@ -95,13 +232,17 @@ impl Handler {
                 let enabled = youtube.enabled.load().await;
                 ("YouTube", user.has_scope(Scope::SongYouTube).await, enabled)
             }
+            TrackId::Bandcamp(..) => {
+                let enabled = bandcamp.enabled.load().await;
+                let has_scope = user.has_scope(Scope::SongBandcamp).await;
+                ("Bandcamp", has_scope, enabled)
+            }
         };
 
         if !enabled {
             respond!(
                 user,
-                "{} song requests are currently not enabled, sorry :(",
-                what
+                message!(self.messages, "song/service-disabled", service = what)
             );
             return Ok(());
         }
@@ -109,8 +250,7 @@ impl Handler {
         if !has_scope {
             respond!(
                 user,
-                "You are not allowed to do {what} requests, sorry :(",
-                what = what
+                message!(self.messages, "song/service-not-allowed", service = what)
             );
             return Ok(());
         }
@@ -118,11 +258,13 @@ impl Handler {
         let max_duration = match track_id {
             TrackId::Spotify(_) => spotify.max_duration.load().await,
             TrackId::YouTube(_) => youtube.max_duration.load().await,
+            TrackId::Bandcamp(_) => bandcamp.max_duration.load().await,
         };
 
         let min_currency = match track_id {
             TrackId::Spotify(_) => spotify.min_currency.load().await,
             TrackId::YouTube(_) => youtube.min_currency.load().await,
+            TrackId::Bandcamp(_) => bandcamp.min_currency.load().await,
         };
 
         let has_bypass_constraints = user.has_scope(Scope::SongBypassConstraints).await;
@@ -135,10 +277,7 @@ impl Handler {
                     let currency = match currency.as_ref() {
                         Some(currency) => currency,
                         None => {
-                            respond!(
-                                user,
-                                "No currency configured for stream, but it is required."
-                            );
+                            respond!(user, message!(self.messages, "song/no-currency"));
                             return Ok(());
                         }
                     };
@@ -151,10 +290,13 @@ impl Handler {
                     if balance.balance < min_currency {
                         respond!(
                             user,
-                            "You don't have enough {currency} to request songs. Need {required}, but you have {balance}, sorry :(",
-                            currency = currency.name,
-                            required = min_currency,
-                            balance = balance.balance,
+                            message!(
+                                self.messages,
+                                "song/not-enough-currency",
+                                currency = currency.name,
+                                required = min_currency,
+                                balance = balance.balance,
+                            )
                         );
 
                         return Ok(());
@@ -174,7 +316,7 @@ impl Handler {
             Err(AddTrackError::UnsupportedPlaybackMode) => {
                 respond!(
                     user,
-                    "Playback mode not supported for the given track type, sorry :("
+                    message!(self.messages, "song/unsupported-playback-mode")
                 );
 
                 return Ok(());
@@ -185,7 +327,7 @@ impl Handler {
                         respond!(user, reason.as_str());
                     }
                     None => {
-                        respond!(user, "Player is closed from further requests, sorry :(");
+                        respond!(user, message!(self.messages, "song/player-closed"));
                     }
                 }
 
@@ -194,8 +336,7 @@ impl Handler {
             Err(AddTrackError::QueueContainsTrack(pos)) => {
                 respond!(
                     user,
-                    "Player already contains that track (position #{pos}).",
-                    pos = pos + 1,
+                    message!(self.messages, "song/already-queued", pos = pos + 1)
                 );
 
                 return Ok(());
@@ -203,19 +344,12 @@ impl Handler {
             Err(AddTrackError::TooManyUserTracks(count)) => {
                 match count {
                     0 => {
-                        respond!(user, "Unfortunately you are not allowed to add tracks :(");
-                    }
-                    1 => {
-                        respond!(
-                            user,
-                            "<3 your enthusiasm, but you already have a track in the queue.",
-                        );
+                        respond!(user, message!(self.messages, "song/not-allowed"));
                     }
                     count => {
                         respond!(
                             user,
-                            "<3 your enthusiasm, but you already have {count} tracks in the queue.",
-                            count = count,
+                            message!(self.messages, "song/too-many-tracks", count = count)
                         );
                     }
                 }
@@ -223,53 +357,76 @@ impl Handler {
                 return Ok(());
             }
             Err(AddTrackError::QueueFull) => {
-                respond!(user, "Player is full, try again later!");
+                respond!(user, message!(self.messages, "song/player-full"));
                 return Ok(());
             }
             Err(AddTrackError::Duplicate(when, who, limit)) => {
-                let duration = Utc::now().signed_duration_since(when);
-
-                let duration = match duration.to_std() {
-                    Err(_) => None,
+                let ago = match Utc::now().signed_duration_since(when).to_std() {
                     Ok(duration) => Some(utils::compact_duration(duration)),
+                    Err(_) => None,
                 };
 
-                let limit = utils::compact_duration(limit);
-
-                let who = match who {
-                    Some(ref who) if who == user.name() => String::from(" by you"),
-                    Some(ref who) => format!(" by {}", who),
-                    None => String::from(""),
-                };
+                respond!(
+                    user,
+                    message!(
+                        self.messages,
+                        "song/duplicate",
+                        by_you = who.as_deref() == Some(user.name()),
+                        who = who,
+                        ago = ago,
+                        limit = utils::compact_duration(limit),
+                    )
+                );
 
-                let duration = match duration {
-                    Some(duration) => format!(" {} ago", duration),
-                    None => String::from(" not too long ago"),
+                return Ok(());
+            }
+            Err(AddTrackError::RecentlyPlayed(when, limit)) => {
+                let ago = match Utc::now().signed_duration_since(when).to_std() {
+                    Ok(duration) => Some(utils::compact_duration(duration)),
+                    Err(_) => None,
                 };
 
                 respond!(
                     user,
-                    "That song was requested{who}{duration}, \
-                         you have to wait at least {limit} between duplicate requests!",
-                    who = who,
-                    duration = duration,
-                    limit = limit,
+                    message!(
+                        self.messages,
+                        "song/recently-played",
+                        ago = ago,
+                        limit = utils::compact_duration(limit),
+                    )
                 );
 
                 return Ok(());
             }
             Err(AddTrackError::MissingAuth) => {
-                respond!(
-                    user,
-                    "Cannot add the given song because the service has not been authenticated by the streamer!",
-                );
+                respond!(user, message!(self.messages, "song/missing-auth"));
 
                 return Ok(());
             }
             Err(AddTrackError::NotPlayable) => {
+                respond!(user, message!(self.messages, "song/not-playable"));
+
+                return Ok(());
+            }
+            Err(AddTrackError::Explicit) => {
+                respond!(user, message!(self.messages, "song/explicit"));
+
+                return Ok(());
+            }
+            Err(AddTrackError::Banned(ban)) => {
+                let what = match ban.name.as_deref() {
+                    Some(name) => format!("{} {}", ban.kind, name),
+                    None => format!("{}", ban.kind),
+                };
+
                 respond!(
                     user,
-                    "This song is not available in the streamer's region :("
+                    message!(
+                        self.messages,
+                        "song/banned",
+                        what = what,
+                        reason = ban.reason,
+                    )
                 );
 
                 return Ok(());
@@ -279,45 +436,61 @@ impl Handler {
             }
         };
 
+        let pos = pos.map(|pos| pos + 1);
+        let staged = player.is_staging().await;
+
         let currency = match currency.as_ref() {
             Some(currency) if request_reward > 0 => currency,
             _ => {
-                if let Some(pos) = pos {
-                    respond!(
-                        user,
-                        "Added {what} at position #{pos}!",
+                respond!(
+                    user,
+                    message!(
+                        self.messages,
+                        "song/added",
                         what = item.what(),
-                        pos = pos + 1
-                    );
-                } else {
-                    respond!(user, "Added {what}!", what = item.what());
-                }
-
+                        pos = pos,
+                        staged = staged,
+                    )
+                );
                 return Ok(());
             }
         };
 
         match currency
-            .balance_add(user.channel(), user.name(), request_reward as i64)
+            .balance_add(user.channel(), user.name(), request_reward)
             .await
         {
             Ok(()) => {
-                if let Some(pos) = pos {
-                    respond!(
-                        user,
-                        "Added {what} at position #{pos}, here's your {amount} {currency}!",
+                let data = RewardData {
+                    user: user.display_name(),
+                    amount: request_reward,
+                    currency: &currency.name,
+                };
+
+                let message = self.rewards.message(RewardSource::SongRequest, data).await;
+
+                if let Some(message) = message {
+                    let added = message!(
+                        self.messages,
+                        "song/added",
                         what = item.what(),
-                        pos = pos + 1,
-                        amount = request_reward,
-                        currency = currency.name,
+                        pos = pos,
+                        staged = staged,
                     );
+
+                    respond!(user, "{} {}", added, message);
                 } else {
                     respond!(
                         user,
-                        "Added {what}, here's your {amount} {currency}!",
-                        what = item.what(),
-                        amount = request_reward,
-                        currency = currency.name,
+                        message!(
+                            self.messages,
+                            "song/added-reward",
+                            what = item.what(),
+                            pos = pos,
+                            staged = staged,
+                            amount = request_reward,
+                            currency = currency.name,
+                        )
                     );
                 }
             }
@@ -329,6 +502,62 @@ impl Handler {
         Ok(())
     }
 
+    /// Handle banning or unbanning of tracks, artists, or channels.
+    async fn handle_ban(
+        &self,
+        ctx: &mut command::Context,
+        player: &Player,
+        ban: bool,
+    ) -> Result<()> {
+        let banned_tracks = self.banned_tracks.load().await.ok_or_else(|| {
+            respond_err!("{}", message!(self.messages, "song/bans-not-configured"))
+        })?;
+
+        let kind = match ctx.next().as_deref() {
+            Some("track") => db::BanKind::Track,
+            Some("artist") => db::BanKind::Artist,
+            Some("channel") => db::BanKind::Channel,
+            _ => respond_bail!("Expected: track, artist, or channel"),
+        };
+
+        let (id, name) = match ctx.next() {
+            Some(id) => (parse_ban_id(&self.messages, kind, &id)?, None),
+            None => {
+                let current = player.current().await.ok_or_else(|| {
+                    respond_err!("{}", message!(self.messages, "song/nothing-playing"))
+                })?;
+
+                current_ban_target(&self.messages, kind, &current.item)?
+            }
+        };
+
+        let what = match name.as_deref() {
+            Some(name) => format!("{} {}", kind, name),
+            None => format!("{} `{}`", kind, id),
+        };
+
+        if ban {
+            let reason = match ctx.rest() {
+                "" => None,
+                reason => Some(reason),
+            };
+
+            banned_tracks
+                .ban(kind, &id, name.as_deref(), reason, ctx.user.name())
+                .await?;
+            respond!(ctx, message!(self.messages, "song/ban-added", what = what));
+        } else if banned_tracks.unban(kind, &id).await? {
+            respond!(
+                ctx,
+                message!(self.messages, "song/ban-removed", what = what)
+            );
+        } else {
+            respond!(ctx, message!(self.messages, "song/not-banned", what = what));
+        }
+
+        Ok(())
+    }
+
     /// Provide a help message instructing the user how to perform song requests.
     async fn request_help(&self, ctx: &mut command::Context, reason: Option<&str>) {
         if !self.request_help_cooldown.lock().await.is_open() {
@@ -339,17 +568,15 @@ impl Handler {
             return;
         }
 
-        let mut response = format!(
-            "You can request a song from Spotify with \
-                <search>, like \"{search}\". You can also use an URI or an URL if you feel adventurous PogChamp",
-            search = EXAMPLE_SEARCH,
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "song/request-help",
+                reason = reason,
+                search = EXAMPLE_SEARCH,
+            )
         );
-
-        if let Some(reason) = reason {
-            response = format!("{}. {}", reason, response);
-        }
-
-        respond!(ctx, response);
     }
 }
 
@@ -359,6 +586,13 @@ impl command::Handler for Handler {
         Some(Scope::Song)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!song <request|skip|list|current|history|last|when|delete|length|...>",
+            description: "Request and control songs.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
@@ -368,7 +602,7 @@ impl command::Handler for Handler {
             .player
             .load()
             .await
-            .ok_or_else(|| respond_err!("No player configured"))?;
+            .ok_or_else(|| respond_err!("{}", message!(self.messages, "song/no-player")))?;
 
         match ctx.next().as_deref() {
             Some("theme") => {
@@ -379,20 +613,21 @@ impl command::Handler for Handler {
                 match player.play_theme(user.channel(), name.as_str()).await {
                     Ok(()) => (),
                     Err(PlayThemeError::NoSuchTheme) => {
-                        user.respond("No such theme :(").await;
+                        user.respond(message!(self.messages, "song/no-such-theme"))
+                            .await;
                     }
                     Err(PlayThemeError::NotConfigured) => {
-                        user.respond("Theme system is not configured :(").await;
+                        user.respond(message!(self.messages, "song/themes-not-configured"))
+                            .await;
                     }
                     Err(PlayThemeError::Error(e)) => {
-                        user.respond("There was a problem playing that theme :(")
+                        user.respond(message!(self.messages, "song/theme-failed"))
                             .await;
                         log_error!(e, "failed to add song");
                     }
                     Err(PlayThemeError::MissingAuth) => {
-                        user.respond(
-                            "Cannot play the given theme because the service has not been authenticated by the streamer!",
-                        ).await;
+                        user.respond(message!(self.messages, "song/theme-missing-auth"))
+                            .await;
                     }
                 }
             }
@@ -401,15 +636,15 @@ impl command::Handler for Handler {
                 let index = ctx
                     .next()
                     .ok_or_else(|| respond_err!("Expected <number>"))?;
-                let index = parse_queue_position(&index).await?;
+                let index = parse_queue_position(&self.messages, &index).await?;
 
                 if let Some(item) = player.promote_song(ctx.user.name(), index).await? {
                     respond!(
                         ctx,
-                        format!("Promoted song to head of queue: {}", item.what())
+                        message!(self.messages, "song/promoted", what = item.what())
                     );
                 } else {
-                    respond!(ctx, "No such song to promote");
+                    respond!(ctx, message!(self.messages, "song/not-promoted"));
                 }
             }
             Some("close") => {
@@ -422,21 +657,50 @@ impl command::Handler for Handler {
                     })
                     .await;
 
-                respond!(ctx, "Closed player from further requests.");
+                respond!(ctx, message!(self.messages, "song/closed"));
             }
             Some("open") => {
                 ctx.check_scope(Scope::SongEditQueue).await?;
                 player.open().await;
-                respond!(ctx, "Opened player for requests.");
+                respond!(ctx, message!(self.messages, "song/opened"));
+            }
+            Some("stage") => {
+                ctx.check_scope(Scope::SongEditQueue).await?;
+
+                match ctx.next().as_deref() {
+                    None => {
+                        player.stage().await;
+                        let count = player.staged().await.len();
+
+                        respond!(ctx, message!(self.messages, "song/staging", count = count));
+                    }
+                    Some("promote") => {
+                        let promoted = player.promote_staged().await?;
+                        respond!(
+                            ctx,
+                            message!(
+                                self.messages,
+                                "song/staged-promoted",
+                                count = promoted.len(),
+                            )
+                        );
+                    }
+                    Some("clear") => {
+                        let purged = player.purge_staged().await?;
+                        respond!(
+                            ctx,
+                            message!(self.messages, "song/staged-cleared", count = purged.len())
+                        );
+                    }
+                    Some(..) => {
+                        respond!(ctx, "Expected: !song stage [promote|clear]");
+                    }
+                }
             }
             Some("list") => {
                 if let Some(api_url) = ctx.api_url() {
-                    respond!(
-                        ctx,
-                        "You can find the queue at {}/player/{}",
-                        api_url,
-                        ctx.user.streamer().name
-                    );
+                    let url = format!("{}/player/{}", api_url, ctx.user.streamer().name);
+                    respond!(ctx, message!(self.messages, "song/queue-url", url = url));
                     return Ok(());
                 }
 
@@ -458,42 +722,102 @@ impl command::Handler for Handler {
                     None
                 };
 
-                display_songs(&ctx.user, has_more, items.iter().take(limit).cloned()).await;
+                display_songs(
+                    &self.messages,
+                    &ctx.user,
+                    has_more,
+                    items.iter().take(limit).cloned(),
+                )
+                .await;
             }
             Some("current") => match player.current().await {
                 Some(current) => {
                     let elapsed = utils::digital_duration(current.elapsed());
                     let duration = utils::digital_duration(current.duration());
 
-                    if let Some(name) = current.item.user.as_ref() {
-                        respond!(
-                            ctx,
-                            "Current song: {}, requested by {} - {elapsed} / {duration} - {url}",
-                            current.item.what(),
-                            name,
-                            elapsed = elapsed,
-                            duration = duration,
-                            url = current.item.track_id.url(),
-                        );
-                    } else {
-                        respond!(
-                            ctx,
-                            "Current song: {} - {elapsed} / {duration} - {url}",
-                            current.item.what(),
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "song/current",
+                            what = current.item.what(),
+                            user = current.item.user,
                             elapsed = elapsed,
                             duration = duration,
                             url = current.item.track_id.url(),
-                        );
-                    }
+                        )
+                    );
+                }
+                None => {
+                    respond!(ctx, message!(self.messages, "song/nothing-playing"));
+                }
+            },
+            Some("history") => {
+                let page = match ctx.next() {
+                    Some(page) => str::parse::<usize>(&page)
+                        .map_err(|_| respond_err!("Expected <page>"))?
+                        .max(1),
+                    None => 1,
+                };
+
+                let offset = (page - 1)
+                    .checked_mul(HISTORY_PAGE_SIZE)
+                    .ok_or_else(|| respond_err!("Expected <page>"))?;
+
+                let history = player.history(offset, HISTORY_PAGE_SIZE).await?;
+
+                display_history(&self.messages, &ctx.user, page, &history).await;
+            }
+            Some("last") => match player.history(0, 1).await?.into_iter().next() {
+                Some(entry) => {
+                    let what = describe_history(&self.messages, &entry);
+                    respond!(ctx, message!(self.messages, "song/last", what = what));
                 }
                 None => {
-                    respond!(ctx, "No song :(");
+                    respond!(ctx, message!(self.messages, "song/no-history"));
                 }
             },
+            Some("ban") => {
+                ctx.check_scope(Scope::SongBan).await?;
+                self.handle_ban(ctx, &player, true).await?;
+            }
+            Some("unban") => {
+                ctx.check_scope(Scope::SongBan).await?;
+                self.handle_ban(ctx, &player, false).await?;
+            }
             Some("purge") => {
                 ctx.check_scope(Scope::SongEditQueue).await?;
                 player.purge().await?;
-                respond!(ctx, "Song queue purged.");
+                respond!(ctx, message!(self.messages, "song/purged"));
+            }
+            Some("purgeuser") => {
+                ctx.check_scope(Scope::SongEditQueue).await?;
+                let user = ctx.next_str("<user>")?;
+                let user = user.trim_start_matches('@').to_lowercase();
+                let removed = player.remove_all_by_user(&user).await?;
+
+                if removed.is_empty() {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "song/purged-user-none", user = user)
+                    );
+                }
+
+                let duration = removed
+                    .iter()
+                    .map(|i| i.duration)
+                    .sum::<std::time::Duration>();
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "song/purged-user",
+                        count = removed.len(),
+                        user = user,
+                        duration = utils::compact_duration(duration),
+                    )
+                );
             }
             // print when your next song will play.
             Some("when") => {
@@ -505,7 +829,7 @@ impl command::Handler for Handler {
                         let user = match ctx.user.real() {
                             Some(user) => user,
                             None => {
-                                respond!(ctx, "Not a real user");
+                                respond!(ctx, message!(self.messages, "song/when-only-real-users"));
                                 return Ok(());
                             }
                         };
@@ -520,34 +844,26 @@ impl command::Handler for Handler {
                     .find(|item| item.user.as_ref().map(|u| *u == user).unwrap_or_default())
                     .await;
 
-                match result {
-                    Some((when, ref item)) if when.as_secs() == 0 => {
-                        if your {
-                            respond!(ctx, "Your song is currently playing");
-                        } else {
-                            respond!(ctx, "{}'s song {} is currently playing", user, item.what());
-                        }
-                    }
-                    Some((when, item)) => {
-                        let when = utils::compact_duration(when);
-
-                        if your {
-                            respond!(
-                                ctx,
-                                format!("Your song {} will play in {}", item.what(), when)
-                            );
-                        } else {
-                            respond!(ctx, "{}'s song {} will play in {}", user, item.what(), when);
-                        }
-                    }
-                    None => {
-                        if your {
-                            respond!(ctx, "You don't have any songs in queue :(");
-                        } else {
-                            respond!(ctx, format!("{} doesn't have any songs in queue :(", user));
-                        }
-                    }
-                }
+                let response = match result {
+                    Some((when, ref item)) if when.as_secs() == 0 => message!(
+                        self.messages,
+                        "song/when-playing",
+                        yours = your,
+                        user = user,
+                        what = item.what(),
+                    ),
+                    Some((when, item)) => message!(
+                        self.messages,
+                        "song/when",
+                        yours = your,
+                        user = user,
+                        what = item.what(),
+                        when = utils::compact_duration(when),
+                    ),
+                    None => message!(self.messages, "song/when-none", yours = your, user = user),
+                };
+
+                respond!(ctx, response);
             }
             Some("delete") => {
                 let removed = match ctx.next().as_deref() {
@@ -566,7 +882,10 @@ impl command::Handler for Handler {
                         let user = match ctx.user.real() {
                             Some(user) => user,
                             None => {
-                                respond!(ctx, "Only real users can delete their own songs");
+                                respond!(
+                                    ctx,
+                                    message!(self.messages, "song/delete-only-real-users")
+                                );
                                 return Ok(());
                             }
                         };
@@ -575,7 +894,7 @@ impl command::Handler for Handler {
                     }
                     Some(n) => {
                         ctx.check_scope(Scope::SongEditQueue).await?;
-                        let n = parse_queue_position(n).await?;
+                        let n = parse_queue_position(&self.messages, n).await?;
                         player.remove_at(n).await?
                     }
                     None => {
@@ -585,8 +904,13 @@ impl command::Handler for Handler {
                 };
 
                 match removed {
-                    None => ctx.respond("No song removed, sorry :(").await,
-                    Some(item) => ctx.respond(format!("Removed: {}!", item.what())).await,
+                    None => respond!(ctx, message!(self.messages, "song/not-removed")),
+                    Some(item) => {
+                        respond!(
+                            ctx,
+                            message!(self.messages, "song/removed", what = item.what())
+                        )
+                    }
                 }
             }
             Some("volume") => {
@@ -617,28 +941,67 @@ impl command::Handler for Handler {
 
                         match player.volume(volume).await? {
                             Some(volume) => {
-                                respond!(ctx, format!("Updated volume to {}.", volume));
+                                respond!(
+                                    ctx,
+                                    message!(self.messages, "song/volume-updated", volume = volume)
+                                );
                             }
                             None => {
-                                respond!(ctx, "Cannot update volume");
+                                respond!(ctx, message!(self.messages, "song/volume-failed"));
                             }
                         }
                     }
                     // reading volume
                     None => match player.current_volume().await {
                         Some(volume) => {
-                            respond!(ctx, format!("Current volume: {}.", volume));
+                            respond!(ctx, message!(self.messages, "song/volume", volume = volume));
                         }
                         None => {
-                            respond!(ctx, "No active player");
+                            respond!(ctx, message!(self.messages, "song/no-active-player"));
                         }
                     },
                 }
             }
             Some("skip") => {
-                ctx.check_scope(Scope::SongPlaybackControl).await?;
+                ctx.check_scope(Scope::SongSkip).await?;
                 player.skip().await?;
             }
+            Some("playlist") => {
+                ctx.check_scope(Scope::SongPlaylist).await?;
+
+                match ctx.next_str("<playlist> or reset")?.as_str() {
+                    "reset" => {
+                        if player.reset_fallback().await {
+                            respond!(ctx, message!(self.messages, "song/fallback-reset"));
+                        } else {
+                            respond!(ctx, message!(self.messages, "song/fallback-not-taken-over"));
+                        }
+                    }
+                    playlist => {
+                        let id = match Uri::parse_with_urls(playlist) {
+                            Ok(Uri::SpotifyPlaylist(id)) => id,
+                            _ => respond_bail!("Expected a Spotify playlist URL or URI"),
+                        };
+
+                        match player.takeover_fallback(id).await {
+                            Ok(name) => {
+                                respond!(
+                                    ctx,
+                                    message!(self.messages, "song/fallback-playlist", name = name)
+                                );
+                            }
+                            Err(e) => {
+                                log_error!(e, "failed to load playlist");
+                                respond!(ctx, message!(self.messages, "song/playlist-failed"));
+                            }
+                        }
+                    }
+                }
+            }
+            Some("voteskip") => {
+                ctx.check_scope(Scope::SongVoteSkip).await?;
+                self.handle_vote_skip(ctx, player).await?;
+            }
             Some("request") => {
                 self.handle_request(ctx, player).await?;
             }
@@ -658,19 +1021,14 @@ impl command::Handler for Handler {
                 let (count, duration) = player.length().await;
 
                 match count {
-                    0 => ctx.respond("No songs in queue :(").await,
-                    1 => {
-                        let length = utils::long_duration(duration);
-                        ctx.respond(format!("One song in queue with {} of play time.", length))
-                            .await;
-                    }
+                    0 => respond!(ctx, message!(self.messages, "song/length-empty")),
                     count => {
                         let length = utils::long_duration(duration);
-                        ctx.respond(format!(
-                            "{} songs in queue with {} of play time.",
-                            count, length
-                        ))
-                        .await;
+
+                        respond!(
+                            ctx,
+                            message!(self.messages, "song/length", count = count, length = length)
+                        );
                     }
                 }
             }
@@ -687,12 +1045,24 @@ impl command::Handler for Handler {
                     alts.push("promote");
                     alts.push("close");
                     alts.push("open");
+                    alts.push("stage");
                     alts.push("purge");
+                    alts.push("purgeuser");
                 } else {
                     alts.push("promote 🛇");
                     alts.push("close 🛇");
                     alts.push("open 🛇");
+                    alts.push("stage 🛇");
                     alts.push("purge 🛇");
+                    alts.push("purgeuser 🛇");
+                }
+
+                if ctx.user.has_scope(Scope::SongBan).await {
+                    alts.push("ban");
+                    alts.push("unban");
+                } else {
+                    alts.push("ban 🛇");
+                    alts.push("unban 🛇");
                 }
 
                 if ctx.user.has_scope(Scope::SongVolume).await {
@@ -701,20 +1071,38 @@ impl command::Handler for Handler {
                     alts.push("volume 🛇");
                 }
 
-                if ctx.user.has_scope(Scope::SongPlaybackControl).await {
+                if ctx.user.has_scope(Scope::SongSkip).await {
                     alts.push("skip");
+                } else {
+                    alts.push("skip 🛇");
+                }
+
+                if ctx.user.has_scope(Scope::SongPlaybackControl).await {
                     alts.push("toggle");
                     alts.push("play");
                     alts.push("pause");
                 } else {
-                    alts.push("skip 🛇");
                     alts.push("toggle 🛇");
                     alts.push("play 🛇");
                     alts.push("pause 🛇");
                 }
 
+                if ctx.user.has_scope(Scope::SongPlaylist).await {
+                    alts.push("playlist");
+                } else {
+                    alts.push("playlist 🛇");
+                }
+
+                if ctx.user.has_scope(Scope::SongVoteSkip).await {
+                    alts.push("voteskip");
+                } else {
+                    alts.push("voteskip 🛇");
+                }
+
                 alts.push("list");
                 alts.push("current");
+                alts.push("history");
+                alts.push("last");
                 alts.push("when");
                 alts.push("delete");
                 alts.push("request");
@@ -744,18 +1132,37 @@ impl module::Module for Module {
             sender,
             settings,
             injector,
+            activity,
             ..
         }: module::HookContext<'_>,
     ) -> Result<()> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
         let currency = injector.var().await?;
+        let banned_tracks = injector.var().await?;
+        let rewards = Rewards::new(settings).await?;
+        let stream_latency = settings
+            .var("player/stream-latency", Duration::seconds(0))
+            .await?;
         let settings = settings.scoped("song");
 
         let enabled = settings.var("enabled", false).await?;
         let chat_feedback = settings.var("chat-feedback", true).await?;
         let request_reward = settings.var("request-reward", 0).await?;
 
+        let vote_skip = settings.scoped("voteskip");
+        let vote_skip_enabled = vote_skip.var("enabled", false).await?;
+        let vote_skip_threshold = vote_skip.var("threshold%", 50).await?;
+        let vote_skip_window = vote_skip.var("window", Duration::seconds(60)).await?;
+        let vote_skip_active_window = vote_skip
+            .var("active-window", Duration::seconds(10 * 60))
+            .await?;
+
         let spotify = Constraint::build(&mut settings.scoped("spotify"), true, 0).await?;
         let youtube = Constraint::build(&mut settings.scoped("youtube"), false, 60).await?;
+        let bandcamp = Constraint::build(&mut settings.scoped("bandcamp"), false, 0).await?;
 
         let (mut player_stream, player) = injector.stream().await;
 
@@ -763,12 +1170,20 @@ impl module::Module for Module {
 
         let future = {
             let sender = sender.clone();
+            let messages = messages.clone();
             let shared_player = shared_player.clone();
 
             async move {
                 let new_feedback_loop = move |new_player: Option<&Player>| match new_player {
                     Some(new_player) => Some(
-                        feedback(new_player.clone(), sender.clone(), chat_feedback.clone()).boxed(),
+                        feedback(
+                            new_player.clone(),
+                            sender.clone(),
+                            messages.clone(),
+                            chat_feedback.clone(),
+                            stream_latency.clone(),
+                        )
+                        .boxed(),
                     ),
                     None => None,
                 };
@@ -797,12 +1212,22 @@ impl module::Module for Module {
             "song",
             Handler {
                 enabled,
+                messages,
                 request_help_cooldown: Mutex::new(help_cooldown),
                 player: shared_player,
                 request_reward,
+                rewards,
                 currency,
+                banned_tracks,
                 spotify,
                 youtube,
+                bandcamp,
+                activity: activity.clone(),
+                vote_skip: Mutex::new(VoteSkip::default()),
+                vote_skip_enabled,
+                vote_skip_threshold,
+                vote_skip_window,
+                vote_skip_active_window,
             },
         );
 
@@ -838,16 +1263,94 @@ impl Constraint {
 }
 
 /// Parse a queue position.
-async fn parse_queue_position(n: &str) -> Result<usize> {
+async fn parse_queue_position(messages: &Messages, n: &str) -> Result<usize> {
     match str::parse::<usize>(n) {
-        Ok(0) => respond_bail!("Can't mess with the current song :("),
+        Ok(0) => respond_bail!("{}", message!(messages, "song/current-position")),
         Ok(n) => Ok(n.saturating_sub(1)),
         Err(_) => respond_bail!("Expected whole number argument"),
     }
 }
 
+/// Parse the identifier of something to ban.
+///
+/// Accepts URIs and URLs in addition to raw identifiers.
+fn parse_ban_id(messages: &Messages, kind: db::BanKind, s: &str) -> Result<String> {
+    match kind {
+        db::BanKind::Track => match TrackId::parse_with_urls(s) {
+            Ok(track_id) => Ok(track_id.to_string()),
+            Err(e) => respond_bail!(
+                "{}",
+                message!(messages, "song/bad-track", error = e.to_string())
+            ),
+        },
+        db::BanKind::Artist => {
+            if s.starts_with("spotify:artist:") {
+                return Ok(s.trim_start_matches("spotify:artist:").to_string());
+            }
+
+            Ok(parse_url_id(s, "open.spotify.com", "artist").unwrap_or_else(|| s.to_string()))
+        }
+        db::BanKind::Channel => Ok(parse_url_id(s, "www.youtube.com", "channel")
+            .or_else(|| parse_url_id(s, "youtube.com", "channel"))
+            .unwrap_or_else(|| s.to_string())),
+    }
+}
+
+/// Parse an identifier out of an URL like `https://<host>/<prefix>/<id>`.
+fn parse_url_id(s: &str, host: &str, prefix: &str) -> Option<String> {
+    let url = str::parse::<url::Url>(s).ok()?;
+
+    if url.host_str()? != host {
+        return None;
+    }
+
+    let mut parts = url.path_segments()?;
+
+    if parts.next()? != prefix {
+        return None;
+    }
+
+    parts.next().map(|id| id.to_string())
+}
+
+/// Get the identifier and name of what to ban from the given item.
+fn current_ban_target(
+    messages: &Messages,
+    kind: db::BanKind,
+    item: &Item,
+) -> Result<(String, Option<String>)> {
+    match (kind, &item.track) {
+        (db::BanKind::Track, _) => Ok((item.track_id.to_string(), Some(item.what()))),
+        (db::BanKind::Artist, player::Track::Spotify { track }) => {
+            let artist = track
+                .artists
+                .iter()
+                .find(|a| a.id.is_some())
+                .ok_or_else(|| respond_err!("{}", message!(messages, "song/no-artist")))?;
+
+            let id = artist.id.clone().unwrap_or_default();
+            Ok((id, Some(artist.name.clone())))
+        }
+        (db::BanKind::Channel, player::Track::YouTube { video }) => {
+            let snippet = video
+                .snippet
+                .as_ref()
+                .ok_or_else(|| respond_err!("{}", message!(messages, "song/no-channel")))?;
+
+            Ok((snippet.channel_id.clone(), snippet.channel_title.clone()))
+        }
+        (db::BanKind::Artist, _) => {
+            respond_bail!("{}", message!(messages, "song/not-spotify"))
+        }
+        (db::BanKind::Channel, _) => {
+            respond_bail!("{}", message!(messages, "song/not-youtube"))
+        }
+    }
+}
+
 /// Display the collection of songs.
 async fn display_songs(
+    messages: &Messages,
     user: &irc::User,
     has_more: Option<usize>,
     it: impl IntoIterator<Item = Arc<Item>>,
@@ -866,72 +1369,121 @@ async fn display_songs(
     }
 
     if lines.is_empty() {
-        user.respond("Song queue is empty.").await;
+        user.respond(message!(messages, "song/queue-empty")).await;
         return;
     }
 
-    if let Some(more) = has_more {
-        user.respond(format!("{} ... and {} more.", lines.join("; "), more))
-            .await;
+    let songs = lines.join("; ");
+    user.respond(message!(
+        messages,
+        "song/queue",
+        songs = songs,
+        more = has_more
+    ))
+    .await;
+}
+
+/// Display a page of the song history.
+async fn display_history(
+    messages: &Messages,
+    user: &irc::User,
+    page: usize,
+    history: &[db::models::SongHistory],
+) {
+    if history.is_empty() {
+        if page == 1 {
+            user.respond(message!(messages, "song/no-history")).await;
+        } else {
+            user.respond(message!(messages, "song/no-history-page", page = page))
+                .await;
+        }
+
         return;
     }
 
+    let lines = history
+        .iter()
+        .map(|entry| describe_history(messages, entry))
+        .collect::<Vec<_>>();
+
     user.respond(format!("{}.", lines.join("; "))).await;
 }
 
+/// Describe a single entry in the song history.
+fn describe_history(messages: &Messages, entry: &db::models::SongHistory) -> String {
+    let ago = Utc::now()
+        .naive_utc()
+        .signed_duration_since(entry.played_at)
+        .to_std()
+        .ok()
+        .map(utils::compact_duration);
+
+    message!(
+        messages,
+        "song/history-entry",
+        name = entry.name,
+        user = entry.user,
+        ago = ago,
+        skipped = entry.skipped,
+    )
+}
+
 /// Notifications from the player.
+///
+/// Song switches are announced after the configured stream latency.
 async fn feedback(
     player: Player,
     sender: irc::Sender,
+    messages: Messages,
     chat_feedback: settings::Var<bool>,
+    stream_latency: settings::Var<Duration>,
 ) -> Result<()> {
     let mut configured_cooldown = Cooldown::from_duration(Duration::seconds(10));
     let mut rx = player.subscribe().await.fuse();
+    let mut delayed = player::Delayed::new(stream_latency);
 
     loop {
-        let e = rx.select_next_some().await?;
+        let e = futures::select! {
+            e = rx.select_next_some() => e?,
+            item = delayed.select_next_some() => {
+                now_playing(&sender, &messages, item.as_deref()).await;
+                continue;
+            }
+        };
+
         log::trace!("Player event: {:?}", e);
 
         match e {
             Event::Detached => {
-                sender.privmsg("Player is detached!").await;
+                sender.privmsg(message!(messages, "song/detached")).await;
             }
             Event::Playing(feedback, item) => {
                 if !feedback || !chat_feedback.load().await {
                     continue;
                 }
 
-                if let Some(item) = item {
-                    let message = match item.user.as_ref() {
-                        Some(user) => {
-                            format!("Now playing: {}, requested by {}.", item.what(), user)
-                        }
-                        None => format!("Now playing: {}.", item.what(),),
-                    };
-
-                    sender.privmsg(message).await;
-                } else {
-                    sender.privmsg("Now playing.").await;
+                if let Some(item) = delayed.schedule(item).await {
+                    now_playing(&sender, &messages, item.as_deref()).await;
                 }
             }
             Event::Skip => {
-                sender.privmsg("Skipping song.").await;
+                sender.privmsg(message!(messages, "song/skipping")).await;
             }
             Event::Pausing => {
                 if !chat_feedback.load().await {
                     continue;
                 }
 
-                sender.privmsg("Pausing playback.").await;
+                sender.privmsg(message!(messages, "song/pausing")).await;
             }
             Event::Empty => {
-                sender
-                    .privmsg("Song queue is empty (use !song request <spotify-id> to add more).")
-                    .await;
+                sender.privmsg(message!(messages, "song/empty")).await;
             }
             Event::NotConfigured => {
                 if configured_cooldown.is_open() {
-                    sender.privmsg("Player has not been configured!").await;
+                    sender
+                        .privmsg(message!(messages, "song/not-configured"))
+                        .await;
                 }
             }
             // other event we don't care about
@@ -939,3 +1491,15 @@ async fn feedback(
         }
     }
 }
+
+/// Announce the song which is now playing.
+async fn now_playing(sender: &irc::Sender, messages: &Messages, item: Option<&Item>) {
+    let message = message!(
+        messages,
+        "song/now-playing",
+        what = item.map(|i| i.what()),
+        user = item.and_then(|i| i.user.as_ref()),
+    );
+
+    sender.privmsg(message).await;
+}
@@ -7,18 +7,36 @@ use crate::api::{
 };
 use crate::auth;
 use crate::command;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
 use crate::storage::Cache;
 use crate::utils;
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
+use std::time;
+
+/// Usage of the `!speedrun` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!speedrun <record|personal-bests> [options]",
+    description: "Look up records and personal bests on speedrun.com.",
+};
+
+/// Usage of the `!pb` command.
+const PB_USAGE: command::Usage = command::Usage {
+    usage: "!pb [category]",
+    description: "Show the personal bests of the streamer on speedrun.com.",
+};
+
+/// The number of runs shown per page of a leaderboard.
+const RUNS_PER_PAGE: usize = 3;
 
 /// Handler for the !speedrun command.
 pub struct Speedrun {
     speedrun: CachedSpeedrun,
     enabled: settings::Var<bool>,
     top: settings::Var<u32>,
+    messages: Messages,
 }
 
 impl Speedrun {
@@ -81,14 +99,17 @@ impl Speedrun {
                 }
                 "--abbrev" => abbrev = true,
                 other if other.starts_with("--") => {
-                    respond!(ctx, format!("`{}` is not a valid parameter", other));
+                    respond!(
+                        ctx,
+                        message!(self.messages, "speedrun/bad-parameter", parameter = other)
+                    );
                     return Ok(());
                 }
                 other if query_user.is_none() => {
                     query_user = Some(other.to_lowercase());
                 }
                 _ => {
-                    respond!(ctx, "did not expect more arguments");
+                    respond!(ctx, message!(self.messages, "speedrun/unexpected-argument"));
                     return Ok(());
                 }
             }
@@ -99,7 +120,7 @@ impl Speedrun {
         let query_user = match query_user {
             Some(query_user) => query_user,
             None => {
-                respond!(ctx, "No user in query");
+                respond!(ctx, message!(self.messages, "speedrun/no-user"));
                 return Ok(());
             }
         };
@@ -111,7 +132,7 @@ impl Speedrun {
             None => {
                 respond!(
                     ctx,
-                    format!("No user on speedrun.com named `{}`", query_user)
+                    message!(self.messages, "speedrun/no-such-user", user = query_user)
                 );
                 return Ok(());
             }
@@ -125,7 +146,7 @@ impl Speedrun {
         let personal_bests = match personal_bests {
             Some(personal_bests) => personal_bests,
             None => {
-                respond!(ctx, "No personal bests found");
+                respond!(ctx, message!(self.messages, "speedrun/no-personal-bests"));
                 return Ok(());
             }
         };
@@ -223,7 +244,9 @@ impl Speedrun {
             ));
         }
 
-        ctx.user.respond_lines(results, "*no runs*").await;
+        ctx.user
+            .respond_lines(results, &message!(self.messages, "speedrun/no-runs"))
+            .await;
         return Ok(());
 
         /// Runs per game.
@@ -256,11 +279,19 @@ impl Speedrun {
         let mut match_user = None;
         let mut category_filter = CategoryFilter::default();
         let mut abbrev = false;
+        let mut page = 1;
 
         category_filter.ty = Some(CategoryType::PerGame);
 
         while let Some(arg) = ctx.next().as_deref() {
             match arg {
+                "--page" => match ctx.next().and_then(|p| str::parse::<usize>(&p).ok()) {
+                    Some(p) if p > 0 => page = p,
+                    _ => {
+                        respond!(ctx, "Expected a page number to `--page`");
+                        return Ok(());
+                    }
+                },
                 "--user" => match ctx.next() {
                     Some(u) => match_user = Some(u.to_lowercase()),
                     None => {
@@ -297,7 +328,10 @@ impl Speedrun {
                 }
                 "--abbrev" => abbrev = true,
                 other => {
-                    respond!(ctx, format!("`{}` is not a valid parameter", other));
+                    respond!(
+                        ctx,
+                        message!(self.messages, "speedrun/bad-parameter", parameter = other)
+                    );
                     return Ok(());
                 }
             }
@@ -310,7 +344,10 @@ impl Speedrun {
         let game = match game {
             Some(game) => game,
             None => {
-                respond!(ctx, "No game matching `{}`", game_query);
+                respond!(
+                    ctx,
+                    message!(self.messages, "speedrun/no-such-game", game = game_query)
+                );
                 return Result::Ok(());
             }
         };
@@ -326,7 +363,7 @@ impl Speedrun {
         let categories = match categories {
             Some(categories) => categories,
             None => {
-                respond!(ctx, "No categories for that game");
+                respond!(ctx, message!(self.messages, "speedrun/no-categories"));
                 return Ok(());
             }
         };
@@ -380,6 +417,11 @@ impl Speedrun {
         let mut embeds = Embeds::default();
         embeds.push(Embed::Players);
 
+        // NB: only one run per category is shown when showing many categories.
+        let per_page = if num_categories > 1 { 1 } else { RUNS_PER_PAGE };
+        let skip = (page - 1) * per_page;
+        let top = u32::max(top, (skip + per_page) as u32);
+
         for (name, variables, category) in categories_to_use {
             let records = self
                 .speedrun
@@ -404,8 +446,8 @@ impl Speedrun {
 
             let mut runs = Vec::new();
 
-            for run in records.runs.into_iter() {
-                if runs.len() >= 3 || num_categories > 1 && !runs.is_empty() {
+            for run in records.runs.into_iter().skip(skip) {
+                if runs.len() >= per_page {
                     break;
                 }
 
@@ -438,7 +480,9 @@ impl Speedrun {
             results.push(format!("{} -> {}", name, runs));
         }
 
-        ctx.user.respond_lines(results, "*no runs*").await;
+        ctx.user
+            .respond_lines(results, &message!(self.messages, "speedrun/no-runs"))
+            .await;
         Ok(())
     }
 
@@ -486,6 +530,10 @@ impl command::Handler for Speedrun {
         Some(auth::Scope::Speedrun)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
@@ -499,7 +547,7 @@ impl command::Handler for Speedrun {
                 self.query_game(ctx).await?;
             }
             _ => {
-                respond!(ctx, "Expected argument: record, personal-bests.");
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
             }
         }
 
@@ -507,6 +555,203 @@ impl command::Handler for Speedrun {
     }
 }
 
+/// Handler for the !pb command.
+pub struct PersonalBest {
+    speedrun: CachedSpeedrun,
+    enabled: settings::Var<bool>,
+    user: settings::Var<Option<String>>,
+    game: settings::Var<Option<String>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for PersonalBest {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Speedrun)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(PB_USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let query = ctx.rest().trim().to_string();
+
+        let user = match self.user.load().await {
+            Some(user) => user,
+            None => ctx.user.streamer().name.clone(),
+        };
+
+        let u = match self.speedrun.user_by_id(&user).await? {
+            Some(u) => u,
+            None => respond_bail!(
+                "{}",
+                message!(self.messages, "speedrun/no-such-user", user = user)
+            ),
+        };
+
+        let match_game = self.game.load().await.map(|g| g.to_lowercase());
+
+        let mut embeds = Embeds::default();
+        embeds.push(Embed::Game);
+        embeds.push(Embed::Category);
+
+        let personal_bests = match self.speedrun.own_personal_bests(&u.id, &embeds).await? {
+            Some(personal_bests) => personal_bests,
+            None => respond_bail!("{}", message!(self.messages, "speedrun/no-personal-bests")),
+        };
+
+        let mut pbs = Vec::new();
+
+        for mut run in personal_bests {
+            // NB: per-level runs are only available through `!speedrun`.
+            if run.run.level.is_some() {
+                continue;
+            }
+
+            let game = match run.game.take() {
+                Some(game) => game.data,
+                None => continue,
+            };
+
+            if let Some(match_game) = match_game.as_deref() {
+                if !game.matches(match_game) {
+                    continue;
+                }
+            }
+
+            let category = match run.category.take() {
+                Some(category) => category.data,
+                None => continue,
+            };
+
+            if category.ty != CategoryType::PerGame {
+                continue;
+            }
+
+            let variables = self
+                .speedrun
+                .category_variables(&category.id)
+                .await?
+                .unwrap_or_default();
+
+            let sub_categories = SubCategory::from_variables(&variables);
+
+            let mut name = category.name.clone();
+            let mut variables = Variables::default();
+
+            if let Some(c) = SubCategory::match_run(&run.run, &sub_categories) {
+                name = format!("{} / {}", name, c.label);
+                variables.insert(&c.key, &c.value);
+            }
+
+            pbs.push(Pb {
+                game,
+                category,
+                name,
+                variables,
+                run,
+            });
+        }
+
+        if query.is_empty() {
+            let lines = pbs
+                .iter()
+                .filter(|pb| !pb.category.miscellaneous)
+                .map(|pb| {
+                    format!(
+                        "{} ({}): {} (#{})",
+                        pb.name,
+                        pb.game.abbreviation,
+                        utils::compact_duration(pb.run.run.times.primary.as_std()),
+                        pb.run.place
+                    )
+                });
+
+            ctx.respond_lines(
+                lines,
+                &message!(self.messages, "speedrun/no-personal-bests"),
+            )
+            .await;
+            return Ok(());
+        }
+
+        let pb = match fuzzy_match(&query, pbs.iter().map(|pb| pb.name.as_str())) {
+            Some(index) => &pbs[index],
+            None => {
+                let names = pbs.iter().map(|pb| pb.name.clone()).collect::<Vec<_>>();
+
+                match utils::human_list(&names) {
+                    Some(names) => respond_bail!(
+                        "{}",
+                        message!(
+                            self.messages,
+                            "speedrun/no-matching-personal-best",
+                            query = query,
+                            names = names,
+                        )
+                    ),
+                    None => {
+                        respond_bail!("{}", message!(self.messages, "speedrun/no-personal-bests"))
+                    }
+                }
+            }
+        };
+
+        let record = self
+            .speedrun
+            .leaderboard(
+                &pb.game.id,
+                &pb.category.id,
+                1,
+                &pb.variables,
+                &Embeds::default(),
+            )
+            .await?;
+
+        let record = record
+            .and_then(|r| r.runs.into_iter().next())
+            .map(|r| r.run.times.primary.into_std());
+
+        let time = pb.run.run.times.primary.as_std();
+
+        let delta = match record {
+            Some(record) => format!(
+                ", {}",
+                record_delta(&self.messages, pb.run.place, time, record)
+            ),
+            None => String::new(),
+        };
+
+        respond!(
+            ctx,
+            "{} ({}) -> {}: {} (#{}{})",
+            pb.game.names.name(),
+            pb.game.abbreviation,
+            pb.name,
+            utils::compact_duration(time),
+            pb.run.place,
+            delta
+        );
+
+        return Ok(());
+
+        /// A personal best in a single category.
+        struct Pb {
+            game: Game,
+            category: Category,
+            name: String,
+            /// Variables identifying the sub category of the run.
+            variables: Variables,
+            run: Run,
+        }
+    }
+}
+
 #[derive(serde::Serialize)]
 #[serde(tag = "method")]
 pub enum Key<'a> {
@@ -517,6 +762,10 @@ pub enum Key<'a> {
         user_id: &'a str,
         embeds: &'a Embeds,
     },
+    OwnPersonalBests {
+        user_id: &'a str,
+        embeds: &'a Embeds,
+    },
     CategoryVariables {
         category_id: &'a str,
     },
@@ -585,6 +834,27 @@ impl CachedSpeedrun {
         Ok(result)
     }
 
+    /// Get personal bests of the streamer.
+    ///
+    /// These are cached for a shorter time than personal bests in general,
+    /// so that new personal bests show up quickly.
+    pub async fn own_personal_bests(
+        &self,
+        user_id: &str,
+        embeds: &Embeds,
+    ) -> Result<Option<Vec<Run>>> {
+        let result = self
+            .cache
+            .wrap(
+                Key::OwnPersonalBests { user_id, embeds },
+                chrono::Duration::minutes(10),
+                self.speedrun.user_personal_bests(user_id, embeds),
+            )
+            .await?;
+
+        Ok(result)
+    }
+
     /// Get the variables of a category.
     pub async fn category_variables(&self, category_id: &str) -> Result<Option<Vec<Variable>>> {
         let result = self
@@ -698,7 +968,11 @@ pub struct Module;
 #[async_trait]
 impl super::Module for Module {
     fn ty(&self) -> &'static str {
-        "8ball"
+        "speedrun"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("speedrun/enabled", false))
     }
 
     /// Set up command handlers for this module.
@@ -726,12 +1000,31 @@ impl super::Module for Module {
             speedrun,
         };
 
+        let enabled = settings.var("speedrun/enabled", false).await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
         handlers.insert(
             "speedrun",
             Speedrun {
-                speedrun,
-                enabled: settings.var("speedrun/enabled", false).await?,
+                speedrun: speedrun.clone(),
+                enabled: enabled.clone(),
                 top: settings.var("speedrun/top", 20).await?,
+                messages: messages.clone(),
+            },
+        );
+
+        handlers.insert(
+            "pb",
+            PersonalBest {
+                speedrun,
+                enabled,
+                user: settings.optional("speedrun/user").await?,
+                game: settings.optional("speedrun/game").await?,
+                messages,
             },
         );
 
@@ -850,6 +1143,67 @@ fn abbreviate_text(mut text: &str) -> String {
     out
 }
 
+/// Normalize a name for fuzzy matching, so that `Any%` becomes `any`.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Find the index of the name which best matches the given query.
+///
+/// Exact matches are preferred over prefixes, which are preferred over
+/// abbreviations like `NMS` for `No Mission Skips`, which are preferred over
+/// names which contain the query. Case and anything which isn't alphanumeric
+/// is ignored.
+fn fuzzy_match<'a>(query: &str, names: impl IntoIterator<Item = &'a str>) -> Option<usize> {
+    let query = normalize(query);
+
+    if query.is_empty() {
+        return None;
+    }
+
+    let mut best = None::<(u32, usize)>;
+
+    for (index, name) in names.into_iter().enumerate() {
+        let normalized = normalize(name);
+
+        let score = if normalized == query {
+            0
+        } else if normalized.starts_with(&query) {
+            1
+        } else if normalize(&abbreviate_text(name)) == query {
+            2
+        } else if normalized.contains(&query) {
+            3
+        } else {
+            continue;
+        };
+
+        if best.map(|(s, _)| score < s).unwrap_or(true) {
+            best = Some((score, index));
+        }
+    }
+
+    best.map(|(_, index)| index)
+}
+
+/// Describe how a personal best compares to the world record.
+fn record_delta(
+    messages: &Messages,
+    place: u32,
+    time: time::Duration,
+    record: time::Duration,
+) -> String {
+    if place == 1 || time <= record {
+        return message!(messages, "speedrun/world-record");
+    }
+
+    let delta = utils::compact_duration(time - record);
+    message!(messages, "speedrun/behind-record", delta = delta)
+}
+
 /// A filter over categories.
 pub struct CategoryFilter {
     /// The category type to filter for.
@@ -934,10 +1288,56 @@ fn match_levels<'a>(level: Option<&String>, levels: &'a [Level]) -> Option<&'a L
 
 #[cfg(test)]
 mod tests {
-    use super::abbreviate_text;
+    use super::{abbreviate_text, fuzzy_match, record_delta};
+    use crate::messages::{Catalog, Messages};
+    use std::time::Duration;
 
     #[test]
     fn test_abbreviate_text() {
         assert_eq!("100% NMS", abbreviate_text("100% No Mission Skips"));
     }
+
+    #[test]
+    fn test_fuzzy_match() {
+        let names = [
+            "Any%",
+            "100% / No Mission Skips",
+            "100% / Mission Skips",
+            "Classic%",
+        ];
+
+        assert_eq!(Some(0), fuzzy_match("any", names.iter().copied()));
+        assert_eq!(Some(0), fuzzy_match("ANY%", names.iter().copied()));
+        assert_eq!(Some(1), fuzzy_match("100", names.iter().copied()));
+        assert_eq!(Some(1), fuzzy_match("100 nms", names.iter().copied()));
+        assert_eq!(Some(2), fuzzy_match("100 ms", names.iter().copied()));
+        assert_eq!(Some(3), fuzzy_match("classic", names.iter().copied()));
+        assert_eq!(Some(1), fuzzy_match("mission skips", names.iter().copied()));
+        assert_eq!(None, fuzzy_match("glitchless", names.iter().copied()));
+        assert_eq!(None, fuzzy_match("%", names.iter().copied()));
+    }
+
+    #[test]
+    fn test_record_delta() {
+        let messages = Messages::new(Catalog::load_static().expect("catalog to load"));
+
+        assert_eq!(
+            "WR",
+            record_delta(
+                &messages,
+                1,
+                Duration::from_secs(100),
+                Duration::from_secs(100)
+            )
+        );
+        assert_eq!(
+            "+1m 5s behind WR",
+            record_delta(
+                &messages,
+                4,
+                Duration::from_secs(165),
+                Duration::from_secs(100)
+            )
+        );
+    }
 }
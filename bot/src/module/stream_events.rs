@@ -0,0 +1,371 @@
+//! Events which happen on stream, like follows, subscriptions, raids, and
+//! cheers.
+//!
+//! Subscriptions, raids, and cheers are picked up from chat, while new
+//! followers are polled for. All of them are broadcast as alerts on the global
+//! bus, recorded in the database, and thanked for in chat if configured.
+//!
+//! Subscription streaks and gift counts are tracked by the chat handler, and
+//! can be looked up through `!subcount`.
+
+use crate::api;
+use crate::auth;
+use crate::bus;
+use crate::chat_stats::ChatStats;
+use crate::command;
+use crate::db;
+use crate::irc;
+use crate::messages;
+use crate::module;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::template::Template;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// How often to check for new followers.
+const FOLLOWERS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of followers to fetch every time we check for new ones.
+const FOLLOWERS_PAGE: u32 = 20;
+
+/// Data available when rendering thank-you messages.
+#[derive(serde::Serialize)]
+struct EventData<'a> {
+    user: &'a str,
+    months: Option<u32>,
+    streak: Option<u32>,
+    recipient: Option<&'a str>,
+    gifts: Option<u32>,
+    viewers: Option<u32>,
+    bits: Option<u32>,
+    amount: Option<&'a str>,
+    message: Option<&'a str>,
+}
+
+/// Thank-you messages for each kind of event.
+struct Messages {
+    follow: settings::Var<Option<Template>>,
+    sub: settings::Var<Option<Template>>,
+    resub: settings::Var<Option<Template>>,
+    subgift: settings::Var<Option<Template>>,
+    raid: settings::Var<Option<Template>>,
+    cheer: settings::Var<Option<Template>>,
+}
+
+impl Messages {
+    /// Get the message template for the given kind of event, if configured.
+    async fn template(&self, kind: bus::AlertKind) -> Option<Template> {
+        match kind {
+            bus::AlertKind::Follow => self.follow.load().await,
+            bus::AlertKind::Sub => self.sub.load().await,
+            bus::AlertKind::Resub => self.resub.load().await,
+            bus::AlertKind::SubGift => self.subgift.load().await,
+            bus::AlertKind::Raid => self.raid.load().await,
+            bus::AlertKind::Cheer => self.cheer.load().await,
+            // NB: donations are thanked for by the donations module.
+            bus::AlertKind::Donation => None,
+        }
+    }
+}
+
+/// Handler for the `!subcount` command.
+pub struct SubCount {
+    enabled: settings::Var<bool>,
+    subscribers: injector::Var<Option<db::Subscribers>>,
+    chat_stats: injector::Var<Option<ChatStats>>,
+    stream_info: stream_info::StreamInfo,
+    messages: messages::Messages,
+}
+
+#[async_trait]
+impl command::Handler for SubCount {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::SubCount)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!subcount [user]",
+            description: "Show the number of subscribers, or how long a user has subscribed.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let user = match ctx.next() {
+            Some(user) => user.trim_start_matches('@').to_string(),
+            None => {
+                let count = self.stream_info.data.read().subs.len();
+
+                let subs = match self.chat_stats.load().await {
+                    Some(chat_stats) => Some(chat_stats.current_stream().subs),
+                    None => None,
+                };
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "stream-events/subscribers",
+                        count = count,
+                        stream = subs.is_some(),
+                        subs = subs.as_ref().map(|s| s.subs),
+                        resubs = subs.as_ref().map(|s| s.resubs),
+                        gifted = subs.as_ref().map(|s| s.gifted),
+                    )
+                );
+                return Ok(());
+            }
+        };
+
+        let subscribers = match self.subscribers.load().await {
+            Some(subscribers) => subscribers,
+            None => respond_bail!("{}", message!(self.messages, "stream-events/not-tracked")),
+        };
+
+        let s = match subscribers.get(&user).await? {
+            Some(s) => s,
+            None => {
+                respond!(
+                    ctx,
+                    message!(self.messages, "stream-events/not-seen", user = user)
+                );
+                return Ok(());
+            }
+        };
+
+        if s.months > 0 {
+            respond!(
+                ctx,
+                message!(
+                    self.messages,
+                    "stream-events/subscribed",
+                    user = user,
+                    months = s.months,
+                    streak = s.streak,
+                    gifts = s.gifts,
+                )
+            );
+        } else {
+            respond!(
+                ctx,
+                message!(
+                    self.messages,
+                    "stream-events/gifted",
+                    user = user,
+                    gifts = s.gifts
+                )
+            );
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "stream-events"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("stream-events/enabled", false))
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            futures,
+            stream_info,
+            streamer_twitch,
+            sender,
+            settings,
+            global_bus,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("stream-events");
+        let enabled = settings.var("enabled", false).await?;
+
+        handlers.insert(
+            "subcount",
+            SubCount {
+                enabled: enabled.clone(),
+                subscribers: injector.var().await?,
+                chat_stats: injector.var().await?,
+                stream_info: stream_info.clone(),
+                messages: injector
+                    .get::<messages::Messages>()
+                    .await
+                    .ok_or_else(|| anyhow!("messages are not configured"))?,
+            },
+        );
+
+        let messages = Messages {
+            follow: settings.optional("follow-message").await?,
+            sub: settings.optional("sub-message").await?,
+            resub: settings.optional("resub-message").await?,
+            subgift: settings.optional("subgift-message").await?,
+            raid: settings.optional("raid-message").await?,
+            cheer: settings.optional("cheer-message").await?,
+        };
+
+        let db = injector.var::<db::Database>().await?;
+        let streamer_twitch = streamer_twitch.clone();
+        let user_id = stream_info.user.id.clone();
+        let sender = sender.clone();
+        let global_bus = global_bus.clone();
+        let mut rx = global_bus.subscribe();
+
+        let future = async move {
+            let mut interval = tokio::time::interval(FOLLOWERS_INTERVAL).fuse();
+            let mut last_follow = None;
+
+            loop {
+                futures::select! {
+                    _ = interval.select_next_some() => {
+                        if !enabled.load().await {
+                            continue;
+                        }
+
+                        let result = poll_followers(
+                            &streamer_twitch,
+                            &user_id,
+                            &global_bus,
+                            &mut last_follow,
+                        );
+
+                        if let Err(e) = result.await {
+                            log_warn!(e, "failed to check for new followers");
+                        }
+                    }
+                    event = rx.select_next_some() => {
+                        let event = event?;
+
+                        let (kind, data) = match &event {
+                            bus::Global::Alert {
+                                kind,
+                                user,
+                                months,
+                                streak,
+                                recipient,
+                                gifts,
+                                viewers,
+                                bits,
+                                amount,
+                                message,
+                            } => {
+                                let data = EventData {
+                                    user,
+                                    months: *months,
+                                    streak: *streak,
+                                    recipient: recipient.as_deref(),
+                                    gifts: *gifts,
+                                    viewers: *viewers,
+                                    bits: *bits,
+                                    amount: amount.as_deref(),
+                                    message: message.as_deref(),
+                                };
+
+                                (*kind, data)
+                            }
+                            _ => continue,
+                        };
+
+                        if !enabled.load().await {
+                            continue;
+                        }
+
+                        if let Some(db) = db.load().await {
+                            record(&db, kind, &data).await;
+                        }
+
+                        if let Some(template) = messages.template(kind).await {
+                            thank(&sender, &template, &data).await;
+                        }
+                    }
+                }
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
+
+/// Check for new followers and raise an alert for each of them.
+///
+/// The first check only establishes which followers we already know of, so
+/// that we don't raise alerts for everyone who followed before the bot started.
+async fn poll_followers(
+    twitch: &api::Twitch,
+    user_id: &str,
+    global_bus: &bus::Bus<bus::Global>,
+    last_follow: &mut Option<DateTime<Utc>>,
+) -> Result<()> {
+    let mut follows = twitch.followers(user_id, FOLLOWERS_PAGE).await?;
+    // NB: oldest first, so that alerts are raised in the order people followed.
+    follows.sort_by_key(|f| f.followed_at);
+
+    let last = match *last_follow {
+        Some(last) => last,
+        None => {
+            let last = follows.last().map(|f| f.followed_at);
+            *last_follow = Some(last.unwrap_or_else(Utc::now));
+            return Ok(());
+        }
+    };
+
+    for follow in follows.into_iter().filter(|f| f.followed_at > last) {
+        *last_follow = Some(follow.followed_at);
+
+        global_bus
+            .send(bus::Global::Alert {
+                kind: bus::AlertKind::Follow,
+                user: follow.from_name,
+                months: None,
+                streak: None,
+                recipient: None,
+                gifts: None,
+                viewers: None,
+                bits: None,
+                amount: None,
+                message: None,
+            })
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Record the event in the database.
+async fn record(db: &db::Database, kind: bus::AlertKind, data: &EventData<'_>) {
+    let event = db::models::AddStreamEvent {
+        kind: kind.as_str().to_string(),
+        user: data.user.to_string(),
+        amount: data.months.or(data.viewers).or(data.bits).map(|n| n as i32),
+        recipient: data.recipient.map(String::from),
+        at: Utc::now().naive_utc(),
+    };
+
+    if let Err(e) = db.stream_event_push(&event).await {
+        log_error!(e, "failed to record stream event");
+    }
+}
+
+/// Send a thank-you message to chat.
+async fn thank(sender: &irc::Sender, template: &Template, data: &EventData<'_>) {
+    match template.render_to_string(data) {
+        Ok(message) => sender.privmsg(message).await,
+        Err(e) => log_error!(e, "failed to render stream event message"),
+    }
+}
@@ -1,19 +1,22 @@
 use crate::api;
 use crate::auth::Scope;
 use crate::command;
-use crate::currency::Currency;
+use crate::currency::{Currency, RewardData, RewardSource, Rewards};
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
 use crate::utils::{Cooldown, Duration};
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use std::collections::HashSet;
 
 pub struct Handler {
     enabled: settings::Var<bool>,
     reward: settings::Var<i64>,
+    rewards: Rewards,
     cooldown: settings::Var<Cooldown>,
     currency: injector::Var<Option<Currency>>,
     twitch: api::Twitch,
+    messages: Messages,
 }
 
 #[async_trait]
@@ -22,6 +25,13 @@ impl command::Handler for Handler {
         Some(Scope::SwearJar)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!swearjar",
+            description: "Punish the streamer for swearing by rewarding everyone in chat.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
@@ -30,21 +40,19 @@ impl command::Handler for Handler {
         let currency = match self.currency.load().await {
             Some(currency) => currency,
             None => {
-                respond!(ctx, "No currency configured for stream, sorry :(");
+                respond!(ctx, message!(self.messages, "swearjar/no-currency"));
                 return Ok(());
             }
         };
 
         if !self.cooldown.write().await.is_open() {
-            respond!(
-                ctx,
-                "A !swearjar command was recently issued, please wait a bit longer!"
-            );
+            respond!(ctx, message!(self.messages, "swearjar/cooldown"));
             return Ok(());
         }
 
         let user = &ctx.user;
         let reward = self.reward.load().await;
+        let reward = self.rewards.amount(RewardSource::Swearjar, reward).await;
 
         let chatters = self.twitch.chatters(user.channel()).await?;
 
@@ -66,10 +74,26 @@ impl command::Handler for Handler {
             .balances_increment(user.channel(), u, reward, 0)
             .await?;
 
-        user.sender().privmsg(format!(
-            "/me has taken {} {currency} from {streamer} and given it to the viewers for listening to their bad mouth!",
-            total_reward, currency = currency.name, streamer = user.streamer().display_name,
-        )).await;
+        let data = RewardData {
+            user: Some(&user.streamer().display_name),
+            amount: total_reward,
+            currency: &currency.name,
+        };
+
+        match self.rewards.message(RewardSource::Swearjar, data).await {
+            Some(message) => user.sender().privmsg(message).await,
+            None => {
+                let message = message!(
+                    self.messages,
+                    "swearjar/rewarded",
+                    amount = total_reward,
+                    currency = currency.name,
+                    streamer = user.streamer().display_name,
+                );
+
+                user.sender().privmsg(message).await
+            }
+        }
 
         Ok(())
     }
@@ -83,6 +107,10 @@ impl super::Module for Module {
         "swearjar"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("swearjar/enabled", false))
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
@@ -97,6 +125,7 @@ impl super::Module for Module {
     ) -> Result<()> {
         let enabled = settings.var("swearjar/enabled", false).await?;
         let reward = settings.var("swearjar/reward", 10).await?;
+        let rewards = Rewards::new(settings).await?;
 
         let (mut cooldown_stream, cooldown) = settings
             .stream("swearjar/cooldown")
@@ -107,14 +136,21 @@ impl super::Module for Module {
 
         let currency = injector.var().await?;
 
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
         handlers.insert(
             "swearjar",
             Handler {
                 enabled,
                 reward,
+                rewards,
                 cooldown: cooldown.clone(),
                 currency,
                 twitch: twitch.clone(),
+                messages,
             },
         );
 
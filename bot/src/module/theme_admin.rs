@@ -1,22 +1,35 @@
 use crate::auth;
 use crate::command;
 use crate::db;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
+use anyhow::anyhow;
+
+/// Usage of the `!theme` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!theme <show|list|edit|edit-duration|rename|delete|enable|disable|group|clear-group>",
+    description: "Manage song themes.",
+};
 
 pub struct Handler {
     pub themes: injector::Var<Option<db::Themes>>,
+    pub messages: Messages,
 }
 
 #[async_trait]
 impl command::Handler for Handler {
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
         let themes = match self.themes.load().await {
             Some(themes) => themes,
             None => return Ok(()),
         };
 
-        let next = command_base!(ctx, themes, "theme", ThemeEdit);
+        let next = command_base!(ctx, self.messages, themes, "theme", ThemeEdit);
 
         match next.as_deref() {
             Some("edit") => {
@@ -26,7 +39,10 @@ impl command::Handler for Handler {
                 let track_id = ctx.next_parse("<name> <track-id>")?;
 
                 themes.edit(ctx.channel(), &name, track_id).await?;
-                respond!(ctx, "Edited theme.");
+                respond!(
+                    ctx,
+                    message!(self.messages, "manage/edited", what = "theme")
+                );
             }
             Some("edit-duration") => {
                 ctx.check_scope(auth::Scope::ThemeEdit).await?;
@@ -38,14 +54,14 @@ impl command::Handler for Handler {
                 themes
                     .edit_duration(ctx.channel(), &name, start, end)
                     .await?;
-                respond!(ctx, "Edited theme.");
-            }
-            None | Some(..) => {
                 respond!(
                     ctx,
-                    "Expected: show, list, edit, edit-duration, delete, enable, disable, or group.",
+                    message!(self.messages, "manage/edited", what = "theme")
                 );
             }
+            None | Some(..) => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
         }
 
         Ok(())
@@ -66,10 +82,16 @@ impl super::Module for Module {
             injector, handlers, ..
         }: module::HookContext<'_>,
     ) -> Result<(), anyhow::Error> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
         handlers.insert(
             "theme",
             Handler {
                 themes: injector.var().await?,
+                messages,
             },
         );
         Ok(())
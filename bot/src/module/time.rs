@@ -21,6 +21,13 @@ impl command::Handler for Time {
         Some(auth::Scope::Time)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!time",
+            description: "Show the current time for the streamer.",
+        })
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
@@ -96,6 +103,10 @@ impl super::Module for Module {
         "time"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("time/enabled", true))
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
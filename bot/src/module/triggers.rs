@@ -0,0 +1,384 @@
+//! Respond to patterns in chat, which aren't commands, with templated
+//! responses.
+//!
+//! Triggers match messages with a regular expression or keywords, and have a
+//! cooldown and a probability of firing when matched. Responses can use
+//! `{{name}}`, `{{target}}`, `{{message}}`, and `{{song}}` for the current
+//! song.
+
+use crate::auth;
+use crate::command;
+use crate::db;
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::player;
+use crate::prelude::*;
+use crate::utils::{Cooldown, Duration};
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use rand::Rng as _;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Usage of the `!trigger` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!trigger <show|list|edit|pattern|keyword|cooldown|chance|rename|delete|enable|disable|group|clear-group>",
+    description: "Manage triggers which respond to patterns in chat.",
+};
+
+/// Variables available in trigger responses.
+#[derive(serde::Serialize)]
+struct Vars<'a> {
+    name: &'a str,
+    target: &'a str,
+    message: &'a str,
+    song: Option<String>,
+}
+
+/// Build a case-insensitive pattern matching the given keywords as whole
+/// words.
+fn keyword_pattern(keywords: &str) -> Option<regex::Regex> {
+    let words = keywords.split_whitespace().collect::<Vec<_>>();
+
+    let (first, last) = match (words.first(), words.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return None,
+    };
+
+    let is_word = |c: Option<char>| c.map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false);
+
+    // NB: word boundaries only apply next to word characters.
+    let start = if is_word(first.chars().next()) {
+        r"\b"
+    } else {
+        ""
+    };
+    let end = if is_word(last.chars().last()) {
+        r"\b"
+    } else {
+        ""
+    };
+
+    let words = words.iter().map(|w| regex::escape(w)).collect::<Vec<_>>();
+    regex::Regex::new(&format!(r"(?i){}{}{}", start, words.join(r"\s+"), end)).ok()
+}
+
+/// Test if a trigger which last fired at `last` is off cooldown at `now`.
+fn is_ready(last: Option<Instant>, cooldown: std::time::Duration, now: Instant) -> bool {
+    match last {
+        Some(last) => now.saturating_duration_since(last) >= cooldown,
+        None => true,
+    }
+}
+
+/// Peeks at chat messages to fire triggers.
+struct Responder {
+    triggers: injector::Var<Option<db::Triggers>>,
+    song: injector::Var<Option<player::Song>>,
+    cooldown: settings::Var<Cooldown>,
+    fired: Mutex<HashMap<db::Key, Instant>>,
+}
+
+#[async_trait]
+impl command::MessageHook for Responder {
+    async fn peek(&self, user: &irc::User, m: &str) -> Result<()> {
+        let name = match user.real() {
+            Some(user) => user.display_name().to_string(),
+            None => return Ok(()),
+        };
+
+        if m.starts_with('!') {
+            return Ok(());
+        }
+
+        let triggers = match self.triggers.load().await {
+            Some(triggers) => triggers,
+            None => return Ok(()),
+        };
+
+        let mut triggers = triggers.list(user.channel()).await;
+        triggers.sort_by(|a, b| a.key.name.cmp(&b.key.name));
+
+        for trigger in triggers {
+            if !trigger.is_match(m) {
+                continue;
+            }
+
+            let now = Instant::now();
+            let last = self.fired.lock().get(&trigger.key).copied();
+
+            if !is_ready(last, trigger.cooldown.as_std(), now) {
+                continue;
+            }
+
+            if rand::thread_rng().gen_range(0, 100) >= trigger.probability {
+                continue;
+            }
+
+            if !self.cooldown.write().await.is_open() {
+                return Ok(());
+            }
+
+            self.fired.lock().insert(trigger.key.clone(), now);
+
+            let song = match self.song.load().await {
+                Some(song) if trigger.has_var("song") => Some(song.item.what()),
+                _ => None,
+            };
+
+            let response = trigger.render(&Vars {
+                name: &name,
+                target: user.channel(),
+                message: m,
+                song,
+            })?;
+
+            if !response.is_empty() {
+                user.sender().privmsg(response).await;
+            }
+
+            return Ok(());
+        }
+
+        Ok(())
+    }
+}
+
+/// Handler for the `!trigger` command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    triggers: injector::Var<Option<db::Triggers>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::TriggerEdit)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let triggers = match self.triggers.load().await {
+            Some(triggers) => triggers,
+            None => return Ok(()),
+        };
+
+        let next = command_base!(ctx, self.messages, triggers, "trigger", TriggerEdit);
+
+        match next.as_deref() {
+            Some("edit") => {
+                let name = ctx.next_str("<name> <template>")?;
+                let template = ctx.rest_parse("<name> <template>")?;
+                triggers.edit(ctx.channel(), &name, template).await?;
+
+                match triggers.get_any(ctx.channel(), &name).await? {
+                    Some(trigger) if trigger.pattern.is_none() => respond!(
+                        ctx,
+                        message!(self.messages, "triggers/edited-without-pattern")
+                    ),
+                    _ => respond!(
+                        ctx,
+                        message!(self.messages, "manage/edited", what = "trigger")
+                    ),
+                }
+            }
+            Some("pattern") => {
+                let name = ctx.next_str("<name> <pattern>")?;
+
+                let pattern = match regex::Regex::new(ctx.rest().trim()) {
+                    Ok(pattern) if !pattern.as_str().is_empty() => pattern,
+                    Ok(..) => respond_bail!("Expected: <name> <pattern>"),
+                    Err(e) => respond_bail!(
+                        "{}",
+                        message!(self.messages, "manage/bad-pattern", error = e.to_string())
+                    ),
+                };
+
+                if !triggers.edit_pattern(ctx.channel(), &name, pattern).await? {
+                    respond_bail!(
+                        "{}",
+                        message!(
+                            self.messages,
+                            "manage/missing",
+                            what = "trigger",
+                            name = name
+                        )
+                    );
+                }
+
+                respond!(
+                    ctx,
+                    message!(self.messages, "manage/pattern-edited", what = "trigger")
+                );
+            }
+            Some("keyword") => {
+                let name = ctx.next_str("<name> <keywords...>")?;
+
+                let pattern = match keyword_pattern(ctx.rest()) {
+                    Some(pattern) => pattern,
+                    None => respond_bail!("Expected: <name> <keywords...>"),
+                };
+
+                if !triggers.edit_pattern(ctx.channel(), &name, pattern).await? {
+                    respond_bail!(
+                        "{}",
+                        message!(
+                            self.messages,
+                            "manage/missing",
+                            what = "trigger",
+                            name = name
+                        )
+                    );
+                }
+
+                respond!(ctx, message!(self.messages, "triggers/keywords-edited"));
+            }
+            Some("cooldown") => {
+                let name = ctx.next_str("<name> <duration>")?;
+                let cooldown = ctx.next_parse::<Duration, _>("<name> <duration>")?;
+
+                if !triggers
+                    .edit_cooldown(ctx.channel(), &name, cooldown)
+                    .await?
+                {
+                    respond_bail!(
+                        "{}",
+                        message!(
+                            self.messages,
+                            "manage/missing",
+                            what = "trigger",
+                            name = name
+                        )
+                    );
+                }
+
+                respond!(ctx, message!(self.messages, "triggers/cooldown-edited"));
+            }
+            Some("chance") => {
+                let name = ctx.next_str("<name> <percent>")?;
+                let probability = ctx.next_parse::<u32, _>("<name> <percent>")?;
+
+                if probability > 100 {
+                    respond_bail!("{}", message!(self.messages, "triggers/bad-chance"));
+                }
+
+                if !triggers
+                    .edit_probability(ctx.channel(), &name, probability)
+                    .await?
+                {
+                    respond_bail!(
+                        "{}",
+                        message!(
+                            self.messages,
+                            "manage/missing",
+                            what = "trigger",
+                            name = name
+                        )
+                    );
+                }
+
+                respond!(ctx, message!(self.messages, "triggers/chance-edited"));
+            }
+            None | Some(..) => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "triggers"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("triggers/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("triggers");
+        let triggers = injector.var().await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert_hook(Responder {
+            triggers: triggers.clone(),
+            song: injector.var().await?,
+            cooldown: settings
+                .var("cooldown", Cooldown::from_duration(Duration::seconds(5)))
+                .await?,
+            fired: Default::default(),
+        });
+
+        handlers.insert(
+            "trigger",
+            Handler {
+                enabled: settings.var("enabled", false).await?,
+                triggers,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_ready, keyword_pattern};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_keyword_pattern() {
+        let pattern = keyword_pattern("what  song").unwrap();
+        assert!(pattern.is_match("hey, WHAT song is this?"));
+        assert!(pattern.is_match("what\tsong"));
+        assert!(!pattern.is_match("whatsong is this"));
+        assert!(!pattern.is_match("somewhat songs"));
+
+        let pattern = keyword_pattern("c++").unwrap();
+        assert!(pattern.is_match("do you like c++"));
+
+        assert!(keyword_pattern("  ").is_none());
+    }
+
+    #[test]
+    fn test_is_ready() {
+        let now = Instant::now();
+        let cooldown = Duration::from_secs(60);
+
+        assert!(is_ready(None, cooldown, now));
+        assert!(!is_ready(
+            Some(now),
+            cooldown,
+            now + Duration::from_secs(59)
+        ));
+        assert!(is_ready(Some(now), cooldown, now + Duration::from_secs(60)));
+    }
+}
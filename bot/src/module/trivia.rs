@@ -0,0 +1,634 @@
+//! Trivia rounds played in chat.
+//!
+//! Moderators start a round with `!trivia start [category]`, after which the
+//! bot asks a number of questions picked at random from the question bank.
+//! The first user to answer a question correctly in chat wins it, and is
+//! rewarded with stream currency if configured. Questions are imported
+//! through the web API.
+
+use crate::auth::Scope;
+use crate::command;
+use crate::currency::{Currency, RewardData, RewardSource, Rewards};
+use crate::db;
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::utils::Duration;
+use anyhow::{anyhow, Result};
+use tokio::sync::Mutex;
+
+/// Usage of the `!trivia` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!trivia <start [category]|stop|categories|scores>",
+    description: "Play a round of trivia in chat.",
+};
+
+/// Pause before each question is asked.
+const PAUSE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Number of users listed on the scoreboard.
+const SCOREBOARD: usize = 5;
+
+/// Articles which are ignored at the start of answers.
+const ARTICLES: &[&str] = &["the", "a", "an"];
+
+/// Something that happened during a round.
+enum Event {
+    /// A question was answered correctly.
+    Answer {
+        question_id: i32,
+        name: String,
+        display_name: String,
+    },
+    /// The round was stopped.
+    Stop,
+}
+
+/// A round waiting to be played.
+struct Round {
+    ctx: command::Context,
+    questions: Vec<db::TriviaQuestion>,
+    /// Sender used by the hook which peeks for answers.
+    answers: mpsc::UnboundedSender<Event>,
+    /// Events of the round, including a stop sent while it was queued.
+    events: mpsc::UnboundedReceiver<Event>,
+}
+
+#[derive(Default)]
+struct State {
+    /// If a round is running.
+    running: bool,
+    /// The question currently waiting for an answer.
+    question: Option<db::TriviaQuestion>,
+    /// Events of the queued or running round.
+    events: Option<mpsc::UnboundedSender<Event>>,
+}
+
+/// Handler for the `!trivia` command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    questions_per_round: settings::Var<u32>,
+    trivia: injector::Var<Option<db::Trivia>>,
+    stream_info: stream_info::StreamInfo,
+    state: Arc<Mutex<State>>,
+    rounds: mpsc::UnboundedSender<Round>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<Scope> {
+        Some(Scope::Trivia)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let trivia = match self.trivia.load().await {
+            Some(trivia) => trivia,
+            None => respond_bail!("{}", message!(self.messages, "trivia/not-configured")),
+        };
+
+        match ctx.next().as_deref() {
+            Some("start") => {
+                ctx.check_scope(Scope::TriviaEdit).await?;
+
+                let category = match ctx.rest().trim() {
+                    "" => None,
+                    category => Some(category.to_string()),
+                };
+
+                let mut state = self.state.lock().await;
+
+                if state.running {
+                    respond_bail!("{}", message!(self.messages, "trivia/already-running"));
+                }
+
+                let limit = self.questions_per_round.load().await as usize;
+                let questions = trivia.pick(category.clone(), limit).await?;
+
+                if questions.is_empty() {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "trivia/no-questions", category = category)
+                    );
+                }
+
+                let (tx, events) = mpsc::unbounded();
+
+                let round = Round {
+                    ctx: ctx.clone(),
+                    questions,
+                    answers: tx.clone(),
+                    events,
+                };
+
+                if self.rounds.unbounded_send(round).is_err() {
+                    respond_bail!("{}", message!(self.messages, "trivia/shutting-down"));
+                }
+
+                state.running = true;
+                state.events = Some(tx);
+            }
+            Some("stop") => {
+                ctx.check_scope(Scope::TriviaEdit).await?;
+
+                let state = self.state.lock().await;
+
+                match &state.events {
+                    Some(events) => {
+                        let _ = events.unbounded_send(Event::Stop);
+                    }
+                    None => respond!(ctx, message!(self.messages, "trivia/not-running")),
+                }
+            }
+            Some("categories") => {
+                let categories = trivia.categories().await?;
+
+                if categories.is_empty() {
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "trivia/no-questions",
+                            category = None::<String>
+                        )
+                    );
+                } else {
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "trivia/categories",
+                            categories = categories.join(", ")
+                        )
+                    );
+                }
+            }
+            Some("scores") => {
+                let stream = match self.stream_info.started_at() {
+                    Some(started_at) => Some(started_at.naive_utc()),
+                    None => trivia.latest_stream().await?,
+                };
+
+                let scores = match stream {
+                    Some(stream) => trivia.scoreboard(stream, SCOREBOARD).await?,
+                    None => Vec::new(),
+                };
+
+                if scores.is_empty() {
+                    respond!(ctx, message!(self.messages, "trivia/no-scores"));
+                } else {
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "trivia/scores",
+                            scores = format_scores(&scores)
+                        )
+                    );
+                }
+            }
+            _ => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Peeks at chat messages for answers to the current question.
+struct Answers {
+    state: Arc<Mutex<State>>,
+    events: mpsc::UnboundedSender<Event>,
+}
+
+#[async_trait]
+impl command::MessageHook for Answers {
+    async fn peek(&self, user: &irc::User, m: &str) -> Result<()> {
+        let user = match user.real() {
+            Some(user) => user,
+            None => return Ok(()),
+        };
+
+        let mut state = self.state.lock().await;
+
+        let correct = match &state.question {
+            Some(q) => q.answers.iter().any(|a| is_match(m, a)),
+            None => false,
+        };
+
+        if !correct {
+            return Ok(());
+        }
+
+        // NB: take the question so that nobody else can answer it.
+        if let Some(q) = state.question.take() {
+            let _ = self.events.unbounded_send(Event::Answer {
+                question_id: q.id,
+                name: user.name().to_string(),
+                display_name: user.display_name().to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings and services used while playing a round.
+struct Game {
+    question_time: settings::Var<Duration>,
+    reward: settings::Var<i64>,
+    rewards: Rewards,
+    currency: injector::Var<Option<Currency>>,
+    trivia: injector::Var<Option<db::Trivia>>,
+    stream_info: stream_info::StreamInfo,
+    state: Arc<Mutex<State>>,
+    messages: Messages,
+}
+
+impl Game {
+    /// Play a single round of trivia.
+    async fn play(&self, round: Round) {
+        let Round {
+            ctx,
+            questions,
+            answers,
+            mut events,
+        } = round;
+
+        // NB: the round might have been stopped while it was queued.
+        if let Ok(Some(Event::Stop)) = events.try_next() {
+            self.state.lock().await.events = None;
+            ctx.privmsg(message!(self.messages, "trivia/stopped")).await;
+            return;
+        }
+
+        let hook = ctx
+            .insert_hook(Answers {
+                state: self.state.clone(),
+                events: answers,
+            })
+            .await;
+
+        ctx.privmsg(message!(
+            self.messages,
+            "trivia/starting",
+            count = questions.len()
+        ))
+        .await;
+
+        let total = questions.len();
+        let mut stopped = false;
+
+        for (n, q) in questions.into_iter().enumerate() {
+            tokio::time::delay_for(PAUSE).await;
+
+            self.state.lock().await.question = Some(q.clone());
+
+            ctx.privmsg(message!(
+                self.messages,
+                "trivia/question",
+                number = n + 1,
+                total = total,
+                category = q.category,
+                question = q.question,
+            ))
+            .await;
+
+            let question_time = self.question_time.load().await;
+            let mut timeout = tokio::time::delay_for(question_time.as_std()).fuse();
+
+            let event = loop {
+                futures::select! {
+                    event = events.select_next_some() => match event {
+                        // NB: answer to a question which has already timed out.
+                        Event::Answer { question_id, .. } if question_id != q.id => continue,
+                        event => break Some(event),
+                    },
+                    _ = timeout => break None,
+                }
+            };
+
+            self.state.lock().await.question = None;
+            let answer = q.answers.first().map(String::as_str).unwrap_or_default();
+
+            match event {
+                Some(Event::Answer {
+                    name, display_name, ..
+                }) => {
+                    let (reward, message) = self.reward(&ctx, &name, q.id).await;
+                    let (amount, currency) = match reward {
+                        Some((amount, currency)) => (Some(amount), Some(currency)),
+                        None => (None, None),
+                    };
+
+                    ctx.privmsg(message!(
+                        self.messages,
+                        "trivia/correct",
+                        user = display_name,
+                        amount = amount,
+                        currency = currency,
+                        answer = answer,
+                    ))
+                    .await;
+
+                    if let Some(message) = message {
+                        ctx.privmsg(message).await;
+                    }
+                }
+                Some(Event::Stop) => {
+                    stopped = true;
+                    break;
+                }
+                None => {
+                    ctx.privmsg(message!(self.messages, "trivia/timeout", answer = answer))
+                        .await;
+                }
+            }
+        }
+
+        ctx.remove_hook(hook).await;
+
+        {
+            let mut state = self.state.lock().await;
+            state.question = None;
+            state.events = None;
+        }
+
+        if stopped {
+            ctx.privmsg(message!(self.messages, "trivia/stopped")).await;
+            return;
+        }
+
+        let scores = match self.scores().await {
+            Ok(scores) => scores,
+            Err(e) => {
+                log_error!(e, "failed to load trivia scoreboard");
+                Vec::new()
+            }
+        };
+
+        let scores = if scores.is_empty() {
+            None
+        } else {
+            Some(format_scores(&scores))
+        };
+
+        ctx.privmsg(message!(self.messages, "trivia/over", scores = scores))
+            .await;
+    }
+
+    /// Record a correct answer and reward the user who gave it.
+    ///
+    /// Returns the amount and name of the currency rewarded, and the
+    /// configured reward message if there is one.
+    async fn reward(
+        &self,
+        ctx: &command::Context,
+        name: &str,
+        question_id: i32,
+    ) -> (Option<(i64, String)>, Option<String>) {
+        if let Some(trivia) = self.trivia.load().await {
+            let win = db::AddTriviaWin {
+                user: name.to_string(),
+                question_id,
+                stream_started_at: self.stream_info.started_at().map(|at| at.naive_utc()),
+            };
+
+            if let Err(e) = trivia.push_win(win).await {
+                log_error!(e, "failed to record trivia win");
+            }
+        }
+
+        let reward = self.reward.load().await;
+        let reward = self.rewards.amount(RewardSource::TriviaWin, reward).await;
+
+        if reward <= 0 {
+            return (None, None);
+        }
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => return (None, None),
+        };
+
+        if let Err(e) = currency.balance_add(ctx.channel(), name, reward).await {
+            log_error!(e, "failed to reward {} for trivia", name);
+            return (None, None);
+        }
+
+        let data = RewardData {
+            user: Some(name),
+            amount: reward,
+            currency: &currency.name,
+        };
+
+        let message = self.rewards.message(RewardSource::TriviaWin, data).await;
+        (Some((reward, currency.name.to_string())), message)
+    }
+
+    /// Get the scoreboard of the current stream.
+    async fn scores(&self) -> Result<Vec<db::TriviaScore>> {
+        let trivia = match self.trivia.load().await {
+            Some(trivia) => trivia,
+            None => return Ok(Vec::new()),
+        };
+
+        match self.stream_info.started_at() {
+            Some(started_at) => trivia.scoreboard(started_at.naive_utc(), SCOREBOARD).await,
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "trivia"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("trivia/enabled", false))
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            futures,
+            stream_info,
+            settings,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let root = settings;
+        let settings = settings.scoped("trivia");
+        let enabled = settings.var("enabled", false).await?;
+        let trivia = injector.var::<db::Trivia>().await?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let (rounds, mut rounds_rx) = mpsc::unbounded();
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "trivia",
+            Handler {
+                enabled,
+                questions_per_round: settings.var("questions-per-round", 5).await?,
+                trivia: trivia.clone(),
+                stream_info: stream_info.clone(),
+                state: state.clone(),
+                rounds,
+                messages: messages.clone(),
+            },
+        );
+
+        let game = Game {
+            question_time: settings.var("question-time", Duration::seconds(30)).await?,
+            reward: settings.var("reward", 50).await?,
+            rewards: Rewards::new(root).await?,
+            currency: injector.var().await?,
+            trivia,
+            stream_info: stream_info.clone(),
+            state,
+            messages,
+        };
+
+        let future = async move {
+            while let Some(round) = rounds_rx.next().await {
+                game.play(round).await;
+                game.state.lock().await.running = false;
+            }
+
+            Ok(())
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
+
+/// Format the scoreboard for chat.
+fn format_scores(scores: &[db::TriviaScore]) -> String {
+    scores
+        .iter()
+        .map(|s| format!("{} ({})", s.user, s.wins))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Normalize an answer into the lowercase words it consists of.
+///
+/// Punctuation and leading articles are dropped, so that `The Beatles!`
+/// becomes `["beatles"]`.
+fn words(s: &str) -> Vec<String> {
+    let mut words = s
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+
+    if words.len() > 1 && ARTICLES.contains(&words[0].as_str()) {
+        words.remove(0);
+    }
+
+    words
+}
+
+/// Calculate the edit distance between two strings, in characters.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(prev + cost);
+            prev = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Test if a chat message answers a question with the given answer.
+///
+/// The answer can appear anywhere in the message, and small typos are
+/// tolerated unless the answer is short or contains numbers.
+fn is_match(message: &str, answer: &str) -> bool {
+    let answer = words(answer);
+
+    if answer.is_empty() {
+        return false;
+    }
+
+    let message = words(message);
+    let answer = answer.join(" ");
+
+    let tolerance = if answer.chars().any(char::is_numeric) {
+        0
+    } else {
+        (answer.chars().count() / 5).min(2)
+    };
+
+    let len = answer.split(' ').count();
+
+    message
+        .windows(len)
+        .any(|w| distance(&w.join(" "), &answer) <= tolerance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distance, is_match, words};
+
+    #[test]
+    fn test_words() {
+        assert_eq!(vec!["beatles"], words("The Beatles!"));
+        assert_eq!(vec!["a"], words("A"));
+        assert_eq!(vec!["new", "york", "city"], words("  new-york  City? "));
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(0, distance("paris", "paris"));
+        assert_eq!(1, distance("pariss", "paris"));
+        assert_eq!(2, distance("prais", "paris"));
+        assert_eq!(5, distance("", "paris"));
+        assert_eq!(3, distance("kitten", "sitting"));
+    }
+
+    #[test]
+    fn test_is_match() {
+        assert!(is_match("Paris", "paris"));
+        assert!(is_match("is it paris?", "Paris"));
+        assert!(is_match("the beatles", "Beatles"));
+        assert!(is_match("the beatls", "The Beatles"));
+        assert!(is_match("new york city", "New York City"));
+        assert!(!is_match("york", "New York City"));
+        assert!(!is_match("cat", "car"));
+        assert!(!is_match("1998", "1999"));
+        assert!(is_match("it was 1999", "1999"));
+        assert!(!is_match("anything", ""));
+    }
+}
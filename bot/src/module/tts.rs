@@ -0,0 +1,502 @@
+//! Text-to-speech for selected chat messages.
+//!
+//! Messages are queued either by redeeming the channel points reward
+//! configured in `tts/reward-id`, or by moderators through `!tts say`. Queued
+//! messages are spoken one at a time through the configured backend.
+
+use crate::api;
+use crate::auth;
+use crate::bus;
+use crate::command;
+use crate::db;
+use crate::irc;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::{anyhow, bail, Result};
+use futures::future::FusedFuture as _;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Usage of the `!tts` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!tts <say <message>|skip|clear|pause|resume>",
+    description: "Speak a message, or control the text-to-speech queue.",
+};
+
+/// Roughly how many characters are spoken per second. Used to estimate how
+/// long a message takes to speak when the backend can't tell us.
+const CHARACTERS_PER_SECOND: u64 = 12;
+/// Pause between each spoken message.
+const GAP: Duration = Duration::from_secs(1);
+/// The maximum number of messages in the queue.
+const MAX_QUEUE: usize = 50;
+
+/// The backend used to speak messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum Backend {
+    /// Speak through the browser's speech synthesis in the overlay.
+    #[serde(rename = "overlay")]
+    Overlay,
+    /// Synthesize with Google Cloud Text-to-Speech, and play it in the overlay.
+    #[serde(rename = "google")]
+    Google,
+    /// Speak locally with espeak.
+    #[serde(rename = "espeak")]
+    Espeak,
+}
+
+/// A message waiting to be spoken.
+struct Entry {
+    user: String,
+    text: String,
+}
+
+enum Event {
+    /// Add a message to the queue.
+    Push(Entry),
+    /// Stop speaking the current message.
+    Skip,
+    /// Remove all queued messages.
+    Clear,
+    /// Stop speaking messages until resumed.
+    Pause,
+    /// Resume speaking messages.
+    Resume,
+}
+
+/// Queue of messages to speak, shared by the command and redemptions.
+#[derive(Clone)]
+struct Queue {
+    sender: mpsc::UnboundedSender<Event>,
+    cooldown: settings::Var<utils::Duration>,
+    max_length: settings::Var<usize>,
+    bad_words: injector::Var<Option<db::Words>>,
+    /// When each user last had a message queued.
+    last: Arc<Mutex<HashMap<String, Instant>>>,
+    /// The number of messages which are queued, including ones which haven't
+    /// been received by the speaker yet.
+    len: Arc<AtomicUsize>,
+    messages: Messages,
+}
+
+impl Queue {
+    /// Queue up a message from the given user.
+    async fn push(&self, user: &str, text: &str) -> Result<()> {
+        if self.len.load(Ordering::SeqCst) >= MAX_QUEUE {
+            respond_bail!("{}", message!(self.messages, "tts/queue-full"));
+        }
+
+        let cooldown = self.cooldown.load().await.as_std();
+
+        {
+            let mut last = self.last.lock().await;
+
+            if let Some(at) = last.get(user) {
+                let elapsed = at.elapsed();
+
+                if elapsed < cooldown {
+                    respond_bail!(
+                        "{}",
+                        message!(
+                            self.messages,
+                            "tts/cooldown",
+                            remaining = utils::compact_duration(cooldown - elapsed)
+                        )
+                    );
+                }
+            }
+
+            last.insert(user.to_string(), Instant::now());
+        }
+
+        let mut text = match self.bad_words.load().await {
+            Some(bad_words) => {
+                let tester = bad_words.tester().await;
+                strip_words(text, |word| tester.test(word).is_some())
+            }
+            None => text.trim().to_string(),
+        };
+
+        let max_length = self.max_length.load().await;
+
+        if let Some((index, _)) = text.char_indices().nth(max_length) {
+            text.truncate(index);
+        }
+
+        if text.is_empty() {
+            respond_bail!("{}", message!(self.messages, "tts/empty"));
+        }
+
+        let reserved = self
+            .len
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |len| {
+                if len < MAX_QUEUE {
+                    Some(len + 1)
+                } else {
+                    None
+                }
+            });
+
+        if reserved.is_err() {
+            respond_bail!("{}", message!(self.messages, "tts/queue-full"));
+        }
+
+        let result = self.send(Event::Push(Entry {
+            user: user.to_string(),
+            text,
+        }));
+
+        if result.is_err() {
+            self.len.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        result
+    }
+
+    /// Send an event to the queue.
+    fn send(&self, event: Event) -> Result<()> {
+        if self.sender.unbounded_send(event).is_err() {
+            respond_bail!("{}", message!(self.messages, "tts/not-running"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Speaks messages through the configured backend.
+struct Speaker {
+    backend: settings::Var<Backend>,
+    voice: settings::Var<Option<String>>,
+    language: settings::Var<String>,
+    google: injector::Var<Option<api::GoogleTts>>,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+}
+
+impl Speaker {
+    /// Speak the given text, completing once it has been spoken.
+    async fn speak(&self, text: String) -> Result<()> {
+        let voice = self.voice.load().await;
+
+        match self.backend.load().await {
+            Backend::Overlay => {
+                let duration = estimate(&text);
+
+                self.global_bus
+                    .send(bus::Global::OverlayTts {
+                        text,
+                        voice,
+                        audio: None,
+                    })
+                    .await;
+
+                tokio::time::delay_for(duration).await;
+            }
+            Backend::Google => {
+                let google = match self.google.load().await {
+                    Some(google) => google,
+                    None => bail!("Google text-to-speech is not configured"),
+                };
+
+                let language = self.language.load().await;
+                let audio = google
+                    .synthesize(&text, &language, voice.as_deref())
+                    .await?;
+                let duration = estimate(&text);
+
+                self.global_bus
+                    .send(bus::Global::OverlayTts {
+                        text,
+                        voice: None,
+                        audio: Some(format!("data:audio/mp3;base64,{}", audio)),
+                    })
+                    .await;
+
+                tokio::time::delay_for(duration).await;
+            }
+            Backend::Espeak => {
+                let mut command = tokio::process::Command::new("espeak");
+
+                if let Some(voice) = &voice {
+                    command.arg("-v").arg(voice);
+                }
+
+                // NB: killed if the message is skipped.
+                command.arg("--").arg(&text).kill_on_drop(true);
+
+                let status = command.status().await?;
+
+                if !status.success() {
+                    bail!("espeak exited with {}", status);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop speaking the current message.
+    async fn skip(&self) {
+        match self.backend.load().await {
+            Backend::Overlay | Backend::Google => {
+                self.global_bus.send(bus::Global::OverlayTtsSkip).await;
+            }
+            // NB: espeak is stopped by dropping the future speaking it.
+            Backend::Espeak => (),
+        }
+    }
+}
+
+/// Handler for the `!tts` command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    queue: Queue,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Tts)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        match ctx.next().as_deref() {
+            Some("say") => {
+                let user = match ctx.user.name() {
+                    Some(name) => name.to_string(),
+                    None => String::from("api"),
+                };
+
+                self.queue.push(&user, ctx.rest()).await?;
+                respond!(ctx, message!(self.messages, "tts/queued"));
+            }
+            Some("skip") => {
+                self.queue.send(Event::Skip)?;
+                respond!(ctx, message!(self.messages, "tts/skipped"));
+            }
+            Some("clear") => {
+                self.queue.send(Event::Clear)?;
+                respond!(ctx, message!(self.messages, "tts/cleared"));
+            }
+            Some("pause") => {
+                self.queue.send(Event::Pause)?;
+                respond!(ctx, message!(self.messages, "tts/paused"));
+            }
+            Some("resume") => {
+                self.queue.send(Event::Resume)?;
+                respond!(ctx, message!(self.messages, "tts/resumed"));
+            }
+            _ => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "tts"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("tts/enabled", false))
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            futures,
+            settings,
+            injector,
+            sender,
+            global_bus,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("tts");
+        let enabled = settings.var("enabled", false).await?;
+        let reward_id = settings.optional::<String>("reward-id").await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        let (tx, mut rx) = mpsc::unbounded();
+
+        let queue = Queue {
+            sender: tx,
+            cooldown: settings
+                .var("cooldown", utils::Duration::seconds(60))
+                .await?,
+            max_length: settings.var("max-length", 200).await?,
+            bad_words: injector.var().await?,
+            last: Arc::new(Mutex::new(HashMap::new())),
+            len: Arc::new(AtomicUsize::new(0)),
+            messages: messages.clone(),
+        };
+
+        let speaker = Speaker {
+            backend: settings.var("backend", Backend::Overlay).await?,
+            voice: settings.optional("voice").await?,
+            language: settings
+                .var("google/language", String::from("en-US"))
+                .await?,
+            google: injector.var().await?,
+            global_bus: global_bus.clone(),
+        };
+
+        handlers.insert(
+            "tts",
+            Handler {
+                enabled: enabled.clone(),
+                queue: queue.clone(),
+                messages,
+            },
+        );
+
+        let sender = sender.clone();
+        let mut redemptions = global_bus.subscribe();
+
+        let future = async move {
+            let mut entries = VecDeque::new();
+            let mut paused = false;
+            let mut current = future::Fuse::terminated();
+
+            loop {
+                if current.is_terminated() && !paused {
+                    if let Some(entry) = entries.pop_front() {
+                        queue.len.fetch_sub(1, Ordering::SeqCst);
+                        let Entry { user, text } = entry;
+                        log::trace!("Speaking message from {}: {}", user, text);
+                        current = speak(&speaker, text).boxed().fuse();
+                    }
+                }
+
+                futures::select! {
+                    event = redemptions.select_next_some() => {
+                        let (redeemed, user, text) = match event? {
+                            bus::Global::Redemption { reward_id, user, text } => {
+                                (reward_id, user, text)
+                            }
+                            _ => continue,
+                        };
+
+                        if !enabled.load().await {
+                            continue;
+                        }
+
+                        match reward_id.load().await {
+                            Some(reward_id) if reward_id == redeemed => (),
+                            _ => continue,
+                        }
+
+                        if let Err(e) = queue.push(&user, &text).await {
+                            respond_redemption(&sender, &user, e).await;
+                        }
+                    }
+                    event = rx.select_next_some() => {
+                        match event {
+                            Event::Push(entry) => {
+                                // NB: room in the queue was reserved when
+                                // the message was pushed.
+                                entries.push_back(entry);
+                            }
+                            Event::Skip => {
+                                current = future::Fuse::terminated();
+                                speaker.skip().await;
+                            }
+                            Event::Clear => {
+                                queue.len.fetch_sub(entries.len(), Ordering::SeqCst);
+                                entries.clear();
+                            }
+                            Event::Pause => {
+                                paused = true;
+                            }
+                            Event::Resume => {
+                                paused = false;
+                            }
+                        }
+                    }
+                    result = current => {
+                        if let Err(e) = result {
+                            log_warn!(e, "Failed to speak message");
+                        }
+                    }
+                }
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
+
+/// Speak the given text, followed by a short pause.
+async fn speak(speaker: &Speaker, text: String) -> Result<()> {
+    speaker.speak(text).await?;
+    tokio::time::delay_for(GAP).await;
+    Ok(())
+}
+
+/// Let the user know why their redemption wasn't queued.
+async fn respond_redemption(sender: &irc::Sender, user: &str, e: anyhow::Error) {
+    match e.downcast_ref::<command::Respond>() {
+        Some(command::Respond(respond)) => {
+            sender.privmsg(format!("{} -> {}", user, respond)).await;
+        }
+        None => {
+            log_error!(e, "Failed to queue text-to-speech redemption");
+        }
+    }
+}
+
+/// Estimate how long it takes to speak the given text.
+fn estimate(text: &str) -> Duration {
+    let characters = text.chars().count() as u64;
+    Duration::from_secs(1 + characters / CHARACTERS_PER_SECOND)
+}
+
+/// Strip all words which are banned from the given text.
+fn strip_words(text: &str, is_banned: impl Fn(&str) -> bool) -> String {
+    text.split_whitespace()
+        .filter(|word| !is_banned(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate, strip_words};
+    use std::time::Duration;
+
+    #[test]
+    fn test_strip_words() {
+        let stripped = strip_words("  hello  bad world bad ", |w| w == "bad");
+        assert_eq!("hello world", stripped);
+    }
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(Duration::from_secs(1), estimate(""));
+        assert_eq!(Duration::from_secs(3), estimate("twenty-four characters!!"));
+    }
+}
@@ -0,0 +1,156 @@
+//! Quick lookups of what is known about a user, for moderators.
+//!
+//! Activity in chat is recorded by [db::Chatters], while timeouts and bans
+//! are read from the [db::ModerationLog].
+
+use crate::auth;
+use crate::command;
+use crate::currency::Currency;
+use crate::db;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+
+/// Handler for the `!user` command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    chatters: injector::Var<Option<db::Chatters>>,
+    moderation_log: injector::Var<Option<db::ModerationLog>>,
+    currency: injector::Var<Option<Currency>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::UserInfo)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!user <name>",
+            description: "Show what is known about a user in chat.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let chatters = match self.chatters.load().await {
+            Some(chatters) => chatters,
+            None => respond_bail!("{}", message!(self.messages, "user-info/not-available")),
+        };
+
+        let user = ctx.next_str("<name>")?;
+        let user = user.trim_start_matches('@').to_lowercase();
+
+        let chatter = match chatters.get(ctx.channel(), &user).await? {
+            Some(chatter) => chatter,
+            None => respond_bail!(
+                "{}",
+                message!(self.messages, "user-info/not-seen", user = user)
+            ),
+        };
+
+        let now = Utc::now();
+        let first_seen = DateTime::<Utc>::from_utc(chatter.first_seen, Utc);
+        let last_seen = DateTime::<Utc>::from_utc(chatter.last_seen, Utc);
+
+        let mut parts = vec![
+            message!(
+                self.messages,
+                "user-info/first-seen",
+                age = utils::human_age(first_seen, now)
+            ),
+            message!(
+                self.messages,
+                "user-info/last-seen",
+                age = utils::human_age(last_seen, now)
+            ),
+            message!(
+                self.messages,
+                "user-info/messages",
+                count = chatter.messages
+            ),
+        ];
+
+        if let Some(currency) = self.currency.load().await {
+            if let Some(balance) = currency.balance_of(ctx.channel(), &user).await? {
+                parts.push(message!(
+                    self.messages,
+                    "user-info/watch-time",
+                    watch_time = balance.watch_time().to_string()
+                ));
+                parts.push(format!("{} {}", balance.balance, currency.name));
+            }
+        }
+
+        if let Some(moderation_log) = self.moderation_log.load().await {
+            let (timeouts, bans) = moderation_log.counts(ctx.channel(), &user).await?;
+            parts.push(message!(
+                self.messages,
+                "user-info/timeouts",
+                count = timeouts
+            ));
+            parts.push(message!(self.messages, "user-info/bans", count = bans));
+        }
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "user-info/info",
+                user = user,
+                info = parts.join(", ")
+            )
+        );
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "user-info"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("user-info/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "user",
+            Handler {
+                enabled: settings.var("user-info/enabled", false).await?,
+                chatters: injector.var().await?,
+                moderation_log: injector.var().await?,
+                currency: injector.var().await?,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
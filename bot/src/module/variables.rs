@@ -0,0 +1,241 @@
+//! Persistent variables which can be set from chat.
+//!
+//! Variables are set with `!var set greetings 12`, after which custom commands
+//! can show their value with `{{var.greetings}}`. By default only users with
+//! the `var/edit` scope can set variables, but `!var permit greetings @vip`
+//! allows a role to set a single variable.
+
+use crate::auth::{Role, Scope};
+use crate::command;
+use crate::db;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::{anyhow, Result};
+
+/// Usage of the `!var` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!var <list|get <name>|set <name> <value>|delete <name>|permit <name> [role]>",
+    description: "Manage variables which can be used in custom commands.",
+};
+
+/// Handler for the `!var` command.
+pub struct Handler {
+    enabled: settings::Var<bool>,
+    variables: injector::Var<Option<db::Variables>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<Scope> {
+        Some(Scope::Var)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let variables = match self.variables.load().await {
+            Some(variables) => variables,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            Some("list") => {
+                let list = variables
+                    .list(ctx.channel())
+                    .await
+                    .into_iter()
+                    .map(|v| v.name)
+                    .collect::<Vec<_>>();
+
+                match utils::human_list(&list) {
+                    Some(list) => respond!(
+                        ctx,
+                        message!(self.messages, "variables/list", variables = list)
+                    ),
+                    None => respond!(ctx, message!(self.messages, "variables/list-empty")),
+                }
+            }
+            Some("get") => {
+                let name = ctx.next_str("<name>")?;
+
+                match variables.get(ctx.channel(), &name).await {
+                    Some(v) => respond!(ctx, "{}: {}", v.name, v.value),
+                    None => respond!(
+                        ctx,
+                        message!(self.messages, "variables/missing", name = name)
+                    ),
+                }
+            }
+            Some("set") => {
+                let name = ctx.next_str("<name> <value>")?.to_lowercase();
+
+                if !is_valid_name(&name) {
+                    respond_bail!("{}", message!(self.messages, "variables/bad-name"));
+                }
+
+                let value = ctx.rest().trim().to_string();
+
+                if value.is_empty() {
+                    respond_bail!("Expected: <name> <value>");
+                }
+
+                let role = variables
+                    .get(ctx.channel(), &name)
+                    .await
+                    .and_then(|v| v.role);
+
+                if !can_set(role, &ctx.user.roles()) {
+                    ctx.check_scope(Scope::VarEdit).await?;
+                }
+
+                variables
+                    .set(ctx.channel(), &name, &value, ctx.user.name())
+                    .await?;
+
+                respond!(ctx, "{}: {}", name, value);
+            }
+            Some("delete") => {
+                ctx.check_scope(Scope::VarEdit).await?;
+
+                let name = ctx.next_str("<name>")?;
+
+                if !variables.delete(ctx.channel(), &name).await? {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "variables/missing", name = name)
+                    );
+                }
+
+                respond!(
+                    ctx,
+                    message!(self.messages, "variables/deleted", name = name)
+                );
+            }
+            Some("permit") => {
+                ctx.check_scope(Scope::VarEdit).await?;
+
+                let name = ctx.next_str("<name> [role]")?;
+
+                let role = match ctx.next().as_deref() {
+                    None | Some("none") => None,
+                    Some(role) => match str::parse::<Role>(role)? {
+                        Role::Unknown => respond_bail!(
+                            "{}",
+                            message!(self.messages, "variables/unknown-role", role = role)
+                        ),
+                        role => Some(role),
+                    },
+                };
+
+                if !variables.set_role(ctx.channel(), &name, role).await? {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "variables/missing", name = name)
+                    );
+                }
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "variables/permitted",
+                        role = role.map(|r| r.to_string()),
+                        name = name,
+                    )
+                );
+            }
+            _ => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "variables"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("variables/enabled", false))
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "var",
+            Handler {
+                enabled: settings.var("variables/enabled", false).await?,
+                variables: injector.var().await?,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+/// Test if the given name can be used for a variable.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Test if a user with the given roles can set a variable which the given role
+/// is permitted to set, without having the `var/edit` scope.
+fn can_set(role: Option<Role>, roles: &[Role]) -> bool {
+    match role {
+        Some(role) => roles.contains(&role),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{can_set, is_valid_name};
+    use crate::auth::Role;
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(is_valid_name("greetings"));
+        assert!(is_valid_name("last_game2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("last-game"));
+        assert!(!is_valid_name("var.greetings"));
+    }
+
+    #[test]
+    fn test_can_set() {
+        let roles = [Role::Vip, Role::Everyone];
+
+        assert!(can_set(Some(Role::Vip), &roles));
+        assert!(can_set(Some(Role::Everyone), &roles));
+        assert!(!can_set(Some(Role::Moderator), &roles));
+        assert!(!can_set(None, &roles));
+    }
+}
@@ -0,0 +1,355 @@
+//! A queue of viewers waiting to play with the streamer.
+//!
+//! Viewers join the lobby with `!join`, optionally leaving a note like their
+//! in-game name, and the streamer picks the next player with `!queue next`.
+//! This is separate from the song request queue.
+
+use crate::auth::{Role, Scope};
+use crate::command;
+use crate::currency::Currency;
+use crate::db;
+use crate::messages::Messages;
+use crate::module;
+use crate::prelude::*;
+use anyhow::{anyhow, Result};
+
+/// The maximum number of users listed by `!queue list`.
+const LIST_LIMIT: usize = 10;
+
+/// Usage of the `!queue` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!queue <list|next|remove <user>|clear>",
+    description: "Manage the queue of viewers waiting to play.",
+};
+
+/// Handler for the `!join` command.
+pub struct Join {
+    enabled: settings::Var<bool>,
+    sub_priority: settings::Var<bool>,
+    cost: settings::Var<i64>,
+    queue: injector::Var<Option<db::ViewerQueue>>,
+    currency: injector::Var<Option<Currency>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Join {
+    fn scope(&self) -> Option<Scope> {
+        Some(Scope::ViewerQueue)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!join [note]",
+            description: "Join the queue of viewers waiting to play.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let queue = match self.queue.load().await {
+            Some(queue) => queue,
+            None => return Ok(()),
+        };
+
+        let user = match ctx.user.real() {
+            Some(user) => user,
+            None => respond_bail!(
+                "{}",
+                message!(self.messages, "viewer-queue/join-only-real-users")
+            ),
+        };
+
+        let note = ctx.rest().trim();
+        let note = if note.is_empty() { None } else { Some(note) };
+
+        let cost = self.cost.load().await;
+
+        let currency = if cost > 0 {
+            let currency = match self.currency.load().await {
+                Some(currency) => currency,
+                None => respond_bail!("{}", message!(self.messages, "viewer-queue/no-currency")),
+            };
+
+            let balance = currency
+                .balance_of(ctx.channel(), user.name())
+                .await?
+                .unwrap_or_default()
+                .balance;
+
+            if balance < cost {
+                respond_bail!(
+                    "{}",
+                    message!(
+                        self.messages,
+                        "viewer-queue/insufficient-balance",
+                        cost = cost,
+                        currency = currency.name,
+                        balance = balance,
+                    )
+                );
+            }
+
+            Some(currency)
+        } else {
+            None
+        };
+
+        let subscriber = user.roles().contains(&Role::Subscriber);
+
+        if !queue
+            .join(ctx.channel(), user.name(), note, subscriber)
+            .await?
+        {
+            respond_bail!("{}", message!(self.messages, "viewer-queue/already-joined"));
+        }
+
+        if let Some(currency) = currency {
+            currency
+                .balance_add(ctx.channel(), user.name(), -cost)
+                .await?;
+        }
+
+        let sub_priority = self.sub_priority.load().await;
+
+        let position = queue
+            .list(ctx.channel(), sub_priority)
+            .await?
+            .iter()
+            .position(|e| e.user == user.name());
+
+        respond!(
+            ctx,
+            message!(
+                self.messages,
+                "viewer-queue/joined",
+                position = position.map(|p| p + 1),
+            )
+        );
+
+        Ok(())
+    }
+}
+
+/// Handler for the `!leave` command.
+pub struct Leave {
+    enabled: settings::Var<bool>,
+    queue: injector::Var<Option<db::ViewerQueue>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Leave {
+    fn scope(&self) -> Option<Scope> {
+        Some(Scope::ViewerQueue)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(command::Usage {
+            usage: "!leave",
+            description: "Leave the queue of viewers waiting to play.",
+        })
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let queue = match self.queue.load().await {
+            Some(queue) => queue,
+            None => return Ok(()),
+        };
+
+        let user = match ctx.user.name() {
+            Some(user) => user.to_string(),
+            None => respond_bail!(
+                "{}",
+                message!(self.messages, "viewer-queue/leave-only-real-users")
+            ),
+        };
+
+        if !queue.leave(ctx.channel(), &user).await? {
+            respond_bail!("{}", message!(self.messages, "viewer-queue/not-joined"));
+        }
+
+        respond!(ctx, message!(self.messages, "viewer-queue/left"));
+        Ok(())
+    }
+}
+
+/// Handler for the `!queue` command.
+pub struct Queue {
+    enabled: settings::Var<bool>,
+    sub_priority: settings::Var<bool>,
+    queue: injector::Var<Option<db::ViewerQueue>>,
+    messages: Messages,
+}
+
+#[async_trait]
+impl command::Handler for Queue {
+    fn scope(&self) -> Option<Scope> {
+        Some(Scope::ViewerQueue)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let queue = match self.queue.load().await {
+            Some(queue) => queue,
+            None => return Ok(()),
+        };
+
+        let sub_priority = self.sub_priority.load().await;
+
+        match ctx.next().as_deref() {
+            None | Some("list") => {
+                let entries = queue.list(ctx.channel(), sub_priority).await?;
+                let total = entries.len();
+
+                let results = entries
+                    .into_iter()
+                    .take(LIST_LIMIT)
+                    .enumerate()
+                    .map(|(n, e)| format!("#{} {}", n + 1, e.user));
+
+                ctx.respond_lines(results, &message!(self.messages, "viewer-queue/empty"))
+                    .await;
+
+                if total > LIST_LIMIT {
+                    respond!(
+                        ctx,
+                        message!(
+                            self.messages,
+                            "viewer-queue/more",
+                            count = total - LIST_LIMIT
+                        )
+                    );
+                }
+            }
+            Some("next") => {
+                ctx.check_scope(Scope::ViewerQueueEdit).await?;
+
+                let entry = match queue.next(ctx.channel(), sub_priority).await? {
+                    Some(entry) => entry,
+                    None => respond_bail!("{}", message!(self.messages, "viewer-queue/empty")),
+                };
+
+                respond!(
+                    ctx,
+                    message!(
+                        self.messages,
+                        "viewer-queue/next",
+                        user = entry.user,
+                        note = entry.note,
+                    )
+                );
+            }
+            Some("remove") => {
+                ctx.check_scope(Scope::ViewerQueueEdit).await?;
+
+                let user = ctx.next_str("<user>")?;
+                let user = user.trim_start_matches('@').to_lowercase();
+
+                if !queue.leave(ctx.channel(), &user).await? {
+                    respond_bail!(
+                        "{}",
+                        message!(self.messages, "viewer-queue/missing", user = user)
+                    );
+                }
+
+                respond!(
+                    ctx,
+                    message!(self.messages, "viewer-queue/removed", user = user)
+                );
+            }
+            Some("clear") => {
+                ctx.check_scope(Scope::ViewerQueueEdit).await?;
+                queue.clear(ctx.channel()).await?;
+                respond!(ctx, message!(self.messages, "viewer-queue/cleared"));
+            }
+            Some(..) => {
+                respond!(ctx, message!(self.messages, "usage", usage = USAGE.usage));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "viewer-queue"
+    }
+
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("viewer-queue/enabled", false))
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("viewer-queue");
+
+        let enabled = settings.var("enabled", false).await?;
+        let sub_priority = settings.var("sub-priority", false).await?;
+        let queue = injector.var().await?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        handlers.insert(
+            "join",
+            Join {
+                enabled: enabled.clone(),
+                sub_priority: sub_priority.clone(),
+                cost: settings.var("cost", 0).await?,
+                queue: queue.clone(),
+                currency: injector.var().await?,
+                messages: messages.clone(),
+            },
+        );
+
+        handlers.insert(
+            "leave",
+            Leave {
+                enabled: enabled.clone(),
+                queue: queue.clone(),
+                messages: messages.clone(),
+            },
+        );
+
+        handlers.insert(
+            "queue",
+            Queue {
+                enabled,
+                sub_priority,
+                queue,
+                messages,
+            },
+        );
+
+        Ok(())
+    }
+}
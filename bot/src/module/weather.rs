@@ -1,15 +1,37 @@
-use crate::api::OpenWeatherMap;
+use crate::api::{
+    open_weather_map::{Current, OneCall},
+    OpenWeatherMap,
+};
 use crate::auth;
 use crate::command;
+use crate::db;
+use crate::messages::Messages;
 use crate::module;
 use crate::prelude::*;
-use anyhow::Result;
+use crate::storage::Cache;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDateTime;
 use uom::si::{
     f32::ThermodynamicTemperature,
     thermodynamic_temperature::{degree_celsius, degree_fahrenheit, kelvin},
     Unit as _,
 };
 
+/// Usage of the `!weather` command.
+const USAGE: command::Usage = command::Usage {
+    usage: "!weather <current [location]|set <location>|clear>",
+    description: "Show the current weather, or save your location.",
+};
+
+/// Usage of the `!forecast` command.
+const FORECAST_USAGE: command::Usage = command::Usage {
+    usage: "!forecast [location]",
+    description: "Show the weather forecast for the next few days.",
+};
+
+/// The number of days shown by `!forecast`.
+const FORECAST_DAYS: usize = 3;
+
 #[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize)]
 enum TemperatureUnit {
     #[serde(rename = "degrees-celsius")]
@@ -41,12 +63,291 @@ impl TemperatureUnit {
     }
 }
 
+/// The system of units used to show weather.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+enum Units {
+    #[serde(rename = "metric")]
+    Metric,
+    #[serde(rename = "imperial")]
+    Imperial,
+}
+
+impl Units {
+    /// The temperature unit to use unless one is configured.
+    fn temperature(self) -> TemperatureUnit {
+        match self {
+            Units::Metric => TemperatureUnit::DegreesCelsius,
+            Units::Imperial => TemperatureUnit::DegressFahrenheit,
+        }
+    }
+
+    /// Format the given precipitation, in millimeters.
+    fn precipitation(self, mm: f32) -> String {
+        match self {
+            Units::Metric => format!("{:.0}mm", mm),
+            Units::Imperial => format!("{:.2}in", mm / 25.4),
+        }
+    }
+
+    /// Format the given speed, in meters per second.
+    fn speed(self, meters_per_second: f32) -> String {
+        match self {
+            Units::Metric => format!("{:.1} m/s", meters_per_second),
+            Units::Imperial => format!("{:.1} mph", meters_per_second * 2.236_936),
+        }
+    }
+}
+
+/// Key used to cache weather lookups.
+#[derive(serde::Serialize)]
+#[serde(tag = "method")]
+enum Key<'a> {
+    Current { location: &'a str },
+    OneCall { location: &'a str },
+}
+
+/// Looks up weather, caching responses per location.
+#[derive(Clone)]
+struct Lookup {
+    api: injector::Var<Option<OpenWeatherMap>>,
+    cache: Cache,
+    units: settings::Var<Units>,
+    temperature_unit: settings::Var<Option<TemperatureUnit>>,
+    location: settings::Var<Option<String>>,
+    locations: injector::Var<Option<db::WeatherLocations>>,
+    messages: Messages,
+}
+
+impl Lookup {
+    /// Access the configured API.
+    async fn api(&self) -> Result<OpenWeatherMap> {
+        Ok(self
+            .api
+            .read()
+            .await
+            .as_ref()
+            .ok_or_else(|| respond_err!("{}", message!(self.messages, "weather/not-configured")))?
+            .clone())
+    }
+
+    /// Resolve the location to use from the given arguments, the location
+    /// saved by the user, or the default location.
+    async fn location(&self, ctx: &command::Context) -> Result<String> {
+        if !ctx.rest().trim().is_empty() {
+            return Ok(ctx.rest().trim().to_string());
+        }
+
+        if let (Some(user), Some(locations)) = (ctx.user.real(), self.locations.load().await) {
+            if let Some(saved) = locations.get(ctx.channel(), user.name()).await? {
+                return Ok(saved.location);
+            }
+        }
+
+        match self.location.load().await {
+            Some(location) => Ok(location),
+            None => respond_bail!("{}", message!(self.messages, "weather/no-location")),
+        }
+    }
+
+    /// Get the current weather at the given location.
+    async fn current(&self, location: &str) -> Result<Option<Current>> {
+        let api = self.api().await?;
+        let key = normalize_location(location);
+
+        let result = self
+            .cache
+            .wrap(
+                Key::Current { location: &key },
+                chrono::Duration::minutes(10),
+                api.current(location.to_string()),
+            )
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Get the forecast for the given location.
+    async fn forecast(&self, location: &str) -> Result<Option<(Current, OneCall)>> {
+        let current = match self.current(location).await? {
+            Some(current) => current,
+            None => return Ok(None),
+        };
+
+        let api = self.api().await?;
+        let key = normalize_location(location);
+
+        let result = self
+            .cache
+            .wrap(
+                Key::OneCall { location: &key },
+                chrono::Duration::minutes(30),
+                api.one_call(current.coord.clone()),
+            )
+            .await?;
+
+        Ok(result.map(|one_call| (current, one_call)))
+    }
+
+    /// The temperature unit to use.
+    async fn temperature_unit(&self) -> TemperatureUnit {
+        match self.temperature_unit.load().await {
+            Some(unit) => unit,
+            None => self.units.load().await.temperature(),
+        }
+    }
+}
+
 /// Handler for the !weather command.
 pub struct Weather {
     enabled: settings::Var<bool>,
-    temperature_unit: settings::Var<TemperatureUnit>,
-    location: settings::Var<Option<String>>,
-    api: injector::Var<Option<OpenWeatherMap>>,
+    lookup: Lookup,
+}
+
+impl Weather {
+    /// Show the current weather.
+    async fn current(&self, ctx: &mut command::Context) -> Result<()> {
+        let loc = self.lookup.location(ctx).await?;
+
+        let current = match self.lookup.current(&loc).await? {
+            Some(current) => current,
+            None => {
+                respond!(
+                    ctx,
+                    message!(self.lookup.messages, "weather/not-found", location = loc)
+                );
+                return Ok(());
+            }
+        };
+
+        let temperature_unit = self.lookup.temperature_unit().await;
+        let units = self.lookup.units.load().await;
+
+        let mut parts = Vec::with_capacity(4);
+
+        let t = ThermodynamicTemperature::new::<kelvin>(current.main.temp);
+
+        parts.push(temperature_unit.with(t));
+
+        for w in current.weather {
+            parts.push(w.to_string());
+        }
+
+        if let Some(rain) = current.rain {
+            let rate = match (rain._1h, rain._3h) {
+                (Some(m), _) => Some(format!("{}/h", units.precipitation(m))),
+                (_, Some(m)) => Some(format!("{}/3h", units.precipitation(m))),
+                _ => None,
+            };
+
+            if let Some(rate) = rate {
+                parts.push(message!(
+                    self.lookup.messages,
+                    "weather/raining",
+                    rate = rate
+                ));
+            }
+        }
+
+        if let Some(snow) = current.snow {
+            let rate = match (snow._1h, snow._3h) {
+                (Some(m), _) => Some(format!("{}/h", units.precipitation(m))),
+                (_, Some(m)) => Some(format!("{}/3h", units.precipitation(m))),
+                _ => None,
+            };
+
+            if let Some(rate) = rate {
+                parts.push(message!(
+                    self.lookup.messages,
+                    "weather/snowing",
+                    rate = rate
+                ));
+            }
+        }
+
+        if let Some(speed) = current.wind.speed {
+            let speed = units.speed(speed);
+            parts.push(message!(
+                self.lookup.messages,
+                "weather/wind",
+                speed = speed
+            ));
+        }
+
+        respond!(ctx, "{} -> {}.", current.name, parts.join(", "));
+        Ok(())
+    }
+
+    /// Save the location of the current user.
+    async fn set(&self, ctx: &mut command::Context) -> Result<()> {
+        let user = match ctx.user.real() {
+            Some(user) => user.name().to_string(),
+            None => respond_bail!(
+                "{}",
+                message!(self.lookup.messages, "weather/only-real-users")
+            ),
+        };
+
+        let locations = match self.lookup.locations.load().await {
+            Some(locations) => locations,
+            None => respond_bail!(
+                "{}",
+                message!(self.lookup.messages, "weather/not-available")
+            ),
+        };
+
+        let loc = ctx.rest().trim().to_string();
+
+        if loc.is_empty() {
+            respond_bail!("{}", message!(self.lookup.messages, "weather/no-location"));
+        }
+
+        let current = match self.lookup.current(&loc).await? {
+            Some(current) => current,
+            None => respond_bail!(
+                "{}",
+                message!(self.lookup.messages, "weather/not-found", location = loc)
+            ),
+        };
+
+        locations.set(ctx.channel(), &user, &loc).await?;
+        respond!(
+            ctx,
+            message!(
+                self.lookup.messages,
+                "weather/saved",
+                name = current.name,
+                country = current.sys.country,
+            )
+        );
+        Ok(())
+    }
+
+    /// Clear the location of the current user.
+    async fn clear(&self, ctx: &mut command::Context) -> Result<()> {
+        let user = match ctx.user.real() {
+            Some(user) => user.name().to_string(),
+            None => respond_bail!(
+                "{}",
+                message!(self.lookup.messages, "weather/only-real-users")
+            ),
+        };
+
+        let locations = match self.lookup.locations.load().await {
+            Some(locations) => locations,
+            None => respond_bail!(
+                "{}",
+                message!(self.lookup.messages, "weather/not-available")
+            ),
+        };
+
+        if locations.delete(ctx.channel(), &user).await? {
+            respond!(ctx, message!(self.lookup.messages, "weather/cleared"));
+        } else {
+            respond!(ctx, message!(self.lookup.messages, "weather/no-saved"));
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -55,6 +356,10 @@ impl command::Handler for Weather {
         Some(auth::Scope::Weather)
     }
 
+    fn usage(&self) -> Option<command::Usage> {
+        Some(USAGE)
+    }
+
     async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
         if !self.enabled.load().await {
             return Ok(());
@@ -62,76 +367,133 @@ impl command::Handler for Weather {
 
         match ctx.next().as_deref() {
             Some("current") => {
-                let api = self
-                    .api
-                    .read()
-                    .await
-                    .as_ref()
-                    .ok_or_else(|| respond_err!("API not configured"))?
-                    .clone();
-
-                let loc = match ctx.rest() {
-                    "" => self.location.load().await,
-                    rest => Some(rest.to_string()),
-                };
-
-                let loc = match loc {
-                    Some(loc) => loc,
-                    None => {
-                        respond!(ctx, "Must specify <location>");
-                        return Ok(());
-                    }
-                };
-
-                let temperature_unit = self.temperature_unit.load().await;
-
-                let current = api.current(loc.clone()).await?;
-
-                let current = match current {
-                    Some(current) => current,
-                    None => {
-                        respond!(ctx, "Could not find location `{}`", loc);
-                        return Ok(());
-                    }
-                };
-
-                let mut parts = Vec::with_capacity(4);
-
-                let t = ThermodynamicTemperature::new::<kelvin>(current.main.temp);
-
-                parts.push(temperature_unit.with(t));
-
-                for w in current.weather {
-                    parts.push(w.to_string());
-                }
-
-                if let Some(rain) = current.rain {
-                    parts.extend(match (rain._1h, rain._3h) {
-                        (Some(m), _) => Some(format!("raining {:.0}mm/h", m)),
-                        (_, Some(m)) => Some(format!("raining {:.0}mm/3h", m)),
-                        _ => None,
-                    });
-                }
-
-                if let Some(snow) = current.snow {
-                    parts.extend(match (snow._1h, snow._3h) {
-                        (Some(m), _) => Some(format!("snowing {:.0}mm/h", m)),
-                        (_, Some(m)) => Some(format!("snowing {:.0}mm/3h", m)),
-                        _ => None,
-                    });
-                }
-
-                respond!(ctx, "{} -> {}.", current.name, parts.join(", "));
+                self.current(ctx).await?;
+            }
+            Some("set") => {
+                self.set(ctx).await?;
+            }
+            Some("clear") => {
+                self.clear(ctx).await?;
             }
             _ => {
-                respond!(ctx, "Expected: current.");
+                respond!(
+                    ctx,
+                    message!(self.lookup.messages, "usage", usage = USAGE.usage)
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Handler for the !forecast command.
+pub struct Forecast {
+    enabled: settings::Var<bool>,
+    lookup: Lookup,
+}
+
+#[async_trait]
+impl command::Handler for Forecast {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Weather)
+    }
+
+    fn usage(&self) -> Option<command::Usage> {
+        Some(FORECAST_USAGE)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let loc = self.lookup.location(ctx).await?;
+
+        let (current, one_call) = match self.lookup.forecast(&loc).await? {
+            Some(forecast) => forecast,
+            None => {
+                respond!(
+                    ctx,
+                    message!(self.lookup.messages, "weather/not-found", location = loc)
+                );
+                return Ok(());
+            }
+        };
+
+        let temperature_unit = self.lookup.temperature_unit().await;
+        let units = self.lookup.units.load().await;
+
+        let mut days = Vec::new();
+
+        for daily in one_call.daily.iter().take(FORECAST_DAYS) {
+            let max = ThermodynamicTemperature::new::<kelvin>(daily.temp.max);
+            let min = ThermodynamicTemperature::new::<kelvin>(daily.temp.min);
+
+            let mut parts = vec![format!(
+                "{} / {}",
+                temperature_unit.with(max),
+                temperature_unit.with(min)
+            )];
+
+            for w in &daily.weather {
+                parts.push(w.to_string());
+            }
+
+            if let Some(rain) = daily.rain {
+                let amount = units.precipitation(rain);
+                parts.push(message!(
+                    self.lookup.messages,
+                    "weather/forecast-rain",
+                    amount = amount
+                ));
+            }
+
+            if let Some(snow) = daily.snow {
+                let amount = units.precipitation(snow);
+                parts.push(message!(
+                    self.lookup.messages,
+                    "weather/forecast-snow",
+                    amount = amount
+                ));
             }
+
+            days.push(format!(
+                "{}: {}",
+                day_name(daily.dt, one_call.timezone_offset),
+                parts.join(", ")
+            ));
+        }
+
+        if days.is_empty() {
+            respond!(
+                ctx,
+                message!(self.lookup.messages, "weather/no-forecast", location = loc)
+            );
+            return Ok(());
         }
 
+        respond!(ctx, "{} -> {}", current.name, days.join(" | "));
         Ok(())
     }
 }
 
+/// Normalize a location so that it can be used as a cache key.
+fn normalize_location(location: &str) -> String {
+    location
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The short name of the local day of the given timestamp.
+fn day_name(dt: i64, timezone_offset: i64) -> String {
+    NaiveDateTime::from_timestamp(dt + timezone_offset, 0)
+        .format("%a")
+        .to_string()
+}
+
 pub struct Module;
 
 #[async_trait]
@@ -140,6 +502,10 @@ impl super::Module for Module {
         "weather"
     }
 
+    fn enabled_setting(&self) -> Option<(&'static str, bool)> {
+        Some(("weather/enabled", false))
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
@@ -150,18 +516,64 @@ impl super::Module for Module {
             ..
         }: module::HookContext<'_>,
     ) -> Result<()> {
+        let cache: Cache = injector
+            .get()
+            .await
+            .ok_or_else(|| anyhow!("missing cache"))?;
+
+        let messages = injector
+            .get::<Messages>()
+            .await
+            .ok_or_else(|| anyhow!("messages are not configured"))?;
+
+        let lookup = Lookup {
+            api: injector.var().await?,
+            cache: cache.namespaced(&"weather")?,
+            units: settings.var("weather/units", Units::Metric).await?,
+            temperature_unit: settings.optional("weather/temperature-unit").await?,
+            location: settings.optional("weather/location").await?,
+            locations: injector.var().await?,
+            messages,
+        };
+
+        let enabled = settings.var("weather/enabled", false).await?;
+
         handlers.insert(
             "weather",
             Weather {
-                enabled: settings.var("weather/enabled", false).await?,
-                temperature_unit: settings
-                    .var("weather/temperature-unit", TemperatureUnit::DegreesCelsius)
-                    .await?,
-                location: settings.optional("weather/location").await?,
-                api: injector.var().await?,
+                enabled: enabled.clone(),
+                lookup: lookup.clone(),
             },
         );
 
+        handlers.insert("forecast", Forecast { enabled, lookup });
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{day_name, normalize_location, Units};
+
+    #[test]
+    fn test_normalize_location() {
+        assert_eq!("new york", normalize_location("  New   York "));
+        assert_eq!("berlin", normalize_location("BERLIN"));
+    }
+
+    #[test]
+    fn test_day_name() {
+        // 2021-01-10 23:00:00 UTC, a Sunday.
+        assert_eq!("Sun", day_name(1_610_319_600, 0));
+        assert_eq!("Mon", day_name(1_610_319_600, 3600));
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!("3mm", Units::Metric.precipitation(3.0));
+        assert_eq!("1.00in", Units::Imperial.precipitation(25.4));
+        assert_eq!("10.0 m/s", Units::Metric.speed(10.0));
+        assert_eq!("22.4 mph", Units::Imperial.speed(10.0));
+    }
+}
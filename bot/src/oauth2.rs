@@ -2,12 +2,15 @@ use crate::api::{
     setbac::{Connection, ConnectionMeta, Token},
     Setbac,
 };
-use crate::injector::{Injector, Key};
+use crate::db;
+use crate::injector::{self, Injector, Key};
 use crate::prelude::*;
 use crate::settings::Settings;
+use crate::sys;
 use crate::utils::Duration;
 use crate::web;
 use anyhow::Error;
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 use std::collections::VecDeque;
 use std::fmt;
@@ -24,6 +27,61 @@ pub enum TokenId {
     YouTube,
     NightBot,
     Spotify,
+    Streamlabs,
+    StreamElements,
+}
+
+/// Where connections are managed, and where users are sent to re-authenticate.
+const CONNECTIONS_URL: &str = "https://setbac.tv/connections";
+/// Prompt for re-authentication if a token is this close to expiring, since
+/// it should've been refreshed long before.
+const REAUTH_WARNING: time::Duration = time::Duration::from_secs(10 * 60);
+/// Number of consecutive refresh failures before a connection is degraded.
+const DEGRADED_FAILURES: u32 = 2;
+
+/// The state of a connection, as reported to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConnectionState {
+    /// Connection is working as expected.
+    Healthy,
+    /// Connection is about to expire and can't be refreshed.
+    Expiring,
+    /// Connection repeatedly failed to refresh.
+    Degraded,
+    /// No connection is available.
+    Disconnected,
+}
+
+impl ConnectionState {
+    /// Determine the state of a connection.
+    fn new(token: Option<&Token>, health: &Health) -> Result<Self, Error> {
+        let token = match token {
+            Some(token) => token,
+            None => return Ok(ConnectionState::Disconnected),
+        };
+
+        if health.refresh_failures >= DEGRADED_FAILURES {
+            return Ok(ConnectionState::Degraded);
+        }
+
+        if token.expires_within(REAUTH_WARNING)? {
+            return Ok(ConnectionState::Expiring);
+        }
+
+        Ok(ConnectionState::Healthy)
+    }
+}
+
+/// Health of a connection.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Health {
+    /// Number of consecutive failures to refresh the connection.
+    pub refresh_failures: u32,
+    /// The last error encountered when refreshing the connection.
+    pub last_error: Option<String>,
+    /// When the connection last failed to refresh.
+    pub last_failure_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Error)]
@@ -40,6 +98,8 @@ pub struct InnerSyncToken {
     connection: Option<Connection>,
     /// Queue to notify when a connection is available.
     ready_queue: VecDeque<oneshot::Sender<()>>,
+    /// Health of the connection.
+    health: Health,
 }
 
 #[derive(Clone, Debug)]
@@ -64,6 +124,38 @@ impl SyncToken {
         }
     }
 
+    /// Get the name of the flow associated with the token.
+    pub fn what(&self) -> &'static str {
+        self.what
+    }
+
+    /// Get a copy of the current token, if available.
+    pub async fn token(&self) -> Option<Token> {
+        self.inner
+            .read()
+            .await
+            .connection
+            .as_ref()
+            .map(|c| c.token.clone())
+    }
+
+    /// Log a warning for each of the given scopes which the current token
+    /// is missing.
+    pub async fn warn_missing_scopes(&self, scopes: &[&str]) {
+        let inner = self.inner.read().await;
+
+        let token = match inner.connection.as_ref() {
+            Some(connection) => &connection.token,
+            None => return,
+        };
+
+        for scope in scopes {
+            if !token.scopes.iter().any(|s| s == scope) {
+                log::warn!("{}: Token is missing the `{}` scope", self.what, scope);
+            }
+        }
+    }
+
     /// Set the connection and notify all waiters.
     pub async fn update(&self, update: Connection) {
         let mut lock = self.inner.write().await;
@@ -71,6 +163,7 @@ impl SyncToken {
         let InnerSyncToken {
             ref mut connection,
             ref mut ready_queue,
+            ..
         } = *lock;
 
         *connection = Some(update);
@@ -83,6 +176,32 @@ impl SyncToken {
         }
     }
 
+    /// Get the health of the connection.
+    pub async fn health(&self) -> Health {
+        self.inner.read().await.health.clone()
+    }
+
+    /// Get the current state of the connection.
+    pub async fn state(&self) -> Result<ConnectionState, Error> {
+        let inner = self.inner.read().await;
+        let token = inner.connection.as_ref().map(|c| &c.token);
+        ConnectionState::new(token, &inner.health)
+    }
+
+    /// Record that the connection was successfully built.
+    async fn record_success(&self) {
+        let mut inner = self.inner.write().await;
+        inner.health.refresh_failures = 0;
+    }
+
+    /// Record that the connection failed to build.
+    async fn record_failure(&self, e: &Error) {
+        let mut inner = self.inner.write().await;
+        inner.health.refresh_failures += 1;
+        inner.health.last_error = Some(format!("{:#}", e));
+        inner.health.last_failure_at = Some(Utc::now());
+    }
+
     /// Clear the current connection.
     pub async fn clear(&self) {
         self.inner.write().await.connection = None;
@@ -109,6 +228,7 @@ impl SyncToken {
             let InnerSyncToken {
                 ref connection,
                 ref mut ready_queue,
+                ..
             } = *lock;
 
             if connection.is_some() {
@@ -154,6 +274,10 @@ struct ConnectionFactory {
     key: Key<SyncToken>,
     server: web::Server,
     current_hash: Option<String>,
+    system: sys::System,
+    notifications: injector::Var<Option<db::Notifications>>,
+    /// The last state of the connection, used to notify on changes.
+    state: Option<ConnectionState>,
 }
 
 enum Validation {
@@ -253,14 +377,80 @@ impl ConnectionFactory {
     /// Construct a new connection and log on failures.
     pub async fn log_build(&mut self) -> Validation {
         match self.build().await {
-            Ok(connection) => connection,
+            Ok(connection) => {
+                self.sync_token.record_success().await;
+                connection
+            }
             Err(e) => {
                 log_error!(e, "{}: Failed to build connection", self.what);
+                self.sync_token.record_failure(&e).await;
                 Validation::Ok
             }
         }
     }
 
+    /// Check the state of the connection, and notify the user if it changed
+    /// in a way that requires their attention.
+    pub async fn check_state(&mut self) {
+        let state = match self.sync_token.state().await {
+            Ok(state) => state,
+            Err(e) => {
+                log_error!(e, "{}: Failed to check connection state", self.what);
+                return;
+            }
+        };
+
+        let previous = std::mem::replace(&mut self.state, Some(state));
+
+        if previous == Some(state) {
+            return;
+        }
+
+        let n = match (previous, state) {
+            (_, ConnectionState::Expiring) => sys::Notification::new(format!(
+                "{} connection is about to expire.\nClick to re-authenticate...",
+                self.what
+            ))
+            .icon(sys::NotificationIcon::Warning),
+            (_, ConnectionState::Degraded) => {
+                let health = self.sync_token.health().await;
+
+                sys::Notification::new(format!(
+                    "{} connection is degraded, failed to refresh {} times: {}\n\
+                     Click to re-authenticate...",
+                    self.what,
+                    health.refresh_failures,
+                    health.last_error.as_deref().unwrap_or("unknown error"),
+                ))
+                .icon(sys::NotificationIcon::Error)
+            }
+            // NB: only notify if a connection we had was lost.
+            (Some(_), ConnectionState::Disconnected) => sys::Notification::new(format!(
+                "{} connection was lost.\nClick to re-authenticate...",
+                self.what
+            ))
+            .icon(sys::NotificationIcon::Error),
+            (Some(ConnectionState::Expiring), ConnectionState::Healthy)
+            | (Some(ConnectionState::Degraded), ConnectionState::Healthy) => {
+                sys::Notification::new(format!("{} connection restored", self.what))
+            }
+            _ => return,
+        };
+
+        let n = n.title("Connection").on_click(|| {
+            webbrowser::open(CONNECTIONS_URL)?;
+            Ok(())
+        });
+
+        if let Some(notifications) = self.notifications.load().await {
+            if let Err(e) = notifications.push(&n).await {
+                log_error!(e, "Failed to store notification");
+            }
+        }
+
+        self.system.notification(n);
+    }
+
     /// Construct a new connection.
     pub async fn build(&mut self) -> Result<Validation, Error> {
         let setbac = match self.setbac.as_ref() {
@@ -403,6 +593,7 @@ pub async fn build(
     injector: Injector,
     key: Key<SyncToken>,
     server: web::Server,
+    system: sys::System,
 ) -> Result<(SyncToken, impl Future<Output = Result<(), Error>>), Error> {
     // connection expires within 30 minutes.
     let expires = time::Duration::from_secs(30 * 60);
@@ -420,7 +611,10 @@ pub async fn build(
         .or_with(Duration::seconds(30))
         .await?;
 
+    let notifications = injector.var().await?;
+
     let sync_token = SyncToken::new(what, force_refresh);
+    server.register_token(flow_id, sync_token.clone()).await;
 
     let mut builder = ConnectionFactory {
         setbac,
@@ -435,12 +629,16 @@ pub async fn build(
         key,
         server,
         current_hash: None,
+        system,
+        notifications,
+        state: None,
     };
 
     // check for expirations.
     let mut check_interval = tokio::time::interval(check_interval.as_std()).fuse();
 
     builder.update_from_settings(connection).await?;
+    builder.check_state().await;
 
     let future = async move {
         log::trace!("{}: Running loop", what);
@@ -450,19 +648,23 @@ pub async fn build(
                 setbac = setbac_stream.select_next_some() => {
                     builder.setbac = setbac;
                     builder.update().await?;
+                    builder.check_state().await;
                 }
                 connection = connection_stream.select_next_some() => {
                     log::trace!("{}: New from settings", what);
                     builder.update_from_settings(connection).await?;
+                    builder.check_state().await;
                 }
                 _ = force_refresh_rx.select_next_some() => {
                     log::trace!("{}: Forced refresh", what);
                     builder.force_refresh = true;
                     builder.update().await?;
+                    builder.check_state().await;
                 }
                 _ = check_interval.select_next_some() => {
                     log::trace!("{}: Check for expiration", what);
                     builder.update().await?;
+                    builder.check_state().await;
                 }
                 update = check_interval_stream.select_next_some() => {
                     check_interval = tokio::time::interval(update.as_std()).fuse();
@@ -473,3 +675,42 @@ pub async fn build(
 
     Ok((sync_token, future.boxed()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionState, Health};
+    use crate::api::setbac::Token;
+    use chrono::Utc;
+
+    fn token(expires_in: u64) -> Token {
+        Token {
+            client_id: String::from("client"),
+            flow_id: String::from("spotify"),
+            access_token: String::from("secret"),
+            refreshed_at: Utc::now(),
+            expires_in: Some(expires_in),
+            scopes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_connection_state() {
+        let health = Health::default();
+        let state =
+            |token: Option<&Token>, health: &Health| ConnectionState::new(token, health).unwrap();
+
+        assert_eq!(ConnectionState::Disconnected, state(None, &health));
+        assert_eq!(ConnectionState::Healthy, state(Some(&token(3600)), &health));
+        assert_eq!(ConnectionState::Expiring, state(Some(&token(60)), &health));
+
+        let health = Health {
+            refresh_failures: 2,
+            ..Health::default()
+        };
+
+        assert_eq!(
+            ConnectionState::Degraded,
+            state(Some(&token(3600)), &health)
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Overlays which can be added to OBS as browser sources.
+//!
+//! The text of each overlay is rendered here from templates, and sent over the
+//! global bus to the overlay pages.
+
+use crate::bus;
+use crate::injector::Injector;
+use crate::player;
+use crate::prelude::*;
+use crate::settings::Settings;
+use crate::template::Template;
+use crate::utils;
+use anyhow::Result;
+
+static DEFAULT_CURRENT_SONG_TEMPLATE: &str =
+    "{{name}}{{#if artists}} by {{artists}}{{/if}}{{#if user}} (requested by {{user}}){{/if}}";
+static DEFAULT_SUB_TEMPLATE: &str = "{{user}} just subscribed!";
+static DEFAULT_RESUB_TEMPLATE: &str = "{{user}} resubscribed for {{months}} months!";
+static DEFAULT_SUBGIFT_TEMPLATE: &str = "{{user}} gifted a sub to {{recipient}}!";
+static DEFAULT_RAID_TEMPLATE: &str = "{{user}} is raiding with {{viewers}} viewers!";
+static DEFAULT_FOLLOW_TEMPLATE: &str = "{{user}} just followed!";
+static DEFAULT_CHEER_TEMPLATE: &str = "{{user}} cheered {{bits}} bits!";
+static DEFAULT_DONATION_TEMPLATE: &str = "{{user}} donated {{amount}}!";
+
+/// Data available when rendering alert templates.
+#[derive(serde::Serialize)]
+struct AlertData<'a> {
+    user: &'a str,
+    months: Option<u32>,
+    streak: Option<u32>,
+    recipient: Option<&'a str>,
+    gifts: Option<u32>,
+    viewers: Option<u32>,
+    bits: Option<u32>,
+    amount: Option<&'a str>,
+    message: Option<&'a str>,
+}
+
+/// Settings of the alerts overlay.
+struct Alerts {
+    duration: settings::Var<utils::Duration>,
+    sub: settings::Var<Template>,
+    resub: settings::Var<Template>,
+    subgift: settings::Var<Template>,
+    raid: settings::Var<Template>,
+    follow: settings::Var<Template>,
+    cheer: settings::Var<Template>,
+    donation: settings::Var<Template>,
+}
+
+impl Alerts {
+    /// Get the template for the given kind of alert.
+    async fn template(&self, kind: bus::AlertKind) -> Template {
+        match kind {
+            bus::AlertKind::Sub => self.sub.load().await,
+            bus::AlertKind::Resub => self.resub.load().await,
+            bus::AlertKind::SubGift => self.subgift.load().await,
+            bus::AlertKind::Raid => self.raid.load().await,
+            bus::AlertKind::Follow => self.follow.load().await,
+            bus::AlertKind::Cheer => self.cheer.load().await,
+            bus::AlertKind::Donation => self.donation.load().await,
+        }
+    }
+}
+
+/// An update to the current song, which is delayed by the stream latency.
+enum SongUpdate {
+    Song(Option<player::Song>),
+    State(Option<player::State>),
+}
+
+impl SongUpdate {
+    /// Apply the update.
+    fn apply(self, song: &mut Option<player::Song>, state: &mut Option<player::State>) {
+        match self {
+            SongUpdate::Song(update) => *song = update,
+            SongUpdate::State(update) => *state = update,
+        }
+    }
+}
+
+/// What to do next in the overlay loop.
+enum Next {
+    /// Schedule an update to the current song.
+    Schedule(SongUpdate),
+    /// An update to the current song is due.
+    Song(SongUpdate),
+    /// Show the given alert.
+    Alert(String),
+}
+
+/// Run the overlays, rendering the current song and alerts as they change.
+pub async fn run(
+    settings: &Settings,
+    injector: &Injector,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+) -> Result<impl Future<Output = Result<()>>> {
+    let stream_latency = settings
+        .var("player/stream-latency", utils::Duration::seconds(0))
+        .await?;
+
+    let settings = settings.scoped("overlay");
+
+    let enabled = settings.var("enabled", false).await?;
+    let theme = settings.var("theme", String::from("default")).await?;
+    let current_song_template = settings
+        .var(
+            "current-song/template",
+            Template::compile(DEFAULT_CURRENT_SONG_TEMPLATE)?,
+        )
+        .await?;
+
+    let alerts = {
+        let settings = settings.scoped("alerts");
+
+        Alerts {
+            duration: settings
+                .var("duration", utils::Duration::seconds(10))
+                .await?,
+            sub: settings
+                .var("sub-template", Template::compile(DEFAULT_SUB_TEMPLATE)?)
+                .await?,
+            resub: settings
+                .var("resub-template", Template::compile(DEFAULT_RESUB_TEMPLATE)?)
+                .await?,
+            subgift: settings
+                .var(
+                    "subgift-template",
+                    Template::compile(DEFAULT_SUBGIFT_TEMPLATE)?,
+                )
+                .await?,
+            raid: settings
+                .var("raid-template", Template::compile(DEFAULT_RAID_TEMPLATE)?)
+                .await?,
+            follow: settings
+                .var(
+                    "follow-template",
+                    Template::compile(DEFAULT_FOLLOW_TEMPLATE)?,
+                )
+                .await?,
+            cheer: settings
+                .var("cheer-template", Template::compile(DEFAULT_CHEER_TEMPLATE)?)
+                .await?,
+            donation: settings
+                .var(
+                    "donation-template",
+                    Template::compile(DEFAULT_DONATION_TEMPLATE)?,
+                )
+                .await?,
+        }
+    };
+
+    let (mut song_stream, mut song) = injector.stream::<player::Song>().await;
+    let (mut state_stream, mut state) = injector.stream::<player::State>().await;
+    let mut delayed = player::Delayed::new(stream_latency);
+    let mut rx = global_bus.subscribe();
+
+    Ok(async move {
+        loop {
+            let next = futures::select! {
+                update = song_stream.select_next_some() => {
+                    Next::Schedule(SongUpdate::Song(update))
+                }
+                update = state_stream.select_next_some() => {
+                    Next::Schedule(SongUpdate::State(update))
+                }
+                update = delayed.select_next_some() => Next::Song(update),
+                event = rx.select_next_some() => {
+                    match event? {
+                        bus::Global::Alert {
+                            kind,
+                            user,
+                            months,
+                            streak,
+                            recipient,
+                            gifts,
+                            viewers,
+                            bits,
+                            amount,
+                            message,
+                        } => {
+                            let data = AlertData {
+                                user: &user,
+                                months,
+                                streak,
+                                recipient: recipient.as_deref(),
+                                gifts,
+                                viewers,
+                                bits,
+                                amount: amount.as_deref(),
+                                message: message.as_deref(),
+                            };
+
+                            Next::Alert(alerts.template(kind).await.render_to_string(&data)?)
+                        }
+                        _ => continue,
+                    }
+                }
+            };
+
+            let alert = match next {
+                Next::Schedule(update) => match delayed.schedule(update).await {
+                    Some(update) => {
+                        update.apply(&mut song, &mut state);
+                        None
+                    }
+                    None => continue,
+                },
+                Next::Song(update) => {
+                    update.apply(&mut song, &mut state);
+                    None
+                }
+                Next::Alert(text) => Some(text),
+            };
+
+            if !enabled.load().await {
+                continue;
+            }
+
+            let theme = theme.load().await;
+
+            if let Some(text) = alert {
+                global_bus
+                    .send(bus::Global::OverlayAlert {
+                        text,
+                        theme,
+                        duration: alerts.duration.load().await.num_seconds(),
+                    })
+                    .await;
+
+                continue;
+            }
+
+            let text = match song.as_ref() {
+                Some(song) => {
+                    let data = song.data(state.unwrap_or_default())?;
+                    Some(current_song_template.load().await.render_to_string(&data)?)
+                }
+                None => None,
+            };
+
+            global_bus
+                .send(bus::Global::OverlayCurrentSong { text, theme })
+                .await;
+        }
+    })
+}
@@ -0,0 +1,70 @@
+use crate::prelude::*;
+use crate::utils;
+use futures::stream::FusedStream;
+use tokio::time::DelayQueue;
+
+/// Schedules player events so that they can be delayed by the latency of the
+/// stream.
+///
+/// This way viewers see an announcement roughly at the same time as they hear
+/// the song it's about. Items are yielded by the stream once they are due.
+pub struct Delayed<T> {
+    latency: settings::Var<utils::Duration>,
+    queue: DelayQueue<T>,
+}
+
+impl<T> Delayed<T> {
+    /// Construct a new scheduler, delaying items by the given latency.
+    pub fn new(latency: settings::Var<utils::Duration>) -> Self {
+        Self {
+            latency,
+            queue: DelayQueue::new(),
+        }
+    }
+
+    /// Schedule the given item.
+    ///
+    /// If no latency is configured, the item is handed back immediately.
+    pub async fn schedule(&mut self, item: T) -> Option<T> {
+        let latency = self.latency.load().await;
+
+        if latency.is_empty() {
+            return Some(item);
+        }
+
+        self.queue.insert(item, latency.as_std());
+        None
+    }
+}
+
+impl<T> Stream for Delayed<T>
+where
+    T: Unpin,
+{
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+
+        loop {
+            match this.queue.poll_expired(cx) {
+                Poll::Ready(Some(Ok(expired))) => return Poll::Ready(Some(expired.into_inner())),
+                Poll::Ready(Some(Err(e))) => {
+                    log::error!("failed to delay player event: {}", e);
+                }
+                // NB: an empty queue doesn't end the stream, since more items
+                // might be scheduled later.
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<T> FusedStream for Delayed<T>
+where
+    T: Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        false
+    }
+}
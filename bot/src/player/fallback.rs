@@ -0,0 +1,120 @@
+//! Ordering of fallback items.
+
+use rand::seq::SliceRandom as _;
+use rand::Rng;
+use std::collections::VecDeque;
+
+/// Decides the order in which fallback items are played.
+///
+/// Items are played in cycles, where every item is played once per cycle.
+/// When shuffling, the most recently played items are moved towards the end of
+/// the next cycle so that they don't repeat across the cycle boundary.
+#[derive(Debug, Default)]
+pub(super) struct FallbackCycle {
+    /// Number of items being cycled.
+    len: usize,
+    /// Indexes of items left to play in the current cycle.
+    order: VecDeque<usize>,
+    /// Indexes of the most recently played items, most recent last.
+    recent: VecDeque<usize>,
+}
+
+impl FallbackCycle {
+    /// Reset the cycle to cover the given number of items.
+    pub(super) fn reset(&mut self, len: usize) {
+        self.len = len;
+        self.order.clear();
+        self.recent.clear();
+    }
+
+    /// Get the index of the next item to play.
+    ///
+    /// `no_repeat` is the number of most recently played items to avoid when
+    /// starting a new shuffled cycle.
+    pub(super) fn next(
+        &mut self,
+        shuffle: bool,
+        no_repeat: usize,
+        rng: &mut impl Rng,
+    ) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // NB: we can at most avoid all but one item.
+        let no_repeat = usize::min(no_repeat, self.len - 1);
+
+        if self.order.is_empty() {
+            let mut order = (0..self.len).collect::<Vec<_>>();
+
+            if shuffle {
+                order.shuffle(rng);
+
+                let recent = self.recent.iter().rev().take(no_repeat).collect::<Vec<_>>();
+
+                let (recent, rest): (Vec<_>, Vec<_>) =
+                    order.into_iter().partition(|i| recent.contains(&i));
+
+                order = rest;
+                order.extend(recent);
+            }
+
+            self.order.extend(order);
+        }
+
+        let index = self.order.pop_front()?;
+
+        self.recent.push_back(index);
+
+        while self.recent.len() > self.len {
+            self.recent.pop_front();
+        }
+
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FallbackCycle;
+
+    #[test]
+    fn test_in_order() {
+        let mut rng = rand::thread_rng();
+        let mut cycle = FallbackCycle::default();
+        cycle.reset(3);
+
+        let played = (0..6)
+            .map(|_| cycle.next(false, 0, &mut rng))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![Some(0), Some(1), Some(2), Some(0), Some(1), Some(2)],
+            played
+        );
+    }
+
+    #[test]
+    fn test_no_repeat() {
+        let mut rng = rand::thread_rng();
+        let mut cycle = FallbackCycle::default();
+        cycle.reset(10);
+
+        let played = (0..1000)
+            .map(|_| cycle.next(true, 4, &mut rng).unwrap())
+            .collect::<Vec<_>>();
+
+        for window in played.windows(5) {
+            for (n, a) in window.iter().enumerate() {
+                assert!(!window[n + 1..].contains(a), "repeat in {:?}", window);
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut rng = rand::thread_rng();
+        let mut cycle = FallbackCycle::default();
+        assert_eq!(None, cycle.next(true, 4, &mut rng));
+    }
+}
@@ -31,6 +31,21 @@ impl Item {
                 },
                 None => String::from("*Some YouTube Video*"),
             },
+            Track::Bandcamp { ref track } => format!("\"{}\" by {}", track.title, track.artist),
+        }
+    }
+
+    /// Test if the item is flagged as explicit or age restricted.
+    pub fn is_explicit(&self) -> bool {
+        match self.track {
+            Track::Spotify { ref track } => track.explicit,
+            Track::YouTube { ref video } => video
+                .content_details
+                .as_ref()
+                .map(|c| c.is_age_restricted())
+                .unwrap_or_default(),
+            // NB: Bandcamp doesn't flag explicit tracks.
+            Track::Bandcamp { .. } => false,
         }
     }
 
@@ -45,6 +60,9 @@ impl Item {
             Track::YouTube { video: _ } => {
                 return true;
             }
+            Track::Bandcamp { ref track } => {
+                return track.stream_url.is_some();
+            }
         }
     }
 }
@@ -1,6 +1,7 @@
 use crate::api;
 use crate::db;
-use crate::player::{convert_item, Item, Song};
+use crate::player::{convert_item, FallbackCycle, Item, Song, TrackCache};
+use crate::settings;
 use crate::track_id::TrackId;
 use crate::utils;
 use anyhow::Result;
@@ -14,26 +15,37 @@ pub(super) struct Mixer {
     db: db::Database,
     /// In-memory queue.
     queue: VecDeque<Arc<Item>>,
+    /// Songs collected before the stream, which are moved to the queue once it
+    /// starts.
+    staged: VecDeque<Arc<Item>>,
     /// A song that has been sidelined by another song.
     sidelined: VecDeque<Song>,
     /// Currently loaded fallback items.
     fallback_items: Vec<Arc<Item>>,
-    /// Items ordered in the reverse way they are meant to be played.
-    fallback_queue: VecDeque<Arc<Item>>,
+    /// The order in which fallback items are played.
+    fallback_cycle: FallbackCycle,
+    /// If fallback items should be shuffled.
+    fallback_shuffle: settings::Var<bool>,
+    /// Number of recently played fallback items to avoid repeating.
+    fallback_no_repeat: settings::Var<u32>,
 }
 
 impl Mixer {
-    /// The minimum size of the fallback queue.
-    const FALLBACK_QUEUE_SIZE: usize = 10;
-
     /// Construct a new mixer around the given queue.
-    pub(super) fn new(db: db::Database) -> Self {
+    pub(super) fn new(
+        db: db::Database,
+        fallback_shuffle: settings::Var<bool>,
+        fallback_no_repeat: settings::Var<u32>,
+    ) -> Self {
         Self {
             db,
             queue: Default::default(),
+            staged: Default::default(),
             sidelined: Default::default(),
             fallback_items: Default::default(),
-            fallback_queue: Default::default(),
+            fallback_cycle: Default::default(),
+            fallback_shuffle,
+            fallback_no_repeat,
         }
     }
 
@@ -42,16 +54,32 @@ impl Mixer {
         &mut self,
         spotify: &api::Spotify,
         youtube: &api::YouTube,
+        track_cache: &TrackCache,
     ) -> Result<()> {
         // TODO: cache this value
         let streamer = spotify.me().await?;
         let market = streamer.country.as_deref();
 
+        let songs = self.db.player_list().await?;
+        let staged = self.db.player_staged_list().await?;
+
+        let track_ids = songs
+            .iter()
+            .map(|s| &s.track_id)
+            .chain(staged.iter().map(|s| &s.track_id));
+
+        // NB: failing to pre-fetch is not fatal, we fall back to looking up
+        // each track individually below.
+        if let Err(e) = track_cache.prefetch(spotify, track_ids, market).await {
+            log_error!(e, "failed to pre-fetch track metadata");
+        }
+
         // Add tracks from database.
-        for song in self.db.player_list().await? {
+        for song in songs {
             let item = convert_item(
                 spotify,
                 youtube,
+                track_cache,
                 song.user.as_deref(),
                 &song.track_id,
                 None,
@@ -66,6 +94,26 @@ impl Mixer {
             }
         }
 
+        // Add staged tracks from database.
+        for song in staged {
+            let item = convert_item(
+                spotify,
+                youtube,
+                track_cache,
+                song.user.as_deref(),
+                &song.track_id,
+                None,
+                market,
+            )
+            .await;
+
+            if let Ok(Some(item)) = item {
+                self.staged.push_back(Arc::new(item));
+            } else {
+                log::warn!("failed to convert staged db item: {:?}", song);
+            }
+        }
+
         Ok(())
     }
 
@@ -93,6 +141,65 @@ impl Mixer {
         Ok(())
     }
 
+    /// List items staged for the next stream.
+    pub(super) fn staged(&self) -> impl Iterator<Item = &Arc<Item>> {
+        self.staged.iter()
+    }
+
+    /// Get the number of items staged for the next stream.
+    pub(super) fn staged_len(&self) -> usize {
+        self.staged.len()
+    }
+
+    /// Push item to back of the staged songs.
+    pub(super) async fn push_staged(&mut self, item: Arc<Item>) -> Result<()> {
+        self.db
+            .player_stage(&db::models::AddStagedSong {
+                track_id: item.track_id.clone(),
+                added_at: Utc::now().naive_utc(),
+                user: item.user.clone(),
+            })
+            .await?;
+
+        self.staged.push_back(item);
+        Ok(())
+    }
+
+    /// Move all staged items to the back of the queue.
+    ///
+    /// Items which are already in the queue are dropped. Returns the items
+    /// which were added to the queue.
+    pub(super) async fn promote_staged(&mut self) -> Result<Vec<Arc<Item>>> {
+        let staged = std::mem::replace(&mut self.staged, VecDeque::new());
+        let mut promoted = Vec::new();
+
+        for item in staged {
+            if self.queue.iter().any(|i| i.track_id == item.track_id) {
+                continue;
+            }
+
+            self.push_back(item.clone()).await?;
+            promoted.push(item);
+        }
+
+        self.db.player_staged_purge().await?;
+        Ok(promoted)
+    }
+
+    /// Purge the staged songs.
+    pub(super) async fn purge_staged(&mut self) -> Result<Vec<Arc<Item>>> {
+        if self.staged.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let purged = std::mem::replace(&mut self.staged, VecDeque::new())
+            .into_iter()
+            .collect();
+
+        self.db.player_staged_purge().await?;
+        Ok(purged)
+    }
+
     /// Purge the song queue.
     pub(super) async fn purge(&mut self) -> Result<Vec<Arc<Item>>> {
         if self.queue.is_empty() {
@@ -155,6 +262,73 @@ impl Mixer {
         Ok(None)
     }
 
+    /// Remove all songs requested by the given user, including staged songs.
+    pub(super) async fn remove_all_by_user(&mut self, user: &str) -> Result<Vec<Arc<Item>>> {
+        let queue = std::mem::replace(&mut self.queue, VecDeque::new());
+
+        let (mut removed, kept): (Vec<_>, Vec<_>) = queue
+            .into_iter()
+            .partition(|i| i.user.as_ref().map(|u| u == user).unwrap_or_default());
+
+        self.queue = kept.into_iter().collect();
+
+        if !removed.is_empty() {
+            self.db.player_remove_songs_by_user(user).await?;
+        }
+
+        let staged = std::mem::replace(&mut self.staged, VecDeque::new());
+
+        let (removed_staged, kept): (Vec<_>, Vec<_>) = staged
+            .into_iter()
+            .partition(|i| i.user.as_ref().map(|u| u == user).unwrap_or_default());
+
+        self.staged = kept.into_iter().collect();
+
+        if !removed_staged.is_empty() {
+            self.db.player_staged_remove_by_user(user).await?;
+        }
+
+        removed.extend(removed_staged);
+        Ok(removed)
+    }
+
+    /// Reorder the queue so that it matches the given order.
+    ///
+    /// The order must contain every track in the queue exactly once, otherwise
+    /// the queue is left as-is and `false` is returned.
+    pub(super) async fn reorder(&mut self, order: &[TrackId]) -> Result<bool> {
+        if order.len() != self.queue.len() {
+            return Ok(false);
+        }
+
+        let mut queue = Vec::with_capacity(order.len());
+
+        for track_id in order {
+            let item = match self.queue.iter().find(|i| i.track_id == *track_id) {
+                Some(item) => item.clone(),
+                None => return Ok(false),
+            };
+
+            if queue.iter().any(|i: &Arc<Item>| i.track_id == *track_id) {
+                return Ok(false);
+            }
+
+            queue.push(item);
+        }
+
+        self.db.player_reorder(order).await?;
+        self.queue = queue.into_iter().collect();
+        Ok(true)
+    }
+
+    /// Remove the first song in the queue with the given track id.
+    pub(super) async fn remove_track(&mut self, track_id: &TrackId) -> Result<Option<Arc<Item>>> {
+        match self.queue.iter().position(|i| i.track_id == *track_id) {
+            Some(n) => self.remove_at(n).await,
+            None => Ok(None),
+        }
+    }
+
     /// Promote the given song.
     pub(super) async fn promote_song(
         &mut self,
@@ -187,25 +361,19 @@ impl Mixer {
         self.db.player_last_song_within(track_id, duration).await
     }
 
-    /// Get next song to play.
+    /// Get next fallback song to play.
     ///
-    /// Will shuffle all fallback items and add them to a queue to avoid playing the same song twice.
-    pub(super) fn next_fallback_item(&mut self) -> Option<Song> {
-        use rand::seq::SliceRandom;
+    /// Fallback items are either played in order, or shuffled while avoiding
+    /// repeating recently played items.
+    async fn next_fallback_item(&mut self) -> Option<Song> {
+        let shuffle = self.fallback_shuffle.load().await;
+        let no_repeat = self.fallback_no_repeat.load().await as usize;
 
-        if self.fallback_items.is_empty() {
-            return None;
-        }
-
-        let mut rng = rand::thread_rng();
-
-        while self.fallback_queue.len() < Self::FALLBACK_QUEUE_SIZE {
-            let mut extension = self.fallback_items.clone();
-            extension.shuffle(&mut rng);
-            self.fallback_queue.extend(extension);
-        }
+        let index = self
+            .fallback_cycle
+            .next(shuffle, no_repeat, &mut rand::thread_rng())?;
 
-        let item = self.fallback_queue.pop_front()?;
+        let item = self.fallback_items.get(index)?.clone();
         Some(Song::new(item, Default::default()))
     }
 
@@ -232,7 +400,7 @@ impl Mixer {
             return Ok(None);
         }
 
-        Ok(self.next_fallback_item())
+        Ok(self.next_fallback_item().await)
     }
 
     /// Pop the front of the queue.
@@ -250,9 +418,9 @@ impl Mixer {
         self.sidelined.push_back(song);
     }
 
-    /// Update available fallback items and clear the current fallback queue.
+    /// Update available fallback items and reset the fallback cycle.
     pub(super) fn update_fallback_items(&mut self, items: Vec<Arc<Item>>) {
+        self.fallback_cycle.reset(items.len());
         self.fallback_items = items;
-        self.fallback_queue.clear();
     }
 }
@@ -10,6 +10,7 @@ use crate::track_id::TrackId;
 use crate::utils;
 use anyhow::{bail, Result};
 use chrono::{DateTime, Utc};
+use std::convert::TryFrom as _;
 use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
@@ -18,19 +19,29 @@ use tracing::trace_span;
 use tracing_futures::Instrument as _;
 
 pub(self) use self::connect::{ConnectDevice, ConnectPlayer, ConnectStream};
+pub use self::delayed::Delayed;
+pub(self) use self::fallback::FallbackCycle;
 pub(self) use self::mixer::Mixer;
 pub(self) use self::playback_future::PlaybackFuture;
 pub(self) use self::player_internal::PlayerInternal;
+pub(self) use self::track_cache::TrackCache;
 pub(self) use self::youtube::YouTubePlayer;
-pub use self::{item::Item, song::Song, track::Track};
+pub use self::{
+    item::Item,
+    song::{CurrentData, Song},
+    track::Track,
+};
 
 mod connect;
+mod delayed;
+mod fallback;
 mod item;
 mod mixer;
 mod playback_future;
 mod player_internal;
 mod song;
 mod track;
+mod track_cache;
 mod youtube;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -117,43 +128,18 @@ impl ModifyVolume {
 pub(self) async fn convert_item(
     spotify: &api::Spotify,
     youtube: &api::YouTube,
+    track_cache: &TrackCache,
     user: Option<&str>,
     track_id: &TrackId,
     duration_override: Option<Duration>,
     market: Option<&str>,
 ) -> Result<Option<Item>> {
-    let (track, duration) = match track_id {
-        TrackId::Spotify(id) => {
-            if !spotify.token.is_ready().await {
-                return Ok(None);
-            }
-
-            let track_id_string = id.to_base62();
-            let track = spotify.track(track_id_string, market).await?;
-            let duration = Duration::from_millis(track.duration_ms.into());
-
-            (Track::Spotify { track }, duration)
-        }
-        TrackId::YouTube(id) => {
-            if !youtube.token.is_ready().await {
-                return Ok(None);
-            }
-
-            let video = youtube.videos_by_id(id, "contentDetails,snippet").await?;
-
-            let video = match video {
-                Some(video) => video,
-                None => bail!("no video found for id `{}`", id),
-            };
-
-            let content_details = video
-                .content_details
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("video does not have content details"))?;
-
-            let duration = str::parse::<utils::PtDuration>(&content_details.duration)?;
-            (Track::YouTube { video }, duration.into_std())
-        }
+    let (track, duration) = match track_cache
+        .track(spotify, youtube, track_id, market)
+        .await?
+    {
+        Some(track) => track,
+        None => return Ok(None),
     };
 
     let duration = match duration_override {
@@ -179,6 +165,10 @@ pub async fn run(
     youtube_bus: Arc<bus::Bus<bus::YouTube>>,
     settings: settings::Settings,
 ) -> Result<(Player, impl Future<Output = Result<()>>)> {
+    let reject_explicit = settings.var("song/reject-explicit", false).await?;
+    let played_duration = settings
+        .var("song/duplicate-duration", utils::Duration::default())
+        .await?;
     let settings = settings.scoped("player");
 
     let mut futures = utils::Futures::default();
@@ -229,7 +219,11 @@ pub async fn run(
     let max_songs_per_user = settings.var("max-songs-per-user", 2).await?;
     let max_queue_length = settings.var("max-queue-length", 30).await?;
 
-    let mixer = Mixer::new(db.clone());
+    let fallback_shuffle = settings.var("fallback-shuffle", true).await?;
+    let fallback_no_repeat = settings.var("fallback-no-repeat", 10).await?;
+    let mixer = Mixer::new(db.clone(), fallback_shuffle, fallback_no_repeat);
+    let bandcamp = api::Bandcamp::new()?;
+    let track_cache = TrackCache::new(injector.var().await?, bandcamp.clone());
 
     let (playback_mode_stream, playback_mode) = settings
         .stream("playback-mode")
@@ -239,14 +233,17 @@ pub async fn run(
     let internal = Arc::new(RwLock::new(PlayerInternal {
         initialized: Default::default(),
         injector: injector.clone(),
+        db: db.clone(),
         player: PlayerKind::None,
         detached,
         spotify: spotify.clone(),
         youtube: youtube.clone(),
+        bandcamp,
         connect_player: connect_player.clone(),
         youtube_player,
         playback_mode,
         mixer,
+        track_cache,
         bus,
         global_bus,
         song_switch_feedback,
@@ -255,9 +252,15 @@ pub async fn run(
         max_queue_length,
         max_songs_per_user,
         duplicate_duration,
+        played_duration,
+        reject_explicit,
 
         themes: injector.var().await?,
+        banned_tracks: injector.var().await?,
         closed: None,
+        staging: false,
+        fallback_uri: None,
+        fallback_takeover: None,
     }));
 
     let playback = PlaybackFuture {
@@ -317,6 +320,8 @@ pub enum Event {
     NotConfigured,
     /// Player is detached.
     Detached,
+    /// The volume of the player was changed.
+    Volume(u32),
 }
 
 /// All parts of a Player that can be shared between threads.
@@ -422,10 +427,15 @@ impl Player {
             }
         };
 
-        Ok(match track_id {
-            TrackId::Spotify(..) => Some(inner.connect_player.volume(modify).await?),
-            TrackId::YouTube(..) => Some(inner.youtube_player.volume(modify).await?),
-        })
+        let volume = match track_id {
+            TrackId::Spotify(..) => inner.connect_player.volume(modify).await?,
+            TrackId::YouTube(..) | TrackId::Bandcamp(..) => {
+                inner.youtube_player.volume(modify).await?
+            }
+        };
+
+        inner.bus.send_sync(Event::Volume(volume));
+        Ok(Some(volume))
     }
 
     /// Get the current volume.
@@ -443,7 +453,9 @@ impl Player {
 
         match track_id {
             TrackId::Spotify(..) => Some(inner.connect_player.current_volume().await),
-            TrackId::YouTube(..) => Some(inner.youtube_player.current_volume().await),
+            TrackId::YouTube(..) | TrackId::Bandcamp(..) => {
+                Some(inner.youtube_player.current_volume().await)
+            }
         }
     }
 
@@ -457,6 +469,43 @@ impl Player {
         self.inner.write().await.closed = None;
     }
 
+    /// Stage requests for the next stream instead of adding them to the queue.
+    ///
+    /// Staging only applies to the default playback mode.
+    pub async fn stage(&self) {
+        self.inner.write().await.staging = true;
+    }
+
+    /// Test if requests are being staged for the next stream.
+    pub async fn is_staging(&self) -> bool {
+        self.inner.read().await.staging
+    }
+
+    /// List the songs staged for the next stream.
+    pub async fn staged(&self) -> Vec<Arc<Item>> {
+        self.inner.read().await.mixer.staged().cloned().collect()
+    }
+
+    /// Stop staging and move all staged songs to the queue.
+    ///
+    /// Returns the songs added to the queue.
+    pub async fn promote_staged(&self) -> Result<Vec<Arc<Item>>> {
+        let mut inner = self.inner.write().await;
+        inner.staging = false;
+        let promoted = inner.mixer.promote_staged().await?;
+
+        if !promoted.is_empty() {
+            inner.modified(Source::Manual).await?;
+        }
+
+        Ok(promoted)
+    }
+
+    /// Remove all staged songs.
+    pub async fn purge_staged(&self) -> Result<Vec<Arc<Item>>> {
+        self.inner.write().await.mixer.purge_staged().await
+    }
+
     /// Search for a track.
     pub async fn search_track(&self, q: &str) -> Result<Option<TrackId>> {
         let inner = self.inner.read().await;
@@ -510,6 +559,7 @@ impl Player {
         let item = convert_item(
             &*inner.spotify,
             &*inner.youtube,
+            &inner.track_cache,
             None,
             &theme.track_id,
             duration,
@@ -596,6 +646,65 @@ impl Player {
         Ok(removed)
     }
 
+    /// Reorder the queue so that it matches the given order.
+    ///
+    /// Returns `false` if the order doesn't contain every track in the queue
+    /// exactly once.
+    pub async fn reorder(&self, order: &[TrackId]) -> Result<bool> {
+        let mut inner = self.inner.write().await;
+
+        if !inner.mixer.reorder(order).await? {
+            return Ok(false);
+        }
+
+        inner.modified(Source::Manual).await?;
+        Ok(true)
+    }
+
+    /// Remove the tracks with the given ids from the queue.
+    pub async fn remove_tracks(&self, track_ids: &[TrackId]) -> Result<Vec<Arc<Item>>> {
+        let mut inner = self.inner.write().await;
+        let mut removed = Vec::new();
+
+        for track_id in track_ids {
+            if let Some(item) = inner.mixer.remove_track(track_id).await? {
+                removed.push(item);
+            }
+        }
+
+        if !removed.is_empty() {
+            inner.modified(Source::Manual).await?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Temporarily replace the fallback items with the given playlist.
+    ///
+    /// Returns the name of the playlist.
+    pub async fn takeover_fallback(&self, id: SpotifyId) -> Result<String> {
+        self.inner.write().await.takeover_fallback(id).await
+    }
+
+    /// Restore the configured fallback items.
+    ///
+    /// Returns `false` if no playlist had taken over fallback playback.
+    pub async fn reset_fallback(&self) -> bool {
+        self.inner.write().await.reset_fallback().await
+    }
+
+    /// Remove all tracks requested by the given user.
+    pub async fn remove_all_by_user(&self, user: &str) -> Result<Vec<Arc<Item>>> {
+        let mut inner = self.inner.write().await;
+        let removed = inner.mixer.remove_all_by_user(user).await?;
+
+        if !removed.is_empty() {
+            inner.modified(Source::Manual).await?;
+        }
+
+        Ok(removed)
+    }
+
     /// Find the next item that matches the given predicate and how long until it plays.
     pub async fn find(
         &self,
@@ -648,6 +757,20 @@ impl Player {
     pub async fn current(&self) -> Option<Song> {
         self.inner.read().await.injector.get::<Song>().await
     }
+
+    /// List songs which have been played, most recently played first.
+    pub async fn history(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<db::models::SongHistory>> {
+        // Values past `i64::MAX` would otherwise wrap around to negative
+        // numbers, which the database rejects.
+        let offset = i64::try_from(offset).unwrap_or(i64::MAX);
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let inner = self.inner.read().await;
+        inner.db.player_history(offset, limit).await
+    }
 }
 
 /// Error raised when failing to play a theme song.
@@ -674,12 +797,18 @@ pub enum AddTrackError {
     PlayerClosed(Option<Arc<String>>),
     /// Duplicate song that was added at the specified time by the specified user.
     Duplicate(DateTime<Utc>, Option<String>, Duration),
+    /// Song was played at the specified time, according to the song history.
+    RecentlyPlayed(DateTime<Utc>, Duration),
     /// Authentication missing for adding the given track.
     MissingAuth,
     /// Playback mode is not supported for the given track.
     UnsupportedPlaybackMode,
     /// Song cannot be played in the streamer's region
     NotPlayable,
+    /// Song, or the artist or channel it belongs to, has been banned.
+    Banned(Arc<db::BannedTrack>),
+    /// Song is explicit or age restricted, and explicit songs are rejected.
+    Explicit,
     /// Other generic error happened.
     Error(anyhow::Error),
 }
@@ -43,7 +43,7 @@ impl PlaybackFuture {
         self.internal
             .write()
             .await
-            .update_fallback_items(fallback)
+            .update_fallback_uri(fallback)
             .await;
 
         let (mut song_stream, song) = injector.stream::<Song>().await;
@@ -55,7 +55,7 @@ impl PlaybackFuture {
                     song_timeout = song.map(|s| tokio::time::delay_until(s.deadline().into()));
                 }
                 fallback = fallback_stream.select_next_some() => {
-                    self.internal.write().await.update_fallback_items(fallback).await;
+                    self.internal.write().await.update_fallback_uri(fallback).await;
                 }
                 /* player */
                 _ = song_timeout.current() => {
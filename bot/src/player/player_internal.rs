@@ -5,7 +5,7 @@ use crate::db;
 use crate::injector;
 use crate::player::{
     convert_item, AddTrackError, ConnectDevice, ConnectPlayer, Event, IntegrationEvent, Item,
-    Mixer, PlaybackMode, PlayerKind, Song, Source, State, Track, YouTubePlayer,
+    Mixer, PlaybackMode, PlayerKind, Song, Source, State, Track, TrackCache, YouTubePlayer,
 };
 use crate::prelude::*;
 use crate::settings;
@@ -27,6 +27,8 @@ pub(super) struct Initialized {
 pub(super) struct PlayerInternal {
     pub(super) initialized: Initialized,
     pub(super) injector: injector::Injector,
+    /// Database access.
+    pub(super) db: db::Database,
     /// Current player kind.
     pub(super) player: PlayerKind,
     /// Updated to the current playback mode.
@@ -35,6 +37,7 @@ pub(super) struct PlayerInternal {
     /// API clients and streams.
     pub(super) spotify: Arc<api::Spotify>,
     pub(super) youtube: Arc<api::YouTube>,
+    pub(super) bandcamp: api::Bandcamp,
     pub(super) connect_player: ConnectPlayer,
     pub(super) youtube_player: YouTubePlayer,
     /// The mode of the player.
@@ -44,6 +47,8 @@ pub(super) struct PlayerInternal {
     pub(super) playback_mode: PlaybackMode,
     /// The internal mixer.
     pub(super) mixer: Mixer,
+    /// Cache of track metadata.
+    pub(super) track_cache: TrackCache,
     /// The player bus.
     pub(super) bus: bus::Bus<Event>,
     /// Notifier to use when sending song updates.
@@ -54,10 +59,23 @@ pub(super) struct PlayerInternal {
     pub(super) max_queue_length: settings::Var<u32>,
     pub(super) max_songs_per_user: settings::Var<u32>,
     pub(super) duplicate_duration: settings::Var<utils::Duration>,
+    /// Reject songs which have been played within this duration, according to
+    /// the song history.
+    pub(super) played_duration: settings::Var<utils::Duration>,
+    /// Reject explicit songs, unless the requester can bypass constraints.
+    pub(super) reject_explicit: settings::Var<bool>,
     /// Theme songs.
     pub(super) themes: injector::Var<Option<db::Themes>>,
+    /// Tracks, artists, and channels which have been banned.
+    pub(super) banned_tracks: injector::Var<Option<db::BannedTracks>>,
     /// Player is closed for more requests.
     pub(super) closed: Option<Option<Arc<String>>>,
+    /// Requests are staged for the next stream instead of being queued.
+    pub(super) staging: bool,
+    /// The configured fallback URI.
+    pub(super) fallback_uri: Option<Uri>,
+    /// Playlist which has temporarily taken over fallback playback.
+    pub(super) fallback_takeover: Option<SpotifyId>,
 }
 
 impl PlayerInternal {
@@ -91,9 +109,11 @@ impl PlayerInternal {
 
         if !self.initialized.queue {
             self.mixer
-                .initialize_queue(&*self.spotify, &*self.youtube)
+                .initialize_queue(&*self.spotify, &*self.youtube, &self.track_cache)
                 .await?;
 
+            // NB: keep staging if songs were staged before a restart.
+            self.staging = self.mixer.staged_len() > 0;
             self.initialized.queue = true;
         }
 
@@ -121,6 +141,8 @@ impl PlayerInternal {
 
         log::trace!("Song ended, loading next song...");
 
+        self.record_history(false).await;
+
         if let Some(song) = self.mixer.next_song().await? {
             self.play_song(Source::Manual, song).await?;
         } else {
@@ -131,6 +153,31 @@ impl PlayerInternal {
         Ok(())
     }
 
+    /// Record the current song in the song history.
+    async fn record_history(&self, skipped: bool) {
+        let song = match self.injector.get::<Song>().await {
+            Some(song) => song,
+            None => return,
+        };
+
+        let played_at = match chrono::Duration::from_std(song.elapsed()) {
+            Ok(elapsed) => Utc::now() - elapsed,
+            Err(_) => Utc::now(),
+        };
+
+        let entry = db::models::AddSongHistory {
+            track_id: song.item.track_id.clone(),
+            name: song.item.what(),
+            user: song.item.user.clone(),
+            played_at: played_at.naive_utc(),
+            skipped,
+        };
+
+        if let Err(e) = self.db.player_history_push(&entry).await {
+            log_error!(e, "failed to record song history");
+        }
+    }
+
     /// Notify a change in the current song.
     async fn notify_song_change(&self, song: Option<&Song>) -> Result<()> {
         self.global_bus.send(bus::Global::song(song)?).await;
@@ -218,11 +265,29 @@ impl PlayerInternal {
                     .play(song.elapsed(), song.duration(), id)
                     .await;
             }
+            TrackId::Bandcamp(id) => {
+                let url = self.bandcamp_stream_url(&id).await?;
+
+                self.youtube_player
+                    .play_audio(song.elapsed(), song.duration(), url)
+                    .await;
+            }
         }
 
         Ok(())
     }
 
+    /// Look up the stream of a Bandcamp track.
+    ///
+    /// This is done right before playing it since stream URLs expire.
+    async fn bandcamp_stream_url(&self, id: &str) -> Result<String> {
+        self.bandcamp
+            .track(id)
+            .await?
+            .and_then(|track| track.stream_url)
+            .ok_or_else(|| anyhow!("no stream available for bandcamp track `{}`", id))
+    }
+
     /// Switch the player to the specified song without changing its state.
     async fn switch_to_song(&mut self, mut song: Option<Song>) -> Result<()> {
         if let Some(song) = song.as_mut() {
@@ -416,6 +481,8 @@ impl PlayerInternal {
 
         match self.playback_mode {
             PlaybackMode::Default => {
+                self.record_history(true).await;
+
                 let state = self.injector.get::<State>().await.unwrap_or_default();
                 let song = self.mixer.next_song().await?;
 
@@ -533,8 +600,50 @@ impl PlayerInternal {
         Ok(())
     }
 
+    /// Update the configured fallback URI.
+    ///
+    /// The fallback items are only reloaded if no playlist has taken over
+    /// fallback playback.
+    pub(super) async fn update_fallback_uri(&mut self, uri: Option<Uri>) {
+        self.fallback_uri = uri.clone();
+
+        if self.fallback_takeover.is_none() {
+            self.update_fallback_items(uri).await;
+        }
+    }
+
+    /// Temporarily take over fallback playback with the given playlist.
+    ///
+    /// Returns the name of the playlist.
+    pub(super) async fn takeover_fallback(&mut self, id: SpotifyId) -> Result<String> {
+        let (name, items) = Self::playlist_to_items(&self.spotify, id.to_string()).await?;
+
+        log::info!(
+            "\"{}\" playlist took over the fallback queue with {} items.",
+            name,
+            items.len()
+        );
+
+        self.mixer.update_fallback_items(items);
+        self.fallback_takeover = Some(id);
+        Ok(name)
+    }
+
+    /// Restore fallback playback to the configured fallback URI.
+    ///
+    /// Returns `false` if no playlist had taken over fallback playback.
+    pub(super) async fn reset_fallback(&mut self) -> bool {
+        if self.fallback_takeover.take().is_none() {
+            return false;
+        }
+
+        let uri = self.fallback_uri.clone();
+        self.update_fallback_items(uri).await;
+        true
+    }
+
     /// Update fallback items based on an URI.
-    pub(super) async fn update_fallback_items(&mut self, uri: Option<Uri>) {
+    async fn update_fallback_items(&mut self, uri: Option<Uri>) {
         let result = match uri.as_ref() {
             Some(uri) => {
                 let id = match uri {
@@ -694,6 +803,12 @@ impl PlayerInternal {
                         self.switch_current_player(PlayerKind::YouTube).await?;
                         self.injector.update(State::Playing).await;
                     }
+                    TrackId::Bandcamp(id) => {
+                        let url = self.bandcamp_stream_url(&id).await?;
+                        self.youtube_player.play_audio(elapsed, duration, url).await;
+                        self.switch_current_player(PlayerKind::YouTube).await?;
+                        self.injector.update(State::Playing).await;
+                    }
                 }
             }
         }
@@ -742,6 +857,41 @@ impl PlayerInternal {
         Ok(())
     }
 
+    /// Test if the given item has been banned, either directly or through the
+    /// artists or channel it belongs to.
+    async fn banned(&self, item: &Item) -> Option<Arc<db::BannedTrack>> {
+        let banned_tracks = self.banned_tracks.load().await?;
+
+        let track_id = item.track_id.to_string();
+
+        if let Some(ban) = banned_tracks.get(db::BanKind::Track, &track_id).await {
+            return Some(ban);
+        }
+
+        match &item.track {
+            Track::Spotify { track } => {
+                for artist in &track.artists {
+                    if let Some(id) = artist.id.as_deref() {
+                        if let Some(ban) = banned_tracks.get(db::BanKind::Artist, id).await {
+                            return Some(ban);
+                        }
+                    }
+                }
+            }
+            Track::YouTube { video } => {
+                if let Some(snippet) = video.snippet.as_ref() {
+                    let channel_id = snippet.channel_id.as_str();
+
+                    if let Some(ban) = banned_tracks.get(db::BanKind::Channel, channel_id).await {
+                        return Some(ban);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
     /// Add the given track to the queue.
     ///
     /// Returns the item added.
@@ -785,8 +935,14 @@ impl PlayerInternal {
 
                 let max_queue_length = self.max_queue_length.load().await;
 
+                let queue_length = if self.staging {
+                    self.mixer.staged_len()
+                } else {
+                    self.mixer.len()
+                };
+
                 // NB: moderator is allowed to violate max queue length.
-                if self.mixer.len() >= max_queue_length as usize {
+                if queue_length >= max_queue_length as usize {
                     return Err(AddTrackError::QueueFull);
                 }
 
@@ -808,12 +964,37 @@ impl PlayerInternal {
                         ));
                     }
                 }
+
+                let played_duration = self.played_duration.load().await;
+
+                if !played_duration.is_empty() {
+                    if let Some(last) = self
+                        .db
+                        .player_history_last_within(&track_id, played_duration.clone())
+                        .await
+                        .map_err(AddTrackError::Error)?
+                    {
+                        let played_at = DateTime::from_utc(last.played_at, Utc);
+
+                        return Err(AddTrackError::RecentlyPlayed(
+                            played_at,
+                            played_duration.as_std(),
+                        ));
+                    }
+                }
             }
 
             let mut user_count = 0;
             let mut len = 0;
 
-            for (index, i) in self.mixer.list().enumerate() {
+            // NB: while staging, requests are checked against the staged songs.
+            let items = if self.staging {
+                self.mixer.staged().collect::<Vec<_>>()
+            } else {
+                self.mixer.list().collect::<Vec<_>>()
+            };
+
+            for (index, i) in items.into_iter().enumerate() {
                 len += 1;
 
                 if i.track_id == track_id {
@@ -838,6 +1019,7 @@ impl PlayerInternal {
         let item = convert_item(
             &*self.spotify,
             &*self.youtube,
+            &self.track_cache,
             Some(user),
             &track_id,
             None,
@@ -855,6 +1037,15 @@ impl PlayerInternal {
             return Err(AddTrackError::NotPlayable);
         }
 
+        if let Some(ban) = self.banned(&item).await {
+            return Err(AddTrackError::Banned(ban));
+        }
+
+        // NB: moderator is allowed to add explicit songs.
+        if !bypass_constraints && self.reject_explicit.load().await && item.is_explicit() {
+            return Err(AddTrackError::Explicit);
+        }
+
         if let Some(max_duration) = max_duration {
             let max_duration = max_duration.as_std();
 
@@ -865,6 +1056,15 @@ impl PlayerInternal {
 
         let item = Arc::new(item);
 
+        if self.staging {
+            self.mixer
+                .push_staged(item.clone())
+                .await
+                .map_err(AddTrackError::Error)?;
+
+            return Ok((Some(len), item));
+        }
+
         self.mixer
             .push_back(item.clone())
             .await
@@ -882,13 +1082,14 @@ impl PlayerInternal {
         &mut self,
         user: &str,
         track_id: TrackId,
-        _bypass_constraints: bool,
+        bypass_constraints: bool,
         _max_duration: Option<utils::Duration>,
         market: Option<&str>,
     ) -> Result<(Option<usize>, Arc<Item>), AddTrackError> {
         let item = convert_item(
             &*self.spotify,
             &*self.youtube,
+            &self.track_cache,
             Some(user),
             &track_id,
             None,
@@ -902,6 +1103,14 @@ impl PlayerInternal {
             None => return Err(AddTrackError::MissingAuth),
         };
 
+        if let Some(ban) = self.banned(&item).await {
+            return Err(AddTrackError::Banned(ban));
+        }
+
+        if !bypass_constraints && self.reject_explicit.load().await && item.is_explicit() {
+            return Err(AddTrackError::Explicit);
+        }
+
         match track_id {
             TrackId::Spotify(id) => {
                 self.connect_player
@@ -909,7 +1118,7 @@ impl PlayerInternal {
                     .await
                     .map_err(|e| AddTrackError::Error(e.into()))?;
             }
-            TrackId::YouTube(..) => {
+            TrackId::YouTube(..) | TrackId::Bandcamp(..) => {
                 return Err(AddTrackError::UnsupportedPlaybackMode);
             }
         }
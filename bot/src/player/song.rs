@@ -163,7 +163,8 @@ impl Song {
     pub fn player(&self) -> PlayerKind {
         match self.item.track_id {
             TrackId::Spotify(..) => PlayerKind::Spotify,
-            TrackId::YouTube(..) => PlayerKind::YouTube,
+            // NB: Bandcamp tracks are played in the same web player as YouTube.
+            TrackId::YouTube(..) | TrackId::Bandcamp(..) => PlayerKind::YouTube,
         }
     }
 
@@ -10,6 +10,8 @@ pub enum Track {
     Spotify { track: api::spotify::FullTrack },
     #[serde(rename = "youtube")]
     YouTube { video: api::youtube::Video },
+    #[serde(rename = "bandcamp")]
+    Bandcamp { track: api::bandcamp::Track },
 }
 
 impl Track {
@@ -20,6 +22,7 @@ impl Track {
             Self::YouTube { ref video } => {
                 video.snippet.as_ref().and_then(|s| s.channel_title.clone())
             }
+            Self::Bandcamp { ref track } => Some(track.artist.clone()),
         }
     }
 
@@ -33,6 +36,7 @@ impl Track {
                 .map(|s| s.title.as_str())
                 .unwrap_or("no name")
                 .to_string(),
+            Self::Bandcamp { ref track } => track.title.to_string(),
         }
     }
 
@@ -42,6 +46,7 @@ impl Track {
         let json = match *self {
             Self::Spotify { ref track } => serde_json::to_value(&track)?,
             Self::YouTube { ref video } => serde_json::to_value(&video)?,
+            Self::Bandcamp { ref track } => serde_json::to_value(&track)?,
         };
 
         Ok(json)
@@ -0,0 +1,225 @@
+use crate::api;
+use crate::injector;
+use crate::player::Track;
+use crate::prelude::*;
+use crate::storage::Cache;
+use crate::track_id::TrackId;
+use crate::utils;
+use anyhow::{anyhow, bail, Result};
+use futures_cache as cache;
+use std::time::Duration;
+
+/// Key used for cached track metadata.
+#[derive(Debug, serde::Serialize)]
+struct Key<'a> {
+    track_id: &'a TrackId,
+    market: Option<&'a str>,
+}
+
+/// A persistent cache of track metadata.
+///
+/// Avoids hitting the remote APIs every time a track is converted into a
+/// playback item.
+#[derive(Clone)]
+pub(super) struct TrackCache {
+    cache: injector::Var<Option<Cache>>,
+    bandcamp: api::Bandcamp,
+}
+
+impl TrackCache {
+    /// How long metadata for a single track is cached for.
+    const TTL_HOURS: i64 = 24;
+
+    /// Construct a new track cache.
+    pub(super) fn new(cache: injector::Var<Option<Cache>>, bandcamp: api::Bandcamp) -> Self {
+        Self { cache, bandcamp }
+    }
+
+    /// Access the namespaced cache, if it's available.
+    async fn cache(&self) -> Result<Option<Cache>> {
+        match self.cache.load().await {
+            Some(cache) => Ok(Some(cache.namespaced(&"tracks")?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Pre-fetch metadata for the given tracks.
+    ///
+    /// Spotify tracks which are not already cached are looked up in batches,
+    /// which is substantially cheaper than looking them up one at a time.
+    pub(super) async fn prefetch<'a>(
+        &self,
+        spotify: &api::Spotify,
+        track_ids: impl IntoIterator<Item = &'a TrackId>,
+        market: Option<&str>,
+    ) -> Result<()> {
+        let cache = match self.cache().await? {
+            Some(cache) => cache,
+            None => return Ok(()),
+        };
+
+        if !spotify.token.is_ready().await {
+            return Ok(());
+        }
+
+        let mut missing = Vec::new();
+
+        for track_id in track_ids {
+            if let TrackId::Spotify(..) = track_id {
+                if let cache::State::Fresh(..) = cache.test(Key { track_id, market })? {
+                    continue;
+                }
+
+                missing.push(track_id.clone());
+            }
+        }
+
+        for chunk in missing.chunks(api::spotify::MAX_TRACKS) {
+            let ids = chunk
+                .iter()
+                .flat_map(|track_id| match track_id {
+                    TrackId::Spotify(id) => Some(id.to_base62()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            let tracks = spotify.tracks(&ids, market).await?;
+
+            for (track_id, track) in chunk.iter().zip(tracks) {
+                let track = match track {
+                    Some(track) => Track::Spotify { track },
+                    None => {
+                        log::warn!("no metadata available for track: {}", track_id);
+                        continue;
+                    }
+                };
+
+                cache
+                    .wrap(
+                        Key { track_id, market },
+                        chrono::Duration::hours(Self::TTL_HOURS),
+                        future::ok::<_, anyhow::Error>(track),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Look up metadata for the given track, using the cache if possible.
+    ///
+    /// Returns `None` if the service required to look up the track is not
+    /// authenticated.
+    pub(super) async fn track(
+        &self,
+        spotify: &api::Spotify,
+        youtube: &api::YouTube,
+        track_id: &TrackId,
+        market: Option<&str>,
+    ) -> Result<Option<(Track, Duration)>> {
+        let track = match track_id {
+            TrackId::Spotify(id) => {
+                if !spotify.token.is_ready().await {
+                    return Ok(None);
+                }
+
+                let future = async move {
+                    let track = spotify.track(id.to_base62(), market).await?;
+                    Ok::<_, anyhow::Error>(Track::Spotify { track })
+                };
+
+                self.wrap(Key { track_id, market }, future).await?
+            }
+            TrackId::YouTube(id) => {
+                if !youtube.token.is_ready().await {
+                    return Ok(None);
+                }
+
+                let future = async move {
+                    let video = youtube.videos_by_id(id, "contentDetails,snippet").await?;
+
+                    match video {
+                        Some(video) => Ok(Track::YouTube { video }),
+                        None => bail!("no video found for id `{}`", id),
+                    }
+                };
+
+                self.wrap(
+                    Key {
+                        track_id,
+                        market: None,
+                    },
+                    future,
+                )
+                .await?
+            }
+            TrackId::Bandcamp(id) => {
+                let bandcamp = &self.bandcamp;
+
+                let future = async move {
+                    match bandcamp.track(id).await? {
+                        Some(track) => Ok(Track::Bandcamp { track }),
+                        None => bail!("no bandcamp track found for id `{}`", id),
+                    }
+                };
+
+                self.wrap(
+                    Key {
+                        track_id,
+                        market: None,
+                    },
+                    future,
+                )
+                .await?
+            }
+        };
+
+        let duration = track_duration(&track)?;
+        Ok(Some((track, duration)))
+    }
+
+    /// Wrap the given future in the cache, if it's available.
+    async fn wrap(
+        &self,
+        key: Key<'_>,
+        future: impl Future<Output = Result<Track>>,
+    ) -> Result<Track> {
+        match self.cache().await? {
+            Some(cache) => {
+                cache
+                    .wrap(key, chrono::Duration::hours(Self::TTL_HOURS), future)
+                    .await
+            }
+            None => future.await,
+        }
+    }
+}
+
+/// Get the duration of the given track.
+fn track_duration(track: &Track) -> Result<Duration> {
+    match track {
+        Track::Spotify { track } => Ok(Duration::from_millis(track.duration_ms.into())),
+        Track::YouTube { video } => {
+            let content_details = video
+                .content_details
+                .as_ref()
+                .ok_or_else(|| anyhow!("video does not have content details"))?;
+
+            let duration = str::parse::<utils::PtDuration>(&content_details.duration)?;
+            Ok(duration.into_std())
+        }
+        Track::Bandcamp { track } => {
+            // NB: `from_secs_f64` panics on negative, non-finite, or
+            // overflowing durations.
+            if !track.duration.is_finite()
+                || track.duration < 0f64
+                || track.duration >= u64::MAX as f64
+            {
+                bail!("bad track duration: {}", track.duration);
+            }
+
+            Ok(Duration::from_secs_f64(track.duration))
+        }
+    }
+}
@@ -76,6 +76,17 @@ impl YouTubePlayer {
         self.bus.send(bus::YouTube::YouTubeCurrent { event }).await;
     }
 
+    /// Play an audio file through the web player.
+    pub(super) async fn play_audio(&self, elapsed: Duration, duration: Duration, url: String) {
+        let event = bus::YouTubeEvent::PlayAudio {
+            url,
+            elapsed: elapsed.as_secs(),
+            duration: duration.as_secs(),
+        };
+
+        self.bus.send(bus::YouTube::YouTubeCurrent { event }).await;
+    }
+
     pub(super) async fn pause(&self) {
         let event = bus::YouTubeEvent::Pause;
         self.bus.send(bus::YouTube::YouTubeCurrent { event }).await;
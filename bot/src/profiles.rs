@@ -0,0 +1,251 @@
+//! Named profiles of settings, and reading and writing settings to files.
+//!
+//! A profile is a snapshot of some settings, like the song limits used during
+//! a music stream, which can be activated at any time to switch back to them.
+
+use crate::settings::{Import, Settings};
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Setting where saved profiles are stored.
+const SAVED: &str = "profiles/saved";
+/// Setting where the name of the last activated profile is stored.
+const ACTIVE: &str = "profiles/active";
+/// Prefix of the settings used by profiles, which are never part of a
+/// profile themselves.
+const PREFIX: &str = "profiles/";
+
+/// Settings by key.
+pub type Values = BTreeMap<String, serde_json::Value>;
+
+/// The format of a settings file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Format {
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "toml")]
+    Toml,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Json
+    }
+}
+
+impl Format {
+    /// Pick the format of a file from its extension, defaulting to JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+
+    /// Encode the given settings.
+    pub fn encode(self, values: &Values) -> Result<String> {
+        match self {
+            Format::Json => Ok(serde_json::to_string_pretty(values)?),
+            Format::Toml => {
+                // NB: TOML has no null, so unset values are left out.
+                let values = values
+                    .iter()
+                    .filter(|(_, v)| !v.is_null())
+                    .map(|(k, v)| (k.clone(), strip_nulls(v.clone())))
+                    .collect::<Values>();
+
+                let value = toml::Value::try_from(values)?;
+                Ok(toml::to_string_pretty(&value)?)
+            }
+        }
+    }
+
+    /// Decode settings.
+    pub fn decode(self, content: &str) -> Result<Values> {
+        match self {
+            Format::Json => Ok(serde_json::from_str(content)?),
+            Format::Toml => Ok(toml::from_str(content)?),
+        }
+    }
+}
+
+/// Remove nulls nested in the given value.
+fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .filter(|v| !v.is_null())
+                .map(strip_nulls)
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Test if the given key is covered by the prefix.
+fn matches_prefix(key: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+
+    match key.strip_prefix(prefix) {
+        Some(rest) => prefix.is_empty() || rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// A saved profile.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Profile {
+    /// The settings applied when the profile is activated.
+    #[serde(default)]
+    pub settings: Values,
+}
+
+/// Named profiles of settings.
+#[derive(Clone)]
+pub struct Profiles {
+    settings: Settings,
+}
+
+impl Profiles {
+    /// Construct profiles stored in the given settings.
+    pub fn new(settings: Settings) -> Self {
+        Self { settings }
+    }
+
+    /// Get the name of the profile which was last activated.
+    pub async fn active(&self) -> Result<Option<String>> {
+        Ok(self.settings.get(ACTIVE).await?)
+    }
+
+    /// List all saved profiles.
+    pub async fn list(&self) -> Result<BTreeMap<String, Profile>> {
+        Ok(self.settings.get(SAVED).await?.unwrap_or_default())
+    }
+
+    /// Save the current value of every setting matching any of the given
+    /// prefixes as a profile, replacing any profile with the same name.
+    ///
+    /// Settings which use their default value are saved as well, so that
+    /// activating the profile restores them. All settings except for secrets
+    /// are saved if no prefixes are given.
+    pub async fn save(&self, name: &str, prefixes: &[String]) -> Result<Profile> {
+        if name.trim().is_empty() || name.contains('/') {
+            bail!("bad profile name `{}`", name);
+        }
+
+        let settings = self
+            .settings
+            .snapshot()
+            .await?
+            .into_iter()
+            .filter(|(key, _)| !key.starts_with(PREFIX))
+            .filter(|(key, _)| {
+                prefixes.is_empty() || prefixes.iter().any(|p| matches_prefix(key, p))
+            })
+            .collect();
+
+        let profile = Profile { settings };
+
+        let mut profiles = self.list().await?;
+        profiles.insert(name.to_string(), profile.clone());
+        self.settings.set(SAVED, profiles).await?;
+        Ok(profile)
+    }
+
+    /// Activate the given profile, applying all of its settings.
+    ///
+    /// Nothing is applied if any of the settings fail to validate.
+    pub async fn activate(&self, name: &str) -> Result<Import> {
+        let profile = self
+            .list()
+            .await?
+            .remove(name)
+            .ok_or_else(|| anyhow!("no profile named `{}`", name))?;
+
+        let import = self.settings.import(profile.settings).await?;
+
+        if import.errors.is_empty() {
+            self.settings.set(ACTIVE, name).await?;
+        }
+
+        Ok(import)
+    }
+
+    /// Delete the given profile, returning `true` if it existed.
+    pub async fn delete(&self, name: &str) -> Result<bool> {
+        let mut profiles = self.list().await?;
+
+        if profiles.remove(name).is_none() {
+            return Ok(false);
+        }
+
+        self.settings.set(SAVED, profiles).await?;
+
+        if self.active().await?.as_deref() == Some(name) {
+            self.settings.clear(ACTIVE).await?;
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{matches_prefix, Format, Values};
+    use serde_json::json;
+
+    #[test]
+    fn test_matches_prefix() {
+        assert!(matches_prefix("song/max-queue-length", "song"));
+        assert!(matches_prefix("song/max-queue-length", "song/"));
+        assert!(matches_prefix(
+            "song/max-queue-length",
+            "song/max-queue-length"
+        ));
+        assert!(matches_prefix("song/max-queue-length", ""));
+        assert!(!matches_prefix("songs/max-queue-length", "song"));
+        assert!(!matches_prefix("player/volume", "song"));
+    }
+
+    #[test]
+    fn test_toml_roundtrip() {
+        let mut values = Values::new();
+        values.insert("song/enabled".into(), json!(true));
+        values.insert("song/max-queue-length".into(), json!(30));
+        values.insert("song/spotify/max-duration".into(), json!("10m"));
+        values.insert("chat/cooldown".into(), json!(null));
+        values.insert(
+            "gtav/command-configs".into(),
+            json!([{"name": "Wanted", "cooldown": null}]),
+        );
+
+        let encoded = Format::Toml.encode(&values).expect("settings to encode");
+        let decoded = Format::Toml.decode(&encoded).expect("settings to decode");
+
+        values.remove("chat/cooldown");
+        values.insert("gtav/command-configs".into(), json!([{"name": "Wanted"}]));
+        assert_eq!(values, decoded);
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let mut values = Values::new();
+        values.insert("song/enabled".into(), json!(false));
+        values.insert("chat/cooldown".into(), json!(null));
+
+        let encoded = Format::Json.encode(&values).expect("settings to encode");
+        let decoded = Format::Json.decode(&encoded).expect("settings to decode");
+        assert_eq!(values, decoded);
+    }
+}
@@ -0,0 +1,389 @@
+//! Timezone-aware, cron-like scheduling.
+//!
+//! A schedule is an expression made up of five fields followed by an optional
+//! time zone, like `0 20 * * fri Europe/Stockholm`. The fields are, in order:
+//! minute (0-59), hour (0-23), day of month (1-31), month (1-12 or `jan`-`dec`)
+//! and day of week (0-7 or `sun`-`sat`, where both 0 and 7 are Sunday).
+//!
+//! Each field is either `*`, a single value, a range like `1-5`, a step like
+//! `*/15` or `10-50/10`, or a comma-separated list of the above.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{
+    DateTime, Datelike as _, Duration, NaiveDate, NaiveDateTime, TimeZone as _, Timelike as _, Utc,
+};
+use chrono_tz::Tz;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+static MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+static WEEKDAYS: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// How many years ahead we look for a matching time before giving up.
+const MAX_YEARS: i32 = 5;
+
+/// A single parsed field in a schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Field {
+    /// Bit set of matching values.
+    bits: u64,
+    /// If the field was a wildcard.
+    any: bool,
+}
+
+impl Field {
+    /// Test if the given value matches the field.
+    fn test(self, value: u32) -> bool {
+        self.bits & (1 << value) != 0
+    }
+
+    /// Parse a field, where values are in the inclusive range `min` to `max`.
+    ///
+    /// Names are matched case-insensitively, with the first name corresponding
+    /// to `min`.
+    fn parse(s: &str, min: u32, max: u32, names: &[&str]) -> Result<Self> {
+        let mut bits = 0u64;
+        let mut any = false;
+
+        for part in s.split(',') {
+            let (range, step) = match part.find('/') {
+                Some(n) => {
+                    let step = str::parse::<u32>(&part[n + 1..])
+                        .map_err(|_| anyhow!("bad step in `{}`", part))?;
+
+                    if step == 0 {
+                        bail!("step must be greater than zero in `{}`", part);
+                    }
+
+                    (&part[..n], step)
+                }
+                None => (part, 1),
+            };
+
+            let (start, end) = if range == "*" {
+                any = any || step == 1;
+                (min, max)
+            } else {
+                match range.find('-') {
+                    Some(n) => (
+                        parse_value(&range[..n], min, max, names)?,
+                        parse_value(&range[n + 1..], min, max, names)?,
+                    ),
+                    None => {
+                        let value = parse_value(range, min, max, names)?;
+
+                        // NB: `5/10` means starting at 5, every 10.
+                        if part.contains('/') {
+                            (value, max)
+                        } else {
+                            (value, value)
+                        }
+                    }
+                }
+            };
+
+            if start > end {
+                bail!("bad range `{}`", range);
+            }
+
+            let mut value = start;
+
+            while value <= end {
+                bits |= 1 << value;
+                value += step;
+            }
+        }
+
+        return Ok(Field { bits, any });
+
+        fn parse_value(s: &str, min: u32, max: u32, names: &[&str]) -> Result<u32> {
+            let lower = s.to_lowercase();
+
+            if let Some(n) = names.iter().position(|name| *name == lower) {
+                return Ok(min + n as u32);
+            }
+
+            let value = str::parse::<u32>(s).map_err(|_| anyhow!("bad value `{}`", s))?;
+
+            if value < min || value > max {
+                bail!("value `{}` not in range {}-{}", value, min, max);
+            }
+
+            Ok(value)
+        }
+    }
+}
+
+/// A timezone-aware, cron-like schedule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: Field,
+    hours: Field,
+    days: Field,
+    months: Field,
+    weekdays: Field,
+    timezone: Tz,
+    /// The expression the schedule was parsed from.
+    source: String,
+}
+
+impl Schedule {
+    /// Access the time zone of the schedule.
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// Find the next time the schedule fires strictly after the given time.
+    ///
+    /// Returns `None` if the schedule doesn't fire within the next couple of
+    /// years, like for `0 0 31 2 *`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = after.with_timezone(&self.timezone).naive_local();
+        let mut t = local.date().and_hms(local.hour(), local.minute(), 0) + Duration::minutes(1);
+        let limit = t.year() + MAX_YEARS;
+
+        while t.year() <= limit {
+            if !self.months.test(t.month()) {
+                t = next_month(t.date());
+                continue;
+            }
+
+            if !self.test_day(t.date()) {
+                t = t.date().and_hms(0, 0, 0) + Duration::days(1);
+                continue;
+            }
+
+            if !self.hours.test(t.hour()) {
+                t = t.date().and_hms(t.hour(), 0, 0) + Duration::hours(1);
+                continue;
+            }
+
+            if !self.minutes.test(t.minute()) {
+                t += Duration::minutes(1);
+                continue;
+            }
+
+            // NB: times which don't exist locally, like during a DST
+            // transition, are skipped.
+            let found = match self.timezone.from_local_datetime(&t) {
+                chrono::LocalResult::Single(found) => Some(found),
+                chrono::LocalResult::Ambiguous(earliest, latest) => {
+                    if earliest.with_timezone(&Utc) > after {
+                        Some(earliest)
+                    } else {
+                        Some(latest)
+                    }
+                }
+                chrono::LocalResult::None => None,
+            };
+
+            if let Some(found) = found {
+                let found = found.with_timezone(&Utc);
+
+                if found > after {
+                    return Some(found);
+                }
+            }
+
+            t += Duration::minutes(1);
+        }
+
+        None
+    }
+
+    /// Find the next time the schedule fires from now.
+    pub fn next(&self) -> Option<DateTime<Utc>> {
+        self.next_after(Utc::now())
+    }
+
+    /// Construct a delay which resolves when the schedule next fires.
+    ///
+    /// Returns `None` if the schedule never fires.
+    pub fn delay(&self) -> Option<tokio::time::Delay> {
+        let next = self.next()?;
+        let duration = (next - Utc::now()).to_std().unwrap_or_default();
+        Some(tokio::time::delay_for(duration))
+    }
+
+    /// Test if the given day matches the schedule.
+    ///
+    /// Like cron, if both day of month and day of week are restricted, the
+    /// day matches if either of them matches.
+    fn test_day(&self, date: NaiveDate) -> bool {
+        let day = self.days.test(date.day());
+        let weekday = self.weekdays.test(date.weekday().num_days_from_sunday());
+
+        match (self.days.any, self.weekdays.any) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+/// Get the first minute of the month following the given date.
+fn next_month(date: NaiveDate) -> NaiveDateTime {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        month => (date.year(), month + 1),
+    };
+
+    NaiveDate::from_ymd(year, month, 1).and_hms(0, 0, 0)
+}
+
+impl std::str::FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut it = s.split_whitespace();
+
+        let mut next = |what: &str| {
+            it.next()
+                .ok_or_else(|| anyhow!("missing {} in schedule `{}`", what, s))
+        };
+
+        let minutes = Field::parse(next("minute")?, 0, 59, &[])?;
+        let hours = Field::parse(next("hour")?, 0, 23, &[])?;
+        let days = Field::parse(next("day of month")?, 1, 31, &[])?;
+        let months = Field::parse(next("month")?, 1, 12, MONTHS)?;
+        let mut weekdays = Field::parse(next("day of week")?, 0, 7, WEEKDAYS)?;
+
+        // NB: both 0 and 7 are Sunday.
+        if weekdays.test(7) {
+            weekdays.bits = (weekdays.bits & !(1 << 7)) | 1;
+        }
+
+        let timezone = match it.next() {
+            Some(tz) => str::parse::<Tz>(tz).map_err(|e| anyhow!("{}", e))?,
+            None => Tz::UTC,
+        };
+
+        if let Some(extra) = it.next() {
+            bail!("unexpected `{}` in schedule `{}`", extra, s);
+        }
+
+        Ok(Schedule {
+            minutes,
+            hours,
+            days,
+            months,
+            weekdays,
+            timezone,
+            source: s.trim().to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(fmt)
+    }
+}
+
+impl serde::Serialize for Schedule {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Schedule {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        str::parse::<Schedule>(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A registry of named schedules which are currently in use, used for
+/// introspection.
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    schedules: Arc<RwLock<BTreeMap<String, Schedule>>>,
+}
+
+impl Scheduler {
+    /// Construct a new, empty scheduler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register or update the schedule with the given name.
+    ///
+    /// Providing `None` removes the schedule.
+    pub async fn update(&self, name: &str, schedule: Option<Schedule>) {
+        let mut schedules = self.schedules.write().await;
+
+        match schedule {
+            Some(schedule) => {
+                schedules.insert(name.to_string(), schedule);
+            }
+            None => {
+                schedules.remove(name);
+            }
+        }
+    }
+
+    /// List all registered schedules.
+    pub async fn list(&self) -> Vec<(String, Schedule)> {
+        self.schedules
+            .read()
+            .await
+            .iter()
+            .map(|(name, schedule)| (name.clone(), schedule.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Schedule;
+    use chrono::{TimeZone as _, Utc};
+
+    #[test]
+    fn test_parse() {
+        assert!(str::parse::<Schedule>("* * * * *").is_ok());
+        assert!(str::parse::<Schedule>("*/15 8-17 * jan-mar mon-fri").is_ok());
+        assert!(str::parse::<Schedule>("0 20 * * fri Europe/Stockholm").is_ok());
+        assert!(str::parse::<Schedule>("0 20 * *").is_err());
+        assert!(str::parse::<Schedule>("60 * * * *").is_err());
+        assert!(str::parse::<Schedule>("0 20 * * * Not/AZone").is_err());
+    }
+
+    #[test]
+    fn test_next_after() {
+        let s = str::parse::<Schedule>("*/15 * * * *").unwrap();
+        let t = Utc.ymd(2020, 8, 1).and_hms(10, 7, 30);
+        assert_eq!(
+            Some(Utc.ymd(2020, 8, 1).and_hms(10, 15, 0)),
+            s.next_after(t)
+        );
+
+        // 2020-08-01 is a saturday.
+        let s = str::parse::<Schedule>("0 20 * * fri").unwrap();
+        let t = Utc.ymd(2020, 8, 1).and_hms(10, 0, 0);
+        assert_eq!(Some(Utc.ymd(2020, 8, 7).and_hms(20, 0, 0)), s.next_after(t));
+
+        // Stockholm is UTC+2 in the summer.
+        let s = str::parse::<Schedule>("0 20 * * * Europe/Stockholm").unwrap();
+        let t = Utc.ymd(2020, 8, 1).and_hms(10, 0, 0);
+        assert_eq!(Some(Utc.ymd(2020, 8, 1).and_hms(18, 0, 0)), s.next_after(t));
+
+        // Sunday as 7.
+        let s = str::parse::<Schedule>("0 0 * * 7").unwrap();
+        let t = Utc.ymd(2020, 8, 1).and_hms(10, 0, 0);
+        assert_eq!(Some(Utc.ymd(2020, 8, 2).and_hms(0, 0, 0)), s.next_after(t));
+
+        // Never fires.
+        let s = str::parse::<Schedule>("0 0 31 2 *").unwrap();
+        assert_eq!(None, s.next_after(t));
+    }
+}
@@ -1,9 +1,21 @@
+use crate::api;
 use crate::command;
+use crate::currency;
 use crate::db;
+use crate::injector;
+use crate::settings;
 use anyhow::Result;
 use std::path::Path;
 
-pub(crate) async fn load_dir<I>(_channel: String, _db: db::Database, _paths: I) -> Result<Scripts>
+pub(crate) async fn load_dir<I>(
+    _channel: String,
+    _db: db::Database,
+    _obs: injector::Var<Option<api::Obs>>,
+    _currency: injector::Var<Option<currency::Currency>>,
+    _counters: injector::Var<Option<db::Counters>>,
+    _settings: settings::Settings,
+    _paths: I,
+) -> Result<Scripts>
 where
     I: IntoIterator,
     I::Item: AsRef<Path>,
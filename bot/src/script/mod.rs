@@ -1,6 +1,10 @@
+use crate::api;
 use crate::command;
+use crate::currency;
 use crate::db;
-use anyhow::{anyhow, Result};
+use crate::injector;
+use crate::settings;
+use anyhow::{anyhow, bail, Result};
 use ignore::Walk;
 use rune::diagnostics::EmitDiagnostics as _;
 use rune::termcolor;
@@ -9,16 +13,30 @@ use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 mod io;
 
+/// How long scripts wait for HTTP responses.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+/// The largest HTTP response scripts can read, in bytes.
+const HTTP_MAX_BODY: usize = 64 * 1024;
+
 /// Load all scripts from the given directory.
-pub(crate) async fn load_dir<I>(channel: String, db: db::Database, paths: I) -> Result<Scripts>
+pub(crate) async fn load_dir<I>(
+    channel: String,
+    db: db::Database,
+    obs: injector::Var<Option<api::Obs>>,
+    currency: injector::Var<Option<currency::Currency>>,
+    counters: injector::Var<Option<db::Counters>>,
+    settings: settings::Settings,
+    paths: I,
+) -> Result<Scripts>
 where
     I: IntoIterator,
     I::Item: AsRef<Path>,
 {
-    let mut scripts = Scripts::new(channel, db).await?;
+    let mut scripts = Scripts::new(channel, db, obs, currency, counters, settings).await?;
 
     for path in paths {
         let path = path.as_ref();
@@ -99,8 +117,216 @@ impl ScopedDb {
     }
 }
 
+/// Access to OBS from scripts.
+#[derive(Clone, Any)]
+struct Obs {
+    obs: injector::Var<Option<api::Obs>>,
+}
+
+impl Obs {
+    /// Get the OBS client, if it's configured.
+    async fn client(&self) -> Result<api::Obs> {
+        match self.obs.load().await {
+            Some(obs) => Ok(obs),
+            None => bail!("OBS is not configured"),
+        }
+    }
+
+    /// Switch to the given scene.
+    async fn set_scene(&self, scene: &str) -> Result<(), runestick::Error> {
+        self.client().await?.set_current_scene(scene).await?;
+        Ok(())
+    }
+
+    /// Show or hide a source in the current scene.
+    async fn set_visible(&self, source: &str, visible: bool) -> Result<(), runestick::Error> {
+        self.client()
+            .await?
+            .set_visible(None, source, visible)
+            .await?;
+        Ok(())
+    }
+
+    /// Set the text of a text source.
+    async fn set_text(&self, source: &str, text: &str) -> Result<(), runestick::Error> {
+        self.client().await?.set_text(source, text).await?;
+        Ok(())
+    }
+
+    /// Mute or unmute an audio source.
+    async fn set_mute(&self, source: &str, mute: bool) -> Result<(), runestick::Error> {
+        self.client().await?.set_mute(source, mute).await?;
+        Ok(())
+    }
+}
+
+/// Access to the stream currency from scripts.
+#[derive(Clone, Any)]
+struct Currency {
+    channel: Arc<String>,
+    currency: injector::Var<Option<currency::Currency>>,
+}
+
+impl Currency {
+    /// Get the currency, if it's configured.
+    async fn currency(&self) -> Result<currency::Currency> {
+        match self.currency.load().await {
+            Some(currency) => Ok(currency),
+            None => bail!("No currency is configured"),
+        }
+    }
+
+    /// Get the name of the currency.
+    async fn name(&self) -> Result<String, runestick::Error> {
+        Ok(self.currency().await?.name.to_string())
+    }
+
+    /// Get the balance of the given user.
+    async fn balance_of(&self, user: &str) -> Result<i64, runestick::Error> {
+        let currency = self.currency().await?;
+        let balance = currency.balance_of(&self.channel, user).await?;
+        Ok(balance.map(|b| b.balance).unwrap_or_default())
+    }
+
+    /// Add to the balance of the given user, or remove from it if the amount
+    /// is negative.
+    async fn balance_add(&self, user: &str, amount: i64) -> Result<(), runestick::Error> {
+        let currency = self.currency().await?;
+        currency.balance_add(&self.channel, user, amount).await?;
+        Ok(())
+    }
+}
+
+/// Access to counters from scripts.
+#[derive(Clone, Any)]
+struct Counters {
+    channel: Arc<String>,
+    counters: injector::Var<Option<db::Counters>>,
+}
+
+impl Counters {
+    /// Get the counters, if they're configured.
+    async fn counters(&self) -> Result<db::Counters> {
+        match self.counters.load().await {
+            Some(counters) => Ok(counters),
+            None => bail!("Counters are not configured"),
+        }
+    }
+
+    /// Get the value of the given counter, if it exists.
+    async fn get(&self, name: &str) -> Result<Option<i64>, runestick::Error> {
+        Ok(self.counters().await?.get(&self.channel, name).await)
+    }
+
+    /// Add to the value of the given counter, or remove from it if the
+    /// amount is negative.
+    ///
+    /// Returns the new value, if the counter exists.
+    async fn add(&self, name: &str, amount: i64) -> Result<Option<i64>, runestick::Error> {
+        let counters = self.counters().await?;
+        Ok(counters.add(&self.channel, name, amount, None).await?)
+    }
+
+    /// Set the value of the given counter.
+    ///
+    /// Returns the new value, if the counter exists.
+    async fn set(&self, name: &str, count: i64) -> Result<Option<i64>, runestick::Error> {
+        let counters = self.counters().await?;
+        Ok(counters.set(&self.channel, name, count, None).await?)
+    }
+}
+
+/// Restricted HTTP access from scripts, which can only GET documents.
+#[derive(Clone, Any)]
+struct Http {
+    client: reqwest::Client,
+}
+
+impl Http {
+    /// Get the document at the given URL as a string.
+    async fn get(&self, url: &str) -> Result<String, runestick::Error> {
+        let url = str::parse::<reqwest::Url>(url).map_err(anyhow::Error::from)?;
+
+        match url.scheme() {
+            "http" | "https" => (),
+            scheme => return Err(anyhow!("Unsupported scheme: {}", scheme).into()),
+        }
+
+        let mut response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Bad response: {}", response.status()).into());
+        }
+
+        let mut body = Vec::new();
+
+        while let Some(chunk) = response.chunk().await.map_err(anyhow::Error::from)? {
+            if body.len() + chunk.len() > HTTP_MAX_BODY {
+                return Err(anyhow!("Response is larger than {} bytes", HTTP_MAX_BODY).into());
+            }
+
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+}
+
+/// Read-only access to settings from scripts.
+///
+/// Secret settings, like tokens, can't be read.
+#[derive(Clone, Any)]
+struct Settings {
+    settings: settings::Settings,
+}
+
+impl Settings {
+    /// Get the value of the given setting, falling back to its default.
+    async fn get<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let default = match self.settings.lookup(key) {
+            Some(schema) if !schema.secret => schema.default.clone(),
+            Some(..) => bail!("Setting `{}` is secret", key),
+            None => bail!("No such setting `{}`", key),
+        };
+
+        if let Some(value) = self.settings.get::<T>(key).await? {
+            return Ok(Some(value));
+        }
+
+        Ok(default.map(serde_json::from_value::<T>).transpose()?)
+    }
+
+    /// Get a boolean setting.
+    async fn get_bool(&self, key: &str) -> Result<Option<bool>, runestick::Error> {
+        Ok(self.get(key).await?)
+    }
+
+    /// Get a number setting.
+    async fn get_number(&self, key: &str) -> Result<Option<i64>, runestick::Error> {
+        Ok(self.get(key).await?)
+    }
+
+    /// Get a string setting.
+    async fn get_string(&self, key: &str) -> Result<Option<String>, runestick::Error> {
+        Ok(self.get(key).await?)
+    }
+}
+
 pub(crate) struct Handler {
     db: Db,
+    obs: Obs,
+    currency: Currency,
+    counters: Counters,
+    http: Http,
+    settings: Settings,
     handler: Arc<InternalHandler>,
 }
 
@@ -110,6 +336,11 @@ impl Handler {
         let ctx = Ctx {
             ctx,
             db: self.db.scoped(&self.handler.name),
+            obs: self.obs,
+            currency: self.currency,
+            counters: self.counters,
+            http: self.http,
+            settings: self.settings,
         };
 
         let result: Result<(), ConstValue> =
@@ -142,6 +373,11 @@ pub(crate) struct Scripts {
     context: Arc<Context>,
     options: rune::Options,
     db: Db,
+    obs: Obs,
+    currency: Currency,
+    counters: Counters,
+    http: Http,
+    settings: Settings,
     handlers: HashMap<String, Arc<InternalHandler>>,
     // Keeps track of commands by path so that they may be unregistered.
     handlers_by_path: HashMap<PathBuf, Vec<String>>,
@@ -149,11 +385,37 @@ pub(crate) struct Scripts {
 
 impl Scripts {
     /// Construct a new script handler.
-    async fn new(channel: String, db: db::Database) -> Result<Self> {
+    async fn new(
+        channel: String,
+        db: db::Database,
+        obs: injector::Var<Option<api::Obs>>,
+        currency: injector::Var<Option<currency::Currency>>,
+        counters: injector::Var<Option<db::Counters>>,
+        settings: settings::Settings,
+    ) -> Result<Self> {
+        let currency = Currency {
+            channel: Arc::new(channel.clone()),
+            currency,
+        };
+
+        let counters = Counters {
+            channel: currency.channel.clone(),
+            counters,
+        };
+
+        let http = Http {
+            client: reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?,
+        };
+
         Ok(Self {
             context: Self::context()?,
             options: Default::default(),
             db: Db::open(channel, db).await?,
+            obs: Obs { obs },
+            currency,
+            counters,
+            http,
+            settings: Settings { settings },
             handlers: HashMap::new(),
             handlers_by_path: HashMap::new(),
         })
@@ -165,6 +427,11 @@ impl Scripts {
 
         Some(Handler {
             db: self.db.clone(),
+            obs: self.obs.clone(),
+            currency: self.currency.clone(),
+            counters: self.counters.clone(),
+            http: self.http.clone(),
+            settings: self.settings.clone(),
             handler,
         })
     }
@@ -250,8 +517,15 @@ impl Scripts {
     }
 
     /// Construct a new context.
+    ///
+    /// NB: `rune_modules::http` isn't installed, since it gives scripts a
+    /// full HTTP client. Scripts use the GET-only `ctx.http` instead.
     fn context() -> Result<Arc<Context>, ContextError> {
-        let mut ctx = rune_modules::with_config(false)?;
+        let mut ctx = Context::with_config(false)?;
+        ctx.install(&rune_modules::json::module(false)?)?;
+        ctx.install(&rune_modules::toml::module(false)?)?;
+        ctx.install(&rune_modules::time::module(false)?)?;
+        ctx.install(&rune_modules::rand::module(false)?)?;
         ctx.install(&Self::oxi_mod()?)?;
         ctx.install(&self::io::module()?)?;
         Ok(Arc::new(ctx))
@@ -265,6 +539,11 @@ impl Scripts {
         m.async_inst_fn("privmsg", Ctx::privmsg)?;
         m.inst_fn("user", Ctx::user)?;
         m.getter("db", Ctx::db)?;
+        m.getter("obs", Ctx::obs)?;
+        m.getter("currency", Ctx::currency)?;
+        m.getter("counters", Ctx::counters)?;
+        m.getter("http", Ctx::http)?;
+        m.getter("settings", Ctx::settings)?;
 
         m.ty::<Registry>()?;
         m.inst_fn("register", Registry::register)?;
@@ -275,6 +554,30 @@ impl Scripts {
         m.async_inst_fn(runestick::INDEX_GET, ScopedDb::get)?;
         m.async_inst_fn("get", ScopedDb::get)?;
 
+        m.ty::<Obs>()?;
+        m.async_inst_fn("set_scene", Obs::set_scene)?;
+        m.async_inst_fn("set_visible", Obs::set_visible)?;
+        m.async_inst_fn("set_text", Obs::set_text)?;
+        m.async_inst_fn("set_mute", Obs::set_mute)?;
+
+        m.ty::<Currency>()?;
+        m.async_inst_fn("name", Currency::name)?;
+        m.async_inst_fn("balance_of", Currency::balance_of)?;
+        m.async_inst_fn("balance_add", Currency::balance_add)?;
+
+        m.ty::<Counters>()?;
+        m.async_inst_fn("get", Counters::get)?;
+        m.async_inst_fn("add", Counters::add)?;
+        m.async_inst_fn("set", Counters::set)?;
+
+        m.ty::<Http>()?;
+        m.async_inst_fn("get", Http::get)?;
+
+        m.ty::<Settings>()?;
+        m.async_inst_fn("get_bool", Settings::get_bool)?;
+        m.async_inst_fn("get_number", Settings::get_number)?;
+        m.async_inst_fn("get_string", Settings::get_string)?;
+
         Ok(m)
     }
 }
@@ -302,6 +605,11 @@ impl Registry {
 struct Ctx {
     ctx: command::Context,
     db: ScopedDb,
+    obs: Obs,
+    currency: Currency,
+    counters: Counters,
+    http: Http,
+    settings: Settings,
 }
 
 impl Ctx {
@@ -310,6 +618,31 @@ impl Ctx {
         self.db.clone()
     }
 
+    /// Access OBS, to for example switch scenes.
+    fn obs(&self) -> Obs {
+        self.obs.clone()
+    }
+
+    /// Access the stream currency.
+    fn currency(&self) -> Currency {
+        self.currency.clone()
+    }
+
+    /// Access counters, like deaths or wins.
+    fn counters(&self) -> Counters {
+        self.counters.clone()
+    }
+
+    /// Access HTTP, which can only be used to GET documents.
+    fn http(&self) -> Http {
+        self.http.clone()
+    }
+
+    /// Access settings, which are read-only.
+    fn settings(&self) -> Settings {
+        self.settings.clone()
+    }
+
     /// Get the user name, if present.
     fn user(&self) -> Option<String> {
         self.ctx.user.name().map(|s| s.to_owned())
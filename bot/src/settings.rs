@@ -1,6 +1,7 @@
 //! Utilities for dealing with dynamic configuration and settings.
 
 use crate::auth::Scope;
+use crate::crypt;
 use crate::db;
 use crate::prelude::*;
 use crate::utils;
@@ -8,7 +9,7 @@ use chrono_tz::Tz;
 use diesel::prelude::*;
 use futures::ready;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error;
 use std::fmt;
 use std::marker;
@@ -23,6 +24,10 @@ pub use crate::injector::Var;
 
 const SEPARATOR: char = '/';
 
+/// Prefix of settings which are always treated as secrets, and which are
+/// encrypted when stored.
+const SECRET_PREFIX: &str = "secrets/";
+
 type EventSender = mpsc::UnboundedSender<Event<serde_json::Value>>;
 type Subscriptions = Arc<RwLock<HashMap<String, Vec<EventSender>>>>;
 
@@ -67,6 +72,12 @@ pub enum Error {
     BadBoolean(std::str::ParseBoolError),
     /// Background task failed.
     TaskError(JoinError),
+    /// The schema doesn't declare a default value for the given key.
+    MissingDefault(String),
+    /// The default value declared in the schema is not compatible with its type.
+    IncompatibleDefault(String),
+    /// The value doesn't validate against the schema of the setting.
+    Invalid(Vec<Invalid>),
 }
 
 impl fmt::Display for Error {
@@ -100,6 +111,25 @@ impl fmt::Display for Error {
             Self::FailedToLoadSchema(ref e) => write!(fmt, "Failed to load settings.yaml: {}", e),
             Self::BadBoolean(ref e) => write!(fmt, "Bad boolean value: {}", e),
             Self::TaskError(..) => write!(fmt, "Task failed"),
+            Self::MissingDefault(ref key) => write!(fmt, "No default value for: {}", key),
+            Self::IncompatibleDefault(ref key) => {
+                write!(fmt, "Default value is not compatible with type of: {}", key)
+            }
+            Self::Invalid(ref errors) => {
+                write!(fmt, "Invalid value: ")?;
+
+                let mut it = errors.iter().peekable();
+
+                while let Some(e) = it.next() {
+                    e.fmt(fmt)?;
+
+                    if it.peek().is_some() {
+                        write!(fmt, ", ")?;
+                    }
+                }
+
+                Ok(())
+            }
         }
     }
 }
@@ -153,6 +183,28 @@ pub struct Setting {
     pub schema: SchemaType,
     pub key: String,
     pub value: serde_json::Value,
+    /// If the value is the default declared in the schema, since no value has
+    /// been stored.
+    pub is_default: bool,
+}
+
+impl Setting {
+    /// Test if the setting holds a secret, which must never be exported or
+    /// shown.
+    pub fn is_secret(&self) -> bool {
+        self.schema.secret || is_encrypted_key(&self.key)
+    }
+}
+
+/// The outcome of importing settings.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct Import {
+    /// Settings which were stored.
+    pub imported: Vec<String>,
+    /// Settings which were ignored, since they are secret or unknown.
+    pub ignored: Vec<String>,
+    /// Settings which failed to validate, in which case nothing is stored.
+    pub errors: BTreeMap<String, Vec<Invalid>>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -165,13 +217,19 @@ pub struct SettingRef<'a, T> {
 impl SettingRef<'_, serde_json::Value> {
     /// Convert into an owned value.
     pub fn to_setting(&self) -> Setting {
+        let (value, is_default) = match self.value.clone() {
+            Some(value) => (value, false),
+            None => match self.schema.default.clone() {
+                Some(default) => (default, true),
+                None => (serde_json::Value::Null, false),
+            },
+        };
+
         Setting {
             schema: self.schema.clone(),
             key: self.key.to_string(),
-            value: match self.value.clone() {
-                None => serde_json::Value::Null,
-                Some(value) => value,
-            },
+            value,
+            is_default,
         }
     }
 }
@@ -195,6 +253,84 @@ pub struct SchemaType {
     /// A human-readable title for the setting.
     #[serde(default)]
     pub title: Option<String>,
+    /// The value used when nothing has been stored for the setting.
+    #[serde(default)]
+    pub default: Option<serde_json::Value>,
+}
+
+impl SchemaType {
+    /// Get the value to report for a setting, falling back to the default
+    /// value if nothing has been stored.
+    ///
+    /// Returns `None` if the setting shouldn't be reported at all.
+    fn effective_value(
+        &self,
+        stored: Option<&String>,
+    ) -> Result<Option<(serde_json::Value, bool)>, Error> {
+        if let Some(value) = stored {
+            return Ok(Some((serde_json::from_str(value)?, false)));
+        }
+
+        if let Some(default) = &self.default {
+            return Ok(Some((default.clone(), true)));
+        }
+
+        if self.ty.optional {
+            return Ok(Some((serde_json::Value::Null, false)));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Test if the setting with the given key is encrypted when stored.
+fn is_encrypted_key(key: &str) -> bool {
+    key.starts_with(SECRET_PREFIX)
+}
+
+/// Load all stored values, decrypting the ones which are encrypted.
+///
+/// Values which can't be decrypted are left out, as if they were never set.
+fn load_values(c: &db::Connection, key: &crypt::Key) -> Result<HashMap<String, String>, Error> {
+    use self::db::schema::settings::dsl;
+
+    let mut values = HashMap::new();
+
+    for (k, value) in dsl::settings
+        .select((dsl::key, dsl::value))
+        .load::<(String, String)>(c)?
+    {
+        match key.decrypt(&value) {
+            Ok(value) => {
+                values.insert(k, value.into_owned());
+            }
+            Err(e) => {
+                log::warn!("bad value for key: {}: {}", k, e);
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+/// Store the serialized value of a setting, replacing any existing value.
+///
+/// Callers must do this in a transaction, since the value is updated before
+/// it's inserted.
+fn store_value(c: &db::Connection, key: &str, json: &str) -> Result<(), Error> {
+    use self::db::schema::settings::dsl;
+
+    let updated = diesel::update(dsl::settings.filter(dsl::key.eq(key)))
+        .set(dsl::value.eq(json))
+        .execute(c)?;
+
+    if updated == 0 {
+        diesel::insert_into(dsl::settings)
+            .values((dsl::key.eq(key), dsl::value.eq(json)))
+            .execute(c)?;
+    }
+
+    Ok(())
 }
 
 const SCHEMA: &[u8] = include_bytes!("settings.yaml");
@@ -249,7 +385,17 @@ pub struct Migration {
 impl Schema {
     /// Load schema from the given set of bytes.
     pub fn load_static() -> Result<Schema, Error> {
-        serde_yaml::from_slice(SCHEMA).map_err(Error::FailedToLoadSchema)
+        let schema: Schema = serde_yaml::from_slice(SCHEMA).map_err(Error::FailedToLoadSchema)?;
+
+        for (key, ty) in &schema.types {
+            if let Some(default) = &ty.default {
+                if !ty.ty.is_compatible_with_json(default) {
+                    return Err(Error::IncompatibleDefault(key.to_string()));
+                }
+            }
+        }
+
+        Ok(schema)
     }
 
     /// Lookup the given type by key.
@@ -257,6 +403,13 @@ impl Schema {
         self.types.get(key).cloned()
     }
 
+    /// Register a type which is generated at runtime.
+    ///
+    /// Types declared in `settings.yaml` take precedence.
+    pub fn insert(&mut self, key: String, ty: SchemaType) {
+        self.types.entry(key).or_insert(ty);
+    }
+
     /// Test if schema contains the given key.
     pub fn contains(&self, key: &str) -> bool {
         self.types.contains_key(key)
@@ -285,6 +438,8 @@ impl Future for Driver {
 
 pub struct Inner {
     db: db::Database,
+    /// Key used to encrypt secrets.
+    key: crypt::Key,
     /// Maps setting prefixes to subscriptions.
     subscriptions: Subscriptions,
     /// Schema for every corresponding type.
@@ -305,7 +460,7 @@ pub struct Settings {
 }
 
 impl Settings {
-    pub fn new(db: db::Database, schema: Schema) -> Self {
+    pub fn new(db: db::Database, schema: Schema, key: crypt::Key) -> Self {
         let prefixes = schema.as_prefixes();
         let (drivers, drivers_rx) = mpsc::unbounded();
 
@@ -313,6 +468,7 @@ impl Settings {
             scope: String::from(""),
             inner: Arc::new(Inner {
                 db,
+                key,
                 subscriptions: Default::default(),
                 schema: Arc::new(schema),
                 prefixes: Arc::new(prefixes),
@@ -332,6 +488,47 @@ impl Settings {
             }
         }
 
+        self.encrypt_secrets().await?;
+        Ok(())
+    }
+
+    /// Encrypt secrets which were stored before encryption was introduced.
+    async fn encrypt_secrets(&self) -> Result<(), Error> {
+        use self::db::schema::settings::dsl;
+
+        let key = self.inner.key.clone();
+
+        let count = self
+            .inner
+            .db
+            .asyncify(move |c| {
+                let values = dsl::settings
+                    .select((dsl::key, dsl::value))
+                    .filter(dsl::key.like(format!("{}%", SECRET_PREFIX)))
+                    .load::<(String, String)>(c)?;
+
+                let mut count = 0;
+
+                for (k, value) in values {
+                    if crypt::Key::is_encrypted(&value) {
+                        continue;
+                    }
+
+                    diesel::update(dsl::settings.filter(dsl::key.eq(&k)))
+                        .set(dsl::value.eq(key.encrypt(&value)?))
+                        .execute(c)?;
+
+                    count += 1;
+                }
+
+                Ok::<_, Error>(count)
+            })
+            .await?;
+
+        if count > 0 {
+            log::info!("Encrypted {} secret settings", count);
+        }
+
         Ok(())
     }
 
@@ -420,8 +617,6 @@ impl Settings {
 
     /// Get a setting by prefix.
     pub async fn list_by_prefix(&self, prefix: &str) -> Result<Vec<Setting>, Error> {
-        use self::db::schema::settings::dsl;
-
         let prefix = self.key(prefix);
         let inner = self.inner.clone();
         let prefix = prefix.to_string();
@@ -436,12 +631,7 @@ impl Settings {
 
                 let mut settings = Vec::new();
 
-                let values = dsl::settings
-                    .select((dsl::key, dsl::value))
-                    .order(dsl::key)
-                    .load::<(String, String)>(c)?
-                    .into_iter()
-                    .collect::<HashMap<_, _>>();
+                let values = load_values(c, &inner.key)?;
 
                 for key in &prefix.keys {
                     let schema = match inner.schema.types.get(key) {
@@ -449,9 +639,8 @@ impl Settings {
                         None => continue,
                     };
 
-                    let value = match values.get(key) {
-                        Some(value) => serde_json::from_str(value)?,
-                        None if schema.ty.optional => serde_json::Value::Null,
+                    let (value, is_default) = match schema.effective_value(values.get(key))? {
+                        Some(value) => value,
                         None => continue,
                     };
 
@@ -459,6 +648,7 @@ impl Settings {
                         schema: schema.clone(),
                         key: key.to_string(),
                         value,
+                        is_default,
                     });
                 }
 
@@ -500,6 +690,30 @@ impl Settings {
         self.inner_get(&key).await
     }
 
+    /// Get the value of the given key from the database, falling back to the
+    /// default value declared in the schema.
+    pub async fn get_or_default<T>(&self, key: &str) -> Result<T, Error>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let key = self.key(key);
+
+        if let Some(value) = self.inner_get(&key).await? {
+            return Ok(value);
+        }
+
+        match self
+            .inner
+            .schema
+            .types
+            .get(key.as_ref())
+            .and_then(|s| s.default.clone())
+        {
+            Some(default) => Ok(serde_json::from_value(default)?),
+            None => Err(Error::MissingDefault(key.to_string())),
+        }
+    }
+
     /// Insert the given setting without sending an update notification to other components.
     pub async fn set_silent<T>(&self, key: &str, value: T) -> Result<(), Error>
     where
@@ -519,8 +733,17 @@ impl Settings {
     }
 
     /// Insert the given setting as raw JSON.
+    ///
+    /// The value is validated against the schema before it's stored.
     pub async fn set_json(&self, key: &str, value: serde_json::Value) -> Result<(), Error> {
         let key = self.key(key);
+
+        let ty = match self.inner.schema.types.get(key.as_ref()) {
+            Some(schema) => &schema.ty,
+            None => return Err(Error::NoTargetForSchema(key.to_string())),
+        };
+
+        ty.validate(&value).map_err(Error::Invalid)?;
         self.inner_set_json(key.as_ref(), value, true).await
     }
 
@@ -531,41 +754,24 @@ impl Settings {
         value: serde_json::Value,
         notify: bool,
     ) -> Result<(), Error> {
-        use self::db::schema::settings::dsl;
-
         if log::log_enabled!(log::Level::Trace) {
             log::trace!("{}: Setting to {:?} (notify: {})", key, value, notify);
         }
 
         let key = key.to_string();
+        let crypt_key = self.inner.key.clone();
 
         let (key, value) = self
             .inner
             .db
             .asyncify(move |c| {
-                let filter = dsl::settings.filter(dsl::key.eq(&key));
+                let mut json = serde_json::to_string(&value)?;
 
-                let b = filter
-                    .clone()
-                    .select((dsl::key, dsl::value))
-                    .first::<(String, String)>(c)
-                    .optional()?;
-
-                let json = serde_json::to_string(&value)?;
-
-                match b {
-                    None => {
-                        diesel::insert_into(dsl::settings)
-                            .values((dsl::key.eq(&key), dsl::value.eq(json)))
-                            .execute(c)?;
-                    }
-                    Some(_) => {
-                        diesel::update(filter)
-                            .set((dsl::key.eq(&key), dsl::value.eq(json)))
-                            .execute(c)?;
-                    }
+                if is_encrypted_key(&key) {
+                    json = crypt_key.encrypt(&json)?;
                 }
 
+                c.transaction::<_, Error, _>(|| store_value(c, &key, &json))?;
                 Ok::<_, Error>((key, value))
             })
             .await?;
@@ -579,8 +785,6 @@ impl Settings {
 
     /// Insert the given setting.
     pub async fn list(&self) -> Result<Vec<Setting>, Error> {
-        use self::db::schema::settings::dsl;
-
         let inner = self.inner.clone();
 
         self.inner
@@ -588,17 +792,11 @@ impl Settings {
             .asyncify(move |c| {
                 let mut settings = Vec::new();
 
-                let values = dsl::settings
-                    .select((dsl::key, dsl::value))
-                    .order(dsl::key)
-                    .load::<(String, String)>(c)?
-                    .into_iter()
-                    .collect::<HashMap<_, _>>();
+                let values = load_values(c, &inner.key)?;
 
                 for (key, schema) in &inner.schema.types {
-                    let value = match values.get(key) {
-                        Some(value) => serde_json::from_str(value)?,
-                        None if schema.ty.optional => serde_json::Value::Null,
+                    let (value, is_default) = match schema.effective_value(values.get(key))? {
+                        Some(value) => value,
                         None => continue,
                     };
 
@@ -606,6 +804,7 @@ impl Settings {
                         schema: schema.clone(),
                         key: key.to_string(),
                         value,
+                        is_default,
                     });
                 }
 
@@ -614,6 +813,100 @@ impl Settings {
             .await
     }
 
+    /// Export all settings which have been set, except for secrets.
+    ///
+    /// Settings which use their default value are not included.
+    pub async fn export(&self) -> Result<BTreeMap<String, serde_json::Value>, Error> {
+        let mut out = BTreeMap::new();
+
+        for setting in self.list().await? {
+            if setting.is_default || setting.value.is_null() || setting.is_secret() {
+                continue;
+            }
+
+            out.insert(setting.key, setting.value);
+        }
+
+        Ok(out)
+    }
+
+    /// Snapshot the current value of every setting, except for secrets.
+    ///
+    /// Unlike [Settings::export], settings which use their default value or
+    /// which are unset are included, so importing the snapshot restores them
+    /// exactly.
+    pub async fn snapshot(&self) -> Result<BTreeMap<String, serde_json::Value>, Error> {
+        let mut out = BTreeMap::new();
+
+        for setting in self.list().await? {
+            if setting.is_secret() {
+                continue;
+            }
+
+            out.insert(setting.key, setting.value);
+        }
+
+        Ok(out)
+    }
+
+    /// Import settings, like the ones produced by [Settings::export].
+    ///
+    /// All values are validated before anything is stored, and they are
+    /// stored in a single transaction, so an import either applies completely
+    /// or not at all. Secrets and unknown settings are ignored.
+    pub async fn import(
+        &self,
+        values: BTreeMap<String, serde_json::Value>,
+    ) -> Result<Import, Error> {
+        let mut import = Import::default();
+        let mut valid = Vec::new();
+
+        for (key, value) in values {
+            let schema = match self.lookup(&key) {
+                Some(schema) if !schema.secret && !key.starts_with(SECRET_PREFIX) => schema,
+                _ => {
+                    import.ignored.push(key);
+                    continue;
+                }
+            };
+
+            if let Err(errors) = schema.ty.validate(&value) {
+                import.errors.insert(key, errors);
+                continue;
+            }
+
+            valid.push((self.key(&key).to_string(), value));
+            import.imported.push(key);
+        }
+
+        if !import.errors.is_empty() {
+            import.imported.clear();
+            return Ok(import);
+        }
+
+        let valid = self
+            .inner
+            .db
+            .asyncify(move |c| {
+                c.transaction::<_, Error, _>(|| {
+                    for (key, value) in &valid {
+                        store_value(c, key, &serde_json::to_string(value)?)?;
+                    }
+
+                    Ok(())
+                })?;
+
+                Ok::<_, Error>(valid)
+            })
+            .await?;
+
+        for (key, value) in valid {
+            self.try_send(&key, Event::Set(value)).await;
+        }
+
+        Ok(import)
+    }
+
     /// Clear the given setting. Returning `true` if it was removed.
     pub async fn clear(&self, key: &str) -> Result<bool, Error> {
         let key = self.key(key);
@@ -787,6 +1080,17 @@ impl Settings {
             })
             .await?;
 
+        let result = match result {
+            Some(value) => match self.inner.key.decrypt(&value) {
+                Ok(value) => Some(value.into_owned()),
+                Err(e) => {
+                    log::warn!("bad value for key: {}: {}", key, e);
+                    None
+                }
+            },
+            None => None,
+        };
+
         let value = match result {
             Some(value) => match serde_json::from_str::<Option<T>>(&value) {
                 Ok(value) => value,
@@ -1067,13 +1371,53 @@ where
 pub enum Format {
     /// mysql://<user>:<password>@<host>/<database>
     #[serde(rename = "regex")]
-    Regex { pattern: String },
+    Regex { pattern: Pattern },
     #[serde(rename = "time-zone")]
     TimeZone,
     #[serde(rename = "none")]
     None,
 }
 
+/// A regular expression used to validate strings.
+///
+/// It's compiled once when the schema is loaded, so that a bad pattern is
+/// caught early and validating values doesn't have to compile it again.
+#[derive(Debug, Clone)]
+pub struct Pattern(regex::Regex);
+
+impl Pattern {
+    /// Test if the pattern matches the given string.
+    pub fn is_match(&self, s: &str) -> bool {
+        self.0.is_match(s)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.as_str().fmt(fmt)
+    }
+}
+
+impl serde::Serialize for Pattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Pattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        let pattern = regex::Regex::new(&pattern).map_err(serde::de::Error::custom)?;
+        Ok(Pattern(pattern))
+    }
+}
+
 impl Default for Format {
     fn default() -> Self {
         Format::None
@@ -1122,7 +1466,14 @@ pub enum Kind {
     #[serde(rename = "raw")]
     Raw,
     #[serde(rename = "duration")]
-    Duration,
+    Duration {
+        /// The shortest allowed duration.
+        #[serde(default)]
+        min: Option<utils::Duration>,
+        /// The longest allowed duration.
+        #[serde(default)]
+        max: Option<utils::Duration>,
+    },
     #[serde(rename = "bool")]
     Bool,
     #[serde(rename = "number")]
@@ -1176,7 +1527,7 @@ impl Type {
 
         let value = match self.kind {
             Raw => serde_json::from_str(s)?,
-            Duration => {
+            Duration { .. } => {
                 let d = str::parse::<utils::Duration>(s)?;
                 Value::String(d.to_string())
             }
@@ -1251,51 +1602,144 @@ impl Type {
             }
         };
 
+        self.validate(&value).map_err(Error::Invalid)?;
         Ok(value)
     }
 
     /// Test if JSON value is compatible with the current type.
     pub fn is_compatible_with_json(&self, other: &serde_json::Value) -> bool {
+        self.validate(other).is_ok()
+    }
+
+    /// Validate the given JSON value against the current type.
+    ///
+    /// All problems found are reported, so that they can be presented at
+    /// once. Messages never include the value itself, since it might be a
+    /// secret.
+    pub fn validate(&self, value: &serde_json::Value) -> Result<(), Vec<Invalid>> {
+        let mut errors = Vec::new();
+        self.validate_at(value, "", &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate the value found at the given path.
+    fn validate_at(&self, value: &serde_json::Value, path: &str, errors: &mut Vec<Invalid>) {
         use self::Kind::*;
         use serde_json::Value;
 
-        if self.optional && *other == Value::Null {
-            return true;
+        if self.optional && *value == Value::Null {
+            return;
         }
 
-        match (&self.kind, other) {
-            (Raw, _) => true,
-            (Duration, Value::String(ref s)) => str::parse::<utils::Duration>(s).is_ok(),
-            (Bool, Value::Bool(..)) => true,
-            (Number, Value::Number(..)) => true,
-            (Percentage, Value::Number(..)) => true,
-            (String { .. }, Value::String(..)) => true,
-            (Text, Value::String(..)) => true,
-            (Set { ref value }, Value::Array(ref values)) => {
-                values.iter().all(|v| value.is_compatible_with_json(v))
+        let message = match (&self.kind, value) {
+            (Raw, _) => return,
+            (Duration { min, max }, Value::String(s)) => {
+                let d = match str::parse::<utils::Duration>(s) {
+                    Ok(d) => d,
+                    Err(e) => {
+                        errors.push(Invalid::new(path, format!("bad duration: {}", e)));
+                        return;
+                    }
+                };
+
+                match (min, max) {
+                    (Some(min), _) if d < *min => format!("must be at least {}", min),
+                    (_, Some(max)) if d > *max => format!("must be at most {}", max),
+                    _ => return,
+                }
             }
+            (Bool, Value::Bool(..)) => return,
+            (Number, Value::Number(..)) => return,
+            (Percentage, Value::Number(..)) => return,
             (
-                Select {
-                    ref value,
-                    ref options,
+                String {
+                    format: Format::Regex { pattern },
                     ..
                 },
-                json,
-            ) => {
-                if !value.is_compatible_with_json(json) {
-                    return false;
+                Value::String(s),
+            ) if pattern.is_match(s) => return,
+            (
+                String {
+                    format: Format::Regex { pattern },
+                    ..
+                },
+                Value::String(..),
+            ) => format!("must match the pattern `{}`", pattern),
+            (String { .. }, Value::String(..)) => return,
+            (Text, Value::String(..)) => return,
+            (TimeZone, Value::String(s)) => match str::parse::<Tz>(s) {
+                Ok(..) => return,
+                Err(e) => format!("bad time zone: {}", e),
+            },
+            (Set { value }, Value::Array(values)) => {
+                for (index, v) in values.iter().enumerate() {
+                    value.validate_at(v, &format!("{}[{}]", path, index), errors);
                 }
 
-                options.iter().any(|opt| opt.value == *json)
+                return;
             }
-            (Object { ref fields, .. }, Value::Object(ref object)) => {
-                // NB: check that all fields match expected schema.
-                fields.iter().all(|f| match object.get(&f.field) {
-                    Some(field) => f.ty.is_compatible_with_json(field),
-                    None => f.ty.optional,
-                })
+            (Select { value, options, .. }, json) => {
+                if !value.is_compatible_with_json(json) {
+                    format!("expected {}", value)
+                } else if options.iter().any(|o| o.value == *json) {
+                    return;
+                } else {
+                    let options = options.iter().map(|o| o.title.as_str()).collect::<Vec<_>>();
+
+                    format!("expected one of: {}", options.join(", "))
+                }
+            }
+            (Object { fields }, Value::Object(object)) => {
+                for f in fields {
+                    let path = format!("{}.{}", path, f.field);
+
+                    match object.get(&f.field) {
+                        Some(field) => f.ty.validate_at(field, &path, errors),
+                        None if f.ty.optional => (),
+                        None => errors.push(Invalid::new(&path, "missing required field")),
+                    }
+                }
+
+                return;
             }
-            _ => false,
+            _ => format!("expected {}", self),
+        };
+
+        errors.push(Invalid::new(path, message));
+    }
+}
+
+/// Describes why a value is not valid for a type.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Invalid {
+    /// Path to the invalid part of the value, like `[1]` or `.name`.
+    ///
+    /// Empty if the value itself is invalid.
+    pub path: String,
+    /// What's wrong with the value.
+    pub message: String,
+}
+
+impl Invalid {
+    fn new(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            self.message.fmt(fmt)
+        } else {
+            write!(fmt, "{}: {}", self.path, self.message)
         }
     }
 }
@@ -1306,7 +1750,7 @@ impl fmt::Display for Type {
 
         match &self.kind {
             Raw => write!(fmt, "any")?,
-            Duration => write!(fmt, "duration")?,
+            Duration { .. } => write!(fmt, "duration")?,
             Bool => write!(fmt, "bool")?,
             Number => write!(fmt, "number")?,
             Percentage => write!(fmt, "percentage")?,
@@ -1333,3 +1777,61 @@ impl fmt::Display for Type {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Type;
+    use serde_json::json;
+
+    fn parse(yaml: &str) -> Type {
+        serde_yaml::from_str(yaml).expect("type to parse")
+    }
+
+    #[test]
+    fn test_validate_duration_bounds() {
+        let ty = parse("{id: duration, min: 1s, max: 1h}");
+        assert!(ty.validate(&json!("30m")).is_ok());
+        assert!(ty.validate(&json!("0s")).is_err());
+        assert!(ty.validate(&json!("2h")).is_err());
+        assert!(ty.validate(&json!("not a duration")).is_err());
+    }
+
+    #[test]
+    fn test_validate_regex() {
+        let ty = parse(r#"{id: string, format: {type: "regex", pattern: "^mysql://"}}"#);
+        assert!(ty.validate(&json!("mysql://localhost/db")).is_ok());
+        assert!(ty.validate(&json!("postgres://localhost/db")).is_err());
+
+        let ty =
+            serde_yaml::from_str::<Type>(r#"{id: string, format: {type: "regex", pattern: "("}}"#);
+        assert!(ty.is_err());
+    }
+
+    #[test]
+    fn test_validate_paths() {
+        let ty = parse("{id: set, value: {id: string}}");
+        let errors = ty.validate(&json!(["a", 1, "b", false])).unwrap_err();
+        let paths = errors.iter().map(|e| e.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["[1]", "[3]"], paths);
+
+        let ty = parse(
+            "{id: object, fields: [{title: Name, field: name, type: {id: string}}, \
+             {title: Cooldown, field: cooldown, type: {id: duration, optional: true}}]}",
+        );
+        assert!(ty.validate(&json!({"name": "foo"})).is_ok());
+        let errors = ty.validate(&json!({"cooldown": 42})).unwrap_err();
+        let paths = errors.iter().map(|e| e.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec![".name", ".cooldown"], paths);
+    }
+
+    #[test]
+    fn test_validate_select() {
+        let ty = parse(
+            "{id: select, value: {id: string}, options: \
+             [{title: English, value: en}, {title: Svenska, value: sv}]}",
+        );
+        assert!(ty.validate(&json!("sv")).is_ok());
+        let errors = ty.validate(&json!("de")).unwrap_err();
+        assert_eq!("expected one of: English, Svenska", errors[0].message);
+    }
+}
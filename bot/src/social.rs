@@ -0,0 +1,226 @@
+//! Posts an announcement to Twitter and Mastodon when the stream goes live.
+//!
+//! The id of the last stream announced on each service is stored in the
+//! settings, so restarting the bot during a stream doesn't announce it again.
+
+use crate::api::{self, twitter};
+use crate::injector::{self, Injector};
+use crate::prelude::*;
+use crate::stream_info;
+use crate::template::Template;
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+
+/// The default template for going live announcements.
+const DEFAULT_TEMPLATE: &str = "{{name}} is now live with {{title}}! {{url}}";
+
+/// An event which is announced on social media.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The stream started.
+    StreamStarted,
+}
+
+/// Handle used to send events to social media.
+#[derive(Clone)]
+pub struct Social {
+    tx: mpsc::UnboundedSender<Event>,
+}
+
+impl Social {
+    /// Send the given event to social media.
+    pub fn send(&self, event: Event) {
+        let _ = self.tx.unbounded_send(event);
+    }
+}
+
+/// Set up social media announcements.
+pub fn setup(
+    injector: &Injector,
+    settings: settings::Settings,
+) -> (Social, impl Future<Output = Result<()>>) {
+    let (tx, rx) = mpsc::unbounded();
+    let future = run(injector.clone(), settings, rx);
+    (Social { tx }, future)
+}
+
+async fn run(
+    injector: Injector,
+    settings: settings::Settings,
+    mut rx: mpsc::UnboundedReceiver<Event>,
+) -> Result<()> {
+    let settings = settings.scoped("social");
+
+    let enabled = settings.var("enabled", false).await?;
+    let template = settings
+        .var("template", Template::compile(DEFAULT_TEMPLATE)?)
+        .await?;
+    let channel: injector::Var<Option<stream_info::Channel>> = injector.var().await?;
+
+    let twitter = Twitter::new(settings.scoped("twitter")).await?;
+    let mastodon = Mastodon::new(settings.scoped("mastodon")).await?;
+
+    while let Some(Event::StreamStarted) = rx.next().await {
+        if !enabled.load().await {
+            continue;
+        }
+
+        let (stream, text) = {
+            let channel = channel.read().await;
+
+            let channel = match channel.as_ref() {
+                Some(channel) => channel,
+                None => continue,
+            };
+
+            let stream = match channel.stream() {
+                Some(stream) => stream,
+                None => continue,
+            };
+
+            let user = channel.user();
+            let url = format!("https://twitch.tv/{}", user.name);
+
+            let text = template.read().await.render_to_string(&Vars {
+                name: &user.display_name,
+                url: &url,
+                title: &stream.title,
+                game: stream.game_name.as_deref(),
+            });
+
+            match text {
+                Ok(text) => (stream, text),
+                Err(e) => {
+                    log_error!(e, "failed to build going live announcement");
+                    continue;
+                }
+            }
+        };
+
+        if let Err(e) = twitter.announce(&stream.id, &text).await {
+            log_error!(e, "failed to announce stream on twitter");
+        }
+
+        if let Err(e) = mastodon.announce(&stream.id, &text).await {
+            log_error!(e, "failed to announce stream on mastodon");
+        }
+    }
+
+    Ok(())
+}
+
+/// Variables available in going live announcements.
+#[derive(serde::Serialize)]
+struct Vars<'a> {
+    name: &'a str,
+    url: &'a str,
+    title: &'a str,
+    game: Option<&'a str>,
+}
+
+/// Announcements posted as tweets.
+struct Twitter {
+    settings: settings::Settings,
+    api: api::Twitter,
+    enabled: settings::Var<bool>,
+    consumer_key: settings::Var<Option<String>>,
+    consumer_secret: settings::Var<Option<String>>,
+    access_token: settings::Var<Option<String>>,
+    access_token_secret: settings::Var<Option<String>>,
+    last_stream: settings::Var<Option<String>>,
+}
+
+impl Twitter {
+    async fn new(settings: settings::Settings) -> Result<Self> {
+        Ok(Self {
+            api: api::Twitter::new()?,
+            enabled: settings.var("enabled", false).await?,
+            consumer_key: settings.optional("consumer-key").await?,
+            consumer_secret: settings.optional("consumer-secret").await?,
+            access_token: settings.optional("access-token").await?,
+            access_token_secret: settings.optional("access-token-secret").await?,
+            last_stream: settings.optional("last-stream").await?,
+            settings,
+        })
+    }
+
+    /// Announce the stream with the given id, unless it has already been
+    /// announced.
+    async fn announce(&self, stream_id: &str, text: &str) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        if self.last_stream.read().await.as_deref() == Some(stream_id) {
+            log::trace!("stream {} already announced on twitter", stream_id);
+            return Ok(());
+        }
+
+        let credentials = twitter::Credentials {
+            consumer_key: required(&self.consumer_key, "consumer-key").await?,
+            consumer_secret: required(&self.consumer_secret, "consumer-secret").await?,
+            access_token: required(&self.access_token, "access-token").await?,
+            access_token_secret: required(&self.access_token_secret, "access-token-secret").await?,
+        };
+
+        self.api.tweet(&credentials, text).await?;
+        self.settings.set("last-stream", stream_id).await?;
+        Ok(())
+    }
+}
+
+/// Announcements posted as Mastodon statuses.
+struct Mastodon {
+    settings: settings::Settings,
+    api: api::Mastodon,
+    enabled: settings::Var<bool>,
+    url: settings::Var<Option<String>>,
+    access_token: settings::Var<Option<String>>,
+    last_stream: settings::Var<Option<String>>,
+}
+
+impl Mastodon {
+    async fn new(settings: settings::Settings) -> Result<Self> {
+        Ok(Self {
+            api: api::Mastodon::new(),
+            enabled: settings.var("enabled", false).await?,
+            url: settings.optional("url").await?,
+            access_token: settings.optional("access-token").await?,
+            last_stream: settings.optional("last-stream").await?,
+            settings,
+        })
+    }
+
+    /// Announce the stream with the given id, unless it has already been
+    /// announced.
+    async fn announce(&self, stream_id: &str, text: &str) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        if self.last_stream.read().await.as_deref() == Some(stream_id) {
+            log::trace!("stream {} already announced on mastodon", stream_id);
+            return Ok(());
+        }
+
+        let url = str::parse::<Url>(&required(&self.url, "url").await?)?;
+        let access_token = required(&self.access_token, "access-token").await?;
+
+        // NB: the idempotency key protects against posting twice if we fail
+        // to store the last stream.
+        self.api
+            .post_status(&url, &access_token, text, stream_id)
+            .await?;
+
+        self.settings.set("last-stream", stream_id).await?;
+        Ok(())
+    }
+}
+
+/// Load a setting which is required to post announcements.
+async fn required(var: &settings::Var<Option<String>>, key: &str) -> Result<String> {
+    match var.load().await {
+        Some(value) if !value.is_empty() => Ok(value),
+        _ => Err(anyhow!("`{}` is not configured", key)),
+    }
+}
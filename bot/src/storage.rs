@@ -1,9 +1,18 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context as _, Result};
+use std::fs;
+use std::io;
 use std::path::Path;
 use std::sync::Arc;
 
 pub use futures_cache::{sled, Cache};
 
+/// Name of the sled database in the storage directory.
+const SLED: &str = "sled.31";
+
+/// A single exported collection: its type, name, and key-value pairs.
+type Collection = (Vec<u8>, Vec<u8>, Vec<Vec<Vec<u8>>>);
+
+#[derive(Clone)]
 pub struct Storage {
     db: Arc<sled::Db>,
 }
@@ -11,12 +20,71 @@ pub struct Storage {
 impl Storage {
     /// Open the given storage location.
     pub fn open(path: &Path) -> Result<Storage> {
-        let db = sled::open(path.join("sled.31"))?;
+        let db = sled::open(path.join(SLED))?;
         Ok(Storage { db: Arc::new(db) })
     }
 
     /// Access the cache abstraction of your storage.
     pub fn cache(&self) -> Result<Cache> {
-        Ok(Cache::load(Arc::new(self.db.open_tree("cache")?))?)
+        Ok(Cache::load(self.tree("cache")?)?)
+    }
+
+    /// Open the tree with the given name.
+    pub fn tree(&self, name: &str) -> Result<Arc<sled::Tree>> {
+        Ok(Arc::new(self.db.open_tree(name)?))
+    }
+
+    /// Export the content of every tree to the given file.
+    pub fn export(&self, file: &Path) -> Result<()> {
+        self.db.flush()?;
+
+        let export = self
+            .db
+            .export()
+            .into_iter()
+            .map(|(ty, name, values)| (ty, name, values.collect()))
+            .collect::<Vec<Collection>>();
+
+        let f = io::BufWriter::new(fs::File::create(file)?);
+        serde_cbor::to_writer(f, &export)?;
+        Ok(())
+    }
+
+    /// Restore the storage at the given location from a file written by
+    /// [export](Storage::export).
+    ///
+    /// Existing storage is moved aside by appending the given suffix to it.
+    pub fn restore(path: &Path, file: &Path, suffix: &str) -> Result<()> {
+        let f = io::BufReader::new(
+            fs::File::open(file).with_context(|| anyhow!("failed to open: {}", file.display()))?,
+        );
+
+        let export: Vec<Collection> = serde_cbor::from_reader(f)
+            .with_context(|| anyhow!("failed to read storage from: {}", file.display()))?;
+
+        let current = path.join(SLED);
+
+        if current.exists() {
+            let aside = path.join(format!("{}.{}", SLED, suffix));
+
+            if aside.exists() {
+                bail!("storage already moved aside to: {}", aside.display());
+            }
+
+            fs::rename(&current, &aside)?;
+            log::info!("Moved existing storage to: {}", aside.display());
+        }
+
+        let db = sled::open(&current)?;
+
+        db.import(
+            export
+                .into_iter()
+                .map(|(ty, name, values)| (ty, name, values.into_iter()))
+                .collect(),
+        );
+
+        db.flush()?;
+        Ok(())
     }
 }
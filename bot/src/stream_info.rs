@@ -2,10 +2,12 @@ use crate::api;
 use crate::api::twitch;
 use crate::prelude::*;
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use std::collections::HashSet;
 use std::sync::Arc;
 use std::time;
+use tokio::sync::Notify;
 
 #[derive(Debug, Default)]
 pub struct Data {
@@ -26,9 +28,33 @@ pub enum StreamState {
 pub struct StreamInfo {
     pub user: Arc<twitch::User>,
     pub data: Arc<RwLock<Data>>,
+    resync: Arc<Notify>,
 }
 
 impl StreamInfo {
+    /// Refresh all stream information as soon as possible, like after
+    /// reconnecting to chat.
+    pub fn resync(&self) {
+        self.resync.notify();
+    }
+
+    /// When the current stream started, if we are live.
+    pub fn started_at(&self) -> Option<DateTime<Utc>> {
+        let data = self.data.read();
+        data.stream.as_ref().map(|s| s.started_at)
+    }
+
+    /// Test if we are live.
+    pub fn is_live(&self) -> bool {
+        self.data.read().stream.is_some()
+    }
+
+    /// The id of the current stream, if we are live.
+    pub fn stream_id(&self) -> Option<String> {
+        let data = self.data.read();
+        data.stream.as_ref().map(|s| s.id.clone())
+    }
+
     /// Check if a name is a subscriber.
     pub fn is_subscriber(&self, name: &str) -> bool {
         self.data.read().subs_set.contains(name)
@@ -64,7 +90,11 @@ impl StreamInfo {
         streamer: &'a twitch::User,
     ) -> Result<()> {
         let channel = match twitch.channel_by_id(&streamer.id).await {
-            Ok(channel) => channel,
+            Ok(Some(channel)) => channel,
+            Ok(None) => {
+                log::warn!("no channel information for streamer: {}", streamer.name);
+                return Ok(());
+            }
             Err(e) => {
                 log_error!(e, "failed to refresh channel");
                 return Ok(());
@@ -72,8 +102,8 @@ impl StreamInfo {
         };
 
         let mut info = self.data.write();
-        info.title = channel.status;
-        info.game = channel.game;
+        info.title = channel.title;
+        info.game = channel.game_name;
         Ok(())
     }
 
@@ -100,6 +130,10 @@ impl StreamInfo {
             _ => None,
         };
 
+        // NB: update the stream before notifying, so that it can be inspected
+        // by anyone receiving the update.
+        self.data.write().stream = stream;
+
         if let Some(update) = update {
             stream_state_tx
                 .send(update)
@@ -107,12 +141,141 @@ impl StreamInfo {
                 .map_err(|_| anyhow!("failed to send stream state update"))?;
         }
 
-        let mut info = self.data.write();
-        info.stream = stream;
         Ok(())
     }
 }
 
+/// Information on the channel of the streamer which can be changed, like its
+/// title and game.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ChannelInfo {
+    pub title: Option<String>,
+    pub game: Option<String>,
+}
+
+/// Reads and modifies the channel of the streamer.
+///
+/// Changes are made through the streamer token, and are immediately reflected
+/// in the stream info.
+#[derive(Debug, Clone)]
+pub struct Channel {
+    twitch: api::Twitch,
+    stream_info: StreamInfo,
+}
+
+impl Channel {
+    /// Construct access to the channel of the streamer.
+    pub fn new(twitch: api::Twitch, stream_info: StreamInfo) -> Self {
+        Self {
+            twitch,
+            stream_info,
+        }
+    }
+
+    /// Get the current title and game of the channel.
+    pub fn info(&self) -> ChannelInfo {
+        let data = self.stream_info.data.read();
+
+        ChannelInfo {
+            title: data.title.clone(),
+            game: data.game.clone(),
+        }
+    }
+
+    /// The user of the streamer.
+    pub fn user(&self) -> &twitch::User {
+        &self.stream_info.user
+    }
+
+    /// Get the current stream, if we are live.
+    pub fn stream(&self) -> Option<twitch::Stream> {
+        self.stream_info.data.read().stream.clone()
+    }
+
+    /// Look up the game with the given id.
+    pub async fn game_by_id(&self, id: &str) -> Result<Option<twitch::Game>> {
+        self.twitch.game_by_id(id).await
+    }
+
+    /// Set the title of the channel.
+    pub async fn set_title(&self, title: &str) -> Result<()> {
+        let request = twitch::ModifyChannelRequest {
+            title: Some(title.to_string()),
+            ..Default::default()
+        };
+
+        self.modify(request).await
+    }
+
+    /// Set the game of the channel to the one which best matches the given
+    /// name.
+    ///
+    /// Returns the game which was set, or `None` if no game matched.
+    pub async fn set_game(&self, name: &str) -> Result<Option<twitch::Game>> {
+        let game = match self.find_game(name).await? {
+            Some(game) => game,
+            None => return Ok(None),
+        };
+
+        let request = twitch::ModifyChannelRequest {
+            game_id: Some(game.id.clone()),
+            ..Default::default()
+        };
+
+        self.modify(request).await?;
+        Ok(Some(game))
+    }
+
+    /// Search for games matching the given name.
+    pub async fn search_games(&self, name: &str) -> Result<Vec<twitch::Game>> {
+        self.twitch.search_categories(name).await
+    }
+
+    /// Find the game which best matches the given name, preferring exact
+    /// matches.
+    async fn find_game(&self, name: &str) -> Result<Option<twitch::Game>> {
+        if let Some(game) = self.twitch.game_by_name(name).await? {
+            return Ok(Some(game));
+        }
+
+        let games = self.twitch.search_categories(name).await?;
+        Ok(best_game(name, games))
+    }
+
+    /// Remove a ban or timeout of the user with the given id.
+    pub async fn unban(&self, user_id: &str) -> Result<()> {
+        let user = &*self.stream_info.user;
+        self.twitch.unban_user(&user.id, &user.id, user_id).await
+    }
+
+    /// Modify the channel and refresh the stream info to reflect it.
+    async fn modify(&self, request: twitch::ModifyChannelRequest) -> Result<()> {
+        let user = &*self.stream_info.user;
+        self.twitch.modify_channel(&user.id, request).await?;
+        self.stream_info.refresh_channel(&self.twitch, user).await
+    }
+}
+
+/// Pick the game which best matches the given name out of search results.
+///
+/// Prefers games with the same name ignoring case, then games whose name
+/// starts with the given name, falling back to the most relevant result.
+fn best_game(name: &str, games: Vec<twitch::Game>) -> Option<twitch::Game> {
+    let name = name.trim().to_lowercase();
+
+    let position = games
+        .iter()
+        .position(|g| g.name.to_lowercase() == name)
+        .or_else(|| {
+            games
+                .iter()
+                .position(|g| g.name.to_lowercase().starts_with(&name))
+        })
+        .unwrap_or(0);
+
+    games.into_iter().nth(position)
+}
+
 /// Set up a stream information loop.
 pub fn setup(
     streamer: Arc<twitch::User>,
@@ -127,6 +290,7 @@ pub fn setup(
     let stream_info = StreamInfo {
         user: streamer.clone(),
         data: Default::default(),
+        resync: Default::default(),
     };
 
     let mut stream_interval = tokio::time::interval(time::Duration::from_secs(30)).fuse();
@@ -139,6 +303,18 @@ pub fn setup(
 
         loop {
             futures::select! {
+                _ = future_info.resync.notified().fuse() => {
+                    log::trace!("Resynchronizing stream information");
+
+                    let stream = future_info
+                        .refresh_stream(&twitch, &*streamer, &mut stream_state_tx);
+
+                    let channel = future_info
+                        .refresh_channel(&twitch, &*streamer);
+
+                    future::try_join(stream, channel).await?;
+                    future_info.refresh_subs(&twitch, &*streamer).await;
+                }
                 _ = subs_interval.select_next_some() => {
                     future_info.refresh_subs(&twitch, &*streamer).await;
                 }
@@ -157,3 +333,40 @@ pub fn setup(
 
     (stream_info, stream_state_rx, future)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::best_game;
+    use crate::api::twitch::Game;
+
+    #[test]
+    fn test_best_game() {
+        let games = |names: &[&str]| {
+            names
+                .iter()
+                .enumerate()
+                .map(|(id, name)| Game {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    box_art_url: None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let best = |name: &str, names: &[&str]| best_game(name, games(names)).map(|g| g.name);
+
+        assert_eq!(
+            Some(String::from("Minecraft")),
+            best("minecraft", &["Minecraft Dungeons", "Minecraft"])
+        );
+        assert_eq!(
+            Some(String::from("Super Mario 64")),
+            best("super mario", &["New Super Mario Bros.", "Super Mario 64"])
+        );
+        assert_eq!(
+            Some(String::from("The Legend of Zelda: Breath of the Wild")),
+            best("botw", &["The Legend of Zelda: Breath of the Wild"])
+        );
+        assert_eq!(None, best("nothing", &[]));
+    }
+}
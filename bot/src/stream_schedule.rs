@@ -0,0 +1,244 @@
+//! The schedule of the stream, which is configured in `schedule/streams`.
+//!
+//! The schedule is kept up to date by the schedule module, and is used to
+//! answer `!schedule` and `!next` and to show upcoming streams on overlays.
+
+use crate::scheduler::Schedule;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, TimeZone as _, Utc};
+use chrono_tz::Tz;
+use parking_lot::RwLock;
+use std::fmt;
+use std::sync::Arc;
+
+/// A recurring stream in the schedule.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Slot {
+    /// The title of the stream.
+    pub title: String,
+    /// When the stream starts.
+    pub when: Schedule,
+    /// The game which is played on stream.
+    #[serde(default)]
+    pub game: Option<String>,
+}
+
+/// An upcoming stream.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Upcoming {
+    pub title: String,
+    pub game: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    /// The time zone the stream is scheduled in.
+    pub timezone: Tz,
+}
+
+impl Upcoming {
+    /// Format when the stream starts in the time zone it's scheduled in, like
+    /// `Fri 20:00 CET`.
+    pub fn local_time(&self) -> String {
+        self.starts_at
+            .with_timezone(&self.timezone)
+            .format("%a %H:%M %Z")
+            .to_string()
+    }
+}
+
+/// A one-shot announcement at a specific point in time.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Announcement {
+    /// When to post the announcement.
+    pub at: Time,
+    /// The message to post.
+    pub message: String,
+}
+
+/// A point in time with an optional time zone, like
+/// `2021-02-01 20:00 Europe/Stockholm`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Time {
+    time: DateTime<Utc>,
+    source: String,
+}
+
+impl Time {
+    /// Access the time as UTC.
+    pub fn as_utc(&self) -> DateTime<Utc> {
+        self.time
+    }
+}
+
+impl std::str::FromStr for Time {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut it = s.split_whitespace();
+
+        let (date, time) = match (it.next(), it.next()) {
+            (Some(date), Some(time)) => (date, time),
+            _ => return Err(anyhow!("expected `<date> <time> [timezone]`, got `{}`", s)),
+        };
+
+        let local = NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M")
+            .map_err(|e| anyhow!("bad time `{}`: {}", s, e))?;
+
+        let timezone = match it.next() {
+            Some(tz) => str::parse::<Tz>(tz).map_err(|e| anyhow!("{}", e))?,
+            None => Tz::UTC,
+        };
+
+        if let Some(extra) = it.next() {
+            return Err(anyhow!("unexpected `{}` in time `{}`", extra, s));
+        }
+
+        let time = timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .ok_or_else(|| anyhow!("time `{}` doesn't exist in {}", s, timezone.name()))?;
+
+        Ok(Time {
+            time: time.with_timezone(&Utc),
+            source: s.trim().to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(fmt)
+    }
+}
+
+impl serde::Serialize for Time {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Time {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        str::parse::<Time>(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Find the upcoming streams strictly after the given time, the earliest
+/// first.
+pub fn upcoming(slots: &[Slot], after: DateTime<Utc>, limit: usize) -> Vec<Upcoming> {
+    let mut out = Vec::new();
+
+    for slot in slots {
+        let mut after = after;
+
+        for _ in 0..limit {
+            let starts_at = match slot.when.next_after(after) {
+                Some(starts_at) => starts_at,
+                None => break,
+            };
+
+            out.push(Upcoming {
+                title: slot.title.clone(),
+                game: slot.game.clone(),
+                starts_at,
+                timezone: slot.when.timezone(),
+            });
+
+            after = starts_at;
+        }
+    }
+
+    out.sort_by(|a, b| a.starts_at.cmp(&b.starts_at).then(a.title.cmp(&b.title)));
+    out.truncate(limit);
+    out
+}
+
+/// The configured stream schedule.
+#[derive(Debug, Clone, Default)]
+pub struct StreamSchedule {
+    slots: Arc<RwLock<Vec<Slot>>>,
+}
+
+impl StreamSchedule {
+    /// Construct a new, empty schedule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the slots in the schedule.
+    pub fn set(&self, slots: Vec<Slot>) {
+        *self.slots.write() = slots;
+    }
+
+    /// List the upcoming streams, the earliest first.
+    pub fn upcoming(&self, now: DateTime<Utc>, limit: usize) -> Vec<Upcoming> {
+        upcoming(&self.slots.read(), now, limit)
+    }
+
+    /// Get the next upcoming stream.
+    pub fn next(&self, now: DateTime<Utc>) -> Option<Upcoming> {
+        self.upcoming(now, 1).into_iter().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{upcoming, Slot, Time};
+    use chrono::{TimeZone as _, Utc};
+
+    fn slot(title: &str, when: &str) -> Slot {
+        Slot {
+            title: title.to_string(),
+            when: str::parse(when).unwrap(),
+            game: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let t = str::parse::<Time>("2020-08-01 20:00 Europe/Stockholm").unwrap();
+        assert_eq!(Utc.ymd(2020, 8, 1).and_hms(18, 0, 0), t.as_utc());
+        assert_eq!("2020-08-01 20:00 Europe/Stockholm", t.to_string());
+
+        let t = str::parse::<Time>("2020-08-01 20:00").unwrap();
+        assert_eq!(Utc.ymd(2020, 8, 1).and_hms(20, 0, 0), t.as_utc());
+
+        assert!(str::parse::<Time>("2020-08-01").is_err());
+        assert!(str::parse::<Time>("2020-08-01 25:00").is_err());
+        assert!(str::parse::<Time>("2020-08-01 20:00 Not/AZone").is_err());
+    }
+
+    #[test]
+    fn test_upcoming() {
+        // 2020-08-01 is a saturday.
+        let slots = vec![
+            slot("Rust", "0 20 * * fri"),
+            slot("Speedruns", "0 18 * * sat,sun"),
+        ];
+
+        let now = Utc.ymd(2020, 8, 1).and_hms(10, 0, 0);
+        let upcoming = upcoming(&slots, now, 4);
+
+        let times = upcoming
+            .iter()
+            .map(|u| (u.title.as_str(), u.starts_at))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("Speedruns", Utc.ymd(2020, 8, 1).and_hms(18, 0, 0)),
+                ("Speedruns", Utc.ymd(2020, 8, 2).and_hms(18, 0, 0)),
+                ("Rust", Utc.ymd(2020, 8, 7).and_hms(20, 0, 0)),
+                ("Speedruns", Utc.ymd(2020, 8, 8).and_hms(18, 0, 0)),
+            ],
+            times
+        );
+
+        assert_eq!("Sat 18:00 UTC", upcoming[0].local_time());
+    }
+}
@@ -0,0 +1,170 @@
+//! Reports for stream sessions.
+//!
+//! A session is recorded for every stream and groups everything that happened
+//! during it: chat activity, new followers and subscriptions, commands used,
+//! songs played, and afterstream messages.
+
+use crate::chat_stats::{ChatStats, StreamSummary};
+use crate::db;
+use crate::utils;
+use anyhow::Result;
+
+/// A report on a single stream session.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Report {
+    #[serde(flatten)]
+    pub report: db::SessionReport,
+    /// Chat activity during the stream, if it was recorded.
+    pub chat: Option<StreamSummary>,
+}
+
+impl Report {
+    /// Summarize the highlights of the session in a few lines.
+    pub fn highlights(&self) -> String {
+        let report = &self.report;
+        let mut lines = Vec::new();
+
+        if let Some(ended_at) = report.session.ended_at {
+            if let Ok(duration) = (ended_at - report.session.started_at).to_std() {
+                lines.push(format!(
+                    "Streamed for {}.",
+                    utils::compact_duration(duration)
+                ));
+            }
+        }
+
+        if let Some(chat) = &self.chat {
+            lines.push(format!(
+                "{} message(s) from {} chatter(s).",
+                chat.messages, chat.chatters
+            ));
+        }
+
+        lines.push(format!(
+            "{} new follower(s), {} sub(s).",
+            report.follows.len(),
+            report.subs.len()
+        ));
+
+        if let Some(command) = report.commands.first() {
+            lines.push(format!(
+                "Most used command: !{} ({} time(s)).",
+                command.command, command.uses
+            ));
+        }
+
+        lines.push(format!(
+            "{} song(s) played, {} afterstream message(s).",
+            report.songs.len(),
+            report.after_streams.len()
+        ));
+
+        lines.join("\n")
+    }
+}
+
+/// Build a report for the given session.
+///
+/// Chat activity is taken from the current stream if the session hasn't
+/// ended yet.
+pub async fn report(
+    sessions: &db::StreamSessions,
+    chat_stats: Option<&ChatStats>,
+    id: i32,
+) -> Result<Option<Report>> {
+    let report = match sessions.report(id).await? {
+        Some(report) => report,
+        None => return Ok(None),
+    };
+
+    let chat = match chat_stats {
+        Some(chat_stats) if report.session.ended_at.is_none() => Some(chat_stats.current_stream()),
+        Some(chat_stats) => match report.session.stream_stats_id {
+            Some(stats_id) => chat_stats.stream(stats_id).await?,
+            None => None,
+        },
+        None => None,
+    };
+
+    Ok(Some(Report { report, chat }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Report;
+    use crate::chat_stats::{StreamSummary, SubStats};
+    use crate::db;
+    use chrono::NaiveDate;
+
+    fn session() -> db::StreamSession {
+        let started_at = NaiveDate::from_ymd(2021, 2, 7).and_hms(18, 0, 0);
+
+        db::StreamSession {
+            id: 1,
+            stream_id: String::from("1234"),
+            title: String::from("Speedruns"),
+            game: None,
+            started_at,
+            ended_at: Some(started_at + chrono::Duration::minutes(150)),
+            stream_stats_id: None,
+        }
+    }
+
+    #[test]
+    fn test_highlights() {
+        let report = Report {
+            report: db::SessionReport {
+                session: session(),
+                follows: vec![],
+                subs: vec![],
+                commands: vec![db::models::StreamSessionCommand {
+                    session_id: 1,
+                    command: String::from("song"),
+                    uses: 12,
+                }],
+                songs: vec![],
+                after_streams: vec![],
+            },
+            chat: Some(StreamSummary {
+                started_at: None,
+                ended_at: None,
+                messages: 420,
+                chatters: 17,
+                emotes: vec![],
+                subs: SubStats::default(),
+            }),
+        };
+
+        assert_eq!(
+            "Streamed for 2h 30m.\n\
+             420 message(s) from 17 chatter(s).\n\
+             0 new follower(s), 0 sub(s).\n\
+             Most used command: !song (12 time(s)).\n\
+             0 song(s) played, 0 afterstream message(s).",
+            report.highlights()
+        );
+    }
+
+    #[test]
+    fn test_highlights_without_chat() {
+        let mut session = session();
+        session.ended_at = None;
+
+        let report = Report {
+            report: db::SessionReport {
+                session,
+                follows: vec![],
+                subs: vec![],
+                commands: vec![],
+                songs: vec![],
+                after_streams: vec![],
+            },
+            chat: None,
+        };
+
+        assert_eq!(
+            "0 new follower(s), 0 sub(s).\n0 song(s) played, 0 afterstream message(s).",
+            report.highlights()
+        );
+    }
+}
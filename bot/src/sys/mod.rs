@@ -16,6 +16,17 @@ pub enum NotificationIcon {
     Error,
 }
 
+impl NotificationIcon {
+    /// Get the identifier of the icon.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
 // Windows-specific implementation details.
 #[cfg(target_os = "windows")]
 impl NotificationIcon {
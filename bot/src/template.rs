@@ -7,6 +7,13 @@ lazy_static::lazy_static! {
     static ref REGISTRY: handlebars::Handlebars<'static> = {
         let mut reg = handlebars::Handlebars::new();
         reg.register_escape_fn(|s| s.to_string());
+        reg.register_helper("upper", Box::new(helpers::upper));
+        reg.register_helper("lower", Box::new(helpers::lower));
+        reg.register_helper("trim", Box::new(helpers::trim));
+        reg.register_helper("default", Box::new(helpers::default));
+        reg.register_helper("random", Box::new(helpers::random));
+        reg.register_helper("counter", Box::new(helpers::counter));
+        reg.register_helper("api", Box::new(helpers::api));
         reg
     };
 }
@@ -101,62 +108,82 @@ impl Template {
 
     /// Test if the template has the given variable.
     pub fn vars(&self) -> HashSet<String> {
-        use handlebars::template::{HelperTemplate, Parameter, TemplateElement};
-        use std::collections::VecDeque;
-
         let mut out = HashSet::new();
 
-        for e in &self.template.elements {
-            collect_element(&mut out, e);
-        }
+        self.visit(&mut |_| (), &mut |p| {
+            if let Some(name) = p.as_name() {
+                out.insert(name.to_string());
+            }
+        });
 
-        return out;
+        out
+    }
 
-        /// Helper to collect all expressions without recursing.
-        fn collect_element<'e>(out: &mut HashSet<String>, e: &'e TemplateElement) {
-            let mut queue = VecDeque::new();
+    /// Get the URLs looked up using the `api` helper.
+    ///
+    /// Only URLs which are string literals can be looked up, since they have
+    /// to be fetched before the template is rendered.
+    pub fn api_urls(&self) -> HashSet<String> {
+        use handlebars::template::Parameter;
 
-            queue.push_back(e);
+        let mut out = HashSet::new();
 
-            while let Some(e) = queue.pop_front() {
-                match e {
-                    TemplateElement::Expression(helper) => {
-                        collect_helper(out, &mut queue, &*helper);
-                    }
-                    TemplateElement::HTMLExpression(param) => {
-                        collect_parameter(out, &mut queue, &*param);
-                    }
-                    _ => (),
+        self.visit(
+            &mut |h| {
+                if h.name.as_name() != Some("api") {
+                    return;
                 }
-            }
-        }
 
-        fn collect_parameter<'e>(
-            out: &mut HashSet<String>,
-            queue: &mut VecDeque<&'e TemplateElement>,
-            p: &'e Parameter,
-        ) {
-            match p {
-                Parameter::Subexpression(ref e) => {
-                    queue.push_back(&*e.element);
+                if let Some(Parameter::Literal(serde_json::Value::String(url))) = h.params.first() {
+                    out.insert(url.to_string());
                 }
-                p => {
-                    if let Some(name) = p.as_name() {
-                        out.insert(name.to_string());
+            },
+            &mut |_| (),
+        );
+
+        out
+    }
+
+    /// Visit every helper and parameter in the template, including the ones
+    /// in blocks and subexpressions.
+    fn visit(
+        &self,
+        helper: &mut dyn FnMut(&handlebars::template::HelperTemplate),
+        param: &mut dyn FnMut(&handlebars::template::Parameter),
+    ) {
+        use handlebars::template::{Parameter, TemplateElement};
+        use std::collections::VecDeque;
+
+        let mut queue = self.template.elements.iter().collect::<VecDeque<_>>();
+
+        while let Some(e) = queue.pop_front() {
+            let mut params = Vec::new();
+
+            match e {
+                TemplateElement::Expression(h) | TemplateElement::HelperBlock(h) => {
+                    helper(&**h);
+
+                    params.push(&h.name);
+                    params.extend(h.params.iter());
+                    params.extend(h.hash.values());
+
+                    for t in h.template.iter().chain(h.inverse.iter()) {
+                        queue.extend(t.elements.iter());
                     }
                 }
+                TemplateElement::HTMLExpression(p) => {
+                    params.push(&**p);
+                }
+                _ => (),
             }
-        }
-
-        fn collect_helper<'e>(
-            out: &mut HashSet<String>,
-            queue: &mut VecDeque<&'e TemplateElement>,
-            e: &'e HelperTemplate,
-        ) {
-            collect_parameter(out, queue, &e.name);
 
-            for p in &e.params {
-                collect_parameter(out, queue, p);
+            for p in params {
+                match p {
+                    Parameter::Subexpression(e) => {
+                        queue.push_back(&*e.element);
+                    }
+                    p => param(p),
+                }
             }
         }
     }
@@ -196,6 +223,135 @@ impl fmt::Display for Template {
     }
 }
 
+/// The key of the data used by the `api` helper, which has to be looked up
+/// before rendering. See [Template::api_urls].
+pub const API_DATA: &str = "_api";
+
+/// Helpers available in templates, in addition to built-in ones like `if` and
+/// `eq`.
+mod helpers {
+    use handlebars::{
+        handlebars_helper, Context, Handlebars, Helper, HelperResult, Output, RenderContext,
+        RenderError,
+    };
+    use rand::seq::SliceRandom as _;
+    use serde_json::Value;
+
+    handlebars_helper!(upper: |v: Json| display(v).to_uppercase());
+    handlebars_helper!(lower: |v: Json| display(v).to_lowercase());
+    handlebars_helper!(trim: |v: Json| display(v).trim().to_string());
+    handlebars_helper!(default: |v: Json, fallback: Json| {
+        if is_empty(v) {
+            fallback.clone()
+        } else {
+            v.clone()
+        }
+    });
+
+    /// Pick one of the parameters at random, like `{{random "heads" "tails"}}`.
+    pub fn random(
+        h: &Helper<'_, '_>,
+        _: &Handlebars<'_>,
+        _: &Context,
+        _: &mut RenderContext<'_, '_>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let choice = h
+            .params()
+            .choose(&mut rand::thread_rng())
+            .ok_or_else(|| RenderError::new("`random` requires at least one choice"))?;
+
+        out.write(&display(choice.value()))?;
+        Ok(())
+    }
+
+    /// The value of a counter, like `{{counter "deaths"}}`.
+    pub fn counter(
+        h: &Helper<'_, '_>,
+        _: &Handlebars<'_>,
+        ctx: &Context,
+        _: &mut RenderContext<'_, '_>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let name = param_str(h, 0, "counter")?.to_lowercase();
+
+        if let Some(count) = ctx.data().get("counters").and_then(|c| c.get(&name)) {
+            out.write(&display(count))?;
+        }
+
+        Ok(())
+    }
+
+    /// A value looked up from a JSON API, like
+    /// `{{api "https://example.com/api" "data.0.name"}}`.
+    pub fn api(
+        h: &Helper<'_, '_>,
+        _: &Handlebars<'_>,
+        ctx: &Context,
+        _: &mut RenderContext<'_, '_>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let url = param_str(h, 0, "api")?;
+        let value = ctx.data().get(super::API_DATA).and_then(|api| api.get(url));
+
+        let value = match h.param(1) {
+            Some(path) => value.and_then(|v| json_path(v, &display(path.value()))),
+            None => value,
+        };
+
+        if let Some(value) = value {
+            out.write(&display(value))?;
+        }
+
+        Ok(())
+    }
+
+    /// Get a string parameter of a helper.
+    fn param_str<'a>(
+        h: &'a Helper<'_, '_>,
+        index: usize,
+        helper: &str,
+    ) -> Result<&'a str, RenderError> {
+        match h.param(index).and_then(|p| p.value().as_str()) {
+            Some(value) => Ok(value),
+            None => Err(RenderError::new(format!(
+                "`{}` requires a string parameter",
+                helper
+            ))),
+        }
+    }
+
+    /// Look up a value in a JSON document with a path like `data.0.name`.
+    pub(super) fn json_path<'a>(mut value: &'a Value, path: &str) -> Option<&'a Value> {
+        for part in path.split('.').filter(|p| !p.is_empty()) {
+            value = match value {
+                Value::Array(array) => array.get(str::parse::<usize>(part).ok()?)?,
+                value => value.get(part)?,
+            };
+        }
+
+        Some(value)
+    }
+
+    /// Format a value for output.
+    fn display(value: &Value) -> String {
+        match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        }
+    }
+
+    /// Test if a value is missing or empty.
+    fn is_empty(value: &Value) -> bool {
+        match value {
+            Value::Null => true,
+            Value::String(s) => s.trim().is_empty(),
+            _ => false,
+        }
+    }
+}
+
 pub struct WriteOutput<W> {
     write: W,
 }
@@ -246,8 +402,9 @@ impl Default for StringOutput {
 
 #[cfg(test)]
 mod tests {
-    use super::Template;
+    use super::{helpers, Template};
     use anyhow::Error;
+    use serde_json::json;
     use std::collections::HashSet;
 
     #[test]
@@ -262,4 +419,90 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    pub fn test_template_vars_in_blocks() -> Result<(), Error> {
+        let template = Template::compile("{{#if foo}}{{upper bar}}{{else}}{{baz}}{{/if}}")?;
+        let vars = template.vars();
+
+        for var in &["foo", "bar", "baz"] {
+            assert!(vars.contains(*var), "missing var `{}`", var);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_helpers() -> Result<(), Error> {
+        let render = |template: &str, data: serde_json::Value| {
+            Template::compile(template)?.render_to_string(data)
+        };
+
+        assert_eq!(
+            "SETBAC",
+            render("{{upper name}}", json!({"name": "setbac"}))?
+        );
+        assert_eq!(
+            "setbac",
+            render("{{lower name}}", json!({"name": "SetBac"}))?
+        );
+        assert_eq!(
+            "setbac",
+            render("{{trim name}}", json!({"name": "  setbac "}))?
+        );
+        assert_eq!(
+            "someone",
+            render("{{default name \"someone\"}}", json!({}))?
+        );
+        assert_eq!(
+            "setbac",
+            render("{{default name \"someone\"}}", json!({"name": "setbac"}))?
+        );
+        assert_eq!("heads", render("{{random \"heads\"}}", json!({}))?);
+        assert_eq!(
+            "3",
+            render("{{counter \"Deaths\"}}", json!({"counters": {"deaths": 3}}))?
+        );
+
+        let template = "{{#if (eq count 1)}}once{{else}}{{count}} times{{/if}}";
+        assert_eq!("once", render(template, json!({"count": 1}))?);
+        assert_eq!("2 times", render(template, json!({"count": 2}))?);
+
+        let template = "{{api \"https://example.com\" \"data.0.name\"}}";
+        let data = json!({"_api": {"https://example.com": {"data": [{"name": "setbac"}]}}});
+        assert_eq!("setbac", render(template, data)?);
+        assert_eq!("", render(template, json!({}))?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_api_urls() -> Result<(), Error> {
+        let template = Template::compile(concat!(
+            "{{api \"https://a.example.com\"}} ",
+            "{{#if foo}}{{upper (api \"https://b.example.com\" \"x\")}}{{/if}}",
+        ))?;
+
+        assert_eq!(
+            vec!["https://a.example.com", "https://b.example.com"]
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect::<HashSet<String>>(),
+            template.api_urls()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_path() {
+        let doc = json!({"data": [{"name": "setbac"}], "total": 1});
+        assert_eq!(
+            Some(&json!("setbac")),
+            helpers::json_path(&doc, "data.0.name")
+        );
+        assert_eq!(Some(&json!(1)), helpers::json_path(&doc, "total"));
+        assert_eq!(Some(&doc), helpers::json_path(&doc, ""));
+        assert_eq!(None, helpers::json_path(&doc, "data.1.name"));
+        assert_eq!(None, helpers::json_path(&doc, "data.first"));
+    }
 }
@@ -1,3 +1,4 @@
+use crate::api::bandcamp;
 pub use crate::spotify_id::SpotifyId;
 use std::fmt;
 use thiserror::Error;
@@ -14,6 +15,8 @@ pub enum TrackId {
     Spotify(SpotifyId),
     /// A YouTube track.
     YouTube(String),
+    /// A Bandcamp track, identified by `<artist>/<slug>`.
+    Bandcamp(String),
 }
 
 #[derive(Debug, Error)]
@@ -24,8 +27,9 @@ pub enum ParseTrackIdError {
     #[error(
         "bad URL, expected: \
                        https://open.spotify.com/track/<id>, \
-                       https://youtube.com/watch?v=<id>, or \
-                       https://youtu.be/<id>"
+                       https://youtube.com/watch?v=<id>, \
+                       https://youtu.be/<id>, or \
+                       https://<artist>.bandcamp.com/track/<name>"
     )]
     BadUrl(String),
     /// Argument had a bad URI.
@@ -34,7 +38,13 @@ pub enum ParseTrackIdError {
     /// Failed to parse an ID.
     #[error("bad spotify track id (expected base62): {}", _0)]
     BadBase62(String),
-    #[error("missing uri prefix, expected youtube:video:<id>, or spotify:track:<id>")]
+    /// Failed to parse a Bandcamp ID.
+    #[error("bad bandcamp track id (expected <artist>/<name>): {}", _0)]
+    BadBandcamp(String),
+    #[error(
+        "missing uri prefix, expected youtube:video:<id>, spotify:track:<id>, \
+         or bandcamp:track:<artist>/<name>"
+    )]
     MissingUriPrefix,
 }
 
@@ -55,6 +65,16 @@ impl std::str::FromStr for TrackId {
             return Ok(TrackId::Spotify(id));
         }
 
+        if s.starts_with("bandcamp:track:") {
+            let id = s.trim_start_matches("bandcamp:track:");
+
+            if bandcamp::track_url(id).is_none() {
+                return Err(ParseTrackIdError::BadBandcamp(id.to_string()));
+            }
+
+            return Ok(TrackId::Bandcamp(id.to_string()));
+        }
+
         Err(ParseTrackIdError::MissingUriPrefix)
     }
 }
@@ -64,6 +84,7 @@ impl fmt::Display for TrackId {
         match *self {
             TrackId::Spotify(ref id) => write!(fmt, "spotify:track:{}", id.to_base62()),
             TrackId::YouTube(ref id) => write!(fmt, "youtube:video:{}", id),
+            TrackId::Bandcamp(ref id) => write!(fmt, "bandcamp:track:{}", id),
         }
     }
 }
@@ -82,6 +103,7 @@ impl TrackId {
         match *self {
             TrackId::Spotify(ref id) => format!("{}/{}", SPOTIFY_URL, id.to_base62()),
             TrackId::YouTube(ref id) => format!("{}/{}", YOUTUBE_URL, id),
+            TrackId::Bandcamp(ref id) => bandcamp::track_url(id).unwrap_or_default(),
         }
     }
 
@@ -145,6 +167,23 @@ impl TrackId {
 
                     return Ok(TrackId::YouTube(video_id.to_string()));
                 }
+                Some(url::Host::Domain(host)) if host.ends_with(".bandcamp.com") => {
+                    let artist = host.trim_end_matches(".bandcamp.com");
+                    let parts = url.path().split('/').collect::<Vec<_>>();
+
+                    let name = match parts.as_slice() {
+                        ["", "track", name] => *name,
+                        _ => return Err(ParseTrackIdError::BadUrl(url.to_string())),
+                    };
+
+                    let id = format!("{}/{}", artist, name);
+
+                    if bandcamp::track_url(&id).is_none() {
+                        return Err(ParseTrackIdError::BadBandcamp(id));
+                    }
+
+                    return Ok(TrackId::Bandcamp(id));
+                }
                 Some(..) => {
                     return Err(ParseTrackIdError::BadHost(url.to_string()));
                 }
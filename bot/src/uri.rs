@@ -26,6 +26,39 @@ pub enum ParseUriError {
     BadBase62(String),
     #[error("missing uri prefix, expected youtube:video:<id>, or spotify:track:<id>")]
     BadURIPrefix,
+    #[error(
+        "bad URL, expected: \
+                       https://open.spotify.com/track/<id>, or \
+                       https://open.spotify.com/playlist/<id>"
+    )]
+    BadUrl(String),
+}
+
+impl Uri {
+    /// Parse by trying URL forms first.
+    pub fn parse_with_urls(s: &str) -> Result<Self, ParseUriError> {
+        if let Ok(url) = str::parse::<url::Url>(s) {
+            if url.host() == Some(url::Host::Domain("open.spotify.com")) {
+                let parts = url.path().split('/').collect::<Vec<_>>();
+
+                let (kind, id) = match parts.as_slice() {
+                    ["", kind, id] => (*kind, *id),
+                    _ => return Err(ParseUriError::BadUrl(url.to_string())),
+                };
+
+                let id = SpotifyId::from_base62(id)
+                    .map_err(|_| ParseUriError::BadBase62(id.to_string()))?;
+
+                return match kind {
+                    "track" => Ok(Uri::SpotifyTrack(id)),
+                    "playlist" => Ok(Uri::SpotifyPlaylist(id)),
+                    _ => Err(ParseUriError::BadUrl(url.to_string())),
+                };
+            }
+        }
+
+        str::parse(s)
+    }
 }
 
 impl std::str::FromStr for Uri {
@@ -1,5 +1,6 @@
 use crate::api;
 use crate::prelude::*;
+use chrono::{DateTime, Datelike as _, NaiveDate, Utc};
 use percent_encoding::PercentDecode;
 use std::borrow::Cow;
 use std::fmt;
@@ -462,6 +463,62 @@ pub fn digital_duration(duration: time::Duration) -> String {
     parts.join(":")
 }
 
+/// Format the time between `from` and `to` as a human age in calendar years,
+/// months, and days, like `2 years, 3 months, & 5 days`.
+///
+/// Ages shorter than a day are formatted as a [compact_duration].
+pub fn human_age(from: DateTime<Utc>, to: DateTime<Utc>) -> String {
+    if to - from < chrono::Duration::days(1) {
+        return compact_duration((to - from).to_std().unwrap_or_default());
+    }
+
+    let (start, end) = (from.naive_utc().date(), to.naive_utc().date());
+
+    let mut months = (end.year() - start.year()) * 12 + end.month() as i32 - start.month() as i32;
+
+    if end.day() < start.day() {
+        months -= 1;
+    }
+
+    let months = months.max(0) as u32;
+    let days = (end - add_months(start, months)).num_days().max(0);
+
+    let mut parts = Vec::new();
+
+    parts.extend(match months / 12 {
+        0 => None,
+        1 => Some(String::from("1 year")),
+        n => Some(format!("{} years", n)),
+    });
+
+    parts.extend(match months % 12 {
+        0 => None,
+        1 => Some(String::from("1 month")),
+        n => Some(format!("{} months", n)),
+    });
+
+    parts.extend(match days {
+        0 => None,
+        1 => Some(String::from("1 day")),
+        n => Some(format!("{} days", n)),
+    });
+
+    human_list(&parts).unwrap_or_default()
+}
+
+/// Add the given number of months to a date, clamping the day to the last
+/// day of the resulting month.
+fn add_months(date: NaiveDate, months: u32) -> NaiveDate {
+    let months = date.month0() + months;
+    let year = date.year() + (months / 12) as i32;
+    let month = months % 12 + 1;
+
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .unwrap_or(date)
+}
+
 /// Format the given number as a string according to english conventions.
 pub fn english_num(n: u64) -> Cow<'static, str> {
     let n = match n {
@@ -681,6 +738,11 @@ impl Cooldown {
     pub fn poke(&mut self, now: time::Instant) {
         self.last_action_at = Some(now);
     }
+
+    /// Clear the cooldown, so that it's open again.
+    pub fn clear(&mut self) {
+        self.last_action_at = None;
+    }
 }
 
 impl serde::Serialize for Cooldown {
@@ -840,9 +902,52 @@ impl serde::Serialize for PtDuration {
     }
 }
 
+/// Pick a random index, where each index is picked with a probability
+/// proportional to its weight.
+///
+/// Returns `None` if there are no weights above zero.
+pub fn weighted_pick(weights: &[u32]) -> Option<usize> {
+    use rand::Rng as _;
+
+    let total = weights.iter().copied().map(u64::from).sum::<u64>();
+
+    if total == 0 {
+        return None;
+    }
+
+    pick_with_roll(weights, rand::thread_rng().gen_range(0, total))
+}
+
+/// Pick the index of a weight using the given roll, which must be less than
+/// the sum of all weights.
+fn pick_with_roll(weights: &[u32], mut roll: u64) -> Option<usize> {
+    for (index, weight) in weights.iter().copied().map(u64::from).enumerate() {
+        if roll < weight {
+            return Some(index);
+        }
+
+        roll -= weight;
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Offset, TrimmedWords, Urls, Words};
+    use super::{human_age, pick_with_roll, Offset, TrimmedWords, Urls, Words};
+
+    #[test]
+    fn test_pick_with_roll() {
+        let weights = [1, 3, 0, 2];
+
+        assert_eq!(Some(0), pick_with_roll(&weights, 0));
+        assert_eq!(Some(1), pick_with_roll(&weights, 1));
+        assert_eq!(Some(1), pick_with_roll(&weights, 3));
+        assert_eq!(Some(3), pick_with_roll(&weights, 4));
+        assert_eq!(Some(3), pick_with_roll(&weights, 5));
+        assert_eq!(None, pick_with_roll(&weights, 6));
+        assert_eq!(None, pick_with_roll(&[], 0));
+    }
 
     #[test]
     pub fn test_offset() -> Result<(), anyhow::Error> {
@@ -856,6 +961,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn test_human_age() {
+        let age =
+            |from: &str, to: &str| human_age(str::parse(from).unwrap(), str::parse(to).unwrap());
+
+        assert_eq!(
+            "2 years, 3 months, & 5 days",
+            age("2018-07-01T12:00:00Z", "2020-10-06T08:00:00Z")
+        );
+        assert_eq!(
+            "1 year",
+            age("2019-10-06T12:00:00Z", "2020-10-06T12:00:00Z")
+        );
+        assert_eq!(
+            "1 month, & 1 day",
+            age("2020-01-31T12:00:00Z", "2020-03-01T12:00:00Z")
+        );
+        assert_eq!("5h", age("2020-10-06T01:00:00Z", "2020-10-06T06:00:00Z"));
+        assert_eq!("2h", age("2020-10-05T23:00:00Z", "2020-10-06T01:00:00Z"));
+        assert_eq!("0s", age("2020-10-06T06:00:00Z", "2020-10-06T01:00:00Z"));
+    }
+
     #[test]
     pub fn test_trimmed_words() {
         let out = TrimmedWords::new("hello, do you feel alive?").collect::<Vec<_>>();
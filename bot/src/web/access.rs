@@ -0,0 +1,200 @@
+use super::editors::{Editors, SESSION_COOKIE};
+use super::proxy::{Client, Proxy};
+use crate::auth::{self, Role, Scope};
+use crate::settings;
+use anyhow::Result;
+use warp::filters;
+use warp::Filter as _;
+
+/// Header used to provide an API key.
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// A single configured API key.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ApiKey {
+    key: String,
+    role: Role,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct KeyQuery {
+    #[serde(default)]
+    key: Option<String>,
+}
+
+/// Rejection raised when a request is not authorized for a given scope.
+#[derive(Debug)]
+pub(crate) struct Unauthorized(pub(crate) Scope);
+
+impl warp::reject::Reject for Unauthorized {}
+
+/// Access control for web endpoints.
+///
+/// Resolves the role of an incoming request through its API key or editor
+/// session, and tests it against the grants in the auth system.
+#[derive(Clone)]
+pub(crate) struct Access {
+    auth: auth::Auth,
+    proxy: Proxy,
+    editors: Editors,
+    require_api_key: settings::Var<bool>,
+    api_keys: settings::Var<Vec<ApiKey>>,
+}
+
+impl Access {
+    /// Construct a new access control.
+    pub(crate) async fn new(
+        auth: auth::Auth,
+        proxy: Proxy,
+        editors: Editors,
+        settings: &settings::Settings,
+    ) -> Result<Self> {
+        let settings = settings.scoped("web");
+
+        Ok(Self {
+            auth,
+            proxy,
+            editors,
+            require_api_key: settings.var("require-api-key", false).await?,
+            api_keys: settings.var("api-keys", Vec::new()).await?,
+        })
+    }
+
+    /// Resolve the role associated with the given key or editor session.
+    ///
+    /// If `authenticated` is set, requests without a key or session never
+    /// fall back to the streamer role.
+    async fn role(&self, key: Option<&str>, session: Option<&str>, authenticated: bool) -> Role {
+        if let Some(key) = key {
+            for api_key in self.api_keys.load().await {
+                if api_key.key == key {
+                    return api_key.role;
+                }
+            }
+
+            return Role::Everyone;
+        }
+
+        if let Some(session) = session {
+            if self.editors.editor(session).await.is_some() {
+                return Role::Editor;
+            }
+        }
+
+        if authenticated || self.require_api_key.load().await {
+            Role::Everyone
+        } else {
+            Role::Streamer
+        }
+    }
+
+    /// Filter that resolves the role of the request.
+    pub(crate) fn request_role(&self) -> filters::BoxedFilter<(Role,)> {
+        self.request_role_with(false)
+    }
+
+    /// Filter that resolves the role of the request, which has to be
+    /// authenticated through an API key or editor session.
+    ///
+    /// Used for endpoints which external tools trigger, where a keyless
+    /// request might have been forged by any web page.
+    pub(crate) fn authenticated_role(&self) -> filters::BoxedFilter<(Role,)> {
+        self.request_role_with(true)
+    }
+
+    fn request_role_with(&self, authenticated: bool) -> filters::BoxedFilter<(Role,)> {
+        let access = self.clone();
+
+        warp::header::optional::<String>(API_KEY_HEADER)
+            .and(
+                warp::query::<KeyQuery>()
+                    .or(warp::any().map(KeyQuery::default))
+                    .unify(),
+            )
+            .and(warp::cookie::optional(SESSION_COOKIE))
+            .and_then(
+                move |header: Option<String>, query: KeyQuery, session: Option<String>| {
+                    let access = access.clone();
+
+                    async move {
+                        let key = header.or(query.key);
+                        let role = access
+                            .role(key.as_deref(), session.as_deref(), authenticated)
+                            .await;
+                        Ok::<_, warp::Rejection>(role)
+                    }
+                },
+            )
+            .boxed()
+    }
+
+    /// Filter that only lets through requests that have access to the given
+    /// scope.
+    pub(crate) fn scope(&self, scope: Scope) -> filters::BoxedFilter<()> {
+        self.scope_with(scope, false)
+    }
+
+    /// Filter that only lets through requests that have access to the given
+    /// scope through an API key or editor session.
+    pub(crate) fn authenticated_scope(&self, scope: Scope) -> filters::BoxedFilter<()> {
+        self.scope_with(scope, true)
+    }
+
+    fn scope_with(&self, scope: Scope, authenticated: bool) -> filters::BoxedFilter<()> {
+        let access = self.clone();
+
+        warp::header::optional::<String>(API_KEY_HEADER)
+            .and(
+                warp::query::<KeyQuery>()
+                    .or(warp::any().map(KeyQuery::default))
+                    .unify(),
+            )
+            .and(warp::cookie::optional(SESSION_COOKIE))
+            .and(self.proxy.client())
+            .and_then(
+                move |header: Option<String>,
+                      query: KeyQuery,
+                      session: Option<String>,
+                      client: Client| {
+                    let access = access.clone();
+
+                    async move {
+                        let key = header.or(query.key);
+                        let role = access
+                            .role(key.as_deref(), session.as_deref(), authenticated)
+                            .await;
+
+                        if access.auth.test_role(scope, role).await {
+                            Ok(())
+                        } else {
+                            log::warn!("unauthorized request for `{}` from {}", scope, client);
+                            Err(warp::reject::custom(Unauthorized(scope)))
+                        }
+                    }
+                },
+            )
+            .untuple_one()
+            .boxed()
+    }
+
+    /// Filter that resolves the login of the editor making the request, if
+    /// it's made through an editor session.
+    pub(crate) fn editor(&self) -> filters::BoxedFilter<(Option<String>,)> {
+        let editors = self.editors.clone();
+
+        warp::cookie::optional(SESSION_COOKIE)
+            .and_then(move |session: Option<String>| {
+                let editors = editors.clone();
+
+                async move {
+                    let editor = match session {
+                        Some(session) => editors.editor(&session).await,
+                        None => None,
+                    };
+
+                    Ok::<_, warp::Rejection>(editor)
+                }
+            })
+            .boxed()
+    }
+}
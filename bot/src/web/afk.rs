@@ -0,0 +1,44 @@
+use crate::injector;
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// Endpoints to see who is away from keyboard.
+#[derive(Clone)]
+pub struct Afk(injector::Var<Option<crate::afk::Afk>>);
+
+impl Afk {
+    pub fn route(
+        afk: injector::Var<Option<crate::afk::Afk>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Afk(afk);
+
+        let list = warp::get()
+            .and(path::end())
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("afk").and(list).boxed()
+    }
+
+    /// Access underlying statuses.
+    async fn afk(&self) -> Result<RwLockReadGuard<'_, crate::afk::Afk>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("afk not configured"),
+        }
+    }
+
+    /// List everyone who is away.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let statuses = self.afk().await?.list();
+        Ok(warp::reply::json(&statuses))
+    }
+}
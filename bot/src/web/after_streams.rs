@@ -0,0 +1,155 @@
+use crate::auth::Scope;
+use crate::db;
+use crate::injector;
+use crate::web::{Access, EMPTY};
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct ListQuery {
+    #[serde(default)]
+    status: Option<db::AfterStreamStatus>,
+}
+
+#[derive(serde::Deserialize)]
+struct SetStatus {
+    ids: Vec<i32>,
+    status: db::AfterStreamStatus,
+}
+
+#[derive(serde::Deserialize)]
+struct Reply {
+    text: String,
+}
+
+/// Endpoints to review, reply to, and resolve afterstream messages.
+#[derive(Clone)]
+pub struct AfterStreams(injector::Var<Option<db::AfterStreams>>);
+
+impl AfterStreams {
+    pub fn route(
+        after_streams: injector::Var<Option<db::AfterStreams>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = AfterStreams(after_streams);
+
+        let list = warp::get()
+            .and(path!("after-streams").and(path::end()))
+            .and(access.scope(Scope::WebAfterStreams))
+            .and(
+                warp::query::<ListQuery>()
+                    .or(warp::any().map(ListQuery::default))
+                    .unify(),
+            )
+            .and_then({
+                let api = api.clone();
+                move |query: ListQuery| {
+                    let api = api.clone();
+                    async move { api.list(query).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let set_status = warp::post()
+            .and(path!("after-streams" / "status").and(path::end()))
+            .and(access.scope(Scope::WebAfterStreams))
+            .and(warp::body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: SetStatus| {
+                    let api = api.clone();
+                    async move { api.set_status(body).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let delete = warp::delete()
+            .and(path!("after-stream" / i32).and(path::end()))
+            .and(access.scope(Scope::WebAfterStreams))
+            .and_then({
+                let api = api.clone();
+                move |id: i32| {
+                    let api = api.clone();
+                    async move { api.delete(id).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let reply = warp::post()
+            .and(path!("after-stream" / i32 / "replies").and(path::end()))
+            .and(access.scope(Scope::WebAfterStreams))
+            .and(access.editor())
+            .and(warp::body::json())
+            .and_then({
+                move |id: i32, editor: Option<String>, body: Reply| {
+                    let api = api.clone();
+                    async move {
+                        api.reply(id, editor, body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        list.or(set_status).or(delete).or(reply).boxed()
+    }
+
+    /// Access underlying after streams abstraction.
+    async fn after_streams(&self) -> Result<RwLockReadGuard<'_, db::AfterStreams>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("after streams not configured"),
+        }
+    }
+
+    /// List afterstream messages and their replies.
+    async fn list(&self, query: ListQuery) -> Result<impl warp::Reply> {
+        let entries = self.after_streams().await?.entries(query.status).await?;
+        Ok(warp::reply::json(&entries))
+    }
+
+    /// Set the status of multiple afterstream messages at once.
+    async fn set_status(&self, body: SetStatus) -> Result<impl warp::Reply> {
+        self.after_streams()
+            .await?
+            .set_status(body.ids, body.status)
+            .await?;
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Delete an afterstream message.
+    async fn delete(&self, id: i32) -> Result<impl warp::Reply> {
+        self.after_streams().await?.delete(id).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Reply to an afterstream message.
+    async fn reply(
+        &self,
+        id: i32,
+        editor: Option<String>,
+        body: Reply,
+    ) -> Result<impl warp::Reply> {
+        let text = body.text.trim();
+
+        if text.is_empty() {
+            bail!("reply must not be empty");
+        }
+
+        if !self
+            .after_streams()
+            .await?
+            .reply(id, editor.as_deref(), text)
+            .await?
+        {
+            bail!("no such after stream: {}", id);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
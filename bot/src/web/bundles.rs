@@ -0,0 +1,361 @@
+use crate::auth::Scope;
+use crate::db;
+use crate::injector;
+use crate::template::Template;
+use crate::track_id::TrackId;
+use crate::utils;
+use crate::web::Access;
+use anyhow::{bail, Result};
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// A command in a bundle.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct CommandEntry {
+    name: String,
+    template: Template,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+/// An alias in a bundle.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct AliasEntry {
+    name: String,
+    template: Template,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+/// A promotion in a bundle.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct PromotionEntry {
+    name: String,
+    frequency: utils::Duration,
+    template: Template,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+/// A theme in a bundle.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ThemeEntry {
+    name: String,
+    track_id: TrackId,
+    #[serde(default)]
+    start: utils::Offset,
+    #[serde(default)]
+    end: Option<utils::Offset>,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+/// A bad word in a bundle.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct BadWordEntry {
+    word: String,
+    #[serde(default)]
+    why: Option<String>,
+}
+
+/// A bundle of data belonging to a single module, which can be exported from
+/// one bot and imported into another.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", content = "items", rename_all = "kebab-case")]
+enum Bundle {
+    Commands(Vec<CommandEntry>),
+    Aliases(Vec<AliasEntry>),
+    Promotions(Vec<PromotionEntry>),
+    Themes(Vec<ThemeEntry>),
+    BadWords(Vec<BadWordEntry>),
+}
+
+/// The result of importing a bundle.
+#[derive(Debug, serde::Serialize)]
+struct Imported {
+    imported: usize,
+}
+
+/// Bundle endpoints.
+#[derive(Clone)]
+pub struct Bundles {
+    commands: injector::Var<Option<db::Commands>>,
+    aliases: injector::Var<Option<db::Aliases>>,
+    promotions: injector::Var<Option<db::Promotions>>,
+    themes: injector::Var<Option<db::Themes>>,
+    bad_words: injector::Var<Option<db::Words>>,
+}
+
+impl Bundles {
+    pub async fn route(
+        injector: &injector::Injector,
+        access: &Access,
+    ) -> Result<filters::BoxedFilter<(impl warp::Reply,)>> {
+        let api = Bundles {
+            commands: injector.var().await?,
+            aliases: injector.var().await?,
+            promotions: injector.var().await?,
+            themes: injector.var().await?,
+            bad_words: injector.var().await?,
+        };
+
+        let export = warp::get()
+            .and(path!(String / String).and(path::end()))
+            .and(access.scope(Scope::WebBundles))
+            .and_then({
+                let api = api.clone();
+                move |channel: String, kind: String| {
+                    let api = api.clone();
+                    async move {
+                        api.export(&channel, &kind)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let import = warp::put()
+            .and(path!(String).and(path::end()))
+            .and(access.scope(Scope::WebBundles))
+            .and(body::json())
+            .and_then({
+                move |channel: String, bundle: Bundle| {
+                    let api = api.clone();
+                    async move {
+                        api.import(&channel, bundle)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        Ok(warp::path("bundles").and(export.or(import)).boxed())
+    }
+
+    /// Export all data of the given kind for a channel.
+    async fn export(&self, channel: &str, kind: &str) -> Result<impl warp::Reply> {
+        let bundle = match kind {
+            "commands" => {
+                let commands = load(&self.commands, "commands").await?;
+                let mut items = Vec::new();
+
+                for c in commands.list_all(channel).await? {
+                    items.push(CommandEntry {
+                        name: c.key.name,
+                        template: c.template,
+                        pattern: pattern(&c.pattern),
+                        group: c.group,
+                        disabled: c.disabled,
+                    });
+                }
+
+                Bundle::Commands(items)
+            }
+            "aliases" => {
+                let aliases = load(&self.aliases, "aliases").await?;
+                let mut items = Vec::new();
+
+                for a in aliases.list_all(channel).await? {
+                    items.push(AliasEntry {
+                        name: a.key.name,
+                        template: a.template,
+                        pattern: pattern(&a.pattern),
+                        group: a.group,
+                        disabled: a.disabled,
+                    });
+                }
+
+                Bundle::Aliases(items)
+            }
+            "promotions" => {
+                let promotions = load(&self.promotions, "promotions").await?;
+                let mut items = Vec::new();
+
+                for p in promotions.list_all(channel).await? {
+                    items.push(PromotionEntry {
+                        name: p.key.name,
+                        frequency: p.frequency,
+                        template: p.template,
+                        group: p.group,
+                        disabled: p.disabled,
+                    });
+                }
+
+                Bundle::Promotions(items)
+            }
+            "themes" => {
+                let themes = load(&self.themes, "themes").await?;
+                let mut items = Vec::new();
+
+                for t in themes.list_all(channel).await? {
+                    items.push(ThemeEntry {
+                        name: t.key.name,
+                        track_id: t.track_id,
+                        start: t.start,
+                        end: t.end,
+                        group: t.group,
+                        disabled: t.disabled,
+                    });
+                }
+
+                Bundle::Themes(items)
+            }
+            "bad-words" => {
+                let bad_words = load(&self.bad_words, "bad words").await?;
+                let mut items = Vec::new();
+
+                for w in bad_words.list().await? {
+                    items.push(BadWordEntry {
+                        word: w.word,
+                        why: w.why,
+                    });
+                }
+
+                Bundle::BadWords(items)
+            }
+            kind => bail!("unsupported bundle kind `{}`", kind),
+        };
+
+        Ok(warp::reply::json(&bundle))
+    }
+
+    /// Import the given bundle into a channel.
+    ///
+    /// Existing entries with the same name are overwritten.
+    async fn import(&self, channel: &str, bundle: Bundle) -> Result<impl warp::Reply> {
+        let imported = match bundle {
+            Bundle::Commands(items) => {
+                let commands = load(&self.commands, "commands").await?;
+                let patterns = compile_patterns(items.iter().map(|c| c.pattern.as_deref()))?;
+
+                for (c, pattern) in items.iter().zip(patterns) {
+                    commands.edit(channel, &c.name, c.template.clone()).await?;
+                    commands.edit_pattern(channel, &c.name, pattern).await?;
+                    commands
+                        .edit_group(channel, &c.name, c.group.clone())
+                        .await?;
+
+                    if c.disabled {
+                        commands.disable(channel, &c.name).await?;
+                    }
+                }
+
+                items.len()
+            }
+            Bundle::Aliases(items) => {
+                let aliases = load(&self.aliases, "aliases").await?;
+                let patterns = compile_patterns(items.iter().map(|a| a.pattern.as_deref()))?;
+
+                for (a, pattern) in items.iter().zip(patterns) {
+                    aliases.edit(channel, &a.name, a.template.clone()).await?;
+                    aliases.edit_pattern(channel, &a.name, pattern).await?;
+                    aliases
+                        .edit_group(channel, &a.name, a.group.clone())
+                        .await?;
+
+                    if a.disabled {
+                        aliases.disable(channel, &a.name).await?;
+                    }
+                }
+
+                items.len()
+            }
+            Bundle::Promotions(items) => {
+                let promotions = load(&self.promotions, "promotions").await?;
+
+                for p in &items {
+                    promotions
+                        .edit(channel, &p.name, p.frequency, p.template.clone())
+                        .await?;
+                    promotions
+                        .edit_group(channel, &p.name, p.group.clone())
+                        .await?;
+
+                    if p.disabled {
+                        promotions.disable(channel, &p.name).await?;
+                    }
+                }
+
+                items.len()
+            }
+            Bundle::Themes(items) => {
+                let themes = load(&self.themes, "themes").await?;
+
+                for t in &items {
+                    themes.edit(channel, &t.name, t.track_id.clone()).await?;
+                    themes
+                        .edit_duration(channel, &t.name, t.start.clone(), t.end.clone())
+                        .await?;
+                    themes.edit_group(channel, &t.name, t.group.clone()).await?;
+
+                    if t.disabled {
+                        themes.disable(channel, &t.name).await?;
+                    }
+                }
+
+                items.len()
+            }
+            Bundle::BadWords(items) => {
+                let bad_words = load(&self.bad_words, "bad words").await?;
+
+                for w in &items {
+                    bad_words.edit(&w.word, w.why.as_deref()).await?;
+                }
+
+                items.len()
+            }
+        };
+
+        log::info!("imported {} bundle item(s) into {}", imported, channel);
+        Ok(warp::reply::json(&Imported { imported }))
+    }
+}
+
+/// Load the given injected value, or error if it's not configured.
+async fn load<T>(var: &injector::Var<Option<T>>, what: &str) -> Result<T>
+where
+    T: Clone,
+{
+    match var.load().await {
+        Some(value) => Ok(value),
+        None => bail!("{} not configured", what),
+    }
+}
+
+/// Get the regular expression of a pattern, if it has one.
+fn pattern(pattern: &db::Pattern) -> Option<String> {
+    match pattern {
+        db::Pattern::Regex { pattern } => Some(pattern.as_str().to_string()),
+        db::Pattern::Name => None,
+    }
+}
+
+/// Compile all patterns up front, so that an invalid bundle is rejected before
+/// anything is imported.
+fn compile_patterns<'a>(
+    patterns: impl Iterator<Item = Option<&'a str>>,
+) -> Result<Vec<Option<regex::Regex>>> {
+    let mut out = Vec::new();
+
+    for pattern in patterns {
+        out.push(pattern.map(regex::Regex::new).transpose()?);
+    }
+
+    Ok(out)
+}
@@ -1,5 +1,6 @@
+use crate::auth::Scope;
 use crate::injector;
-use crate::web::EMPTY;
+use crate::web::{Access, EMPTY};
 use anyhow::{bail, Result};
 use tokio::sync::RwLockReadGuard;
 use warp::body;
@@ -20,6 +21,7 @@ pub struct Cache(injector::Var<Option<crate::storage::Cache>>);
 impl Cache {
     pub fn route(
         cache: injector::Var<Option<crate::storage::Cache>>,
+        access: &Access,
     ) -> filters::BoxedFilter<(impl warp::Reply,)> {
         let api = Cache(cache);
 
@@ -34,6 +36,7 @@ impl Cache {
             .boxed();
 
         let delete = warp::delete()
+            .and(access.scope(Scope::WebEdit))
             .and(path::end().and(body::json()).and_then({
                 move |body: DeleteRequest| {
                     let api = api.clone();
@@ -0,0 +1,132 @@
+use crate::auth::Scope;
+use crate::injector;
+use crate::stream_info;
+use crate::web::{Access, EMPTY};
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+#[derive(serde::Deserialize)]
+struct PutTitle {
+    title: String,
+}
+
+#[derive(serde::Deserialize)]
+struct PutGame {
+    game: String,
+}
+
+#[derive(serde::Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+/// Endpoints to read and modify the channel of the streamer.
+#[derive(Clone)]
+pub struct Channel(injector::Var<Option<stream_info::Channel>>);
+
+impl Channel {
+    pub fn route(
+        channel: injector::Var<Option<stream_info::Channel>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Channel(channel);
+
+        let info = warp::get()
+            .and(path::end())
+            .and(access.scope(Scope::Title))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.info().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let title = warp::put()
+            .and(path!("title").and(path::end()))
+            .and(access.scope(Scope::TitleEdit))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: PutTitle| {
+                    let api = api.clone();
+                    async move { api.set_title(body).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let game = warp::put()
+            .and(path!("game").and(path::end()))
+            .and(access.scope(Scope::GameEdit))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: PutGame| {
+                    let api = api.clone();
+                    async move { api.set_game(body).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let games = warp::get()
+            .and(path!("games").and(path::end()))
+            .and(access.scope(Scope::GameEdit))
+            .and(warp::query::<SearchQuery>())
+            .and_then({
+                move |query: SearchQuery| {
+                    let api = api.clone();
+                    async move { api.search_games(query).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("channel")
+            .and(info.or(title).or(game).or(games))
+            .boxed()
+    }
+
+    /// Access the underlying channel.
+    async fn channel(&self) -> Result<RwLockReadGuard<'_, stream_info::Channel>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("channel not available, is the bot connected to Twitch?"),
+        }
+    }
+
+    /// Get the current title and game.
+    async fn info(&self) -> Result<impl warp::Reply> {
+        let info = self.channel().await?.info();
+        Ok(warp::reply::json(&info))
+    }
+
+    /// Set the title.
+    async fn set_title(&self, body: PutTitle) -> Result<impl warp::Reply> {
+        let title = body.title.trim();
+
+        if title.is_empty() {
+            bail!("title must not be empty");
+        }
+
+        self.channel().await?.set_title(title).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Set the game to the one best matching the given name.
+    async fn set_game(&self, body: PutGame) -> Result<impl warp::Reply> {
+        match self.channel().await?.set_game(body.game.trim()).await? {
+            Some(game) => Ok(warp::reply::json(&game)),
+            None => bail!("no such game: {}", body.game),
+        }
+    }
+
+    /// Search for games by name.
+    async fn search_games(&self, query: SearchQuery) -> Result<impl warp::Reply> {
+        let games = self.channel().await?.search_games(&query.q).await?;
+        Ok(warp::reply::json(&games))
+    }
+}
@@ -1,7 +1,10 @@
+use crate::auth::{Role, Scope};
 use crate::bus;
 use crate::message_log;
-use crate::web::EMPTY;
+use crate::web::{Access, EMPTY};
+use anyhow::bail;
 use std::sync::Arc;
+use warp::body;
 use warp::filters;
 use warp::path;
 use warp::Filter as _;
@@ -11,6 +14,17 @@ struct CommandQuery {
     command: String,
 }
 
+#[derive(serde::Deserialize)]
+struct SendBody {
+    message: String,
+}
+
+#[derive(serde::Deserialize)]
+struct RunBody {
+    /// The command to run with its arguments, like `song request <track>`.
+    command: String,
+}
+
 /// Chat endpoint.
 #[derive(Clone)]
 pub struct Chat {
@@ -22,6 +36,7 @@ impl Chat {
     pub fn route(
         bus: Arc<bus::Bus<bus::Command>>,
         message_log: message_log::MessageLog,
+        access: &Access,
     ) -> filters::BoxedFilter<(impl warp::Reply,)> {
         let api = Self { bus, message_log };
 
@@ -39,6 +54,7 @@ impl Chat {
         let messages = warp::get()
             .and(warp::path("messages").and(path::end()))
             .and_then({
+                let api = api.clone();
                 move || {
                     let api = api.clone();
                     async move { api.messages().await.map_err(super::custom_reject) }
@@ -46,7 +62,48 @@ impl Chat {
             })
             .boxed();
 
-        warp::path("chat").and(command.or(messages)).boxed()
+        let log = warp::get()
+            .and(path!("log").and(path::end()))
+            .and(access.scope(Scope::WebChatLog))
+            .and(warp::query::<message_log::Search>())
+            .and_then({
+                let api = api.clone();
+                move |search: message_log::Search| {
+                    let api = api.clone();
+                    async move { api.log(search).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let send = warp::post()
+            .and(path!("send").and(path::end()))
+            .and(access.scope(Scope::WebChatSend))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: SendBody| {
+                    let api = api.clone();
+                    async move { api.send(body).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let run = warp::post()
+            .and(path!("run").and(path::end()))
+            .and(access.scope(Scope::WebCommandRun))
+            .and(access.request_role())
+            .and(body::json())
+            .and_then({
+                move |role: Role, body: RunBody| {
+                    let api = api.clone();
+                    async move { api.run(role, body).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let chat = warp::path("chat").and(command.or(messages).or(log).or(send));
+        let command = warp::path("command").and(run);
+        chat.or(command).boxed()
     }
 
     /// Run a command.
@@ -65,4 +122,45 @@ impl Chat {
         let messages = self.message_log.messages().await;
         Ok(warp::reply::json(&*messages))
     }
+
+    /// Search the chat log.
+    async fn log(&self, search: message_log::Search) -> Result<impl warp::Reply, anyhow::Error> {
+        let messages = self.message_log.search(search).await?;
+        Ok(warp::reply::json(&messages))
+    }
+
+    /// Send a message to chat.
+    async fn send(&self, body: SendBody) -> Result<impl warp::Reply, anyhow::Error> {
+        let message = body.message.trim();
+
+        if message.is_empty() {
+            bail!("message must not be empty");
+        }
+
+        self.bus
+            .send(bus::Command::Send {
+                message: message.to_string(),
+            })
+            .await;
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Run a registered command, subject to the scopes of the given role.
+    async fn run(&self, role: Role, body: RunBody) -> Result<impl warp::Reply, anyhow::Error> {
+        let command = body.command.trim();
+
+        if command.trim_start_matches('!').is_empty() {
+            bail!("command must not be empty");
+        }
+
+        self.bus
+            .send(bus::Command::Run {
+                command: command.to_string(),
+                role,
+            })
+            .await;
+
+        Ok(warp::reply::json(&EMPTY))
+    }
 }
@@ -0,0 +1,62 @@
+use crate::chat_stats::StreamSummary;
+use crate::injector;
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// How many past streams to include.
+const HISTORY_LIMIT: i64 = 20;
+
+#[derive(serde::Serialize)]
+struct Streams {
+    /// Chat activity for the current stream.
+    current: StreamSummary,
+    /// Chat activity for past streams, most recent first.
+    history: Vec<StreamSummary>,
+}
+
+/// Chat statistics endpoints.
+#[derive(Clone)]
+pub struct ChatStats(injector::Var<Option<crate::chat_stats::ChatStats>>);
+
+impl ChatStats {
+    pub fn route(
+        chat_stats: injector::Var<Option<crate::chat_stats::ChatStats>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = ChatStats(chat_stats);
+
+        let streams = warp::get()
+            .and(path!("streams").and(path::end()))
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.streams().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("chat-stats").and(streams).boxed()
+    }
+
+    /// Access underlying chat statistics.
+    async fn chat_stats(&self) -> Result<RwLockReadGuard<'_, crate::chat_stats::ChatStats>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("chat stats not configured"),
+        }
+    }
+
+    /// Get chat activity for the current and past streams.
+    async fn streams(&self) -> Result<impl warp::Reply> {
+        let chat_stats = self.chat_stats().await?;
+
+        let streams = Streams {
+            current: chat_stats.current_stream(),
+            history: chat_stats.stream_history(HISTORY_LIMIT).await?,
+        };
+
+        Ok(warp::reply::json(&streams))
+    }
+}
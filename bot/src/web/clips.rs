@@ -0,0 +1,48 @@
+use crate::db;
+use crate::injector;
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// The number of clips to list.
+const LIST_LIMIT: i64 = 100;
+
+/// Clip history endpoints.
+#[derive(Clone)]
+pub struct Clips(injector::Var<Option<db::Clips>>);
+
+impl Clips {
+    pub fn route(
+        clips: injector::Var<Option<db::Clips>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Clips(clips);
+
+        let list = warp::get()
+            .and(path::end())
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("clips").and(list).boxed()
+    }
+
+    /// Access underlying clip storage.
+    async fn clips(&self) -> Result<RwLockReadGuard<'_, db::Clips>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("clips not configured"),
+        }
+    }
+
+    /// List the most recently created clips.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let clips = self.clips().await?.list(LIST_LIMIT).await?;
+        Ok(warp::reply::json(&clips))
+    }
+}
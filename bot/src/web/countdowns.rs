@@ -0,0 +1,46 @@
+use crate::countdowns;
+use crate::injector;
+use anyhow::{bail, Result};
+use chrono::Utc;
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// Endpoints to see which countdowns are running.
+#[derive(Clone)]
+pub struct Countdowns(injector::Var<Option<countdowns::Countdowns>>);
+
+impl Countdowns {
+    pub fn route(
+        countdowns: injector::Var<Option<countdowns::Countdowns>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Countdowns(countdowns);
+
+        let list = warp::get()
+            .and(path::end())
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("countdowns").and(list).boxed()
+    }
+
+    /// Access underlying countdowns.
+    async fn countdowns(&self) -> Result<RwLockReadGuard<'_, countdowns::Countdowns>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("countdowns not configured"),
+        }
+    }
+
+    /// List all running countdowns, the one ending first first.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let statuses = self.countdowns().await?.statuses(Utc::now());
+        Ok(warp::reply::json(&statuses))
+    }
+}
@@ -0,0 +1,221 @@
+//! Minimal endpoints intended for Stream Deck "Website" actions.
+//!
+//! Every action is a plain `POST` request without a body. Actions always
+//! have to be authenticated through an API key, either through the
+//! `X-Api-Key` header or the `key` query parameter, or an editor session.
+//! Requests without either are never treated as coming from the streamer, so
+//! that other web pages can't trigger actions.
+
+use crate::auth::{Role, Scope};
+use crate::bus;
+use crate::injector;
+use crate::player;
+use crate::utils::Duration;
+use crate::web::{Access, EMPTY};
+use anyhow::{bail, Result};
+use std::sync::Arc;
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// The template used for countdowns if none is specified.
+const DEFAULT_COUNTDOWN_TEXT: &str = "{{remaining}}";
+
+/// An action which can be triggered from a Stream Deck.
+#[derive(Debug, serde::Serialize)]
+struct Action {
+    /// The identifier of the action, which is also the last segment of its
+    /// path.
+    id: &'static str,
+    title: &'static str,
+    description: &'static str,
+    /// Query parameters accepted by the action, in addition to `key`.
+    params: &'static [&'static str],
+}
+
+/// All available actions.
+const ACTIONS: &[Action] = &[
+    Action {
+        id: "play-pause",
+        title: "Play / Pause",
+        description: "Toggle playback of the player.",
+        params: &[],
+    },
+    Action {
+        id: "skip",
+        title: "Skip Song",
+        description: "Skip the current song.",
+        params: &[],
+    },
+    Action {
+        id: "countdown",
+        title: "Start Countdown",
+        description: "Start a countdown for `duration`, like `5m`, rendered using `text`.",
+        params: &["duration", "text"],
+    },
+    Action {
+        id: "countdown-clear",
+        title: "Clear Countdown",
+        description: "Clear the current countdown.",
+        params: &[],
+    },
+    Action {
+        id: "clip",
+        title: "Create Clip",
+        description: "Create a clip of the stream.",
+        params: &[],
+    },
+];
+
+#[derive(serde::Serialize)]
+struct ActionEntry {
+    #[serde(flatten)]
+    action: &'static Action,
+    /// The method to use to trigger the action.
+    method: &'static str,
+    /// The path to request to trigger the action.
+    path: String,
+}
+
+#[derive(serde::Deserialize)]
+struct CountdownQuery {
+    duration: Duration,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+/// Stream Deck endpoints.
+#[derive(Clone)]
+pub struct Deck {
+    player: injector::Var<Option<player::Player>>,
+    bus: Arc<bus::Bus<bus::Command>>,
+}
+
+impl Deck {
+    pub fn route(
+        player: injector::Var<Option<player::Player>>,
+        bus: Arc<bus::Bus<bus::Command>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Deck { player, bus };
+
+        let list = warp::get().and(path::end()).map(|| {
+            let actions = ACTIONS
+                .iter()
+                .map(|action| ActionEntry {
+                    action,
+                    method: "POST",
+                    path: format!("/api/deck/{}", action.id),
+                })
+                .collect::<Vec<_>>();
+
+            warp::reply::json(&actions)
+        });
+
+        let play_pause = warp::post()
+            .and(path!("play-pause").and(path::end()))
+            .and(access.authenticated_scope(Scope::PlayerControl))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.play_pause().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let skip = warp::post()
+            .and(path!("skip").and(path::end()))
+            .and(access.authenticated_scope(Scope::PlayerControl))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.skip().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let countdown = warp::post()
+            .and(path!("countdown").and(path::end()))
+            .and(access.authenticated_scope(Scope::WebCommandRun))
+            .and(access.authenticated_role())
+            .and(warp::query::<CountdownQuery>())
+            .and_then({
+                let api = api.clone();
+                move |role: Role, query: CountdownQuery| {
+                    let api = api.clone();
+                    let text = query
+                        .text
+                        .unwrap_or_else(|| DEFAULT_COUNTDOWN_TEXT.to_string());
+                    let command = format!("countdown set {} {}", query.duration, text);
+                    async move { api.run(role, command).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let countdown_clear = warp::post()
+            .and(path!("countdown-clear").and(path::end()))
+            .and(access.authenticated_scope(Scope::WebCommandRun))
+            .and(access.authenticated_role())
+            .and_then({
+                let api = api.clone();
+                move |role: Role| {
+                    let api = api.clone();
+                    let command = String::from("countdown clear");
+                    async move { api.run(role, command).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let clip = warp::post()
+            .and(path!("clip").and(path::end()))
+            .and(access.authenticated_scope(Scope::WebCommandRun))
+            .and(access.authenticated_role())
+            .and_then({
+                move |role: Role| {
+                    let api = api.clone();
+                    let command = String::from("clip");
+                    async move { api.run(role, command).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("deck")
+            .and(
+                list.or(play_pause)
+                    .or(skip)
+                    .or(countdown)
+                    .or(countdown_clear)
+                    .or(clip),
+            )
+            .boxed()
+    }
+
+    /// Access underlying player abstraction.
+    async fn player(&self) -> Result<RwLockReadGuard<'_, player::Player>> {
+        match RwLockReadGuard::try_map(self.player.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("player not configured"),
+        }
+    }
+
+    /// Toggle playback.
+    async fn play_pause(&self) -> Result<impl warp::Reply> {
+        self.player().await?.toggle().await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Skip the current song.
+    async fn skip(&self) -> Result<impl warp::Reply> {
+        self.player().await?.skip().await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Run the given command, subject to the scopes of the given role.
+    async fn run(&self, role: Role, command: String) -> Result<impl warp::Reply> {
+        self.bus.send(bus::Command::Run { command, role }).await;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
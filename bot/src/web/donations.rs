@@ -0,0 +1,88 @@
+use crate::auth::Scope;
+use crate::db;
+use crate::injector;
+use crate::web::Access;
+use anyhow::{bail, Result};
+use chrono::NaiveDateTime;
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// The number of recent donations to list.
+const RECENT_LIMIT: i64 = 50;
+/// The number of top donors to list for each currency.
+const TOP_LIMIT: usize = 10;
+
+#[derive(serde::Serialize)]
+struct Summary {
+    recent: Vec<db::Donation>,
+    totals: Vec<db::DonationTotal>,
+    top: Vec<db::DonationTotal>,
+    /// Totals for the most recent stream which received donations.
+    stream: Option<StreamSummary>,
+}
+
+#[derive(serde::Serialize)]
+struct StreamSummary {
+    started_at: NaiveDateTime,
+    totals: Vec<db::DonationTotal>,
+    top: Vec<db::DonationTotal>,
+}
+
+/// Donation endpoints.
+#[derive(Clone)]
+pub struct Donations(injector::Var<Option<db::Donations>>);
+
+impl Donations {
+    pub fn route(
+        donations: injector::Var<Option<db::Donations>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Donations(donations);
+
+        let summary = warp::get()
+            .and(path::end())
+            .and(access.scope(Scope::WebDonations))
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.summary().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("donations").and(summary).boxed()
+    }
+
+    /// Access underlying donation storage.
+    async fn donations(&self) -> Result<RwLockReadGuard<'_, db::Donations>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("donations not configured"),
+        }
+    }
+
+    /// Summarize recent donations, and donation totals.
+    async fn summary(&self) -> Result<impl warp::Reply> {
+        let donations = self.donations().await?;
+
+        let stream = match donations.latest_stream().await? {
+            Some(started_at) => Some(StreamSummary {
+                started_at,
+                totals: donations.totals(Some(started_at)).await?,
+                top: donations.top(Some(started_at), TOP_LIMIT).await?,
+            }),
+            None => None,
+        };
+
+        let summary = Summary {
+            recent: donations.list(RECENT_LIMIT).await?,
+            totals: donations.totals(None).await?,
+            top: donations.top(None, TOP_LIMIT).await?,
+            stream,
+        };
+
+        Ok(warp::reply::json(&summary))
+    }
+}
@@ -0,0 +1,264 @@
+use super::proxy::{Client, Proxy};
+use crate::api;
+use crate::settings;
+use crate::utils;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng as _;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// Cookie holding the session of a logged in editor.
+pub(crate) const SESSION_COOKIE: &str = "oxidize-session";
+
+/// How long a user has to complete a login with Twitch.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Path that Twitch redirects to after a login.
+const CALLBACK_PATH: &str = "/api/editors/callback";
+
+/// Query parameters provided by Twitch when a login completes.
+#[derive(Debug, serde::Deserialize)]
+struct CallbackQuery {
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// The session of a logged in editor.
+#[derive(Debug, Clone)]
+struct Session {
+    login: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Editors are designated Twitch users who can log into the web interface.
+///
+/// Their sessions are granted the `@editor` role, which only gives access to a
+/// restricted set of scopes.
+#[derive(Clone)]
+pub(crate) struct Editors {
+    proxy: Proxy,
+    users: settings::Var<Vec<String>>,
+    client_id: settings::Var<Option<String>>,
+    client_secret: settings::Var<Option<String>>,
+    session_duration: settings::Var<utils::Duration>,
+    sessions: Arc<RwLock<HashMap<String, Session>>>,
+    /// Logins waiting to be completed, keyed by their state parameter.
+    pending: Arc<RwLock<HashMap<String, Instant>>>,
+}
+
+impl Editors {
+    /// Construct a new editors configuration.
+    pub(crate) async fn new(proxy: Proxy, settings: &settings::Settings) -> Result<Self> {
+        let settings = settings.scoped("web/editors");
+
+        Ok(Self {
+            proxy,
+            users: settings.var("users", Vec::new()).await?,
+            client_id: settings.optional("client-id").await?,
+            client_secret: settings.optional("client-secret").await?,
+            session_duration: settings
+                .var("session-duration", utils::Duration::hours(12))
+                .await?,
+            sessions: Default::default(),
+            pending: Default::default(),
+        })
+    }
+
+    /// Get the login of the editor with the given session.
+    ///
+    /// Returns `None` if the session has expired, or if the user is no longer
+    /// an editor.
+    pub(crate) async fn editor(&self, session: &str) -> Option<String> {
+        let now = Utc::now();
+
+        let login = {
+            let mut sessions = self.sessions.write().await;
+            sessions.retain(|_, s| s.expires_at > now);
+            sessions.get(session)?.login.clone()
+        };
+
+        if !self.is_editor(&login).await {
+            return None;
+        }
+
+        Some(login)
+    }
+
+    /// Test if the given login belongs to a designated editor.
+    async fn is_editor(&self, login: &str) -> bool {
+        self.users
+            .load()
+            .await
+            .iter()
+            .any(|u| u.trim_start_matches('@').eq_ignore_ascii_case(login))
+    }
+
+    pub(crate) fn route(&self) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let login = warp::get()
+            .and(path!("login").and(path::end()))
+            .and(self.proxy.client())
+            .and_then({
+                let api = self.clone();
+                move |client: Client| {
+                    let api = api.clone();
+                    async move { api.login(client).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let callback = warp::get()
+            .and(path!("callback").and(path::end()))
+            .and(warp::query::<CallbackQuery>())
+            .and(self.proxy.client())
+            .and_then({
+                let api = self.clone();
+                move |query: CallbackQuery, client: Client| {
+                    let api = api.clone();
+                    async move {
+                        api.callback(query, client)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let logout = warp::post()
+            .and(path!("logout").and(path::end()))
+            .and(warp::cookie::optional(SESSION_COOKIE))
+            .and_then({
+                let api = self.clone();
+                move |session: Option<String>| {
+                    let api = api.clone();
+                    async move { api.logout(session).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("editors")
+            .and(login.or(callback).or(logout))
+            .boxed()
+    }
+
+    /// Build the URI Twitch should redirect to after a login.
+    async fn redirect_uri(&self, client: &Client) -> String {
+        format!("{}{}", self.proxy.base_url(client).await, CALLBACK_PATH)
+    }
+
+    /// Start a login by redirecting to Twitch.
+    async fn login(&self, client: Client) -> Result<impl warp::Reply> {
+        let client_id = match self.client_id.load().await {
+            Some(client_id) => client_id,
+            None => bail!("editor logins are not configured"),
+        };
+
+        let state = random_string();
+
+        {
+            let mut pending = self.pending.write().await;
+            pending.retain(|_, at| at.elapsed() < LOGIN_TIMEOUT);
+            pending.insert(state.clone(), Instant::now());
+        }
+
+        let redirect_uri = self.redirect_uri(&client).await;
+        let url = api::twitch::login_url(&client_id, &redirect_uri, &state)?;
+        Ok(warp::redirect::temporary(
+            url.as_str().parse::<warp::http::Uri>()?,
+        ))
+    }
+
+    /// Complete a login and start a session if the user is an editor.
+    async fn callback(&self, query: CallbackQuery, client: Client) -> Result<impl warp::Reply> {
+        if let Some(error) = query.error_description {
+            bail!("login failed: {}", error);
+        }
+
+        let (code, state) = match (query.code, query.state) {
+            (Some(code), Some(state)) => (code, state),
+            _ => bail!("missing code or state"),
+        };
+
+        match self.pending.write().await.remove(&state) {
+            Some(at) if at.elapsed() < LOGIN_TIMEOUT => (),
+            _ => bail!("login expired or not started here, please try again"),
+        }
+
+        let (client_id, client_secret) =
+            match (self.client_id.load().await, self.client_secret.load().await) {
+                (Some(client_id), Some(client_secret)) => (client_id, client_secret),
+                _ => bail!("editor logins are not configured"),
+            };
+
+        let redirect_uri = self.redirect_uri(&client).await;
+        let user = api::twitch::login(&client_id, &client_secret, &redirect_uri, &code).await?;
+
+        if !self.is_editor(&user.login).await {
+            log::warn!(
+                "{} tried to log in as an editor from {}",
+                user.login,
+                client
+            );
+            bail!("{} is not an editor", user.login);
+        }
+
+        let duration = self.session_duration.load().await;
+        let session = random_string();
+
+        self.sessions.write().await.insert(
+            session.clone(),
+            Session {
+                login: user.login.clone(),
+                expires_at: Utc::now() + duration.as_chrono(),
+            },
+        );
+
+        log::info!("{} logged in as an editor from {}", user.login, client);
+
+        let cookie = format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}",
+            SESSION_COOKIE,
+            session,
+            duration.num_seconds()
+        );
+
+        let base_url = self.proxy.base_url(&client).await;
+        let redirect = warp::redirect::temporary(base_url.parse::<warp::http::Uri>()?);
+        Ok(warp::reply::with_header(redirect, "set-cookie", cookie))
+    }
+
+    /// End the current session.
+    async fn logout(&self, session: Option<String>) -> Result<impl warp::Reply> {
+        if let Some(session) = session {
+            self.sessions.write().await.remove(&session);
+        }
+
+        let cookie = format!(
+            "{}=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0",
+            SESSION_COOKIE
+        );
+        Ok(warp::reply::with_header(
+            warp::reply::json(&super::EMPTY),
+            "set-cookie",
+            cookie,
+        ))
+    }
+}
+
+/// Generate a random string suitable for states and session keys.
+fn random_string() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .collect()
+}
@@ -0,0 +1,134 @@
+use crate::auth::Scope;
+use crate::db;
+use crate::injector;
+use crate::template::Template;
+use crate::web::{Access, Fragment, EMPTY};
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+#[derive(serde::Deserialize)]
+struct AddAnswer {
+    answer: String,
+    #[serde(default = "default_weight")]
+    weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Endpoints to manage the answers of the magic 8 ball.
+#[derive(Clone)]
+pub struct EightBall(injector::Var<Option<db::EightBallAnswers>>);
+
+impl EightBall {
+    pub fn route(
+        answers: injector::Var<Option<db::EightBallAnswers>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = EightBall(answers);
+
+        let list = warp::get()
+            .and(path!(Fragment).and(path::end()))
+            .and(access.scope(Scope::WebEightBall))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let add = warp::put()
+            .and(path!(Fragment).and(path::end()))
+            .and(access.scope(Scope::WebEightBall))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, body: AddAnswer| {
+                    let api = api.clone();
+                    async move {
+                        api.add(channel.as_str(), body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let remove = warp::delete()
+            .and(path!(Fragment / i32).and(path::end()))
+            .and(access.scope(Scope::WebEightBall))
+            .and_then({
+                move |channel: Fragment, id: i32| {
+                    let api = api.clone();
+                    async move {
+                        api.remove(channel.as_str(), id)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        warp::path("8ball").and(list.or(add).or(remove)).boxed()
+    }
+
+    /// Access underlying answer storage.
+    async fn answers(&self) -> Result<RwLockReadGuard<'_, db::EightBallAnswers>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("8 ball answers not configured"),
+        }
+    }
+
+    /// List all custom answers.
+    async fn list(&self, channel: &str) -> Result<impl warp::Reply> {
+        let answers = self.answers().await?.list(channel).await?;
+        Ok(warp::reply::json(&answers))
+    }
+
+    /// Add a custom answer.
+    async fn add(&self, channel: &str, body: AddAnswer) -> Result<impl warp::Reply> {
+        let answer = body.answer.trim();
+
+        if answer.is_empty() {
+            bail!("answer is empty");
+        }
+
+        if body.weight == 0 || body.weight > db::EightBallAnswers::MAX_WEIGHT {
+            bail!(
+                "weight must be between 1 and {}",
+                db::EightBallAnswers::MAX_WEIGHT
+            );
+        }
+
+        if let Err(e) = Template::compile(answer) {
+            bail!("bad answer template: {}", e);
+        }
+
+        self.answers()
+            .await?
+            .add(channel, answer, body.weight)
+            .await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Remove a custom answer.
+    async fn remove(&self, channel: &str, id: i32) -> Result<impl warp::Reply> {
+        if !self.answers().await?.remove(channel, id).await? {
+            bail!("no answer with id `{}`", id);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
@@ -0,0 +1,79 @@
+use crate::injector;
+use crate::stream_info;
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// How many emotes to include by default.
+const DEFAULT_LIMIT: usize = 20;
+/// The most emotes which can be requested at once.
+const MAX_LIMIT: usize = 100;
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct TopQuery {
+    /// The channel to get emote usage for. Defaults to the channel of the
+    /// streamer.
+    #[serde(default)]
+    channel: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// Emote statistics endpoints.
+#[derive(Clone)]
+pub struct EmoteStats {
+    emote_stats: injector::Var<Option<crate::emote_stats::EmoteStats>>,
+    channel: injector::Var<Option<stream_info::Channel>>,
+}
+
+impl EmoteStats {
+    pub fn route(
+        emote_stats: injector::Var<Option<crate::emote_stats::EmoteStats>>,
+        channel: injector::Var<Option<stream_info::Channel>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = EmoteStats {
+            emote_stats,
+            channel,
+        };
+
+        warp::get()
+            .and(path!("stats" / "emotes").and(path::end()))
+            .and(
+                warp::query::<TopQuery>()
+                    .or(warp::any().map(TopQuery::default))
+                    .unify(),
+            )
+            .and_then({
+                move |query: TopQuery| {
+                    let api = api.clone();
+                    async move { api.top(query).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed()
+    }
+
+    /// Access underlying emote statistics.
+    async fn emote_stats(&self) -> Result<RwLockReadGuard<'_, crate::emote_stats::EmoteStats>> {
+        match RwLockReadGuard::try_map(self.emote_stats.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("emote stats not configured"),
+        }
+    }
+
+    /// Get the most used emotes in a channel over the rolling window.
+    async fn top(&self, query: TopQuery) -> Result<impl warp::Reply> {
+        let channel = match query.channel {
+            Some(channel) => channel,
+            None => match self.channel.load().await {
+                Some(channel) => channel.user().name.clone(),
+                None => bail!("not connected to a channel"),
+            },
+        };
+
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        let emotes = self.emote_stats().await?.top(&channel, limit).await?;
+        Ok(warp::reply::json(&emotes))
+    }
+}
@@ -0,0 +1,48 @@
+use crate::auth::Scope;
+use crate::injector;
+use crate::web::Access;
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// Endpoints for looking up errors by their reference code.
+#[derive(Clone)]
+pub struct Errors(injector::Var<Option<crate::errors::Errors>>);
+
+impl Errors {
+    pub fn route(
+        errors: injector::Var<Option<crate::errors::Errors>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Errors(errors);
+
+        let get = warp::get()
+            .and(path!(String).and(path::end()))
+            .and(access.scope(Scope::WebErrors))
+            .and_then({
+                move |code: String| {
+                    let api = api.clone();
+                    async move { api.get(&code).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("errors").and(get).boxed()
+    }
+
+    /// Access underlying error storage.
+    async fn errors(&self) -> Result<RwLockReadGuard<'_, crate::errors::Errors>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("errors not configured"),
+        }
+    }
+
+    /// Get the error with the given reference code.
+    async fn get(&self, code: &str) -> Result<impl warp::Reply> {
+        let entry = self.errors().await?.get(code).await;
+        Ok(warp::reply::json(&entry))
+    }
+}
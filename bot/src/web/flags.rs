@@ -0,0 +1,43 @@
+use crate::injector;
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// Feature flag endpoints.
+#[derive(Clone)]
+pub struct Flags(injector::Var<Option<crate::flags::Flags>>);
+
+impl Flags {
+    pub fn route(
+        flags: injector::Var<Option<crate::flags::Flags>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Flags(flags);
+
+        let list = warp::get()
+            .and(path::end())
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("flags").and(list).boxed()
+    }
+
+    /// Access underlying feature flags.
+    async fn flags(&self) -> Result<RwLockReadGuard<'_, crate::flags::Flags>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("flags not configured"),
+        }
+    }
+
+    /// List the state of all known feature flags.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        Ok(warp::reply::json(&self.flags().await?.list()))
+    }
+}
@@ -0,0 +1,74 @@
+use crate::auth::Scope;
+use crate::groups;
+use crate::injector;
+use crate::web::Access;
+use anyhow::Result;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// Group endpoints.
+#[derive(Clone)]
+pub struct Groups(groups::Groups);
+
+impl Groups {
+    pub async fn route(
+        injector: &injector::Injector,
+        access: &Access,
+    ) -> Result<filters::BoxedFilter<(impl warp::Reply,)>> {
+        let api = Groups(groups::Groups::new(injector).await?);
+
+        let list = warp::get()
+            .and(path!(String).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: String| {
+                    let api = api.clone();
+                    async move { api.list(&channel).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let edit_disabled = warp::post()
+            .and(path!(String / String / "disabled").and(path::end()))
+            .and(access.scope(Scope::WebEdit))
+            .and(body::json())
+            .and_then({
+                move |channel: String, group: String, body: super::DisabledBody| {
+                    let api = api.clone();
+
+                    async move {
+                        api.edit_disabled(&channel, &group, body.disabled)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        Ok(warp::path("groups").and(list.or(edit_disabled)).boxed())
+    }
+
+    /// List all groups in the given channel.
+    async fn list(&self, channel: &str) -> Result<impl warp::Reply> {
+        let groups = self.0.list(channel).await?;
+        Ok(warp::reply::json(&groups))
+    }
+
+    /// Enable or disable everything in the given group.
+    async fn edit_disabled(
+        &self,
+        channel: &str,
+        group: &str,
+        disabled: bool,
+    ) -> Result<impl warp::Reply> {
+        if disabled {
+            self.0.disable(channel, group).await?;
+        } else {
+            self.0.enable(channel, group).await?;
+        }
+
+        Ok(warp::reply::json(&super::EMPTY))
+    }
+}
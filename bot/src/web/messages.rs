@@ -0,0 +1,148 @@
+use crate::auth::Scope;
+use crate::injector;
+use crate::messages::{setting_key, Messages as MessageCatalog};
+use crate::template::Template;
+use crate::web::{Access, Fragment, EMPTY};
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::{body, filters, path, Filter as _};
+
+#[derive(serde::Deserialize)]
+pub struct PutMessage {
+    template: String,
+}
+
+#[derive(serde::Serialize)]
+struct Language<'a> {
+    id: &'a str,
+    title: &'a str,
+}
+
+#[derive(serde::Serialize)]
+struct List<'a> {
+    language: String,
+    languages: Vec<Language<'a>>,
+    messages: Vec<crate::messages::Message<'a>>,
+}
+
+/// Endpoints to review and override chat messages.
+#[derive(Clone)]
+pub struct Messages {
+    messages: injector::Var<Option<MessageCatalog>>,
+    settings: injector::Var<Option<crate::settings::Settings>>,
+}
+
+impl Messages {
+    pub fn route(
+        messages: injector::Var<Option<MessageCatalog>>,
+        settings: injector::Var<Option<crate::settings::Settings>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Messages { messages, settings };
+
+        let list = warp::get()
+            .and(path::end())
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let edit = warp::put()
+            .and(access.scope(Scope::WebSettings))
+            .and(path::tail().and(body::json()).and_then({
+                let api = api.clone();
+                move |key: path::Tail, body: PutMessage| {
+                    let api = api.clone();
+
+                    async move {
+                        let key =
+                            str::parse::<Fragment>(key.as_str()).map_err(super::custom_reject)?;
+                        api.edit(key.as_str(), body.template)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            }))
+            .boxed();
+
+        let delete = warp::delete()
+            .and(access.scope(Scope::WebSettings))
+            .and(path::tail().and_then({
+                move |key: path::Tail| {
+                    let api = api.clone();
+
+                    async move {
+                        let key =
+                            str::parse::<Fragment>(key.as_str()).map_err(super::custom_reject)?;
+                        api.delete(key.as_str()).await.map_err(super::custom_reject)
+                    }
+                }
+            }))
+            .boxed();
+
+        warp::path("messages").and(list.or(edit).or(delete)).boxed()
+    }
+
+    /// Access the underlying message catalog.
+    async fn messages(&self) -> Result<RwLockReadGuard<'_, MessageCatalog>> {
+        match RwLockReadGuard::try_map(self.messages.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("messages not configured"),
+        }
+    }
+
+    /// Access underlying settings abstraction.
+    async fn settings(&self) -> Result<RwLockReadGuard<'_, crate::settings::Settings>> {
+        match RwLockReadGuard::try_map(self.settings.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("settings not configured"),
+        }
+    }
+
+    /// List all messages in the current language.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let messages = self.messages().await?;
+
+        let languages = messages
+            .catalog()
+            .languages
+            .iter()
+            .map(|(id, title)| Language { id, title })
+            .collect();
+
+        let list = List {
+            language: messages.language(),
+            languages,
+            messages: messages.list(),
+        };
+
+        Ok(warp::reply::json(&list))
+    }
+
+    /// Override the given message.
+    async fn edit(&self, key: &str, template: String) -> Result<impl warp::Reply> {
+        if !self.messages().await?.catalog().messages.contains_key(key) {
+            bail!("no message named `{}`", key);
+        }
+
+        if let Err(e) = Template::compile(&template) {
+            bail!("bad template: {}", e);
+        }
+
+        self.settings()
+            .await?
+            .set(&setting_key(key), template)
+            .await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Remove the override of the given message.
+    async fn delete(&self, key: &str) -> Result<impl warp::Reply> {
+        self.settings().await?.clear(&setting_key(key)).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
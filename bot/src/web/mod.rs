@@ -7,25 +7,72 @@ use crate::currency::Currency;
 use crate::db;
 use crate::injector;
 use crate::message_log;
+use crate::metrics;
+use crate::oauth2;
 use crate::player;
 use crate::prelude::*;
 use crate::template;
 use crate::track_id::TrackId;
 use crate::utils;
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::{RwLock, RwLockReadGuard};
 use warp::{body, filters, path, Filter as _};
 
+mod access;
+mod afk;
+mod after_streams;
+mod bundles;
 mod cache;
+mod channel;
 mod chat;
+mod chat_stats;
+mod clips;
+mod countdowns;
+mod deck;
+mod donations;
+mod editors;
+mod eight_ball;
+mod emote_stats;
+mod errors;
+mod flags;
+mod groups;
+mod messages;
+mod moderation;
+mod notifications;
+mod player;
+mod profiles;
+mod proxy;
+mod schedules;
+mod sessions;
 mod settings;
-
-use self::{cache::Cache, chat::Chat, settings::Settings};
+mod setup;
+mod stream_schedule;
+mod tokens;
+mod trivia;
+mod viewer_queue;
+
+pub(crate) use self::access::Access;
+pub use self::access::ApiKey;
+use self::access::Unauthorized;
+use self::editors::Editors;
+use self::proxy::{Client, Proxy};
+use self::{
+    afk::Afk, after_streams::AfterStreams, bundles::Bundles, cache::Cache, channel::Channel,
+    chat::Chat, chat_stats::ChatStats, clips::Clips, countdowns::Countdowns, deck::Deck,
+    donations::Donations, eight_ball::EightBall, emote_stats::EmoteStats, errors::Errors,
+    flags::Flags, groups::Groups, messages::Messages, moderation::Moderation,
+    notifications::Notifications, player::Player, profiles::Profiles, schedules::Schedules,
+    sessions::Sessions, settings::Settings, setup::Setup, stream_schedule::StreamSchedule,
+    tokens::Tokens, trivia::Trivia, viewer_queue::ViewerQueue,
+};
+
+/// The number of stream markers to list.
+const MARKERS_LIMIT: i64 = 100;
 
 pub const URL: &str = "http://localhost:12345";
 
@@ -99,6 +146,7 @@ struct Aliases(injector::Var<Option<db::Aliases>>);
 impl Aliases {
     fn route(
         aliases: injector::Var<Option<db::Aliases>>,
+        access: &Access,
     ) -> filters::BoxedFilter<(impl warp::Reply,)> {
         let api = Aliases(aliases);
 
@@ -114,6 +162,7 @@ impl Aliases {
 
         let delete = warp::delete()
             .and(path!("aliases" / Fragment / Fragment).and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and_then({
                 let api = api.clone();
                 move |channel: Fragment, name: Fragment| {
@@ -128,6 +177,7 @@ impl Aliases {
 
         let edit = warp::put()
             .and(path!("aliases" / Fragment / Fragment).and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and(body::json())
             .and_then({
                 let api = api.clone();
@@ -143,6 +193,7 @@ impl Aliases {
 
         let edit_disabled = warp::post()
             .and(path!("aliases" / Fragment / Fragment / "disabled").and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and(body::json())
             .and_then({
                 move |channel: Fragment, name: Fragment, body: DisabledBody| {
@@ -220,6 +271,7 @@ struct Commands(injector::Var<Option<db::Commands>>);
 impl Commands {
     fn route(
         commands: injector::Var<Option<db::Commands>>,
+        access: &Access,
     ) -> filters::BoxedFilter<(impl warp::Reply,)> {
         let api = Commands(commands);
 
@@ -235,6 +287,7 @@ impl Commands {
 
         let delete = warp::delete()
             .and(path!("commands" / Fragment / Fragment).and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and_then({
                 let api = api.clone();
                 move |channel: Fragment, name: Fragment| {
@@ -249,6 +302,7 @@ impl Commands {
 
         let edit_disabled = warp::post()
             .and(path!("commands" / Fragment / Fragment / "disabled").and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and(body::json())
             .and_then({
                 let api = api.clone();
@@ -265,6 +319,7 @@ impl Commands {
 
         let edit = warp::put()
             .and(path!("commands" / Fragment / Fragment).and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and(body::json())
             .and_then({
                 move |channel: Fragment, name: Fragment, body: PutCommand| {
@@ -342,6 +397,7 @@ struct Promotions(injector::Var<Option<db::Promotions>>);
 impl Promotions {
     fn route(
         promotions: injector::Var<Option<db::Promotions>>,
+        access: &Access,
     ) -> filters::BoxedFilter<(impl warp::Reply,)> {
         let api = Promotions(promotions);
 
@@ -357,6 +413,7 @@ impl Promotions {
 
         let delete = warp::delete()
             .and(path!("promotions" / Fragment / Fragment).and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and_then({
                 let api = api.clone();
                 move |channel: Fragment, name: Fragment| {
@@ -372,6 +429,7 @@ impl Promotions {
 
         let edit = warp::put()
             .and(path!("promotions" / Fragment / Fragment).and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and(body::json())
             .and_then({
                 let api = api.clone();
@@ -393,6 +451,7 @@ impl Promotions {
 
         let edit_disabled = warp::post()
             .and(path!("promotions" / Fragment / Fragment / "disabled").and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and(body::json())
             .and_then({
                 move |channel: Fragment, name: Fragment, body: DisabledBody| {
@@ -476,6 +535,7 @@ struct Themes(injector::Var<Option<db::Themes>>);
 impl Themes {
     fn route(
         themes: injector::Var<Option<db::Themes>>,
+        access: &Access,
     ) -> filters::BoxedFilter<(impl warp::Reply,)> {
         let api = Themes(themes);
 
@@ -491,6 +551,7 @@ impl Themes {
 
         let delete = warp::delete()
             .and(path!("themes" / Fragment / Fragment).and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and_then({
                 let api = api.clone();
                 move |channel: Fragment, name: Fragment| {
@@ -506,6 +567,7 @@ impl Themes {
 
         let edit = warp::put()
             .and(path!("themes" / Fragment / Fragment).and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and(body::json())
             .and_then({
                 let api = api.clone();
@@ -522,6 +584,7 @@ impl Themes {
 
         let edit_disabled = warp::post()
             .and(path!("themes" / Fragment / Fragment / "disabled").and(path::end()))
+            .and(access.scope(auth::Scope::WebEdit))
             .and(body::json())
             .and_then({
                 move |channel: Fragment, name: Fragment, body: DisabledBody| {
@@ -588,12 +651,109 @@ impl Themes {
     }
 }
 
+/// Banned tracks endpoint.
+#[derive(Clone)]
+struct BannedTracks(injector::Var<Option<db::BannedTracks>>);
+
+impl BannedTracks {
+    fn route(
+        banned_tracks: injector::Var<Option<db::BannedTracks>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = BannedTracks(banned_tracks);
+
+        let list = warp::get()
+            .and(path!("banned-tracks").and(path::end()))
+            .and(access.scope(auth::Scope::PlayerRead))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(custom_reject) }
+                }
+            });
+
+        let ban = warp::put()
+            .and(path!("banned-tracks").and(path::end()))
+            .and(access.scope(auth::Scope::PlayerControl))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: PutBan| {
+                    let api = api.clone();
+                    async move { api.ban(body).await.map_err(custom_reject) }
+                }
+            });
+
+        let unban = warp::delete()
+            .and(path!("banned-tracks" / db::BanKind / Fragment).and(path::end()))
+            .and(access.scope(auth::Scope::PlayerControl))
+            .and_then({
+                move |kind: db::BanKind, id: Fragment| {
+                    let api = api.clone();
+                    async move { api.unban(kind, id.as_str()).await.map_err(custom_reject) }
+                }
+            });
+
+        return list.or(ban).or(unban).boxed();
+    }
+
+    /// Access underlying banned tracks abstraction.
+    async fn banned_tracks(&self) -> Result<RwLockReadGuard<'_, db::BannedTracks>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("banned tracks not configured"),
+        }
+    }
+
+    /// Get the list of all bans.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let bans = self.banned_tracks().await?.list().await;
+        let bans = bans.iter().map(|b| &**b).collect::<Vec<_>>();
+        Ok(warp::reply::json(&bans))
+    }
+
+    /// Ban something.
+    async fn ban(&self, body: PutBan) -> Result<impl warp::Reply> {
+        self.banned_tracks()
+            .await?
+            .ban(
+                body.kind,
+                &body.id,
+                body.name.as_deref(),
+                body.reason.as_deref(),
+                None,
+            )
+            .await?;
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Remove a ban.
+    async fn unban(&self, kind: db::BanKind, id: &str) -> Result<impl warp::Reply> {
+        self.banned_tracks().await?.unban(kind, id).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct PutBan {
+    kind: db::BanKind,
+    id: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
 /// Auth API endpoints.
 #[derive(Clone)]
 struct Auth {
     active_connections: Arc<RwLock<HashMap<String, ConnectionMeta>>>,
+    tokens: Arc<RwLock<BTreeMap<String, oauth2::SyncToken>>>,
     auth: auth::Auth,
     settings: injector::Var<Option<crate::settings::Settings>>,
+    proxy: Proxy,
 }
 
 #[derive(serde::Deserialize)]
@@ -606,12 +766,17 @@ impl Auth {
     fn route(
         auth: auth::Auth,
         active_connections: Arc<RwLock<HashMap<String, ConnectionMeta>>>,
+        tokens: Arc<RwLock<BTreeMap<String, oauth2::SyncToken>>>,
         settings: injector::Var<Option<crate::settings::Settings>>,
+        proxy: Proxy,
+        access: &Access,
     ) -> filters::BoxedFilter<(impl warp::Reply,)> {
         let api = Auth {
             auth,
             active_connections,
+            tokens,
             settings,
+            proxy: proxy.clone(),
         };
 
         let route = warp::get()
@@ -664,6 +829,7 @@ impl Auth {
         let route = route
             .or(warp::put()
                 .and(warp::path!("grants").and(path::end()))
+                .and(access.scope(auth::Scope::WebSettings))
                 .and(body::json())
                 .and_then({
                     let api = api.clone();
@@ -681,6 +847,7 @@ impl Auth {
         let route = route
             .or(warp::delete()
                 .and(warp::path!("grants" / Fragment / Fragment).and(path::end()))
+                .and(access.scope(auth::Scope::WebSettings))
                 .and_then({
                     let api = api.clone();
                     move |scope: Fragment, role: Fragment| {
@@ -694,6 +861,24 @@ impl Auth {
                 }))
             .boxed();
 
+        let route = route
+            .or(warp::put()
+                .and(warp::path!("minimum-role").and(path::end()))
+                .and(access.scope(auth::Scope::WebSettings))
+                .and(body::json())
+                .and_then({
+                    let api = api.clone();
+                    move |body: PutMinimumRole| {
+                        let api = api.clone();
+                        async move {
+                            api.set_minimum_role(body.scope, body.role)
+                                .await
+                                .map_err(custom_reject)
+                        }
+                    }
+                }))
+            .boxed();
+
         let route = route
             .or(warp::get()
                 .and(
@@ -701,10 +886,11 @@ impl Auth {
                         .and(warp::query::<AuthKeyQuery>())
                         .and(path::end()),
                 )
+                .and(proxy.client())
                 .and_then({
-                    move |query: AuthKeyQuery| {
+                    move |query: AuthKeyQuery, client: Client| {
                         let api = api.clone();
-                        async move { api.set_key(query).await.map_err(custom_reject) }
+                        async move { api.set_key(query, client).await.map_err(custom_reject) }
                     }
                 }))
             .boxed();
@@ -716,19 +902,39 @@ impl Auth {
             scope: auth::Scope,
             role: auth::Role,
         }
+
+        #[derive(serde::Deserialize)]
+        pub struct PutMinimumRole {
+            scope: auth::Scope,
+            #[serde(default)]
+            role: Option<auth::Role>,
+        }
     }
 
     /// Get a list of things that need authentication.
     async fn connections(&self) -> Result<impl warp::Reply, Error> {
-        let active_connections = self.active_connections.read().await;
+        let active_connections = self.active_connections.read().await.clone();
+        let tokens = self.tokens.read().await.clone();
         let mut out = Vec::new();
 
-        for c in active_connections.values() {
-            out.push(c.clone());
+        for (id, meta) in active_connections {
+            let state = match tokens.get(&id) {
+                Some(token) => Some(token.state().await?),
+                None => None,
+            };
+
+            out.push(Connection { meta, state });
         }
 
-        out.sort_by(|a, b| a.title.cmp(&b.title));
-        Ok(warp::reply::json(&out))
+        out.sort_by(|a, b| a.meta.title.cmp(&b.meta.title));
+        return Ok(warp::reply::json(&out));
+
+        #[derive(serde::Serialize)]
+        struct Connection {
+            #[serde(flatten)]
+            meta: ConnectionMeta,
+            state: Option<oauth2::ConnectionState>,
+        }
     }
 
     /// Get the list of all scopes.
@@ -763,14 +969,25 @@ impl Auth {
         Ok(warp::reply::json(&EMPTY))
     }
 
-    async fn set_key(&self, key: AuthKeyQuery) -> Result<impl warp::Reply> {
+    /// Grant a scope to a minimum role in the role hierarchy.
+    async fn set_minimum_role(
+        &self,
+        scope: auth::Scope,
+        role: Option<auth::Role>,
+    ) -> Result<impl warp::Reply> {
+        self.auth.set_minimum_role(scope, role).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    async fn set_key(&self, key: AuthKeyQuery, client: Client) -> Result<impl warp::Reply> {
         let settings = self.settings.read().await;
 
         if let (Some(settings), Some(key)) = (settings.as_ref(), key.key) {
             settings.set("remote/secret-key", key.as_str()).await?;
         }
 
-        let mut parts = URL.parse::<warp::http::Uri>()?.into_parts();
+        let base_url = self.proxy.base_url(&client).await;
+        let mut parts = base_url.parse::<warp::http::Uri>()?.into_parts();
         parts.path_and_query = Some(warp::http::uri::PathAndQuery::from_static(
             "?received-key=true",
         ));
@@ -784,7 +1001,7 @@ impl Auth {
 #[derive(Clone)]
 struct Api {
     player: injector::Var<Option<player::Player>>,
-    after_streams: injector::Var<Option<db::AfterStreams>>,
+    markers: injector::Var<Option<db::Markers>>,
     currency: injector::Var<Option<Currency>>,
     latest: injector::Var<Option<api::github::Release>>,
 }
@@ -880,24 +1097,18 @@ impl Api {
         }
     }
 
-    /// Access underlying after streams abstraction.
-    async fn after_streams(&self) -> Result<RwLockReadGuard<'_, db::AfterStreams>> {
-        match RwLockReadGuard::try_map(self.after_streams.read().await, |c| c.as_ref()) {
+    /// Access underlying stream markers abstraction.
+    async fn markers(&self) -> Result<RwLockReadGuard<'_, db::Markers>> {
+        match RwLockReadGuard::try_map(self.markers.read().await, |c| c.as_ref()) {
             Ok(out) => Ok(out),
-            Err(_) => bail!("after streams not configured"),
+            Err(_) => bail!("markers not configured"),
         }
     }
 
-    /// Get the list of available after streams.
-    async fn get_after_streams(&self) -> Result<impl warp::Reply> {
-        let after_streams = self.after_streams().await?.list().await?;
-        Ok(warp::reply::json(&after_streams))
-    }
-
-    /// Get the list of available after streams.
-    async fn delete_after_stream(&self, id: i32) -> Result<impl warp::Reply> {
-        self.after_streams().await?.delete(id).await?;
-        Ok(warp::reply::json(&EMPTY))
+    /// Get the list of the most recently created stream markers.
+    async fn get_markers(&self) -> Result<impl warp::Reply> {
+        let markers = self.markers().await?.list(MARKERS_LIMIT).await?;
+        Ok(warp::reply::json(&markers))
     }
 
     /// Import balances.
@@ -985,17 +1196,26 @@ pub async fn setup(
     youtube_bus: Arc<bus::Bus<bus::YouTube>>,
     command_bus: Arc<bus::Bus<bus::Command>>,
     auth: auth::Auth,
+    settings: crate::settings::Settings,
     channel: injector::Var<Option<String>>,
     latest: injector::Var<Option<api::github::Release>>,
 ) -> Result<(Server, impl Future<Output = ()>)> {
-    let addr: SocketAddr = str::parse("0.0.0.0:12345")?;
+    let bind = settings.get_or_default::<String>("web/bind").await?;
+
+    let addr = str::parse::<SocketAddr>(&bind)
+        .map_err(|e| anyhow!("bad web bind address `{}`: {}", bind, e))?;
+
+    let proxy = Proxy::new(&settings).await?;
+    let editors = Editors::new(proxy.clone(), &settings).await?;
+    let access = Access::new(auth.clone(), proxy.clone(), editors.clone(), &settings).await?;
 
     let player = injector::Var::new(None);
     let active_connections: Arc<RwLock<HashMap<String, ConnectionMeta>>> = Default::default();
+    let tokens: Arc<RwLock<BTreeMap<String, oauth2::SyncToken>>> = Default::default();
 
     let api = Api {
         player: player.clone(),
-        after_streams: injector.var().await?,
+        markers: injector.var().await?,
         currency: injector.var().await?,
         latest,
     };
@@ -1003,6 +1223,7 @@ pub async fn setup(
     let api = {
         let route = warp::post()
             .and(path!("device" / String))
+            .and(access.scope(auth::Scope::PlayerControl))
             .and_then({
                 let api = api.clone();
                 move |id| {
@@ -1023,38 +1244,35 @@ pub async fn setup(
             .boxed();
 
         let route = route
-            .or(warp::get().and(warp::path("devices")).and_then({
-                let api = api.clone();
-                move || {
-                    let api = api.clone();
-                    async move { api.clone().devices().await.map_err(custom_reject) }
-                }
-            }))
-            .boxed();
-
-        let route = route
-            .or(warp::delete().and(path!("after-stream" / i32)).and_then({
-                let api = api.clone();
-                move |id| {
+            .or(warp::get()
+                .and(warp::path("devices"))
+                .and(access.scope(auth::Scope::PlayerRead))
+                .and_then({
                     let api = api.clone();
-                    async move { api.delete_after_stream(id).await.map_err(custom_reject) }
-                }
-            }))
+                    move || {
+                        let api = api.clone();
+                        async move { api.clone().devices().await.map_err(custom_reject) }
+                    }
+                }))
             .boxed();
 
         let route = route
-            .or(warp::get().and(warp::path("after-streams")).and_then({
-                let api = api.clone();
-                move || {
+            .or(warp::get()
+                .and(warp::path("markers"))
+                .and(access.scope(auth::Scope::WebAfterStreams))
+                .and_then({
                     let api = api.clone();
-                    async move { api.get_after_streams().await.map_err(custom_reject) }
-                }
-            }))
+                    move || {
+                        let api = api.clone();
+                        async move { api.get_markers().await.map_err(custom_reject) }
+                    }
+                }))
             .boxed();
 
         let route = route
             .or(warp::put()
                 .and(warp::path("balances"))
+                .and(access.scope(auth::Scope::WebEdit))
                 .and(body::json())
                 .and_then({
                     let api = api.clone();
@@ -1085,16 +1303,70 @@ pub async fn setup(
             .and(Auth::route(
                 auth,
                 active_connections.clone(),
+                tokens.clone(),
                 injector.var().await?,
+                proxy.clone(),
+                &access,
             ))
             .boxed());
-        let route = route.or(Aliases::route(injector.var().await?));
-        let route = route.or(Commands::route(injector.var().await?));
-        let route = route.or(Promotions::route(injector.var().await?));
-        let route = route.or(Themes::route(injector.var().await?));
-        let route = route.or(Settings::route(injector.var().await?));
-        let route = route.or(Cache::route(injector.var().await?));
-        let route = route.or(Chat::route(command_bus, message_log));
+        let route = route.or(Player::route(player.clone(), &access));
+        let route = route.or(BannedTracks::route(injector.var().await?, &access));
+        let route = route.or(Aliases::route(injector.var().await?, &access));
+        let route = route.or(Commands::route(injector.var().await?, &access));
+        let route = route.or(Promotions::route(injector.var().await?, &access));
+        let route = route.or(Themes::route(injector.var().await?, &access));
+        let route = route.or(Settings::route(injector.var().await?, &access));
+        let route = route.or(Profiles::route(injector.var().await?, &access));
+        let route = route.or(Messages::route(
+            injector.var().await?,
+            injector.var().await?,
+            &access,
+        ));
+        let route = route.or(Cache::route(injector.var().await?, &access));
+        let route = route.or(Errors::route(injector.var().await?, &access));
+        let route = route.or(ChatStats::route(injector.var().await?));
+        let route = route.or(EmoteStats::route(
+            injector.var().await?,
+            injector.var().await?,
+        ));
+        let route = route.or(Afk::route(injector.var().await?));
+        let route = route.or(Countdowns::route(injector.var().await?));
+        let route = route.or(Clips::route(injector.var().await?));
+        let route = route.or(Donations::route(injector.var().await?, &access));
+        let route = route.or(Trivia::route(injector.var().await?, &access));
+        let route = route.or(EightBall::route(injector.var().await?, &access));
+        let route = route.or(Channel::route(injector.var().await?, &access));
+        let route = route.or(ViewerQueue::route(
+            injector.var().await?,
+            injector.var().await?,
+            &access,
+        ));
+        let route = route.or(AfterStreams::route(injector.var().await?, &access));
+        let route = route.or(Sessions::route(
+            injector.var().await?,
+            injector.var().await?,
+            &access,
+        ));
+        let route = route.or(Moderation::route(
+            injector.var().await?,
+            injector.var().await?,
+            &access,
+        ));
+        let route = route.or(Flags::route(injector.var().await?));
+        let route = route.or(Notifications::route(injector.var().await?, &access));
+        let route = route.or(Schedules::route(injector.var().await?));
+        let route = route.or(StreamSchedule::route(injector.var().await?));
+        let route = route.or(Tokens::route(tokens.clone(), &access));
+        let route = route.or(Setup::route(injector.var().await?, tokens.clone(), &access));
+        let route = route.or(Bundles::route(injector, &access).await?);
+        let route = route.or(Groups::route(injector, &access).await?);
+        let route = route.or(editors.route());
+        let route = route.or(Deck::route(
+            injector.var().await?,
+            command_bus.clone(),
+            &access,
+        ));
+        let route = route.or(Chat::route(command_bus, message_log, &access));
 
         // TODO: move endpoint into abstraction thingie.
         let route = route
@@ -1127,10 +1399,36 @@ pub async fn setup(
         .and(warp::path!("ws" / "youtube"))
         .and(send_bus(youtube_bus).recover(recover));
 
+    let ws_player = warp::get()
+        .and(warp::path!("ws" / "player"))
+        .and(Player::events(player.clone(), &access).recover(recover));
+
+    let metrics = warp::get()
+        .and(path!("metrics").and(path::end()))
+        .and_then({
+            let enabled = settings.var("web/metrics", false).await?;
+            let player = player.clone();
+
+            move || {
+                let enabled = enabled.clone();
+                let player = player.clone();
+
+                async move {
+                    if !enabled.load().await {
+                        return Err(warp::reject::not_found());
+                    }
+
+                    Ok(encode_metrics(player).await)
+                }
+            }
+        });
+
     let routes = api.recover(recover);
     let routes = routes.or(ws_messages.recover(recover));
     let routes = routes.or(ws_overlay.recover(recover));
     let routes = routes.or(ws_youtube.recover(recover));
+    let routes = routes.or(ws_player.recover(recover));
+    let routes = routes.or(metrics);
 
     let fallback = Asset::get("index.html");
 
@@ -1151,6 +1449,7 @@ pub async fn setup(
     let server = Server {
         player,
         active_connections,
+        tokens,
     };
 
     return Ok((server, server_future));
@@ -1233,6 +1532,15 @@ async fn recover(err: warp::Rejection) -> Result<impl warp::Reply, warp::Rejecti
             message: msg,
         });
 
+        Ok(warp::reply::with_status(json, code))
+    } else if let Some(Unauthorized(scope)) = err.find::<Unauthorized>() {
+        let code = warp::http::StatusCode::FORBIDDEN;
+
+        let json = warp::reply::json(&ErrorMessage {
+            code: code.as_u16(),
+            message: format!("missing access to scope `{}`", scope),
+        });
+
         Ok(warp::reply::with_status(json, code))
     } else if let Some(e) = err.find::<CustomReject>() {
         // TODO: Also log which endpoint caused the error
@@ -1266,6 +1574,8 @@ pub struct Server {
     player: injector::Var<Option<player::Player>>,
     /// Callbacks for when we have received a token.
     active_connections: Arc<RwLock<HashMap<String, ConnectionMeta>>>,
+    /// Tokens which are in use, by flow id.
+    tokens: Arc<RwLock<BTreeMap<String, oauth2::SyncToken>>>,
 }
 
 impl Server {
@@ -1284,6 +1594,11 @@ impl Server {
     pub async fn clear_connection(&self, id: &str) {
         let _ = self.active_connections.write().await.remove(id);
     }
+
+    /// Register a token so that its health can be reported.
+    pub async fn register_token(&self, id: &str, token: oauth2::SyncToken) {
+        self.tokens.write().await.insert(id.to_string(), token);
+    }
 }
 
 #[derive(Debug)]
@@ -1292,6 +1607,26 @@ pub struct ReceivedToken {
     pub state: String,
 }
 
+/// Encode metrics in the Prometheus text format.
+async fn encode_metrics(player: injector::Var<Option<player::Player>>) -> impl warp::Reply {
+    let mut encoder = metrics::Encoder::default();
+    encoder.process();
+
+    if let Some(player) = player.load().await {
+        encoder.gauge(
+            "oxidize_player_queue_length",
+            "Number of songs in the player queue.",
+            player.list().await.len() as i64,
+        );
+    }
+
+    warp::reply::with_header(
+        encoder.finish(),
+        "content-type",
+        "text/plain; version=0.0.4",
+    )
+}
+
 /// Connecting a bus to a websocket connection.
 fn send_bus<T>(bus: Arc<bus::Bus<T>>) -> filters::BoxedFilter<(impl warp::Reply,)>
 where
@@ -1320,6 +1655,7 @@ async fn send_bus_forward<T>(
 where
     T: bus::Message,
 {
+    let _guard = metrics::WEBSOCKETS.guard();
     let (mut tx, _) = websocket.split();
 
     // add a receiver and forward all new messages.
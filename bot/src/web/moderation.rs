@@ -0,0 +1,112 @@
+use crate::auth::Scope;
+use crate::db;
+use crate::injector;
+use crate::stream_info;
+use crate::web::{Access, Fragment, EMPTY};
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// Endpoints to review and undo moderation actions.
+#[derive(Clone)]
+pub struct Moderation {
+    moderation_log: injector::Var<Option<db::ModerationLog>>,
+    channel: injector::Var<Option<stream_info::Channel>>,
+}
+
+impl Moderation {
+    pub fn route(
+        moderation_log: injector::Var<Option<db::ModerationLog>>,
+        channel: injector::Var<Option<stream_info::Channel>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Moderation {
+            moderation_log,
+            channel,
+        };
+
+        let list = warp::get()
+            .and(path!(Fragment).and(path::end()))
+            .and(access.scope(Scope::WebModeration))
+            .and(warp::query::<db::ModerationFilter>())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, filter: db::ModerationFilter| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_str(), filter)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let undo = warp::post()
+            .and(path!(Fragment / i32 / "undo").and(path::end()))
+            .and(access.scope(Scope::WebModeration))
+            .and_then({
+                move |channel: Fragment, id: i32| {
+                    let api = api.clone();
+                    async move {
+                        api.undo(channel.as_str(), id)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        warp::path("moderation").and(list.or(undo)).boxed()
+    }
+
+    /// Access underlying moderation log storage.
+    async fn moderation_log(&self) -> Result<RwLockReadGuard<'_, db::ModerationLog>> {
+        match RwLockReadGuard::try_map(self.moderation_log.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("moderation log not configured"),
+        }
+    }
+
+    /// List moderation actions matching the given filter.
+    async fn list(&self, channel: &str, filter: db::ModerationFilter) -> Result<impl warp::Reply> {
+        let actions = self.moderation_log().await?.list(channel, filter).await?;
+        Ok(warp::reply::json(&actions))
+    }
+
+    /// Undo a timeout or ban.
+    async fn undo(&self, channel: &str, id: i32) -> Result<impl warp::Reply> {
+        let moderation_log = self.moderation_log().await?;
+
+        let action = match moderation_log.get(channel, id).await? {
+            Some(action) => action,
+            None => bail!("no moderation action with id `{}`", id),
+        };
+
+        if action.undone_at.is_some() {
+            bail!("action has already been undone");
+        }
+
+        match action.action.as_str() {
+            "timeout" | "ban" => {
+                let user_id = match action.user_id.as_deref() {
+                    Some(user_id) => user_id,
+                    None => bail!("the id of `{}` is not known", action.user),
+                };
+
+                let channel = match self.channel.load().await {
+                    Some(channel) => channel,
+                    None => bail!("not connected to a channel"),
+                };
+
+                channel.unban(user_id).await?;
+            }
+            other => bail!("`{}` actions can't be undone", other),
+        }
+
+        moderation_log.mark_undone(action.id).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
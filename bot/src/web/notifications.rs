@@ -0,0 +1,102 @@
+use crate::auth::Scope;
+use crate::db;
+use crate::injector;
+use crate::web::{Access, EMPTY};
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// The number of notifications to include in the feed.
+const FEED_LIMIT: i64 = 100;
+
+#[derive(serde::Serialize)]
+struct Feed {
+    unread: i64,
+    notifications: Vec<db::Notification>,
+}
+
+/// Notification center endpoints.
+#[derive(Clone)]
+pub struct Notifications(injector::Var<Option<db::Notifications>>);
+
+impl Notifications {
+    pub fn route(
+        notifications: injector::Var<Option<db::Notifications>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Notifications(notifications);
+
+        let list = warp::get()
+            .and(path::end())
+            .and(access.scope(Scope::WebNotifications))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let read_all = warp::post()
+            .and(path!("read").and(path::end()))
+            .and(access.scope(Scope::WebNotifications))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.read_all().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let read = warp::post()
+            .and(path!(i32 / "read").and(path::end()))
+            .and(access.scope(Scope::WebNotifications))
+            .and_then({
+                move |id: i32| {
+                    let api = api.clone();
+                    async move { api.read(id).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("notifications")
+            .and(list.or(read_all).or(read))
+            .boxed()
+    }
+
+    /// Access underlying notification storage.
+    async fn notifications(&self) -> Result<RwLockReadGuard<'_, db::Notifications>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("notifications not configured"),
+        }
+    }
+
+    /// List the most recent notifications, and how many are unread.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let notifications = self.notifications().await?;
+
+        let feed = Feed {
+            unread: notifications.unread().await?,
+            notifications: notifications.list(FEED_LIMIT).await?,
+        };
+
+        Ok(warp::reply::json(&feed))
+    }
+
+    /// Mark all notifications as read.
+    async fn read_all(&self) -> Result<impl warp::Reply> {
+        self.notifications().await?.mark_all_read().await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Mark a single notification as read.
+    async fn read(&self, id: i32) -> Result<impl warp::Reply> {
+        self.notifications().await?.mark_read(id).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
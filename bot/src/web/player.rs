@@ -0,0 +1,394 @@
+use crate::auth::Scope;
+use crate::injector;
+use crate::metrics;
+use crate::player;
+use crate::prelude::*;
+use crate::track_id::TrackId;
+use crate::utils;
+use crate::web::{Access, EMPTY};
+use anyhow::{anyhow, bail, Result};
+use tokio::sync::{broadcast, RwLockReadGuard};
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// A single item in the queue.
+#[derive(Debug, serde::Serialize)]
+struct QueueItem<'a> {
+    track_id: &'a TrackId,
+    name: String,
+    artists: Option<String>,
+    user: Option<&'a str>,
+    duration: String,
+}
+
+impl<'a> From<&'a player::Item> for QueueItem<'a> {
+    fn from(item: &'a player::Item) -> Self {
+        Self {
+            track_id: &item.track_id,
+            name: item.track.name(),
+            artists: item.track.artists(),
+            user: item.user.as_deref(),
+            duration: utils::digital_duration(item.duration),
+        }
+    }
+}
+
+/// A snapshot of the player, sent over websocket whenever the player changes.
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "type", rename = "player/state")]
+struct PlayerState<'a> {
+    /// The event that caused the snapshot to be sent.
+    event: &'static str,
+    current: Option<player::CurrentData<'a>>,
+    /// The queue, including the current song, like `GET /api/player/queue`.
+    queue: Vec<QueueItem<'a>>,
+    volume: Option<u32>,
+}
+
+/// Query parameters for paginating the song history.
+#[derive(Debug, serde::Deserialize)]
+struct HistoryQuery {
+    #[serde(default)]
+    offset: usize,
+    #[serde(default = "default_history_limit")]
+    limit: usize,
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: default_history_limit(),
+        }
+    }
+}
+
+fn default_history_limit() -> usize {
+    50
+}
+
+/// A list of tracks in the queue.
+#[derive(Debug, serde::Deserialize)]
+struct TrackIds {
+    track_ids: Vec<TrackId>,
+}
+
+/// Player endpoints.
+#[derive(Clone)]
+pub struct Player(injector::Var<Option<player::Player>>);
+
+impl Player {
+    pub fn route(
+        player: injector::Var<Option<player::Player>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Player(player);
+
+        let current = warp::get()
+            .and(path!("current").and(path::end()))
+            .and(access.scope(Scope::PlayerRead))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.current().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let queue = warp::get()
+            .and(path!("queue").and(path::end()))
+            .and(access.scope(Scope::PlayerRead))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.queue().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let history = warp::get()
+            .and(path!("history").and(path::end()))
+            .and(access.scope(Scope::PlayerRead))
+            .and(
+                warp::query::<HistoryQuery>()
+                    .or(warp::any().map(HistoryQuery::default))
+                    .unify(),
+            )
+            .and_then({
+                let api = api.clone();
+                move |query: HistoryQuery| {
+                    let api = api.clone();
+                    async move { api.history(query).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let purge = warp::delete()
+            .and(path!("queue").and(path::end()))
+            .and(access.scope(Scope::PlayerControl))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.purge().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let reorder = warp::put()
+            .and(path!("queue" / "order").and(path::end()))
+            .and(access.scope(Scope::PlayerControl))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: TrackIds| {
+                    let api = api.clone();
+                    async move {
+                        api.reorder(body.track_ids)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let remove = warp::delete()
+            .and(path!("queue" / TrackId).and(path::end()))
+            .and(access.scope(Scope::PlayerControl))
+            .and_then({
+                let api = api.clone();
+                move |track_id: TrackId| {
+                    let api = api.clone();
+                    async move {
+                        api.remove(vec![track_id])
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let remove_bulk = warp::post()
+            .and(path!("queue" / "remove").and(path::end()))
+            .and(access.scope(Scope::PlayerControl))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: TrackIds| {
+                    let api = api.clone();
+                    async move {
+                        api.remove(body.track_ids)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let skip = warp::post()
+            .and(path!("skip").and(path::end()))
+            .and(access.scope(Scope::PlayerControl))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.skip().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let toggle = warp::post()
+            .and(path!("toggle").and(path::end()))
+            .and(access.scope(Scope::PlayerControl))
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.toggle().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("player")
+            .and(
+                current
+                    .or(queue)
+                    .or(history)
+                    .or(purge)
+                    .or(reorder)
+                    .or(remove)
+                    .or(remove_bulk)
+                    .or(skip)
+                    .or(toggle),
+            )
+            .boxed()
+    }
+
+    /// Websocket streaming a snapshot of the player every time it changes.
+    ///
+    /// This is read-only, and intended for public pages where viewers can
+    /// follow the queue.
+    pub fn events(
+        player: injector::Var<Option<player::Player>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        warp::ws()
+            .and(access.scope(Scope::PlayerRead))
+            .and_then(move |ws: warp::ws::Ws| {
+                let player = player.clone();
+
+                async move {
+                    let player = match player.load().await {
+                        Some(player) => player,
+                        None => return Err(super::custom_reject(anyhow!("player not configured"))),
+                    };
+
+                    Ok(ws.on_upgrade(move |websocket| async move {
+                        if let Err(e) = send_events(player, websocket).await {
+                            log::error!("websocket error: {}", e);
+                        }
+                    }))
+                }
+            })
+            .boxed()
+    }
+
+    /// Access underlying player abstraction.
+    async fn player(&self) -> Result<RwLockReadGuard<'_, player::Player>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("player not configured"),
+        }
+    }
+
+    /// Get the current song.
+    async fn current(&self) -> Result<impl warp::Reply> {
+        let current = self.player().await?.current().await;
+
+        let data = match current.as_ref() {
+            Some(song) => Some(song.data(song.state())?),
+            None => None,
+        };
+
+        Ok(warp::reply::json(&data))
+    }
+
+    /// List the songs in the queue.
+    async fn queue(&self) -> Result<impl warp::Reply> {
+        let items = self.player().await?.list().await;
+        let items = items
+            .iter()
+            .map(|i| QueueItem::from(&**i))
+            .collect::<Vec<_>>();
+        Ok(warp::reply::json(&items))
+    }
+
+    /// List songs that have been played.
+    async fn history(&self, query: HistoryQuery) -> Result<impl warp::Reply> {
+        /// The maximum number of history entries returned in one request.
+        const MAX_LIMIT: usize = 100;
+
+        let limit = usize::min(query.limit, MAX_LIMIT);
+        let history = self.player().await?.history(query.offset, limit).await?;
+        Ok(warp::reply::json(&history))
+    }
+
+    /// Purge the queue.
+    async fn purge(&self) -> Result<impl warp::Reply> {
+        self.player().await?.purge().await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Reorder the queue.
+    ///
+    /// The order must contain every queued track exactly once, not including
+    /// the song currently playing.
+    async fn reorder(&self, track_ids: Vec<TrackId>) -> Result<impl warp::Reply> {
+        if !self.player().await?.reorder(&track_ids).await? {
+            bail!("order must contain every queued track exactly once");
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Remove the given tracks from the queue.
+    ///
+    /// Responds with the items that were removed.
+    async fn remove(&self, track_ids: Vec<TrackId>) -> Result<impl warp::Reply> {
+        let removed = self.player().await?.remove_tracks(&track_ids).await?;
+        let removed = removed
+            .iter()
+            .map(|i| QueueItem::from(&**i))
+            .collect::<Vec<_>>();
+        Ok(warp::reply::json(&removed))
+    }
+
+    /// Skip the current song.
+    async fn skip(&self) -> Result<impl warp::Reply> {
+        self.player().await?.skip().await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Toggle playback.
+    async fn toggle(&self) -> Result<impl warp::Reply> {
+        self.player().await?.toggle().await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
+
+/// Forward player events as snapshots to the websocket.
+async fn send_events(player: player::Player, websocket: warp::ws::WebSocket) -> Result<()> {
+    let _guard = metrics::WEBSOCKETS.guard();
+    let (mut tx, _) = websocket.split();
+    let mut rx = player.subscribe().await;
+
+    tx.send(state(&player, "initial").await?).await?;
+
+    while let Some(e) = rx.next().await {
+        let event = match e {
+            Ok(e) => event_name(&e),
+            // NB: every snapshot is complete, so missed events are harmless.
+            Err(broadcast::RecvError::Lagged(..)) => "lagged",
+            Err(e) => return Err(e.into()),
+        };
+
+        tx.send(state(&player, event).await?).await?;
+    }
+
+    Ok(())
+}
+
+/// Build a snapshot message of the player.
+async fn state(player: &player::Player, event: &'static str) -> Result<warp::ws::Message> {
+    let current = player.current().await;
+    let items = player.list().await;
+
+    let state = PlayerState {
+        event,
+        current: match current.as_ref() {
+            Some(song) => Some(song.data(song.state())?),
+            None => None,
+        },
+        queue: items.iter().map(|i| QueueItem::from(&**i)).collect(),
+        volume: player.current_volume().await,
+    };
+
+    Ok(warp::ws::Message::text(serde_json::to_string(&state)?))
+}
+
+/// The name of a player event, as seen by websocket clients.
+fn event_name(event: &player::Event) -> &'static str {
+    match event {
+        player::Event::Empty => "empty",
+        player::Event::Playing(..) => "playing",
+        player::Event::Skip => "skip",
+        player::Event::Pausing => "pausing",
+        player::Event::Modified => "modified",
+        player::Event::NotConfigured => "not-configured",
+        player::Event::Detached => "detached",
+        player::Event::Volume(..) => "volume",
+    }
+}
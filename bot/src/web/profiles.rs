@@ -0,0 +1,133 @@
+use crate::auth::Scope;
+use crate::injector;
+use crate::profiles::{Profile, Profiles as Store};
+use crate::web::{Access, Fragment, EMPTY};
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use warp::{body, filters, path, Filter as _};
+
+#[derive(serde::Deserialize)]
+pub struct PutProfile {
+    #[serde(default)]
+    prefixes: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+struct List {
+    active: Option<String>,
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// Endpoints to save and switch between settings profiles.
+#[derive(Clone)]
+pub struct Profiles(injector::Var<Option<crate::settings::Settings>>);
+
+impl Profiles {
+    pub fn route(
+        settings: injector::Var<Option<crate::settings::Settings>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Profiles(settings);
+
+        let list = warp::get()
+            .and(path::end())
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let save = warp::put()
+            .and(path!(Fragment).and(path::end()))
+            .and(access.scope(Scope::WebSettings))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |name: Fragment, body: PutProfile| {
+                    let api = api.clone();
+                    async move {
+                        api.save(name.as_str(), &body.prefixes)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let activate = warp::post()
+            .and(path!(Fragment / "activate").and(path::end()))
+            .and(access.scope(Scope::WebSettings))
+            .and_then({
+                let api = api.clone();
+                move |name: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.activate(name.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let delete = warp::delete()
+            .and(path!(Fragment).and(path::end()))
+            .and(access.scope(Scope::WebSettings))
+            .and_then({
+                move |name: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.delete(name.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        warp::path("profiles")
+            .and(list.or(save).or(activate).or(delete))
+            .boxed()
+    }
+
+    /// Access the profiles stored in settings.
+    async fn profiles(&self) -> Result<Store> {
+        match self.0.read().await.as_ref() {
+            Some(settings) => Ok(Store::new(settings.clone())),
+            None => bail!("settings not configured"),
+        }
+    }
+
+    /// List all profiles.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let profiles = self.profiles().await?;
+
+        let list = List {
+            active: profiles.active().await?,
+            profiles: profiles.list().await?,
+        };
+
+        Ok(warp::reply::json(&list))
+    }
+
+    /// Save the current settings as the given profile.
+    async fn save(&self, name: &str, prefixes: &[String]) -> Result<impl warp::Reply> {
+        let profile = self.profiles().await?.save(name, prefixes).await?;
+        Ok(warp::reply::json(&profile))
+    }
+
+    /// Activate the given profile.
+    async fn activate(&self, name: &str) -> Result<impl warp::Reply> {
+        let import = self.profiles().await?.activate(name).await?;
+        Ok(warp::reply::json(&import))
+    }
+
+    /// Delete the given profile.
+    async fn delete(&self, name: &str) -> Result<impl warp::Reply> {
+        self.profiles().await?.delete(name).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
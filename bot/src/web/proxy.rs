@@ -0,0 +1,122 @@
+use crate::settings;
+use anyhow::Result;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use warp::filters;
+use warp::Filter as _;
+
+/// Information about the client that made a request, as seen through any
+/// trusted reverse proxy.
+#[derive(Debug, Clone)]
+pub(crate) struct Client {
+    /// Address of the client, if known.
+    pub(crate) addr: Option<IpAddr>,
+    /// Scheme used by the client, like `http` or `https`.
+    pub(crate) scheme: String,
+    /// Host the client connected to, if known.
+    pub(crate) host: Option<String>,
+}
+
+impl fmt::Display for Client {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.addr {
+            Some(addr) => addr.fmt(fmt),
+            None => "unknown".fmt(fmt),
+        }
+    }
+}
+
+/// Reverse proxy support for the web server.
+#[derive(Clone)]
+pub(crate) struct Proxy {
+    trust_forwarded: settings::Var<bool>,
+    public_url: settings::Var<Option<String>>,
+}
+
+impl Proxy {
+    /// Construct new reverse proxy support.
+    pub(crate) async fn new(settings: &settings::Settings) -> Result<Self> {
+        let settings = settings.scoped("web");
+
+        Ok(Self {
+            trust_forwarded: settings.var("trust-forwarded", false).await?,
+            public_url: settings.optional("public-url").await?,
+        })
+    }
+
+    /// Filter that extracts information about the client making the request.
+    ///
+    /// `X-Forwarded-For`, `X-Forwarded-Proto`, and `X-Forwarded-Host` are
+    /// only honored if the proxy is trusted.
+    pub(crate) fn client(&self) -> filters::BoxedFilter<(Client,)> {
+        let proxy = self.clone();
+
+        warp::addr::remote()
+            .and(warp::header::optional::<String>("x-forwarded-for"))
+            .and(warp::header::optional::<String>("x-forwarded-proto"))
+            .and(warp::header::optional::<String>("x-forwarded-host"))
+            .and(warp::header::optional::<String>("host"))
+            .and_then(
+                move |remote: Option<SocketAddr>,
+                      forwarded_for: Option<String>,
+                      forwarded_proto: Option<String>,
+                      forwarded_host: Option<String>,
+                      host: Option<String>| {
+                    let proxy = proxy.clone();
+
+                    async move {
+                        let mut client = Client {
+                            addr: remote.map(|a| a.ip()),
+                            scheme: String::from("http"),
+                            host,
+                        };
+
+                        if proxy.trust_forwarded.load().await {
+                            // NB: the right-most address is the one appended by
+                            // the trusted proxy, everything before it is
+                            // controlled by the client.
+                            let forwarded_for = forwarded_for.as_deref().and_then(|s| {
+                                str::parse::<IpAddr>(s.rsplit(',').next()?.trim()).ok()
+                            });
+
+                            if let Some(addr) = forwarded_for {
+                                client.addr = Some(addr);
+                            }
+
+                            if let Some(proto) = forwarded_proto {
+                                client.scheme = proto.trim().to_lowercase();
+                            }
+
+                            if let Some(host) = forwarded_host {
+                                client.host = Some(host.trim().to_string());
+                            }
+                        }
+
+                        Ok::<_, warp::Rejection>(client)
+                    }
+                },
+            )
+            .boxed()
+    }
+
+    /// Build the external base URL used to reach the web server, like
+    /// `https://bot.example.com`.
+    ///
+    /// Prefers the configured public URL. Otherwise falls back to what the
+    /// client used to connect if the proxy is trusted, since the `Host` header
+    /// can't be trusted on its own.
+    pub(crate) async fn base_url(&self, client: &Client) -> String {
+        if let Some(public_url) = self.public_url.load().await {
+            return public_url.trim_end_matches('/').to_string();
+        }
+
+        if !self.trust_forwarded.load().await {
+            return String::from(super::URL);
+        }
+
+        match &client.host {
+            Some(host) => format!("{}://{}", client.scheme, host),
+            None => String::from(super::URL),
+        }
+    }
+}
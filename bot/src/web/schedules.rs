@@ -0,0 +1,65 @@
+use crate::injector;
+use crate::scheduler;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// A single schedule in use, and when it next fires.
+#[derive(Debug, serde::Serialize)]
+struct ScheduleEntry {
+    name: String,
+    schedule: scheduler::Schedule,
+    timezone: String,
+    next: Option<DateTime<Utc>>,
+}
+
+/// Schedule endpoints.
+#[derive(Clone)]
+pub struct Schedules(injector::Var<Option<scheduler::Scheduler>>);
+
+impl Schedules {
+    pub fn route(
+        scheduler: injector::Var<Option<scheduler::Scheduler>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Schedules(scheduler);
+
+        let list = warp::get()
+            .and(path::end().and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            }))
+            .boxed();
+
+        warp::path("schedules").and(list).boxed()
+    }
+
+    /// Access underlying scheduler.
+    async fn scheduler(&self) -> Result<RwLockReadGuard<'_, scheduler::Scheduler>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("scheduler not configured"),
+        }
+    }
+
+    /// List all schedules in use and when they next fire.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let schedules = self.scheduler().await?.list().await;
+
+        let entries = schedules
+            .into_iter()
+            .map(|(name, schedule)| ScheduleEntry {
+                name,
+                timezone: schedule.timezone().name().to_string(),
+                next: schedule.next(),
+                schedule,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(warp::reply::json(&entries))
+    }
+}
@@ -0,0 +1,84 @@
+use crate::auth::Scope;
+use crate::chat_stats::ChatStats;
+use crate::db;
+use crate::injector;
+use crate::stream_session;
+use crate::web::Access;
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// How many sessions to list.
+const LIST_LIMIT: i64 = 50;
+
+/// Stream session endpoints.
+#[derive(Clone)]
+pub struct Sessions {
+    sessions: injector::Var<Option<db::StreamSessions>>,
+    chat_stats: injector::Var<Option<ChatStats>>,
+}
+
+impl Sessions {
+    pub fn route(
+        sessions: injector::Var<Option<db::StreamSessions>>,
+        chat_stats: injector::Var<Option<ChatStats>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Sessions {
+            sessions,
+            chat_stats,
+        };
+
+        let list = warp::get()
+            .and(path!("sessions").and(path::end()))
+            .and(access.scope(Scope::WebSessions))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let report = warp::get()
+            .and(path!("sessions" / i32 / "report").and(path::end()))
+            .and(access.scope(Scope::WebSessions))
+            .and_then({
+                move |id: i32| {
+                    let api = api.clone();
+                    async move { api.report(id).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        list.or(report).boxed()
+    }
+
+    /// Access underlying stream sessions.
+    async fn sessions(&self) -> Result<RwLockReadGuard<'_, db::StreamSessions>> {
+        match RwLockReadGuard::try_map(self.sessions.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("stream sessions not configured"),
+        }
+    }
+
+    /// List recent stream sessions.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let sessions = self.sessions().await?.list(LIST_LIMIT).await?;
+        Ok(warp::reply::json(&sessions))
+    }
+
+    /// Get the report for a single stream session.
+    async fn report(&self, id: i32) -> Result<impl warp::Reply> {
+        let sessions = self.sessions().await?;
+        let chat_stats = self.chat_stats.read().await;
+
+        match stream_session::report(&*sessions, chat_stats.as_ref(), id).await? {
+            Some(report) => Ok(warp::reply::json(&report)),
+            None => bail!("no such session: {}", id),
+        }
+    }
+}
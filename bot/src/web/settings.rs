@@ -1,15 +1,44 @@
+use crate::auth::Scope;
 use crate::injector;
-use crate::web::{Fragment, EMPTY};
+use crate::profiles::Format;
+use crate::settings::{Error, Invalid};
+use crate::web::{Access, Fragment, EMPTY};
 use anyhow::{bail, Result};
 use std::collections::HashSet;
 use tokio::sync::RwLockReadGuard;
-use warp::{body, filters, path, Filter as _};
+use warp::{body, filters, http::StatusCode, path, Filter as _};
 
 #[derive(serde::Deserialize)]
 pub struct PutSetting {
     value: serde_json::Value,
 }
 
+/// Response when a value doesn't validate against the schema of a setting.
+#[derive(serde::Serialize)]
+struct InvalidSetting {
+    code: u16,
+    message: String,
+    errors: Vec<Invalid>,
+}
+
+#[derive(serde::Deserialize)]
+struct ExportQuery {
+    #[serde(default)]
+    format: Format,
+}
+
+#[derive(serde::Serialize)]
+struct Export {
+    format: Format,
+    content: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ImportBody {
+    format: Format,
+    content: String,
+}
+
 #[derive(serde::Deserialize)]
 struct SettingsQuery {
     #[serde(default)]
@@ -27,6 +56,7 @@ pub struct Settings(injector::Var<Option<crate::settings::Settings>>);
 impl Settings {
     pub fn route(
         settings: injector::Var<Option<crate::settings::Settings>>,
+        access: &Access,
     ) -> filters::BoxedFilter<(impl warp::Reply,)> {
         let api = Settings(settings);
 
@@ -60,7 +90,9 @@ impl Settings {
             .boxed();
 
         let delete = warp::delete()
-            .and(warp::path("settings").and(path::tail()).and_then({
+            .and(warp::path("settings"))
+            .and(access.scope(Scope::WebSettings))
+            .and(path::tail().and_then({
                 let api = api.clone();
 
                 move |key: path::Tail| {
@@ -77,27 +109,56 @@ impl Settings {
             }))
             .boxed();
 
+        let export = warp::get()
+            .and(warp::path("settings-export").and(path::end()))
+            .and(warp::query::<ExportQuery>())
+            .and_then({
+                let api = api.clone();
+                move |query: ExportQuery| {
+                    let api = api.clone();
+                    async move { api.export(query.format).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let import = warp::post()
+            .and(warp::path("settings-import").and(path::end()))
+            .and(access.scope(Scope::WebSettings))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: ImportBody| {
+                    let api = api.clone();
+                    async move { api.import(body).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
         let edit = warp::put()
-            .and(
-                warp::path("settings")
-                    .and(path::tail().and(body::json()))
-                    .and_then({
-                        move |key: path::Tail, body: PutSetting| {
-                            let api = api.clone();
-
-                            async move {
-                                let key = str::parse::<Fragment>(key.as_str())
-                                    .map_err(super::custom_reject)?;
-                                api.edit_setting(key.as_str(), body.value)
-                                    .await
-                                    .map_err(super::custom_reject)
-                            }
-                        }
-                    }),
-            )
+            .and(warp::path("settings"))
+            .and(access.scope(Scope::WebSettings))
+            .and(path::tail().and(body::json()).and_then({
+                move |key: path::Tail, body: PutSetting| {
+                    let api = api.clone();
+
+                    async move {
+                        let key =
+                            str::parse::<Fragment>(key.as_str()).map_err(super::custom_reject)?;
+                        api.edit_setting(key.as_str(), body.value)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            }))
             .boxed();
 
-        list.or(get).or(delete).or(edit).boxed()
+        export
+            .or(import)
+            .or(list)
+            .or(get)
+            .or(delete)
+            .or(edit)
+            .boxed()
     }
 
     /// Access underlying settings abstraction.
@@ -156,6 +217,20 @@ impl Settings {
         Ok(warp::reply::json(&settings))
     }
 
+    /// Export all settings except secrets.
+    async fn export(&self, format: Format) -> Result<impl warp::Reply> {
+        let values = self.settings().await?.export().await?;
+        let content = format.encode(&values)?;
+        Ok(warp::reply::json(&Export { format, content }))
+    }
+
+    /// Import settings, like the ones produced by an export.
+    async fn import(&self, body: ImportBody) -> Result<impl warp::Reply> {
+        let values = body.format.decode(&body.content)?;
+        let import = self.settings().await?.import(values).await?;
+        Ok(warp::reply::json(&import))
+    }
+
     /// Delete the given setting by key.
     async fn delete_setting(&self, key: &str) -> Result<impl warp::Reply> {
         let settings = self.settings().await?;
@@ -173,10 +248,27 @@ impl Settings {
         Ok(warp::reply::json(&setting))
     }
 
-    /// Delete the given setting by key.
+    /// Edit the given setting by key.
+    ///
+    /// Values which don't validate are rejected with the reasons why, so that
+    /// they can be presented next to the setting.
     async fn edit_setting(&self, key: &str, value: serde_json::Value) -> Result<impl warp::Reply> {
         let settings = self.settings().await?;
-        settings.set_json(key, value).await?;
-        Ok(warp::reply::json(&EMPTY))
+
+        let (reply, status) = match settings.set_json(key, value).await {
+            Ok(()) => (warp::reply::json(&EMPTY), StatusCode::OK),
+            Err(Error::Invalid(errors)) => {
+                let reply = warp::reply::json(&InvalidSetting {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    message: format!("invalid value for `{}`", key),
+                    errors,
+                });
+
+                (reply, StatusCode::BAD_REQUEST)
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(warp::reply::with_status(reply, status))
     }
 }
@@ -0,0 +1,257 @@
+use crate::auth::Scope;
+use crate::injector;
+use crate::oauth2;
+use crate::web::{Access, Fragment};
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockReadGuard};
+use warp::{body, filters, path, Filter as _};
+
+/// A single step of the first-run setup.
+struct StepInfo {
+    id: &'static str,
+    title: &'static str,
+    doc: &'static str,
+    /// The token which has to be available for the step to be done.
+    token: Option<&'static str>,
+    /// If the step can be skipped.
+    optional: bool,
+}
+
+/// All steps of the first-run setup, in order.
+static STEPS: &[StepInfo] = &[
+    StepInfo {
+        id: "twitch-streamer",
+        title: "Streamer Account",
+        doc: "Connect the Twitch account you stream with.",
+        token: Some("twitch-streamer"),
+        optional: false,
+    },
+    StepInfo {
+        id: "twitch-bot",
+        title: "Bot Account",
+        doc: "Connect the Twitch account the bot should talk in chat as.",
+        token: Some("twitch-bot"),
+        optional: false,
+    },
+    StepInfo {
+        id: "spotify",
+        title: "Spotify",
+        doc: "Connect Spotify to take song requests.",
+        token: Some("spotify"),
+        optional: true,
+    },
+    StepInfo {
+        id: "modules",
+        title: "Modules",
+        doc: "Pick which features of the bot to enable.",
+        token: None,
+        optional: false,
+    },
+];
+
+/// The state of a single step.
+#[derive(Debug, serde::Serialize)]
+struct Step {
+    id: &'static str,
+    title: &'static str,
+    doc: &'static str,
+    optional: bool,
+    /// If the step has been completed.
+    done: bool,
+    /// If the step has been skipped.
+    skipped: bool,
+}
+
+/// The state of the first-run setup.
+#[derive(Debug, serde::Serialize)]
+struct Status {
+    /// If the setup has been completed.
+    completed: bool,
+    steps: Vec<Step>,
+}
+
+#[derive(serde::Deserialize)]
+struct PutModules {
+    /// Feature toggles to set, by setting key.
+    features: HashMap<String, bool>,
+}
+
+/// Endpoints for the first-run setup.
+#[derive(Clone)]
+pub struct Setup {
+    settings: injector::Var<Option<crate::settings::Settings>>,
+    tokens: Arc<RwLock<BTreeMap<String, oauth2::SyncToken>>>,
+}
+
+impl Setup {
+    pub fn route(
+        settings: injector::Var<Option<crate::settings::Settings>>,
+        tokens: Arc<RwLock<BTreeMap<String, oauth2::SyncToken>>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Setup { settings, tokens };
+
+        let status = warp::get()
+            .and(path::end())
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.status().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let modules = warp::put()
+            .and(path!("modules").and(path::end()))
+            .and(access.scope(Scope::WebSettings))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: PutModules| {
+                    let api = api.clone();
+                    async move { api.modules(body).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let skip = warp::post()
+            .and(path!(Fragment / "skip").and(path::end()))
+            .and(access.scope(Scope::WebSettings))
+            .and_then({
+                let api = api.clone();
+                move |step: Fragment| {
+                    let api = api.clone();
+                    async move { api.skip(step.as_str()).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let complete = warp::post()
+            .and(path!("complete").and(path::end()))
+            .and(access.scope(Scope::WebSettings))
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.complete().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("setup")
+            .and(status.or(modules).or(complete).or(skip))
+            .boxed()
+    }
+
+    /// Access underlying settings abstraction.
+    async fn settings(&self) -> Result<RwLockReadGuard<'_, crate::settings::Settings>> {
+        match RwLockReadGuard::try_map(self.settings.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("settings not configured"),
+        }
+    }
+
+    /// Build the current state of the setup.
+    async fn build_status(&self) -> Result<Status> {
+        let settings = self.settings().await?.scoped("setup");
+        let completed = settings.get_or_default::<bool>("completed").await?;
+        let done = settings
+            .get::<Vec<String>>("done")
+            .await?
+            .unwrap_or_default();
+        let skipped = settings
+            .get::<Vec<String>>("skipped")
+            .await?
+            .unwrap_or_default();
+
+        let tokens = self.tokens.read().await;
+        let mut steps = Vec::with_capacity(STEPS.len());
+
+        for step in STEPS {
+            let token_ready = match step.token.and_then(|id| tokens.get(id)) {
+                Some(token) => token.is_ready().await,
+                None => false,
+            };
+
+            steps.push(Step {
+                id: step.id,
+                title: step.title,
+                doc: step.doc,
+                optional: step.optional,
+                done: token_ready || done.iter().any(|id| id == step.id),
+                skipped: skipped.iter().any(|id| id == step.id),
+            });
+        }
+
+        Ok(Status { completed, steps })
+    }
+
+    /// Get the current state of the setup.
+    async fn status(&self) -> Result<impl warp::Reply> {
+        Ok(warp::reply::json(&self.build_status().await?))
+    }
+
+    /// Enable or disable the selected features, completing the modules step.
+    async fn modules(&self, body: PutModules) -> Result<impl warp::Reply> {
+        {
+            let settings = self.settings().await?;
+
+            // NB: validate everything before we set anything.
+            for key in body.features.keys() {
+                match settings.lookup(key) {
+                    Some(schema) if schema.feature => (),
+                    _ => bail!("`{}` is not a feature", key),
+                }
+            }
+
+            for (key, enabled) in body.features {
+                settings.set(&key, enabled).await?;
+            }
+
+            mark(&settings, "done", "modules").await?;
+        }
+
+        Ok(warp::reply::json(&self.build_status().await?))
+    }
+
+    /// Skip an optional step.
+    async fn skip(&self, id: &str) -> Result<impl warp::Reply> {
+        match STEPS.iter().find(|s| s.id == id) {
+            Some(step) if step.optional => (),
+            Some(..) => bail!("step `{}` can't be skipped", id),
+            None => bail!("no step named `{}`", id),
+        }
+
+        mark(&*self.settings().await?, "skipped", id).await?;
+        Ok(warp::reply::json(&self.build_status().await?))
+    }
+
+    /// Complete the setup, as long as every required step is done.
+    async fn complete(&self) -> Result<impl warp::Reply> {
+        let status = self.build_status().await?;
+
+        for step in &status.steps {
+            if !step.done && !step.skipped {
+                bail!("step `{}` has to be done first", step.title);
+            }
+        }
+
+        self.settings().await?.set("setup/completed", true).await?;
+        Ok(warp::reply::json(&self.build_status().await?))
+    }
+}
+
+/// Add the given step to a list of steps stored in the settings.
+async fn mark(settings: &crate::settings::Settings, list: &str, id: &str) -> Result<()> {
+    let key = format!("setup/{}", list);
+    let mut ids = settings.get::<Vec<String>>(&key).await?.unwrap_or_default();
+
+    if !ids.iter().any(|s| s == id) {
+        ids.push(id.to_string());
+        settings.set(&key, ids).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::injector;
+use crate::stream_schedule;
+use anyhow::{bail, Result};
+use chrono::Utc;
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// The number of upcoming streams to list.
+const UPCOMING_LIMIT: usize = 10;
+
+/// Endpoints to see the upcoming streams, like on overlays.
+#[derive(Clone)]
+pub struct StreamSchedule(injector::Var<Option<stream_schedule::StreamSchedule>>);
+
+impl StreamSchedule {
+    pub fn route(
+        schedule: injector::Var<Option<stream_schedule::StreamSchedule>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = StreamSchedule(schedule);
+
+        let list = warp::get()
+            .and(path::end())
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.upcoming().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("stream-schedule").and(list).boxed()
+    }
+
+    /// Access underlying stream schedule.
+    async fn schedule(&self) -> Result<RwLockReadGuard<'_, stream_schedule::StreamSchedule>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("stream schedule not configured"),
+        }
+    }
+
+    /// List the upcoming streams, the earliest first.
+    async fn upcoming(&self) -> Result<impl warp::Reply> {
+        let upcoming = self.schedule().await?.upcoming(Utc::now(), UPCOMING_LIMIT);
+        Ok(warp::reply::json(&upcoming))
+    }
+}
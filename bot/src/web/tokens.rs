@@ -0,0 +1,194 @@
+use crate::api;
+use crate::auth::Scope;
+use crate::oauth2;
+use crate::web::{Access, EMPTY};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// The health of a single token.
+#[derive(Debug, serde::Serialize)]
+struct TokenStatus {
+    id: String,
+    what: &'static str,
+    /// If a token is currently available.
+    ready: bool,
+    /// If the token is valid. `None` if validity could not be determined.
+    valid: Option<bool>,
+    scopes: Vec<String>,
+    refreshed_at: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+    /// The state of the connection. `None` if it could not be determined.
+    state: Option<oauth2::ConnectionState>,
+    health: oauth2::Health,
+}
+
+/// Token endpoints.
+#[derive(Clone)]
+pub struct Tokens(Arc<RwLock<BTreeMap<String, oauth2::SyncToken>>>);
+
+impl Tokens {
+    pub fn route(
+        tokens: Arc<RwLock<BTreeMap<String, oauth2::SyncToken>>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Tokens(tokens);
+
+        let list = warp::get()
+            .and(path::end())
+            .and(access.scope(Scope::WebTokens))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let refresh = warp::post()
+            .and(path!(String / "refresh").and(path::end()))
+            .and(access.scope(Scope::WebTokens))
+            .and_then({
+                let api = api.clone();
+                move |id: String| {
+                    let api = api.clone();
+                    async move { api.refresh(&id).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let revoke = warp::delete()
+            .and(path!(String).and(path::end()))
+            .and(access.scope(Scope::WebTokens))
+            .and_then({
+                move |id: String| {
+                    let api = api.clone();
+                    async move { api.revoke(&id).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("tokens")
+            .and(list.or(refresh).or(revoke))
+            .boxed()
+    }
+
+    /// Get the token with the given id.
+    async fn token(&self, id: &str) -> Result<oauth2::SyncToken> {
+        match self.0.read().await.get(id) {
+            Some(token) => Ok(token.clone()),
+            None => bail!("no token with id `{}`", id),
+        }
+    }
+
+    /// List the health of all tokens.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let tokens = self.0.read().await.clone();
+        let mut out = Vec::new();
+
+        for (id, sync_token) in tokens {
+            out.push(status(id, sync_token).await);
+        }
+
+        Ok(warp::reply::json(&out))
+    }
+
+    /// Force the token with the given id to be refreshed.
+    async fn refresh(&self, id: &str) -> Result<impl warp::Reply> {
+        self.token(id).await?.force_refresh().await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Revoke the token with the given id.
+    ///
+    /// Only Twitch supports revoking tokens.
+    async fn revoke(&self, id: &str) -> Result<impl warp::Reply> {
+        let sync_token = self.token(id).await?;
+
+        if !is_twitch(id) {
+            bail!("revoking `{}` tokens is not supported", id);
+        }
+
+        api::Twitch::new(sync_token.clone())?.revoke_token().await?;
+        sync_token.force_refresh().await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
+
+/// Test if the given token id is for Twitch.
+fn is_twitch(id: &str) -> bool {
+    id.starts_with("twitch-")
+}
+
+/// Build the status of a single token.
+///
+/// Twitch tokens are checked against the validation endpoint, all other
+/// tokens are considered valid as long as they haven't expired.
+async fn status(id: String, sync_token: oauth2::SyncToken) -> TokenStatus {
+    let token = sync_token.token().await;
+
+    let state = match sync_token.state().await {
+        Ok(state) => Some(state),
+        Err(e) => {
+            log_warn!(e, "failed to get connection state: {}", id);
+            None
+        }
+    };
+
+    let mut status = TokenStatus {
+        id,
+        what: sync_token.what(),
+        ready: token.is_some(),
+        valid: None,
+        scopes: Vec::new(),
+        refreshed_at: None,
+        expires_at: None,
+        state,
+        health: sync_token.health().await,
+    };
+
+    let token = match token {
+        Some(token) => token,
+        None => {
+            status.valid = Some(false);
+            return status;
+        }
+    };
+
+    status.scopes = token.scopes.clone();
+    status.refreshed_at = Some(token.refreshed_at);
+    status.expires_at = token
+        .expires_in
+        .map(|s| token.refreshed_at + chrono::Duration::seconds(s as i64));
+
+    if !is_twitch(&status.id) {
+        status.valid = status.expires_at.map(|expires_at| expires_at > Utc::now());
+        return status;
+    }
+
+    let result = match api::Twitch::new(sync_token) {
+        Ok(twitch) => twitch.validate_token().await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(Some(validated)) => {
+            status.valid = Some(true);
+            status.scopes = validated.scopes;
+        }
+        Ok(None) => {
+            status.valid = Some(false);
+        }
+        Err(e) => {
+            log_warn!(e, "failed to validate token: {}", status.id);
+        }
+    }
+
+    status
+}
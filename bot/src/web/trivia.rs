@@ -0,0 +1,308 @@
+use crate::auth::Scope;
+use crate::db;
+use crate::injector;
+use crate::web::{Access, EMPTY};
+use anyhow::{bail, Result};
+use chrono::NaiveDateTime;
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// The number of users to show on the scoreboard.
+const SCOREBOARD_LIMIT: usize = 20;
+
+#[derive(serde::Serialize)]
+struct Imported {
+    imported: usize,
+}
+
+#[derive(serde::Serialize)]
+struct Scoreboard {
+    /// When the most recent stream with correct answers started.
+    stream: Option<NaiveDateTime>,
+    scores: Vec<db::TriviaScore>,
+}
+
+/// Trivia endpoints.
+#[derive(Clone)]
+pub struct Trivia(injector::Var<Option<db::Trivia>>);
+
+impl Trivia {
+    pub fn route(
+        trivia: injector::Var<Option<db::Trivia>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Trivia(trivia);
+
+        let list = warp::get()
+            .and(path!("questions").and(path::end()))
+            .and(access.scope(Scope::WebTrivia))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let delete = warp::delete()
+            .and(path!("questions" / i32).and(path::end()))
+            .and(access.scope(Scope::WebTrivia))
+            .and_then({
+                let api = api.clone();
+                move |id: i32| {
+                    let api = api.clone();
+                    async move { api.delete(id).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let import_json = warp::put()
+            .and(path!("import" / "json").and(path::end()))
+            .and(access.scope(Scope::WebTrivia))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |questions: Vec<db::NewTriviaQuestion>| {
+                    let api = api.clone();
+                    async move { api.import(questions).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let import_csv = warp::put()
+            .and(path!("import" / "csv").and(path::end()))
+            .and(access.scope(Scope::WebTrivia))
+            .and(body::bytes())
+            .and_then({
+                let api = api.clone();
+                move |body: bytes::Bytes| {
+                    let api = api.clone();
+                    async move { api.import_csv(body).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let scoreboard = warp::get()
+            .and(path!("scoreboard").and(path::end()))
+            .and(access.scope(Scope::WebTrivia))
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.scoreboard().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("trivia")
+            .and(
+                list.or(delete)
+                    .or(import_json)
+                    .or(import_csv)
+                    .or(scoreboard),
+            )
+            .boxed()
+    }
+
+    /// Access underlying trivia storage.
+    async fn trivia(&self) -> Result<RwLockReadGuard<'_, db::Trivia>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("trivia not configured"),
+        }
+    }
+
+    /// List all questions in the question bank.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let questions = self.trivia().await?.list().await?;
+        Ok(warp::reply::json(&questions))
+    }
+
+    /// Delete a question from the question bank.
+    async fn delete(&self, id: i32) -> Result<impl warp::Reply> {
+        if !self.trivia().await?.delete(id).await? {
+            bail!("no question with id `{}`", id);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Import questions into the question bank.
+    async fn import(&self, questions: Vec<db::NewTriviaQuestion>) -> Result<impl warp::Reply> {
+        for (n, q) in questions.iter().enumerate() {
+            if q.question.trim().is_empty() {
+                bail!("question #{} is empty", n + 1);
+            }
+
+            if q.answers.iter().all(|a| a.trim().is_empty()) {
+                bail!("question #{} doesn't have any answers", n + 1);
+            }
+        }
+
+        let imported = self.trivia().await?.import(questions).await?;
+        Ok(warp::reply::json(&Imported { imported }))
+    }
+
+    /// Import questions into the question bank from CSV.
+    async fn import_csv(&self, body: bytes::Bytes) -> Result<impl warp::Reply> {
+        let questions = parse_csv(std::str::from_utf8(&body)?)?;
+        let imported = self.trivia().await?.import(questions).await?;
+        Ok(warp::reply::json(&Imported { imported }))
+    }
+
+    /// Scores for the most recent stream.
+    async fn scoreboard(&self) -> Result<impl warp::Reply> {
+        let trivia = self.trivia().await?;
+
+        let stream = trivia.latest_stream().await?;
+
+        let scores = match stream {
+            Some(stream) => trivia.scoreboard(stream, SCOREBOARD_LIMIT).await?,
+            None => Vec::new(),
+        };
+
+        Ok(warp::reply::json(&Scoreboard { stream, scores }))
+    }
+}
+
+/// Parse questions from CSV.
+///
+/// Each row is `category,question,answers`, where alternative answers are
+/// separated by `|`. Fields may be quoted, and a leading header row is
+/// skipped.
+fn parse_csv(text: &str) -> Result<Vec<db::NewTriviaQuestion>> {
+    let mut questions = Vec::new();
+
+    for (n, (line, record)) in records(text)?.into_iter().enumerate() {
+        if record.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+
+        if n == 0 && record[0].trim().eq_ignore_ascii_case("category") {
+            continue;
+        }
+
+        let (category, question, answers) = match record.as_slice() {
+            [category, question, answers] => (category, question, answers),
+            _ => bail!("line {}: expected 3 columns but got {}", line, record.len()),
+        };
+
+        let question = question.trim();
+
+        if question.is_empty() {
+            bail!("line {}: question is empty", line);
+        }
+
+        let answers = answers
+            .split('|')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        if answers.is_empty() {
+            bail!("line {}: question doesn't have any answers", line);
+        }
+
+        questions.push(db::NewTriviaQuestion {
+            category: category.trim().to_string(),
+            question: question.to_string(),
+            answers,
+        });
+    }
+
+    Ok(questions)
+}
+
+/// Split CSV into records, each with the line number it starts on.
+fn records(text: &str) -> Result<Vec<(usize, Vec<String>)>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+
+    let mut it = text.chars().peekable();
+
+    while let Some(c) = it.next() {
+        match c {
+            '"' if quoted => {
+                if it.peek() == Some(&'"') {
+                    it.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => {
+                quoted = true;
+            }
+            ',' if !quoted => {
+                record.push(std::mem::take(&mut field));
+            }
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push((start, std::mem::take(&mut record)));
+                line += 1;
+                start = line;
+            }
+            '\r' if !quoted => (),
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+
+                field.push(c);
+            }
+        }
+    }
+
+    if quoted {
+        bail!("line {}: unterminated quote", start);
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((start, record));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_csv;
+    use crate::db::NewTriviaQuestion;
+
+    #[test]
+    fn test_parse_csv() {
+        let q = |category: &str, question: &str, answers: &[&str]| NewTriviaQuestion {
+            category: category.to_string(),
+            question: question.to_string(),
+            answers: answers.iter().map(|a| a.to_string()).collect(),
+        };
+
+        let text = "Category,Question,Answers\r\n\
+                    music,\"Who sang \"\"Help!\"\"?\",The Beatles\r\n\
+                    \r\n\
+                    \"math, basic\",\"What is 2 + 2,\nin words?\",four|4\n\
+                    games,Who is Mario's brother?,Luigi";
+
+        assert_eq!(
+            vec![
+                q("music", "Who sang \"Help!\"?", &["The Beatles"]),
+                q("math, basic", "What is 2 + 2,\nin words?", &["four", "4"]),
+                q("games", "Who is Mario's brother?", &["Luigi"]),
+            ],
+            parse_csv(text).unwrap()
+        );
+
+        assert!(parse_csv("music,Who?").is_err());
+        assert!(parse_csv("music,Who?,|").is_err());
+        assert!(parse_csv("music,\"Who?,Me").is_err());
+    }
+}
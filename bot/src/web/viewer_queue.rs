@@ -0,0 +1,144 @@
+use crate::auth::Scope;
+use crate::db;
+use crate::injector;
+use crate::settings;
+use crate::web::{Access, Fragment, EMPTY};
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter as _;
+
+/// Endpoints for the queue of viewers waiting to play.
+#[derive(Clone)]
+pub struct ViewerQueue {
+    queue: injector::Var<Option<db::ViewerQueue>>,
+    settings: injector::Var<Option<settings::Settings>>,
+}
+
+impl ViewerQueue {
+    pub fn route(
+        queue: injector::Var<Option<db::ViewerQueue>>,
+        settings: injector::Var<Option<settings::Settings>>,
+        access: &Access,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = ViewerQueue { queue, settings };
+
+        let list = warp::get()
+            .and(path!(Fragment).and(path::end()))
+            .and(access.scope(Scope::WebViewerQueue))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let next = warp::post()
+            .and(path!(Fragment / "next").and(path::end()))
+            .and(access.scope(Scope::WebViewerQueue))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.next(channel.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let remove = warp::delete()
+            .and(path!(Fragment / Fragment).and(path::end()))
+            .and(access.scope(Scope::WebViewerQueue))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, user: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.remove(channel.as_str(), user.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let clear = warp::delete()
+            .and(path!(Fragment).and(path::end()))
+            .and(access.scope(Scope::WebViewerQueue))
+            .and_then({
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.clear(channel.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        warp::path("viewer-queue")
+            .and(list.or(next).or(remove).or(clear))
+            .boxed()
+    }
+
+    /// Access underlying viewer queue storage.
+    async fn queue(&self) -> Result<RwLockReadGuard<'_, db::ViewerQueue>> {
+        match RwLockReadGuard::try_map(self.queue.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("viewer queue not configured"),
+        }
+    }
+
+    /// If subscribers are placed ahead of everyone else.
+    async fn sub_priority(&self) -> Result<bool> {
+        let settings = match self.settings.load().await {
+            Some(settings) => settings,
+            None => return Ok(false),
+        };
+
+        Ok(settings
+            .get::<bool>("viewer-queue/sub-priority")
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// List everyone in the queue, in the order they will be picked.
+    async fn list(&self, channel: &str) -> Result<impl warp::Reply> {
+        let sub_priority = self.sub_priority().await?;
+        let entries = self.queue().await?.list(channel, sub_priority).await?;
+        Ok(warp::reply::json(&entries))
+    }
+
+    /// Pick the next user from the queue.
+    async fn next(&self, channel: &str) -> Result<impl warp::Reply> {
+        let sub_priority = self.sub_priority().await?;
+        let entry = self.queue().await?.next(channel, sub_priority).await?;
+        Ok(warp::reply::json(&entry))
+    }
+
+    /// Remove a user from the queue.
+    async fn remove(&self, channel: &str, user: &str) -> Result<impl warp::Reply> {
+        if !self.queue().await?.leave(channel, user).await? {
+            bail!("`{}` is not in the queue", user);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Remove everyone from the queue.
+    async fn clear(&self, channel: &str) -> Result<impl warp::Reply> {
+        self.queue().await?.clear(channel).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
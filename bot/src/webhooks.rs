@@ -0,0 +1,296 @@
+//! Outgoing webhooks, which POST JSON to configured URLs when things happen
+//! in the bot.
+//!
+//! Deliveries are signed with an HMAC-SHA256 of the body in the
+//! `X-Oxidize-Signature` header if `webhooks/secret` is set, and are retried
+//! with a backoff if they fail.
+
+use crate::backoff;
+use crate::injector::Injector;
+use crate::player;
+use crate::prelude::*;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac as _};
+use reqwest::{header, Client};
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::sync::broadcast::RecvError;
+
+/// The number of times to try delivering an event to a webhook.
+const MAX_ATTEMPTS: usize = 5;
+
+/// An event which is sent to webhooks.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "event", content = "data")]
+pub enum Event {
+    /// A song started playing.
+    #[serde(rename = "song/played")]
+    SongPlayed {
+        track_id: String,
+        what: String,
+        user: Option<String>,
+    },
+    /// A command was executed in chat.
+    #[serde(rename = "command/executed")]
+    CommandExecuted {
+        command: String,
+        user: Option<String>,
+    },
+    /// The stream started.
+    #[serde(rename = "stream/started")]
+    StreamStarted,
+    /// The stream stopped.
+    #[serde(rename = "stream/stopped")]
+    StreamStopped,
+    /// An afterstream message was added.
+    #[serde(rename = "afterstream/added")]
+    AfterStreamAdded { user: String, text: String },
+}
+
+impl Event {
+    /// The identifier of the event, as used in `webhooks/events`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::SongPlayed { .. } => "song/played",
+            Self::CommandExecuted { .. } => "command/executed",
+            Self::StreamStarted => "stream/started",
+            Self::StreamStopped => "stream/stopped",
+            Self::AfterStreamAdded { .. } => "afterstream/added",
+        }
+    }
+}
+
+/// The body of a webhook delivery.
+#[derive(serde::Serialize)]
+struct Payload<'a> {
+    timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Handle used to send events to webhooks.
+#[derive(Clone)]
+pub struct Webhooks {
+    tx: mpsc::UnboundedSender<Event>,
+}
+
+impl Webhooks {
+    /// Send the given event to all configured webhooks.
+    pub fn send(&self, event: Event) {
+        let _ = self.tx.unbounded_send(event);
+    }
+}
+
+/// Set up outgoing webhooks.
+pub fn setup(
+    injector: &Injector,
+    settings: settings::Settings,
+) -> (Webhooks, impl Future<Output = Result<()>>) {
+    let (tx, rx) = mpsc::unbounded();
+    let future = run(injector.clone(), settings, rx);
+    (Webhooks { tx }, future)
+}
+
+async fn run(
+    injector: Injector,
+    settings: settings::Settings,
+    rx: mpsc::UnboundedReceiver<Event>,
+) -> Result<()> {
+    let settings = settings.scoped("webhooks");
+    let client = Client::new();
+
+    let (mut enabled_stream, mut enabled) = settings.stream("enabled").or_default().await?;
+    let (mut urls_stream, mut urls) = settings.stream::<Vec<String>>("urls").or_default().await?;
+    let (mut events_stream, mut events) = settings
+        .stream::<Vec<String>>("events")
+        .or_default()
+        .await?;
+    let (mut secret_stream, mut secret) = settings.stream::<String>("secret").optional().await?;
+    let (mut player_stream, player) = injector.stream::<player::Player>().await;
+
+    let mut rx = rx.fuse();
+    let mut player_events = player_events(player.as_ref()).await;
+    let mut last_played = None;
+
+    loop {
+        let event = futures::select! {
+            update = enabled_stream.select_next_some() => {
+                enabled = update;
+                continue;
+            }
+            update = urls_stream.select_next_some() => {
+                urls = update;
+                continue;
+            }
+            update = events_stream.select_next_some() => {
+                events = update;
+                continue;
+            }
+            update = secret_stream.select_next_some() => {
+                secret = update;
+                continue;
+            }
+            update = player_stream.select_next_some() => {
+                player_events = player_events(update.as_ref()).await;
+                continue;
+            }
+            event = player_events.select_next_some() => {
+                let item = match event {
+                    Ok(player::Event::Playing(_, Some(item))) => item,
+                    _ => continue,
+                };
+
+                // NB: resuming a song is also reported as playing it.
+                if last_played.as_ref() == Some(&item.track_id) {
+                    continue;
+                }
+
+                last_played = Some(item.track_id.clone());
+
+                Event::SongPlayed {
+                    track_id: item.track_id.to_string(),
+                    what: item.what(),
+                    user: item.user.clone(),
+                }
+            }
+            event = rx.select_next_some() => event,
+        };
+
+        if !enabled || urls.is_empty() {
+            continue;
+        }
+
+        if !events.is_empty() && !events.iter().any(|e| e == event.id()) {
+            continue;
+        }
+
+        let body = serde_json::to_vec(&Payload {
+            timestamp: Utc::now(),
+            event: &event,
+        })?;
+
+        let signature = secret.as_ref().map(|secret| {
+            format!(
+                "sha256={}",
+                hex::encode(hmac_sha256(secret.as_bytes(), &body))
+            )
+        });
+
+        for url in &urls {
+            let delivery = deliver(
+                client.clone(),
+                url.clone(),
+                event.id(),
+                body.clone(),
+                signature.clone(),
+            );
+
+            tokio::spawn(delivery);
+        }
+    }
+}
+
+/// Subscribe to events from the player, if it's available.
+async fn player_events(
+    player: Option<&player::Player>,
+) -> stream::Fuse<stream::BoxStream<'static, Result<player::Event, RecvError>>> {
+    match player {
+        Some(player) => player.subscribe().await.boxed().fuse(),
+        None => stream::pending().boxed().fuse(),
+    }
+}
+
+/// Deliver an event to a single webhook, retrying with a backoff if it fails.
+async fn deliver(
+    client: Client,
+    url: String,
+    event: &'static str,
+    body: Vec<u8>,
+    signature: Option<String>,
+) {
+    let mut backoff = backoff::Exponential::new(Duration::from_secs(2));
+    let mut attempt = 1;
+
+    loop {
+        let mut request = client
+            .post(&url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("X-Oxidize-Event", event)
+            .body(body.clone());
+
+        if let Some(signature) = &signature {
+            request = request.header("X-Oxidize-Signature", signature.as_str());
+        }
+
+        let result = async {
+            let response = request.send().await?;
+
+            if !response.status().is_success() {
+                bail!("bad response status: {}", response.status());
+            }
+
+            Ok(())
+        };
+
+        let e = match result.await {
+            Ok(()) => return,
+            Err(e) => e,
+        };
+
+        if attempt >= MAX_ATTEMPTS {
+            log_warn!(e, "Giving up on delivering `{}` to webhook: {}", event, url);
+            return;
+        }
+
+        let delay = backoff.next();
+        log::trace!("Failed to deliver `{}` to webhook {}: {}", event, url, e);
+        tokio::time::delay_for(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Compute the HMAC-SHA256 of the given message.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    // NB: HMAC accepts keys of any length.
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("any key length is valid");
+    mac.input(message);
+    mac.result().code().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hmac_sha256, Event, Payload};
+
+    #[test]
+    fn test_hmac_sha256() {
+        // Test case 2 from RFC 4231.
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            hex::encode(mac)
+        );
+    }
+
+    #[test]
+    fn test_payload() {
+        let event = Event::CommandExecuted {
+            command: String::from("song"),
+            user: Some(String::from("setbac")),
+        };
+
+        let payload = Payload {
+            timestamp: "2020-09-20T12:00:00Z".parse().unwrap(),
+            event: &event,
+        };
+
+        let expected = serde_json::json!({
+            "timestamp": "2020-09-20T12:00:00Z",
+            "event": "command/executed",
+            "data": {"command": "song", "user": "setbac"},
+        });
+
+        assert_eq!(expected, serde_json::to_value(&payload).unwrap());
+    }
+}